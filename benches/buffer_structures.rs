@@ -0,0 +1,375 @@
+//! Compares the piece tree backing `TextBuffer` against simpler text storage
+//!
+//! Baselines are a plain `Vec<u8>` and a gap buffer. Each workload runs at
+//! several document sizes; the piece tree is additionally measured at several
+//! levels of fragmentation (number of pieces), since that is what drives the
+//! depth of the tree and the cost of its rebuilds.
+//!
+//! Run with: cargo bench --bench buffer_structures
+//! Results are summarized in docs/internal/BUFFER_BENCHMARKS.md
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fresh::model::buffer::TextBuffer;
+use std::hint::black_box;
+
+/// Document sizes in bytes
+const SIZES: [usize; 3] = [4 * 1024, 64 * 1024, 1024 * 1024];
+
+/// Number of pieces the piece tree is split into before measuring
+const PIECE_COUNTS: [usize; 3] = [1, 64, 1024];
+
+/// Edits performed per iteration of the edit workloads
+const EDITS: usize = 100;
+
+/// Small deterministic PRNG so every run edits the same offsets
+struct Lcg(u64);
+
+impl Lcg {
+    fn next_below(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound.max(1)
+    }
+}
+
+fn sample_text(size: usize) -> Vec<u8> {
+    b"fn main() { println!(\"hello\"); }\n"
+        .iter()
+        .copied()
+        .cycle()
+        .take(size)
+        .collect()
+}
+
+/// A text buffer whose piece tree holds at least `pieces` pieces
+fn fragmented_buffer(size: usize, pieces: usize) -> TextBuffer {
+    let mut buffer = TextBuffer::from_bytes(sample_text(size));
+    // Each single-byte overwrite in the middle of a piece splits it in three
+    let mut rng = Lcg(pieces as u64);
+    while buffer.stats().leaf_count < pieces {
+        let offset = rng.next_below(size);
+        buffer.replace_range(offset..offset + 1, "x");
+    }
+    buffer
+}
+
+/// Minimal gap buffer used as a baseline
+struct GapBuffer {
+    data: Vec<u8>,
+    gap_start: usize,
+    gap_end: usize,
+}
+
+impl GapBuffer {
+    fn new(text: &[u8]) -> Self {
+        let gap = 4096;
+        let mut data = Vec::with_capacity(text.len() + gap);
+        data.extend_from_slice(text);
+        data.resize(text.len() + gap, 0);
+        Self {
+            data,
+            gap_start: text.len(),
+            gap_end: text.len() + gap,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.data.len() - (self.gap_end - self.gap_start)
+    }
+
+    fn move_gap(&mut self, offset: usize) {
+        if offset < self.gap_start {
+            let count = self.gap_start - offset;
+            self.data
+                .copy_within(offset..self.gap_start, self.gap_end - count);
+            self.gap_start = offset;
+            self.gap_end -= count;
+        } else if offset > self.gap_start {
+            let count = offset - self.gap_start;
+            self.data
+                .copy_within(self.gap_end..self.gap_end + count, self.gap_start);
+            self.gap_start += count;
+            self.gap_end += count;
+        }
+    }
+
+    fn insert(&mut self, offset: usize, text: &[u8]) {
+        self.move_gap(offset);
+        if self.gap_end - self.gap_start < text.len() {
+            let grow = text.len().max(self.data.len());
+            let tail = self.data.len() - self.gap_end;
+            self.data.resize(self.data.len() + grow, 0);
+            let new_gap_end = self.data.len() - tail;
+            self.data
+                .copy_within(self.gap_end..self.gap_end + tail, new_gap_end);
+            self.gap_end = new_gap_end;
+        }
+        self.data[self.gap_start..self.gap_start + text.len()].copy_from_slice(text);
+        self.gap_start += text.len();
+    }
+
+    fn delete(&mut self, offset: usize, bytes: usize) {
+        self.move_gap(offset);
+        self.gap_end += bytes;
+    }
+
+    fn count_newlines(&self) -> usize {
+        self.data[..self.gap_start]
+            .iter()
+            .chain(&self.data[self.gap_end..])
+            .filter(|&&b| b == b'\n')
+            .count()
+    }
+}
+
+fn bench_insert_random(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_random");
+    for size in SIZES {
+        let text = sample_text(size);
+
+        for pieces in PIECE_COUNTS {
+            group.bench_function(
+                BenchmarkId::new(format!("piece_tree/{pieces}_pieces"), size),
+                |b| {
+                    b.iter_batched(
+                        || fragmented_buffer(size, pieces),
+                        |mut buffer| {
+                            let mut rng = Lcg(1);
+                            for _ in 0..EDITS {
+                                let offset = rng.next_below(buffer.len());
+                                buffer.insert_bytes(offset, b"abc".to_vec());
+                            }
+                            buffer
+                        },
+                        criterion::BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+
+        group.bench_function(BenchmarkId::new("vec", size), |b| {
+            b.iter_batched(
+                || text.clone(),
+                |mut vec| {
+                    let mut rng = Lcg(1);
+                    for _ in 0..EDITS {
+                        let offset = rng.next_below(vec.len());
+                        vec.splice(offset..offset, *b"abc");
+                    }
+                    vec
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+
+        group.bench_function(BenchmarkId::new("gap_buffer", size), |b| {
+            b.iter_batched(
+                || GapBuffer::new(&text),
+                |mut gap| {
+                    let mut rng = Lcg(1);
+                    for _ in 0..EDITS {
+                        let offset = rng.next_below(gap.len());
+                        gap.insert(offset, b"abc");
+                    }
+                    gap
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_insert_end(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_end");
+    for size in SIZES {
+        let text = sample_text(size);
+
+        for pieces in PIECE_COUNTS {
+            group.bench_function(
+                BenchmarkId::new(format!("piece_tree/{pieces}_pieces"), size),
+                |b| {
+                    b.iter_batched(
+                        || fragmented_buffer(size, pieces),
+                        |mut buffer| {
+                            for _ in 0..EDITS {
+                                let end = buffer.len();
+                                buffer.insert_bytes(end, b"abc".to_vec());
+                            }
+                            buffer
+                        },
+                        criterion::BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+
+        group.bench_function(BenchmarkId::new("vec", size), |b| {
+            b.iter_batched(
+                || text.clone(),
+                |mut vec| {
+                    for _ in 0..EDITS {
+                        vec.extend_from_slice(b"abc");
+                    }
+                    vec
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+
+        group.bench_function(BenchmarkId::new("gap_buffer", size), |b| {
+            b.iter_batched(
+                || GapBuffer::new(&text),
+                |mut gap| {
+                    for _ in 0..EDITS {
+                        let end = gap.len();
+                        gap.insert(end, b"abc");
+                    }
+                    gap
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_delete_range(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delete_range");
+    for size in SIZES {
+        let text = sample_text(size);
+
+        for pieces in PIECE_COUNTS {
+            group.bench_function(
+                BenchmarkId::new(format!("piece_tree/{pieces}_pieces"), size),
+                |b| {
+                    b.iter_batched(
+                        || fragmented_buffer(size, pieces),
+                        |mut buffer| {
+                            let mut rng = Lcg(2);
+                            for _ in 0..EDITS {
+                                let offset = rng.next_below(buffer.len() - 16);
+                                buffer.delete_bytes(offset, 16);
+                            }
+                            buffer
+                        },
+                        criterion::BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+
+        group.bench_function(BenchmarkId::new("vec", size), |b| {
+            b.iter_batched(
+                || text.clone(),
+                |mut vec| {
+                    let mut rng = Lcg(2);
+                    for _ in 0..EDITS {
+                        let offset = rng.next_below(vec.len() - 16);
+                        vec.drain(offset..offset + 16);
+                    }
+                    vec
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+
+        group.bench_function(BenchmarkId::new("gap_buffer", size), |b| {
+            b.iter_batched(
+                || GapBuffer::new(&text),
+                |mut gap| {
+                    let mut rng = Lcg(2);
+                    for _ in 0..EDITS {
+                        let offset = rng.next_below(gap.len() - 16);
+                        gap.delete(offset, 16);
+                    }
+                    gap
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_full_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full_scan");
+    for size in SIZES {
+        let text = sample_text(size);
+
+        for pieces in PIECE_COUNTS {
+            let mut buffer = fragmented_buffer(size, pieces);
+            group.bench_function(
+                BenchmarkId::new(format!("piece_tree/{pieces}_pieces"), size),
+                |b| {
+                    b.iter(|| {
+                        let len = buffer.len();
+                        let bytes = buffer.get_text_range_mut(0, len).unwrap();
+                        black_box(bytes.iter().filter(|&&b| b == b'\n').count())
+                    })
+                },
+            );
+        }
+
+        group.bench_function(BenchmarkId::new("vec", size), |b| {
+            b.iter(|| black_box(text.iter().filter(|&&b| b == b'\n').count()))
+        });
+
+        let gap = GapBuffer::new(&text);
+        group.bench_function(BenchmarkId::new("gap_buffer", size), |b| {
+            b.iter(|| black_box(gap.count_newlines()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_overwrite(c: &mut Criterion) {
+    let mut group = c.benchmark_group("overwrite");
+    for size in SIZES {
+        // Same-length overwrites take the in-leaf fast path of replace_range
+        group.bench_function(BenchmarkId::new("piece_tree/in_leaf", size), |b| {
+            b.iter_batched(
+                || TextBuffer::from_bytes(sample_text(size)),
+                |mut buffer| {
+                    let mut rng = Lcg(3);
+                    for _ in 0..EDITS {
+                        let offset = rng.next_below(size);
+                        buffer.replace_range(offset..offset + 1, "x");
+                    }
+                    buffer
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+
+        // The same edits done as a delete followed by an insert
+        group.bench_function(BenchmarkId::new("piece_tree/delete_insert", size), |b| {
+            b.iter_batched(
+                || TextBuffer::from_bytes(sample_text(size)),
+                |mut buffer| {
+                    let mut rng = Lcg(3);
+                    for _ in 0..EDITS {
+                        let offset = rng.next_below(size);
+                        buffer.delete_bytes(offset, 1);
+                        buffer.insert_bytes(offset, b"x".to_vec());
+                    }
+                    buffer
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_insert_random,
+    bench_insert_end,
+    bench_delete_range,
+    bench_overwrite,
+    bench_full_scan
+);
+criterion_main!(benches);
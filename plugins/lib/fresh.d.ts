@@ -356,6 +356,27 @@ interface EditorAPI {
    * @param buffer_id - Target buffer ID
    */
   getBufferLength(buffer_id: number): number;
+  /**
+   * Get the text of a byte range of a buffer
+   *
+   * The range is clamped to the buffer. Only the part of a large file
+   * covering the range is loaded. Requires the `read_buffer` capability.
+   * @param buffer_id - Target buffer ID
+   * @param start - Start byte offset (inclusive)
+   * @param end - End byte offset (exclusive)
+   * @returns Promise resolving to the text of the range
+   */
+  getBufferText(buffer_id: number, start: number, end: number): Promise<string>;
+  /**
+   * Get one line of a buffer, without its line ending
+   *
+   * Lines are 0-indexed. Only the part of a large file up to the line is
+   * loaded. Requires the `read_buffer` capability.
+   * @param buffer_id - Target buffer ID
+   * @param line - Line number (0-indexed)
+   * @returns Promise resolving to the text of the line; rejects if the buffer has fewer lines
+   */
+  getBufferLine(buffer_id: number, line: number): Promise<string>;
   /**
    * Check if a buffer has been modified since last save
    *
@@ -444,6 +465,20 @@ interface EditorAPI {
   setPromptSuggestions(suggestions: PromptSuggestion[]): boolean;
 
   // === Buffer Mutations ===
+  /**
+   * Schedule a one-shot timer
+   *
+   * After at least `after_ms` milliseconds the editor runs this plugin's "timer"
+   * handlers with `{ token }`; other plugins don't see the timer. Register a
+   * handler with `editor.on("timer", "handlerName")` and use the token to tell
+   * your timers apart. Timers fire from the editor's event loop, so they may
+   * run a little late but never early. The token must be a non-negative
+   * integer no larger than `Number.MAX_SAFE_INTEGER`.
+   * @param after_ms - Delay in milliseconds
+   * @param token - Value passed back to the "timer" hook
+   * @returns true if the timer was scheduled, false if the token is out of range
+   */
+  schedule(after_ms: number, token: number): boolean;
   /**
    * Apply a theme by name
    *
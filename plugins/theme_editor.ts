@@ -104,6 +104,8 @@ const THEME_SECTIONS: ThemeSection[] = [
       { key: "current_line_bg", displayName: "Current Line Background", description: "Background of the line containing cursor", section: "editor" },
       { key: "line_number_fg", displayName: "Line Number Foreground", description: "Line number text color", section: "editor" },
       { key: "line_number_bg", displayName: "Line Number Background", description: "Line number gutter background", section: "editor" },
+      { key: "whitespace_indicator_fg", displayName: "Whitespace Indicator", description: "Tab and space markers when whitespace rendering is on", section: "editor" },
+      { key: "ruler_bg", displayName: "Ruler Background", description: "Columns marked by the rulers setting", section: "editor" },
     ],
   },
   {
@@ -125,6 +127,7 @@ const THEME_SECTIONS: ThemeSection[] = [
       { key: "menu_dropdown_bg", displayName: "Menu Dropdown Background", description: "Dropdown menu background", section: "ui" },
       { key: "menu_dropdown_fg", displayName: "Menu Dropdown Foreground", description: "Dropdown menu text color", section: "ui" },
       { key: "menu_highlight_bg", displayName: "Menu Highlight Background", description: "Highlighted menu item background", section: "ui" },
+      { key: "menu_highlight_bg_end", displayName: "Menu Highlight Gradient End", description: "Optional end color fading the highlighted item background across its width", section: "ui" },
       { key: "menu_highlight_fg", displayName: "Menu Highlight Foreground", description: "Highlighted menu item text color", section: "ui" },
       { key: "menu_border_fg", displayName: "Menu Border", description: "Menu border color", section: "ui" },
       { key: "menu_separator_fg", displayName: "Menu Separator", description: "Menu separator line color", section: "ui" },
@@ -132,6 +135,9 @@ const THEME_SECTIONS: ThemeSection[] = [
       { key: "menu_hover_fg", displayName: "Menu Hover Foreground", description: "Menu item hover text color", section: "ui" },
       { key: "menu_disabled_fg", displayName: "Menu Disabled Foreground", description: "Disabled menu item text color", section: "ui" },
       { key: "menu_disabled_bg", displayName: "Menu Disabled Background", description: "Disabled menu item background", section: "ui" },
+      { key: "menu_shadow_bg", displayName: "Menu Shadow", description: "Drop shadow behind dropdown menus", section: "ui" },
+      { key: "menu_destructive_fg", displayName: "Menu Destructive Foreground", description: "Text color of destructive menu items, like Delete All", section: "ui" },
+      { key: "menu_primary_fg", displayName: "Menu Primary Foreground", description: "Text color of primary menu items", section: "ui" },
       { key: "status_bar_fg", displayName: "Status Bar Foreground", description: "Status bar text color", section: "ui" },
       { key: "status_bar_bg", displayName: "Status Bar Background", description: "Status bar background color", section: "ui" },
       { key: "prompt_fg", displayName: "Prompt Foreground", description: "Command prompt text color", section: "ui" },
@@ -382,7 +388,7 @@ function formatColorValue(value: ColorValue): string {
  * Check if a color is a named color (including special colors like Default/Reset)
  */
 function isNamedColor(value: ColorValue): boolean {
-  return typeof value === "string" && (value in NAMED_COLORS || SPECIAL_COLORS.includes(value));
+  return typeof value === "string" && (value in NAMED_COLORS || isSpecialColor(value));
 }
 
 /**
@@ -660,7 +666,7 @@ function isBackgroundColorField(path: string): boolean {
  * Check if a color is a special color (Default/Reset)
  */
 function isSpecialColor(value: ColorValue): boolean {
-  return typeof value === "string" && SPECIAL_COLORS.includes(value);
+  return typeof value === "string" && SPECIAL_COLORS.some((name) => name.toLowerCase() === value.toLowerCase());
 }
 
 /**
@@ -855,7 +861,7 @@ function parseColorInput(input: string): ColorValue | null {
   input = input.trim();
 
   // Check for special colors (Default/Reset - use terminal's native color)
-  if (SPECIAL_COLORS.includes(input)) {
+  if (isSpecialColor(input)) {
     return input;
   }
 
@@ -1090,6 +1096,8 @@ function createDefaultTheme(): Record<string, unknown> {
       current_line_bg: [40, 40, 40],
       line_number_fg: [100, 100, 100],
       line_number_bg: [30, 30, 30],
+      whitespace_indicator_fg: [70, 70, 70],
+      ruler_bg: [45, 45, 45],
     },
     ui: {
       tab_active_fg: "Yellow",
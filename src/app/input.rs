@@ -1,5 +1,6 @@
 use super::normalize_path;
 use super::*;
+use crate::primitives::case_transform::CaseTransform;
 use crate::services::plugins::hooks::HookArgs;
 impl Editor {
     /// Determine the current keybinding context based on UI state
@@ -11,6 +12,12 @@ impl Editor {
             KeyContext::Settings
         } else if self.menu_state.active_menu.is_some() {
             KeyContext::Menu
+        } else if self
+            .prompt
+            .as_ref()
+            .is_some_and(|p| p.prompt_type == PromptType::IncrementalSearch)
+        {
+            KeyContext::IncrementalSearch
         } else if self.is_prompting() {
             KeyContext::Prompt
         } else if self.active_state().popups.is_visible() {
@@ -196,6 +203,7 @@ impl Editor {
                 if let Some(cmd) = commands.iter().find(|c| c.name == command_name) {
                     let action = cmd.action.clone();
                     drop(commands);
+                    self.record_recent_command(&action);
                     return self.handle_action(action);
                 } else if command_name == "close-buffer" {
                     // Handle built-in mode commands
@@ -225,6 +233,7 @@ impl Editor {
                 // Complete chord match - execute action and clear chord state
                 tracing::debug!("Complete chord match -> Action: {:?}", action);
                 self.chord_state.clear();
+                self.record_keybinding_command(&action);
                 return self.handle_action(action);
             }
             crate::input::keybindings::ChordResolution::Partial => {
@@ -246,6 +255,7 @@ impl Editor {
         let action = self.keybindings.resolve(&key_event, context);
 
         tracing::trace!("Context: {:?} -> Action: {:?}", context, action);
+        self.record_keybinding_command(&action);
 
         // Cancel pending LSP requests on user actions (except LSP actions themselves)
         // This ensures stale completions don't show up after the user has moved on
@@ -728,6 +738,7 @@ impl Editor {
                         // Get the appropriate history based on prompt type
                         let history_item = match prompt_type {
                             PromptType::Search
+                            | PromptType::IncrementalSearch
                             | PromptType::ReplaceSearch
                             | PromptType::QueryReplaceSearch => {
                                 self.search_history.navigate_prev(&current_input)
@@ -751,6 +762,8 @@ impl Editor {
                                         | PromptType::QueryReplaceSearch
                                 ) {
                                     self.update_search_highlights(&history_text);
+                                } else if prompt_type == PromptType::IncrementalSearch {
+                                    self.update_incremental_search(&history_text);
                                 }
                             }
                         }
@@ -809,6 +822,7 @@ impl Editor {
                         // Get the appropriate history based on prompt type
                         let history_item = match prompt_type {
                             PromptType::Search
+                            | PromptType::IncrementalSearch
                             | PromptType::ReplaceSearch
                             | PromptType::QueryReplaceSearch => self.search_history.navigate_next(),
                             PromptType::Replace { .. } | PromptType::QueryReplace { .. } => {
@@ -830,6 +844,8 @@ impl Editor {
                                         | PromptType::QueryReplaceSearch
                                 ) {
                                     self.update_search_highlights(&history_text);
+                                } else if prompt_type == PromptType::IncrementalSearch {
+                                    self.update_incremental_search(&history_text);
                                 }
                             }
                         }
@@ -1013,6 +1029,23 @@ impl Editor {
         Ok(())
     }
 
+    /// Remember a command for the "Recent Commands" submenu
+    fn record_recent_command(&self, action: &Action) {
+        if let Some((name, args)) = action.to_config() {
+            self.menu_state.record_action(&name, &args);
+        }
+    }
+
+    /// Remember the action a key resolved to, if a command runs it
+    ///
+    /// Keys also type text and move the cursor, so only actions the command
+    /// palette offers are remembered.
+    fn record_keybinding_command(&self, action: &Action) {
+        if self.command_registry.read().unwrap().has_action(action) {
+            self.record_recent_command(action);
+        }
+    }
+
     /// Handle an action (for normal mode and command execution)
     pub(super) fn handle_action(&mut self, action: Action) -> std::io::Result<()> {
         use crate::input::keybindings::Action;
@@ -1123,6 +1156,20 @@ impl Editor {
                 }
                 self.paste()
             }
+            Action::PastePrevious => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(());
+                }
+                self.paste_previous()
+            }
+            Action::PasteReindent => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(());
+                }
+                self.paste_reindent()
+            }
             Action::Undo => {
                 if self.is_editing_disabled() {
                     self.set_status_message("Editing disabled in this buffer".to_string());
@@ -1143,7 +1190,7 @@ impl Editor {
                 // Apply all inverse events collected during undo
                 for event in &events {
                     tracing::debug!("Undo applying event: {:?}", event);
-                    self.apply_event_to_active_buffer(event);
+                    self.apply_unfiltered_event_to_active_buffer(event);
                 }
                 // Update modified status based on event log position
                 self.update_modified_from_event_log();
@@ -1156,7 +1203,7 @@ impl Editor {
                 let events = self.active_event_log_mut().redo();
                 // Apply all events collected during redo
                 for event in events {
-                    self.apply_event_to_active_buffer(&event);
+                    self.apply_unfiltered_event_to_active_buffer(&event);
                 }
                 // Update modified status based on event log position
                 self.update_modified_from_event_log();
@@ -1494,6 +1541,18 @@ impl Editor {
                     self.start_search_prompt("Search: ".to_string(), PromptType::Search, false);
                 }
             }
+            Action::IncrementalSearch => {
+                // Repeating the action inside the prompt jumps to the next match
+                let in_incremental_search = self
+                    .prompt
+                    .as_ref()
+                    .is_some_and(|p| p.prompt_type == PromptType::IncrementalSearch);
+                if in_incremental_search {
+                    self.incremental_search_next();
+                } else {
+                    self.start_incremental_search();
+                }
+            }
             Action::Replace => {
                 // Use same flow as query-replace, just with confirm_each defaulting to false
                 self.start_search_prompt("Replace: ".to_string(), PromptType::ReplaceSearch, false);
@@ -1582,6 +1641,9 @@ impl Editor {
             Action::FileExplorerToggleHidden => self.file_explorer_toggle_hidden(),
             Action::FileExplorerToggleGitignored => self.file_explorer_toggle_gitignored(),
             Action::RemoveSecondaryCursors => {
+                // Escape also leaves any snippet session
+                self.active_state_mut().snippet = None;
+
                 // Convert action to events and apply them
                 if let Some(events) = self.action_to_events(Action::RemoveSecondaryCursors) {
                     // Wrap in batch for atomic undo
@@ -1589,8 +1651,7 @@ impl Editor {
                         events: events.clone(),
                         description: "Remove secondary cursors".to_string(),
                     };
-                    self.active_event_log_mut().append(batch.clone());
-                    self.apply_event_to_active_buffer(&batch);
+                    self.log_and_apply_event(&batch);
 
                     // Ensure the primary cursor is visible after removing secondary cursors
                     let active_split = self.split_manager.active_split();
@@ -1613,7 +1674,7 @@ impl Editor {
                 self.menu_state.open_menu(0);
             }
             Action::MenuClose => {
-                self.menu_state.close_menu();
+                self.menu_state.escape();
             }
             Action::MenuLeft => {
                 // If in a submenu, close it and go back to parent
@@ -1627,14 +1688,7 @@ impl Editor {
             Action::MenuRight => {
                 // If on a submenu item, open it
                 // Otherwise, go to the next menu
-                let all_menus: Vec<crate::config::Menu> = self
-                    .config
-                    .menu
-                    .menus
-                    .iter()
-                    .chain(self.menu_state.plugin_menus.iter())
-                    .cloned()
-                    .collect();
+                let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
                 if !self.menu_state.open_submenu(&all_menus) {
                     let total_menus =
@@ -1644,14 +1698,7 @@ impl Editor {
             }
             Action::MenuUp => {
                 if let Some(active_idx) = self.menu_state.active_menu {
-                    let all_menus: Vec<crate::config::Menu> = self
-                        .config
-                        .menu
-                        .menus
-                        .iter()
-                        .chain(self.menu_state.plugin_menus.iter())
-                        .cloned()
-                        .collect();
+                    let all_menus = self.menu_state.menus(&self.config.menu.menus);
                     if let Some(menu) = all_menus.get(active_idx) {
                         self.menu_state.prev_item(menu);
                     }
@@ -1659,14 +1706,7 @@ impl Editor {
             }
             Action::MenuDown => {
                 if let Some(active_idx) = self.menu_state.active_menu {
-                    let all_menus: Vec<crate::config::Menu> = self
-                        .config
-                        .menu
-                        .menus
-                        .iter()
-                        .chain(self.menu_state.plugin_menus.iter())
-                        .cloned()
-                        .collect();
+                    let all_menus = self.menu_state.menus(&self.config.menu.menus);
                     if let Some(menu) = all_menus.get(active_idx) {
                         self.menu_state.next_item(menu);
                     }
@@ -1674,14 +1714,7 @@ impl Editor {
             }
             Action::MenuExecute => {
                 // Execute the highlighted menu item's action, or open submenu if it's a submenu
-                let all_menus: Vec<crate::config::Menu> = self
-                    .config
-                    .menu
-                    .menus
-                    .iter()
-                    .chain(self.menu_state.plugin_menus.iter())
-                    .cloned()
-                    .collect();
+                let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
                 // Check if highlighted item is a submenu - if so, open it
                 if self.menu_state.is_highlighted_submenu(&all_menus) {
@@ -1699,11 +1732,16 @@ impl Editor {
                         self.key_context == crate::input::keybindings::KeyContext::FileExplorer,
                     );
 
-                if let Some((action_name, args)) =
-                    self.menu_state.get_highlighted_action(&all_menus)
+                if let Some((action_name, args)) = self
+                    .menu_state
+                    .get_highlighted_action(&all_menus, &|action| {
+                        self.is_menu_action_enabled(action)
+                    })
                 {
-                    // Close the menu
+                    // Close the menu, keeping any preview of the item
+                    self.menu_state.commit_preview();
                     self.menu_state.close_menu();
+                    self.menu_state.record_action(&action_name, &args);
 
                     // Parse and execute the action
                     // First try built-in actions, then fall back to plugin actions
@@ -1720,14 +1758,7 @@ impl Editor {
                 self.on_editor_focus_lost();
 
                 // Find the menu by name and open it
-                let all_menus: Vec<crate::config::Menu> = self
-                    .config
-                    .menu
-                    .menus
-                    .iter()
-                    .chain(self.menu_state.plugin_menus.iter())
-                    .cloned()
-                    .collect();
+                let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
                 for (idx, menu) in all_menus.iter().enumerate() {
                     if menu.label.eq_ignore_ascii_case(&menu_name) {
@@ -1762,15 +1793,48 @@ impl Editor {
             Action::IndentSelection => {
                 self.indent_selection();
             }
+            Action::DedentSelection if self.active_state().snippet.is_some() => {
+                self.snippet_jump(false);
+            }
             Action::DedentSelection => {
                 self.dedent_selection();
             }
+            Action::InsertTab if self.active_state().snippet.is_some() => {
+                self.snippet_jump(true);
+            }
+            Action::InsertTab
+                if !self.is_editing_disabled() && self.snippet_at_cursor().is_some() =>
+            {
+                self.expand_snippet_at_cursor();
+            }
+            Action::InsertTab if !self.is_editing_disabled() && self.selection_spans_lines() => {
+                self.indent_selection();
+            }
             Action::ToggleComment => {
                 self.toggle_comment();
             }
+            Action::UpperCase => self.transform_case(CaseTransform::Upper),
+            Action::LowerCase => self.transform_case(CaseTransform::Lower),
+            Action::TitleCase => self.transform_case(CaseTransform::Title),
+            Action::ToggleCase => self.transform_case(CaseTransform::Toggle),
+            Action::JoinLines => self.join_lines(),
+            Action::SortLines(options) => self.sort_lines(options),
+            Action::ToggleOverwrite => {
+                let state = self.active_state_mut();
+                state.overwrite = !state.overwrite;
+                let mode = if state.overwrite {
+                    "Overwrite"
+                } else {
+                    "Insert"
+                };
+                self.set_status_message(format!("{mode} mode"));
+            }
             Action::GoToMatchingBracket => {
                 self.goto_matching_bracket();
             }
+            Action::SelectToMatchingBracket => {
+                self.select_to_matching_bracket();
+            }
             Action::JumpToNextError => {
                 self.jump_to_next_error();
             }
@@ -1786,9 +1850,20 @@ impl Editor {
             Action::ClearBookmark(key) => {
                 self.clear_bookmark(key);
             }
+            Action::CopyToRegister(reg) => self.copy_to_register(reg),
+            Action::PasteFromRegister(reg) => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(());
+                }
+                self.paste_from_register(reg)
+            }
             Action::ListBookmarks => {
                 self.list_bookmarks();
             }
+            Action::DocumentStatistics => {
+                self.show_document_statistics();
+            }
             Action::ToggleSearchCaseSensitive => {
                 self.search_case_sensitive = !self.search_case_sensitive;
                 let state = if self.search_case_sensitive {
@@ -1808,6 +1883,9 @@ impl Editor {
                     ) {
                         let query = prompt.input.clone();
                         self.update_search_highlights(&query);
+                    } else if prompt.prompt_type == PromptType::IncrementalSearch {
+                        let query = prompt.input.clone();
+                        self.update_incremental_search(&query);
                     }
                 } else if let Some(search_state) = &self.search_state {
                     let query = search_state.query.clone();
@@ -1833,6 +1911,9 @@ impl Editor {
                     ) {
                         let query = prompt.input.clone();
                         self.update_search_highlights(&query);
+                    } else if prompt.prompt_type == PromptType::IncrementalSearch {
+                        let query = prompt.input.clone();
+                        self.update_incremental_search(&query);
                     }
                 } else if let Some(search_state) = &self.search_state {
                     let query = search_state.query.clone();
@@ -1858,6 +1939,9 @@ impl Editor {
                     ) {
                         let query = prompt.input.clone();
                         self.update_search_highlights(&query);
+                    } else if prompt.prompt_type == PromptType::IncrementalSearch {
+                        let query = prompt.input.clone();
+                        self.update_incremental_search(&query);
                     }
                 } else if let Some(search_state) = &self.search_state {
                     let query = search_state.query.clone();
@@ -1883,7 +1967,7 @@ impl Editor {
                 self.stop_macro_recording();
             }
             Action::PlayMacro(key) => {
-                self.play_macro(key);
+                self.play_macro(key, 1);
             }
             Action::ToggleMacroRecording(key) => {
                 self.toggle_macro_recording(key);
@@ -1898,11 +1982,14 @@ impl Editor {
                 self.start_prompt("Record macro (0-9): ".to_string(), PromptType::RecordMacro);
             }
             Action::PromptPlayMacro => {
-                self.start_prompt("Play macro (0-9): ".to_string(), PromptType::PlayMacro);
+                self.start_prompt(
+                    "Play macro (0-9) [count]: ".to_string(),
+                    PromptType::PlayMacro,
+                );
             }
             Action::PlayLastMacro => {
                 if let Some(key) = self.last_macro_register {
-                    self.play_macro(key);
+                    self.play_macro(key, 1);
                 } else {
                     self.set_status_message("No macro has been recorded yet".to_string());
                 }
@@ -1929,13 +2016,11 @@ impl Editor {
                             events: events.clone(),
                             description: "Delete backward".to_string(),
                         };
-                        self.active_event_log_mut().append(batch.clone());
-                        self.apply_event_to_active_buffer(&batch);
+                        self.log_and_apply_event(&batch);
                         // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
                     } else {
                         for event in events {
-                            self.active_event_log_mut().append(event.clone());
-                            self.apply_event_to_active_buffer(&event);
+                            self.log_and_apply_event(&event);
                             // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
                         }
                     }
@@ -2122,6 +2207,7 @@ impl Editor {
                             );
 
                             // Save the buffer to the new file
+                            self.apply_save_cleanup();
                             match self.active_state_mut().buffer.save_to_file(&full_path) {
                                 Ok(()) => {
                                     // Debug: log event log state after buffer save
@@ -2156,7 +2242,7 @@ impl Editor {
                                     }
 
                                     // Notify LSP of the new file if applicable
-                                    self.notify_lsp_save();
+                                    self.notify_lsp_save(self.active_buffer());
 
                                     // Emit file saved event
                                     self.emit_event(
@@ -2201,6 +2287,9 @@ impl Editor {
                         PromptType::Search => {
                             self.perform_search(&input);
                         }
+                        PromptType::IncrementalSearch => {
+                            self.accept_incremental_search(&input);
+                        }
                         PromptType::ReplaceSearch => {
                             self.perform_search(&input);
                             self.start_prompt(
@@ -2246,6 +2335,7 @@ impl Editor {
                                     .write()
                                     .unwrap()
                                     .record_usage(&cmd_name);
+                                self.record_recent_command(&action);
                                 return self.handle_action(action);
                             } else {
                                 self.set_status_message(format!("Unknown command: {input}"));
@@ -2343,13 +2433,22 @@ impl Editor {
                             }
                         }
                         PromptType::PlayMacro => {
-                            if let Some(c) = input.trim().chars().next() {
-                                if c.is_ascii_digit() {
-                                    self.play_macro(c);
-                                } else {
+                            // Accepts "<register>" or "<register> <count>"
+                            let mut parts = input.split_whitespace();
+                            let register = parts.next().and_then(|p| p.chars().next());
+                            let count = parts.next().map(|p| p.parse::<usize>());
+                            if let Some(c) = register {
+                                if !c.is_ascii_digit() {
                                     self.set_status_message(
                                         "Macro register must be 0-9".to_string(),
                                     );
+                                } else if let Some(Err(_)) = count {
+                                    self.set_status_message(
+                                        "Macro repeat count must be a number".to_string(),
+                                    );
+                                } else {
+                                    let count = count.and_then(|c| c.ok()).unwrap_or(1);
+                                    self.play_macro(c, count);
                                 }
                             } else {
                                 self.set_status_message("No register specified".to_string());
@@ -2412,6 +2511,9 @@ impl Editor {
                                 self.set_status_message("Save cancelled".to_string());
                             }
                         }
+                        PromptType::ConfirmExternalChange { buffer_id } => {
+                            self.resolve_external_change(buffer_id, &input);
+                        }
                         PromptType::ConfirmCloseBuffer { buffer_id } => {
                             let input_lower = input.trim().to_lowercase();
                             match input_lower.chars().next() {
@@ -2640,8 +2742,7 @@ impl Editor {
                             cursor_id,
                         };
 
-                        self.active_event_log_mut().append(delete_event.clone());
-                        self.apply_event_to_active_buffer(&delete_event);
+                        self.log_and_apply_event(&delete_event);
 
                         let buffer_len = self.active_state().buffer.len();
                         let insert_pos = word_start.min(buffer_len);
@@ -2652,8 +2753,7 @@ impl Editor {
                             cursor_id,
                         };
 
-                        self.active_event_log_mut().append(insert_event.clone());
-                        self.apply_event_to_active_buffer(&insert_event);
+                        self.log_and_apply_event(&insert_event);
                     } else {
                         let insert_event = crate::model::event::Event::Insert {
                             position: cursor_pos,
@@ -2661,8 +2761,7 @@ impl Editor {
                             cursor_id,
                         };
 
-                        self.active_event_log_mut().append(insert_event.clone());
-                        self.apply_event_to_active_buffer(&insert_event);
+                        self.log_and_apply_event(&insert_event);
                     }
                 }
 
@@ -2690,6 +2789,7 @@ impl Editor {
                     if let Some(ref prompt) = self.prompt {
                         match &prompt.prompt_type {
                             PromptType::Search
+                            | PromptType::IncrementalSearch
                             | PromptType::ReplaceSearch
                             | PromptType::QueryReplaceSearch => {
                                 self.search_history.reset_navigation();
@@ -2724,14 +2824,12 @@ impl Editor {
                                 events: events.clone(),
                                 description: format!("Insert '{}'", c),
                             };
-                            self.active_event_log_mut().append(batch.clone());
-                            self.apply_event_to_active_buffer(&batch);
+                            self.log_and_apply_event(&batch);
                             // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
                         } else {
                             // Single cursor - no need for batch
                             for event in events {
-                                self.active_event_log_mut().append(event.clone());
-                                self.apply_event_to_active_buffer(&event);
+                                self.log_and_apply_event(&event);
                                 // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
                             }
                         }
@@ -2760,6 +2858,12 @@ impl Editor {
                         | Action::IndentSelection
                         | Action::DedentSelection
                         | Action::ToggleComment
+                        | Action::UpperCase
+                        | Action::LowerCase
+                        | Action::TitleCase
+                        | Action::ToggleCase
+                        | Action::JoinLines
+                        | Action::SortLines(_)
                 );
 
                 if is_editing_action && self.is_editing_disabled() {
@@ -2774,8 +2878,7 @@ impl Editor {
                             events: events.clone(),
                             description: action_description,
                         };
-                        self.active_event_log_mut().append(batch.clone());
-                        self.apply_event_to_active_buffer(&batch);
+                        self.log_and_apply_event(&batch);
                         // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
 
                         // Track position history for all events in the batch
@@ -2799,8 +2902,7 @@ impl Editor {
                     } else {
                         // Single cursor - no need for batch
                         for event in events {
-                            self.active_event_log_mut().append(event.clone());
-                            self.apply_event_to_active_buffer(&event);
+                            self.log_and_apply_event(&event);
                             // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
 
                             // Track cursor movements in position history (but not during navigation)
@@ -3019,14 +3121,7 @@ impl Editor {
 
             // If hovering over a menu dropdown item, check if it's a submenu and open it
             if let Some(HoverTarget::MenuDropdownItem(_, item_idx)) = new_target.clone() {
-                let all_menus: Vec<crate::config::Menu> = self
-                    .config
-                    .menu
-                    .menus
-                    .iter()
-                    .chain(self.menu_state.plugin_menus.iter())
-                    .cloned()
-                    .collect();
+                let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
                 // Clear any open submenus since we're at the main dropdown level
                 if !self.menu_state.submenu_path.is_empty() {
@@ -3036,15 +3131,14 @@ impl Editor {
                 }
 
                 // Check if the hovered item is a submenu
-                if let Some(menu) = all_menus.get(active_menu_idx) {
-                    if let Some(crate::config::MenuItem::Submenu { items, .. }) =
-                        menu.items.get(item_idx)
-                    {
-                        if !items.is_empty() {
-                            self.menu_state.submenu_path.push(item_idx);
-                            self.menu_state.highlighted_item = Some(0);
-                            return true;
-                        }
+                if let Some(item) = all_menus
+                    .get(active_menu_idx)
+                    .and_then(|menu| menu.items.get(item_idx))
+                {
+                    if self.menu_state.load_submenu(item) {
+                        self.menu_state.submenu_path.push(item_idx);
+                        self.menu_state.highlighted_item = Some(0);
+                        return true;
                     }
                 }
                 // Update highlighted item for non-submenu items too
@@ -3061,27 +3155,19 @@ impl Editor {
                     self.menu_state.submenu_path.truncate(depth);
                 }
 
-                let all_menus: Vec<crate::config::Menu> = self
-                    .config
-                    .menu
-                    .menus
-                    .iter()
-                    .chain(self.menu_state.plugin_menus.iter())
-                    .cloned()
-                    .collect();
+                let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
-                // Get the items at this depth
+                // Get the hovered item at this depth
                 if let Some(items) = self
                     .menu_state
                     .get_current_items(&all_menus, active_menu_idx)
                 {
+                    let hovered = items.get(item_idx).cloned();
+
                     // Check if hovered item is a submenu - if so, open it
-                    if let Some(crate::config::MenuItem::Submenu {
-                        items: sub_items, ..
-                    }) = items.get(item_idx)
-                    {
-                        if !sub_items.is_empty()
-                            && !self.menu_state.submenu_path.contains(&item_idx)
+                    if let Some(item) = hovered {
+                        if !self.menu_state.submenu_path.contains(&item_idx)
+                            && self.menu_state.load_submenu(&item)
                         {
                             self.menu_state.submenu_path.push(item_idx);
                             self.menu_state.highlighted_item = Some(0);
@@ -3276,14 +3362,7 @@ impl Editor {
 
         // Check menu bar (row 0)
         if row == 0 {
-            let all_menus: Vec<crate::config::Menu> = self
-                .config
-                .menu
-                .menus
-                .iter()
-                .chain(self.menu_state.plugin_menus.iter())
-                .cloned()
-                .collect();
+            let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
             if let Some(menu_idx) = self.menu_state.get_menu_at_position(&all_menus, col) {
                 return Some(HoverTarget::MenuBarItem(menu_idx));
@@ -3292,14 +3371,7 @@ impl Editor {
 
         // Check menu dropdown items if a menu is open (including submenus)
         if let Some(active_idx) = self.menu_state.active_menu {
-            let all_menus: Vec<crate::config::Menu> = self
-                .config
-                .menu
-                .menus
-                .iter()
-                .chain(self.menu_state.plugin_menus.iter())
-                .cloned()
-                .collect();
+            let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
             if let Some(menu) = all_menus.get(active_idx) {
                 if let Some(hover) =
@@ -3567,14 +3639,7 @@ impl Editor {
 
         // Check if click is on menu bar (row 0)
         if row == 0 {
-            let all_menus: Vec<crate::config::Menu> = self
-                .config
-                .menu
-                .menus
-                .iter()
-                .chain(self.menu_state.plugin_menus.iter())
-                .cloned()
-                .collect();
+            let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
             if let Some(menu_idx) = self.menu_state.get_menu_at_position(&all_menus, col) {
                 // Toggle menu: if same menu is open, close it; otherwise open clicked menu
@@ -3594,14 +3659,7 @@ impl Editor {
 
         // Check if click is on an open menu dropdown
         if let Some(active_idx) = self.menu_state.active_menu {
-            let all_menus: Vec<crate::config::Menu> = self
-                .config
-                .menu
-                .menus
-                .iter()
-                .chain(self.menu_state.plugin_menus.iter())
-                .cloned()
-                .collect();
+            let all_menus = self.menu_state.menus(&self.config.menu.menus);
 
             if let Some(menu) = all_menus.get(active_idx) {
                 // Handle click on menu dropdown chain (including submenus)
@@ -3929,6 +3987,7 @@ impl Editor {
             ) else {
                 return Ok(());
             };
+            let target_position = state.buffer.snap_to_grapheme_boundary(target_position);
 
             // Move cursor to target position while keeping anchor to create selection
             let primary_cursor_id = state.cursors.primary_id();
@@ -4834,6 +4893,7 @@ impl Editor {
             ) else {
                 return Ok(());
             };
+            let target_position = state.buffer.snap_to_grapheme_boundary(target_position);
 
             // Check for onClick text property at this position
             // This enables clickable UI elements in virtual buffers
@@ -4961,13 +5021,7 @@ impl Editor {
         use crate::config::MenuItem;
 
         // Calculate dropdown positions for the entire chain
-        let mut x_offset = 0usize;
-        for (idx, m) in all_menus.iter().enumerate() {
-            if idx == menu_index {
-                break;
-            }
-            x_offset += m.label.len() + 3;
-        }
+        let x_offset = crate::view::ui::menu::menu_label_offsets(all_menus)[menu_index];
 
         let mut current_items: &[MenuItem] = &menu.items;
         let mut current_x = x_offset as u16;
@@ -4975,19 +5029,11 @@ impl Editor {
 
         let mut dropdown_rects = Vec::new();
 
+        let inset = self.theme.menu_border_style.inset();
         for depth in 0..=self.menu_state.submenu_path.len() {
-            let max_width = current_items
-                .iter()
-                .filter_map(|item| match item {
-                    MenuItem::Action { label, .. } => Some(label.len() + 20),
-                    MenuItem::Submenu { label, .. } => Some(label.len() + 20),
-                    MenuItem::Separator { .. } => Some(20),
-                })
-                .max()
-                .unwrap_or(20)
-                .min(40) as u16;
+            let max_width = crate::view::ui::menu::dropdown_width(menu, current_items) as u16;
 
-            let dropdown_height = current_items.len() as u16 + 2;
+            let dropdown_height = current_items.len() as u16 + 2 * inset;
 
             dropdown_rects.push((
                 current_x,
@@ -5000,9 +5046,12 @@ impl Editor {
 
             if depth < self.menu_state.submenu_path.len() {
                 let submenu_idx = self.menu_state.submenu_path[depth];
-                if let Some(MenuItem::Submenu { items, .. }) = current_items.get(submenu_idx) {
-                    let next_x = current_x + max_width - 1;
-                    let next_y = current_y + submenu_idx as u16 + 1;
+                if let Some(items) = current_items
+                    .get(submenu_idx)
+                    .and_then(|item| self.menu_state.submenu_items(item))
+                {
+                    let next_x = current_x + max_width - inset;
+                    let next_y = current_y + submenu_idx as u16 + inset;
                     current_items = items;
                     current_x = next_x;
                     current_y = next_y;
@@ -5015,7 +5064,7 @@ impl Editor {
         // Check from deepest submenu to main dropdown
         for (dx, dy, width, height, depth, item_count) in dropdown_rects.iter().rev() {
             if col >= *dx && col < dx + width && row >= *dy && row < dy + height {
-                let item_row = row.saturating_sub(*dy + 1);
+                let item_row = row.saturating_sub(*dy + inset);
                 let item_idx = item_row as usize;
 
                 if item_idx < *item_count {
@@ -5047,13 +5096,7 @@ impl Editor {
         // Similar to render_dropdown_chain but for hit testing
 
         // Calculate the x position of the top-level dropdown
-        let mut x_offset = 0usize;
-        for (idx, m) in all_menus.iter().enumerate() {
-            if idx == menu_index {
-                break;
-            }
-            x_offset += m.label.len() + 3;
-        }
+        let x_offset = crate::view::ui::menu::menu_label_offsets(all_menus)[menu_index];
 
         let mut current_items: &[MenuItem] = &menu.items;
         let mut current_x = x_offset as u16;
@@ -5063,19 +5106,11 @@ impl Editor {
         // This ensures clicks on nested submenus take priority
         let mut dropdown_rects = Vec::new();
 
+        let inset = self.theme.menu_border_style.inset();
         for depth in 0..=self.menu_state.submenu_path.len() {
-            let max_width = current_items
-                .iter()
-                .filter_map(|item| match item {
-                    MenuItem::Action { label, .. } => Some(label.len() + 20),
-                    MenuItem::Submenu { label, .. } => Some(label.len() + 20),
-                    MenuItem::Separator { .. } => Some(20),
-                })
-                .max()
-                .unwrap_or(20)
-                .min(40) as u16;
+            let max_width = crate::view::ui::menu::dropdown_width(menu, current_items) as u16;
 
-            let dropdown_height = current_items.len() as u16 + 2;
+            let dropdown_height = current_items.len() as u16 + 2 * inset;
 
             dropdown_rects.push((
                 current_x,
@@ -5089,9 +5124,12 @@ impl Editor {
             // Navigate to next level if there is one
             if depth < self.menu_state.submenu_path.len() {
                 let submenu_idx = self.menu_state.submenu_path[depth];
-                if let Some(MenuItem::Submenu { items, .. }) = current_items.get(submenu_idx) {
-                    let next_x = current_x + max_width - 1;
-                    let next_y = current_y + submenu_idx as u16 + 1;
+                if let Some(items) = current_items
+                    .get(submenu_idx)
+                    .and_then(|item| self.menu_state.submenu_items(item))
+                {
+                    let next_x = current_x + max_width - inset;
+                    let next_y = current_y + submenu_idx as u16 + inset;
                     current_items = items;
                     current_x = next_x;
                     current_y = next_y;
@@ -5105,7 +5143,7 @@ impl Editor {
         for (dx, dy, width, height, depth, items) in dropdown_rects.iter().rev() {
             if col >= *dx && col < dx + width && row >= *dy && row < dy + height {
                 // Click is inside this dropdown
-                let item_row = row.saturating_sub(*dy + 1); // -1 for border
+                let item_row = row.saturating_sub(*dy + inset); // skip the top border
                 let item_idx = item_row as usize;
 
                 if item_idx < items.len() {
@@ -5115,15 +5153,12 @@ impl Editor {
                             // Clicked on separator - do nothing but consume the click
                             return Ok(Some(Ok(())));
                         }
-                        MenuItem::Submenu {
-                            items: submenu_items,
-                            ..
-                        } => {
+                        item @ MenuItem::Submenu { .. } => {
                             // Clicked on submenu - open it
                             // First, truncate submenu_path to this depth
                             self.menu_state.submenu_path.truncate(*depth);
                             // Then add this submenu
-                            if !submenu_items.is_empty() {
+                            if self.menu_state.load_submenu(item) {
                                 self.menu_state.submenu_path.push(item_idx);
                                 self.menu_state.highlighted_item = Some(0);
                             }
@@ -5134,7 +5169,9 @@ impl Editor {
                             let action_name = action.clone();
                             let action_args = args.clone();
 
+                            self.menu_state.commit_preview();
                             self.menu_state.close_menu();
+                            self.menu_state.record_action(&action_name, &action_args);
 
                             if let Some(action) = Action::from_str(&action_name, &action_args) {
                                 return Ok(Some(self.handle_action(action)));
@@ -5200,15 +5237,17 @@ impl Editor {
     /// Apply a theme by name and persist it to config
     pub(super) fn apply_theme(&mut self, theme_name: &str) {
         if !theme_name.is_empty() {
-            self.theme = crate::view::theme::Theme::from_name(theme_name);
+            let theme = self.load_theme(theme_name);
 
             // Update the config in memory
-            self.config.theme = self.theme.name.clone().into();
+            self.config.theme = theme.name.clone().into();
 
             // Persist to config file
             self.save_theme_to_config();
 
-            self.set_status_message(format!("Theme changed to '{}'", self.theme.name));
+            self.set_status_message(format!("Theme changed to '{}'", theme.name));
+            // Takes effect at the start of the next frame
+            self.theme.stage(theme);
         }
     }
 
@@ -45,8 +45,9 @@ pub(crate) fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
 }
 
 use self::types::{
-    Bookmark, CachedLayout, EventLineInfo, InteractiveReplaceState, LspMessageEntry,
-    LspProgressInfo, MacroRecordingState, MouseState, SearchState, DEFAULT_BACKGROUND_FILE,
+    Bookmark, CachedLayout, EventLineInfo, IncrementalSearch, InteractiveReplaceState,
+    LspMessageEntry, LspProgressInfo, MacroRecordingState, MouseState, SearchState,
+    DEFAULT_BACKGROUND_FILE,
 };
 use crate::config::Config;
 use crate::config_io::DirectoryContext;
@@ -59,12 +60,18 @@ use crate::input::multi_cursor::{
     add_cursor_above, add_cursor_at_next_match, add_cursor_below, AddCursorResult,
 };
 use crate::input::position_history::PositionHistory;
+use crate::model::buffer::ExternalChange;
+use crate::model::cursor::CursorShape;
 use crate::model::event::{CursorId, Event, EventLog, SplitDirection, SplitId};
+use crate::primitives::word_navigation::WordDef;
 use crate::services::async_bridge::{AsyncBridge, AsyncMessage};
 use crate::services::fs::{FsBackend, FsManager, LocalFsBackend};
 use crate::services::lsp::client::LspServerConfig;
 use crate::services::lsp::manager::{detect_language, LspManager, LspSpawnResult};
 use crate::services::plugins::api::{BufferSavedDiff, PluginCommand};
+use crate::services::plugins::buffer_changes::BufferChanges;
+use crate::services::plugins::hooks::EditHook;
+use crate::services::plugins::timers::TimerQueue;
 use crate::services::plugins::PluginManager;
 use crate::services::recovery::{RecoveryConfig, RecoveryService};
 use crate::services::time_source::{RealTimeSource, SharedTimeSource};
@@ -73,7 +80,7 @@ use crate::view::file_tree::{FileTree, FileTreeView};
 use crate::view::prompt::{Prompt, PromptType};
 use crate::view::split::{SplitManager, SplitViewState};
 use crate::view::ui::{
-    FileExplorerRenderer, SplitRenderer, StatusBarRenderer, SuggestionsRenderer,
+    FileExplorerRenderer, RenderOptions, SplitRenderer, StatusBarRenderer, SuggestionsRenderer,
 };
 use crossterm::event::{KeyCode, KeyModifiers};
 use lsp_types::{Position, Range as LspRange, TextDocumentContentChangeEvent};
@@ -100,6 +107,20 @@ fn uri_to_path(uri: &lsp_types::Uri) -> Result<PathBuf, String> {
         .map_err(|_| "URI is not a file path".to_string())
 }
 
+/// Load a theme by name, degraded to `support`
+fn load_theme(name: &str, support: crate::view::theme::ColorSupport) -> crate::view::theme::Theme {
+    let theme = crate::view::theme::Theme::from_name(name);
+    warn_about_theme_contrast(&theme);
+    theme.degrade_to(support)
+}
+
+/// Log a warning for each hard-to-read color pair of a newly loaded theme
+fn warn_about_theme_contrast(theme: &crate::view::theme::Theme) {
+    for warning in theme.contrast_warnings() {
+        tracing::warn!("Theme '{}': {}", theme.name, warning);
+    }
+}
+
 /// The main editor struct - manages multiple buffers, clipboard, and rendering
 pub struct Editor {
     /// All open buffers
@@ -124,7 +145,10 @@ pub struct Editor {
     grammar_registry: std::sync::Arc<crate::primitives::grammar_registry::GrammarRegistry>,
 
     /// Active theme
-    theme: crate::view::theme::Theme,
+    theme: crate::view::theme::ThemeManager,
+
+    /// Color capability of the terminal; themes are degraded to fit it
+    color_support: crate::view::theme::ColorSupport,
 
     /// Optional ANSI background image
     ansi_background: Option<crate::primitives::ansi_background::AnsiBackground>,
@@ -141,6 +165,12 @@ pub struct Editor {
     /// Shared clipboard (handles both internal and system clipboard)
     clipboard: crate::services::clipboard::Clipboard,
 
+    /// History of cut and copied text for "paste previous"
+    kill_ring: crate::services::kill_ring::KillRing,
+
+    /// Vim-style named registers
+    registers: crate::services::registers::Registers,
+
     /// Should the editor quit?
     should_quit: bool,
 
@@ -268,6 +298,9 @@ pub struct Editor {
     /// Search state (if search is active)
     search_state: Option<SearchState>,
 
+    /// In-progress incremental search (while its prompt is open)
+    incremental_search: Option<IncrementalSearch>,
+
     /// Search highlight namespace (for efficient bulk removal)
     search_namespace: crate::view::overlay::OverlayNamespace,
 
@@ -295,6 +328,12 @@ pub struct Editor {
     /// Plugin manager (handles both enabled and disabled cases)
     plugin_manager: PluginManager,
 
+    /// Timers scheduled by plugins, fired from the event loop
+    plugin_timers: TimerQueue,
+
+    /// Edits not yet reported through the `buffer_changed` hook
+    pending_buffer_changes: BufferChanges,
+
     /// Track which byte ranges have been seen per buffer (for lines_changed optimization)
     /// Maps buffer_id -> set of (byte_start, byte_end) ranges that have been processed
     /// Using byte ranges instead of line numbers makes this agnostic to line number shifts
@@ -337,6 +376,9 @@ pub struct Editor {
     /// Bookmarks (character key -> bookmark)
     bookmarks: HashMap<char, Bookmark>,
 
+    /// Remembered cursor, marks and scroll per file (None until enabled)
+    file_sessions: Option<crate::session::SessionStore>,
+
     /// Global search options (persist across searches)
     search_case_sensitive: bool,
     search_whole_word: bool,
@@ -586,6 +628,7 @@ impl Editor {
 
         // Load theme from config
         let theme = crate::view::theme::Theme::from_name(&config.theme);
+        warn_about_theme_contrast(&theme);
 
         // Load grammar registry for TextMate syntax highlighting
         let grammar_registry =
@@ -657,6 +700,7 @@ impl Editor {
         let initial_split_id = split_manager.active_split();
         let mut initial_view_state = SplitViewState::with_buffer(width, height, buffer_id);
         initial_view_state.viewport.line_wrap_enabled = config.editor.line_wrap;
+        initial_view_state.viewport.scroll_offset = config.editor.scroll_offset;
         split_view_states.insert(initial_split_id, initial_view_state);
 
         // Initialize filesystem manager for file explorer
@@ -744,12 +788,15 @@ impl Editor {
             config,
             dir_context: dir_context.clone(),
             grammar_registry,
-            theme,
+            theme: crate::view::theme::ThemeManager::new(theme),
+            color_support: crate::view::theme::ColorSupport::TrueColor,
             ansi_background: None,
             ansi_background_path: None,
             background_fade: crate::primitives::ansi_background::DEFAULT_BACKGROUND_FADE,
             keybindings,
             clipboard: crate::services::clipboard::Clipboard::new(),
+            kill_ring: crate::services::kill_ring::KillRing::default(),
+            registers: crate::services::registers::Registers::new(),
             should_quit: false,
             restart_with_dir: None,
             status_message: None,
@@ -790,6 +837,7 @@ impl Editor {
             hover_symbol_overlay: None,
             mouse_hover_screen_position: None,
             search_state: None,
+            incremental_search: None,
             search_namespace: crate::view::overlay::OverlayNamespace::from_string(
                 "search".to_string(),
             ),
@@ -803,6 +851,8 @@ impl Editor {
             cached_layout: CachedLayout::default(),
             command_registry,
             plugin_manager,
+            plugin_timers: TimerQueue::new(),
+            pending_buffer_changes: BufferChanges::new(),
             seen_byte_ranges: HashMap::new(),
             panel_ids: HashMap::new(),
             search_history: {
@@ -833,6 +883,7 @@ impl Editor {
             stored_diagnostics: HashMap::new(),
             event_broadcaster: crate::model::control_event::EventBroadcaster::default(),
             bookmarks: HashMap::new(),
+            file_sessions: None,
             search_case_sensitive: true,
             search_whole_word: false,
             search_use_regex: false,
@@ -964,6 +1015,22 @@ impl Editor {
         self.active_state().editing_disabled
     }
 
+    /// The DECSCUSR cursor style for the active buffer's mode, which the
+    /// terminal backend sends whenever it changes between frames
+    pub fn cursor_style(&self) -> crossterm::cursor::SetCursorStyle {
+        use crossterm::cursor::SetCursorStyle;
+
+        let blink = self.config.editor.cursor_blink;
+        match (self.active_state().cursor_shape(), blink) {
+            (CursorShape::Block, true) => SetCursorStyle::BlinkingBlock,
+            (CursorShape::Block, false) => SetCursorStyle::SteadyBlock,
+            (CursorShape::Bar, true) => SetCursorStyle::BlinkingBar,
+            (CursorShape::Bar, false) => SetCursorStyle::SteadyBar,
+            (CursorShape::Underline, true) => SetCursorStyle::BlinkingUnderScore,
+            (CursorShape::Underline, false) => SetCursorStyle::SteadyUnderScore,
+        }
+    }
+
     /// Resolve a keybinding for the active buffer's mode
     ///
     /// If the active buffer has a mode (virtual buffer), check if that mode
@@ -1171,6 +1238,49 @@ impl Editor {
         true
     }
 
+    /// Run the `timer` hook for every plugin timer whose deadline has passed
+    ///
+    /// Each timer fires once, and only in the plugin that scheduled it.
+    /// Returns true if any hook ran, since plugin handlers usually change
+    /// something on screen.
+    pub fn fire_plugin_timers(&mut self) -> bool {
+        if self.plugin_timers.is_empty() {
+            return false;
+        }
+        let due = self.plugin_timers.take_due(self.time_source.now());
+        for timer in &due {
+            self.plugin_manager.run_plugin_hook(
+                timer.plugin.as_deref(),
+                "timer",
+                crate::services::plugins::hooks::HookArgs::Timer { token: timer.token },
+            );
+        }
+        !due.is_empty()
+    }
+
+    /// Run the `buffer_changed` hook for every buffer edited since the last call
+    ///
+    /// Called once per frame, so a burst of edits to one buffer is reported as
+    /// a single change. Returns true if any hook ran.
+    pub fn fire_buffer_changed_hooks(&mut self) -> bool {
+        if self.pending_buffer_changes.is_empty() {
+            return false;
+        }
+        let changes = self.pending_buffer_changes.take();
+        for (buffer_id, change) in &changes {
+            self.plugin_manager.run_hook(
+                "buffer_changed",
+                crate::services::plugins::hooks::HookArgs::BufferChanged {
+                    buffer_id: *buffer_id,
+                    start: change.start,
+                    old_end: change.old_end,
+                    new_end: change.new_end,
+                },
+            );
+        }
+        true
+    }
+
     /// Load an ANSI background image from a user-provided path
     fn load_ansi_background(&mut self, input: &str) -> io::Result<()> {
         let trimmed = input.trim();
@@ -1200,12 +1310,20 @@ impl Editor {
         Ok(())
     }
 
+    /// Word characters configured for the language of `path`
+    fn word_def_for(&self, path: &Path) -> WordDef {
+        detect_language(path, &self.config.languages)
+            .and_then(|language| self.config.languages.get(&language))
+            .map(|config| WordDef::with_extra(&config.word_chars))
+            .unwrap_or_default()
+    }
+
     /// Open a file and return its buffer ID
     ///
     /// If the file doesn't exist, creates an unsaved buffer with that filename.
     /// Saving the buffer will create the file.
     pub fn open_file(&mut self, path: &Path) -> io::Result<BufferId> {
-        let buffer_id = self.open_file_no_focus(path)?;
+        let (buffer_id, loaded) = self.load_file_buffer(path)?;
 
         // Check if this was an already-open buffer or a new one
         // For already-open buffers, just switch to them
@@ -1226,6 +1344,9 @@ impl Editor {
         }
 
         self.set_active_buffer(buffer_id);
+        if loaded {
+            self.restore_file_session(buffer_id);
+        }
 
         // Use display_name from metadata for relative path display
         let display_name = self
@@ -1258,6 +1379,12 @@ impl Editor {
     ///
     /// If the file doesn't exist, creates an unsaved buffer with that filename.
     pub fn open_file_no_focus(&mut self, path: &Path) -> io::Result<BufferId> {
+        self.load_file_buffer(path).map(|(buffer_id, _)| buffer_id)
+    }
+
+    /// Find the buffer for `path`, loading the file into a new one if it isn't
+    /// open yet; the flag says whether it was loaded
+    fn load_file_buffer(&mut self, path: &Path) -> io::Result<(BufferId, bool)> {
         // Determine if we're opening a non-existent file (for creating new files)
         let file_exists = path.exists();
 
@@ -1296,7 +1423,7 @@ impl Editor {
             .map(|(id, _)| *id);
 
         if let Some(id) = already_open {
-            return Ok(id);
+            return Ok((id, false));
         }
 
         // If the current buffer is empty and unmodified, replace it instead of creating a new one
@@ -1338,6 +1465,7 @@ impl Editor {
             new_state
         };
         // Note: line_wrap_enabled is set on SplitViewState.viewport when the split is created
+        state.word_def = self.word_def_for(path);
 
         // Check if the buffer contains binary content
         let is_binary = state.buffer.is_binary();
@@ -1395,7 +1523,7 @@ impl Editor {
             },
         );
 
-        Ok(buffer_id)
+        Ok((buffer_id, true))
     }
 
     /// Navigate to a specific line and column in the active buffer.
@@ -1436,7 +1564,8 @@ impl Editor {
                         .line_iterator(clamped_offset, estimated_line_length);
                     let line_start = iter.current_position();
                     // Add column offset, clamped to buffer length
-                    (line_start + target_col).min(buffer_len)
+                    let position = (line_start + target_col).min(buffer_len);
+                    state.buffer.snap_to_grapheme_boundary(position)
                 } else {
                     clamped_offset
                 }
@@ -1444,7 +1573,9 @@ impl Editor {
                 // Small file mode: use exact line position
                 let max_line = state.buffer.line_count().unwrap_or(1).saturating_sub(1);
                 let actual_line = target_line.min(max_line);
-                state.buffer.line_col_to_position(actual_line, target_col)
+                let position = state.buffer.line_col_to_position(actual_line, target_col);
+                // The column counts bytes, so it may land inside a character
+                state.buffer.snap_to_grapheme_boundary(position)
             };
 
             let event = crate::model::event::Event::MoveCursor {
@@ -1538,6 +1669,7 @@ impl Editor {
             let mut view_state =
                 SplitViewState::with_buffer(self.terminal_width, self.terminal_height, buffer_id);
             view_state.viewport.line_wrap_enabled = self.config.editor.line_wrap;
+            view_state.viewport.scroll_offset = self.config.editor.scroll_offset;
             self.split_view_states.insert(active_split, view_state);
         }
 
@@ -1572,6 +1704,7 @@ impl Editor {
             state.buffer.delete_bytes(0, current_len);
         }
         state.buffer.insert(0, &text);
+        state.forget_indentation();
 
         // Clear modified flag since this is virtual buffer content setting, not user edits
         state.buffer.clear_modified();
@@ -1762,10 +1895,15 @@ impl Editor {
 
         // Apply runtime changes
         if old_theme != self.config.theme {
-            self.theme = crate::view::theme::Theme::from_name(&self.config.theme);
+            let theme = self.load_theme(&self.config.theme);
+            self.theme.stage(theme);
             tracing::info!("Theme changed to '{}'", self.config.theme.0);
         }
 
+        for view_state in self.split_view_states.values_mut() {
+            view_state.viewport.scroll_offset = self.config.editor.scroll_offset;
+        }
+
         // Update keybindings
         self.keybindings = KeybindingResolver::new(&self.config);
 
@@ -2071,6 +2209,8 @@ impl Editor {
 
     /// Internal helper to close a buffer (shared by close_buffer and force_close_buffer)
     fn close_buffer_internal(&mut self, id: BufferId) -> io::Result<()> {
+        self.record_file_session(id);
+
         // If it's the last buffer, create a new empty buffer and focus file explorer
         let is_last_buffer = self.buffers.len() == 1;
         let replacement_buffer = if is_last_buffer {
@@ -2403,6 +2543,26 @@ impl Editor {
         self.in_navigation = false;
     }
 
+    /// Shift position history entries in `buffer_id` to follow an edit
+    pub(crate) fn adjust_position_history_for_event(&mut self, buffer_id: BufferId, event: &Event) {
+        match event {
+            Event::Insert { position, text, .. } => {
+                self.position_history
+                    .adjust_for_insert(buffer_id, *position, text.len());
+            }
+            Event::Delete { range, .. } => {
+                self.position_history
+                    .adjust_for_delete(buffer_id, range.clone());
+            }
+            Event::Batch { events, .. } => {
+                for event in events {
+                    self.adjust_position_history_for_event(buffer_id, event);
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Navigate forward in position history
     pub fn navigate_forward(&mut self) {
         // Set flag to prevent recording this navigation movement
@@ -2462,6 +2622,7 @@ impl Editor {
                     current_buffer_id,
                 );
                 view_state.viewport.line_wrap_enabled = self.config.editor.line_wrap;
+                view_state.viewport.scroll_offset = self.config.editor.scroll_offset;
                 self.split_view_states.insert(new_split_id, view_state);
                 // Restore the new split's view state to the buffer
                 self.restore_current_split_view_state();
@@ -2495,6 +2656,7 @@ impl Editor {
                     current_buffer_id,
                 );
                 view_state.viewport.line_wrap_enabled = self.config.editor.line_wrap;
+                view_state.viewport.scroll_offset = self.config.editor.scroll_offset;
                 self.split_view_states.insert(new_split_id, view_state);
                 // Restore the new split's view state to the buffer
                 self.restore_current_split_view_state();
@@ -2611,7 +2773,8 @@ impl Editor {
         }
     }
 
-    /// Adjust cursors in other splits that share the same buffer after an edit
+    /// Adjust cursors and scroll positions in other splits that share the same
+    /// buffer after an edit
     fn adjust_other_split_cursors_for_event(&mut self, event: &Event) {
         // Find the edit parameters from the event
         let adjustments = match event {
@@ -2657,6 +2820,9 @@ impl Editor {
                     view_state
                         .cursors
                         .adjust_for_edit(*edit_pos, *old_len, *new_len);
+                    view_state
+                        .viewport
+                        .adjust_for_edit(*edit_pos, *old_len, *new_len);
                 }
             }
         }
@@ -2799,6 +2965,29 @@ impl Editor {
         self.gpm_active = active;
     }
 
+    /// Set the terminal's color capability
+    ///
+    /// The active theme (and any theme loaded later) is degraded to the
+    /// 256-color or 16-color palette when the terminal can't show RGB colors.
+    pub fn set_color_support(&mut self, support: crate::view::theme::ColorSupport) {
+        self.color_support = support;
+        let theme = self.theme.degrade_to(support);
+        self.theme.stage(theme);
+    }
+
+    /// Load a theme by name, degraded to the terminal's color capability
+    fn load_theme(&self, name: &str) -> crate::view::theme::Theme {
+        load_theme(name, self.color_support)
+    }
+
+    /// Stage the builtin theme following the current one, loaded like any
+    /// other theme
+    /// Returns the name of the staged theme
+    pub fn cycle_theme(&mut self) -> &'static str {
+        let support = self.color_support;
+        self.theme.cycle(|name| load_theme(name, support))
+    }
+
     /// Toggle inlay hints visibility
     pub fn toggle_inlay_hints(&mut self) {
         self.config.editor.enable_inlay_hints = !self.config.editor.enable_inlay_hints;
@@ -2930,10 +3119,15 @@ impl Editor {
 
         // Apply theme change if needed
         if old_theme != self.config.theme {
-            self.theme = crate::view::theme::Theme::from_name(&self.config.theme);
+            let theme = self.load_theme(&self.config.theme);
+            self.theme.stage(theme);
             tracing::info!("Theme changed to '{}'", self.config.theme.0);
         }
 
+        for view_state in self.split_view_states.values_mut() {
+            view_state.viewport.scroll_offset = self.config.editor.scroll_offset;
+        }
+
         // Always reload keybindings (complex types don't implement PartialEq)
         self.keybindings = KeybindingResolver::new(&self.config);
 
@@ -3132,15 +3326,48 @@ impl Editor {
             .unwrap_or_else(|| "[No Name]".to_string())
     }
 
+    /// Add a hook that may reject or rewrite buffer edits, replacing any
+    /// edit hook of the same name (see `PluginManager::add_edit_hook`)
+    pub fn add_edit_hook(&mut self, name: &str, hook: Box<dyn EditHook>) {
+        self.plugin_manager.add_edit_hook(name, hook);
+    }
+
+    /// Log an event in the active buffer's undo history and apply it
+    ///
+    /// The edit hooks run first (see `apply_event_to_active_buffer`), so what
+    /// gets logged is the edit that was actually made, and nothing is logged
+    /// for a rejected one.
+    pub fn log_and_apply_event(&mut self, event: &Event) {
+        if let Some(applied) = self.apply_event_to_active_buffer(event) {
+            self.active_event_log_mut().append(applied);
+        }
+    }
+
     /// Apply an event to the active buffer with all cross-cutting concerns.
     /// This is the centralized method that automatically handles:
+    /// - Edit hooks, which may reject the event or rewrite inserted text
     /// - Event application to buffer
     /// - Plugin hooks (after-insert, after-delete, etc.)
     /// - LSP notifications
     /// - Any other cross-cutting concerns
     ///
-    /// All event applications MUST go through this method to ensure consistency.
-    pub fn apply_event_to_active_buffer(&mut self, event: &Event) {
+    /// Returns the event that was applied, or `None` if an edit hook rejected
+    /// it. All event applications MUST go through this method (or
+    /// `log_and_apply_event`) to ensure consistency.
+    pub fn apply_event_to_active_buffer(&mut self, event: &Event) -> Option<Event> {
+        let buffer_id = self.active_buffer();
+        let event = self.plugin_manager.filter_edit(event, buffer_id)?;
+        self.apply_unfiltered_event_to_active_buffer(&event);
+        for edit in crate::services::plugins::event_hooks::applied_edits(&event, buffer_id) {
+            self.plugin_manager.run_after_edit(&edit);
+        }
+        Some(event)
+    }
+
+    /// Apply an event to the active buffer without running the edit hooks
+    ///
+    /// Used to replay the undo history, which must reproduce exactly what was logged.
+    pub(crate) fn apply_unfiltered_event_to_active_buffer(&mut self, event: &Event) {
         // Handle View events at Editor level - View events go to SplitViewState, not EditorState
         // This properly separates Buffer state from View state
         match event {
@@ -3194,15 +3421,18 @@ impl Editor {
         // 2. Adjust cursors in other splits that share the same buffer
         self.adjust_other_split_cursors_for_event(event);
 
-        // 3. Clear search highlights on edit (Insert/Delete events)
-        // This preserves highlights while navigating but clears them when modifying text
-        // EXCEPT during interactive replace where we want to keep highlights visible
+        // 2b. Keep navigation history pointing at the same text
+        self.adjust_position_history_for_event(self.active_buffer(), event);
+
+        // 3. Update search on edit (Insert/Delete events)
+        // Cached matches follow the edit so next/previous match keep working
+        // EXCEPT during interactive replace, which manages its own matches
         let in_interactive_replace = self.interactive_replace_state.is_some();
 
         if !in_interactive_replace {
             match event {
                 Event::Insert { .. } | Event::Delete { .. } => {
-                    self.clear_search_highlights();
+                    self.update_search_for_edit(self.active_buffer(), event);
                 }
                 Event::Batch { events, .. } => {
                     // Check if batch contains any Insert/Delete events
@@ -3210,7 +3440,7 @@ impl Editor {
                         .iter()
                         .any(|e| matches!(e, Event::Insert { .. } | Event::Delete { .. }));
                     if has_edits {
-                        self.clear_search_highlights();
+                        self.update_search_for_edit(self.active_buffer(), event);
                     }
                 }
                 _ => {}
@@ -3259,6 +3489,14 @@ impl Editor {
                     *seen = adjusted;
                 }
 
+                self.pending_buffer_changes.record(
+                    &crate::services::plugins::hooks::AppliedEdit::Insert {
+                        buffer_id,
+                        range: insert_position..insert_position + insert_len,
+                        text: text.clone(),
+                    },
+                );
+
                 Some((
                     "after_insert",
                     crate::services::plugins::hooks::HookArgs::AfterInsert {
@@ -3308,6 +3546,14 @@ impl Editor {
                     *seen = adjusted;
                 }
 
+                self.pending_buffer_changes.record(
+                    &crate::services::plugins::hooks::AppliedEdit::Delete {
+                        buffer_id,
+                        range: range.clone(),
+                        deleted_text: deleted_text.clone(),
+                    },
+                );
+
                 Some((
                     "after_delete",
                     crate::services::plugins::hooks::HookArgs::AfterDelete {
@@ -3543,6 +3789,38 @@ impl Editor {
 
     /// Copy the current selection to clipboard
     pub fn copy_selection(&mut self) {
+        let text = self.selected_text();
+        if !text.is_empty() {
+            self.kill_ring.push(text.clone());
+            self.registers.yank(text.as_bytes());
+            self.clipboard.copy(text);
+            self.status_message = Some("Copied".to_string());
+        }
+    }
+
+    /// Copy the current selection into register `reg`
+    pub fn copy_to_register(&mut self, reg: char) {
+        let text = self.selected_text();
+        if text.is_empty() {
+            self.set_status_message("No selection to copy".to_string());
+            return;
+        }
+        self.registers.yank_to(reg, text.as_bytes());
+        self.set_status_message(format!("Copied to register '{}'", reg));
+    }
+
+    /// Paste the content of register `reg` at the cursor
+    pub fn paste_from_register(&mut self, reg: char) {
+        let state = &self.buffers[&self.active_buffer()];
+        let Some(event) = self.registers.paste_from(reg, state) else {
+            self.set_status_message(format!("Register '{}' is empty", reg));
+            return;
+        };
+        self.log_and_apply_event(&event);
+    }
+
+    /// Text of all selections, one per line
+    fn selected_text(&mut self) -> String {
         // Collect ranges first
         let ranges: Vec<_> = {
             let state = self.active_state();
@@ -3562,11 +3840,7 @@ impl Editor {
             let range_text = state.get_text_range(range.start, range.end);
             text.push_str(&range_text);
         }
-
-        if !text.is_empty() {
-            self.clipboard.copy(text);
-            self.status_message = Some("Copied".to_string());
-        }
+        text
     }
 
     /// Cut the current selection to clipboard
@@ -3601,8 +3875,7 @@ impl Editor {
 
         // Apply events
         for event in events {
-            self.active_event_log_mut().append(event.clone());
-            self.apply_event_to_active_buffer(&event);
+            self.log_and_apply_event(&event);
         }
 
         if !deletions.is_empty() {
@@ -3618,22 +3891,97 @@ impl Editor {
             None => return,
         };
 
-        let state = self.active_state();
-        let cursor_id = state.cursors.primary_id();
-        let position = state.cursors.primary().position;
+        if self.active_state().cursors.primary().has_block_selection() {
+            self.paste_block(&paste_text);
+            return;
+        }
 
-        let event = Event::Insert {
-            position,
-            text: paste_text,
-            cursor_id,
+        // Text copied outside the editor joins the ring too, so "paste
+        // previous" can cycle from it
+        if self.kill_ring.latest() != Some(paste_text.as_str()) {
+            self.kill_ring.push(paste_text);
+        }
+        let state = &self.buffers[&self.active_buffer()];
+        let Some(event) = self.kill_ring.paste(state) else {
+            return;
+        };
+
+        self.log_and_apply_event(&event);
+
+        self.status_message = Some("Pasted".to_string());
+    }
+
+    /// Paste from the clipboard, re-indented to match the cursor's line
+    pub fn paste_reindent(&mut self) {
+        let Some(paste_text) = self.clipboard.paste() else {
+            return;
+        };
+        let Some(event) = self.active_state().paste_reindent(&paste_text) else {
+            return;
+        };
+        if self.kill_ring.latest() != Some(paste_text.as_str()) {
+            self.kill_ring.push(paste_text);
+        }
+
+        self.log_and_apply_event(&event);
+
+        self.status_message = Some("Pasted".to_string());
+    }
+
+    /// Replace the text just pasted with the previous kill ring entry
+    pub fn paste_previous(&mut self) {
+        let state = &self.buffers[&self.active_buffer()];
+        let Some(event) = self.kill_ring.paste_cycle(state) else {
+            self.status_message = Some("Previous command was not a paste".to_string());
+            return;
         };
 
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
+    }
+
+    /// Paste `text` one line per row at the top-left corner of the primary
+    /// cursor's block selection
+    fn paste_block(&mut self, text: &str) {
+        let state = self.active_state();
+        let cursor = state.cursors.primary();
+        let Some(anchor) = cursor.block_anchor else {
+            return;
+        };
+        let buffer = &state.buffer;
+        let display_column = |line: usize, byte_column: usize| {
+            let content = buffer.get_line(line).unwrap_or_default();
+            let content = String::from_utf8_lossy(&content);
+            crate::primitives::display_width::visual_column_at_byte(
+                &content,
+                byte_column.min(content.len()),
+            )
+        };
+        let line = buffer.get_line_number(cursor.position);
+        let byte_column =
+            cursor.position - buffer.line_start_offset(line).unwrap_or(cursor.position);
+        let col = display_column(line, byte_column).min(display_column(anchor.line, anchor.column));
 
+        let lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let Some(event) = state.paste_block(&lines, line.min(anchor.line), col) else {
+            return;
+        };
+        self.log_and_apply_event(&event);
         self.status_message = Some("Pasted".to_string());
     }
 
+    /// Trim trailing whitespace and add a final newline to the active buffer,
+    /// as configured, before it is saved
+    pub(crate) fn apply_save_cleanup(&mut self) {
+        let editor = &self.config.editor;
+        let (trim, final_newline) = (editor.trim_trailing_whitespace, editor.ensure_final_newline);
+        if !(trim || final_newline) || self.is_editing_disabled() {
+            return;
+        }
+        if let Some(event) = self.active_state().save_cleanup(trim, final_newline) {
+            self.log_and_apply_event(&event);
+        }
+    }
+
     /// Add a cursor at the next occurrence of the selected text
     /// If no selection, does nothing
     pub fn add_cursor_at_next_match(&mut self) {
@@ -3652,8 +4000,7 @@ impl Editor {
                 };
 
                 // Log and apply the event
-                self.active_event_log_mut().append(event.clone());
-                self.apply_event_to_active_buffer(&event);
+                self.log_and_apply_event(&event);
 
                 self.status_message = Some(format!("Added cursor at match ({})", total_cursors));
             }
@@ -3680,8 +4027,7 @@ impl Editor {
                 };
 
                 // Log and apply the event
-                self.active_event_log_mut().append(event.clone());
-                self.apply_event_to_active_buffer(&event);
+                self.log_and_apply_event(&event);
 
                 self.status_message = Some(format!("Added cursor above ({})", total_cursors));
             }
@@ -3708,8 +4054,7 @@ impl Editor {
                 };
 
                 // Log and apply the event
-                self.active_event_log_mut().append(event.clone());
-                self.apply_event_to_active_buffer(&event);
+                self.log_and_apply_event(&event);
 
                 self.status_message = Some(format!("Added cursor below ({})", total_cursors));
             }
@@ -3721,16 +4066,27 @@ impl Editor {
 
     /// Save the active buffer
     pub fn save(&mut self) -> io::Result<()> {
-        let path = self
-            .active_state()
-            .buffer
-            .file_path()
-            .map(|p| p.to_path_buf());
-        self.active_state_mut().buffer.save()?;
+        self.apply_save_cleanup();
+        self.save_buffer(self.active_buffer())?;
         self.status_message = Some("Saved".to_string());
+        Ok(())
+    }
+
+    /// Write a buffer to its file and tell everyone who cares that it was saved
+    ///
+    /// This is the part of a save shared by manual saves and auto-save; the
+    /// on-save cleanup and the status message are left to the caller.
+    pub(crate) fn save_buffer(&mut self, buffer_id: BufferId) -> io::Result<()> {
+        let Some(state) = self.buffers.get_mut(&buffer_id) else {
+            return Ok(());
+        };
+        let path = state.buffer.file_path().map(|p| p.to_path_buf());
+        state.buffer.save()?;
 
         // Mark the event log position as saved (for undo modified tracking)
-        self.active_event_log_mut().mark_saved();
+        if let Some(event_log) = self.event_logs.get_mut(&buffer_id) {
+            event_log.mark_saved();
+        }
 
         // Update file modification time after save
         if let Some(ref p) = path {
@@ -3742,10 +4098,15 @@ impl Editor {
         }
 
         // Notify LSP of save
-        self.notify_lsp_save();
+        self.notify_lsp_save(buffer_id);
 
         // Delete recovery file (buffer is now saved)
-        let _ = self.delete_buffer_recovery(self.active_buffer());
+        let _ = self.delete_buffer_recovery(buffer_id);
+
+        // A sidecar auto-save is stale once the file itself is saved
+        if let Some(ref p) = path {
+            let _ = crate::model::auto_save::remove_sidecar(p);
+        }
 
         // Emit control event
         if let Some(ref p) = path {
@@ -3759,7 +4120,6 @@ impl Editor {
 
         // Fire AfterFileSave hook for plugins
         if let Some(ref p) = path {
-            let buffer_id = self.active_buffer();
             self.plugin_manager.run_hook(
                 "after_file_save",
                 crate::services::plugins::hooks::HookArgs::AfterFileSave {
@@ -3805,6 +4165,7 @@ impl Editor {
             self.config.editor.large_file_threshold_bytes as usize,
             &self.grammar_registry,
         )?;
+        new_state.word_def = self.word_def_for(&path);
 
         // Restore cursor positions (clamped to valid range for new file size)
         let new_file_size = new_state.buffer.len();
@@ -4223,13 +4584,22 @@ impl Editor {
                 continue;
             }
 
-            // If buffer has local modifications, show a warning (don't auto-revert)
-            if state.buffer.is_modified() {
-                self.status_message = Some(format!(
-                    "File {} changed on disk (buffer has unsaved changes)",
-                    path.display()
-                ));
-                continue;
+            match state.buffer.external_change_status() {
+                // Touched, or rewritten with the same content
+                ExternalChange::Unchanged => continue,
+                // If buffer has local modifications, ask what to do (don't auto-revert)
+                ExternalChange::ConflictBothChanged => {
+                    if self.is_prompting() {
+                        self.status_message = Some(format!(
+                            "File {} changed on disk (buffer has unsaved changes)",
+                            path.display()
+                        ));
+                    } else {
+                        self.prompt_external_change(buffer_id, &path);
+                    }
+                    continue;
+                }
+                ExternalChange::ChangedCleanBuffer => {}
             }
 
             // Auto-revert if enabled and buffer is not modified
@@ -4267,6 +4637,120 @@ impl Editor {
         }
     }
 
+    /// Ask whether to reload, keep or compare a buffer whose file changed on
+    /// disk while it has unsaved edits
+    fn prompt_external_change(&mut self, buffer_id: BufferId, path: &Path) {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        self.start_prompt(
+            format!("{} changed on disk. (r)eload, (k)eep mine, (d)iff? ", name),
+            PromptType::ConfirmExternalChange { buffer_id },
+        );
+    }
+
+    /// Resolve an external change to a buffer with unsaved edits as chosen in
+    /// the prompt: reload the file, keep the buffer's edits, or show a diff
+    /// and ask again
+    pub(super) fn resolve_external_change(&mut self, buffer_id: BufferId, choice: &str) {
+        let Some(path) = self
+            .buffers
+            .get(&buffer_id)
+            .and_then(|state| state.buffer.file_path())
+            .map(|p| p.to_path_buf())
+        else {
+            return;
+        };
+
+        match choice.trim().to_lowercase().chars().next() {
+            Some('r') => {
+                let current_active = self.active_buffer();
+                self.split_manager.set_active_buffer_id(buffer_id);
+                let result = self.revert_file();
+                self.split_manager.set_active_buffer_id(current_active);
+                match result {
+                    Ok(true) => {
+                        self.watch_file(&path);
+                        self.set_status_message(format!("Reloaded {}", path.display()));
+                    }
+                    Ok(false) => {}
+                    Err(e) => self.set_status_message(format!("Failed to reload: {}", e)),
+                }
+            }
+            Some('k') => {
+                if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                    state.buffer.acknowledge_external_change();
+                }
+                // Saving the kept edits shouldn't warn about this change again
+                self.watch_file(&path);
+                self.set_status_message(format!("Kept your edits to {}", path.display()));
+            }
+            Some('d') => match self.show_external_change_diff(buffer_id, &path) {
+                Ok(()) => self.prompt_external_change(buffer_id, &path),
+                Err(e) => self.set_status_message(format!("Failed to diff: {}", e)),
+            },
+            _ => self.set_status_message(format!(
+                "File {} changed on disk (buffer has unsaved changes)",
+                path.display()
+            )),
+        }
+    }
+
+    /// Open a read-only buffer showing how the buffer's text differs from its
+    /// file on disk, with lines only on disk marked `-` and lines only in the
+    /// buffer marked `+`
+    fn show_external_change_diff(&mut self, buffer_id: BufferId, path: &Path) -> io::Result<()> {
+        let on_disk = std::fs::read(path)?;
+        let in_buffer = self
+            .buffers
+            .get_mut(&buffer_id)
+            .map(|state| {
+                let len = state.buffer.len();
+                state.buffer.get_text_range_mut(0, len)
+            })
+            .transpose()
+            .map_err(io::Error::other)?
+            .unwrap_or_default();
+
+        let disk_lines: Vec<&[u8]> = on_disk.split(|&b| b == b'\n').collect();
+        let buffer_lines: Vec<&[u8]> = in_buffer.split(|&b| b == b'\n').collect();
+        let mut diff = String::new();
+        for hunk in crate::model::line_diff::diff_hunks(&on_disk, &in_buffer) {
+            use crate::model::line_diff::HunkKind;
+            let (marker, lines) = match hunk.kind {
+                HunkKind::Unchanged => (' ', &buffer_lines[hunk.current]),
+                HunkKind::Added => ('+', &buffer_lines[hunk.current]),
+                HunkKind::Removed => ('-', &disk_lines[hunk.saved]),
+            };
+            for line in lines {
+                diff.push(marker);
+                diff.push(' ');
+                diff.push_str(&String::from_utf8_lossy(line));
+                diff.push('\n');
+            }
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let diff_buffer = self.create_virtual_buffer(
+            format!("*Disk vs buffer: {}*", name),
+            "diff".to_string(),
+            true,
+        );
+        self.set_virtual_buffer_content(
+            diff_buffer,
+            vec![crate::primitives::text_property::TextPropertyEntry::text(
+                diff,
+            )],
+        )
+        .map_err(io::Error::other)?;
+        self.set_active_buffer(diff_buffer);
+        Ok(())
+    }
+
     /// Check if saving would overwrite changes made by another process
     /// Returns Some(current_mtime) if there's a conflict, None otherwise
     pub fn check_save_conflict(&self) -> Option<std::time::SystemTime> {
@@ -4408,6 +4892,7 @@ impl Editor {
                             let total = state.buffer.total_bytes();
                             state.buffer.delete(0..total);
                             state.buffer.insert(0, &text);
+                            state.forget_indentation();
                             // Mark as modified since it differs from disk
                             state.buffer.set_modified(true);
                             recovered_count += 1;
@@ -4418,6 +4903,7 @@ impl Editor {
                         self.new_buffer();
                         let state = self.active_state_mut();
                         state.buffer.insert(0, &text);
+                        state.forget_indentation();
                         state.buffer.set_modified(true);
                         recovered_count += 1;
                         tracing::info!("Recovered unsaved buffer");
@@ -4442,6 +4928,7 @@ impl Editor {
                             }
                             state.buffer.insert(chunk.offset, &text);
                         }
+                        state.forget_indentation();
 
                         // Mark as modified since it differs from disk
                         state.buffer.set_modified(true);
@@ -4618,6 +5105,98 @@ impl Editor {
         Ok(saved_count)
     }
 
+    /// Save modified buffers that have gone idle for `auto_save_delay_ms`
+    /// Returns the number of buffers saved, or an error naming every buffer
+    /// that failed to save
+    ///
+    /// Like `auto_save_dirty_buffers` this is called every frame. Each buffer
+    /// debounces its own edits (see `AutoSave`); buffers written in place go
+    /// through `save_buffer`, just like a manual save. A buffer whose file
+    /// also changed on disk isn't saved; the user is asked what to do instead.
+    pub fn auto_save_idle_buffers(&mut self) -> io::Result<usize> {
+        let delay_ms = self.config.editor.auto_save_delay_ms;
+        if delay_ms == 0 {
+            return Ok(0);
+        }
+        let delay = std::time::Duration::from_millis(delay_ms);
+        let target = if self.config.editor.auto_save_to_sidecar {
+            crate::model::auto_save::AutoSaveTarget::Sidecar
+        } else {
+            crate::model::auto_save::AutoSaveTarget::File
+        };
+        let now = self.time_source.now();
+
+        let due: Vec<BufferId> = self
+            .buffers
+            .iter_mut()
+            .filter_map(|(buffer_id, state)| {
+                let read_only = state.editing_disabled
+                    || self
+                        .buffer_metadata
+                        .get(buffer_id)
+                        .is_some_and(|m| m.read_only || m.binary);
+                state
+                    .auto_save
+                    .tick(now, delay, &state.buffer, read_only)
+                    .then_some(*buffer_id)
+            })
+            .collect();
+
+        // Keep going after a failure so one bad file doesn't hold up the rest
+        let mut saved = 0;
+        let mut errors = Vec::new();
+        for buffer_id in due {
+            let result = match target {
+                crate::model::auto_save::AutoSaveTarget::File => {
+                    // Never write over a file that also changed on disk; ask instead
+                    let conflicted = self.buffers.get(&buffer_id).and_then(|state| {
+                        let path = state.buffer.file_path()?.to_path_buf();
+                        (state.buffer.external_change_status()
+                            == ExternalChange::ConflictBothChanged)
+                            .then_some(path)
+                    });
+                    if let Some(path) = conflicted {
+                        if self.is_prompting() {
+                            self.set_status_message(format!(
+                                "Not auto-saving {}: it changed on disk",
+                                path.display()
+                            ));
+                        } else {
+                            self.prompt_external_change(buffer_id, &path);
+                        }
+                        continue;
+                    }
+                    self.save_buffer(buffer_id)
+                }
+                crate::model::auto_save::AutoSaveTarget::Sidecar => {
+                    self.buffers.get_mut(&buffer_id).map_or(Ok(()), |state| {
+                        crate::model::auto_save::write_sidecar(&mut state.buffer).map(|_| ())
+                    })
+                }
+            };
+            match result {
+                Ok(()) => saved += 1,
+                Err(e) => {
+                    let name = self
+                        .buffers
+                        .get(&buffer_id)
+                        .and_then(|state| state.buffer.file_path())
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|| format!("{:?}", buffer_id));
+                    errors.push(format!("{}: {}", name, e));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(saved)
+        } else {
+            let errors = errors.join("; ");
+            self.set_status_message(format!("Auto-save failed: {}", errors));
+            Err(io::Error::other(errors))
+        }
+    }
+
     /// Check if the active buffer is marked dirty for recovery auto-save
     /// Used for testing to verify that edits properly trigger recovery tracking
     pub fn is_active_buffer_recovery_dirty(&self) -> bool {
@@ -4731,7 +5310,10 @@ impl Editor {
         // Clear search highlights when starting a new search prompt
         // This ensures old highlights from previous searches don't persist
         match prompt_type {
-            PromptType::Search | PromptType::ReplaceSearch | PromptType::QueryReplaceSearch => {
+            PromptType::Search
+            | PromptType::IncrementalSearch
+            | PromptType::ReplaceSearch
+            | PromptType::QueryReplaceSearch => {
                 self.clear_search_highlights();
             }
             _ => {}
@@ -4778,6 +5360,7 @@ impl Editor {
             if matches!(
                 prompt.prompt_type,
                 PromptType::Search
+                    | PromptType::IncrementalSearch
                     | PromptType::ReplaceSearch
                     | PromptType::Replace { .. }
                     | PromptType::QueryReplaceSearch
@@ -4932,6 +5515,11 @@ impl Editor {
                     self.search_history.reset_navigation();
                     self.clear_search_highlights();
                 }
+                PromptType::IncrementalSearch => {
+                    self.search_history.reset_navigation();
+                    self.clear_search_highlights();
+                    self.cancel_incremental_search();
+                }
                 PromptType::Replace { .. } | PromptType::QueryReplace { .. } => {
                     self.replace_history.reset_navigation();
                 }
@@ -5027,7 +5615,10 @@ impl Editor {
 
             // Add to appropriate history based on prompt type
             match prompt.prompt_type {
-                PromptType::Search | PromptType::ReplaceSearch | PromptType::QueryReplaceSearch => {
+                PromptType::Search
+                | PromptType::IncrementalSearch
+                | PromptType::ReplaceSearch
+                | PromptType::QueryReplaceSearch => {
                     self.search_history.push(final_input.clone());
                     // Reset navigation state
                     self.search_history.reset_navigation();
@@ -5070,6 +5661,17 @@ impl Editor {
             .is_some()
     }
 
+    /// Whether a menu item's action can currently run
+    /// Consulted when rendering menus (to dim items) and when activating them
+    pub fn is_menu_action_enabled(&self, action: &str) -> bool {
+        match action {
+            "paste" => !self.clipboard.is_empty(),
+            "undo" => self.active_event_log().can_undo(),
+            "redo" => self.active_event_log().can_redo(),
+            _ => true,
+        }
+    }
+
     /// Get mutable reference to prompt (for input handling)
     pub fn prompt_mut(&mut self) -> Option<&mut Prompt> {
         self.prompt.as_mut()
@@ -5120,6 +5722,9 @@ impl Editor {
                 // Update incremental search highlights as user types
                 self.update_search_highlights(&input);
             }
+            PromptType::IncrementalSearch => {
+                self.update_incremental_search(&input);
+            }
             PromptType::OpenFile | PromptType::SwitchProject => {
                 // For OpenFile/SwitchProject, update the file browser filter (native implementation)
                 self.update_file_open_filter();
@@ -5859,6 +6464,15 @@ impl Editor {
             PluginCommand::SetStatus { message } => {
                 self.handle_set_status(message);
             }
+            PluginCommand::ScheduleTimer {
+                plugin,
+                after_ms,
+                token,
+            } => {
+                let now = self.time_source.now();
+                let after = std::time::Duration::from_millis(after_ms);
+                self.plugin_timers.schedule(now, after, plugin, token);
+            }
             PluginCommand::ApplyTheme { theme_name } => {
                 self.apply_theme(&theme_name);
             }
@@ -5918,6 +6532,20 @@ impl Editor {
             }
 
             // ==================== LSP Commands ====================
+            PluginCommand::GetBufferText {
+                buffer_id,
+                range,
+                request_id,
+            } => {
+                self.handle_get_buffer_text(buffer_id, range, request_id);
+            }
+            PluginCommand::GetBufferLine {
+                buffer_id,
+                line,
+                request_id,
+            } => {
+                self.handle_get_buffer_line(buffer_id, line, request_id);
+            }
             PluginCommand::SendLspRequest {
                 language,
                 method,
@@ -6141,6 +6769,7 @@ impl Editor {
                                 buffer_id,
                             );
                             view_state.viewport.line_wrap_enabled = self.config.editor.line_wrap;
+                            view_state.viewport.scroll_offset = self.config.editor.scroll_offset;
                             self.split_view_states.insert(new_split_id, view_state);
 
                             // Focus the new split (the diagnostics panel)
@@ -7762,8 +8391,8 @@ impl Editor {
             let cursor_pos = state.cursors.primary().position;
 
             // Find the word boundaries
-            let word_start = find_word_start(&state.buffer, cursor_pos);
-            let word_end = find_word_end(&state.buffer, cursor_pos);
+            let word_start = find_word_start(&state.buffer, cursor_pos, &state.word_def);
+            let word_end = find_word_end(&state.buffer, cursor_pos, &state.word_def);
 
             // Check if we're on a word
             if word_start >= word_end {
@@ -7965,6 +8594,26 @@ mod tests {
         assert!(!editor.should_quit());
     }
 
+    #[test]
+    fn test_cycle_theme_degrades_to_color_support() {
+        use crate::view::theme::ColorSupport;
+        use ratatui::style::Color;
+
+        let config = Config::default();
+        let (dir_context, _temp) = test_dir_context();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+        editor.set_color_support(ColorSupport::Ansi16);
+        editor.theme.commit();
+
+        let name = editor.cycle_theme();
+        editor.theme.commit();
+        let theme = editor.theme.current();
+        assert_eq!(theme.name, name);
+        let expected = crate::view::theme::Theme::from_name(name).degrade_to(ColorSupport::Ansi16);
+        assert_eq!(theme.editor_bg, expected.editor_bg);
+        assert!(!matches!(theme.editor_bg, Color::Rgb(..)));
+    }
+
     #[test]
     fn test_new_buffer() {
         let config = Config::default();
@@ -8724,7 +9373,7 @@ mod tests {
             "Should find only 1 exact match"
         );
         assert_eq!(
-            search_state.matches[0], 6,
+            search_state.matches[0].start, 6,
             "Should find 'hello' at position 6"
         );
     }
@@ -8765,8 +9414,142 @@ mod tests {
             2,
             "Should find only whole word 'test'"
         );
-        assert_eq!(search_state.matches[0], 0, "First match at position 0");
-        assert_eq!(search_state.matches[1], 27, "Second match at position 27");
+        assert_eq!(search_state.matches[0], 0..4, "First match at position 0");
+        assert_eq!(
+            search_state.matches[1],
+            27..31,
+            "Second match at position 27"
+        );
+    }
+
+    #[test]
+    fn test_search_matches_belong_to_searched_buffer() {
+        let config = Config::default();
+        let (dir_context, _temp) = test_dir_context();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+
+        let searched = editor.active_buffer();
+        let state = editor.active_state_mut();
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "foo bar foo".to_string(),
+            cursor_id: state.cursors.primary_id(),
+        });
+        editor.perform_search("foo");
+
+        // Edits to another buffer leave the stored matches alone
+        let other = editor.new_buffer();
+        let cursor_id = editor.active_state().cursors.primary_id();
+        editor.apply_event_to_active_buffer(&Event::Insert {
+            position: 0,
+            text: "xx foo".to_string(),
+            cursor_id,
+        });
+        let search_state = editor.search_state.as_ref().unwrap();
+        assert_eq!(search_state.buffer_id, searched);
+        assert_eq!(search_state.matches, vec![0..3, 8..11]);
+
+        // Navigating in the other buffer searches it instead
+        editor.active_state_mut().cursors.primary_mut().position = 0;
+        editor.find_next();
+        let search_state = editor.search_state.as_ref().unwrap();
+        assert_eq!(search_state.buffer_id, other);
+        assert_eq!(search_state.matches, vec![3..6]);
+        assert_eq!(editor.active_state().cursors.primary().position, 3);
+    }
+
+    #[test]
+    fn test_read_buffer_text_loads_only_the_range() {
+        let mut config = Config::default();
+        config.editor.large_file_threshold_bytes = 1024;
+        let (dir_context, temp) = test_dir_context();
+        let path = temp.path().join("big.txt");
+        let content: String = (0..2000).map(|i| format!("line {:04}\n", i)).collect();
+        std::fs::write(&path, &content).unwrap();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+        let buffer_id = editor.open_file(&path).unwrap();
+        assert!(editor.active_state().buffer.is_large_file());
+
+        // Nothing near the end has been read yet
+        let start = content.len() - 20;
+        assert!(editor
+            .active_state()
+            .buffer
+            .slice_bytes(start..content.len())
+            .is_empty());
+
+        assert_eq!(
+            editor
+                .read_buffer_text(buffer_id, start..content.len() + 100)
+                .unwrap(),
+            &content[start..]
+        );
+        assert_eq!(
+            editor.read_buffer_text(buffer_id, 10..20).unwrap(),
+            "line 0001\n"
+        );
+        assert!(editor.read_buffer_text(BufferId(999), 0..1).is_err());
+    }
+
+    #[test]
+    fn test_read_buffer_line() {
+        let (dir_context, temp) = test_dir_context();
+        let path = temp.path().join("lines.txt");
+        std::fs::write(&path, "first\r\nsecond\n\nlast\n").unwrap();
+        let mut editor = Editor::new(Config::default(), 80, 24, dir_context).unwrap();
+        let buffer_id = editor.open_file(&path).unwrap();
+
+        let lines: Vec<_> = (0..5)
+            .map(|n| editor.read_buffer_line(buffer_id, n).unwrap())
+            .collect();
+        assert_eq!(lines, vec!["first", "second", "", "last", ""]);
+        assert!(editor.read_buffer_line(buffer_id, 5).is_err());
+        assert!(editor.read_buffer_line(BufferId(999), 0).is_err());
+    }
+
+    #[test]
+    fn test_read_buffer_line_in_large_file() {
+        let mut config = Config::default();
+        config.editor.large_file_threshold_bytes = 1024;
+        let (dir_context, temp) = test_dir_context();
+        let path = temp.path().join("big.txt");
+        let content: String = (0..2000).map(|i| format!("line {:04}\n", i)).collect();
+        std::fs::write(&path, &content).unwrap();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+        let buffer_id = editor.open_file(&path).unwrap();
+        assert!(editor.active_state().buffer.is_large_file());
+
+        assert_eq!(editor.read_buffer_line(buffer_id, 0).unwrap(), "line 0000");
+        assert_eq!(
+            editor.read_buffer_line(buffer_id, 1999).unwrap(),
+            "line 1999"
+        );
+        assert_eq!(editor.read_buffer_line(buffer_id, 2000).unwrap(), "");
+        assert!(editor.read_buffer_line(buffer_id, 2001).is_err());
+    }
+
+    #[test]
+    fn test_plugin_edits_are_reported_as_buffer_changes() {
+        use crate::services::plugins::buffer_changes::BufferChange;
+
+        let (dir_context, _temp) = test_dir_context();
+        let mut editor = Editor::new(Config::default(), 80, 24, dir_context).unwrap();
+        let buffer_id = editor.active_buffer();
+
+        editor.handle_insert_text(buffer_id, 0, "hello world".to_string());
+        editor.handle_delete_range(buffer_id, 0..6);
+        assert_eq!(editor.active_state().buffer.to_string().unwrap(), "world");
+        assert_eq!(
+            editor.pending_buffer_changes.take(),
+            vec![(
+                buffer_id,
+                BufferChange {
+                    start: 0,
+                    old_end: 0,
+                    new_end: 5,
+                }
+            )]
+        );
     }
 
     #[test]
@@ -8820,6 +9603,44 @@ mod tests {
         assert!(!editor.bookmarks.contains_key(&'1'));
     }
 
+    #[test]
+    fn test_bookmark_follows_edits() {
+        let config = Config::default();
+        let (dir_context, _temp) = test_dir_context();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+
+        let state = editor.active_state_mut();
+        let cursor_id = state.cursors.primary_id();
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "Line 1\nLine 2\nLine 3".to_string(),
+            cursor_id,
+        });
+        state.cursors.primary_mut().position = 7;
+        editor.set_bookmark('1');
+
+        // Insert a line above the bookmark
+        let state = editor.active_state_mut();
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "Line 0\n".to_string(),
+            cursor_id,
+        });
+
+        editor.jump_to_bookmark('1');
+        assert_eq!(editor.active_state().cursors.primary().position, 14);
+
+        // Deleting the bookmarked line drops the bookmark
+        let state = editor.active_state_mut();
+        state.apply(&Event::Delete {
+            range: 14..21,
+            deleted_text: "Line 2\n".to_string(),
+            cursor_id,
+        });
+        editor.jump_to_bookmark('1');
+        assert!(!editor.bookmarks.contains_key(&'1'));
+    }
+
     #[test]
     fn test_action_enum_new_variants() {
         // Test that new actions can be parsed from strings
@@ -8850,6 +9671,14 @@ mod tests {
             Action::from_str("list_bookmarks", &args),
             Some(Action::ListBookmarks)
         );
+        assert_eq!(
+            Action::from_str("document_statistics", &args),
+            Some(Action::DocumentStatistics)
+        );
+        assert_eq!(
+            Action::from_str("title_case", &args),
+            Some(Action::TitleCase)
+        );
         assert_eq!(
             Action::from_str("toggle_search_case_sensitive", &args),
             Some(Action::ToggleSearchCaseSensitive)
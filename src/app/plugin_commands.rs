@@ -6,6 +6,7 @@ use crate::model::event::{BufferId, CursorId, Event, SplitId};
 use crate::services::plugins::api::{
     LayoutHints, MenuPosition, PluginResponse, ViewTransformPayload,
 };
+use crate::services::plugins::event_hooks::applied_edits;
 use crate::view::overlay::{OverlayHandle, OverlayNamespace};
 use crate::view::split::SplitViewState;
 use std::io;
@@ -547,7 +548,15 @@ impl Editor {
                 text,
                 cursor_id: CursorId(0),
             };
+            let Some(event) = self.plugin_manager.filter_edit(&event, buffer_id) else {
+                return;
+            };
             state.apply(&event);
+            self.adjust_position_history_for_event(buffer_id, &event);
+            for edit in applied_edits(&event, buffer_id) {
+                self.pending_buffer_changes.record(&edit);
+                self.plugin_manager.run_after_edit(&edit);
+            }
             if let Some(log) = self.event_logs.get_mut(&buffer_id) {
                 log.append(event);
             }
@@ -567,7 +576,15 @@ impl Editor {
                 deleted_text,
                 cursor_id: CursorId(0),
             };
+            let Some(event) = self.plugin_manager.filter_edit(&event, buffer_id) else {
+                return;
+            };
             state.apply(&event);
+            self.adjust_position_history_for_event(buffer_id, &event);
+            for edit in applied_edits(&event, buffer_id) {
+                self.pending_buffer_changes.record(&edit);
+                self.plugin_manager.run_after_edit(&edit);
+            }
             if let Some(log) = self.event_logs.get_mut(&buffer_id) {
                 log.append(event);
             }
@@ -577,15 +594,13 @@ impl Editor {
     /// Handle InsertAtCursor command
     pub(super) fn handle_insert_at_cursor(&mut self, text: String) {
         // Insert text at current cursor position in active buffer
-        let state = self.active_state_mut();
-        let cursor_pos = state.cursors.primary().position;
+        let cursor_pos = self.active_state().cursors.primary().position;
         let event = Event::Insert {
             position: cursor_pos,
             text,
             cursor_id: CursorId(0),
         };
-        state.apply(&event);
-        self.active_event_log_mut().append(event);
+        self.log_and_apply_event(&event);
     }
 
     /// Handle DeleteSelection command
@@ -619,12 +634,92 @@ impl Editor {
 
             // Apply events
             for event in events {
-                self.active_event_log_mut().append(event.clone());
-                self.apply_event_to_active_buffer(&event);
+                self.log_and_apply_event(&event);
             }
         }
     }
 
+    /// Handle GetBufferText command
+    pub(super) fn handle_get_buffer_text(
+        &mut self,
+        buffer_id: BufferId,
+        range: std::ops::Range<usize>,
+        request_id: u64,
+    ) {
+        let text = self.read_buffer_text(buffer_id, range);
+        self.send_plugin_response(PluginResponse::BufferText { request_id, text });
+    }
+
+    /// Handle GetBufferLine command
+    pub(super) fn handle_get_buffer_line(
+        &mut self,
+        buffer_id: BufferId,
+        line: usize,
+        request_id: u64,
+    ) {
+        let text = self.read_buffer_line(buffer_id, line);
+        self.send_plugin_response(PluginResponse::BufferText { request_id, text });
+    }
+
+    /// Text of line `line` (0-indexed) of a buffer, without its line ending
+    ///
+    /// Reads through the buffer's line iterator, so only the chunks holding
+    /// the line are loaded. Large files have no line index, so the lines
+    /// before it are walked to find where it starts.
+    pub(super) fn read_buffer_line(
+        &mut self,
+        buffer_id: BufferId,
+        line: usize,
+    ) -> Result<String, String> {
+        let estimated_line_length = self.config.editor.estimated_line_length;
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| format!("Buffer {:?} not found", buffer_id))?;
+        let past_end = || format!("Line {} is past the end of buffer {:?}", line, buffer_id);
+
+        let text = if state.buffer.is_large_file() {
+            let mut lines = state.buffer.line_iterator(0, estimated_line_length);
+            for _ in 0..line {
+                lines.next().ok_or_else(past_end)?;
+            }
+            lines.next().map(|(_, text)| text)
+        } else {
+            let start = state.buffer.line_start_offset(line).ok_or_else(past_end)?;
+            state
+                .buffer
+                .line_iterator(start, estimated_line_length)
+                .next()
+                .map(|(_, text)| text)
+        };
+        // The empty line after a final line feed has no text of its own
+        let text = text.unwrap_or_default();
+        let text = text.strip_suffix('\n').unwrap_or(&text);
+        Ok(text.strip_suffix('\r').unwrap_or(text).to_string())
+    }
+
+    /// Text of a byte range of a buffer, clamped to its length
+    ///
+    /// Reads through the buffer's lazy loading, so only the chunks covering
+    /// the range are loaded from disk.
+    pub(super) fn read_buffer_text(
+        &mut self,
+        buffer_id: BufferId,
+        range: std::ops::Range<usize>,
+    ) -> Result<String, String> {
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| format!("Buffer {:?} not found", buffer_id))?;
+        let end = range.end.min(state.buffer.len());
+        let start = range.start.min(end);
+        state
+            .buffer
+            .get_text_range_mut(start, end - start)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .map_err(|e| format!("Failed to read buffer {:?}: {}", buffer_id, e))
+    }
+
     // ==================== File/Navigation Commands ====================
 
     /// Helper to jump to a line/column position in the active buffer
@@ -836,9 +931,12 @@ impl Editor {
                 ratatui::style::Color::Rgb(color.0, color.1, color.2),
                 priority,
             );
-            state
-                .margins
-                .set_line_indicator(byte_offset, namespace, indicator);
+            state.margins.set_line_indicator(
+                &mut state.marker_list,
+                byte_offset,
+                namespace,
+                indicator,
+            );
         }
     }
 
@@ -847,7 +945,7 @@ impl Editor {
         if let Some(state) = self.buffers.get_mut(&buffer_id) {
             state
                 .margins
-                .clear_line_indicators_for_namespace(&namespace);
+                .clear_line_indicators_for_namespace(&mut state.marker_list, &namespace);
         }
     }
 
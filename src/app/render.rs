@@ -1,4 +1,9 @@
 use super::*;
+use crate::primitives::bracket_match::{CommentSyntax, LexicalSyntax};
+use crate::primitives::case_transform::CaseTransform;
+use crate::primitives::indent::IndentStyle;
+use crate::primitives::line_sort::SortOptions;
+use crate::primitives::snippet::Snippet;
 
 impl Editor {
     /// Render the editor to the terminal
@@ -6,6 +11,10 @@ impl Editor {
         let _span = tracing::trace_span!("render").entered();
         let size = frame.area();
 
+        // Theme changes made since the last frame take effect here, so a frame
+        // is always drawn with a single theme
+        self.theme.commit();
+
         // NOTE: Viewport sync with cursor is handled by split_rendering.rs which knows the
         // correct content area dimensions. Don't sync here with incorrect EditorState viewport size.
 
@@ -36,6 +45,7 @@ impl Editor {
             matches!(
                 p.prompt_type,
                 PromptType::Search
+                    | PromptType::IncrementalSearch
                     | PromptType::ReplaceSearch
                     | PromptType::Replace { .. }
                     | PromptType::QueryReplaceSearch
@@ -309,6 +319,9 @@ impl Editor {
         };
 
         let is_maximized = self.split_manager.is_maximized();
+        let inactive_theme = self
+            .theme
+            .dimmed(self.config.editor.inactive_split_dim, self.color_support);
 
         let (split_areas, tab_areas, close_split_areas, maximize_split_areas, view_line_mappings) =
             SplitRenderer::render_content(
@@ -326,6 +339,11 @@ impl Editor {
                 self.config.editor.line_wrap,
                 self.config.editor.estimated_line_length,
                 self.config.editor.highlight_context_bytes,
+                RenderOptions {
+                    render_whitespace: self.config.editor.render_whitespace,
+                    rulers: &self.config.editor.rulers,
+                    inactive_theme: &inactive_theme,
+                },
                 Some(&mut self.split_view_states),
                 hide_cursor,
                 hovered_tab,
@@ -415,7 +433,7 @@ impl Editor {
         let plugin_status_message = self.plugin_status_message.clone();
         let prompt = self.prompt.clone();
         let lsp_status = self.lsp_status.clone();
-        let theme = self.theme.clone();
+        let theme = self.theme.current();
         let keybindings_cloned = self.keybindings.clone(); // Clone the keybindings
         let chord_state_cloned = self.chord_state.clone(); // Clone the chord state
 
@@ -504,7 +522,7 @@ impl Editor {
 
         // Render popups from the active buffer state
         // Clone theme to avoid borrow checker issues with active_state_mut()
-        let theme_clone = self.theme.clone();
+        let theme_clone = self.theme.current();
         let hover_target = self.mouse_state.hover_target.clone();
 
         // Clear popup areas and recalculate
@@ -680,6 +698,7 @@ impl Editor {
             &self.keybindings,
             &self.theme,
             self.mouse_state.hover_target.as_ref(),
+            &|action| self.is_menu_action_enabled(action),
         );
 
         // Render software mouse cursor when GPM is active
@@ -941,15 +960,13 @@ impl Editor {
     /// Remove all overlays in a range
     pub fn remove_overlays_in_range(&mut self, range: Range<usize>) {
         let event = Event::RemoveOverlaysInRange { range };
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     /// Clear all overlays
     pub fn clear_overlays(&mut self) {
         let event = Event::ClearOverlays;
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     // === Popup Management (Event-Driven) ===
@@ -957,15 +974,13 @@ impl Editor {
     /// Show a popup window
     pub fn show_popup(&mut self, popup: crate::model::event::PopupData) {
         let event = Event::ShowPopup { popup };
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     /// Hide the topmost popup
     pub fn hide_popup(&mut self) {
         let event = Event::HidePopup;
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
 
         // Clear hover symbol highlight if present
         if let Some(handle) = self.hover_symbol_overlay.take() {
@@ -1017,8 +1032,7 @@ impl Editor {
     /// Clear all popups
     pub fn clear_popups(&mut self) {
         let event = Event::ClearPopups;
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     // === LSP Confirmation Popup ===
@@ -1301,29 +1315,25 @@ impl Editor {
     /// Navigate popup selection (next item)
     pub fn popup_select_next(&mut self) {
         let event = Event::PopupSelectNext;
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     /// Navigate popup selection (previous item)
     pub fn popup_select_prev(&mut self) {
         let event = Event::PopupSelectPrev;
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     /// Navigate popup (page down)
     pub fn popup_page_down(&mut self) {
         let event = Event::PopupPageDown;
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     /// Navigate popup (page up)
     pub fn popup_page_up(&mut self) {
         let event = Event::PopupPageUp;
-        self.active_event_log_mut().append(event.clone());
-        self.apply_event_to_active_buffer(&event);
+        self.log_and_apply_event(&event);
     }
 
     // === LSP Diagnostics Display ===
@@ -1472,16 +1482,13 @@ impl Editor {
         }
     }
 
-    /// Notify LSP of a file save
-    pub(super) fn notify_lsp_save(&mut self) {
+    /// Notify LSP that a buffer was saved
+    pub(super) fn notify_lsp_save(&mut self, buffer_id: BufferId) {
         // Check if LSP is enabled for this buffer
-        let metadata = match self.buffer_metadata.get(&self.active_buffer()) {
+        let metadata = match self.buffer_metadata.get(&buffer_id) {
             Some(m) => m,
             None => {
-                tracing::debug!(
-                    "notify_lsp_save: no metadata for buffer {:?}",
-                    self.active_buffer()
-                );
+                tracing::debug!("notify_lsp_save: no metadata for buffer {:?}", buffer_id);
                 return;
             }
         };
@@ -1518,7 +1525,11 @@ impl Editor {
         };
 
         // Get the full text to send with didSave
-        let full_text = match self.active_state().buffer.to_string() {
+        let full_text = match self
+            .buffers
+            .get(&buffer_id)
+            .and_then(|s| s.buffer.to_string())
+        {
             Some(t) => t,
             None => {
                 tracing::debug!("notify_lsp_save: buffer not fully loaded");
@@ -1553,10 +1564,22 @@ impl Editor {
     /// Convert an action into a list of events to apply to the active buffer
     /// Returns None for actions that don't generate events (like Quit)
     pub fn action_to_events(&mut self, action: Action) -> Option<Vec<Event>> {
-        let tab_size = self.config.editor.tab_size;
+        let indent_style = self.indent_style();
+        let tab_size = indent_style.width(self.config.editor.tab_size);
+        let backspace_indent = self
+            .config
+            .editor
+            .soft_tab_backspace
+            .then_some(indent_style);
         let auto_indent = self.config.editor.auto_indent;
         let estimated_line_length = self.config.editor.estimated_line_length;
 
+        // Tab types a literal tab when indenting with tabs, a soft tab otherwise
+        let action = match action {
+            Action::InsertTab if indent_style == IndentStyle::Tabs => Action::InsertChar('\t'),
+            action => action,
+        };
+
         // Get viewport height from SplitViewState (the authoritative source)
         let active_split = self.split_manager.active_split();
         let viewport_height = self
@@ -1569,6 +1592,7 @@ impl Editor {
             self.active_state_mut(),
             action,
             tab_size,
+            backspace_indent,
             auto_indent,
             estimated_line_length,
             viewport_height,
@@ -1587,6 +1611,20 @@ impl Editor {
         self.search_state = None;
     }
 
+    /// Regex source for `query` under the current whole-word and regex options
+    fn search_regex_pattern(&self, query: &str) -> String {
+        let pattern = if self.search_use_regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        if self.search_whole_word {
+            format!(r"\b{}\b", pattern)
+        } else {
+            pattern
+        }
+    }
+
     /// Update search highlights in visible viewport only (for incremental search)
     /// This is called as the user types in the search prompt for real-time feedback
     pub(super) fn update_search_highlights(&mut self, query: &str) {
@@ -1600,25 +1638,8 @@ impl Editor {
         let search_bg = self.theme.search_match_bg;
         let search_fg = self.theme.search_match_fg;
         let case_sensitive = self.search_case_sensitive;
-        let whole_word = self.search_whole_word;
-        let use_regex = self.search_use_regex;
         let ns = self.search_namespace.clone();
-
-        // Build regex pattern if regex mode is enabled, or escape for literal search
-        let regex_pattern = if use_regex {
-            if whole_word {
-                format!(r"\b{}\b", query)
-            } else {
-                query.to_string()
-            }
-        } else {
-            let escaped = regex::escape(query);
-            if whole_word {
-                format!(r"\b{}\b", escaped)
-            } else {
-                escaped
-            }
-        };
+        let regex_pattern = self.search_regex_pattern(query);
 
         // Build regex with case sensitivity
         let regex = regex::RegexBuilder::new(&regex_pattern)
@@ -1718,8 +1739,6 @@ impl Editor {
 
         // Get search settings
         let case_sensitive = self.search_case_sensitive;
-        let whole_word = self.search_whole_word;
-        let use_regex = self.search_use_regex;
 
         // Determine search boundaries
         let (search_start, search_end) = if let Some(ref range) = search_range {
@@ -1728,21 +1747,7 @@ impl Editor {
             (0, buffer_content.len())
         };
 
-        // Build regex pattern
-        let regex_pattern = if use_regex {
-            if whole_word {
-                format!(r"\b{}\b", query)
-            } else {
-                query.to_string()
-            }
-        } else {
-            let escaped = regex::escape(query);
-            if whole_word {
-                format!(r"\b{}\b", escaped)
-            } else {
-                escaped
-            }
-        };
+        let regex_pattern = self.search_regex_pattern(query);
 
         // Build regex with case sensitivity
         let regex = match regex::RegexBuilder::new(&regex_pattern)
@@ -1759,9 +1764,9 @@ impl Editor {
 
         // Find all matches within the search range
         let search_slice = &buffer_content[search_start..search_end];
-        let matches: Vec<usize> = regex
+        let matches: Vec<Range<usize>> = regex
             .find_iter(search_slice)
-            .map(|m| search_start + m.start())
+            .map(|m| search_start + m.start()..search_start + m.end())
             .collect();
 
         if matches.is_empty() {
@@ -1782,11 +1787,11 @@ impl Editor {
         };
         let current_match_index = matches
             .iter()
-            .position(|&pos| pos >= cursor_pos)
+            .position(|m| m.start >= cursor_pos)
             .unwrap_or(0);
 
         // Move cursor to the first match
-        let match_pos = matches[current_match_index];
+        let match_pos = matches[current_match_index].start;
         {
             let active_split = self.split_manager.active_split();
             let active_buffer = self.active_buffer();
@@ -1806,6 +1811,7 @@ impl Editor {
 
         // Update search state
         self.search_state = Some(SearchState {
+            buffer_id: self.active_buffer(),
             query: query.to_string(),
             matches,
             current_match_index: Some(current_match_index),
@@ -1831,87 +1837,218 @@ impl Editor {
         self.set_status_message(msg);
     }
 
-    /// Find the next match
+    /// Find the next match after the cursor
     pub(super) fn find_next(&mut self) {
-        if let Some(ref mut search_state) = self.search_state {
-            if search_state.matches.is_empty() {
+        self.navigate_to_match(true);
+    }
+
+    /// Find the previous match before the cursor
+    pub(super) fn find_previous(&mut self) {
+        self.navigate_to_match(false);
+    }
+
+    /// Move the cursor to the next (or previous) match of the active search
+    fn navigate_to_match(&mut self, forward: bool) {
+        // Matches of a search in another buffer mean nothing here, so run
+        // the same query again on this buffer first
+        let active_buffer = self.active_buffer();
+        if let Some(query) = self
+            .search_state
+            .as_ref()
+            .filter(|s| s.buffer_id != active_buffer)
+            .map(|s| s.query.clone())
+        {
+            let cursor = *self.active_state().cursors.primary();
+            self.perform_search(&query);
+            if self.search_state.is_none() {
                 return;
             }
+            *self.active_state_mut().cursors.primary_mut() = cursor;
+        }
 
-            let current_index = search_state.current_match_index.unwrap_or(0);
-            let next_index = if current_index + 1 < search_state.matches.len() {
-                current_index + 1
-            } else if search_state.wrap_search {
-                0 // Wrap to beginning
-            } else {
-                self.set_status_message("No more matches.".to_string());
-                return;
-            };
+        let cursor_pos = self.active_state().cursors.primary().position;
+        let Some(ref mut search_state) = self.search_state else {
+            self.set_status_message("No active search. Press Ctrl+F to search.".to_string());
+            return;
+        };
+        if search_state.match_count() == 0 {
+            return;
+        }
 
-            search_state.current_match_index = Some(next_index);
-            let match_pos = search_state.matches[next_index];
-            let matches_len = search_state.matches.len();
+        let found = if forward {
+            search_state.next_match(cursor_pos)
+        } else {
+            search_state.prev_match(cursor_pos)
+        };
+        let Some(found) = found else {
+            self.set_status_message("No more matches.".to_string());
+            return;
+        };
+        let index = search_state.current_index().unwrap_or(0);
+        let matches_len = search_state.match_count();
 
-            {
-                let active_split = self.split_manager.active_split();
-                let active_buffer = self.active_buffer();
-                let state = self.active_state_mut();
-                state.cursors.primary_mut().position = match_pos;
-                state.cursors.primary_mut().anchor = None;
-                // Ensure cursor is visible - get viewport from SplitViewState
-                if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
-                    let state = self.buffers.get_mut(&active_buffer).unwrap();
-                    view_state
-                        .viewport
-                        .ensure_visible(&mut state.buffer, state.cursors.primary());
-                }
+        {
+            let active_split = self.split_manager.active_split();
+            let active_buffer = self.active_buffer();
+            let state = self.active_state_mut();
+            state.cursors.primary_mut().position = found.start;
+            state.cursors.primary_mut().anchor = None;
+            // Ensure cursor is visible - get viewport from SplitViewState
+            if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
+                let state = self.buffers.get_mut(&active_buffer).unwrap();
+                view_state
+                    .viewport
+                    .ensure_visible(&mut state.buffer, state.cursors.primary());
             }
+        }
 
-            self.set_status_message(format!("Match {} of {}", next_index + 1, matches_len));
-        } else {
-            self.set_status_message("No active search. Press Ctrl+F to search.".to_string());
+        self.set_status_message(format!("Match {} of {}", index + 1, matches_len));
+    }
+
+    /// Start an incremental search from the cursor
+    pub(super) fn start_incremental_search(&mut self) {
+        let cursor = *self.active_state().cursors.primary();
+        let top_byte = self
+            .split_view_states
+            .get(&self.split_manager.active_split())
+            .map_or(0, |vs| vs.viewport.top_byte);
+        self.start_prompt("I-search: ".to_string(), PromptType::IncrementalSearch);
+        self.incremental_search = Some(IncrementalSearch::new(
+            cursor.position,
+            cursor.anchor,
+            top_byte,
+        ));
+    }
+
+    /// Move the cursor to the match for the query typed so far
+    ///
+    /// Called on every change to the incremental search prompt.
+    pub(super) fn update_incremental_search(&mut self, query: &str) {
+        self.update_search_highlights(query);
+        let regex = self.incremental_search_regex(query);
+        let buffer_id = self.active_buffer();
+        let (Some(search), Some(state)) = (
+            self.incremental_search.as_mut(),
+            self.buffers.get(&buffer_id),
+        ) else {
+            return;
+        };
+        search.update(&state.buffer, regex.as_ref());
+        self.show_incremental_match(query);
+    }
+
+    /// Jump to the next match of the incremental search query
+    pub(super) fn incremental_search_next(&mut self) {
+        let Some(query) = self.prompt.as_ref().map(|p| p.input.clone()) else {
+            return;
+        };
+        let regex = self.incremental_search_regex(&query);
+        let buffer_id = self.active_buffer();
+        let (Some(search), Some(state)) = (
+            self.incremental_search.as_mut(),
+            self.buffers.get(&buffer_id),
+        ) else {
+            return;
+        };
+        search.advance(&state.buffer, regex.as_ref());
+        self.show_incremental_match(&query);
+    }
+
+    /// Keep the cursor at the current match and make the query the active search
+    pub(super) fn accept_incremental_search(&mut self, query: &str) {
+        if self.incremental_search.take().is_some() && !query.is_empty() {
+            self.perform_search(query);
         }
     }
 
-    /// Find the previous match
-    pub(super) fn find_previous(&mut self) {
-        if let Some(ref mut search_state) = self.search_state {
-            if search_state.matches.is_empty() {
-                return;
-            }
+    /// Put the cursor and view back where the incremental search started
+    pub(super) fn cancel_incremental_search(&mut self) {
+        let Some(search) = self.incremental_search.take() else {
+            return;
+        };
+        let state = self.active_state_mut();
+        let cursor = state.cursors.primary_mut();
+        cursor.position = search.origin;
+        cursor.anchor = search.origin_anchor;
+        let active_split = self.split_manager.active_split();
+        if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
+            view_state.viewport.top_byte = search.origin_top_byte;
+        }
+    }
 
-            let current_index = search_state.current_match_index.unwrap_or(0);
-            let prev_index = if current_index > 0 {
-                current_index - 1
-            } else if search_state.wrap_search {
-                search_state.matches.len() - 1 // Wrap to end
-            } else {
-                self.set_status_message("No more matches.".to_string());
-                return;
-            };
+    /// Compiled incremental search query, or None if it is empty or invalid
+    fn incremental_search_regex(&self, query: &str) -> Option<regex::bytes::Regex> {
+        if query.is_empty() {
+            return None;
+        }
+        regex::bytes::RegexBuilder::new(&self.search_regex_pattern(query))
+            .case_insensitive(!self.search_case_sensitive)
+            .build()
+            .ok()
+    }
+
+    /// Move the cursor to the current incremental search match, or back to
+    /// where the search started if the query doesn't match
+    fn show_incremental_match(&mut self, query: &str) {
+        let Some(search) = &self.incremental_search else {
+            return;
+        };
+        let position = search
+            .current
+            .as_ref()
+            .map_or(search.origin, |found| found.start);
+        let anchor = if search.current.is_some() {
+            None
+        } else {
+            search.origin_anchor
+        };
+        let failing = search.current.is_none() && !query.is_empty();
 
-            search_state.current_match_index = Some(prev_index);
-            let match_pos = search_state.matches[prev_index];
-            let matches_len = search_state.matches.len();
+        let active_split = self.split_manager.active_split();
+        let active_buffer = self.active_buffer();
+        let state = self.buffers.get_mut(&active_buffer).unwrap();
+        state.cursors.primary_mut().position = position;
+        state.cursors.primary_mut().anchor = anchor;
+        if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
+            view_state
+                .viewport
+                .ensure_visible(&mut state.buffer, state.cursors.primary());
+        }
 
-            {
-                let active_split = self.split_manager.active_split();
-                let active_buffer = self.active_buffer();
-                let state = self.active_state_mut();
-                state.cursors.primary_mut().position = match_pos;
-                state.cursors.primary_mut().anchor = None;
-                // Ensure cursor is visible - get viewport from SplitViewState
-                if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
-                    let state = self.buffers.get_mut(&active_buffer).unwrap();
-                    view_state
-                        .viewport
-                        .ensure_visible(&mut state.buffer, state.cursors.primary());
-                }
+        if failing {
+            self.set_status_message(format!("Failing I-search: {}", query));
+        }
+    }
+
+    /// Keep the active search in sync with an edit to `buffer_id`
+    ///
+    /// Cached matches are shifted so next/previous keep working. Edits to
+    /// other buffers leave them alone. Without an active search, leftover
+    /// incremental-search highlights are cleared.
+    pub(super) fn update_search_for_edit(&mut self, buffer_id: BufferId, event: &Event) {
+        match self.search_state.as_mut() {
+            Some(search_state) if search_state.buffer_id == buffer_id => {
+                Self::adjust_search_state(search_state, event)
             }
+            Some(_) => {}
+            None => self.clear_search_highlights(),
+        }
+    }
 
-            self.set_status_message(format!("Match {} of {}", prev_index + 1, matches_len));
-        } else {
-            self.set_status_message("No active search. Press Ctrl+F to search.".to_string());
+    fn adjust_search_state(search_state: &mut SearchState, event: &Event) {
+        match event {
+            Event::Insert { position, text, .. } => {
+                search_state.adjust_for_insert(*position, text.len());
+            }
+            Event::Delete { range, .. } => {
+                search_state.adjust_for_delete(range.start, range.len());
+            }
+            Event::Batch { events, .. } => {
+                for event in events {
+                    Self::adjust_search_state(search_state, event);
+                }
+            }
+            _ => {}
         }
     }
 
@@ -2004,8 +2141,7 @@ impl Editor {
         };
 
         // Apply through event log for proper undo support
-        self.active_event_log_mut().append(batch.clone());
-        self.apply_event_to_active_buffer(&batch);
+        self.log_and_apply_event(&batch);
 
         // Clear search state since positions are now invalid
         self.search_state = None;
@@ -2206,8 +2342,7 @@ impl Editor {
                         ),
                     };
 
-                    self.active_event_log_mut().append(batch.clone());
-                    self.apply_event_to_active_buffer(&batch);
+                    self.log_and_apply_event(&batch);
 
                     ir_state.replacements_made += remaining_count;
                 }
@@ -2329,8 +2464,7 @@ impl Editor {
         };
 
         // Apply the batch through the event log
-        self.active_event_log_mut().append(batch.clone());
-        self.apply_event_to_active_buffer(&batch);
+        self.log_and_apply_event(&batch);
 
         Ok(())
     }
@@ -2387,456 +2521,297 @@ impl Editor {
 
     /// Smart home: toggle between line start and first non-whitespace character
     pub(super) fn smart_home(&mut self) {
-        let estimated_line_length = self.config.editor.estimated_line_length;
-        let state = self.active_state_mut();
-        let cursor = state.cursors.primary().clone();
-        let cursor_id = state.cursors.primary_id();
+        let mut events = self.active_state().smart_home();
+        let event = match events.len() {
+            0 => return,
+            1 => events.remove(0),
+            _ => Event::Batch {
+                events,
+                description: "Smart home".to_string(),
+            },
+        };
 
-        // Get line information
-        let mut iter = state
-            .buffer
-            .line_iterator(cursor.position, estimated_line_length);
-        if let Some((line_start, line_content)) = iter.next() {
-            // Find first non-whitespace character
-            let first_non_ws = line_content
-                .chars()
-                .take_while(|c| *c != '\n')
-                .position(|c| !c.is_whitespace())
-                .map(|offset| line_start + offset)
-                .unwrap_or(line_start);
-
-            // Toggle: if at first non-ws, go to line start; otherwise go to first non-ws
-            let new_pos = if cursor.position == first_non_ws {
-                line_start
-            } else {
-                first_non_ws
-            };
+        self.log_and_apply_event(&event);
+    }
 
-            let event = Event::MoveCursor {
-                cursor_id,
-                old_position: cursor.position,
-                new_position: new_pos,
-                old_anchor: cursor.anchor,
-                new_anchor: None,
-                old_sticky_column: cursor.sticky_column,
-                new_sticky_column: 0,
-            };
+    /// Lines covered by the primary cursor's selection, or its line if there is none
+    ///
+    /// A selection ending at the very start of a line doesn't include that line.
+    fn selected_lines(&self) -> std::ops::Range<usize> {
+        let state = self.active_state();
+        let cursor = state.cursors.primary();
+        let range = cursor
+            .selection_range()
+            .unwrap_or(cursor.position..cursor.position);
+        let first = state.buffer.get_line_number(range.start);
+        let mut last = state.buffer.get_line_number(range.end);
+        if last > first && state.buffer.line_start_offset(last) == Some(range.end) {
+            last -= 1;
+        }
+        first..last + 1
+    }
+
+    /// Whether the primary cursor's selection crosses a line break, which makes
+    /// Tab indent the lines instead of replacing the selection
+    pub(super) fn selection_spans_lines(&self) -> bool {
+        let state = self.active_state();
+        state
+            .cursors
+            .primary()
+            .selection_range()
+            .is_some_and(|range| {
+                state.buffer.get_line_number(range.start) != state.buffer.get_line_number(range.end)
+            })
+    }
 
-            self.active_event_log_mut().append(event.clone());
-            self.apply_event_to_active_buffer(&event);
+    /// Indentation style of the active buffer: detected from its lines when
+    /// `detect_indentation` is on, otherwise the configured one
+    pub(super) fn indent_style(&self) -> IndentStyle {
+        let configured = IndentStyle::from_config(&self.config.editor);
+        if self.config.editor.detect_indentation {
+            self.active_state().detect_indentation(configured)
+        } else {
+            configured
         }
     }
 
     /// Indent the selection or current line
     pub(super) fn indent_selection(&mut self) {
-        let tab_size = self.config.editor.tab_size;
-        let estimated_line_length = self.config.editor.estimated_line_length;
-        let indent_str = " ".repeat(tab_size);
+        let unit = self.indent_style().unit();
+        let lines = self.selected_lines();
+        let line_count = lines.len();
 
-        let state = self.active_state_mut();
-        // Collect lines to indent
-        let cursor = state.cursors.primary().clone();
-        let cursor_id = state.cursors.primary_id();
-
-        let (start_pos, end_pos) = if let Some(range) = cursor.selection_range() {
-            (range.start, range.end)
-        } else {
-            // No selection - indent current line
-            let iter = state
-                .buffer
-                .line_iterator(cursor.position, estimated_line_length);
-            let line_start = iter.current_position();
-            (line_start, cursor.position)
-        };
-
-        // Find all line starts in the range
-        let buffer_len = state.buffer.len();
-        let mut line_starts = Vec::new();
-        let mut iter = state.buffer.line_iterator(start_pos, estimated_line_length);
-        let mut current_pos = iter.current_position();
-        line_starts.push(current_pos);
-
-        // Collect all line starts by iterating through lines
-        loop {
-            if let Some((_, content)) = iter.next() {
-                current_pos += content.len();
-                if current_pos > end_pos || current_pos > buffer_len {
-                    break;
-                }
-                let next_iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-                let next_start = next_iter.current_position();
-                if next_start != *line_starts.last().unwrap() {
-                    line_starts.push(next_start);
-                }
-                iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-            } else {
-                break;
-            }
-        }
-
-        if line_starts.is_empty() {
+        let Some(event) = self.active_state().indent(lines, &unit) else {
             return;
-        }
-
-        // Create insert events for each line start (in reverse order)
-        let mut events = Vec::new();
-        for &line_start in line_starts.iter().rev() {
-            events.push(Event::Insert {
-                position: line_start,
-                text: indent_str.clone(),
-                cursor_id,
-            });
-        }
-
-        let batch = Event::Batch {
-            events,
-            description: "Indent selection".to_string(),
         };
-
-        self.active_event_log_mut().append(batch.clone());
-        self.apply_event_to_active_buffer(&batch);
-        self.set_status_message(format!("Indented {} line(s)", line_starts.len()));
+        self.log_and_apply_event(&event);
+        self.set_status_message(format!("Indented {} line(s)", line_count));
     }
 
     /// Dedent the selection or current line
     pub(super) fn dedent_selection(&mut self) {
-        let tab_size = self.config.editor.tab_size;
-        let estimated_line_length = self.config.editor.estimated_line_length;
+        let tab_size = self.indent_style().width(self.config.editor.tab_size);
+        let lines = self.selected_lines();
 
-        let state = self.active_state_mut();
-        // Collect lines to dedent
-        let cursor = state.cursors.primary().clone();
-        let cursor_id = state.cursors.primary_id();
+        let Some(event) = self.active_state().dedent(lines, tab_size) else {
+            self.set_status_message("No indentation to remove".to_string());
+            return;
+        };
+        let lines_dedented = match &event {
+            Event::Batch { events, .. } => events.len(),
+            _ => 1,
+        };
+        self.log_and_apply_event(&event);
+        self.set_status_message(format!("Dedented {} line(s)", lines_dedented));
+    }
 
-        let (start_pos, end_pos) = if let Some(range) = cursor.selection_range() {
-            (range.start, range.end)
-        } else {
-            // No selection - dedent current line
-            let iter = state
-                .buffer
-                .line_iterator(cursor.position, estimated_line_length);
-            let line_start = iter.current_position();
-            (line_start, cursor.position)
-        };
-
-        // Find all line starts in the range (same logic as indent)
-        let buffer_len = state.buffer.len();
-        let mut line_starts = Vec::new();
-        let mut iter = state.buffer.line_iterator(start_pos, estimated_line_length);
-        let mut current_pos = iter.current_position();
-        line_starts.push(current_pos);
-
-        loop {
-            if let Some((_, content)) = iter.next() {
-                current_pos += content.len();
-                if current_pos > end_pos || current_pos > buffer_len {
-                    break;
-                }
-                let next_iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-                let next_start = next_iter.current_position();
-                if next_start != *line_starts.last().unwrap() {
-                    line_starts.push(next_start);
-                }
-                iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-            } else {
-                break;
-            }
-        }
+    /// The configured snippet whose trigger ends at the cursor, with the trigger's range
+    ///
+    /// Only a single cursor without a selection can expand a snippet.
+    pub(super) fn snippet_at_cursor(&self) -> Option<(Snippet, std::ops::Range<usize>)> {
+        let state = self.active_state();
+        if state.cursors.count() > 1 || state.cursors.primary().selection_range().is_some() {
+            return None;
+        }
+        let end = state.cursors.primary().position;
+        let start =
+            crate::primitives::word_navigation::find_completion_word_start(&state.buffer, end);
+        if start == end {
+            return None;
+        }
+        let trigger = String::from_utf8_lossy(&state.buffer.slice_bytes(start..end)).into_owned();
+        let body = self.config.snippets.get(&trigger)?;
+        Some((Snippet::parse(body), start..end))
+    }
 
-        if line_starts.is_empty() {
+    /// Expand the snippet whose trigger ends at the cursor and start a session for it
+    pub(super) fn expand_snippet_at_cursor(&mut self) {
+        let Some((snippet, trigger)) = self.snippet_at_cursor() else {
             return;
-        }
-
-        // Create delete events for leading spaces (in reverse order)
-        let mut events = Vec::new();
-        let mut lines_dedented = 0;
-
-        for &line_start in line_starts.iter().rev() {
-            // Check how many leading spaces the line has
-            let line_bytes = state
-                .buffer
-                .slice_bytes(line_start..buffer_len.min(line_start + tab_size + 1));
-            let spaces_to_remove = line_bytes
-                .iter()
-                .take(tab_size)
-                .take_while(|&&b| b == b' ')
-                .count();
+        };
+        let state = self.active_state_mut();
+        state.snippet = None;
+        let (event, engine) = state.expand_snippet(&snippet, trigger);
 
-            if spaces_to_remove > 0 {
-                let deleted_text = " ".repeat(spaces_to_remove);
-                events.push(Event::Delete {
-                    range: line_start..line_start + spaces_to_remove,
-                    deleted_text,
-                    cursor_id,
-                });
-                lines_dedented += 1;
-            }
+        self.log_and_apply_event(&event);
+        if !engine.is_at_final() {
+            self.active_state_mut().snippet = Some(engine);
         }
+    }
 
-        if events.is_empty() {
-            self.set_status_message("No indentation to remove".to_string());
-            return;
+    /// Select the next (or previous) stop of the active snippet
+    pub(super) fn snippet_jump(&mut self, forward: bool) {
+        if let Some(event) = self.active_state_mut().snippet_jump(forward) {
+            self.log_and_apply_event(&event);
         }
+    }
 
-        let batch = Event::Batch {
-            events,
-            description: "Dedent selection".to_string(),
+    /// Line comment prefix for the active buffer, based on its file extension
+    fn comment_prefix(&self) -> &'static str {
+        let Some(path) = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|metadata| metadata.file_path())
+        else {
+            return "// ";
         };
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("rs") | Some("c") | Some("cpp") | Some("h") | Some("hpp") | Some("js")
+            | Some("ts") | Some("jsx") | Some("tsx") | Some("java") | Some("go")
+            | Some("swift") | Some("kt") | Some("scala") => "// ",
+            Some("py") | Some("rb") | Some("sh") | Some("bash") | Some("zsh") | Some("pl")
+            | Some("r") | Some("yml") | Some("yaml") | Some("toml") => "# ",
+            Some("lua") | Some("sql") => "-- ",
+            Some("html") | Some("xml") => "<!-- ",
+            Some("css") | Some("scss") | Some("sass") => "/* ",
+            Some("vim") => "\" ",
+            Some("lisp") | Some("el") | Some("clj") => ";; ",
+            _ => "// ",
+        }
+    }
 
-        self.active_event_log_mut().append(batch.clone());
-        self.apply_event_to_active_buffer(&batch);
-        self.set_status_message(format!("Dedented {} line(s)", lines_dedented));
+    /// How strings and comments look in the active buffer, for bracket matching
+    fn code_syntax(&self) -> LexicalSyntax<'static> {
+        let comments = match self.comment_prefix().trim_end() {
+            "//" | "/*" => CommentSyntax::C,
+            "<!--" => CommentSyntax {
+                line: None,
+                block: Some(("<!--", "-->")),
+            },
+            line => CommentSyntax {
+                line: Some(line),
+                block: None,
+            },
+        };
+        let extension = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|metadata| metadata.file_path())
+            .and_then(|path| path.extension())
+            .and_then(|e| e.to_str());
+        let single_quote_strings = matches!(
+            extension,
+            Some(
+                "py" | "rb"
+                    | "sh"
+                    | "bash"
+                    | "zsh"
+                    | "pl"
+                    | "r"
+                    | "lua"
+                    | "sql"
+                    | "js"
+                    | "ts"
+                    | "jsx"
+                    | "tsx"
+                    | "yml"
+                    | "yaml"
+                    | "toml"
+                    | "html"
+                    | "xml"
+                    | "css"
+                    | "scss"
+                    | "sass"
+                    | "vim"
+            )
+        );
+        LexicalSyntax {
+            comments,
+            single_quote_strings,
+        }
     }
 
     /// Toggle comment on the current line or selection
     pub(super) fn toggle_comment(&mut self) {
-        // Determine comment prefix based on file extension
-        let comment_prefix = if let Some(metadata) = self.buffer_metadata.get(&self.active_buffer())
-        {
-            if let Some(path) = metadata.file_path() {
-                match path.extension().and_then(|e| e.to_str()) {
-                    Some("rs") | Some("c") | Some("cpp") | Some("h") | Some("hpp") | Some("js")
-                    | Some("ts") | Some("jsx") | Some("tsx") | Some("java") | Some("go")
-                    | Some("swift") | Some("kt") | Some("scala") => "// ",
-                    Some("py") | Some("rb") | Some("sh") | Some("bash") | Some("zsh")
-                    | Some("pl") | Some("r") | Some("yml") | Some("yaml") | Some("toml") => "# ",
-                    Some("lua") | Some("sql") => "-- ",
-                    Some("html") | Some("xml") => "<!-- ",
-                    Some("css") | Some("scss") | Some("sass") => "/* ",
-                    Some("vim") => "\" ",
-                    Some("lisp") | Some("el") | Some("clj") => ";; ",
-                    _ => "// ",
-                }
-            } else {
-                "// "
-            }
-        } else {
-            "// "
-        };
-
-        let estimated_line_length = self.config.editor.estimated_line_length;
+        let comment_prefix = self.comment_prefix();
 
-        let state = self.active_state_mut();
-        let cursor = state.cursors.primary().clone();
-        let cursor_id = state.cursors.primary_id();
+        let state = self.active_state();
+        let cursor = state.cursors.primary();
+        let (start_pos, end_pos) = match cursor.selection_range() {
+            Some(range) => (range.start, range.end),
+            None => (cursor.position, cursor.position),
+        };
+        let first_line = state.buffer.get_line_number(start_pos);
+        let last_line = state.buffer.get_line_number(end_pos);
 
-        let (start_pos, end_pos) = if let Some(range) = cursor.selection_range() {
-            (range.start, range.end)
-        } else {
-            let iter = state
-                .buffer
-                .line_iterator(cursor.position, estimated_line_length);
-            let line_start = iter.current_position();
-            (line_start, cursor.position)
-        };
-
-        // Find all line starts in the range
-        let buffer_len = state.buffer.len();
-        let mut line_starts = Vec::new();
-        let mut iter = state.buffer.line_iterator(start_pos, estimated_line_length);
-        let mut current_pos = iter.current_position();
-        line_starts.push(current_pos);
-
-        loop {
-            if let Some((_, content)) = iter.next() {
-                current_pos += content.len();
-                if current_pos > end_pos || current_pos > buffer_len {
-                    break;
-                }
-                let next_iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-                let next_start = next_iter.current_position();
-                if next_start != *line_starts.last().unwrap() {
-                    line_starts.push(next_start);
-                }
-                iter = state
-                    .buffer
-                    .line_iterator(current_pos, estimated_line_length);
-            } else {
-                break;
-            }
-        }
+        let Some(batch) = state.toggle_comment(first_line..last_line + 1, comment_prefix) else {
+            return;
+        };
+        let Event::Batch { events, .. } = &batch else {
+            return;
+        };
+        let action_desc = match events.first() {
+            Some(Event::Insert { .. }) => "Commented",
+            _ => "Uncommented",
+        };
+        let status = format!("{} {} line(s)", action_desc, events.len());
 
-        // Determine if we should comment or uncomment
-        // If all lines are commented, uncomment; otherwise comment
-        let all_commented = line_starts.iter().all(|&line_start| {
-            let line_bytes = state
-                .buffer
-                .slice_bytes(line_start..buffer_len.min(line_start + comment_prefix.len() + 10));
-            let line_str = String::from_utf8_lossy(&line_bytes);
-            let trimmed = line_str.trim_start();
-            trimmed.starts_with(comment_prefix.trim())
-        });
+        self.log_and_apply_event(&batch);
+        self.set_status_message(status);
+    }
 
-        let mut events = Vec::new();
+    /// Change the case of the selections, or of the words under the cursors
+    pub(super) fn transform_case(&mut self, transform: CaseTransform) {
+        let Some(event) = self.active_state().transform_case(transform) else {
+            return;
+        };
+        self.log_and_apply_event(&event);
+        self.set_status_message(format!("{} text", transform.label()));
+    }
 
-        if all_commented {
-            // Uncomment: remove comment prefix from each line
-            for &line_start in line_starts.iter().rev() {
-                let line_bytes = state
-                    .buffer
-                    .slice_bytes(line_start..buffer_len.min(line_start + 100));
-                let line_str = String::from_utf8_lossy(&line_bytes);
-
-                // Find where the comment prefix starts (after leading whitespace)
-                let leading_ws: usize = line_str
-                    .chars()
-                    .take_while(|c| c.is_whitespace() && *c != '\n')
-                    .map(|c| c.len_utf8())
-                    .sum();
-                let rest = &line_str[leading_ws..];
-
-                if rest.starts_with(comment_prefix.trim()) {
-                    let remove_len = if rest.starts_with(comment_prefix) {
-                        comment_prefix.len()
-                    } else {
-                        comment_prefix.trim().len()
-                    };
-                    let deleted_text = String::from_utf8_lossy(&state.buffer.slice_bytes(
-                        line_start + leading_ws..line_start + leading_ws + remove_len,
-                    ))
-                    .to_string();
-                    events.push(Event::Delete {
-                        range: (line_start + leading_ws)..(line_start + leading_ws + remove_len),
-                        deleted_text,
-                        cursor_id,
-                    });
+    /// Sort the lines touched by the primary selection, or the whole buffer
+    /// without one
+    pub(super) fn sort_lines(&mut self, options: SortOptions) {
+        let state = self.active_state();
+        let lines = match state.cursors.primary().selection_range() {
+            Some(range) => {
+                let first = state.buffer.get_line_number(range.start);
+                let last = state.buffer.get_line_number(range.end);
+                // A selection ending at the start of a line doesn't include it
+                if last > first && state.buffer.line_start_offset(last) == Some(range.end) {
+                    first..last
+                } else {
+                    first..last + 1
                 }
             }
-        } else {
-            // Comment: add comment prefix to each line
-            for &line_start in line_starts.iter().rev() {
-                events.push(Event::Insert {
-                    position: line_start,
-                    text: comment_prefix.to_string(),
-                    cursor_id,
-                });
-            }
-        }
-
-        if events.is_empty() {
-            return;
-        }
-
-        let action_desc = if all_commented {
-            "Uncomment"
-        } else {
-            "Comment"
+            None => 0..usize::MAX,
         };
-        let batch = Event::Batch {
-            events,
-            description: format!("{} lines", action_desc),
+        let Some(event) = state.sort_lines(lines, options) else {
+            self.set_status_message("Lines already sorted".to_string());
+            return;
         };
+        self.log_and_apply_event(&event);
+        self.set_status_message("Sorted lines".to_string());
+    }
 
-        self.active_event_log_mut().append(batch.clone());
-        self.apply_event_to_active_buffer(&batch);
-        self.set_status_message(format!("{}ed {} line(s)", action_desc, line_starts.len()));
+    /// Join the cursor's line with the next one
+    pub(super) fn join_lines(&mut self) {
+        let Some(event) = self.active_state().join_lines(1) else {
+            self.set_status_message("No line to join".to_string());
+            return;
+        };
+        self.log_and_apply_event(&event);
     }
 
     /// Go to matching bracket
     pub(super) fn goto_matching_bracket(&mut self) {
-        let state = self.active_state_mut();
-        let cursor = state.cursors.primary().clone();
-        let cursor_id = state.cursors.primary_id();
-
-        let pos = cursor.position;
-        if pos >= state.buffer.len() {
-            self.set_status_message("No bracket at cursor".to_string());
-            return;
-        }
-
-        let bytes = state.buffer.slice_bytes(pos..pos + 1);
-        if bytes.is_empty() {
-            self.set_status_message("No bracket at cursor".to_string());
-            return;
-        }
-
-        let ch = bytes[0] as char;
-        let (opening, closing, forward) = match ch {
-            '(' => ('(', ')', true),
-            ')' => ('(', ')', false),
-            '[' => ('[', ']', true),
-            ']' => ('[', ']', false),
-            '{' => ('{', '}', true),
-            '}' => ('{', '}', false),
-            '<' => ('<', '>', true),
-            '>' => ('<', '>', false),
-            _ => {
-                self.set_status_message("No bracket at cursor".to_string());
-                return;
+        let syntax = self.code_syntax();
+        match self.active_state_mut().goto_matching_bracket(syntax) {
+            Some(event) => {
+                self.log_and_apply_event(&event);
             }
-        };
+            None => self.set_status_message("No matching bracket found".to_string()),
+        }
+    }
 
-        // Find matching bracket
-        let buffer_len = state.buffer.len();
-        let mut depth = 1;
-        let matching_pos = if forward {
-            let mut search_pos = pos + 1;
-            let mut found = None;
-            while search_pos < buffer_len && depth > 0 {
-                let b = state.buffer.slice_bytes(search_pos..search_pos + 1);
-                if !b.is_empty() {
-                    let c = b[0] as char;
-                    if c == opening {
-                        depth += 1;
-                    } else if c == closing {
-                        depth -= 1;
-                        if depth == 0 {
-                            found = Some(search_pos);
-                        }
-                    }
-                }
-                search_pos += 1;
+    /// Select from the bracket at the cursor to its matching bracket
+    pub(super) fn select_to_matching_bracket(&mut self) {
+        let syntax = self.code_syntax();
+        match self.active_state_mut().select_to_matching_bracket(syntax) {
+            Some(event) => {
+                self.log_and_apply_event(&event);
             }
-            found
-        } else {
-            let mut search_pos = pos.saturating_sub(1);
-            let mut found = None;
-            loop {
-                let b = state.buffer.slice_bytes(search_pos..search_pos + 1);
-                if !b.is_empty() {
-                    let c = b[0] as char;
-                    if c == closing {
-                        depth += 1;
-                    } else if c == opening {
-                        depth -= 1;
-                        if depth == 0 {
-                            found = Some(search_pos);
-                            break;
-                        }
-                    }
-                }
-                if search_pos == 0 {
-                    break;
-                }
-                search_pos -= 1;
-            }
-            found
-        };
-
-        if let Some(new_pos) = matching_pos {
-            let event = Event::MoveCursor {
-                cursor_id,
-                old_position: cursor.position,
-                new_position: new_pos,
-                old_anchor: cursor.anchor,
-                new_anchor: None,
-                old_sticky_column: cursor.sticky_column,
-                new_sticky_column: 0,
-            };
-            self.active_event_log_mut().append(event.clone());
-            self.apply_event_to_active_buffer(&event);
-        } else {
-            self.set_status_message("No matching bracket found".to_string());
+            None => self.set_status_message("No matching bracket found".to_string()),
         }
     }
 
@@ -2889,8 +2864,7 @@ impl Editor {
                 old_sticky_column: cursor.sticky_column,
                 new_sticky_column: 0,
             };
-            self.active_event_log_mut().append(event.clone());
-            self.apply_event_to_active_buffer(&event);
+            self.log_and_apply_event(&event);
 
             // Show diagnostic message in status bar
             let state = self.active_state();
@@ -2957,8 +2931,7 @@ impl Editor {
                 old_sticky_column: cursor.sticky_column,
                 new_sticky_column: 0,
             };
-            self.active_event_log_mut().append(event.clone());
-            self.apply_event_to_active_buffer(&event);
+            self.log_and_apply_event(&event);
 
             // Show diagnostic message in status bar
             let state = self.active_state();
@@ -3017,8 +2990,10 @@ impl Editor {
         }
     }
 
-    /// Play back a recorded macro
-    pub(super) fn play_macro(&mut self, key: char) {
+    /// Play back a recorded macro `count` times
+    ///
+    /// All edits made by the playback are grouped into a single undo unit.
+    pub(super) fn play_macro(&mut self, key: char, count: usize) {
         if let Some(actions) = self.macros.get(&key).cloned() {
             if actions.is_empty() {
                 self.set_status_message(format!("Macro '{}' is empty", key));
@@ -3028,15 +3003,37 @@ impl Editor {
             // Temporarily disable recording to avoid recording the playback
             let was_recording = self.macro_recording.take();
 
-            let action_count = actions.len();
-            for action in actions {
-                let _ = self.handle_action(action);
+            let buffer_id = self.active_buffer();
+            let undo_start = self.active_event_log().current_index();
+
+            let count = count.max(1);
+            for _ in 0..count {
+                for action in actions.iter().cloned() {
+                    let _ = self.handle_action(action);
+                }
+            }
+
+            // Only group if playback stayed in the same buffer
+            if self.active_buffer() == buffer_id {
+                self.active_event_log_mut()
+                    .group_since(undo_start, format!("Play macro '{}'", key));
             }
 
             // Restore recording state
             self.macro_recording = was_recording;
 
-            self.set_status_message(format!("Played macro '{}' ({} actions)", key, action_count));
+            let action_count = actions.len();
+            if count > 1 {
+                self.set_status_message(format!(
+                    "Played macro '{}' {} times ({} actions)",
+                    key, count, action_count
+                ));
+            } else {
+                self.set_status_message(format!(
+                    "Played macro '{}' ({} actions)",
+                    key, action_count
+                ));
+            }
         } else {
             self.set_status_message(format!("No macro recorded for '{}'", key));
         }
@@ -3205,6 +3202,13 @@ impl Editor {
     pub(super) fn set_bookmark(&mut self, key: char) {
         let buffer_id = self.active_buffer();
         let position = self.active_state().cursors.primary().position;
+        // The mark in the buffer keeps the bookmark on its text as the buffer is edited
+        if let Some(old) = self.bookmarks.get(&key) {
+            if let Some(state) = self.buffers.get_mut(&old.buffer_id) {
+                state.remove_mark(key);
+            }
+        }
+        self.active_state_mut().set_mark(key);
         self.bookmarks.insert(
             key,
             Bookmark {
@@ -3229,24 +3233,14 @@ impl Editor {
                 }
             }
 
-            // Move cursor to bookmark position
-            let state = self.active_state_mut();
-            let cursor_id = state.cursors.primary_id();
-            let old_pos = state.cursors.primary().position;
-            let new_pos = bookmark.position.min(state.buffer.len());
-
-            let event = Event::MoveCursor {
-                cursor_id,
-                old_position: old_pos,
-                new_position: new_pos,
-                old_anchor: state.cursors.primary().anchor,
-                new_anchor: None,
-                old_sticky_column: state.cursors.primary().sticky_column,
-                new_sticky_column: 0,
+            // Move cursor to the bookmark's mark, which has followed any edits
+            let Some(event) = self.active_state().goto_mark(key) else {
+                self.set_status_message(format!("Bookmark '{}': its line was deleted", key));
+                self.bookmarks.remove(&key);
+                return;
             };
 
-            self.active_event_log_mut().append(event.clone());
-            self.apply_event_to_active_buffer(&event);
+            self.log_and_apply_event(&event);
             self.set_status_message(format!("Jumped to bookmark '{}'", key));
         } else {
             self.set_status_message(format!("Bookmark '{}' not set", key));
@@ -3255,7 +3249,10 @@ impl Editor {
 
     /// Clear a bookmark
     pub(super) fn clear_bookmark(&mut self, key: char) {
-        if self.bookmarks.remove(&key).is_some() {
+        if let Some(bookmark) = self.bookmarks.remove(&key) {
+            if let Some(state) = self.buffers.get_mut(&bookmark.buffer_id) {
+                state.remove_mark(key);
+            }
             self.set_status_message(format!("Bookmark '{}' cleared", key));
         } else {
             self.set_status_message(format!("Bookmark '{}' not set", key));
@@ -3280,7 +3277,12 @@ impl Editor {
                     .get(&bm.buffer_id)
                     .map(|m| m.display_name.as_str())
                     .unwrap_or("unknown");
-                format!("'{}': {} @ {}", k, buffer_name, bm.position)
+                let position = self
+                    .buffers
+                    .get(&bm.buffer_id)
+                    .and_then(|state| state.mark(**k))
+                    .unwrap_or(bm.position);
+                format!("'{}': {} @ {}", k, buffer_name, position)
             })
             .collect::<Vec<_>>()
             .join(", ");
@@ -3288,6 +3290,23 @@ impl Editor {
         self.set_status_message(format!("Bookmarks: {}", list_str));
     }
 
+    /// Show line, word and character counts for the buffer and selection
+    pub(super) fn show_document_statistics(&mut self) {
+        let stats = self.active_state().stats();
+        let describe = |s: crate::primitives::text_stats::TextStats| {
+            format!(
+                "{} lines, {} words, {} chars ({} bytes)",
+                s.lines, s.words, s.graphemes, s.bytes
+            )
+        };
+
+        let mut message = describe(stats.document);
+        if let Some(selection) = stats.selection {
+            message.push_str(&format!(" | Selection: {}", describe(selection)));
+        }
+        self.set_status_message(message);
+    }
+
     /// Clear the search history
     /// Used primarily for testing to ensure test isolation
     pub fn clear_search_history(&mut self) {
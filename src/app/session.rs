@@ -34,7 +34,7 @@ use crate::session::{
     FileExplorerState, SearchOptions, SerializedBookmark, SerializedCursor, SerializedFileState,
     SerializedScroll, SerializedSplitDirection, SerializedSplitNode, SerializedSplitViewState,
     SerializedTabRef, SerializedTerminalSession, SerializedViewMode, Session,
-    SessionConfigOverrides, SessionError, SessionHistories, SESSION_VERSION,
+    SessionConfigOverrides, SessionError, SessionHistories, SessionStore, SESSION_VERSION,
 };
 use crate::state::ViewMode;
 use crate::view::split::{SplitNode, SplitViewState};
@@ -244,8 +244,12 @@ impl Editor {
         };
 
         // Capture bookmarks
-        let bookmarks =
-            serialize_bookmarks(&self.bookmarks, &self.buffer_metadata, &self.working_dir);
+        let bookmarks = serialize_bookmarks(
+            &self.bookmarks,
+            &self.buffers,
+            &self.buffer_metadata,
+            &self.working_dir,
+        );
 
         Session {
             version: SESSION_VERSION,
@@ -277,6 +281,72 @@ impl Editor {
         session.save()
     }
 
+    /// Start remembering cursor, marks and scroll per file, picking up what
+    /// earlier runs remembered
+    pub fn enable_file_sessions(&mut self) {
+        let store = SessionStore::load().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load file sessions: {}", e);
+            SessionStore::default()
+        });
+        self.file_sessions = Some(store);
+    }
+
+    /// Remember the navigation state of every open file and write it to disk
+    pub fn save_file_sessions(&mut self) -> Result<(), SessionError> {
+        let ids: Vec<BufferId> = self.buffers.keys().copied().collect();
+        for id in ids {
+            self.record_file_session(id);
+        }
+        match &self.file_sessions {
+            Some(store) => store.save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Remember the navigation state of buffer `id`, if it is a file
+    pub(super) fn record_file_session(&mut self, id: BufferId) {
+        let Some(store) = self.file_sessions.as_mut() else {
+            return;
+        };
+        let Some(state) = self.buffers.get(&id) else {
+            return;
+        };
+        let Some(path) = state.buffer.file_path() else {
+            return;
+        };
+        let viewport = self
+            .split_manager
+            .splits_for_buffer(id)
+            .first()
+            .and_then(|split_id| self.split_view_states.get(split_id))
+            .map(|view_state| &view_state.viewport);
+        store.save_session(path, state, viewport);
+    }
+
+    /// Put back the remembered navigation state of buffer `id`, which must be
+    /// showing in the active split
+    pub(super) fn restore_file_session(&mut self, id: BufferId) {
+        let Some(store) = self.file_sessions.as_ref() else {
+            return;
+        };
+        let Some(state) = self.buffers.get_mut(&id) else {
+            return;
+        };
+        let Some(path) = state.buffer.file_path().map(Path::to_path_buf) else {
+            return;
+        };
+        let active_split = self.split_manager.active_split();
+        let viewport = self
+            .split_view_states
+            .get_mut(&active_split)
+            .map(|view_state| &mut view_state.viewport);
+        if store.restore_session(&path, state, viewport) {
+            if let Some(view_state) = self.split_view_states.get_mut(&active_split) {
+                view_state.cursors = state.cursors.clone();
+            }
+        }
+    }
+
     /// Sync all active terminal visible screens to their backing files.
     ///
     /// Called before session save to ensure backing files contain complete
@@ -464,8 +534,9 @@ impl Editor {
         for (key, bookmark) in &session.bookmarks {
             if let Some(&buffer_id) = path_to_buffer.get(&bookmark.file_path) {
                 // Verify position is valid
-                if let Some(buffer) = self.buffers.get(&buffer_id) {
+                if let Some(buffer) = self.buffers.get_mut(&buffer_id) {
                     let pos = bookmark.position.min(buffer.buffer.len());
+                    buffer.set_mark_at(*key, pos);
                     self.bookmarks.insert(
                         *key,
                         Bookmark {
@@ -726,6 +797,7 @@ impl Editor {
                             second_buffer_id,
                         );
                         view_state.viewport.line_wrap_enabled = self.config.editor.line_wrap;
+                        view_state.viewport.scroll_offset = self.config.editor.scroll_offset;
                         self.split_view_states.insert(new_split_id, view_state);
 
                         // Map the container split ID (though we mainly care about leaves)
@@ -1074,6 +1146,7 @@ fn serialize_split_view_state(
 
 fn serialize_bookmarks(
     bookmarks: &HashMap<char, Bookmark>,
+    buffers: &HashMap<BufferId, EditorState>,
     buffer_metadata: &HashMap<BufferId, super::types::BufferMetadata>,
     working_dir: &Path,
 ) -> HashMap<char, SerializedBookmark> {
@@ -1089,7 +1162,11 @@ fn serialize_bookmarks(
                             *key,
                             SerializedBookmark {
                                 file_path: rel_path.to_path_buf(),
-                                position: bookmark.position,
+                                // Prefer the mark, which tracks edits since the bookmark was set
+                                position: buffers
+                                    .get(&bookmark.buffer_id)
+                                    .and_then(|state| state.mark(*key))
+                                    .unwrap_or(bookmark.position),
                             },
                         )
                     })
@@ -24,10 +24,12 @@ pub(super) struct EventLineInfo {
 /// Search state for find/replace functionality
 #[derive(Debug, Clone)]
 pub(super) struct SearchState {
+    /// The buffer the search ran on; matches are offsets into it
+    pub buffer_id: BufferId,
     /// The search query
     pub query: String,
-    /// All match positions in the buffer (byte offsets)
-    pub matches: Vec<usize>,
+    /// All match ranges in the buffer (byte offsets), in document order
+    pub matches: Vec<Range<usize>>,
     /// Index of the currently selected match
     pub current_match_index: Option<usize>,
     /// Whether search wraps around at document boundaries
@@ -36,6 +38,108 @@ pub(super) struct SearchState {
     pub search_range: Option<Range<usize>>,
 }
 
+impl SearchState {
+    /// Number of cached matches
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Index of the selected match (0-based), for "3 of 12" style status
+    pub fn current_index(&self) -> Option<usize> {
+        self.current_match_index
+    }
+
+    /// Select the first match starting after `cursor`
+    ///
+    /// Wraps to the first match if `wrap_search` is set. Returns the selected
+    /// match, or None if there is no match to move to.
+    pub fn next_match(&mut self, cursor: usize) -> Option<Range<usize>> {
+        let index = match self.matches.iter().position(|m| m.start > cursor) {
+            Some(index) => index,
+            None if self.wrap_search && !self.matches.is_empty() => 0,
+            None => return None,
+        };
+        self.current_match_index = Some(index);
+        Some(self.matches[index].clone())
+    }
+
+    /// Select the last match starting before `cursor`
+    ///
+    /// Wraps to the last match if `wrap_search` is set. Returns the selected
+    /// match, or None if there is no match to move to.
+    pub fn prev_match(&mut self, cursor: usize) -> Option<Range<usize>> {
+        let index = match self.matches.iter().rposition(|m| m.start < cursor) {
+            Some(index) => index,
+            None if self.wrap_search && !self.matches.is_empty() => self.matches.len() - 1,
+            None => return None,
+        };
+        self.current_match_index = Some(index);
+        Some(self.matches[index].clone())
+    }
+
+    /// Shift cached matches after `length` bytes were inserted at `position`
+    ///
+    /// A match the insertion lands inside no longer matches the query, so it is
+    /// dropped. Text inserted right at a match's end stays outside it.
+    pub fn adjust_for_insert(&mut self, position: usize, length: usize) {
+        let shift = |offset: usize, moves: bool| if moves { offset + length } else { offset };
+        self.retain_and_shift(
+            |m| !(m.start < position && position < m.end),
+            |m| shift(m.start, m.start >= position)..shift(m.end, m.end > position),
+        );
+        if let Some(range) = &mut self.search_range {
+            if range.start >= position {
+                range.start += length;
+            }
+            if range.end >= position {
+                range.end += length;
+            }
+        }
+    }
+
+    /// Shift cached matches after `length` bytes were deleted at `position`
+    ///
+    /// Matches that overlap the deleted text are dropped.
+    pub fn adjust_for_delete(&mut self, position: usize, length: usize) {
+        let end = position + length;
+        let map = |offset: usize| {
+            if offset >= end {
+                offset - length
+            } else {
+                offset.min(position)
+            }
+        };
+        self.retain_and_shift(
+            |m| m.end <= position || m.start >= end,
+            |m| map(m.start)..map(m.end),
+        );
+        if let Some(range) = &mut self.search_range {
+            *range = map(range.start)..map(range.end);
+        }
+    }
+
+    /// Keep matches satisfying `keep`, remap them with `map`, and keep
+    /// `current_match_index` pointing at the same match (None if it was dropped)
+    fn retain_and_shift(
+        &mut self,
+        keep: impl Fn(&Range<usize>) -> bool,
+        map: impl Fn(&Range<usize>) -> Range<usize>,
+    ) {
+        let current = self.current_match_index;
+        self.current_match_index = None;
+        let mut kept = Vec::with_capacity(self.matches.len());
+        for (index, m) in self.matches.drain(..).enumerate() {
+            if keep(&m) {
+                if current == Some(index) {
+                    self.current_match_index = Some(kept.len());
+                }
+                kept.push(map(&m));
+            }
+        }
+        self.matches = kept;
+    }
+}
+
 /// A bookmark in the editor (position in a specific buffer)
 #[derive(Debug, Clone)]
 pub(super) struct Bookmark {
@@ -397,3 +501,183 @@ pub(super) struct CachedLayout {
     /// Settings modal layout for hit testing
     pub settings_layout: Option<crate::view::settings::SettingsLayout>,
 }
+
+/// State of an incremental search (isearch) prompt
+///
+/// Each time the query changes, the match is looked up again from
+/// `search_from`, so deleting characters from the query moves the cursor back
+/// to where the shorter query matched.
+#[derive(Debug, Clone)]
+pub(super) struct IncrementalSearch {
+    /// Cursor position when the search started
+    pub origin: usize,
+    /// Selection anchor when the search started
+    pub origin_anchor: Option<usize>,
+    /// Top of the viewport when the search started
+    pub origin_top_byte: usize,
+    /// Where matching starts; moves past the current match on "next"
+    pub search_from: usize,
+    /// The match the cursor is on
+    pub current: Option<Range<usize>>,
+}
+
+impl IncrementalSearch {
+    pub fn new(origin: usize, origin_anchor: Option<usize>, origin_top_byte: usize) -> Self {
+        Self {
+            origin,
+            origin_anchor,
+            origin_top_byte,
+            search_from: origin,
+            current: None,
+        }
+    }
+
+    /// Find the first match at or after `search_from`, wrapping around
+    ///
+    /// `regex` is None for an empty or invalid query, which matches nothing.
+    pub fn update(
+        &mut self,
+        buffer: &crate::model::buffer::Buffer,
+        regex: Option<&regex::bytes::Regex>,
+    ) -> Option<Range<usize>> {
+        self.current =
+            regex.and_then(|regex| buffer.find_next_regex_match(regex, self.search_from));
+        self.current.clone()
+    }
+
+    /// Move on to the first match after the current one
+    pub fn advance(
+        &mut self,
+        buffer: &crate::model::buffer::Buffer,
+        regex: Option<&regex::bytes::Regex>,
+    ) -> Option<Range<usize>> {
+        if let Some(current) = &self.current {
+            self.search_from = current.start + 1;
+        }
+        self.update(buffer, regex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search_state(starts: &[usize], wrap_search: bool) -> SearchState {
+        SearchState {
+            buffer_id: BufferId(0),
+            query: "foo".to_string(),
+            matches: starts.iter().map(|&start| start..start + 3).collect(),
+            current_match_index: None,
+            wrap_search,
+            search_range: None,
+        }
+    }
+
+    #[test]
+    fn test_search_next_prev_wrap_around() {
+        let mut state = search_state(&[0, 10, 20], true);
+        assert_eq!(state.match_count(), 3);
+        assert_eq!(state.current_index(), None);
+
+        assert_eq!(state.next_match(0), Some(10..13));
+        assert_eq!(state.current_index(), Some(1));
+        assert_eq!(state.next_match(10), Some(20..23));
+        // Past the last match wraps to the first
+        assert_eq!(state.next_match(20), Some(0..3));
+        assert_eq!(state.current_index(), Some(0));
+
+        // Before the first match wraps to the last
+        assert_eq!(state.prev_match(0), Some(20..23));
+        assert_eq!(state.current_index(), Some(2));
+        assert_eq!(state.prev_match(20), Some(10..13));
+
+        // Navigation is relative to the cursor, not the previously selected match
+        assert_eq!(state.next_match(15), Some(20..23));
+        assert_eq!(state.prev_match(15), Some(10..13));
+    }
+
+    #[test]
+    fn test_search_without_wrap_stops_at_ends() {
+        let mut state = search_state(&[5, 15], false);
+        assert_eq!(state.next_match(15), None);
+        assert_eq!(state.prev_match(5), None);
+        assert_eq!(state.current_index(), None);
+    }
+
+    fn literal(query: &str) -> regex::bytes::Regex {
+        regex::bytes::Regex::new(&regex::escape(query)).unwrap()
+    }
+
+    #[test]
+    fn test_incremental_search_refines_keystroke_by_keystroke() {
+        let buffer = crate::model::buffer::Buffer::from_str_test("fox fork foo\nfoo bar\n");
+        let mut search = IncrementalSearch::new(1, None, 0);
+
+        // "f" -> "fo" -> "foo": each refinement searches again from the origin
+        assert_eq!(search.update(&buffer, Some(&literal("f"))), Some(4..5));
+        assert_eq!(search.update(&buffer, Some(&literal("fo"))), Some(4..6));
+        assert_eq!(search.update(&buffer, Some(&literal("foo"))), Some(9..12));
+        // "fool" fails; backspacing to "foo" and then "fo" goes back
+        assert_eq!(search.update(&buffer, Some(&literal("fool"))), None);
+        assert_eq!(search.update(&buffer, Some(&literal("foo"))), Some(9..12));
+        assert_eq!(search.update(&buffer, Some(&literal("fo"))), Some(4..6));
+        // An empty query matches nothing
+        assert_eq!(search.update(&buffer, None), None);
+    }
+
+    #[test]
+    fn test_incremental_search_advance_wraps_around() {
+        let buffer = crate::model::buffer::Buffer::from_str_test("foo bar foo");
+        let mut search = IncrementalSearch::new(4, None, 0);
+        let regex = literal("foo");
+
+        assert_eq!(search.update(&buffer, Some(&regex)), Some(8..11));
+        assert_eq!(search.advance(&buffer, Some(&regex)), Some(0..3));
+        assert_eq!(search.advance(&buffer, Some(&regex)), Some(8..11));
+        assert_eq!(search.origin, 4);
+    }
+
+    #[test]
+    fn test_search_empty_results() {
+        let mut state = search_state(&[], true);
+        assert_eq!(state.match_count(), 0);
+        assert_eq!(state.next_match(0), None);
+        assert_eq!(state.prev_match(100), None);
+        assert_eq!(state.current_index(), None);
+    }
+
+    #[test]
+    fn test_search_matches_follow_edits() {
+        let mut state = search_state(&[0, 10, 20], true);
+        state.next_match(5);
+        assert_eq!(state.current_index(), Some(1));
+
+        // Insert before the selected match shifts it and everything after it
+        state.adjust_for_insert(5, 2);
+        assert_eq!(state.matches, vec![0..3, 12..15, 22..25]);
+        assert_eq!(state.current_index(), Some(1));
+
+        // Insert inside a match invalidates it
+        state.adjust_for_insert(1, 1);
+        assert_eq!(state.matches, vec![13..16, 23..26]);
+        assert_eq!(state.current_index(), Some(0));
+
+        // Delete overlapping the selected match drops it
+        state.adjust_for_delete(10, 4);
+        assert_eq!(state.matches, vec![19..22]);
+        assert_eq!(state.current_index(), None);
+    }
+
+    #[test]
+    fn test_insert_at_match_edges_keeps_match_text() {
+        let mut state = search_state(&[0, 10], true);
+
+        // Typing right after a match leaves the match as it was
+        state.adjust_for_insert(3, 2);
+        assert_eq!(state.matches, vec![0..3, 12..15]);
+
+        // Typing right before a match pushes the whole match forward
+        state.adjust_for_insert(12, 4);
+        assert_eq!(state.matches, vec![0..3, 16..19]);
+    }
+}
@@ -165,6 +165,11 @@ pub struct Config {
     /// Menu bar configuration
     #[serde(default)]
     pub menu: MenuConfig,
+
+    /// Snippets by trigger word, expanded by pressing Tab after the trigger.
+    /// Bodies may use `$1`, `${2:placeholder}` tab stops and `$0` for the final cursor.
+    #[serde(default)]
+    pub snippets: HashMap<String, String>,
 }
 
 fn default_keybinding_map_name() -> KeybindingMapName {
@@ -182,6 +187,31 @@ pub struct EditorConfig {
     #[serde(default = "default_tab_size")]
     pub tab_size: usize,
 
+    /// Indent with a tab character instead of `tab_size` spaces: the Tab key
+    /// inserts a literal tab and selected lines are indented with one.
+    /// Otherwise Tab inserts spaces up to the next tab stop
+    #[serde(default = "default_false")]
+    pub indent_with_tabs: bool,
+
+    /// Match the indentation already used in a file: tabs or spaces, and how
+    /// many spaces, are detected from its lines, falling back to `tab_size`
+    /// and `indent_with_tabs` when the file doesn't make it clear
+    #[serde(default = "default_false")]
+    pub detect_indentation: bool,
+
+    /// Backspace in the leading spaces of a line removes back to the previous
+    /// tab stop, as if the spaces were a tab, when indenting with spaces
+    #[serde(default = "default_false")]
+    pub soft_tab_backspace: bool,
+
+    /// Remove spaces and tabs at the end of every line when saving
+    #[serde(default = "default_false")]
+    pub trim_trailing_whitespace: bool,
+
+    /// Make sure a non-empty file ends with a line ending when saving
+    #[serde(default = "default_false")]
+    pub ensure_final_newline: bool,
+
     /// Automatically indent new lines based on the previous line
     #[serde(default = "default_true")]
     pub auto_indent: bool,
@@ -206,6 +236,16 @@ pub struct EditorConfig {
     #[serde(default = "default_true")]
     pub line_wrap: bool,
 
+    /// How far text in unfocused splits fades toward its background, from 0.0
+    /// (not at all, the default) to 1.0 (invisible)
+    #[serde(default = "default_inactive_split_dim")]
+    pub inactive_split_dim: f32,
+
+    /// Make the text cursor blink (its shape follows the mode: a bar when
+    /// inserting, a block in overtype mode, an underline in read-only buffers)
+    #[serde(default = "default_true")]
+    pub cursor_blink: bool,
+
     /// Maximum time in milliseconds for syntax highlighting per frame
     #[serde(default = "default_highlight_timeout")]
     pub highlight_timeout_ms: u64,
@@ -216,6 +256,8 @@ pub struct EditorConfig {
 
     /// File size threshold in bytes for "large file" behavior
     /// Files larger than this will:
+    /// - Load on demand without indexing lines up front
+    /// - Skip syntax highlighting and line wrapping
     /// - Skip LSP features
     /// - Use constant-size scrollbar thumb (1 char)
     /// Files smaller will count actual lines for accurate scrollbar rendering
@@ -245,6 +287,19 @@ pub struct EditorConfig {
     #[serde(default = "default_auto_save_interval")]
     pub auto_save_interval_secs: u32,
 
+    /// Idle delay in milliseconds before a modified buffer is saved automatically
+    /// The delay restarts on every edit, so the save happens once typing stops.
+    /// Unnamed and read-only buffers are never auto-saved.
+    /// Default: 0 (disabled)
+    #[serde(default)]
+    pub auto_save_delay_ms: u64,
+
+    /// Write auto-saves to a `<file>.autosave` sidecar instead of the file itself
+    /// The buffer stays marked as modified until it is saved explicitly.
+    /// Default: false
+    #[serde(default)]
+    pub auto_save_to_sidecar: bool,
+
     /// Number of bytes to look back/forward from the viewport for syntax highlighting context.
     /// Larger values improve accuracy for multi-line constructs (strings, comments, nested blocks)
     /// but may slow down highlighting for very large files.
@@ -283,6 +338,19 @@ pub struct EditorConfig {
     /// Default: 3000ms (3 seconds)
     #[serde(default = "default_file_tree_poll_interval")]
     pub file_tree_poll_interval_ms: u64,
+
+    /// Which whitespace characters to draw markers for.
+    /// Tabs are shown as "→" and spaces as "·" in a dim color.
+    /// Default: "tabs"
+    #[serde(default)]
+    pub render_whitespace: WhitespaceMode,
+
+    /// Columns at which to draw a vertical ruler, e.g. [80, 100].
+    /// Columns count from 0 with tabs expanded to `tab_size`, so a ruler at 80
+    /// marks the first column past an 80-character line.
+    /// Default: [] (no rulers)
+    #[serde(default)]
+    pub rulers: Vec<usize>,
 }
 
 fn default_tab_size() -> usize {
@@ -310,6 +378,10 @@ fn default_scroll_offset() -> usize {
     3
 }
 
+fn default_inactive_split_dim() -> f32 {
+    0.0
+}
+
 fn default_highlight_timeout() -> u64 {
     5
 }
@@ -350,12 +422,19 @@ impl Default for EditorConfig {
     fn default() -> Self {
         Self {
             tab_size: default_tab_size(),
+            indent_with_tabs: false,
+            detect_indentation: false,
+            soft_tab_backspace: false,
+            trim_trailing_whitespace: false,
+            ensure_final_newline: false,
             auto_indent: true,
             line_numbers: true,
             relative_line_numbers: false,
             scroll_offset: default_scroll_offset(),
             syntax_highlighting: true,
             line_wrap: true,
+            inactive_split_dim: default_inactive_split_dim(),
+            cursor_blink: true,
             highlight_timeout_ms: default_highlight_timeout(),
             snapshot_interval: default_snapshot_interval(),
             large_file_threshold_bytes: default_large_file_threshold(),
@@ -363,12 +442,16 @@ impl Default for EditorConfig {
             enable_inlay_hints: true,
             recovery_enabled: true,
             auto_save_interval_secs: default_auto_save_interval(),
+            auto_save_delay_ms: 0,
+            auto_save_to_sidecar: false,
             highlight_context_bytes: default_highlight_context_bytes(),
             mouse_hover_enabled: true,
             mouse_hover_delay_ms: default_mouse_hover_delay(),
             double_click_time_ms: default_double_click_time(),
             auto_revert_poll_interval_ms: default_auto_revert_poll_interval(),
             file_tree_poll_interval_ms: default_file_tree_poll_interval(),
+            render_whitespace: WhitespaceMode::default(),
+            rulers: Vec::new(),
         }
     }
 }
@@ -507,6 +590,11 @@ pub struct LanguageConfig {
     /// If specified, this grammar will be used when highlighter is "textmate"
     #[serde(default)]
     pub textmate_grammar: Option<std::path::PathBuf>,
+
+    /// Characters besides letters, digits and `_` that word motion treats as
+    /// part of a word (e.g. "-" for CSS or Lisp)
+    #[serde(default)]
+    pub word_chars: String,
 }
 
 /// Preference for which syntax highlighting backend to use
@@ -524,6 +612,28 @@ pub enum HighlighterPreference {
     TextMate,
 }
 
+/// Which whitespace characters the editor draws visible markers for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WhitespaceMode {
+    /// Draw no whitespace markers
+    None,
+    /// Mark tabs only
+    #[default]
+    Tabs,
+    /// Mark tabs and spaces at the end of a line
+    Trailing,
+    /// Mark tabs and every space
+    All,
+}
+
+impl WhitespaceMode {
+    /// Whether tabs get a marker in this mode
+    pub fn shows_tabs(self) -> bool {
+        self != Self::None
+    }
+}
+
 /// Menu bar configuration
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MenuConfig {
@@ -537,12 +647,39 @@ pub struct MenuConfig {
 pub struct Menu {
     /// Display label for the menu (e.g., "File", "Edit")
     pub label: String,
+    /// Start a new group of menus: a divider is drawn before this menu in the menu bar
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub group_start: bool,
+    /// Minimum dropdown width in columns, widening it past what the labels need
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<u16>,
+    /// Maximum dropdown width in columns, replacing the default cap of 40;
+    /// longer labels are truncated with "…"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<u16>,
+    /// Most items the dropdown lists; past this, the last slot becomes a
+    /// "More…" submenu with the remaining items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_visible_items: Option<u16>,
     /// Menu items (actions, separators, or submenus)
     pub items: Vec<MenuItem>,
 }
 
+/// Role of a menu action, picking the theme color its label is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuRole {
+    /// Irreversible actions (`menu_destructive_fg`)
+    Destructive,
+    /// The main action of a menu (`menu_primary_fg`)
+    Primary,
+}
+
 /// A menu item (action, separator, or submenu)
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+///
+/// Deserialized through [`MenuItemRepr`], which requires a submenu to list
+/// its `items` unless a provider supplies them; its schema is the same.
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub enum MenuItem {
     /// A separator line
@@ -558,9 +695,132 @@ pub enum MenuItem {
         /// Checkbox state condition (e.g., "line_numbers", "line_wrap")
         #[serde(default)]
         checkbox: Option<String>,
+        /// Accent for the label, e.g. "destructive" for "Delete All"
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        style_role: Option<MenuRole>,
+    },
+    /// A submenu
+    Submenu {
+        label: String,
+        #[serde(default)]
+        items: Vec<MenuItem>,
+        /// Id of a registered provider that supplies the items when the submenu opens
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
     },
-    /// A submenu (for future extensibility)
-    Submenu { label: String, items: Vec<MenuItem> },
+}
+
+// Accepted shapes of a menu item in config files, kept apart from
+// `MenuItem` so an item with a misspelled or missing `action` is rejected
+// instead of matching as an empty submenu. Its doc comments make up the
+// schema the config editor validates menus against.
+
+/// A menu item (action, separator, or submenu)
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum MenuItemRepr {
+    /// A separator line
+    Separator { separator: bool },
+    /// An action item
+    Action {
+        label: String,
+        action: String,
+        #[serde(default)]
+        args: HashMap<String, serde_json::Value>,
+        #[serde(default)]
+        when: Option<String>,
+        /// Checkbox state condition (e.g., "line_numbers", "line_wrap")
+        #[serde(default)]
+        checkbox: Option<String>,
+        /// Accent for the label, e.g. "destructive" for "Delete All"
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        style_role: Option<MenuRole>,
+    },
+    /// A submenu
+    Submenu {
+        label: String,
+        items: Vec<MenuItem>,
+        /// Id of a registered provider that supplies the items when the submenu opens
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
+    },
+    /// A submenu whose items come from a provider
+    ProvidedSubmenu {
+        label: String,
+        #[serde(default)]
+        items: Vec<MenuItem>,
+        /// Id of a registered provider that supplies the items when the submenu opens
+        provider: String,
+    },
+}
+
+impl JsonSchema for MenuItem {
+    fn schema_name() -> Cow<'static, str> {
+        Cow::Borrowed("MenuItem")
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        MenuItemRepr::json_schema(generator)
+    }
+}
+
+impl From<MenuItemRepr> for MenuItem {
+    fn from(repr: MenuItemRepr) -> Self {
+        match repr {
+            MenuItemRepr::Separator { separator } => MenuItem::Separator { separator },
+            MenuItemRepr::Action {
+                label,
+                action,
+                args,
+                when,
+                checkbox,
+                style_role,
+            } => MenuItem::Action {
+                label,
+                action,
+                args,
+                when,
+                checkbox,
+                style_role,
+            },
+            MenuItemRepr::Submenu {
+                label,
+                items,
+                provider,
+            } => MenuItem::Submenu {
+                label,
+                items,
+                provider,
+            },
+            MenuItemRepr::ProvidedSubmenu {
+                label,
+                items,
+                provider,
+            } => MenuItem::Submenu {
+                label,
+                items,
+                provider: Some(provider),
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MenuItem {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        MenuItemRepr::deserialize(&value)
+            .map(MenuItem::from)
+            .map_err(|_| {
+                let label = value.get("label").and_then(|l| l.as_str()).unwrap_or("");
+                serde::de::Error::custom(format!(
+                    "menu item \"{}\" needs an \"action\", or \"items\" or a \"provider\" for a submenu",
+                    label
+                ))
+            })
+    }
 }
 
 impl Default for Config {
@@ -577,6 +837,7 @@ impl Default for Config {
             languages: Self::default_languages(),
             lsp: Self::default_lsp_config(),
             menu: MenuConfig::default(),
+            snippets: HashMap::new(),
         }
     }
 }
@@ -727,6 +988,7 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
 
@@ -739,6 +1001,7 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
 
@@ -751,6 +1014,7 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
 
@@ -763,6 +1027,7 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
 
@@ -775,6 +1040,7 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
 
@@ -794,6 +1060,7 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
 
@@ -806,6 +1073,7 @@ impl Config {
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
 
@@ -979,6 +1247,10 @@ impl Config {
             // File menu
             Menu {
                 label: "File".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "New File".to_string(),
@@ -986,6 +1258,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Open File...".to_string(),
@@ -993,6 +1266,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1001,6 +1275,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Save As...".to_string(),
@@ -1008,6 +1283,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Revert".to_string(),
@@ -1015,6 +1291,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: Some(MenuRole::Destructive),
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1023,6 +1300,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1031,6 +1309,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Quit".to_string(),
@@ -1038,12 +1317,17 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
             // Edit menu
             Menu {
                 label: "Edit".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "Undo".to_string(),
@@ -1051,6 +1335,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Redo".to_string(),
@@ -1058,6 +1343,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1066,6 +1352,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Copy".to_string(),
@@ -1073,6 +1360,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Paste".to_string(),
@@ -1080,6 +1368,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1088,6 +1377,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1096,6 +1386,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Find in Selection".to_string(),
@@ -1103,6 +1394,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::HAS_SELECTION.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Find Next".to_string(),
@@ -1110,6 +1402,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Find Previous".to_string(),
@@ -1117,6 +1410,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Replace...".to_string(),
@@ -1124,6 +1418,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1132,12 +1427,17 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
             // View menu
             Menu {
                 label: "View".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "File Explorer".to_string(),
@@ -1145,6 +1445,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: Some(context_keys::FILE_EXPLORER.to_string()),
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1153,6 +1454,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: Some(context_keys::LINE_NUMBERS.to_string()),
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Line Wrap".to_string(),
@@ -1160,6 +1462,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: Some(context_keys::LINE_WRAP.to_string()),
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Mouse Support".to_string(),
@@ -1167,6 +1470,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: Some(context_keys::MOUSE_CAPTURE.to_string()),
+                        style_role: None,
                     },
                     // Note: Compose Mode removed from menu - markdown_compose plugin provides this
                     MenuItem::Separator { separator: true },
@@ -1176,6 +1480,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Set Background Blend...".to_string(),
@@ -1183,6 +1488,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Set Compose Width...".to_string(),
@@ -1190,6 +1496,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1198,6 +1505,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Settings...".to_string(),
@@ -1205,6 +1513,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1213,6 +1522,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Split Vertical".to_string(),
@@ -1220,6 +1530,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Close Split".to_string(),
@@ -1227,6 +1538,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Focus Next Split".to_string(),
@@ -1234,6 +1546,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Focus Previous Split".to_string(),
@@ -1241,6 +1554,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Toggle Maximize Split".to_string(),
@@ -1248,6 +1562,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Submenu {
@@ -1259,6 +1574,7 @@ impl Config {
                                 args: HashMap::new(),
                                 when: None,
                                 checkbox: None,
+                                style_role: None,
                             },
                             MenuItem::Action {
                                 label: "Close Terminal".to_string(),
@@ -1266,6 +1582,7 @@ impl Config {
                                 args: HashMap::new(),
                                 when: None,
                                 checkbox: None,
+                                style_role: None,
                             },
                             MenuItem::Separator { separator: true },
                             MenuItem::Action {
@@ -1274,8 +1591,10 @@ impl Config {
                                 args: HashMap::new(),
                                 when: None,
                                 checkbox: None,
+                                style_role: None,
                             },
                         ],
+                        provider: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Submenu {
@@ -1291,6 +1610,7 @@ impl Config {
                                 },
                                 when: None,
                                 checkbox: None,
+                                style_role: None,
                             },
                             MenuItem::Action {
                                 label: "Emacs".to_string(),
@@ -1302,6 +1622,7 @@ impl Config {
                                 },
                                 when: None,
                                 checkbox: None,
+                                style_role: None,
                             },
                             MenuItem::Action {
                                 label: "VSCode".to_string(),
@@ -1313,14 +1634,20 @@ impl Config {
                                 },
                                 when: None,
                                 checkbox: None,
+                                style_role: None,
                             },
                         ],
+                        provider: None,
                     },
                 ],
             },
             // Selection menu
             Menu {
                 label: "Selection".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "Select All".to_string(),
@@ -1328,6 +1655,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Select Word".to_string(),
@@ -1335,6 +1663,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Select Line".to_string(),
@@ -1342,6 +1671,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Expand Selection".to_string(),
@@ -1349,6 +1679,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1357,6 +1688,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Add Cursor Below".to_string(),
@@ -1364,6 +1696,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Add Cursor at Next Match".to_string(),
@@ -1371,6 +1704,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Remove Secondary Cursors".to_string(),
@@ -1378,12 +1712,17 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
             // Go menu
             Menu {
                 label: "Go".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "Go to Line...".to_string(),
@@ -1391,6 +1730,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Go to Definition".to_string(),
@@ -1398,6 +1738,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Find References".to_string(),
@@ -1405,6 +1746,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1413,6 +1755,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Previous Buffer".to_string(),
@@ -1420,20 +1763,31 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
+                    MenuItem::Submenu {
+                        label: "Recent Commands".to_string(),
+                        items: vec![],
+                        provider: Some(crate::types::RECENT_COMMANDS_PROVIDER.to_string()),
+                    },
                     MenuItem::Action {
                         label: "Command Palette...".to_string(),
                         action: "command_palette".to_string(),
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
             // LSP menu (Language Server Protocol operations)
             Menu {
                 label: "LSP".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "Show Hover Info".to_string(),
@@ -1441,6 +1795,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Go to Definition".to_string(),
@@ -1448,6 +1803,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Find References".to_string(),
@@ -1455,6 +1811,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Rename Symbol".to_string(),
@@ -1462,6 +1819,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1470,6 +1828,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Show Signature Help".to_string(),
@@ -1477,6 +1836,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Code Actions".to_string(),
@@ -1484,6 +1844,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1492,6 +1853,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::LSP_AVAILABLE.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Toggle Mouse Hover".to_string(),
@@ -1499,6 +1861,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: Some(context_keys::MOUSE_HOVER.to_string()),
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1507,6 +1870,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Stop Server".to_string(),
@@ -1514,12 +1878,17 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
             // Explorer menu (file explorer operations)
             Menu {
                 label: "Explorer".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "New File".to_string(),
@@ -1527,6 +1896,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER_FOCUSED.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "New Folder".to_string(),
@@ -1534,6 +1904,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER_FOCUSED.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1542,6 +1913,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER_FOCUSED.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Rename".to_string(),
@@ -1549,6 +1921,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER_FOCUSED.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Delete".to_string(),
@@ -1556,6 +1929,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER_FOCUSED.to_string()),
                         checkbox: None,
+                        style_role: Some(MenuRole::Destructive),
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1564,6 +1938,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER_FOCUSED.to_string()),
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1572,6 +1947,7 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER.to_string()),
                         checkbox: Some(context_keys::FILE_EXPLORER_SHOW_HIDDEN.to_string()),
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Show Gitignored Files".to_string(),
@@ -1579,12 +1955,17 @@ impl Config {
                         args: HashMap::new(),
                         when: Some(context_keys::FILE_EXPLORER.to_string()),
                         checkbox: Some(context_keys::FILE_EXPLORER_SHOW_GITIGNORED.to_string()),
+                        style_role: None,
                     },
                 ],
             },
             // Help menu
             Menu {
                 label: "Help".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "Show Fresh Manual".to_string(),
@@ -1592,6 +1973,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Keyboard Shortcuts".to_string(),
@@ -1599,6 +1981,7 @@ impl Config {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
@@ -1679,6 +2062,58 @@ mod tests {
         assert!(!resolved.is_empty());
     }
 
+    #[test]
+    fn test_menu_item_requires_action_or_items() {
+        let typo = serde_json::json!({ "label": "Save", "acton": "save" });
+        let err = serde_json::from_value::<MenuItem>(typo).unwrap_err();
+        assert!(err.to_string().contains("\"Save\""));
+
+        let provided = serde_json::json!({ "label": "Recent", "provider": "recent" });
+        match serde_json::from_value::<MenuItem>(provided).unwrap() {
+            MenuItem::Submenu {
+                items, provider, ..
+            } => {
+                assert!(items.is_empty());
+                assert_eq!(provider.as_deref(), Some("recent"));
+            }
+            other => panic!("expected a submenu, got {:?}", other),
+        }
+
+        let submenu = serde_json::json!({
+            "label": "View",
+            "items": [{ "label": "Zoom", "action": "zoom" }, { "separator": true }]
+        });
+        match serde_json::from_value::<MenuItem>(submenu).unwrap() {
+            MenuItem::Submenu {
+                items, provider, ..
+            } => {
+                assert_eq!(items.len(), 2);
+                assert!(provider.is_none());
+            }
+            other => panic!("expected a submenu, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_menu_item_schema_requires_action_or_items() {
+        let schema = serde_json::to_value(schemars::schema_for!(MenuItem)).unwrap();
+        let required: Vec<&serde_json::Value> = schema["anyOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variant| &variant["required"])
+            .collect();
+        assert_eq!(
+            required,
+            vec![
+                &serde_json::json!(["separator"]),
+                &serde_json::json!(["label", "action"]),
+                &serde_json::json!(["label", "items"]),
+                &serde_json::json!(["label", "provider"]),
+            ]
+        );
+    }
+
     #[test]
     fn test_config_validation() {
         let mut config = Config::default();
@@ -5,6 +5,7 @@ use crate::model::buffer::Buffer;
 use crate::model::cursor::{Position2D, SelectionMode};
 use crate::model::event::{CursorId, Event};
 use crate::primitives::display_width::{byte_offset_at_visual_column, str_width};
+use crate::primitives::indent::IndentStyle;
 use crate::primitives::word_navigation::{
     find_word_end, find_word_start, find_word_start_left, find_word_start_right,
 };
@@ -69,12 +70,24 @@ fn calculate_visual_column(
 ///
 /// This is a common pattern used across many deletion actions.
 /// It reads the text from each range and creates Delete events.
+/// Overlapping ranges (two cursors deleting the same line break) are merged,
+/// and the events run from the end of the buffer backwards so no deletion
+/// shifts the ones after it.
 fn apply_deletions(
     state: &mut EditorState,
-    deletions: Vec<(CursorId, Range<usize>)>,
+    mut deletions: Vec<(CursorId, Range<usize>)>,
     events: &mut Vec<Event>,
 ) {
+    deletions.sort_by_key(|(_, range)| range.start);
+    let mut merged: Vec<(CursorId, Range<usize>)> = Vec::with_capacity(deletions.len());
     for (cursor_id, range) in deletions {
+        match merged.last_mut() {
+            Some((_, last)) if range.start < last.end => last.end = last.end.max(range.end),
+            _ => merged.push((cursor_id, range)),
+        }
+    }
+
+    for (cursor_id, range) in merged.into_iter().rev() {
         let deleted_text = state.get_text_range(range.start, range.end);
         events.push(Event::Delete {
             range,
@@ -195,6 +208,19 @@ pub fn clear_block_selection_if_active(state: &mut EditorState) {
     });
 }
 
+/// Closing half of the bracket or quote pair that `ch` opens
+fn auto_close_pair(ch: char) -> Option<char> {
+    match ch {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        '`' => Some('`'),
+        _ => None,
+    }
+}
+
 /// Calculate the maximum valid cursor position in the buffer.
 /// This is the end of the last line (excluding trailing newline).
 /// For empty buffers, returns 0.
@@ -210,6 +236,8 @@ fn max_cursor_position(buffer: &Buffer) -> usize {
 /// * `state` - The current editor state
 /// * `action` - The action to convert
 /// * `tab_size` - Number of spaces per tab
+/// * `backspace_indent` - Indentation of which Backspace in leading spaces
+///   removes a whole level, if any
 /// * `auto_indent` - Whether auto-indent is enabled
 /// * `estimated_line_length` - Estimated bytes per line for large files
 /// * `viewport_height` - Height of the viewport in lines (for PageUp/PageDown)
@@ -221,6 +249,7 @@ pub fn action_to_events(
     state: &mut EditorState,
     action: Action,
     tab_size: usize,
+    backspace_indent: Option<IndentStyle>,
     auto_indent: bool,
     estimated_line_length: usize,
     viewport_height: u16,
@@ -228,6 +257,29 @@ pub fn action_to_events(
     let mut events = Vec::new();
 
     match action {
+        // Overtype mode - replace the character under each cursor
+        Action::InsertChar(ch) if state.overwrite => {
+            events.extend(state.overwrite_events(&ch.to_string()));
+        }
+
+        // Typing an opening bracket or quote over selections wraps them in the pair
+        Action::InsertChar(ch)
+            if auto_indent
+                && auto_close_pair(ch).is_some()
+                && state
+                    .cursors
+                    .iter()
+                    .all(|(_, cursor)| cursor.selection_range().is_some()) =>
+        {
+            if let Some(close) = auto_close_pair(ch) {
+                if let Some(Event::Batch { events: wrap, .. }) =
+                    state.surround(&ch.to_string(), &close.to_string())
+                {
+                    events.extend(wrap);
+                }
+            }
+        }
+
         // Character input - insert at each cursor
         Action::InsertChar(ch) => {
             // Collect cursors and sort by the effective insert position (reverse order)
@@ -245,15 +297,7 @@ pub fn action_to_events(
 
             // Check if this is an opening bracket that should auto-close
             let auto_close_char = if auto_indent {
-                match ch {
-                    '(' => Some(')'),
-                    '[' => Some(']'),
-                    '{' => Some('}'),
-                    '"' => Some('"'),
-                    '\'' => Some('\''),
-                    '`' => Some('`'),
-                    _ => None,
-                }
+                auto_close_pair(ch)
             } else {
                 None
             };
@@ -595,33 +639,31 @@ pub fn action_to_events(
             }
         }
 
+        // Soft tab - insert spaces up to the next tab stop at each cursor
         Action::InsertTab => {
-            let tab_str = " ".repeat(tab_size);
             // Sort cursors by position (reverse order) to avoid position shifts
             let mut cursor_vec: Vec<_> = state.cursors.iter().collect();
             cursor_vec.sort_by_key(|(_, c)| std::cmp::Reverse(c.position));
 
-            // Collect deletions and insert positions
-            let deletions: Vec<_> = cursor_vec
+            // The tab replaces the selection, if any, and lines up from where it starts
+            let edits: Vec<_> = cursor_vec
                 .iter()
-                .filter_map(|(cursor_id, cursor)| {
-                    cursor.selection_range().map(|range| (*cursor_id, range))
+                .map(|(cursor_id, cursor)| {
+                    let range = cursor
+                        .selection_range()
+                        .unwrap_or(cursor.position..cursor.position);
+                    let text = state.soft_tab(range.start, tab_size);
+                    (*cursor_id, range, text)
                 })
                 .collect();
 
-            let insert_positions: Vec<_> = cursor_vec
-                .iter()
-                .map(|(cursor_id, cursor)| (*cursor_id, cursor.position))
-                .collect();
-
-            // Get text for deletions
-            apply_deletions(state, deletions, &mut events);
-
-            // Insert tabs
-            for (cursor_id, position) in insert_positions {
+            for (cursor_id, range, text) in edits {
+                if !range.is_empty() {
+                    apply_deletions(state, vec![(cursor_id, range.clone())], &mut events);
+                }
                 events.push(Event::Insert {
-                    position,
-                    text: tab_str.clone(),
+                    position: range.start,
+                    text,
                     cursor_id,
                 });
             }
@@ -685,12 +727,8 @@ pub fn action_to_events(
                     estimated_line_length,
                 );
 
-                // Use sticky_column if set (now stores visual column), otherwise use current visual column
-                let goal_visual_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_visual_column
-                };
+                // Use the goal column if set, otherwise the current visual column
+                let goal_visual_column = cursor.goal_column().unwrap_or(current_visual_column);
 
                 // Now create iterator for navigation
                 let mut iter = state
@@ -732,12 +770,8 @@ pub fn action_to_events(
                     estimated_line_length,
                 );
 
-                // Use sticky_column if set (now stores visual column), otherwise use current visual column
-                let goal_visual_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_visual_column
-                };
+                // Use the goal column if set, otherwise the current visual column
+                let goal_visual_column = cursor.goal_column().unwrap_or(current_visual_column);
 
                 // Now create iterator for navigation
                 let mut iter = state
@@ -829,7 +863,7 @@ pub fn action_to_events(
 
         Action::MoveWordLeft => {
             for (cursor_id, cursor) in state.cursors.iter() {
-                let new_pos = find_word_start_left(&state.buffer, cursor.position);
+                let new_pos = find_word_start_left(&state.buffer, cursor.position, &state.word_def);
                 // Preserve anchor if deselect_on_move is false (Emacs mark mode)
                 let new_anchor = if cursor.deselect_on_move {
                     None
@@ -850,7 +884,8 @@ pub fn action_to_events(
 
         Action::MoveWordRight => {
             for (cursor_id, cursor) in state.cursors.iter() {
-                let new_pos = find_word_start_right(&state.buffer, cursor.position);
+                let new_pos =
+                    find_word_start_right(&state.buffer, cursor.position, &state.word_def);
                 // Preserve anchor if deselect_on_move is false (Emacs mark mode)
                 let new_anchor = if cursor.deselect_on_move {
                     None
@@ -910,6 +945,14 @@ pub fn action_to_events(
             }
         }
 
+        Action::MoveParagraphForward => {
+            events.extend(state.move_paragraph_forward());
+        }
+
+        Action::MoveParagraphBackward => {
+            events.extend(state.move_paragraph_backward());
+        }
+
         Action::MovePageUp => {
             for (cursor_id, cursor) in state.cursors.iter() {
                 // Move up by viewport height
@@ -920,12 +963,8 @@ pub fn action_to_events(
                 let current_line_start = iter.current_position();
                 let current_column = cursor.position - current_line_start;
 
-                // Use sticky_column if set, otherwise use current column
-                let goal_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_column
-                };
+                // Use the goal column if set, otherwise the current column
+                let goal_column = cursor.goal_column().unwrap_or(current_column);
 
                 let mut new_pos = cursor.position;
                 for _ in 0..lines_to_move {
@@ -966,12 +1005,8 @@ pub fn action_to_events(
                 let current_line_start = iter.current_position();
                 let current_column = cursor.position - current_line_start;
 
-                // Use sticky_column if set, otherwise use current column
-                let goal_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_column
-                };
+                // Use the goal column if set, otherwise the current column
+                let goal_column = cursor.goal_column().unwrap_or(current_column);
 
                 // Consume current line
                 iter.next();
@@ -1054,12 +1089,8 @@ pub fn action_to_events(
                 let current_column = cursor.position - current_line_start;
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
 
-                // Use sticky_column if set, otherwise use current column
-                let goal_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_column
-                };
+                // Use the goal column if set, otherwise the current column
+                let goal_column = cursor.goal_column().unwrap_or(current_column);
 
                 if let Some((prev_line_start, prev_line_content)) = iter.prev() {
                     let prev_line_len = prev_line_content.trim_end_matches('\n').len();
@@ -1087,12 +1118,8 @@ pub fn action_to_events(
                 let current_column = cursor.position - current_line_start;
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
 
-                // Use sticky_column if set, otherwise use current column
-                let goal_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_column
-                };
+                // Use the goal column if set, otherwise the current column
+                let goal_column = cursor.goal_column().unwrap_or(current_column);
 
                 // Skip current line, then get next line
                 iter.next();
@@ -1161,7 +1188,7 @@ pub fn action_to_events(
 
         Action::SelectWordLeft => {
             for (cursor_id, cursor) in state.cursors.iter() {
-                let new_pos = find_word_start_left(&state.buffer, cursor.position);
+                let new_pos = find_word_start_left(&state.buffer, cursor.position, &state.word_def);
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
                 events.push(Event::MoveCursor {
                     cursor_id,
@@ -1177,7 +1204,8 @@ pub fn action_to_events(
 
         Action::SelectWordRight => {
             for (cursor_id, cursor) in state.cursors.iter() {
-                let new_pos = find_word_start_right(&state.buffer, cursor.position);
+                let new_pos =
+                    find_word_start_right(&state.buffer, cursor.position, &state.word_def);
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
                 events.push(Event::MoveCursor {
                     cursor_id,
@@ -1232,12 +1260,8 @@ pub fn action_to_events(
                 let current_column = cursor.position - current_line_start;
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
 
-                // Use sticky_column if set, otherwise use current column
-                let goal_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_column
-                };
+                // Use the goal column if set, otherwise the current column
+                let goal_column = cursor.goal_column().unwrap_or(current_column);
 
                 let mut new_pos = cursor.position;
                 for _ in 0..lines_to_move {
@@ -1272,12 +1296,8 @@ pub fn action_to_events(
                 let current_column = cursor.position - current_line_start;
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
 
-                // Use sticky_column if set, otherwise use current column
-                let goal_column = if cursor.sticky_column > 0 {
-                    cursor.sticky_column
-                } else {
-                    current_column
-                };
+                // Use the goal column if set, otherwise the current column
+                let goal_column = cursor.goal_column().unwrap_or(current_column);
 
                 // Consume current line
                 iter.next();
@@ -1327,10 +1347,10 @@ pub fn action_to_events(
             for (cursor_id, cursor) in state.cursors.iter() {
                 // Find word boundaries at current position
                 // First find the start of the word we're in/adjacent to
-                let word_start = find_word_start(&state.buffer, cursor.position);
+                let word_start = find_word_start(&state.buffer, cursor.position, &state.word_def);
                 // Then find the end of that word (from the start, not from cursor)
                 // This ensures we select the current word, not the next one
-                let word_end = find_word_end(&state.buffer, word_start);
+                let word_end = find_word_end(&state.buffer, word_start, &state.word_def);
 
                 if word_start < word_end {
                     events.push(Event::MoveCursor {
@@ -1357,6 +1377,11 @@ pub fn action_to_events(
                 .filter_map(|(cursor_id, cursor)| {
                     if let Some(range) = cursor.selection_range() {
                         Some((*cursor_id, range))
+                    } else if let Some(start) = backspace_indent
+                        .and_then(|indent| state.soft_tab_backspace_start(cursor.position, indent))
+                    {
+                        // Only spaces before the cursor: remove a whole soft tab
+                        Some((*cursor_id, start..cursor.position))
                     } else if cursor.position > 0 {
                         // Use prev_char_boundary to properly handle multi-byte UTF-8 characters
                         let delete_from = state.buffer.prev_char_boundary(cursor.position);
@@ -1441,7 +1466,8 @@ pub fn action_to_events(
                     if let Some(range) = cursor.selection_range() {
                         Some((cursor_id, range))
                     } else {
-                        let word_start = find_word_start_left(&state.buffer, cursor.position);
+                        let word_start =
+                            find_word_start_left(&state.buffer, cursor.position, &state.word_def);
                         if word_start < cursor.position {
                             Some((cursor_id, word_start..cursor.position))
                         } else {
@@ -1464,7 +1490,8 @@ pub fn action_to_events(
                     if let Some(range) = cursor.selection_range() {
                         Some((cursor_id, range))
                     } else {
-                        let word_end = find_word_start_right(&state.buffer, cursor.position);
+                        let word_end =
+                            find_word_start_right(&state.buffer, cursor.position, &state.word_def);
                         if cursor.position < word_end {
                             Some((cursor_id, cursor.position..word_end))
                         } else {
@@ -1484,14 +1511,10 @@ pub fn action_to_events(
                 .cursors
                 .iter()
                 .filter_map(|(cursor_id, cursor)| {
-                    let mut iter = state
+                    let range = state
                         .buffer
-                        .line_iterator(cursor.position, estimated_line_length);
-                    let line_start = iter.current_position();
-                    iter.next().map(|(_start, content)| {
-                        let line_end = line_start + content.len();
-                        (cursor_id, line_start..line_end)
-                    })
+                        .line_deletion_range(cursor.position, estimated_line_length)?;
+                    Some((cursor_id, range))
                 })
                 .collect();
 
@@ -1531,35 +1554,14 @@ pub fn action_to_events(
         }
 
         Action::TransposeChars => {
-            // Transpose the character before the cursor with the one at the cursor
-            // Collect cursor positions first to avoid borrow issues
-            let cursor_positions: Vec<_> = state
-                .cursors
-                .iter()
-                .map(|(id, c)| (id, c.position))
-                .collect();
+            if let Some(event) = state.transpose_chars() {
+                events.push(event);
+            }
+        }
 
-            for (cursor_id, pos) in cursor_positions {
-                // Need at least 2 characters: one before and one at cursor
-                if pos > 0 && pos < state.buffer.len() {
-                    // Get the two characters as a string
-                    let text = state.get_text_range(pos - 1, pos + 1);
-                    let chars: Vec<char> = text.chars().collect();
-                    if chars.len() >= 2 {
-                        // Delete both characters and insert them swapped
-                        events.push(Event::Delete {
-                            range: (pos - 1)..(pos + 1),
-                            deleted_text: text,
-                            cursor_id,
-                        });
-                        let swapped = format!("{}{}", chars[1], chars[0]);
-                        events.push(Event::Insert {
-                            position: pos - 1,
-                            text: swapped,
-                            cursor_id,
-                        });
-                    }
-                }
+        Action::TransposeWords => {
+            if let Some(event) = state.transpose_words() {
+                events.push(event);
             }
         }
 
@@ -1648,6 +1650,10 @@ pub fn action_to_events(
         | Action::Copy
         | Action::Cut
         | Action::Paste
+        | Action::PastePrevious
+        | Action::PasteReindent
+        | Action::CopyToRegister(_)
+        | Action::PasteFromRegister(_)
         | Action::AddCursorNextMatch
         | Action::AddCursorAbove
         | Action::AddCursorBelow
@@ -1662,6 +1668,7 @@ pub fn action_to_events(
         | Action::Undo
         | Action::Redo
         | Action::GoToMatchingBracket
+        | Action::SelectToMatchingBracket
         | Action::JumpToNextError
         | Action::JumpToPreviousError
         | Action::ShowKeyboardShortcuts
@@ -1669,10 +1676,18 @@ pub fn action_to_events(
         | Action::IndentSelection
         | Action::DedentSelection
         | Action::ToggleComment
+        | Action::UpperCase
+        | Action::LowerCase
+        | Action::TitleCase
+        | Action::ToggleCase
+        | Action::JoinLines
+        | Action::SortLines(_)
+        | Action::ToggleOverwrite
         | Action::SetBookmark(_)
         | Action::JumpToBookmark(_)
         | Action::ClearBookmark(_)
         | Action::ListBookmarks
+        | Action::DocumentStatistics
         | Action::ToggleSearchCaseSensitive
         | Action::ToggleSearchWholeWord
         | Action::ToggleSearchRegex
@@ -1756,6 +1771,7 @@ pub fn action_to_events(
         | Action::ToggleMouseCapture
         | Action::DumpConfig
         | Action::Search
+        | Action::IncrementalSearch
         | Action::FindInSelection
         | Action::FindNext
         | Action::FindPrevious
@@ -1812,6 +1828,10 @@ pub fn action_to_events(
             block_select_action(state, &mut events, BlockDirection::Down);
         }
 
+        Action::SelectParagraph => {
+            events.extend(state.select_paragraph());
+        }
+
         Action::SelectLine => {
             // Select the entire line for each cursor
             for (cursor_id, cursor) in state.cursors.iter() {
@@ -1842,8 +1862,9 @@ pub fn action_to_events(
                 if let Some(anchor) = cursor.anchor {
                     // Already have a selection - expand by one word to the right
                     // First move to the start of the next word, then to its end
-                    let next_word_start = find_word_start_right(&state.buffer, cursor.position);
-                    let new_end = find_word_end(&state.buffer, next_word_start);
+                    let next_word_start =
+                        find_word_start_right(&state.buffer, cursor.position, &state.word_def);
+                    let new_end = find_word_end(&state.buffer, next_word_start, &state.word_def);
                     events.push(Event::MoveCursor {
                         cursor_id,
                         old_position: cursor.position,
@@ -1855,22 +1876,25 @@ pub fn action_to_events(
                     });
                 } else {
                     // No selection - select from cursor to end of current word
-                    let word_start = find_word_start(&state.buffer, cursor.position);
-                    let word_end = find_word_end(&state.buffer, cursor.position);
+                    let word_start =
+                        find_word_start(&state.buffer, cursor.position, &state.word_def);
+                    let word_end = find_word_end(&state.buffer, cursor.position, &state.word_def);
 
                     // If cursor is on non-word char OR at the end of a word,
                     // select from current position to end of next word
-                    let (final_start, final_end) =
-                        if word_start == word_end || cursor.position == word_end {
-                            // Find the next word (skip non-word characters to find it)
-                            let next_start = find_word_start_right(&state.buffer, cursor.position);
-                            let next_end = find_word_end(&state.buffer, next_start);
-                            // Select FROM cursor position TO the end of next word
-                            (cursor.position, next_end)
-                        } else {
-                            // On a word char - select from cursor to end of current word
-                            (cursor.position, word_end)
-                        };
+                    let (final_start, final_end) = if word_start == word_end
+                        || cursor.position == word_end
+                    {
+                        // Find the next word (skip non-word characters to find it)
+                        let next_start =
+                            find_word_start_right(&state.buffer, cursor.position, &state.word_def);
+                        let next_end = find_word_end(&state.buffer, next_start, &state.word_def);
+                        // Select FROM cursor position TO the end of next word
+                        (cursor.position, next_end)
+                    } else {
+                        // On a word char - select from cursor to end of current word
+                        (cursor.position, word_end)
+                    };
 
                     events.push(Event::MoveCursor {
                         cursor_id,
@@ -1925,7 +1949,7 @@ mod tests {
 
         // Press Backspace - should delete the newline at position 5
         let events =
-            action_to_events(&mut state, Action::DeleteBackward, 4, false, 80, 24).unwrap();
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, false, 80, 24).unwrap();
         println!("Generated events: {:?}", events);
 
         for event in events {
@@ -1962,7 +1986,8 @@ mod tests {
         assert_eq!(state.cursors.primary().position, 0);
 
         // Move down - should go to position 6 (start of Line2)
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor { new_position, .. } = &events[0] {
@@ -1975,7 +2000,8 @@ mod tests {
         assert_eq!(state.cursors.primary().position, 6);
 
         // Move down again - should go to position 12 (start of Line3)
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor { new_position, .. } = &events[0] {
@@ -2007,7 +2033,7 @@ mod tests {
         // Should go to end of Line2 (position 11, which is the newline, BUT we want column 5 which is position 11)
         // Wait, Line2 has content "Line2" (5 chars), so column 5 is position 6+5=11 (the newline)
         // This is technically correct but weird - we're on the newline
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor { new_position, .. } = &events[0] {
@@ -2028,7 +2054,7 @@ mod tests {
         // Current line is Line2 (starts at 6), column is 11-6=5
         // Previous line is Line1 (starts at 0), content "Line1" has length 5
         // So we go to position 0 + min(5, 5) = 5 (the newline after Line1)
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor { new_position, .. } = &events[0] {
@@ -2067,7 +2093,8 @@ mod tests {
         assert_eq!(state.cursors.primary().position, 3);
 
         // Move down - should go to position 9 (column 3 of second line, which is end of "123")
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor {
@@ -2091,7 +2118,8 @@ mod tests {
         state.apply(&events[0]);
 
         // Move down again - should go to position 13 (column 3 of third line)
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor {
@@ -2133,7 +2161,7 @@ mod tests {
         assert_eq!(state.cursors.primary().position, 13);
 
         // Move up - should go to position 9 (column 3 of second line, which is end of "123")
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor {
@@ -2157,7 +2185,7 @@ mod tests {
         state.apply(&events[0]);
 
         // Move up again - should go to position 3 (column 3 of first line)
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor {
@@ -2197,7 +2225,8 @@ mod tests {
         });
 
         // Move down - should go to position 6 (start of second line)
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor { new_position, .. } = &events[0] {
@@ -2231,7 +2260,7 @@ mod tests {
         });
 
         // Move up - should go to position 0 (start of first line)
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         assert_eq!(events.len(), 1);
 
         if let Event::MoveCursor { new_position, .. } = &events[0] {
@@ -2268,7 +2297,8 @@ mod tests {
         });
 
         // Move down - should go to position 6 (empty line)
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         if let Event::MoveCursor { new_position, .. } = &events[0] {
             assert_eq!(*new_position, 6, "Cursor should move to empty line");
         }
@@ -2276,7 +2306,8 @@ mod tests {
         state.apply(&events[0]);
 
         // Move down again - should go to position 7 (start of Line3)
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         if let Event::MoveCursor { new_position, .. } = &events[0] {
             assert_eq!(*new_position, 7, "Cursor should move to Line3");
         }
@@ -2306,7 +2337,7 @@ mod tests {
         });
 
         // Try to move up (no previous line exists)
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         assert_eq!(
             events.len(),
             0,
@@ -2314,7 +2345,8 @@ mod tests {
         );
 
         // Try to move down (no next line exists)
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         assert_eq!(
             events.len(),
             0,
@@ -2417,7 +2449,8 @@ mod tests {
         });
 
         // Move to line end
-        let events = action_to_events(&mut state, Action::MoveLineEnd, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveLineEnd, 4, None, false, 80, 24).unwrap();
         for event in events {
             println!("MoveLineEnd event: {:?}", event);
             state.apply(&event);
@@ -2460,7 +2493,8 @@ mod tests {
         );
 
         // Move to line start
-        let events = action_to_events(&mut state, Action::MoveLineStart, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveLineStart, 4, None, false, 80, 24).unwrap();
         for event in events {
             println!("MoveLineStart event from EOF: {:?}", event);
             state.apply(&event);
@@ -2523,7 +2557,7 @@ mod tests {
         );
 
         // Try to move up - this should work even if chunks aren't loaded
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         println!("MoveUp events: {:?}", events);
 
         assert!(
@@ -2587,7 +2621,8 @@ mod tests {
         );
 
         // Move down to second line
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         println!("MoveDown events: {:?}", events);
 
         if events.is_empty() {
@@ -2629,7 +2664,7 @@ mod tests {
         assert_eq!(state.cursors.primary().position, 20); // End of text
 
         // Move up to first line
-        let events = action_to_events(&mut state, Action::MoveUp, 4, false, 80, 24).unwrap();
+        let events = action_to_events(&mut state, Action::MoveUp, 4, None, false, 80, 24).unwrap();
         for event in events {
             state.apply(&event);
         }
@@ -2639,7 +2674,8 @@ mod tests {
         );
 
         // Move to end of first line
-        let events = action_to_events(&mut state, Action::MoveLineEnd, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveLineEnd, 4, None, false, 80, 24).unwrap();
         for event in events {
             state.apply(&event);
         }
@@ -2650,7 +2686,8 @@ mod tests {
         );
 
         // Move down to second line
-        let events = action_to_events(&mut state, Action::MoveDown, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveDown, 4, None, false, 80, 24).unwrap();
         for event in events {
             state.apply(&event);
         }
@@ -2660,7 +2697,8 @@ mod tests {
         );
 
         // Move to start of line (Home)
-        let events = action_to_events(&mut state, Action::MoveLineStart, 4, false, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::MoveLineStart, 4, None, false, 80, 24).unwrap();
         for event in events {
             state.apply(&event);
         }
@@ -2673,7 +2711,7 @@ mod tests {
 
         // Delete backward (should delete the newline)
         let events =
-            action_to_events(&mut state, Action::DeleteBackward, 4, false, 80, 24).unwrap();
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, false, 80, 24).unwrap();
         for event in events.iter() {
             println!("Event: {:?}", event);
             state.apply(event);
@@ -2709,7 +2747,7 @@ mod tests {
 
         // Insert opening parenthesis with auto_indent=true
         let events =
-            action_to_events(&mut state, Action::InsertChar('('), 4, true, 80, 24).unwrap();
+            action_to_events(&mut state, Action::InsertChar('('), 4, None, true, 80, 24).unwrap();
         println!("Events: {:?}", events);
 
         // Should have Insert event for "()" and MoveCursor to position between them
@@ -2728,6 +2766,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_typing_bracket_over_selection_wraps_it() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        state.buffer = Buffer::from_str_test("call x now");
+        state.cursors.primary_mut().position = 5;
+        state.cursors.primary_mut().anchor = Some(6);
+
+        let events =
+            action_to_events(&mut state, Action::InsertChar('('), 4, None, true, 80, 24).unwrap();
+        for event in events {
+            state.apply(&event);
+        }
+        assert_eq!(state.buffer.to_string().unwrap(), "call (x) now");
+        assert_eq!(state.cursors.primary().selection_range(), Some(6..7));
+
+        // Other characters still replace the selection
+        let events =
+            action_to_events(&mut state, Action::InsertChar('y'), 4, None, true, 80, 24).unwrap();
+        for event in events {
+            state.apply(&event);
+        }
+        assert_eq!(state.buffer.to_string().unwrap(), "call (y) now");
+    }
+
+    #[test]
+    fn test_tab_and_backspace_use_tab_stops() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        state.buffer = Buffer::from_str_test("ab");
+        state.cursors.primary_mut().position = 2;
+
+        let press = |state: &mut EditorState, action: Action| {
+            for event in
+                action_to_events(state, action, 4, Some(IndentStyle::Spaces(4)), true, 80, 24)
+                    .unwrap()
+            {
+                state.apply(&event);
+            }
+        };
+        press(&mut state, Action::InsertTab);
+        assert_eq!(state.buffer.to_string().unwrap(), "ab  ");
+
+        state.buffer = Buffer::from_str_test("x");
+        state.cursors.primary_mut().position = 0;
+        press(&mut state, Action::InsertTab);
+        press(&mut state, Action::InsertTab);
+        press(&mut state, Action::InsertChar(' '));
+        assert_eq!(state.buffer.to_string().unwrap(), "         x");
+
+        // Backspace in the indentation removes back to the previous tab stop
+        press(&mut state, Action::DeleteBackward);
+        assert_eq!(state.buffer.to_string().unwrap(), "        x");
+        press(&mut state, Action::DeleteBackward);
+        assert_eq!(state.buffer.to_string().unwrap(), "    x");
+        assert_eq!(state.cursors.primary().position, 4);
+    }
+
+    #[test]
+    fn test_backspace_removes_one_space_unless_indenting_with_spaces() {
+        for indent in [None, Some(IndentStyle::Tabs)] {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("        x");
+            state.cursors.primary_mut().position = 8;
+
+            let events =
+                action_to_events(&mut state, Action::DeleteBackward, 4, indent, true, 80, 24)
+                    .unwrap();
+            for event in events {
+                state.apply(&event);
+            }
+            assert_eq!(state.buffer.to_string().unwrap(), "       x", "{indent:?}");
+        }
+    }
     #[test]
     fn test_bracket_auto_close_curly_brace() {
         let mut state =
@@ -2735,7 +2848,7 @@ mod tests {
 
         // Insert opening curly brace with auto_indent=true
         let events =
-            action_to_events(&mut state, Action::InsertChar('{'), 4, true, 80, 24).unwrap();
+            action_to_events(&mut state, Action::InsertChar('{'), 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2756,7 +2869,7 @@ mod tests {
 
         // Insert opening square bracket
         let events =
-            action_to_events(&mut state, Action::InsertChar('['), 4, true, 80, 24).unwrap();
+            action_to_events(&mut state, Action::InsertChar('['), 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2773,7 +2886,7 @@ mod tests {
 
         // Insert double quote
         let events =
-            action_to_events(&mut state, Action::InsertChar('"'), 4, true, 80, 24).unwrap();
+            action_to_events(&mut state, Action::InsertChar('"'), 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2790,7 +2903,7 @@ mod tests {
 
         // Insert opening parenthesis with auto_indent=false
         let events =
-            action_to_events(&mut state, Action::InsertChar('('), 4, false, 80, 24).unwrap();
+            action_to_events(&mut state, Action::InsertChar('('), 4, None, false, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2826,7 +2939,7 @@ mod tests {
 
         // Insert opening parenthesis before 'abc'
         let events =
-            action_to_events(&mut state, Action::InsertChar('('), 4, true, 80, 24).unwrap();
+            action_to_events(&mut state, Action::InsertChar('('), 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2837,6 +2950,31 @@ mod tests {
         assert_eq!(state.cursors.primary().position, 1);
     }
 
+    #[test]
+    fn test_delete_line_with_cursors_on_last_two_lines() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "a\nb".to_string(),
+            cursor_id: CursorId(0),
+        });
+        state.apply(&Event::AddCursor {
+            position: 0,
+            cursor_id: CursorId(1),
+            anchor: None,
+        });
+
+        // "a\n" and "\nb" share the line break; it is deleted once
+        let events =
+            action_to_events(&mut state, Action::DeleteLine, 4, None, false, 80, 24).unwrap();
+        assert_eq!(events.len(), 1);
+        for event in &events {
+            state.apply(event);
+        }
+        assert_eq!(state.buffer.to_string().unwrap(), "");
+    }
+
     #[test]
     fn test_bracket_auto_close_multiple_cursors() {
         let mut state =
@@ -2879,7 +3017,7 @@ mod tests {
 
         // Insert opening parenthesis at both cursors
         let events =
-            action_to_events(&mut state, Action::InsertChar('('), 4, true, 80, 24).unwrap();
+            action_to_events(&mut state, Action::InsertChar('('), 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2916,7 +3054,8 @@ mod tests {
         assert_eq!(state.cursors.primary().position, 1);
 
         // Delete backward with auto_indent=true - should delete both characters
-        let events = action_to_events(&mut state, Action::DeleteBackward, 4, true, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2950,7 +3089,8 @@ mod tests {
         });
 
         // Delete backward - should delete both
-        let events = action_to_events(&mut state, Action::DeleteBackward, 4, true, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -2983,7 +3123,8 @@ mod tests {
         });
 
         // Delete backward - should delete both quotes
-        let events = action_to_events(&mut state, Action::DeleteBackward, 4, true, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -3017,7 +3158,7 @@ mod tests {
 
         // Delete backward with auto_indent=false - should only delete opening bracket
         let events =
-            action_to_events(&mut state, Action::DeleteBackward, 4, false, 80, 24).unwrap();
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, false, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -3051,7 +3192,8 @@ mod tests {
         });
 
         // Delete backward - should only delete opening bracket since they don't match
-        let events = action_to_events(&mut state, Action::DeleteBackward, 4, true, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -3085,7 +3227,8 @@ mod tests {
         });
 
         // Delete backward - should only delete 'a', not both brackets
-        let events = action_to_events(&mut state, Action::DeleteBackward, 4, true, 80, 24).unwrap();
+        let events =
+            action_to_events(&mut state, Action::DeleteBackward, 4, None, true, 80, 24).unwrap();
 
         for event in events {
             state.apply(&event);
@@ -3093,4 +3236,49 @@ mod tests {
 
         assert_eq!(state.buffer.to_string().unwrap(), "(bc)");
     }
+
+    /// Type `chars` one at a time at `position` in `text`, in insert or overtype mode
+    fn type_at(text: &str, position: usize, chars: &str, overwrite: bool) -> (String, usize) {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        state.buffer = crate::model::buffer::Buffer::from_str_test(text);
+        state.cursors.primary_mut().position = position;
+        state.overwrite = overwrite;
+        for ch in chars.chars() {
+            let events =
+                action_to_events(&mut state, Action::InsertChar(ch), 4, None, false, 80, 24)
+                    .unwrap();
+            for event in events {
+                state.apply(&event);
+            }
+        }
+        (
+            state.buffer.to_string().unwrap(),
+            state.cursors.primary().position,
+        )
+    }
+
+    #[test]
+    fn test_overwrite_vs_insert() {
+        assert_eq!(
+            type_at("hello world", 6, "W", false),
+            ("hello Wworld".into(), 7)
+        );
+        assert_eq!(
+            type_at("hello world", 6, "W", true),
+            ("hello World".into(), 7)
+        );
+
+        // Multi-byte characters are replaced whole, by multi-byte ones too
+        assert_eq!(type_at("a日本b", 1, "xé", true), ("axéb".into(), 4));
+        assert_eq!(type_at("abc", 1, "日", true), ("a日c".into(), 4));
+    }
+
+    #[test]
+    fn test_overwrite_stops_at_line_end() {
+        // The line break is never overwritten, typing past it inserts
+        assert_eq!(type_at("ab\ncd", 1, "xyz", true), ("axyz\ncd".into(), 4));
+        assert_eq!(type_at("ab\r\ncd", 2, "!", true), ("ab!\r\ncd".into(), 3));
+        assert_eq!(type_at("ab", 2, "c", true), ("abc".into(), 3));
+    }
 }
@@ -7,6 +7,7 @@ use crate::input::commands::{get_all_commands, Command, Suggestion};
 use crate::input::fuzzy::fuzzy_match;
 use crate::input::keybindings::Action;
 use crate::input::keybindings::KeyContext;
+use std::collections::HashSet;
 use std::sync::{Arc, RwLock};
 
 /// Registry for managing editor commands
@@ -20,6 +21,12 @@ pub struct CommandRegistry {
     /// Plugin-registered commands (dynamically added/removed)
     plugin_commands: Arc<RwLock<Vec<Command>>>,
 
+    /// Actions run by the built-in commands
+    builtin_actions: HashSet<Action>,
+
+    /// Actions run by the plugin commands, rebuilt when they change
+    plugin_actions: RwLock<HashSet<Action>>,
+
     /// Command usage history (most recent first)
     /// Used to sort command palette suggestions by recency
     command_history: Vec<String>,
@@ -31,9 +38,12 @@ impl CommandRegistry {
 
     /// Create a new command registry with built-in commands
     pub fn new() -> Self {
+        let builtin_commands = get_all_commands();
         Self {
-            builtin_commands: get_all_commands(),
+            builtin_actions: builtin_commands.iter().map(|c| c.action.clone()).collect(),
+            builtin_commands,
             plugin_commands: Arc::new(RwLock::new(Vec::new())),
+            plugin_actions: RwLock::new(HashSet::new()),
             command_history: Vec::new(),
         }
     }
@@ -75,18 +85,25 @@ impl CommandRegistry {
 
         // Add new command
         commands.push(command);
+        self.update_plugin_actions(&commands);
     }
 
     /// Unregister a command by name
     pub fn unregister(&self, name: &str) {
         let mut commands = self.plugin_commands.write().unwrap();
         commands.retain(|c| c.name != name);
+        self.update_plugin_actions(&commands);
     }
 
     /// Unregister all commands registered by a specific plugin
     pub fn unregister_by_prefix(&self, prefix: &str) {
         let mut commands = self.plugin_commands.write().unwrap();
         commands.retain(|c| !c.name.starts_with(prefix));
+        self.update_plugin_actions(&commands);
+    }
+
+    fn update_plugin_actions(&self, commands: &[Command]) {
+        *self.plugin_actions.write().unwrap() = commands.iter().map(|c| c.action.clone()).collect();
     }
 
     /// Get all commands (built-in + plugin)
@@ -201,6 +218,15 @@ impl CommandRegistry {
         self.builtin_commands.len() + self.plugin_command_count()
     }
 
+    /// Whether a built-in or plugin command runs this action
+    ///
+    /// Checked for every key that resolves to an action, so both lookups are
+    /// set lookups rather than scans of the commands.
+    pub fn has_action(&self, action: &Action) -> bool {
+        self.builtin_actions.contains(action)
+            || self.plugin_actions.read().unwrap().contains(action)
+    }
+
     /// Find a command by exact name match
     pub fn find_by_name(&self, name: &str) -> Option<Command> {
         // Check plugin commands first (they can override built-in)
@@ -279,6 +305,28 @@ mod tests {
         assert_eq!(registry.plugin_command_count(), 0);
     }
 
+    #[test]
+    fn test_has_action_follows_plugin_commands() {
+        let registry = CommandRegistry::new();
+        let plugin_action = Action::PluginAction("git_blame".to_string());
+        assert!(registry.has_action(&Action::Save));
+        assert!(!registry.has_action(&Action::MoveRight));
+        assert!(!registry.has_action(&plugin_action));
+
+        registry.register(Command {
+            name: "Git Blame".to_string(),
+            description: "Show blame".to_string(),
+            action: plugin_action.clone(),
+            contexts: vec![],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        });
+        assert!(registry.has_action(&plugin_action));
+
+        registry.unregister("Git Blame");
+        assert!(!registry.has_action(&plugin_action));
+    }
+
     #[test]
     fn test_register_replaces_existing() {
         let registry = CommandRegistry::new();
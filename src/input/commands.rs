@@ -1,6 +1,7 @@
 //! Command palette system for executing editor actions by name
 
 use crate::input::keybindings::{Action, KeyContext};
+use crate::primitives::line_sort::SortOptions;
 
 /// Source of a command (builtin or from a plugin)
 #[derive(Debug, Clone, PartialEq)]
@@ -247,6 +248,23 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Paste Previous".to_string(),
+            description: "Replace the text just pasted with the previous clipboard entry"
+                .to_string(),
+            action: Action::PastePrevious,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Paste and Re-indent".to_string(),
+            description: "Paste from clipboard, indented to match the current line".to_string(),
+            action: Action::PasteReindent,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Delete Line".to_string(),
             description: "Delete the current line".to_string(),
@@ -287,6 +305,14 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Transpose Words".to_string(),
+            description: "Swap the word at or before cursor with the next one".to_string(),
+            action: Action::TransposeWords,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Open Line".to_string(),
             description: "Insert newline at cursor without moving cursor".to_string(),
@@ -336,6 +362,14 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Select Paragraph".to_string(),
+            description: "Select the paragraph around the cursor".to_string(),
+            action: Action::SelectParagraph,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Expand Selection".to_string(),
             description: "Expand the current selection by one word".to_string(),
@@ -646,6 +680,14 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Incremental Search".to_string(),
+            description: "Jump to matches as you type the search query".to_string(),
+            action: Action::IncrementalSearch,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Find in Selection".to_string(),
             description: "Search only within the current selection".to_string(),
@@ -801,6 +843,115 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Upper Case".to_string(),
+            description: "Convert the selection or word under the cursor to UPPER CASE".to_string(),
+            action: Action::UpperCase,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Lower Case".to_string(),
+            description: "Convert the selection or word under the cursor to lower case".to_string(),
+            action: Action::LowerCase,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Title Case".to_string(),
+            description: "Capitalize each word of the selection or word under the cursor"
+                .to_string(),
+            action: Action::TitleCase,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Toggle Case".to_string(),
+            description: "Swap upper and lower case in the selection or word under the cursor"
+                .to_string(),
+            action: Action::ToggleCase,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Join Lines".to_string(),
+            description:
+                "Join the next line onto the current one, collapsing the whitespace between"
+                    .to_string(),
+            action: Action::JoinLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines".to_string(),
+            description: "Sort the selected lines, or the whole buffer, alphabetically".to_string(),
+            action: Action::SortLines(SortOptions::default()),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Descending".to_string(),
+            description: "Sort the selected lines, or the whole buffer, in reverse order"
+                .to_string(),
+            action: Action::SortLines(SortOptions {
+                reverse: true,
+                ..Default::default()
+            }),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Ignoring Case".to_string(),
+            description: "Sort the selected lines, or the whole buffer, ignoring letter case"
+                .to_string(),
+            action: Action::SortLines(SortOptions {
+                case_insensitive: true,
+                ..Default::default()
+            }),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Numerically".to_string(),
+            description: "Sort the selected lines, or the whole buffer, by their leading numbers"
+                .to_string(),
+            action: Action::SortLines(SortOptions {
+                numeric: true,
+                ..Default::default()
+            }),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Sort Lines Removing Duplicates".to_string(),
+            description: "Sort the selected lines, or the whole buffer, keeping one of each"
+                .to_string(),
+            action: Action::SortLines(SortOptions {
+                unique: true,
+                ..Default::default()
+            }),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Toggle Overwrite Mode".to_string(),
+            description: "Switch between inserting typed text and typing over existing text"
+                .to_string(),
+            action: Action::ToggleOverwrite,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Indent Selection".to_string(),
             description: "Increase indentation of selected lines".to_string(),
@@ -817,6 +968,22 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Next Paragraph".to_string(),
+            description: "Move to the blank line after the current paragraph".to_string(),
+            action: Action::MoveParagraphForward,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Previous Paragraph".to_string(),
+            description: "Move to the blank line before the current paragraph".to_string(),
+            action: Action::MoveParagraphBackward,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Go to Matching Bracket".to_string(),
             description: "Jump to the matching bracket, parenthesis, or brace".to_string(),
@@ -825,6 +992,15 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Select to Matching Bracket".to_string(),
+            description: "Select from the bracket at the cursor to its matching bracket"
+                .to_string(),
+            action: Action::SelectToMatchingBracket,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Error navigation
         Command {
             name: "Jump to Next Error".to_string(),
@@ -851,6 +1027,15 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Document Statistics".to_string(),
+            description: "Count lines, words and characters in the buffer and selection"
+                .to_string(),
+            action: Action::DocumentStatistics,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Bookmarks and Macros
         Command {
             name: "List Bookmarks".to_string(),
@@ -886,7 +1071,8 @@ pub fn get_all_commands() -> Vec<Command> {
         },
         Command {
             name: "Play Macro".to_string(),
-            description: "Play macro from a register (0-9)".to_string(),
+            description: "Play macro from a register (0-9), optionally followed by a repeat count"
+                .to_string(),
             action: Action::PromptPlayMacro,
             contexts: vec![KeyContext::Normal],
             custom_contexts: vec![],
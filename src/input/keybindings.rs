@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::primitives::line_sort::SortOptions;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -51,6 +52,7 @@ pub fn format_keybinding(keycode: &KeyCode, modifiers: &KeyModifiers) -> String
         KeyCode::Enter => result.push_str("Enter"),
         KeyCode::Backspace => result.push_str("Backspace"),
         KeyCode::Delete => result.push_str("Del"),
+        KeyCode::Insert => result.push_str("Ins"),
         KeyCode::Tab => result.push_str("Tab"),
         KeyCode::Esc => result.push_str("Esc"),
         KeyCode::Left => result.push_str("←"),
@@ -155,6 +157,8 @@ pub enum KeyContext {
     Normal,
     /// Prompt/minibuffer is active
     Prompt,
+    /// Incremental search prompt is active (falls back to [`KeyContext::Prompt`])
+    IncrementalSearch,
     /// Popup window is visible
     Popup,
     /// File explorer has focus
@@ -178,6 +182,7 @@ impl KeyContext {
         match when.trim() {
             "global" => Some(KeyContext::Global),
             "prompt" => Some(KeyContext::Prompt),
+            "incrementalSearch" | "incremental_search" => Some(KeyContext::IncrementalSearch),
             "popup" => Some(KeyContext::Popup),
             "fileExplorer" | "file_explorer" => Some(KeyContext::FileExplorer),
             "normal" => Some(KeyContext::Normal),
@@ -194,6 +199,7 @@ impl KeyContext {
             KeyContext::Global => "global",
             KeyContext::Normal => "normal",
             KeyContext::Prompt => "prompt",
+            KeyContext::IncrementalSearch => "incrementalSearch",
             KeyContext::Popup => "popup",
             KeyContext::FileExplorer => "fileExplorer",
             KeyContext::Menu => "menu",
@@ -201,10 +207,18 @@ impl KeyContext {
             KeyContext::Settings => "settings",
         }
     }
+
+    /// The context whose bindings apply to keys this one doesn't bind
+    pub fn parent(self) -> Option<Self> {
+        match self {
+            KeyContext::IncrementalSearch => Some(KeyContext::Prompt),
+            _ => None,
+        }
+    }
 }
 
 /// High-level actions that can be performed in the editor
-#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Action {
     // Character input
     InsertChar(char),
@@ -224,6 +238,8 @@ pub enum Action {
     MovePageDown,
     MoveDocumentStart,
     MoveDocumentEnd,
+    MoveParagraphForward,
+    MoveParagraphBackward,
 
     // Selection movement (extends selection while moving)
     SelectLeft,
@@ -241,6 +257,7 @@ pub enum Action {
     SelectAll,
     SelectWord,
     SelectLine,
+    SelectParagraph,
     ExpandSelection,
 
     // Block/rectangular selection (column-wise)
@@ -257,6 +274,7 @@ pub enum Action {
     DeleteLine,
     DeleteToLineEnd,
     TransposeChars,
+    TransposeWords,
     OpenLine,
 
     // View
@@ -269,6 +287,10 @@ pub enum Action {
     Copy,
     Cut,
     Paste,
+    PastePrevious,
+    PasteReindent,
+    CopyToRegister(char),
+    PasteFromRegister(char),
 
     // Multi-cursor
     AddCursorAbove,
@@ -291,6 +313,7 @@ pub enum Action {
     // Navigation
     GotoLine,
     GoToMatchingBracket,
+    SelectToMatchingBracket,
     JumpToNextError,
     JumpToPreviousError,
 
@@ -299,6 +322,14 @@ pub enum Action {
     IndentSelection,
     DedentSelection,
     ToggleComment,
+    UpperCase,
+    LowerCase,
+    TitleCase,
+    ToggleCase,
+    JoinLines,
+    SortLines(SortOptions),
+    ToggleOverwrite,
+    DocumentStatistics,
 
     // Bookmarks
     SetBookmark(char),
@@ -450,6 +481,7 @@ pub enum Action {
 
     // Search and replace
     Search,
+    IncrementalSearch,
     FindInSelection,
     FindNext,
     FindPrevious,
@@ -496,6 +528,267 @@ pub enum Action {
     None,
 }
 
+/// Actions configured by name alone, without arguments, as [`Action::to_config`] names them
+const PLAIN_ACTIONS: &[(&str, Action)] = &[
+    ("insert_newline", Action::InsertNewline),
+    ("insert_tab", Action::InsertTab),
+    ("move_left", Action::MoveLeft),
+    ("move_right", Action::MoveRight),
+    ("move_up", Action::MoveUp),
+    ("move_down", Action::MoveDown),
+    ("move_word_left", Action::MoveWordLeft),
+    ("move_word_right", Action::MoveWordRight),
+    ("move_line_start", Action::MoveLineStart),
+    ("move_line_end", Action::MoveLineEnd),
+    ("move_page_up", Action::MovePageUp),
+    ("move_page_down", Action::MovePageDown),
+    ("move_document_start", Action::MoveDocumentStart),
+    ("move_document_end", Action::MoveDocumentEnd),
+    ("move_paragraph_forward", Action::MoveParagraphForward),
+    ("move_paragraph_backward", Action::MoveParagraphBackward),
+    ("select_left", Action::SelectLeft),
+    ("select_right", Action::SelectRight),
+    ("select_up", Action::SelectUp),
+    ("select_down", Action::SelectDown),
+    ("select_word_left", Action::SelectWordLeft),
+    ("select_word_right", Action::SelectWordRight),
+    ("select_line_start", Action::SelectLineStart),
+    ("select_line_end", Action::SelectLineEnd),
+    ("select_document_start", Action::SelectDocumentStart),
+    ("select_document_end", Action::SelectDocumentEnd),
+    ("select_page_up", Action::SelectPageUp),
+    ("select_page_down", Action::SelectPageDown),
+    ("select_all", Action::SelectAll),
+    ("select_word", Action::SelectWord),
+    ("select_line", Action::SelectLine),
+    ("select_paragraph", Action::SelectParagraph),
+    ("expand_selection", Action::ExpandSelection),
+    // Block/rectangular selection
+    ("block_select_left", Action::BlockSelectLeft),
+    ("block_select_right", Action::BlockSelectRight),
+    ("block_select_up", Action::BlockSelectUp),
+    ("block_select_down", Action::BlockSelectDown),
+    ("delete_backward", Action::DeleteBackward),
+    ("delete_forward", Action::DeleteForward),
+    ("delete_word_backward", Action::DeleteWordBackward),
+    ("delete_word_forward", Action::DeleteWordForward),
+    ("delete_line", Action::DeleteLine),
+    ("delete_to_line_end", Action::DeleteToLineEnd),
+    ("transpose_chars", Action::TransposeChars),
+    ("transpose_words", Action::TransposeWords),
+    ("open_line", Action::OpenLine),
+    ("recenter", Action::Recenter),
+    ("set_mark", Action::SetMark),
+    ("copy", Action::Copy),
+    ("cut", Action::Cut),
+    ("paste", Action::Paste),
+    ("paste_previous", Action::PastePrevious),
+    ("paste_reindent", Action::PasteReindent),
+    ("add_cursor_above", Action::AddCursorAbove),
+    ("add_cursor_below", Action::AddCursorBelow),
+    ("add_cursor_next_match", Action::AddCursorNextMatch),
+    ("remove_secondary_cursors", Action::RemoveSecondaryCursors),
+    ("save", Action::Save),
+    ("save_as", Action::SaveAs),
+    ("open", Action::Open),
+    ("switch_project", Action::SwitchProject),
+    ("new", Action::New),
+    ("close", Action::Close),
+    ("close_tab", Action::CloseTab),
+    ("quit", Action::Quit),
+    ("revert", Action::Revert),
+    ("toggle_auto_revert", Action::ToggleAutoRevert),
+    ("goto_line", Action::GotoLine),
+    ("goto_matching_bracket", Action::GoToMatchingBracket),
+    (
+        "select_to_matching_bracket",
+        Action::SelectToMatchingBracket,
+    ),
+    ("jump_to_next_error", Action::JumpToNextError),
+    ("jump_to_previous_error", Action::JumpToPreviousError),
+    ("smart_home", Action::SmartHome),
+    ("indent_selection", Action::IndentSelection),
+    ("dedent_selection", Action::DedentSelection),
+    ("toggle_comment", Action::ToggleComment),
+    ("upper_case", Action::UpperCase),
+    ("lower_case", Action::LowerCase),
+    ("title_case", Action::TitleCase),
+    ("toggle_case", Action::ToggleCase),
+    ("join_lines", Action::JoinLines),
+    ("toggle_overwrite", Action::ToggleOverwrite),
+    ("list_bookmarks", Action::ListBookmarks),
+    ("document_statistics", Action::DocumentStatistics),
+    (
+        "toggle_search_case_sensitive",
+        Action::ToggleSearchCaseSensitive,
+    ),
+    ("toggle_search_whole_word", Action::ToggleSearchWholeWord),
+    ("toggle_search_regex", Action::ToggleSearchRegex),
+    (
+        "toggle_search_confirm_each",
+        Action::ToggleSearchConfirmEach,
+    ),
+    ("start_macro_recording", Action::StartMacroRecording),
+    ("stop_macro_recording", Action::StopMacroRecording),
+    ("list_macros", Action::ListMacros),
+    ("prompt_record_macro", Action::PromptRecordMacro),
+    ("prompt_play_macro", Action::PromptPlayMacro),
+    ("play_last_macro", Action::PlayLastMacro),
+    ("prompt_set_bookmark", Action::PromptSetBookmark),
+    ("prompt_jump_to_bookmark", Action::PromptJumpToBookmark),
+    ("undo", Action::Undo),
+    ("redo", Action::Redo),
+    ("scroll_up", Action::ScrollUp),
+    ("scroll_down", Action::ScrollDown),
+    ("show_help", Action::ShowHelp),
+    ("keyboard_shortcuts", Action::ShowKeyboardShortcuts),
+    ("command_palette", Action::CommandPalette),
+    ("toggle_line_wrap", Action::ToggleLineWrap),
+    ("toggle_compose_mode", Action::ToggleComposeMode),
+    ("set_compose_width", Action::SetComposeWidth),
+    ("next_buffer", Action::NextBuffer),
+    ("prev_buffer", Action::PrevBuffer),
+    ("switch_to_previous_tab", Action::SwitchToPreviousTab),
+    ("switch_to_tab_by_name", Action::SwitchToTabByName),
+    ("scroll_tabs_left", Action::ScrollTabsLeft),
+    ("scroll_tabs_right", Action::ScrollTabsRight),
+    ("navigate_back", Action::NavigateBack),
+    ("navigate_forward", Action::NavigateForward),
+    ("split_horizontal", Action::SplitHorizontal),
+    ("split_vertical", Action::SplitVertical),
+    ("close_split", Action::CloseSplit),
+    ("next_split", Action::NextSplit),
+    ("prev_split", Action::PrevSplit),
+    ("increase_split_size", Action::IncreaseSplitSize),
+    ("decrease_split_size", Action::DecreaseSplitSize),
+    ("toggle_maximize_split", Action::ToggleMaximizeSplit),
+    ("prompt_confirm", Action::PromptConfirm),
+    ("prompt_cancel", Action::PromptCancel),
+    ("prompt_backspace", Action::PromptBackspace),
+    ("prompt_move_left", Action::PromptMoveLeft),
+    ("prompt_move_right", Action::PromptMoveRight),
+    ("prompt_move_start", Action::PromptMoveStart),
+    ("prompt_move_end", Action::PromptMoveEnd),
+    ("prompt_select_prev", Action::PromptSelectPrev),
+    ("prompt_select_next", Action::PromptSelectNext),
+    ("prompt_page_up", Action::PromptPageUp),
+    ("prompt_page_down", Action::PromptPageDown),
+    ("prompt_accept_suggestion", Action::PromptAcceptSuggestion),
+    (
+        "prompt_delete_word_forward",
+        Action::PromptDeleteWordForward,
+    ),
+    (
+        "prompt_delete_word_backward",
+        Action::PromptDeleteWordBackward,
+    ),
+    ("prompt_delete_to_line_end", Action::PromptDeleteToLineEnd),
+    ("prompt_copy", Action::PromptCopy),
+    ("prompt_cut", Action::PromptCut),
+    ("prompt_paste", Action::PromptPaste),
+    (
+        "prompt_move_left_selecting",
+        Action::PromptMoveLeftSelecting,
+    ),
+    (
+        "prompt_move_right_selecting",
+        Action::PromptMoveRightSelecting,
+    ),
+    (
+        "prompt_move_home_selecting",
+        Action::PromptMoveHomeSelecting,
+    ),
+    ("prompt_move_end_selecting", Action::PromptMoveEndSelecting),
+    ("prompt_select_word_left", Action::PromptSelectWordLeft),
+    ("prompt_select_word_right", Action::PromptSelectWordRight),
+    ("prompt_select_all", Action::PromptSelectAll),
+    ("prompt_move_word_left", Action::PromptMoveWordLeft),
+    ("prompt_move_word_right", Action::PromptMoveWordRight),
+    ("prompt_delete", Action::PromptDelete),
+    ("popup_select_next", Action::PopupSelectNext),
+    ("popup_select_prev", Action::PopupSelectPrev),
+    ("popup_page_up", Action::PopupPageUp),
+    ("popup_page_down", Action::PopupPageDown),
+    ("popup_confirm", Action::PopupConfirm),
+    ("popup_cancel", Action::PopupCancel),
+    ("toggle_file_explorer", Action::ToggleFileExplorer),
+    ("focus_file_explorer", Action::FocusFileExplorer),
+    ("focus_editor", Action::FocusEditor),
+    ("file_explorer_up", Action::FileExplorerUp),
+    ("file_explorer_down", Action::FileExplorerDown),
+    ("file_explorer_page_up", Action::FileExplorerPageUp),
+    ("file_explorer_page_down", Action::FileExplorerPageDown),
+    ("file_explorer_expand", Action::FileExplorerExpand),
+    ("file_explorer_collapse", Action::FileExplorerCollapse),
+    ("file_explorer_open", Action::FileExplorerOpen),
+    ("file_explorer_refresh", Action::FileExplorerRefresh),
+    ("file_explorer_new_file", Action::FileExplorerNewFile),
+    (
+        "file_explorer_new_directory",
+        Action::FileExplorerNewDirectory,
+    ),
+    ("file_explorer_delete", Action::FileExplorerDelete),
+    ("file_explorer_rename", Action::FileExplorerRename),
+    (
+        "file_explorer_toggle_hidden",
+        Action::FileExplorerToggleHidden,
+    ),
+    (
+        "file_explorer_toggle_gitignored",
+        Action::FileExplorerToggleGitignored,
+    ),
+    ("lsp_completion", Action::LspCompletion),
+    ("lsp_goto_definition", Action::LspGotoDefinition),
+    ("lsp_references", Action::LspReferences),
+    ("lsp_rename", Action::LspRename),
+    ("lsp_hover", Action::LspHover),
+    ("lsp_signature_help", Action::LspSignatureHelp),
+    ("lsp_code_actions", Action::LspCodeActions),
+    ("lsp_restart", Action::LspRestart),
+    ("lsp_stop", Action::LspStop),
+    ("toggle_inlay_hints", Action::ToggleInlayHints),
+    ("toggle_mouse_hover", Action::ToggleMouseHover),
+    ("toggle_line_numbers", Action::ToggleLineNumbers),
+    ("toggle_mouse_capture", Action::ToggleMouseCapture),
+    ("set_background", Action::SetBackground),
+    ("set_background_blend", Action::SetBackgroundBlend),
+    ("select_theme", Action::SelectTheme),
+    ("select_keybinding_map", Action::SelectKeybindingMap),
+    ("dump_config", Action::DumpConfig),
+    ("search", Action::Search),
+    ("incremental_search", Action::IncrementalSearch),
+    ("find_in_selection", Action::FindInSelection),
+    ("find_next", Action::FindNext),
+    ("find_previous", Action::FindPrevious),
+    ("replace", Action::Replace),
+    ("query_replace", Action::QueryReplace),
+    ("menu_activate", Action::MenuActivate),
+    ("menu_close", Action::MenuClose),
+    ("menu_left", Action::MenuLeft),
+    ("menu_right", Action::MenuRight),
+    ("menu_up", Action::MenuUp),
+    ("menu_down", Action::MenuDown),
+    ("menu_execute", Action::MenuExecute),
+    // Terminal actions
+    ("open_terminal", Action::OpenTerminal),
+    ("close_terminal", Action::CloseTerminal),
+    ("focus_terminal", Action::FocusTerminal),
+    ("terminal_escape", Action::TerminalEscape),
+    ("toggle_keyboard_capture", Action::ToggleKeyboardCapture),
+    ("terminal_paste", Action::TerminalPaste),
+    // Settings actions
+    ("open_settings", Action::OpenSettings),
+    ("close_settings", Action::CloseSettings),
+    ("settings_save", Action::SettingsSave),
+    ("settings_reset", Action::SettingsReset),
+    ("settings_toggle_focus", Action::SettingsToggleFocus),
+    ("settings_activate", Action::SettingsActivate),
+    ("settings_search", Action::SettingsSearch),
+    ("settings_help", Action::SettingsHelp),
+    ("settings_increment", Action::SettingsIncrement),
+    ("settings_decrement", Action::SettingsDecrement),
+];
+
 impl Action {
     /// Parse action from string (used when loading from config)
     pub fn from_str(s: &str, args: &HashMap<String, serde_json::Value>) -> Option<Action> {
@@ -522,6 +815,8 @@ impl Action {
             "move_page_down" => Some(Action::MovePageDown),
             "move_document_start" => Some(Action::MoveDocumentStart),
             "move_document_end" => Some(Action::MoveDocumentEnd),
+            "move_paragraph_forward" => Some(Action::MoveParagraphForward),
+            "move_paragraph_backward" => Some(Action::MoveParagraphBackward),
 
             "select_left" => Some(Action::SelectLeft),
             "select_right" => Some(Action::SelectRight),
@@ -538,6 +833,7 @@ impl Action {
             "select_all" => Some(Action::SelectAll),
             "select_word" => Some(Action::SelectWord),
             "select_line" => Some(Action::SelectLine),
+            "select_paragraph" => Some(Action::SelectParagraph),
             "expand_selection" => Some(Action::ExpandSelection),
 
             // Block/rectangular selection
@@ -553,6 +849,7 @@ impl Action {
             "delete_line" => Some(Action::DeleteLine),
             "delete_to_line_end" => Some(Action::DeleteToLineEnd),
             "transpose_chars" => Some(Action::TransposeChars),
+            "transpose_words" => Some(Action::TransposeWords),
             "open_line" => Some(Action::OpenLine),
             "recenter" => Some(Action::Recenter),
             "set_mark" => Some(Action::SetMark),
@@ -560,6 +857,22 @@ impl Action {
             "copy" => Some(Action::Copy),
             "cut" => Some(Action::Cut),
             "paste" => Some(Action::Paste),
+            "paste_previous" => Some(Action::PastePrevious),
+            "paste_reindent" => Some(Action::PasteReindent),
+            "copy_to_register" => {
+                if let Some(serde_json::Value::String(c)) = args.get("char") {
+                    c.chars().next().map(Action::CopyToRegister)
+                } else {
+                    None
+                }
+            }
+            "paste_from_register" => {
+                if let Some(serde_json::Value::String(c)) = args.get("char") {
+                    c.chars().next().map(Action::PasteFromRegister)
+                } else {
+                    None
+                }
+            }
 
             "add_cursor_above" => Some(Action::AddCursorAbove),
             "add_cursor_below" => Some(Action::AddCursorBelow),
@@ -578,6 +891,7 @@ impl Action {
             "toggle_auto_revert" => Some(Action::ToggleAutoRevert),
             "goto_line" => Some(Action::GotoLine),
             "goto_matching_bracket" => Some(Action::GoToMatchingBracket),
+            "select_to_matching_bracket" => Some(Action::SelectToMatchingBracket),
             "jump_to_next_error" => Some(Action::JumpToNextError),
             "jump_to_previous_error" => Some(Action::JumpToPreviousError),
 
@@ -585,6 +899,21 @@ impl Action {
             "indent_selection" => Some(Action::IndentSelection),
             "dedent_selection" => Some(Action::DedentSelection),
             "toggle_comment" => Some(Action::ToggleComment),
+            "upper_case" => Some(Action::UpperCase),
+            "lower_case" => Some(Action::LowerCase),
+            "title_case" => Some(Action::TitleCase),
+            "toggle_case" => Some(Action::ToggleCase),
+            "join_lines" => Some(Action::JoinLines),
+            "sort_lines" => {
+                let flag = |name: &str| args.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+                Some(Action::SortLines(SortOptions {
+                    reverse: flag("reverse"),
+                    case_insensitive: flag("case_insensitive"),
+                    numeric: flag("numeric"),
+                    unique: flag("unique"),
+                }))
+            }
+            "toggle_overwrite" => Some(Action::ToggleOverwrite),
 
             "set_bookmark" => {
                 if let Some(serde_json::Value::String(c)) = args.get("char") {
@@ -608,6 +937,7 @@ impl Action {
                 }
             }
             "list_bookmarks" => Some(Action::ListBookmarks),
+            "document_statistics" => Some(Action::DocumentStatistics),
 
             "toggle_search_case_sensitive" => Some(Action::ToggleSearchCaseSensitive),
             "toggle_search_whole_word" => Some(Action::ToggleSearchWholeWord),
@@ -658,6 +988,10 @@ impl Action {
 
             "next_buffer" => Some(Action::NextBuffer),
             "prev_buffer" => Some(Action::PrevBuffer),
+            "switch_to_previous_tab" => Some(Action::SwitchToPreviousTab),
+            "switch_to_tab_by_name" => Some(Action::SwitchToTabByName),
+            "scroll_tabs_left" => Some(Action::ScrollTabsLeft),
+            "scroll_tabs_right" => Some(Action::ScrollTabsRight),
 
             "navigate_back" => Some(Action::NavigateBack),
             "navigate_forward" => Some(Action::NavigateForward),
@@ -747,6 +1081,7 @@ impl Action {
             "dump_config" => Some(Action::DumpConfig),
 
             "search" => Some(Action::Search),
+            "incremental_search" => Some(Action::IncrementalSearch),
             "find_in_selection" => Some(Action::FindInSelection),
             "find_next" => Some(Action::FindNext),
             "find_previous" => Some(Action::FindPrevious),
@@ -793,6 +1128,55 @@ impl Action {
             _ => None,
         }
     }
+
+    /// The config name and arguments of this action, the inverse of [`Action::from_str`]
+    ///
+    /// Plugin actions are named by their plugin function. Returns None for
+    /// actions that can't be written in a config.
+    pub fn to_config(&self) -> Option<(String, HashMap<String, serde_json::Value>)> {
+        let with_arg = |name: &str, key: &str, value: String| {
+            Some((
+                name.to_string(),
+                HashMap::from([(key.to_string(), serde_json::Value::String(value))]),
+            ))
+        };
+        match self {
+            Action::InsertChar(c) => with_arg("insert_char", "char", c.to_string()),
+            Action::CopyToRegister(c) => with_arg("copy_to_register", "char", c.to_string()),
+            Action::PasteFromRegister(c) => with_arg("paste_from_register", "char", c.to_string()),
+            Action::SetBookmark(c) => with_arg("set_bookmark", "char", c.to_string()),
+            Action::JumpToBookmark(c) => with_arg("jump_to_bookmark", "char", c.to_string()),
+            Action::ClearBookmark(c) => with_arg("clear_bookmark", "char", c.to_string()),
+            Action::PlayMacro(c) => with_arg("play_macro", "char", c.to_string()),
+            Action::ToggleMacroRecording(c) => {
+                with_arg("toggle_macro_recording", "char", c.to_string())
+            }
+            Action::ShowMacro(c) => with_arg("show_macro", "char", c.to_string()),
+            Action::SortLines(options) => {
+                let flags = [
+                    ("reverse", options.reverse),
+                    ("case_insensitive", options.case_insensitive),
+                    ("numeric", options.numeric),
+                    ("unique", options.unique),
+                ];
+                let args = flags
+                    .into_iter()
+                    .filter(|(_, set)| *set)
+                    .map(|(flag, _)| (flag.to_string(), serde_json::Value::Bool(true)))
+                    .collect();
+                Some(("sort_lines".to_string(), args))
+            }
+            Action::MenuOpen(name) => with_arg("menu_open", "name", name.clone()),
+            Action::SwitchKeybindingMap(map) => {
+                with_arg("switch_keybinding_map", "map", map.clone())
+            }
+            Action::PluginAction(name) => Some((name.clone(), HashMap::new())),
+            _ => PLAIN_ACTIONS
+                .iter()
+                .find(|(_, action)| action == self)
+                .map(|(name, _)| (name.to_string(), HashMap::new())),
+        }
+    }
 }
 
 /// Result of chord resolution
@@ -1057,7 +1441,8 @@ impl KeybindingResolver {
         );
 
         // Check all chord binding sources in priority order
-        let search_order = vec![
+        let parent = context.parent();
+        let mut search_order = vec![
             (&self.chord_bindings, &KeyContext::Global, "custom global"),
             (
                 &self.default_chord_bindings,
@@ -1067,6 +1452,10 @@ impl KeybindingResolver {
             (&self.chord_bindings, &context, "custom context"),
             (&self.default_chord_bindings, &context, "default context"),
         ];
+        if let Some(parent) = &parent {
+            search_order.push((&self.chord_bindings, parent, "custom parent"));
+            search_order.push((&self.default_chord_bindings, parent, "default parent"));
+        }
 
         let mut has_partial_match = false;
 
@@ -1147,6 +1536,11 @@ impl KeybindingResolver {
             }
         }
 
+        // Keys a refined context doesn't bind resolve as in its parent
+        if let Some(parent) = context.parent() {
+            return self.resolve(event, parent);
+        }
+
         // Fall back to normal context ONLY for application-wide actions
         // This prevents keys from leaking through to the editor when in special contexts
         if context != KeyContext::Normal {
@@ -1368,6 +1762,7 @@ impl KeybindingResolver {
             KeyCode::Enter => "Enter".to_string(),
             KeyCode::Backspace => "Backspace".to_string(),
             KeyCode::Delete => "Delete".to_string(),
+            KeyCode::Insert => "Insert".to_string(),
             KeyCode::Tab => "Tab".to_string(),
             KeyCode::Esc => "Esc".to_string(),
             KeyCode::Left => "Left".to_string(),
@@ -1393,6 +1788,7 @@ impl KeybindingResolver {
             "enter" => Some(KeyCode::Enter),
             "backspace" => Some(KeyCode::Backspace),
             "delete" | "del" => Some(KeyCode::Delete),
+            "insert" | "ins" => Some(KeyCode::Insert),
             "tab" => Some(KeyCode::Tab),
             "esc" | "escape" => Some(KeyCode::Esc),
             "space" => Some(KeyCode::Char(' ')),
@@ -1438,6 +1834,7 @@ impl KeybindingResolver {
         for context in &[
             KeyContext::Normal,
             KeyContext::Prompt,
+            KeyContext::IncrementalSearch,
             KeyContext::Popup,
             KeyContext::FileExplorer,
             KeyContext::Menu,
@@ -1501,6 +1898,8 @@ impl KeybindingResolver {
             Action::MovePageDown => "Move page down".to_string(),
             Action::MoveDocumentStart => "Move to document start".to_string(),
             Action::MoveDocumentEnd => "Move to document end".to_string(),
+            Action::MoveParagraphForward => "Move to next paragraph".to_string(),
+            Action::MoveParagraphBackward => "Move to previous paragraph".to_string(),
             Action::SelectLeft => "Select left".to_string(),
             Action::SelectRight => "Select right".to_string(),
             Action::SelectUp => "Select up".to_string(),
@@ -1516,6 +1915,7 @@ impl KeybindingResolver {
             Action::SelectAll => "Select all".to_string(),
             Action::SelectWord => "Select word under cursor".to_string(),
             Action::SelectLine => "Select current line".to_string(),
+            Action::SelectParagraph => "Select current paragraph".to_string(),
             Action::ExpandSelection => "Expand selection".to_string(),
             Action::BlockSelectLeft => "Block select left".to_string(),
             Action::BlockSelectRight => "Block select right".to_string(),
@@ -1528,12 +1928,17 @@ impl KeybindingResolver {
             Action::DeleteLine => "Delete line".to_string(),
             Action::DeleteToLineEnd => "Delete to end of line".to_string(),
             Action::TransposeChars => "Transpose characters".to_string(),
+            Action::TransposeWords => "Transpose words".to_string(),
             Action::OpenLine => "Open line below".to_string(),
             Action::Recenter => "Recenter view on cursor".to_string(),
             Action::SetMark => "Set mark (start selection)".to_string(),
             Action::Copy => "Copy".to_string(),
             Action::Cut => "Cut".to_string(),
             Action::Paste => "Paste".to_string(),
+            Action::PastePrevious => "Paste previous".to_string(),
+            Action::PasteReindent => "Paste and re-indent".to_string(),
+            Action::CopyToRegister(c) => format!("Copy to register '{}'", c),
+            Action::PasteFromRegister(c) => format!("Paste from register '{}'", c),
             Action::AddCursorAbove => "Add cursor above".to_string(),
             Action::AddCursorBelow => "Add cursor below".to_string(),
             Action::AddCursorNextMatch => "Add cursor at next match".to_string(),
@@ -1550,6 +1955,7 @@ impl KeybindingResolver {
             Action::ToggleAutoRevert => "Toggle auto-revert mode".to_string(),
             Action::GotoLine => "Go to line number".to_string(),
             Action::GoToMatchingBracket => "Go to matching bracket".to_string(),
+            Action::SelectToMatchingBracket => "Select to matching bracket".to_string(),
             Action::JumpToNextError => "Jump to next error/diagnostic".to_string(),
             Action::JumpToPreviousError => "Jump to previous error/diagnostic".to_string(),
             Action::SmartHome => {
@@ -1558,6 +1964,14 @@ impl KeybindingResolver {
             Action::IndentSelection => "Indent selection".to_string(),
             Action::DedentSelection => "Dedent selection".to_string(),
             Action::ToggleComment => "Toggle comment".to_string(),
+            Action::UpperCase => "Convert to UPPER case".to_string(),
+            Action::LowerCase => "Convert to lower case".to_string(),
+            Action::TitleCase => "Convert to Title Case".to_string(),
+            Action::ToggleCase => "Toggle case".to_string(),
+            Action::JoinLines => "Join lines".to_string(),
+            Action::SortLines(_) => "Sort lines".to_string(),
+            Action::ToggleOverwrite => "Toggle overwrite mode".to_string(),
+            Action::DocumentStatistics => "Show document statistics".to_string(),
             Action::SetBookmark(c) => format!("Set bookmark '{}'", c),
             Action::JumpToBookmark(c) => format!("Jump to bookmark '{}'", c),
             Action::ClearBookmark(c) => format!("Clear bookmark '{}'", c),
@@ -1669,6 +2083,7 @@ impl KeybindingResolver {
             Action::SetBackgroundBlend => "Set background blend ratio".to_string(),
             Action::DumpConfig => "Dump config to file".to_string(),
             Action::Search => "Search for text in buffer".to_string(),
+            Action::IncrementalSearch => "Incremental search".to_string(),
             Action::FindInSelection => "Search within selection".to_string(),
             Action::FindNext => "Find next search match".to_string(),
             Action::FindPrevious => "Find previous search match".to_string(),
@@ -1883,6 +2298,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_action_to_config_round_trips() {
+        let actions = crate::input::commands::get_all_commands()
+            .into_iter()
+            .map(|command| command.action)
+            .chain([
+                Action::PlayMacro('q'),
+                Action::MenuOpen("File".to_string()),
+                Action::SortLines(SortOptions {
+                    numeric: true,
+                    unique: true,
+                    ..SortOptions::default()
+                }),
+            ]);
+        for action in actions {
+            let (name, args) = action
+                .to_config()
+                .unwrap_or_else(|| panic!("{action:?} has no config name"));
+            assert_eq!(Action::from_str(&name, &args), Some(action));
+        }
+
+        assert_eq!(
+            Action::PluginAction("git_blame".to_string()).to_config(),
+            Some(("git_blame".to_string(), HashMap::new()))
+        );
+    }
+
+    #[test]
+    fn test_incremental_search_context_from_when_clause() {
+        assert_eq!(
+            KeyContext::from_when_clause("incrementalSearch"),
+            Some(KeyContext::IncrementalSearch)
+        );
+        assert_eq!(
+            KeyContext::from_when_clause("incremental_search"),
+            Some(KeyContext::IncrementalSearch)
+        );
+        assert_eq!(
+            KeyContext::IncrementalSearch.to_when_clause(),
+            "incrementalSearch"
+        );
+    }
+
     #[test]
     fn test_key_context_from_when_clause() {
         assert_eq!(
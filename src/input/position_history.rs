@@ -261,6 +261,46 @@ impl PositionHistory {
     pub fn current_index(&self) -> Option<usize> {
         self.current_index
     }
+
+    /// Shift positions in `buffer_id` at or after `position` right by `length`
+    ///
+    /// Called for every insertion so saved positions keep pointing at the same text.
+    pub fn adjust_for_insert(&mut self, buffer_id: BufferId, position: usize, length: usize) {
+        self.adjust_offsets(buffer_id, |offset| {
+            if *offset >= position {
+                *offset += length;
+            }
+        });
+    }
+
+    /// Shift positions in `buffer_id` after a deleted `range` left by its length
+    ///
+    /// Positions inside the deleted text collapse to the start of the range.
+    pub fn adjust_for_delete(&mut self, buffer_id: BufferId, range: std::ops::Range<usize>) {
+        self.adjust_offsets(buffer_id, |offset| {
+            if *offset >= range.end {
+                *offset -= range.len();
+            } else if *offset > range.start {
+                *offset = range.start;
+            }
+        });
+    }
+
+    /// Apply `f` to every position and anchor recorded for `buffer_id`
+    fn adjust_offsets(&mut self, buffer_id: BufferId, mut f: impl FnMut(&mut usize)) {
+        let pending = self
+            .pending_movement
+            .as_mut()
+            .map(|pending| &mut pending.start_entry);
+        for entry in self.entries.iter_mut().chain(pending) {
+            if entry.buffer_id == buffer_id {
+                f(&mut entry.position);
+                if let Some(anchor) = entry.anchor.as_mut() {
+                    f(anchor);
+                }
+            }
+        }
+    }
 }
 
 impl Default for PositionHistory {
@@ -440,6 +480,41 @@ mod tests {
         assert_eq!(history.current(), Some(&make_entry(1, 20)));
     }
 
+    #[test]
+    fn test_edits_adjust_positions() {
+        let mut history = PositionHistory::new();
+        history.push(make_entry(1, 10));
+        history.push(PositionEntry::new(BufferId(1), 40, Some(30)));
+        history.push(make_entry(2, 40));
+
+        // Typing 5 bytes at 20 in buffer 1 moves only the later position there
+        history.adjust_for_insert(BufferId(1), 20, 5);
+        // Deleting 8..15 pulls the first position to the start of the deletion
+        history.adjust_for_delete(BufferId(1), 8..15);
+
+        assert_eq!(history.current(), Some(&make_entry(2, 40)));
+        assert_eq!(
+            history.back(),
+            Some(&PositionEntry::new(BufferId(1), 38, Some(28)))
+        );
+        assert_eq!(history.back(), Some(&make_entry(1, 8)));
+
+        // Forward history is adjusted too
+        history.adjust_for_delete(BufferId(2), 0..10);
+        history.forward();
+        assert_eq!(history.forward(), Some(&make_entry(2, 30)));
+    }
+
+    #[test]
+    fn test_edits_adjust_pending_movement() {
+        let mut history = PositionHistory::new();
+        history.record_movement(BufferId(1), 100, None);
+        history.adjust_for_insert(BufferId(1), 0, 10);
+        history.commit_pending_movement();
+
+        assert_eq!(history.current(), Some(&make_entry(1, 110)));
+    }
+
     #[test]
     fn test_clear() {
         let mut history = PositionHistory::new();
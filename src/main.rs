@@ -329,9 +329,6 @@ fn initialize_app(args: &Args) -> io::Result<SetupState> {
         tracing::info!("Using GPM for mouse capture, skipping crossterm mouse protocol");
     }
 
-    let _ = stdout().execute(SetCursorStyle::BlinkingBlock);
-    tracing::info!("Enabled blinking block cursor");
-
     let backend = ratatui::backend::CrosstermBackend::new(stdout());
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
@@ -431,6 +428,12 @@ fn main() -> io::Result<()> {
             editor.set_gpm_active(true);
         }
 
+        editor.set_color_support(fresh::view::theme::ColorSupport::detect());
+
+        if !args.no_session {
+            editor.enable_file_sessions();
+        }
+
         if first_run {
             handle_first_run_setup(
                 &mut editor,
@@ -574,6 +577,7 @@ where
     let mut last_render = Instant::now();
     let mut needs_render = true;
     let mut pending_event: Option<CrosstermEvent> = None;
+    let mut cursor_style: Option<SetCursorStyle> = None;
 
     loop {
         // Process async messages and poll for file changes (auto-revert, file tree)
@@ -586,6 +590,16 @@ where
             needs_render = true;
         }
 
+        // Fire plugin timers whose deadline has passed
+        if editor.fire_plugin_timers() {
+            needs_render = true;
+        }
+
+        // Report this frame's buffer edits to plugins
+        if editor.fire_buffer_changed_hooks() {
+            needs_render = true;
+        }
+
         // Check for warnings and open warning log if any occurred
         if editor.check_warning_log() {
             needs_render = true;
@@ -595,7 +609,20 @@ where
             tracing::debug!("Auto-save error: {}", e);
         }
 
+        match editor.auto_save_idle_buffers() {
+            Ok(0) => {}
+            Ok(_) => needs_render = true,
+            Err(e) => {
+                // The editor shows the failure in the status bar
+                tracing::warn!("Auto-save error: {}", e);
+                needs_render = true;
+            }
+        }
+
         if editor.should_quit() {
+            if let Err(e) = editor.save_file_sessions() {
+                tracing::warn!("Failed to save file sessions: {}", e);
+            }
             if session_enabled {
                 if let Err(e) = editor.save_session() {
                     tracing::warn!("Failed to save session: {}", e);
@@ -610,6 +637,13 @@ where
             terminal.draw(|frame| editor.render(frame))?;
             last_render = Instant::now();
             needs_render = false;
+
+            // The cursor shape follows the mode; only send it when it changes
+            let style = editor.cursor_style();
+            if cursor_style != Some(style) {
+                let _ = stdout().execute(style);
+                cursor_style = Some(style);
+            }
         }
 
         let event = if let Some(e) = pending_event.take() {
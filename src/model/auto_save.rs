@@ -0,0 +1,202 @@
+//! Debounced auto-save of a buffer once editing has gone idle
+//!
+//! Unlike recovery files, which are written on a fixed interval to a private
+//! directory, auto-save writes the buffer itself (or a sidecar next to it) and
+//! only once no edit has happened for the configured delay.
+
+use crate::model::buffer::Buffer;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Extension appended to the file name of sidecar auto-saves
+pub const AUTOSAVE_EXTENSION: &str = "autosave";
+
+/// Where an auto-save writes the buffer contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoSaveTarget {
+    /// Save over the buffer's own file, as a manual save would
+    File,
+    /// Write a `<file>.autosave` sidecar and leave the file and modified flag alone
+    Sidecar,
+}
+
+/// Path of the sidecar auto-save for `path` (`main.rs` -> `main.rs.autosave`)
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(AUTOSAVE_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Debounce state for one buffer
+///
+/// Edits only set a flag, since applying an event has no notion of time; the
+/// next `tick` stamps it with the current instant. A save fires on the first
+/// tick at least `delay` after the last stamped edit, after which the state
+/// resets until the buffer is edited again.
+#[derive(Debug, Default)]
+pub struct AutoSave {
+    /// An edit happened since the last tick
+    edited: bool,
+    /// When the most recent edit was seen, if it has not been saved yet
+    last_edit: Option<Instant>,
+}
+
+impl AutoSave {
+    /// Record that the buffer was edited
+    pub fn mark_edited(&mut self) {
+        self.edited = true;
+    }
+
+    /// Whether an edit is waiting for the buffer to go idle
+    pub fn is_pending(&self) -> bool {
+        self.edited || self.last_edit.is_some()
+    }
+
+    /// Advance to `now`, returning whether `buffer` has been idle for `delay`
+    /// and should be saved
+    ///
+    /// Unnamed and read-only buffers are never due, and a buffer that is no
+    /// longer modified (e.g. saved manually or undone) just resets the debounce.
+    pub fn tick(
+        &mut self,
+        now: Instant,
+        delay: Duration,
+        buffer: &Buffer,
+        read_only: bool,
+    ) -> bool {
+        if std::mem::take(&mut self.edited) {
+            self.last_edit = Some(now);
+            return false;
+        }
+        match self.last_edit {
+            Some(last_edit) if now.saturating_duration_since(last_edit) >= delay => {
+                self.last_edit = None;
+            }
+            _ => return false,
+        }
+        buffer.file_path().is_some() && !read_only && buffer.is_modified()
+    }
+}
+
+/// Write `buffer` to the sidecar of its file, returning the sidecar's path
+///
+/// The file itself and the modified flag are left alone.
+pub fn write_sidecar(buffer: &mut Buffer) -> io::Result<PathBuf> {
+    let path = buffer
+        .file_path()
+        .map(sidecar_path)
+        .ok_or_else(|| io::Error::other("buffer has no file"))?;
+    let len = buffer.len();
+    let content = buffer
+        .get_text_range_mut(0, len)
+        .map_err(io::Error::other)?;
+    std::fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// Delete the sidecar of `path`, if there is one
+pub fn remove_sidecar(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(sidecar_path(path)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(500);
+
+    fn named_buffer(dir: &Path) -> Buffer {
+        let path = dir.join("notes.txt");
+        std::fs::write(&path, "hello").unwrap();
+        Buffer::load_from_file(&path, 1024 * 1024).unwrap()
+    }
+
+    fn edit(buffer: &mut Buffer, auto_save: &mut AutoSave, text: &str) {
+        let end = buffer.len();
+        buffer.insert(end, text);
+        auto_save.mark_edited();
+    }
+
+    #[test]
+    fn test_fires_once_after_edits_settle() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut buffer = named_buffer(dir.path());
+        let mut auto_save = AutoSave::default();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut saves = Vec::new();
+        let mut tick = |buffer: &Buffer, auto_save: &mut AutoSave, ms| {
+            if auto_save.tick(at(ms), DELAY, buffer, false) {
+                saves.push(ms);
+            }
+        };
+
+        // Typing every 200ms keeps pushing the deadline back
+        for ms in [0, 200, 400, 600] {
+            edit(&mut buffer, &mut auto_save, "!");
+            tick(&buffer, &mut auto_save, ms);
+            tick(&buffer, &mut auto_save, ms + 100);
+        }
+        // Idle: due 500ms after the last edit, and only once
+        for ms in (800..2000).step_by(100) {
+            tick(&buffer, &mut auto_save, ms);
+        }
+
+        assert_eq!(saves, vec![1100]);
+        assert!(!auto_save.is_pending());
+    }
+
+    #[test]
+    fn test_skips_unnamed_and_read_only_buffers() {
+        let start = Instant::now();
+        let later = start + DELAY;
+
+        let mut unnamed = Buffer::from_str_test("scratch");
+        let mut auto_save = AutoSave::default();
+        edit(&mut unnamed, &mut auto_save, "!");
+        for now in [start, later] {
+            assert!(!auto_save.tick(now, DELAY, &unnamed, false));
+        }
+        assert!(!auto_save.is_pending());
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut read_only = named_buffer(dir.path());
+        edit(&mut read_only, &mut auto_save, "!");
+        for now in [start, later] {
+            assert!(!auto_save.tick(now, DELAY, &read_only, true));
+        }
+        assert!(!auto_save.is_pending());
+    }
+
+    #[test]
+    fn test_sidecar_leaves_file_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut buffer = named_buffer(dir.path());
+        let mut auto_save = AutoSave::default();
+        let start = Instant::now();
+
+        edit(&mut buffer, &mut auto_save, " world");
+        assert!(!auto_save.tick(start, DELAY, &buffer, false));
+        assert!(auto_save.tick(start + DELAY, DELAY, &buffer, false));
+        let saved = write_sidecar(&mut buffer).unwrap();
+
+        let sidecar = dir.path().join("notes.txt.autosave");
+        assert_eq!(saved, sidecar);
+        assert_eq!(std::fs::read_to_string(sidecar).unwrap(), "hello world");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+            "hello"
+        );
+        assert!(buffer.is_modified());
+        assert_eq!(
+            buffer.file_path(),
+            Some(dir.path().join("notes.txt").as_path())
+        );
+    }
+}
@@ -1,16 +1,19 @@
 /// Text buffer that uses PieceTree with integrated line tracking
 /// Architecture where the tree is the single source of truth for text and line information
+use crate::model::line_diff::LineHunk;
 use crate::model::piece_tree::{
-    BufferData, BufferLocation, Cursor, PieceInfo, PieceRangeIter, PieceTree, Position,
-    StringBuffer, TreeStats,
+    BufferData, BufferLocation, ContentHasher, Cursor, LeafData, PieceInfo, PieceRangeIter,
+    PieceTree, Position, StringBuffer, TreeStats,
 };
 use crate::model::piece_tree_diff::PieceTreeDiff;
 use anyhow::{Context, Result};
 use regex::bytes::Regex;
+use std::hash::Hasher;
 use std::io::{self, Read, Seek, SeekFrom, Write};
-use std::ops::Range;
+use std::ops::{ControlFlow, Range};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 // Large file support configuration
 /// Default threshold for considering a file "large" (100 MB)
@@ -101,6 +104,34 @@ impl LineNumber {
     }
 }
 
+/// How the file on disk relates to the buffer, see [`TextBuffer::external_change_status`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalChange {
+    /// The file still has the content last loaded or saved
+    Unchanged,
+    /// The file changed and the buffer has no unsaved edits, so it can be reloaded
+    ChangedCleanBuffer,
+    /// Both the file and the buffer changed; reloading would lose edits and
+    /// saving would overwrite someone else's
+    ConflictBothChanged,
+}
+
+/// The file as it was when last loaded or saved
+#[derive(Debug, Clone, Copy)]
+struct DiskSnapshot {
+    mtime: SystemTime,
+    /// Hash of the file's bytes; None if the file wasn't read in full
+    hash: Option<u64>,
+}
+
+impl DiskSnapshot {
+    /// Snapshot of the file at `path`, if its modification time is available
+    fn of(path: &Path, hash: Option<u64>) -> Option<Self> {
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        Some(Self { mtime, hash })
+    }
+}
+
 /// A text buffer that manages document content using a piece table
 /// with integrated line tracking
 pub struct TextBuffer {
@@ -143,6 +174,9 @@ pub struct TextBuffer {
     /// Used for chunked recovery to know the original file size for reconstruction.
     /// Updated when loading from file or after saving.
     saved_file_size: Option<usize>,
+
+    /// The file as last loaded or saved, for noticing changes made by others
+    disk_snapshot: Option<DiskSnapshot>,
 }
 
 impl TextBuffer {
@@ -162,6 +196,7 @@ impl TextBuffer {
             is_binary: false,
             line_ending: LineEnding::default(),
             saved_file_size: None,
+            disk_snapshot: None,
         }
     }
 
@@ -193,6 +228,7 @@ impl TextBuffer {
             is_binary: false,
             line_ending: LineEnding::default(),
             saved_file_size: Some(bytes), // Treat initial content as "saved" state
+            disk_snapshot: None,
         }
     }
 
@@ -217,6 +253,7 @@ impl TextBuffer {
             is_binary: false,
             line_ending: LineEnding::default(),
             saved_file_size: None,
+            disk_snapshot: None,
         }
     }
 
@@ -252,6 +289,10 @@ impl TextBuffer {
         let mut file = std::fs::File::open(path)?;
         let mut contents = Vec::new();
         file.read_to_end(&mut contents)?;
+        let disk_snapshot = DiskSnapshot::of(
+            path,
+            Some(crate::model::piece_tree::content_hash(&contents)),
+        );
 
         // Detect if this is a binary file
         let is_binary = Self::detect_binary(&contents);
@@ -264,6 +305,7 @@ impl TextBuffer {
 
         let mut buffer = Self::from_bytes(normalized_contents);
         buffer.file_path = Some(path.to_path_buf());
+        buffer.disk_snapshot = disk_snapshot;
         buffer.modified = false;
         buffer.large_file = false;
         buffer.is_binary = is_binary;
@@ -326,6 +368,51 @@ impl TextBuffer {
             is_binary,
             line_ending,
             saved_file_size: Some(file_size),
+            disk_snapshot: DiskSnapshot::of(path, None),
+        })
+    }
+
+    /// Open a file backed by a read-only memory map
+    ///
+    /// Like large-file loading there is no line indexing, but the original text
+    /// is read straight from the mapping rather than copied in chunk by chunk.
+    /// Edits go to owned buffers, so unedited regions never occupy heap memory.
+    /// The file must not be truncated by another process while it is open.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        use crate::model::piece_tree::BufferLocation;
+
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let file_size = file.metadata()?.len() as usize;
+        if file_size == 0 {
+            // Zero-length files can't be mapped
+            return Self::load_from_file(path, 0);
+        }
+
+        // SAFETY: the mapping is only ever read, and the caller is told above
+        // not to truncate the file underneath it
+        let map = Arc::new(unsafe { memmap2::Mmap::map(&file)? });
+        let sample = &map[..file_size.min(8 * 1024)];
+        let is_binary = Self::detect_binary(sample);
+        let line_ending = Self::detect_line_ending(sample);
+
+        let piece_tree = PieceTree::new(BufferLocation::Stored(0), 0, file_size, None);
+        let saved_root = piece_tree.root();
+
+        Ok(TextBuffer {
+            piece_tree,
+            saved_root,
+            buffers: vec![StringBuffer::new_mapped(0, map, 0, file_size)],
+            next_buffer_id: 1,
+            file_path: Some(path.to_path_buf()),
+            modified: false,
+            recovery_pending: false,
+            large_file: true,
+            is_binary,
+            line_ending,
+            saved_file_size: Some(file_size),
+            disk_snapshot: DiskSnapshot::of(path, None),
         })
     }
 
@@ -363,12 +450,16 @@ impl TextBuffer {
             self.file_path = Some(dest_path.to_path_buf());
             self.mark_saved_snapshot();
             self.saved_file_size = Some(0);
+            self.disk_snapshot =
+                DiskSnapshot::of(dest_path, Some(ContentHasher::default().finish()));
             return Ok(());
         }
 
         // Use a temp file to avoid corrupting the original if something goes wrong
         let temp_path = dest_path.with_extension("tmp");
         let mut out_file = std::fs::File::create(&temp_path)?;
+        // Hash what goes to disk, for telling later whether someone else changed it
+        let mut hasher = ContentHasher::default();
 
         // Cache for open source files (for streaming unloaded regions)
         let mut source_file_cache: Option<(PathBuf, std::fs::File)> = None;
@@ -390,6 +481,7 @@ impl TextBuffer {
                     let end = start + piece_view.bytes;
                     let chunk = &data[start..end];
                     let converted = Self::convert_line_endings(chunk, self.line_ending);
+                    hasher.write(&converted);
                     out_file.write_all(&converted)?;
                 }
                 BufferData::Unloaded {
@@ -421,10 +513,20 @@ impl TextBuffer {
                     while remaining > 0 {
                         let to_read = remaining.min(chunk_buf.len());
                         source_file.read_exact(&mut chunk_buf[..to_read])?;
+                        hasher.write(&chunk_buf[..to_read]);
                         out_file.write_all(&chunk_buf[..to_read])?;
                         remaining -= to_read;
                     }
                 }
+                #[cfg(feature = "mmap")]
+                BufferData::Mapped {
+                    map, file_offset, ..
+                } => {
+                    // Mapped regions are the original file bytes, like unloaded ones
+                    let start = *file_offset + piece_view.buffer_offset;
+                    hasher.write(&map[start..start + piece_view.bytes]);
+                    out_file.write_all(&map[start..start + piece_view.bytes])?;
+                }
             }
         }
 
@@ -451,9 +553,59 @@ impl TextBuffer {
 
         self.file_path = Some(dest_path.to_path_buf());
         self.mark_saved_snapshot();
+        self.disk_snapshot = DiskSnapshot::of(dest_path, Some(hasher.finish()));
         Ok(())
     }
 
+    /// Whether the file was changed on disk by someone else since it was last
+    /// loaded or saved, and whether that clashes with unsaved edits
+    ///
+    /// A changed modification time alone doesn't count if the content hash
+    /// still matches. A file the buffer was never loaded from or saved to
+    /// counts as changed. Buffers without a file, and files that can't be
+    /// read, report `Unchanged`.
+    pub fn external_change_status(&self) -> ExternalChange {
+        let Some(path) = &self.file_path else {
+            return ExternalChange::Unchanged;
+        };
+        let Ok(mtime) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+            return ExternalChange::Unchanged;
+        };
+        if let Some(snapshot) = self.disk_snapshot {
+            if mtime == snapshot.mtime {
+                return ExternalChange::Unchanged;
+            }
+            if snapshot.hash.is_some() && Self::hash_file(path).ok() == snapshot.hash {
+                return ExternalChange::Unchanged;
+            }
+        }
+        if self.modified {
+            ExternalChange::ConflictBothChanged
+        } else {
+            ExternalChange::ChangedCleanBuffer
+        }
+    }
+
+    /// Take the file as it is on disk now as the baseline for
+    /// `external_change_status`, e.g. when keeping the buffer's edits over it
+    pub fn acknowledge_external_change(&mut self) {
+        if let Some(path) = &self.file_path {
+            self.disk_snapshot = DiskSnapshot::of(path, Self::hash_file(path).ok());
+        }
+    }
+
+    fn hash_file(path: &Path) -> io::Result<u64> {
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = ContentHasher::default();
+        let mut chunk = vec![0u8; 64 * 1024];
+        loop {
+            match file.read(&mut chunk)? {
+                0 => return Ok(hasher.finish()),
+                n => hasher.write(&chunk[..n]),
+            }
+        }
+    }
+
     /// Restore file metadata (permissions, owner/group) from original file
     fn restore_file_metadata(path: &Path, original_meta: &std::fs::Metadata) -> io::Result<()> {
         // Restore permissions (works cross-platform)
@@ -486,10 +638,20 @@ impl TextBuffer {
     /// Get the total number of lines in the document
     /// Uses the piece tree's integrated line tracking
     /// Returns None if line count is unknown (e.g., for large files without line indexing)
+    ///
+    /// A line feed always starts a new line, so a buffer that ends with one has
+    /// an empty last line after it: "a\nb\n" has three lines and "a\nb" two.
     pub fn line_count(&self) -> Option<usize> {
         self.piece_tree.line_count()
     }
 
+    /// Whether the buffer ends with a line feed, i.e. its last line is the
+    /// empty one after it (see [`TextBuffer::line_count`])
+    pub fn ends_with_newline(&self) -> bool {
+        let len = self.len();
+        len > 0 && self.slice_bytes(len - 1..len) == b"\n"
+    }
+
     /// Snapshot the current tree as the saved baseline
     pub fn mark_saved_snapshot(&mut self) {
         self.saved_root = self.piece_tree.root();
@@ -559,6 +721,84 @@ impl TextBuffer {
         structure_diff
     }
 
+    /// Line-by-line diff of the current content against the last saved snapshot
+    ///
+    /// The hunks cover every line of both versions, in order. Only the lines
+    /// between the pieces the two trees share at either end are read and
+    /// compared; the shared lines around them become single `Unchanged` hunks.
+    /// Returns an empty list when line numbers are unknown (large files).
+    pub fn diff_against_saved(&self) -> Vec<LineHunk> {
+        use crate::model::line_diff::{push_hunk, push_line_hunks, HunkKind};
+
+        let Some(line_count) = self.line_count() else {
+            return Vec::new();
+        };
+        let current_len = self.len();
+        let saved_len = self.tree_total_bytes(&self.saved_root);
+        let root = self.piece_tree.root();
+
+        let (prefix, suffix) = if Arc::ptr_eq(&self.saved_root, &root) {
+            (current_len, 0)
+        } else {
+            crate::model::piece_tree_diff::shared_prefix_suffix(&self.saved_root, &root)
+        };
+
+        // Widen the differing bytes to whole lines. The line holding the end
+        // of the difference is included up to its newline, which lies in the
+        // shared suffix, so both windows end on the same line boundary.
+        let Some(start) = self.offset_to_position(prefix) else {
+            return Vec::new();
+        };
+        let first_line = start.line;
+        let window_start = prefix - start.column;
+        let current_end = current_len - suffix;
+        let Some(end) = self.offset_to_position(current_end) else {
+            return Vec::new();
+        };
+        let window_end = self.line_col_to_position(end.line + 1, 0);
+        let saved_window_end = saved_len - suffix + (window_end - current_end);
+
+        let (Some(saved), Some(current)) = (
+            self.extract_range_from_tree(&self.saved_root, window_start, saved_window_end),
+            self.get_text_range(window_start, window_end - window_start),
+        ) else {
+            return Vec::new();
+        };
+        let mut saved_lines: Vec<&[u8]> = saved.split(|&b| b == b'\n').collect();
+        let mut current_lines: Vec<&[u8]> = current.split(|&b| b == b'\n').collect();
+        if window_end < current_len {
+            // Both windows end just after a newline; the empty remainder is
+            // the start of the next, unchanged line
+            saved_lines.pop();
+            current_lines.pop();
+        }
+
+        let mut hunks = Vec::new();
+        push_hunk(
+            &mut hunks,
+            HunkKind::Unchanged,
+            (0, 0),
+            (first_line, first_line),
+        );
+        push_line_hunks(
+            &mut hunks,
+            &saved_lines,
+            &current_lines,
+            first_line,
+            first_line,
+        );
+        let saved_after = first_line + saved_lines.len();
+        let current_after = first_line + current_lines.len();
+        let rest = line_count - current_after;
+        push_hunk(
+            &mut hunks,
+            HunkKind::Unchanged,
+            (saved_after, current_after),
+            (rest, rest),
+        );
+        hunks
+    }
+
     /// Check if the actual byte content differs in the given ranges.
     /// Returns true if content differs, false if content is identical.
     fn verify_content_differs_in_ranges(&self, byte_ranges: &[std::ops::Range<usize>]) -> bool {
@@ -810,6 +1050,27 @@ impl TextBuffer {
         self.insert_bytes(offset, text.as_bytes().to_vec());
     }
 
+    /// Insert text before the `char_idx`-th character
+    ///
+    /// Returns `None` without inserting if the buffer has fewer characters, or
+    /// the text before the index isn't loaded.
+    pub fn insert_char_index(&mut self, char_idx: usize, data: &[u8]) -> Option<Cursor> {
+        let offset = self.piece_tree.char_to_byte(char_idx, &self.buffers)?;
+        Some(self.insert_bytes(offset, data.to_vec()))
+    }
+
+    /// Delete the characters in `chars` (character indices, end exclusive)
+    ///
+    /// Returns the byte range that was removed, or `None` without deleting if
+    /// either end is past the last character or the text isn't loaded.
+    pub fn remove_char_range(&mut self, chars: Range<usize>) -> Option<Range<usize>> {
+        let start = self.piece_tree.char_to_byte(chars.start, &self.buffers)?;
+        let end = self
+            .piece_tree
+            .char_to_byte(chars.end.max(chars.start), &self.buffers)?;
+        Some(self.delete_tracked(start..end))
+    }
+
     /// Insert text at a line/column position
     /// This now uses the optimized piece_tree.insert_at_position() for a single traversal
     pub fn insert_at_position(&mut self, position: Position, text: Vec<u8>) -> Cursor {
@@ -864,6 +1125,28 @@ impl TextBuffer {
         }
     }
 
+    /// Insert text at the given byte offset and return the range it now occupies
+    ///
+    /// The range is empty if nothing was inserted (empty text, or an offset past
+    /// the end of the buffer), so range-tracking callers can apply it as-is.
+    pub fn insert_tracked(&mut self, offset: usize, text: &str) -> Range<usize> {
+        let len_before = self.total_bytes();
+        self.insert(offset, text);
+        let inserted = self.total_bytes() - len_before;
+        offset..offset + inserted
+    }
+
+    /// Delete text in a range and return the range actually removed
+    ///
+    /// The range is clamped to the buffer, so it is empty if nothing was deleted.
+    pub fn delete_tracked(&mut self, range: Range<usize>) -> Range<usize> {
+        let len_before = self.total_bytes();
+        self.delete(range.clone());
+        let removed = len_before - self.total_bytes();
+        let start = range.start.min(len_before);
+        start..start + removed
+    }
+
     /// Delete text in a line/column range
     /// This now uses the optimized piece_tree.delete_position_range() for a single traversal
     pub fn delete_range(&mut self, start: Position, end: Position) {
@@ -1132,6 +1415,24 @@ impl TextBuffer {
         Ok(())
     }
 
+    /// Borrow all text without copying, if it is stored as one contiguous slice
+    /// Returns None when the text spans several pieces or an unloaded buffer
+    pub fn as_contiguous(&self) -> Option<&[u8]> {
+        self.piece_tree.as_contiguous(&self.buffers)
+    }
+
+    /// Call `f` with each piece's document offset and bytes, in document order
+    /// Pieces in unloaded buffers are skipped; see `PieceTree::visit_leaves`
+    pub fn visit_chunks<F: FnMut(usize, &[u8]) -> ControlFlow<()>>(&self, f: F) -> ControlFlow<()> {
+        self.piece_tree.visit_leaves(&self.buffers, f)
+    }
+
+    /// Hash of all text, comparable with `piece_tree::content_hash` of a file's bytes
+    /// Returns None if any buffers are unloaded (lazy loading)
+    pub fn content_hash(&self) -> Option<u64> {
+        self.piece_tree.content_hash(&self.buffers)
+    }
+
     /// Get all text as a single Vec<u8>
     /// Returns None if any buffers are unloaded (lazy loading)
     /// CRATE-PRIVATE: External code should use get_text_range_mut() or DocumentModel methods
@@ -1143,6 +1444,9 @@ impl TextBuffer {
     /// Returns None if any buffers are unloaded (lazy loading)
     /// CRATE-PRIVATE: External code should use get_text_range_mut() or DocumentModel methods
     pub(crate) fn get_all_text_string(&self) -> Option<String> {
+        if let Some(bytes) = self.as_contiguous() {
+            return Some(String::from_utf8_lossy(bytes).into_owned());
+        }
         self.get_all_text()
             .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
     }
@@ -1467,12 +1771,67 @@ impl TextBuffer {
         self.get_text_range(start, bytes)
     }
 
+    /// Get the rendered width in columns of a line, without its line ending
+    ///
+    /// Wide characters count as two columns and tabs expand to the next
+    /// multiple of `tab_width`. Returns None if the line doesn't exist or
+    /// isn't loaded.
+    pub fn line_width(&self, line: usize, tab_width: usize) -> Option<usize> {
+        let bytes = self.get_line(line)?;
+        let text = String::from_utf8_lossy(&bytes);
+        let text = text.trim_end_matches(['\n', '\r']);
+        Some(crate::primitives::display_width::str_width_with_tabs(
+            text, tab_width,
+        ))
+    }
+
     /// Get the byte offset where a line starts
     pub fn line_start_offset(&self, line: usize) -> Option<usize> {
         let (start, _) = self.piece_tree.line_range(line, &self.buffers)?;
         Some(start)
     }
 
+    /// The bytes that deleting the whole line at `position` removes
+    ///
+    /// That is the line with its line break. The last line has none (it is
+    /// empty if the buffer ends with a line feed), so the line break before it
+    /// goes instead, leaving no empty line behind. Returns None if there is
+    /// nothing to delete.
+    pub fn line_deletion_range(
+        &mut self,
+        position: usize,
+        estimated_line_length: usize,
+    ) -> Option<Range<usize>> {
+        let mut iter = self.line_iterator(position, estimated_line_length);
+        let start = iter.current_position();
+        let end = start + iter.next().map_or(0, |(_, content)| content.len());
+        if end > start && self.slice_bytes(end - 1..end) == b"\n" {
+            return Some(start..end);
+        }
+        let line_break = if start >= 2 && self.slice_bytes(start - 2..start) == b"\r\n" {
+            2
+        } else {
+            usize::from(start > 0)
+        };
+        (start - line_break < end).then(|| start - line_break..end)
+    }
+
+    /// Merge runs of small pieces into single pieces of at most `max_piece_bytes`
+    ///
+    /// A cheaper alternative to rebuilding the piece tree for reducing
+    /// fragmentation after many small edits. Returns the number of pieces removed.
+    pub fn compact(&mut self, max_piece_bytes: usize) -> usize {
+        let removed = self.piece_tree.compact(max_piece_bytes, &mut self.buffers);
+        self.next_buffer_id = self.buffers.len();
+        removed
+    }
+
+    /// Get the document offset and bytes of the leaf (piece) holding a byte offset
+    /// Returns None past the end of the buffer or if that piece isn't loaded
+    pub fn leaf_at(&self, offset: usize) -> Option<(usize, &[u8])> {
+        self.piece_tree.leaf_at(offset, &self.buffers)
+    }
+
     /// Get piece information at a byte offset
     pub fn piece_info_at_offset(&self, offset: usize) -> Option<PieceInfo> {
         self.piece_tree.find_by_offset(offset)
@@ -1589,19 +1948,25 @@ impl TextBuffer {
 
     /// Find the next occurrence of a regex pattern, with wrap-around
     pub fn find_next_regex(&self, regex: &Regex, start_pos: usize) -> Option<usize> {
+        self.find_next_regex_match(regex, start_pos)
+            .map(|found| found.start)
+    }
+
+    /// Find the byte range of the next regex match, with wrap-around
+    pub fn find_next_regex_match(&self, regex: &Regex, start_pos: usize) -> Option<Range<usize>> {
         let buffer_len = self.len();
 
         // Search from start_pos to end
         if start_pos < buffer_len {
-            if let Some(offset) = self.find_regex(start_pos, buffer_len, regex) {
-                return Some(offset);
+            if let Some(found) = self.find_regex(start_pos, buffer_len, regex) {
+                return Some(found);
             }
         }
 
         // Wrap around: search from beginning to start_pos
         if start_pos > 0 {
-            if let Some(offset) = self.find_regex(0, start_pos, regex) {
-                return Some(offset);
+            if let Some(found) = self.find_regex(0, start_pos, regex) {
+                return Some(found);
             }
         }
 
@@ -1621,6 +1986,7 @@ impl TextBuffer {
 
             if search_start < search_end {
                 self.find_regex(search_start, search_end, regex)
+                    .map(|found| found.start)
             } else {
                 None
             }
@@ -1630,7 +1996,7 @@ impl TextBuffer {
     }
 
     /// Find regex pattern in a byte range using overlapping chunks
-    fn find_regex(&self, start: usize, end: usize, regex: &Regex) -> Option<usize> {
+    fn find_regex(&self, start: usize, end: usize, regex: &Regex) -> Option<Range<usize>> {
         if start >= end {
             return None;
         }
@@ -1653,7 +2019,7 @@ impl TextBuffer {
                     // Verify the match doesn't extend beyond our search range
                     let match_len = mat.end() - mat.start();
                     if absolute_pos + match_len <= end {
-                        return Some(absolute_pos);
+                        return Some(absolute_pos..absolute_pos + match_len);
                     }
                 }
             }
@@ -1669,6 +2035,12 @@ impl TextBuffer {
         }
 
         let end = range.end.min(self.len());
+        if end - range.start == replacement.len()
+            && self.overwrite_in_piece(range.start, replacement)
+        {
+            return true;
+        }
+
         if end > range.start {
             self.delete_bytes(range.start, end - range.start);
         }
@@ -1680,6 +2052,37 @@ impl TextBuffer {
         true
     }
 
+    /// Overwrite bytes at `offset` with `text` of the same length, if the
+    /// overwritten range lies within a single piece
+    ///
+    /// Uses the piece tree's in-leaf fast path, which avoids rebuilding the tree.
+    /// Returns false without modifying anything if the range spans pieces.
+    fn overwrite_in_piece(&mut self, offset: usize, text: &str) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+
+        let buffer_id = self.next_buffer_id;
+        let buffer = StringBuffer::new(buffer_id, text.as_bytes().to_vec());
+        let leaf = LeafData::new(
+            BufferLocation::Added(buffer_id),
+            0,
+            text.len(),
+            buffer.line_feed_count(),
+        );
+        // The new piece's own bytes aren't read while replacing, so its buffer
+        // is only added once the tree has taken it
+        if !self.piece_tree.replace_in_leaf(offset, leaf, &self.buffers) {
+            return false;
+        }
+
+        self.next_buffer_id += 1;
+        self.buffers.push(buffer);
+        self.modified = true;
+        self.recovery_pending = true;
+        true
+    }
+
     /// Find and replace the next occurrence of a pattern
     pub fn replace_next(
         &mut self,
@@ -1937,6 +2340,45 @@ impl TextBuffer {
         self.prev_char_boundary(pos)
     }
 
+    /// Snap position to the start of the grapheme cluster containing it
+    /// If already at a cluster boundary, returns the same position.
+    /// Unlike `snap_to_char_boundary`, this also steps back over combining
+    /// marks, ZWJ sequences and "\r\n", so a cursor never splits what is
+    /// displayed as one character. Clusters never span a line break, so only
+    /// the current line (at most `GRAPHEME_LOOKBEHIND` bytes of it) is decoded.
+    pub fn snap_to_grapheme_boundary(&self, pos: usize) -> usize {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        const GRAPHEME_LOOKBEHIND: usize = 1024;
+        // Enough to see the character at `pos` and whether it extends the cluster before it
+        const GRAPHEME_LOOKAHEAD: usize = 16;
+
+        let len = self.len();
+        let pos = self.snap_to_char_boundary(pos);
+        if pos == 0 || pos >= len {
+            return pos;
+        }
+
+        let start = self.snap_to_char_boundary(pos.saturating_sub(GRAPHEME_LOOKBEHIND));
+        let end = self.snap_to_char_boundary((pos + GRAPHEME_LOOKAHEAD).min(len));
+        let Some(bytes) = self.get_text_range(start, end - start) else {
+            // Data unloaded, return pos as fallback
+            return pos;
+        };
+        let Ok(text) = std::str::from_utf8(&bytes) else {
+            // Not valid UTF-8, so there are no clusters beyond single characters
+            return pos;
+        };
+
+        let line_start = text[..pos - start].rfind('\n').map_or(0, |i| i + 1);
+        text[line_start..]
+            .grapheme_indices(true)
+            .map(|(i, _)| start + line_start + i)
+            .take_while(|&boundary| boundary <= pos)
+            .last()
+            .unwrap_or(pos)
+    }
+
     /// Find the previous word boundary
     pub fn prev_word_boundary(&self, pos: usize) -> usize {
         if pos == 0 {
@@ -2383,6 +2825,7 @@ impl<'a> Iterator for OverlappingChunks<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::line_diff::HunkKind;
 
     #[test]
     fn test_empty_buffer() {
@@ -2417,6 +2860,176 @@ mod tests {
         assert_eq!(buffer.line_col_to_position(2, 0), 15); // Start of line 2
     }
 
+    #[test]
+    fn test_typing_keeps_piece_count_small() {
+        let mut buffer = TextBuffer::from_bytes(b"fn main() {}\n".to_vec());
+        for (i, ch) in "let x = 1;\nlet y = 2;".bytes().enumerate() {
+            buffer.insert_bytes(11 + i, vec![ch]);
+        }
+        assert_eq!(
+            buffer.to_string().unwrap(),
+            "fn main() {let x = 1;\nlet y = 2;}\n"
+        );
+        assert_eq!(buffer.line_count(), Some(3));
+        // Original text before and after, plus one piece for everything typed
+        assert_eq!(buffer.stats().leaf_count, 3);
+    }
+
+    #[test]
+    fn test_line_width() {
+        let text = "\tif x {\r\nab\tc\n中文字\n\u{65}\u{301}te\u{301}\nlast";
+        let buffer = TextBuffer::from_bytes(text.as_bytes().to_vec());
+
+        // Tabs expand to the next stop; the line ending takes no columns
+        assert_eq!(buffer.line_width(0, 4), Some(10));
+        assert_eq!(buffer.line_width(0, 8), Some(14));
+        assert_eq!(buffer.line_width(1, 4), Some(5));
+        // Wide CJK characters take two columns each
+        assert_eq!(buffer.line_width(2, 4), Some(6));
+        // Combining accents don't add to the letter they decorate
+        assert_eq!(buffer.line_width(3, 4), Some(3));
+        assert_eq!(buffer.line_width(4, 4), Some(4));
+        assert_eq!(buffer.line_width(5, 4), None);
+    }
+
+    #[test]
+    fn test_tracked_edits_return_affected_ranges() {
+        let mut buffer = TextBuffer::from_bytes(b"hello world".to_vec());
+
+        let inserted = buffer.insert_tracked(5, ", big");
+        assert_eq!(inserted, 5..10);
+        assert_eq!(buffer.slice_bytes(inserted), b", big");
+
+        let removed = buffer.delete_tracked(0..7);
+        assert_eq!(removed, 0..7);
+        assert_eq!(buffer.to_string().unwrap(), "big world");
+
+        // Ranges are clamped to what actually changed
+        assert_eq!(buffer.delete_tracked(6..20), 6..9);
+        assert_eq!(buffer.to_string().unwrap(), "big wo");
+        assert_eq!(buffer.delete_tracked(10..12), 6..6);
+        assert_eq!(buffer.insert_tracked(20, "!"), 20..20);
+        assert_eq!(buffer.insert_tracked(6, ""), 6..6);
+        assert_eq!(buffer.insert_tracked(6, "rld"), 6..9);
+        assert_eq!(buffer.to_string().unwrap(), "big world");
+    }
+
+    #[test]
+    fn test_char_indexed_insert_and_remove() {
+        // "é" and "→" are 2 and 3 bytes, so char and byte indices diverge after them
+        let mut buffer = TextBuffer::from_bytes("aé→b".as_bytes().to_vec());
+
+        buffer.insert_char_index(3, "Ω".as_bytes()).unwrap();
+        assert_eq!(buffer.to_string().unwrap(), "aé→Ωb");
+        buffer.insert_char_index(5, b"!").unwrap();
+        assert_eq!(buffer.to_string().unwrap(), "aé→Ωb!");
+
+        // Chars 1..4 are "é→Ω", bytes 1..8
+        assert_eq!(buffer.remove_char_range(1..4), Some(1..8));
+        assert_eq!(buffer.to_string().unwrap(), "ab!");
+
+        // Out of range indices change nothing
+        assert!(buffer.insert_char_index(4, b"x").is_none());
+        assert_eq!(buffer.remove_char_range(2..9), None);
+        assert_eq!(buffer.to_string().unwrap(), "ab!");
+    }
+
+    #[test]
+    fn test_snap_to_grapheme_boundary() {
+        // "añb" with a precomposed ñ (2 bytes) and "\r\n"
+        let buffer = TextBuffer::from_bytes("añb\r\nc".as_bytes().to_vec());
+        assert_eq!(buffer.snap_to_grapheme_boundary(0), 0);
+        assert_eq!(buffer.snap_to_grapheme_boundary(1), 1);
+        assert_eq!(buffer.snap_to_grapheme_boundary(2), 1); // inside ñ
+        assert_eq!(buffer.snap_to_grapheme_boundary(3), 3);
+        assert_eq!(buffer.snap_to_grapheme_boundary(5), 4); // between \r and \n
+        assert_eq!(buffer.snap_to_grapheme_boundary(6), 6);
+        assert_eq!(buffer.snap_to_grapheme_boundary(100), 7);
+
+        // "e" + combining acute accent + "x", with the accent in its own piece
+        let mut buffer = TextBuffer::from_bytes(b"ex".to_vec());
+        buffer.insert(1, "\u{301}");
+        assert_eq!(buffer.to_string().unwrap(), "e\u{301}x");
+        assert_eq!(buffer.snap_to_grapheme_boundary(1), 0); // before the accent
+        assert_eq!(buffer.snap_to_grapheme_boundary(2), 0); // inside the accent
+        assert_eq!(buffer.snap_to_grapheme_boundary(3), 3);
+
+        // A family emoji joined with ZWJs is a single cluster after "a"
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let buffer = TextBuffer::from_bytes(format!("a{family}b").into_bytes());
+        for pos in 1..1 + family.len() {
+            assert_eq!(buffer.snap_to_grapheme_boundary(pos), 1, "pos {pos}");
+        }
+        assert_eq!(
+            buffer.snap_to_grapheme_boundary(1 + family.len() + 1),
+            1 + family.len() + 1
+        );
+    }
+
+    fn hunks_against_saved(buffer: &TextBuffer) -> Vec<(HunkKind, Range<usize>, Range<usize>)> {
+        buffer
+            .diff_against_saved()
+            .into_iter()
+            .map(|hunk| (hunk.kind, hunk.saved, hunk.current))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_against_saved() {
+        use HunkKind::*;
+        let saved = b"one\ntwo\nthree\nfour\n".to_vec();
+
+        let buffer = TextBuffer::from_bytes(saved.clone());
+        assert_eq!(hunks_against_saved(&buffer), vec![(Unchanged, 0..5, 0..5)]);
+
+        // Pure insertion of a line
+        let mut buffer = TextBuffer::from_bytes(saved.clone());
+        buffer.insert(4, "new\n");
+        assert_eq!(
+            hunks_against_saved(&buffer),
+            vec![
+                (Unchanged, 0..1, 0..1),
+                (Added, 1..1, 1..2),
+                (Unchanged, 1..5, 2..6),
+            ]
+        );
+
+        // Pure deletion of two lines
+        let mut buffer = TextBuffer::from_bytes(saved.clone());
+        buffer.delete(4..14);
+        assert_eq!(buffer.to_string().unwrap(), "one\nfour\n");
+        assert_eq!(
+            hunks_against_saved(&buffer),
+            vec![
+                (Unchanged, 0..1, 0..1),
+                (Removed, 1..3, 1..1),
+                (Unchanged, 3..5, 1..3),
+            ]
+        );
+
+        // Modification within a line, then appending a last line
+        let mut buffer = TextBuffer::from_bytes(saved.clone());
+        buffer.insert(6, "o");
+        buffer.insert(buffer.len(), "five");
+        assert_eq!(
+            hunks_against_saved(&buffer),
+            vec![
+                (Unchanged, 0..1, 0..1),
+                (Removed, 1..2, 1..1),
+                (Added, 2..2, 1..2),
+                (Unchanged, 2..4, 2..4),
+                (Removed, 4..5, 4..4),
+                (Added, 5..5, 4..5),
+            ]
+        );
+
+        // Typing text back in makes the diff empty again
+        let mut buffer = TextBuffer::from_bytes(saved);
+        buffer.delete(4..8);
+        buffer.insert(4, "two\n");
+        assert_eq!(hunks_against_saved(&buffer), vec![(Unchanged, 0..5, 0..5)]);
+    }
+
     #[test]
     fn test_new_from_content() {
         let buffer = TextBuffer::from_bytes(b"hello\nworld".to_vec());
@@ -2424,6 +3037,47 @@ mod tests {
         assert_eq!(buffer.line_count(), Some(2));
     }
 
+    #[test]
+    fn test_replace_range_same_length_overwrite() {
+        let mut buffer = TextBuffer::from_bytes(b"hello\nworld\nagain".to_vec());
+        let leaves_before = buffer.stats().leaf_count;
+
+        // Overwrite within the single original piece uses the in-leaf fast path
+        assert!(buffer.replace_range(6..11, "WORLD"));
+        assert_eq!(buffer.to_string().unwrap(), "hello\nWORLD\nagain");
+        assert_eq!(buffer.stats().leaf_count, leaves_before + 2);
+        assert!(buffer.is_modified());
+
+        // Overwriting a newline keeps line tracking correct
+        assert!(buffer.replace_range(5..6, " "));
+        assert_eq!(buffer.to_string().unwrap(), "hello WORLD\nagain");
+        assert_eq!(buffer.line_count(), Some(2));
+        assert_eq!(buffer.line_start_offset(1), Some(12));
+
+        // Ranges spanning several pieces fall back to delete + insert
+        assert!(buffer.replace_range(3..9, "p me o"));
+        assert_eq!(buffer.to_string().unwrap(), "help me oLD\nagain");
+        assert_eq!(buffer.line_count(), Some(2));
+    }
+
+    #[test]
+    fn test_overwrite_spanning_pieces_falls_back_to_delete_insert() {
+        let mut buffer = TextBuffer::from_bytes(b"hello world".to_vec());
+        buffer.insert(5, ",");
+        buffer.clear_modified();
+        let buffers_before = buffer.buffers.len();
+
+        // "o, w" spans three pieces, so the in-leaf path refuses it untouched
+        assert!(!buffer.overwrite_in_piece(4, "O; W"));
+        assert_eq!(buffer.to_string().unwrap(), "hello, world");
+        assert_eq!(buffer.buffers.len(), buffers_before);
+        assert!(!buffer.is_modified());
+
+        assert!(buffer.replace_range(4..8, "O; W"));
+        assert_eq!(buffer.to_string().unwrap(), "hellO; World");
+        assert!(buffer.is_modified());
+    }
+
     #[test]
     fn test_get_all_text() {
         let buffer = TextBuffer::from_bytes(b"hello\nworld".to_vec());
@@ -2545,6 +3199,42 @@ mod tests {
         assert_eq!(buffer.get_line(3), None);
     }
 
+    #[test]
+    fn test_trailing_newline_ends_with_empty_line() {
+        let mut terminated = TextBuffer::from_bytes(b"a\nb\n".to_vec());
+        assert!(terminated.ends_with_newline());
+        assert_eq!(terminated.line_count(), Some(3));
+        assert_eq!(terminated.get_line(1), Some(b"b\n".to_vec()));
+        assert_eq!(terminated.get_line(2), Some(Vec::new()));
+        assert_eq!(terminated.get_line(3), None);
+
+        let mut unterminated = TextBuffer::from_bytes(b"a\nb".to_vec());
+        assert!(!unterminated.ends_with_newline());
+        assert_eq!(unterminated.line_count(), Some(2));
+        assert_eq!(unterminated.get_line(1), Some(b"b".to_vec()));
+        assert_eq!(unterminated.get_line(2), None);
+
+        // Deleting "b" takes its own line break, or the one before it if it
+        // has none, so neither buffer is left with an empty line
+        assert_eq!(terminated.line_deletion_range(2, 80), Some(2..4));
+        assert_eq!(unterminated.line_deletion_range(2, 80), Some(1..3));
+        // The empty last line goes with the line break before it
+        assert_eq!(terminated.line_deletion_range(4, 80), Some(3..4));
+        assert_eq!(
+            TextBuffer::from_bytes(b"a\r\nb".to_vec()).line_deletion_range(3, 80),
+            Some(1..4)
+        );
+        assert_eq!(unterminated.line_deletion_range(0, 80), Some(0..2));
+        assert_eq!(
+            TextBuffer::from_bytes(b"b".to_vec()).line_deletion_range(0, 80),
+            Some(0..1)
+        );
+        assert_eq!(
+            TextBuffer::from_bytes(Vec::new()).line_deletion_range(0, 80),
+            None
+        );
+    }
+
     #[test]
     fn test_multiple_operations() {
         let mut buffer = TextBuffer::from_bytes(b"line1\nline2\nline3".to_vec());
@@ -3473,6 +4163,90 @@ mod tests {
         assert_eq!(output, Vec::<u8>::new());
     }
 
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open_mmap_reads_tail_without_loading() {
+        use crate::model::piece_tree::BufferData;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("big.log");
+        let line = "2024-01-01 00:00:00 INFO request handled\n";
+        let lines = 64 * 1024 * 1024 / line.len();
+        let mut content = line.repeat(lines);
+        content.push_str("tail marker");
+        std::fs::write(&file_path, &content).unwrap();
+
+        let mut buffer = TextBuffer::open_mmap(&file_path).unwrap();
+        assert_eq!(buffer.len(), content.len());
+        let tail = buffer.get_text_range_mut(content.len() - 11, 11).unwrap();
+        assert_eq!(tail, b"tail marker");
+
+        // Reading didn't copy anything: the only buffer is still the mapping
+        assert_eq!(buffer.buffers.len(), 1);
+        assert!(matches!(buffer.buffers[0].data, BufferData::Mapped { .. }));
+
+        // An edit adds an owned buffer and leaves the mapped text around it
+        buffer.insert_bytes(line.len(), b"EDITED\n".to_vec());
+        assert!(matches!(buffer.buffers[0].data, BufferData::Mapped { .. }));
+        let head = buffer.get_text_range_mut(0, line.len() + 7).unwrap();
+        assert_eq!(head, format!("{line}EDITED\n").as_bytes());
+
+        buffer.save().unwrap();
+        let saved = std::fs::read(&file_path).unwrap();
+        assert_eq!(saved.len(), content.len() + 7);
+        assert!(saved.ends_with(b"INFO request handled\ntail marker"));
+    }
+
+    #[test]
+    fn test_external_change_status() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("notes.txt");
+        std::fs::write(&file_path, "one\n").unwrap();
+        // Give every rewrite its own mtime, whatever the filesystem's resolution
+        let set_mtime = |secs: u64| {
+            let file = std::fs::File::options()
+                .write(true)
+                .open(&file_path)
+                .unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+
+        let mut buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+        assert_eq!(buffer.external_change_status(), ExternalChange::Unchanged);
+
+        // Touching the file without changing its content isn't a change
+        set_mtime(1_000);
+        assert_eq!(buffer.external_change_status(), ExternalChange::Unchanged);
+
+        std::fs::write(&file_path, "two\n").unwrap();
+        set_mtime(2_000);
+        assert_eq!(
+            buffer.external_change_status(),
+            ExternalChange::ChangedCleanBuffer
+        );
+
+        buffer.insert_bytes(0, b"mine ".to_vec());
+        assert_eq!(
+            buffer.external_change_status(),
+            ExternalChange::ConflictBothChanged
+        );
+
+        // Keeping our edits makes the file as it is now the baseline, and our
+        // own save doesn't count as someone else's change
+        buffer.acknowledge_external_change();
+        assert_eq!(buffer.external_change_status(), ExternalChange::Unchanged);
+        buffer.save().unwrap();
+        assert_eq!(buffer.external_change_status(), ExternalChange::Unchanged);
+
+        std::fs::write(&file_path, "three\n").unwrap();
+        set_mtime(3_000);
+        assert_eq!(
+            buffer.external_change_status(),
+            ExternalChange::ChangedCleanBuffer
+        );
+    }
+
     /// Regression test: get_all_text() returns empty for large files with unloaded regions
     ///
     /// This was the root cause of a bug where recovery auto-save would save 0 bytes
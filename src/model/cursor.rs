@@ -17,6 +17,17 @@ impl Default for SelectionMode {
     }
 }
 
+/// How the terminal draws the text cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    /// A full cell, covering the character it is on
+    Block,
+    /// A thin line between characters
+    Bar,
+    /// A line under the character it is on
+    Underline,
+}
+
 /// Position in 2D coordinates (for block selection)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position2D {
@@ -33,8 +44,9 @@ pub struct Cursor {
     /// Selection anchor (if any) for visual selection - byte offset
     pub anchor: Option<usize>,
 
-    /// Desired column for vertical navigation
-    /// When moving up/down, try to stay in this column
+    /// Desired column for vertical navigation ("goal column")
+    /// When moving up/down, try to stay in this column; 0 means no goal is set.
+    /// Vertical moves keep it, horizontal moves reset it.
     pub sticky_column: usize,
 
     /// Selection mode (normal or block)
@@ -90,6 +102,11 @@ impl Cursor {
         })
     }
 
+    /// Column that vertical movement should aim for, if one is remembered
+    pub fn goal_column(&self) -> Option<usize> {
+        (self.sticky_column > 0).then_some(self.sticky_column)
+    }
+
     /// Check if this cursor has a block selection
     pub fn has_block_selection(&self) -> bool {
         self.selection_mode == SelectionMode::Block && self.block_anchor.is_some()
@@ -0,0 +1,186 @@
+/// Byte-range diagnostics attached to a buffer
+///
+/// Linters and language servers report problems against ranges of the
+/// document. The ranges are registered with the document's
+/// [`IntervalTracker`], so they follow the text as the user edits: text
+/// inserted before a diagnostic pushes it forward, text inserted inside it
+/// grows it, and deleting the text a diagnostic covers removes it.
+///
+/// Unlike overlays (which are anchored with markers and collapse to a point
+/// when their text is deleted), a diagnostic whose whole range is deleted is
+/// dropped, since the problem it described no longer exists in the buffer.
+use crate::model::interval_tracker::{Edges, IntervalTracker};
+use std::ops::Range;
+
+/// Severity of a diagnostic, ordered from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+/// A single diagnostic covering a byte range of the buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Byte range in the buffer (start inclusive, end exclusive)
+    pub range: Range<usize>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(
+        range: Range<usize>,
+        severity: DiagnosticSeverity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            range,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Severity and message of a tracked diagnostic; its range is the interval
+struct Tracked {
+    severity: DiagnosticSeverity,
+    message: String,
+}
+
+/// Register a diagnostic with a document's interval tracker
+///
+/// It's registered with [`Edges::Clip`], which keeps text inserted at the
+/// diagnostic's end outside it and drops the diagnostic once all of its text
+/// is deleted.
+pub fn add(intervals: &mut IntervalTracker, diagnostic: Diagnostic) {
+    let tracked = Tracked {
+        severity: diagnostic.severity,
+        message: diagnostic.message,
+    };
+    intervals.insert_with(diagnostic.range, Edges::Clip, tracked);
+}
+
+/// Remove every diagnostic from a document's interval tracker
+pub fn clear(intervals: &mut IntervalTracker) {
+    intervals.clear::<Tracked>();
+}
+
+/// Diagnostics that overlap the given byte range (e.g. the visible viewport),
+/// in buffer order
+///
+/// Zero-width diagnostics are included when they sit inside the range.
+pub fn in_range(
+    intervals: &IntervalTracker,
+    range: Range<usize>,
+) -> impl Iterator<Item = Diagnostic> + '_ {
+    intervals
+        .query::<Tracked>(range)
+        .into_iter()
+        .map(|(_, range, tracked)| {
+            Diagnostic::new(range, tracked.severity, tracked.message.clone())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_with(ranges: &[(usize, usize)]) -> IntervalTracker {
+        let mut list = IntervalTracker::new();
+        for &(start, end) in ranges {
+            add(
+                &mut list,
+                Diagnostic::new(start..end, DiagnosticSeverity::Error, "problem"),
+            );
+        }
+        list
+    }
+
+    fn ranges(list: &IntervalTracker) -> Vec<(usize, usize)> {
+        in_range(list, 0..usize::MAX)
+            .map(|d| (d.range.start, d.range.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_before_shifts_forward() {
+        let mut list = list_with(&[(10, 15)]);
+        list.apply_insert(5, 3);
+        assert_eq!(ranges(&list), vec![(13, 18)]);
+
+        // Inserting exactly at the start also pushes the diagnostic forward
+        list.apply_insert(13, 2);
+        assert_eq!(ranges(&list), vec![(15, 20)]);
+    }
+
+    #[test]
+    fn test_insert_inside_grows_range() {
+        let mut list = list_with(&[(10, 15)]);
+        list.apply_insert(12, 4);
+        assert_eq!(ranges(&list), vec![(10, 19)]);
+    }
+
+    #[test]
+    fn test_insert_after_is_unchanged() {
+        let mut list = list_with(&[(10, 15)]);
+        list.apply_insert(15, 4);
+        list.apply_insert(30, 4);
+        assert_eq!(ranges(&list), vec![(10, 15)]);
+    }
+
+    #[test]
+    fn test_delete_before_shifts_back() {
+        let mut list = list_with(&[(10, 15)]);
+        list.apply_remove(2..7);
+        assert_eq!(ranges(&list), vec![(5, 10)]);
+    }
+
+    #[test]
+    fn test_delete_covering_range_drops_it() {
+        let mut list = list_with(&[(10, 15), (20, 25)]);
+        list.apply_remove(8..18);
+        assert_eq!(ranges(&list), vec![(10, 15)]);
+        assert_eq!(list.len(), 1);
+
+        // Deleting exactly the diagnostic's range also drops it
+        list.apply_remove(10..15);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_delete_partial_overlap_clips() {
+        let mut list = list_with(&[(10, 20)]);
+        list.apply_remove(5..15);
+        assert_eq!(ranges(&list), vec![(5, 10)]);
+
+        let mut list = list_with(&[(10, 20)]);
+        list.apply_remove(15..25);
+        assert_eq!(ranges(&list), vec![(10, 15)]);
+    }
+
+    #[test]
+    fn test_zero_width_diagnostic_at_deletion_edge_survives() {
+        let mut list = list_with(&[(10, 10)]);
+        list.apply_remove(10..15);
+        assert_eq!(ranges(&list), vec![(10, 10)]);
+
+        list.apply_remove(5..10);
+        assert_eq!(ranges(&list), vec![(5, 5)]);
+
+        list.apply_remove(3..8);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_in_range() {
+        let list = list_with(&[(0, 5), (10, 15), (20, 20), (30, 40)]);
+        let visible: Vec<_> = in_range(&list, 12..25)
+            .map(|d| (d.range.start, d.range.end))
+            .collect();
+        assert_eq!(visible, vec![(10, 15), (20, 20)]);
+        assert_eq!(in_range(&list, 5..10).count(), 0);
+    }
+}
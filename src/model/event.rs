@@ -7,7 +7,8 @@ use std::ops::Range;
 pub struct CursorId(pub usize);
 
 impl CursorId {
-    /// Sentinel value used for inverse events during undo/redo
+    /// Sentinel value used for inverse events during undo/redo, and for edits
+    /// such as line indentation that aren't made at a cursor
     /// This indicates that the event shouldn't move any cursor
     pub const UNDO_SENTINEL: CursorId = CursorId(usize::MAX);
 }
@@ -764,6 +765,46 @@ impl EventLog {
         events
     }
 
+    /// Collapse all events appended since `start_index` into a single Batch entry
+    ///
+    /// Used to make compound operations (such as replaying a macro) a single
+    /// undo unit. Events without an inverse (overlays, viewport changes, ...)
+    /// are dropped from the group, since undo skips them anyway.
+    /// Does nothing if fewer than two events were appended since `start_index`.
+    pub fn group_since(&mut self, start_index: usize, description: String) {
+        if start_index >= self.current_index || self.current_index - start_index < 2 {
+            return;
+        }
+
+        let end_index = self.current_index;
+        // Discard any redo history along with the grouped entries
+        let events: Vec<Event> = self
+            .entries
+            .drain(start_index..)
+            .take(end_index - start_index)
+            .map(|entry| entry.event)
+            .filter(|event| event.inverse().is_some())
+            .collect();
+
+        if !events.is_empty() {
+            self.entries.push(LogEntry::new(Event::Batch {
+                events,
+                description,
+            }));
+        }
+        self.current_index = self.entries.len();
+
+        // A saved position at the end of the group moves with it; one inside
+        // the group (or in the discarded redo history) no longer exists
+        if let Some(saved_idx) = self.saved_at_index {
+            if saved_idx == end_index {
+                self.saved_at_index = Some(self.current_index);
+            } else if saved_idx > start_index {
+                self.saved_at_index = None;
+            }
+        }
+    }
+
     /// Get all events from the log
     pub fn entries(&self) -> &[LogEntry] {
         &self.entries
@@ -1050,4 +1091,57 @@ mod tests {
         assert_eq!(log.entries().len(), 2);
         assert_eq!(log.current_index(), 2);
     }
+
+    #[test]
+    fn test_group_since_is_single_undo_unit() {
+        let mut log = EventLog::new();
+        log.append(Event::Insert {
+            position: 0,
+            text: "a".to_string(),
+            cursor_id: CursorId(0),
+        });
+
+        let start = log.current_index();
+        for i in 0..3 {
+            log.append(Event::Insert {
+                position: 1 + i * 2,
+                text: "x".to_string(),
+                cursor_id: CursorId(0),
+            });
+            log.append(Event::MoveCursor {
+                cursor_id: CursorId(0),
+                old_position: 2 + i * 2,
+                new_position: 3 + i * 2,
+                old_anchor: None,
+                new_anchor: None,
+                old_sticky_column: 0,
+                new_sticky_column: 0,
+            });
+        }
+        log.append(Event::SetViewport { top_line: 0 });
+        log.group_since(start, "Play macro".to_string());
+
+        assert_eq!(log.entries().len(), 2);
+        assert_eq!(log.current_index(), 2);
+
+        // One undo reverts the whole group (the non-invertible viewport event is dropped)
+        let undone = log.undo();
+        assert_eq!(undone.len(), 1);
+        match &undone[0] {
+            Event::Batch { events, .. } => assert_eq!(events.len(), 6),
+            other => panic!("Expected Batch event, got {:?}", other),
+        }
+        assert_eq!(log.current_index(), 1);
+
+        // Grouping a single event is a no-op
+        log.redo();
+        let start = log.current_index();
+        log.append(Event::Insert {
+            position: 0,
+            text: "y".to_string(),
+            cursor_id: CursorId(0),
+        });
+        log.group_since(start, "Play macro".to_string());
+        assert_eq!(log.entries().len(), 3);
+    }
 }
@@ -0,0 +1,367 @@
+//! Byte ranges that follow edits, shared by every feature of a document
+//!
+//! Overlays, virtual text, margin line indicators, named marks and
+//! diagnostics all need positions that stay on the same text as the document
+//! changes. They register them with the document's [`IntervalTracker`] (the
+//! editor state's marker list), so one [`apply_insert`] or [`apply_remove`]
+//! per edit moves all of them, instead of each feature adjusting its own
+//! offsets.
+//!
+//! Each interval carries a value of whatever type the registering feature
+//! uses, and [`query`] returns only the intervals of the requested type, so
+//! features share the tracker without seeing each other's entries. Point
+//! markers (see [`MarkerList`](crate::model::marker::MarkerList)) are empty
+//! intervals with a value of their own.
+//!
+//! [`apply_insert`]: IntervalTracker::apply_insert
+//! [`apply_remove`]: IntervalTracker::apply_remove
+//! [`query`]: IntervalTracker::query
+
+use crate::model::marker_tree::IntervalTree;
+use std::any::Any;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Handle of an interval registered with an [`IntervalTracker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IntervalId(pub u64);
+
+/// How an interval reacts to edits at its end and to removal of its text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Edges {
+    /// Text inserted at the end extends the interval, and removing all of
+    /// its text leaves it empty at the start of the removal
+    #[default]
+    Grow,
+    /// Text inserted at the end stays outside the interval, and removing all
+    /// of its text stops tracking it
+    Clip,
+}
+
+/// Value of the intervals created by [`IntervalTracker::create_marker`]
+struct PointMarker;
+
+/// Typed byte ranges kept in step with the edits of a document
+///
+/// Text inserted at an interval's start goes before it. Removing text
+/// shrinks the intervals overlapping it. What happens to text inserted at an
+/// interval's end, and to an interval whose text is all removed, depends on
+/// the [`Edges`] it was registered with.
+#[derive(Default)]
+pub struct IntervalTracker {
+    tree: IntervalTree,
+    values: HashMap<IntervalId, Box<dyn Any>>,
+    /// Intervals whose edges aren't [`Edges::Grow`]
+    edges: HashMap<IntervalId, Edges>,
+}
+
+impl std::fmt::Debug for IntervalTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IntervalTracker")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+impl IntervalTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track `range`, tagged with `value`, with [`Edges::Grow`]
+    pub fn insert<T: Any>(&mut self, range: Range<usize>, value: T) -> IntervalId {
+        self.insert_with(range, Edges::Grow, value)
+    }
+
+    /// Track `range`, tagged with `value`, reacting to edits as `edges` says
+    pub fn insert_with<T: Any>(
+        &mut self,
+        range: Range<usize>,
+        edges: Edges,
+        value: T,
+    ) -> IntervalId {
+        let end = range.end.max(range.start);
+        let id = IntervalId(self.tree.insert(range.start as u64, end as u64));
+        self.values.insert(id, Box::new(value));
+        if edges != Edges::Grow {
+            self.edges.insert(id, edges);
+        }
+        id
+    }
+
+    /// Stop tracking an interval; returns false if it wasn't tracked
+    pub fn remove(&mut self, id: IntervalId) -> bool {
+        self.values.remove(&id);
+        self.edges.remove(&id);
+        self.tree.delete(id.0)
+    }
+
+    /// Stop tracking every interval with a `T` value
+    pub fn clear<T: Any>(&mut self) {
+        let ids: Vec<_> = self
+            .values
+            .iter()
+            .filter(|(_, value)| value.is::<T>())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in ids {
+            self.remove(id);
+        }
+    }
+
+    /// Current range of an interval
+    pub fn range(&self, id: IntervalId) -> Option<Range<usize>> {
+        let (start, end) = self.tree.get_position(id.0)?;
+        Some(start as usize..end as usize)
+    }
+
+    /// Current range and value of an interval, if its value is a `T`
+    pub fn get<T: Any>(&self, id: IntervalId) -> Option<(Range<usize>, &T)> {
+        let value = self.values.get(&id)?.downcast_ref::<T>()?;
+        Some((self.range(id)?, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Shift the intervals for `len` bytes inserted at `at`
+    pub fn apply_insert(&mut self, at: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        // The tree extends every interval ending at `at`; put back the ones
+        // that must not grow
+        let kept_outside: Vec<_> = self
+            .edged_intervals(at, at)
+            .into_iter()
+            .filter(|&(_, start, end, _)| start < at && at == end)
+            .collect();
+
+        self.tree.adjust_for_edit(at as u64, len as i64);
+        for (id, start, end, _) in kept_outside {
+            self.tree.set_position(id.0, start as u64, end as u64);
+        }
+    }
+
+    /// Shift and shrink the intervals for the bytes of `range` being removed
+    pub fn apply_remove(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        for (id, start, end, edges) in self.edged_intervals(range.start, range.end) {
+            let dropped = match edges {
+                Edges::Grow => false,
+                // An empty interval on either edge of the removal survives
+                _ if start == end => range.start < start && start < range.end,
+                Edges::Clip => range.start <= start && end <= range.end,
+            };
+            if dropped {
+                self.remove(id);
+            }
+        }
+
+        self.tree
+            .adjust_for_edit(range.start as u64, -(range.len() as i64));
+    }
+
+    /// Intervals touching `[start, end]` that aren't [`Edges::Grow`]
+    fn edged_intervals(&self, start: usize, end: usize) -> Vec<(IntervalId, usize, usize, Edges)> {
+        if self.edges.is_empty() {
+            return Vec::new();
+        }
+        self.tree
+            .query(start as u64, end as u64)
+            .into_iter()
+            .filter_map(|marker| {
+                let id = IntervalId(marker.id);
+                let edges = *self.edges.get(&id)?;
+                Some((
+                    id,
+                    marker.interval.start as usize,
+                    marker.interval.end as usize,
+                    edges,
+                ))
+            })
+            .collect()
+    }
+
+    /// The intervals with a `T` value that overlap `range`, by position
+    ///
+    /// An empty interval counts as overlapping when it lies inside `range`.
+    pub fn query<T: Any>(&self, range: Range<usize>) -> Vec<(IntervalId, Range<usize>, &T)> {
+        let mut found: Vec<_> = self
+            .tree
+            .query(range.start as u64, range.end as u64)
+            .into_iter()
+            .filter_map(|marker| {
+                let id = IntervalId(marker.id);
+                let value = self.values.get(&id)?.downcast_ref::<T>()?;
+                let (start, end) = (marker.interval.start as usize, marker.interval.end as usize);
+                (start < range.end && range.start < end.max(start + 1)).then_some((
+                    id,
+                    start..end,
+                    value,
+                ))
+            })
+            .collect();
+        found.sort_by_key(|(id, range, _)| (range.start, range.end, *id));
+        found
+    }
+
+    // --- Point markers ---
+
+    /// Create a point marker at `position`
+    ///
+    /// Text inserted at the marker goes before it, and removing text around
+    /// it collapses it to the start of the removal.
+    pub fn create_marker(&mut self, position: usize) -> IntervalId {
+        self.insert(position..position, PointMarker)
+    }
+
+    /// Current byte position of a marker (the start, for an interval)
+    pub fn marker_position(&self, id: IntervalId) -> Option<usize> {
+        self.range(id).map(|range| range.start)
+    }
+
+    /// Number of point markers
+    pub fn marker_count(&self) -> usize {
+        self.values
+            .values()
+            .filter(|value| value.is::<PointMarker>())
+            .count()
+    }
+
+    /// Every marker and interval touching `[start, end]`, as (id, start, end)
+    ///
+    /// Unlike [`query`](Self::query) this is inclusive at both ends and not
+    /// filtered by type, so callers look up the ids they own.
+    pub fn query_range(&self, start: usize, end: usize) -> Vec<(IntervalId, usize, usize)> {
+        self.tree
+            .query(start as u64, end as u64)
+            .into_iter()
+            .map(|m| {
+                (
+                    IntervalId(m.id),
+                    m.interval.start as usize,
+                    m.interval.end as usize,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Fold;
+
+    #[derive(Debug, PartialEq)]
+    struct Phantom(&'static str);
+
+    /// Intervals before, inside and after the edit at [10, 15), plus one around it
+    fn tracker() -> (IntervalTracker, [IntervalId; 4]) {
+        let mut tracker = IntervalTracker::new();
+        let ids = [
+            tracker.insert(2..6, Fold),
+            tracker.insert(11..13, Fold),
+            tracker.insert(20..25, Phantom("after")),
+            tracker.insert(5..30, Fold),
+        ];
+        (tracker, ids)
+    }
+
+    fn ranges(tracker: &IntervalTracker, ids: &[IntervalId]) -> Vec<Range<usize>> {
+        ids.iter().map(|&id| tracker.range(id).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_insert_shifts_intervals_after_and_grows_spanning_ones() {
+        let (mut tracker, ids) = tracker();
+        tracker.apply_insert(12, 5);
+        assert_eq!(ranges(&tracker, &ids), vec![2..6, 11..18, 25..30, 5..35]);
+
+        // At a start the text goes before the interval, at an end it extends it
+        tracker.apply_insert(25, 1);
+        tracker.apply_insert(6, 2);
+        assert_eq!(ranges(&tracker, &ids), vec![2..8, 13..20, 28..33, 5..38]);
+    }
+
+    #[test]
+    fn test_remove_shrinks_and_collapses_intervals() {
+        let (mut tracker, ids) = tracker();
+        tracker.apply_remove(10..15);
+        assert_eq!(ranges(&tracker, &ids), vec![2..6, 10..10, 15..20, 5..25]);
+
+        // Removing across an interval's end or start cuts it back
+        tracker.apply_remove(4..8);
+        assert_eq!(ranges(&tracker, &ids), vec![2..4, 6..6, 11..16, 4..21]);
+        tracker.apply_remove(0..12);
+        assert_eq!(ranges(&tracker, &ids), vec![0..0, 0..0, 0..4, 0..9]);
+    }
+
+    #[test]
+    fn test_query_returns_overlapping_intervals_of_one_type() {
+        let (mut tracker, ids) = tracker();
+        let folds: Vec<_> = tracker
+            .query::<Fold>(6..12)
+            .into_iter()
+            .map(|(id, range, _)| (id, range))
+            .collect();
+        // 2..6 ends where the query starts, so it doesn't overlap
+        assert_eq!(folds, vec![(ids[3], 5..30), (ids[1], 11..13)]);
+        assert_eq!(
+            tracker.query::<Phantom>(0..100),
+            vec![(ids[2], 20..25, &Phantom("after"))]
+        );
+        assert_eq!(tracker.get::<Phantom>(ids[2]).unwrap().1, &Phantom("after"));
+        assert!(tracker.get::<Fold>(ids[2]).is_none());
+
+        // An empty interval is found when it lies inside the query
+        tracker.apply_remove(10..15);
+        assert_eq!(tracker.query::<Fold>(10..11).len(), 2);
+        assert_eq!(tracker.query::<Fold>(9..10).len(), 1);
+
+        assert!(tracker.remove(ids[1]));
+        assert!(!tracker.remove(ids[1]));
+        assert_eq!(tracker.len(), 3);
+    }
+
+    #[test]
+    fn test_clip_edges_keep_inserts_at_the_end_out_and_drop_removed_intervals() {
+        let mut tracker = IntervalTracker::new();
+        let clipped = tracker.insert_with(10..15, Edges::Clip, Fold);
+        let point = tracker.insert_with(20..20, Edges::Clip, Fold);
+
+        tracker.apply_insert(15, 4);
+        tracker.apply_insert(12, 4);
+        assert_eq!(ranges(&tracker, &[clipped, point]), vec![10..19, 28..28]);
+
+        // Partial removals clip, a point on the removal's edge survives
+        tracker.apply_remove(5..12);
+        tracker.apply_remove(21..25);
+        assert_eq!(ranges(&tracker, &[clipped, point]), vec![5..12, 21..21]);
+
+        tracker.apply_remove(5..12);
+        tracker.apply_remove(13..15);
+        assert_eq!(tracker.range(clipped), None);
+        assert_eq!(tracker.range(point), None);
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_clear_removes_one_type() {
+        let (mut tracker, ids) = tracker();
+        tracker.clear::<Fold>();
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(tracker.range(ids[2]), Some(20..25));
+    }
+}
@@ -299,6 +299,99 @@ pub fn merge_ranges(ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
     merged
 }
 
+/// Which side(s) of a side-by-side diff a run of lines belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkKind {
+    /// Lines present in both versions
+    Unchanged,
+    /// Lines only in the current version
+    Added,
+    /// Lines only in the saved version
+    Removed,
+}
+
+/// A run of lines with the same `HunkKind`
+///
+/// Both ranges are line indices; the side a hunk is missing from gets an
+/// empty range at the point where the lines would be. A modified line shows
+/// up as a `Removed` hunk followed by an `Added` one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineHunk {
+    pub kind: HunkKind,
+    /// Lines in the saved version
+    pub saved: Range<usize>,
+    /// Lines in the current version
+    pub current: Range<usize>,
+}
+
+/// Compare two texts line by line, covering every line of both with hunks
+pub fn diff_hunks(saved: &[u8], current: &[u8]) -> Vec<LineHunk> {
+    let saved_lines: Vec<&[u8]> = saved.split(|&b| b == b'\n').collect();
+    let current_lines: Vec<&[u8]> = current.split(|&b| b == b'\n').collect();
+    let mut hunks = Vec::new();
+    push_line_hunks(&mut hunks, &saved_lines, &current_lines, 0, 0);
+    hunks
+}
+
+/// Append the hunks for two runs of lines starting at the given line indices
+///
+/// A hunk continuing the kind of the last one in `hunks` is merged into it, so
+/// a diff can be assembled from pieces (e.g. unchanged regions known without
+/// looking at their lines).
+pub(crate) fn push_line_hunks(
+    hunks: &mut Vec<LineHunk>,
+    saved: &[&[u8]],
+    current: &[&[u8]],
+    saved_base: usize,
+    current_base: usize,
+) {
+    let mut saved_idx = 0;
+    let mut current_idx = 0;
+    let lcs = longest_common_subsequence(saved, current);
+    let ends = LineMatch {
+        saved_idx: saved.len(),
+        current_idx: current.len(),
+    };
+
+    for m in lcs.iter().chain(std::iter::once(&ends)) {
+        let removed = m.saved_idx - saved_idx;
+        let added = m.current_idx - current_idx;
+        let at = (saved_base + saved_idx, current_base + current_idx);
+        push_hunk(hunks, HunkKind::Removed, at, (removed, 0));
+        let at = (at.0 + removed, at.1);
+        push_hunk(hunks, HunkKind::Added, at, (0, added));
+        if m.saved_idx < saved.len() {
+            let at = (saved_base + m.saved_idx, current_base + m.current_idx);
+            push_hunk(hunks, HunkKind::Unchanged, at, (1, 1));
+        }
+        saved_idx = m.saved_idx + 1;
+        current_idx = m.current_idx + 1;
+    }
+}
+
+/// Append `len` (saved, current) lines of `kind` starting at `at`
+pub(crate) fn push_hunk(
+    hunks: &mut Vec<LineHunk>,
+    kind: HunkKind,
+    at: (usize, usize),
+    len: (usize, usize),
+) {
+    if len == (0, 0) {
+        return;
+    }
+    match hunks.last_mut() {
+        Some(last) if last.kind == kind => {
+            last.saved.end += len.0;
+            last.current.end += len.1;
+        }
+        _ => hunks.push(LineHunk {
+            kind,
+            saved: at.0..at.0 + len.0,
+            current: at.1..at.1 + len.1,
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
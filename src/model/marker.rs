@@ -3,60 +3,29 @@
 /// This module provides a marker system where markers automatically adjust
 /// their positions when text is inserted or deleted.
 ///
-/// **Implementation Note:**
-/// The MarkerList struct provides backward-compatible API using the old Vec-based
-/// implementation (O(n) operations). For performance-critical use cases with many
-/// markers, use IntervalTree directly from marker_tree module (O(log n) operations).
-///
-/// The Vec-based implementation is kept for compatibility and simplicity in
-/// situations where marker count is low (<100).
-use std::collections::HashMap;
+/// The marker list is the document's [`IntervalTracker`]: markers are empty
+/// intervals in it, alongside the typed intervals other features register,
+/// so one adjustment per edit moves all of them. It dereferences to the
+/// tracker for registering and querying those intervals.
+use std::ops::{Deref, DerefMut};
 
-use crate::model::marker_tree::IntervalTree;
+use crate::model::interval_tracker::IntervalTracker;
 
 /// Unique identifier for a marker
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct MarkerId(pub u64);
-
-/// Entry in the marker list - either a gap (content bytes) or a marker
-#[derive(Debug, Clone, PartialEq)]
-pub enum MarkerEntry {
-    /// A gap representing N bytes of buffer content
-    Gap(usize),
-
-    /// A marker at this position
-    Marker {
-        id: MarkerId,
-        /// Insertion affinity:
-        /// - true (left): marker stays before text inserted at this position
-        /// - false (right): marker moves after text inserted at this position
-        left_affinity: bool,
-    },
-}
+pub use crate::model::interval_tracker::IntervalId as MarkerId;
 
-/// Marker list implementation using IntervalTree for O(log n) operations
-///
-/// This provides a backward-compatible API for the old Vec-based implementation,
-/// but uses IntervalTree internally for better performance with many markers.
+/// Point markers over the document's interval tracker
 ///
 /// Point markers (single positions) are represented as zero-length intervals.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct MarkerList {
-    /// Internal interval tree for O(log n) operations
-    tree: IntervalTree,
-
-    /// Track affinity for compatibility (though IntervalTree handles this through intervals)
-    /// We don't strictly need this for the tree, but keep it for API compatibility
-    _affinity_map: HashMap<MarkerId, bool>,
+    intervals: IntervalTracker,
 }
 
 impl MarkerList {
     /// Create a new empty marker list
     pub fn new() -> Self {
-        Self {
-            tree: IntervalTree::new(),
-            _affinity_map: HashMap::new(),
-        }
+        Self::default()
     }
 
     /// Create a new marker at the given position
@@ -73,15 +42,7 @@ impl MarkerList {
     /// differs slightly from explicit affinity for zero-length markers at exact edit
     /// positions. In practice, this doesn't affect the LSP diagnostics use case.
     pub fn create(&mut self, position: usize, left_affinity: bool) -> MarkerId {
-        let pos = position as u64;
-
-        // Create a zero-length interval for point markers
-        // The IntervalTree handles affinity through its interval spanning logic
-        let tree_id = self.tree.insert(pos, pos);
-        let id = MarkerId(tree_id);
-
-        // Store affinity for compatibility (though not strictly needed by tree)
-        self._affinity_map.insert(id, left_affinity);
+        let id = self.intervals.create_marker(position);
 
         tracing::trace!(
             "Created marker {:?} at position {} with {} affinity",
@@ -95,8 +56,7 @@ impl MarkerList {
 
     /// Delete a marker
     pub fn delete(&mut self, id: MarkerId) {
-        self.tree.delete(id.0);
-        self._affinity_map.remove(&id);
+        self.intervals.remove(id);
     }
 
     /// Get the current byte position of a marker
@@ -104,14 +64,15 @@ impl MarkerList {
     /// For point markers (zero-length intervals), returns the start position.
     /// Cost: O(log n) with the IntervalTree implementation.
     pub fn get_position(&self, id: MarkerId) -> Option<usize> {
-        let (start, _end) = self.tree.get_position(id.0)?;
-        Some(start as usize)
+        self.intervals.marker_position(id)
     }
 
     /// Query all markers that overlap with a byte range
     ///
     /// This is an efficient way to find all markers in a viewport/visible region.
-    /// Returns a Vec of (MarkerId, start_position, end_position) tuples.
+    /// Returns a Vec of (MarkerId, start_position, end_position) tuples. The
+    /// intervals other features registered are included, so callers look up
+    /// the ids they own.
     ///
     /// Cost: O(log n + k) where k is the number of overlapping markers
     ///
@@ -121,17 +82,7 @@ impl MarkerList {
     /// let visible_markers = marker_list.query_range(viewport_start, viewport_end);
     /// ```
     pub fn query_range(&self, start: usize, end: usize) -> Vec<(MarkerId, usize, usize)> {
-        self.tree
-            .query(start as u64, end as u64)
-            .into_iter()
-            .map(|m| {
-                (
-                    MarkerId(m.id),
-                    m.interval.start as usize,
-                    m.interval.end as usize,
-                )
-            })
-            .collect()
+        self.intervals.query_range(start, end)
     }
 
     /// Adjust all markers for an insertion
@@ -140,14 +91,9 @@ impl MarkerList {
     /// * `position` - Byte offset where text was inserted
     /// * `length` - Number of bytes inserted
     ///
-    /// Delegates to IntervalTree's adjust_for_edit with positive delta.
     /// Cost: O(log n)
     pub fn adjust_for_insert(&mut self, position: usize, length: usize) {
-        if length == 0 {
-            return;
-        }
-
-        self.tree.adjust_for_edit(position as u64, length as i64);
+        self.intervals.apply_insert(position, length);
     }
 
     /// Adjust all markers for a deletion
@@ -156,30 +102,15 @@ impl MarkerList {
     /// * `position` - Byte offset where deletion starts
     /// * `length` - Number of bytes deleted
     ///
-    /// Delegates to IntervalTree's adjust_for_edit with negative delta.
-    /// Markers within the deleted range are automatically handled by the tree.
+    /// Markers within the deleted range collapse to its start.
     /// Cost: O(log n)
     pub fn adjust_for_delete(&mut self, position: usize, length: usize) {
-        if length == 0 {
-            return;
-        }
-
-        self.tree.adjust_for_edit(position as u64, -(length as i64));
-    }
-
-    /// Get the total size of the buffer (not directly tracked by IntervalTree)
-    ///
-    /// Note: This method is kept for API compatibility but is no longer used internally.
-    /// The buffer size is managed by the Buffer struct, not by markers.
-    pub fn buffer_size(&self) -> usize {
-        // Find the maximum end position among all markers
-        // This is an approximation - the actual buffer size should be tracked separately
-        0 // The buffer size is not tracked by markers in the tree-based implementation
+        self.intervals.apply_remove(position..position + length);
     }
 
     /// Get the number of markers
     pub fn marker_count(&self) -> usize {
-        self._affinity_map.len()
+        self.intervals.marker_count()
     }
 
     /// Set the initial buffer size (for tests)
@@ -191,15 +122,6 @@ impl MarkerList {
         // No-op: IntervalTree doesn't track buffer size
     }
 
-    /// Iterate through entries (for testing and debugging)
-    ///
-    /// Note: Not supported in IntervalTree implementation as there are no "entries".
-    /// This returns an empty slice for compatibility.
-    #[cfg(test)]
-    pub fn entries(&self) -> &[MarkerEntry] {
-        &[]
-    }
-
     /// Check invariants (for testing)
     ///
     /// Note: IntervalTree has its own internal invariants. This is a compatibility stub.
@@ -208,108 +130,19 @@ impl MarkerList {
         // IntervalTree maintains its own invariants internally
         Ok(())
     }
+}
 
-    // --- Line Anchor Methods ---
-
-    /// Create a line anchor at a specific byte range
-    ///
-    /// This creates a marker that represents a line with an estimated line number.
-    /// The byte positions are exact, but the line number may be estimated.
-    pub fn create_line_anchor(
-        &mut self,
-        start: usize,
-        end: usize,
-        estimated_line: usize,
-        confidence: crate::model::marker_tree::AnchorConfidence,
-    ) -> MarkerId {
-        let tree_id =
-            self.tree
-                .insert_line_anchor(start as u64, end as u64, estimated_line, confidence);
-        MarkerId(tree_id)
-    }
-
-    /// Get the line number and confidence for a line anchor
-    pub fn get_line_anchor_info(
-        &self,
-        id: MarkerId,
-    ) -> Option<(usize, crate::model::marker_tree::AnchorConfidence)> {
-        let marker = self.tree.get_marker(id.0)?;
-        match marker.marker_type {
-            crate::model::marker_tree::MarkerType::LineAnchor {
-                estimated_line,
-                confidence,
-            } => Some((estimated_line, confidence)),
-            _ => None,
-        }
-    }
-
-    /// Update a line anchor's line number and confidence
-    pub fn update_line_anchor(
-        &mut self,
-        id: MarkerId,
-        estimated_line: usize,
-        confidence: crate::model::marker_tree::AnchorConfidence,
-    ) -> bool {
-        self.tree
-            .update_line_anchor(id.0, estimated_line, confidence)
-    }
-
-    /// Query all line anchors in a byte range
-    pub fn query_line_anchors(
-        &self,
-        start: usize,
-        end: usize,
-    ) -> Vec<(MarkerId, usize, usize, usize)> {
-        self.tree
-            .query_line_anchors(start as u64, end as u64)
-            .into_iter()
-            .filter_map(|m| {
-                if let crate::model::marker_tree::MarkerType::LineAnchor {
-                    estimated_line, ..
-                } = m.marker_type
-                {
-                    Some((
-                        MarkerId(m.id),
-                        m.interval.start as usize,
-                        m.interval.end as usize,
-                        estimated_line,
-                    ))
-                } else {
-                    None
-                }
-            })
-            .collect()
-    }
-
-    /// Find the nearest line anchor before a given byte position
-    pub fn nearest_line_anchor_before(
-        &self,
-        byte_offset: usize,
-    ) -> Option<(MarkerId, usize, usize, usize)> {
-        // Query from 0 to byte_offset to get all anchors before
-        let anchors = self.query_line_anchors(0, byte_offset);
-        // Return the one closest to byte_offset
-        anchors.into_iter().max_by_key(|(_, start, _, _)| *start)
-    }
+impl Deref for MarkerList {
+    type Target = IntervalTracker;
 
-    /// Find the nearest line anchor before a given line number
-    pub fn nearest_line_anchor_before_line(
-        &self,
-        line_num: usize,
-    ) -> Option<(MarkerId, usize, usize, usize)> {
-        // Query all anchors (we need to check line numbers, not byte positions)
-        // This is not optimal but simple - in practice we won't have many anchors
-        let all_anchors = self.query_line_anchors(0, usize::MAX);
-        all_anchors
-            .into_iter()
-            .filter(|(_, _, _, estimated_line)| *estimated_line <= line_num)
-            .max_by_key(|(_, _, _, estimated_line)| *estimated_line)
+    fn deref(&self) -> &IntervalTracker {
+        &self.intervals
     }
 }
 
-impl Default for MarkerList {
-    fn default() -> Self {
-        Self::new()
+impl DerefMut for MarkerList {
+    fn deref_mut(&mut self) -> &mut IntervalTracker {
+        &mut self.intervals
     }
 }
 
@@ -625,7 +458,7 @@ mod tests {
                 // Debug: Get full intervals (start, end) from tree
                 let intervals: Vec<_> = markers
                     .iter()
-                    .filter_map(|&m| list.tree.get_position(m.0))
+                    .filter_map(|&m| list.range(m))
                     .collect();
 
                 // Should still be in order (no inversions)
@@ -1,7 +1,6 @@
 use std::cell::{RefCell, RefMut};
 use std::cmp::{max, Ordering};
 use std::collections::HashMap;
-use std::mem;
 use std::rc::{Rc, Weak};
 
 /// Use a simple u64 for marker IDs
@@ -237,11 +236,29 @@ impl IntervalTree {
             return false;
         }
 
-        self.root = Self::delete_recursive(self.root.take(), start, id);
+        self.root = Self::delete_recursive(self.root.take(), start, id, &mut self.marker_map);
 
         self.marker_map.remove(&id).is_some()
     }
 
+    /// Moves a marker to a new interval, keeping its ID and type. Performance: O(log n)
+    pub fn set_position(&mut self, id: MarkerId, start: u64, end: u64) -> bool {
+        let Some(marker_type) = self.get_marker(id).map(|m| m.marker_type) else {
+            return false;
+        };
+        self.delete(id);
+
+        let marker = Marker {
+            id,
+            interval: Interval { start, end },
+            marker_type,
+        };
+        let new_node = Node::new(marker, Weak::new());
+        self.root = Self::insert_recursive(self.root.take(), new_node.clone());
+        self.marker_map.insert(id, new_node);
+        true
+    }
+
     /// Adjusts all markers for a text edit (insertion or deletion).
     /// Performance: O(log n) due to lazy delta propagation.
     pub fn adjust_for_edit(&mut self, pos: u64, delta: i64) {
@@ -327,7 +344,12 @@ impl IntervalTree {
     }
 
     /// Recursive helper for delete
-    fn delete_recursive(root: NodePtr, start: u64, id: MarkerId) -> NodePtr {
+    fn delete_recursive(
+        root: NodePtr,
+        start: u64,
+        id: MarkerId,
+        marker_map: &mut HashMap<MarkerId, Rc<RefCell<Node>>>,
+    ) -> NodePtr {
         // Remove unnecessary 'mut'
         let root = match root {
             Some(r) => r,
@@ -341,20 +363,23 @@ impl IntervalTree {
 
         match start.cmp(&root_start) {
             Ordering::Less => {
-                root_mut.left = Self::delete_recursive(root_mut.left.take(), start, id);
+                root_mut.left = Self::delete_recursive(root_mut.left.take(), start, id, marker_map);
             }
             Ordering::Greater => {
-                root_mut.right = Self::delete_recursive(root_mut.right.take(), start, id);
+                root_mut.right =
+                    Self::delete_recursive(root_mut.right.take(), start, id, marker_map);
             }
             Ordering::Equal => match id.cmp(&root_id) {
                 Ordering::Less => {
-                    root_mut.left = Self::delete_recursive(root_mut.left.take(), start, id);
+                    root_mut.left =
+                        Self::delete_recursive(root_mut.left.take(), start, id, marker_map);
                 }
                 Ordering::Greater => {
-                    root_mut.right = Self::delete_recursive(root_mut.right.take(), start, id);
+                    root_mut.right =
+                        Self::delete_recursive(root_mut.right.take(), start, id, marker_map);
                 }
                 Ordering::Equal => {
-                    return Self::perform_node_deletion(root_mut, Rc::clone(&root));
+                    return Self::perform_node_deletion(root_mut, Rc::clone(&root), marker_map);
                 }
             },
         }
@@ -365,7 +390,14 @@ impl IntervalTree {
     }
 
     /// Handles the actual structural changes for deletion.
-    fn perform_node_deletion(mut node: RefMut<Node>, node_rc: Rc<RefCell<Node>>) -> NodePtr {
+    ///
+    /// A node with two children takes over its successor's marker, so the
+    /// successor's entry in `marker_map` is pointed at it.
+    fn perform_node_deletion(
+        mut node: RefMut<Node>,
+        node_rc: Rc<RefCell<Node>>,
+        marker_map: &mut HashMap<MarkerId, Rc<RefCell<Node>>>,
+    ) -> NodePtr {
         if node.left.is_none() {
             let right = node.right.take();
             if let Some(ref r) = right {
@@ -381,14 +413,16 @@ impl IntervalTree {
         } else {
             let successor_rc = Self::min_node(&node.right.as_ref().unwrap());
 
-            let (successor_start, successor_id) = {
-                let s = successor_rc.borrow();
-                (s.marker.interval.start, s.marker.id)
-            };
-
-            mem::swap(&mut node.marker, &mut successor_rc.borrow_mut().marker);
+            let successor = successor_rc.borrow().marker.clone();
 
-            node.right = Self::delete_recursive(node.right.take(), successor_start, successor_id);
+            node.marker = successor.clone();
+            node.right = Self::delete_recursive(
+                node.right.take(),
+                successor.interval.start,
+                successor.id,
+                marker_map,
+            );
+            marker_map.insert(successor.id, Rc::clone(&node_rc));
 
             drop(node);
             Node::update_stats(&node_rc);
@@ -455,14 +489,23 @@ impl IntervalTree {
         } else {
             // pos > start
             // CASE 2: This node's start is BEFORE the edit.
-            // Its start is unaffected. We only need to check the right subtree
-            // for nodes that might be affected.
+            // Its start is unaffected, and so are those of the left subtree,
+            // but markers there can still span the edit pos.
             Self::adjust_recursive(&mut node.right, pos, delta);
+            let left_spans_edit = node.left.as_ref().is_some_and(|left| {
+                let left = left.borrow();
+                left.max_end as i64 + left.lazy_delta >= pos as i64
+            });
+            if left_spans_edit {
+                Self::adjust_recursive(&mut node.left, pos, delta);
+            }
         }
 
-        // Always handle the interval span case (where end >= pos)
+        // Always handle the interval span case (where end >= pos). A deletion
+        // covering the end cuts the interval back to the deletion position.
         if node.marker.interval.end >= pos {
             node.marker.interval.end = (node.marker.interval.end as i64 + delta)
+                .max(pos as i64)
                 .max(node.marker.interval.start as i64)
                 as u64;
         }
@@ -604,6 +647,28 @@ mod tests {
         assert_eq!(get_pos(&tree, id2), (30, 40));
     }
 
+    #[test]
+    fn test_delete_node_with_two_children() {
+        let mut tree = IntervalTree::new();
+        let ids: Vec<_> = [20, 10, 30, 25, 40]
+            .iter()
+            .map(|&start| insert_marker(&mut tree, start, start + 2))
+            .collect();
+
+        // The root (20) has two children; its successor (25) takes its node.
+        assert!(tree.delete(ids[0]));
+        assert_eq!(get_pos(&tree, ids[3]), (25, 27));
+
+        // The successor must still be reachable through its new node.
+        tree.adjust_for_edit(0, 5);
+        assert_eq!(get_pos(&tree, ids[3]), (30, 32));
+        assert!(tree.delete(ids[3]));
+        assert_eq!(tree.get_position(ids[3]), None);
+        assert_eq!(get_pos(&tree, ids[1]), (15, 17));
+        assert_eq!(get_pos(&tree, ids[2]), (35, 37));
+        assert_eq!(get_pos(&tree, ids[4]), (45, 47));
+    }
+
     #[test]
     fn test_basic_edit_adjustment() {
         let mut tree = IntervalTree::new();
@@ -743,6 +808,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_edit_inside_marker_with_later_start_in_tree() {
+        let mut tree = IntervalTree::new();
+        let id_outer = insert_marker(&mut tree, 5, 30);
+        let id_inner = insert_marker(&mut tree, 11, 13);
+        insert_marker(&mut tree, 20, 25);
+
+        // The edit is past the inner marker's start, which is the root here;
+        // the outer marker in its left subtree still spans the edit
+        tree.adjust_for_edit(12, 5);
+        assert_eq!(get_pos(&tree, id_outer), (5, 35));
+        assert_eq!(get_pos(&tree, id_inner), (11, 18));
+        tree.adjust_for_edit(12, -5);
+        assert_eq!(get_pos(&tree, id_outer), (5, 30));
+    }
+
+    #[test]
+    fn test_deletion_covering_marker_end() {
+        let mut tree = IntervalTree::new();
+        let id1 = insert_marker(&mut tree, 2, 12);
+
+        // Deleting [10, 15) removes the tail [10, 12) of the marker
+        tree.adjust_for_edit(10, -5);
+
+        assert_eq!(
+            get_pos(&tree, id1),
+            (2, 10),
+            "Marker end should be cut back."
+        );
+    }
+
     #[test]
     fn test_zero_length_marker() {
         let mut tree = IntervalTree::new();
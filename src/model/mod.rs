@@ -2,12 +2,15 @@
 //!
 //! This module contains pure data structures with minimal external dependencies.
 
+pub mod auto_save;
 pub mod buffer;
 pub mod control_event;
 pub mod cursor;
+pub mod diagnostic;
 pub mod document_model;
 pub mod edit;
 pub mod event;
+pub mod interval_tracker;
 pub mod line_diff;
 pub mod marker;
 pub mod marker_tree;
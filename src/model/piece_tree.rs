@@ -1,7 +1,51 @@
+use std::hash::Hasher;
 use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::{ControlFlow, Range};
 use std::path::PathBuf;
 use std::sync::Arc;
 
+/// FNV-1a hash of a byte stream, independent of how the stream is chunked
+///
+/// Used for [`PieceTree::content_hash`]; see [`content_hash`] for hashing a
+/// contiguous slice such as a file read from disk.
+#[derive(Debug, Clone, Copy)]
+pub struct ContentHasher(u64);
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for ContentHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hash of `bytes`, comparable with [`PieceTree::content_hash`]
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = ContentHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// UTF-16 code units of the character whose UTF-8 encoding starts with
+/// `byte`, or 0 for a continuation byte
+fn utf16_units(byte: u8) -> usize {
+    match byte {
+        b if b & 0xC0 == 0x80 => 0,
+        b if b >= 0xF0 => 2,
+        _ => 1,
+    }
+}
+
 /// A position in the document (line and column)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
@@ -23,6 +67,15 @@ pub enum BufferData {
         file_offset: usize, // Where in file this buffer starts
         bytes: usize,       // Length of this region
     },
+    /// Read-only region of a memory-mapped file
+    /// Readable like loaded data, but the OS pages it in on demand instead of
+    /// the buffer owning a copy.
+    #[cfg(feature = "mmap")]
+    Mapped {
+        map: Arc<memmap2::Mmap>,
+        file_offset: usize, // Where in the mapping this buffer starts
+        bytes: usize,       // Length of this region
+    },
 }
 
 /// A string buffer containing a chunk of text data and its line metadata
@@ -74,9 +127,27 @@ impl StringBuffer {
         }
     }
 
-    /// Check if buffer is loaded
+    /// Create buffer for a region of a memory-mapped file
+    #[cfg(feature = "mmap")]
+    pub fn new_mapped(
+        id: usize,
+        map: Arc<memmap2::Mmap>,
+        file_offset: usize,
+        bytes: usize,
+    ) -> Self {
+        StringBuffer {
+            id,
+            data: BufferData::Mapped {
+                map,
+                file_offset,
+                bytes,
+            },
+        }
+    }
+
+    /// Check if buffer is loaded (mapped regions count as loaded)
     pub fn is_loaded(&self) -> bool {
-        matches!(self.data, BufferData::Loaded { .. })
+        !matches!(self.data, BufferData::Unloaded { .. })
     }
 
     /// Get data reference if loaded, None if unloaded
@@ -87,6 +158,12 @@ impl StringBuffer {
         match &self.data {
             BufferData::Loaded { data, .. } => Some(data),
             BufferData::Unloaded { .. } => None,
+            #[cfg(feature = "mmap")]
+            BufferData::Mapped {
+                map,
+                file_offset,
+                bytes,
+            } => Some(&map[*file_offset..*file_offset + *bytes]),
         }
     }
 
@@ -95,6 +172,8 @@ impl StringBuffer {
         match &self.data {
             BufferData::Loaded { line_starts, .. } => line_starts.as_deref(),
             BufferData::Unloaded { .. } => None,
+            #[cfg(feature = "mmap")]
+            BufferData::Mapped { .. } => None,
         }
     }
 
@@ -103,6 +182,8 @@ impl StringBuffer {
     pub fn load(&mut self) -> io::Result<()> {
         match &self.data {
             BufferData::Loaded { .. } => Ok(()), // Already loaded
+            #[cfg(feature = "mmap")]
+            BufferData::Mapped { .. } => Ok(()), // Paged in by the OS
             BufferData::Unloaded {
                 file_path,
                 file_offset,
@@ -162,6 +243,8 @@ impl StringBuffer {
                 ))
             }
             BufferData::Loaded { .. } => None, // Can't create chunk from loaded buffer
+            #[cfg(feature = "mmap")]
+            BufferData::Mapped { .. } => None, // Already readable without loading
         }
     }
 
@@ -184,6 +267,8 @@ impl StringBuffer {
                 .as_ref()
                 .map(|starts| starts.len().saturating_sub(1)),
             BufferData::Unloaded { .. } => None,
+            #[cfg(feature = "mmap")]
+            BufferData::Mapped { .. } => None,
         }
     }
 
@@ -211,6 +296,11 @@ impl StringBuffer {
                 // Can't append to unloaded buffer
                 0
             }
+            #[cfg(feature = "mmap")]
+            BufferData::Mapped { .. } => {
+                // Mapped regions are read-only
+                0
+            }
         }
     }
 }
@@ -252,6 +342,66 @@ pub enum PieceTreeNode {
     },
 }
 
+/// Part of a tree still to be compared by `PieceTree::unchanged_len`
+#[derive(Clone, Copy)]
+enum Span<'a> {
+    /// A whole subtree and its length in bytes
+    Node(&'a Arc<PieceTreeNode>, usize),
+    /// What remains of a leaf: (location, buffer offset, bytes)
+    Piece(BufferLocation, usize, usize),
+}
+
+impl Span<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Span::Node(_, len) | Span::Piece(_, _, len) => *len,
+        }
+    }
+
+    /// Replace the node on top of `stack` with its children, the one to be
+    /// compared next on top
+    fn expand(stack: &mut Vec<Self>, from_end: bool) {
+        let Some(Span::Node(node, len)) = stack.pop() else {
+            return;
+        };
+        match node.as_ref() {
+            PieceTreeNode::Internal {
+                left_bytes,
+                left,
+                right,
+                ..
+            } => {
+                let left = Span::Node(left, *left_bytes);
+                let right = Span::Node(right, len - left_bytes);
+                if from_end {
+                    stack.extend([left, right]);
+                } else {
+                    stack.extend([right, left]);
+                }
+            }
+            PieceTreeNode::Leaf {
+                location,
+                offset,
+                bytes,
+                ..
+            } => stack.push(Span::Piece(*location, *offset, *bytes)),
+        }
+    }
+
+    /// Drop `n` compared bytes from the piece on top of `stack`
+    fn consume(stack: &mut Vec<Self>, n: usize, from_end: bool) {
+        if let Some(Span::Piece(_, offset, len)) = stack.last_mut() {
+            *len -= n;
+            if !from_end {
+                *offset += n;
+            }
+            if *len == 0 {
+                stack.pop();
+            }
+        }
+    }
+}
+
 /// Information about a piece at a specific location
 #[derive(Debug, Clone)]
 pub struct PieceInfo {
@@ -268,6 +418,15 @@ struct OffsetFindResult {
     bytes_before: usize, // Total bytes in all pieces before this one
 }
 
+/// Bytes and line feeds before a point in the document, summed on the way
+/// down the tree by `PieceTreeNode::descend_by`
+#[derive(Debug, Clone, Copy)]
+struct Prefix {
+    bytes: usize,
+    /// None if some piece before the point has an unknown line count
+    line_feeds: Option<usize>,
+}
+
 /// A cursor position in the document
 #[derive(Debug, Clone)]
 pub struct Cursor {
@@ -301,6 +460,9 @@ impl LeafData {
     }
 }
 
+/// How many characters of each leaf [`PieceTree::debug_structure`] shows
+const DEBUG_PREVIEW_CHARS: usize = 16;
+
 /// Statistics about the piece tree structure
 #[derive(Debug, Clone, Copy)]
 pub struct TreeStats {
@@ -319,49 +481,68 @@ pub struct TreeStats {
 // which is managed externally, so this is deferred until buffer integration.
 
 impl PieceTreeNode {
-    /// Find the piece containing the given byte offset
-    fn find_by_offset(&self, offset: usize) -> Option<OffsetFindResult> {
-        match self {
-            PieceTreeNode::Internal {
-                left_bytes,
-                left,
-                right,
-                ..
-            } => {
-                if offset < *left_bytes {
-                    left.find_by_offset(offset)
-                } else {
-                    // Search in right subtree
-                    right.find_by_offset(offset - left_bytes).map(|mut result| {
-                        // Adjust bytes_before to account for left subtree
-                        result.bytes_before += left_bytes;
-                        result
-                    })
+    /// Walk down to one leaf, letting `go_left` pick the branch at each
+    /// internal node
+    ///
+    /// `go_left` gets the totals before the node and the totals at the end of
+    /// its left child. Returns the leaf with the totals before it, or None if
+    /// `go_left` gives up (e.g. on unknown line counts).
+    fn descend_by<F>(&self, mut go_left: F) -> Option<(LeafData, Prefix)>
+    where
+        F: FnMut(Prefix, Prefix) -> Option<bool>,
+    {
+        let mut node = self;
+        let mut before = Prefix {
+            bytes: 0,
+            line_feeds: Some(0),
+        };
+        loop {
+            match node {
+                PieceTreeNode::Internal {
+                    left_bytes,
+                    lf_left,
+                    left,
+                    right,
+                } => {
+                    let mid = Prefix {
+                        bytes: before.bytes + left_bytes,
+                        line_feeds: before.line_feeds.zip(*lf_left).map(|(a, b)| a + b),
+                    };
+                    if go_left(before, mid)? {
+                        node = left;
+                    } else {
+                        node = right;
+                        before = mid;
+                    }
                 }
-            }
-            PieceTreeNode::Leaf {
-                location,
-                offset: piece_offset,
-                bytes,
-                ..
-            } => {
-                if offset < *bytes {
-                    Some(OffsetFindResult {
-                        info: PieceInfo {
-                            location: *location,
-                            offset: *piece_offset,
-                            bytes: *bytes,
-                            offset_in_piece: Some(offset),
-                        },
-                        bytes_before: 0,
-                    })
-                } else {
-                    None
+                PieceTreeNode::Leaf {
+                    location,
+                    offset,
+                    bytes,
+                    line_feed_cnt,
+                } => {
+                    let leaf = LeafData::new(*location, *offset, *bytes, *line_feed_cnt);
+                    return Some((leaf, before));
                 }
             }
         }
     }
 
+    /// Find the piece containing the given byte offset
+    fn find_by_offset(&self, offset: usize) -> Option<OffsetFindResult> {
+        let (leaf, before) = self.descend_by(|_, mid| Some(offset < mid.bytes))?;
+        let offset_in_piece = offset - before.bytes;
+        (offset_in_piece < leaf.bytes).then_some(OffsetFindResult {
+            info: PieceInfo {
+                location: leaf.location,
+                offset: leaf.offset,
+                bytes: leaf.bytes,
+                offset_in_piece: Some(offset_in_piece),
+            },
+            bytes_before: before.bytes,
+        })
+    }
+
     /// Get total bytes in this node
     fn total_bytes(&self) -> usize {
         match self {
@@ -404,6 +585,42 @@ impl PieceTreeNode {
         }
     }
 
+    /// Append one line for this node and each node below it to `out`, nested
+    /// `depth` levels deep
+    fn write_structure(&self, buffers: &[StringBuffer], depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        match self {
+            PieceTreeNode::Internal { left, right, .. } => {
+                out.push_str(&format!("{indent}Internal({})\n", self.total_bytes()));
+                left.write_structure(buffers, depth + 1, out);
+                right.write_structure(buffers, depth + 1, out);
+            }
+            PieceTreeNode::Leaf {
+                location,
+                offset,
+                bytes,
+                ..
+            } => {
+                let data = buffers
+                    .get(location.buffer_id())
+                    .and_then(|buffer| buffer.get_data())
+                    .and_then(|data| data.get(*offset..*offset + *bytes));
+                let Some(data) = data else {
+                    out.push_str(&format!("{indent}Leaf({bytes}) <unloaded>\n"));
+                    return;
+                };
+                let text = String::from_utf8_lossy(data);
+                let preview: String = text.chars().take(DEBUG_PREVIEW_CHARS).collect();
+                let more = if preview.len() < text.len() {
+                    "..."
+                } else {
+                    ""
+                };
+                out.push_str(&format!("{indent}Leaf({bytes}) {preview:?}{more}\n"));
+            }
+        }
+    }
+
     /// Collect all leaves in order
     fn collect_leaves(&self, leaves: &mut Vec<LeafData>) {
         match self {
@@ -422,6 +639,55 @@ impl PieceTreeNode {
         }
     }
 
+    /// Visit leaves in order, starting at document offset `start`
+    /// Returns the offset just past this subtree, or `Break` once `f` asks to stop
+    fn visit_leaves<F: FnMut(usize, &[u8]) -> ControlFlow<()>>(
+        &self,
+        buffers: &[StringBuffer],
+        start: usize,
+        f: &mut F,
+    ) -> ControlFlow<(), usize> {
+        match self {
+            PieceTreeNode::Internal { left, right, .. } => {
+                let mid = left.visit_leaves(buffers, start, f)?;
+                right.visit_leaves(buffers, mid, f)
+            }
+            PieceTreeNode::Leaf {
+                location,
+                offset,
+                bytes,
+                ..
+            } => {
+                let data = buffers
+                    .get(location.buffer_id())
+                    .and_then(|buffer| buffer.get_data())
+                    .and_then(|data| data.get(*offset..*offset + *bytes));
+                if let Some(data) = data {
+                    f(start, data)?;
+                }
+                ControlFlow::Continue(start + *bytes)
+            }
+        }
+    }
+
+    /// Buffer span covered by this subtree, if its pieces sit back to back in one buffer
+    /// Returns (location, start offset, end offset) within that buffer
+    fn contiguous_span(&self) -> Option<(BufferLocation, usize, usize)> {
+        match self {
+            PieceTreeNode::Internal { left, right, .. } => {
+                let (location, start, mid) = left.contiguous_span()?;
+                let (right_location, right_start, end) = right.contiguous_span()?;
+                (right_location == location && right_start == mid).then_some((location, start, end))
+            }
+            PieceTreeNode::Leaf {
+                location,
+                offset,
+                bytes,
+                ..
+            } => Some((*location, *offset, *offset + *bytes)),
+        }
+    }
+
     /// Count line feeds in a byte range [start, end)
     /// current_offset: byte offset at the start of this node
     /// Returns None if any piece in the range has unknown line count
@@ -480,148 +746,10 @@ impl PieceTreeNode {
             }
         }
     }
-
-    /// Find byte offset for a given line/column position
-    /// current_offset: byte offset at start of this node
-    /// lines_before: number of complete lines before this node
-    fn find_byte_offset_for_line(
-        &self,
-        current_offset: usize,
-        lines_before: usize,
-        target_line: usize,
-        column: usize,
-        buffers: &[StringBuffer],
-    ) -> Option<usize> {
-        match self {
-            PieceTreeNode::Internal {
-                left_bytes,
-                lf_left,
-                left,
-                right,
-            } => {
-                // If line count is unknown, we can't do line-based navigation
-                let lf_left = lf_left.as_ref()?;
-                let lines_after_left = lines_before + lf_left;
-
-                // When looking for line start (column == 0), we want the leftmost piece containing the line
-                // So use <= instead of < to prefer going left when the line boundary is exactly at lines_after_left
-                let go_left = if column == 0 {
-                    target_line <= lines_after_left
-                } else {
-                    target_line < lines_after_left
-                };
-
-                if go_left {
-                    // Target is in left subtree
-                    let result = left.find_byte_offset_for_line(
-                        current_offset,
-                        lines_before,
-                        target_line,
-                        column,
-                        buffers,
-                    );
-                    // If left returns None, try right as fallback (happens when line starts after a newline)
-                    result.or_else(|| {
-                        right.find_byte_offset_for_line(
-                            current_offset + left_bytes,
-                            lines_after_left,
-                            target_line,
-                            column,
-                            buffers,
-                        )
-                    })
-                } else {
-                    // Target is in right subtree
-                    right.find_byte_offset_for_line(
-                        current_offset + left_bytes,
-                        lines_after_left,
-                        target_line,
-                        column,
-                        buffers,
-                    )
-                }
-            }
-            PieceTreeNode::Leaf {
-                location,
-                offset,
-                bytes,
-                line_feed_cnt,
-            } => {
-                // If line count is unknown, we can't do line-based navigation
-                let line_feed_cnt = line_feed_cnt.as_ref()?;
-                let lines_in_piece = lines_before + line_feed_cnt;
-
-                // Special case: when looking for column==0 of line N where N == lines_in_piece,
-                // the line might start in the NEXT piece if this piece ends with a newline.
-                // Check if the last byte of this piece is a newline.
-                if column == 0 && target_line == lines_in_piece && target_line > lines_before {
-                    let buffer = buffers.get(location.buffer_id())?;
-                    let data = buffer.get_data()?;
-                    let last_byte_offset = offset + bytes - 1;
-                    let last_byte = data.get(last_byte_offset)?;
-
-                    if *last_byte == b'\n' {
-                        // Piece ends with newline, so the next line starts in the next piece
-                        return None;
-                    }
-                    // Otherwise, line starts within this piece after a newline
-                }
-
-                if target_line < lines_before || target_line > lines_in_piece {
-                    // Target line not in this piece
-                    return None;
-                }
-
-                // Get the buffer for this piece
-                let buffer_id = location.buffer_id();
-                let buffer = buffers.get(buffer_id)?;
-                let line_starts = buffer.get_line_starts()?;
-
-                // Find the line within the piece
-                let line_in_piece = target_line - lines_before;
-
-                // Get piece range in buffer
-                let piece_start_in_buffer = *offset;
-                let piece_end_in_buffer = offset + bytes;
-
-                // Special case: first line of piece (line_in_piece == 0)
-                let line_start_in_buffer = if line_in_piece == 0 {
-                    // First line starts at piece start
-                    piece_start_in_buffer
-                } else {
-                    // Find the Nth newline within this piece
-                    // Count line_starts that fall within [piece_start, piece_end)
-                    let mut lines_seen = 0;
-                    let mut found_line_start = None;
-
-                    for &line_start in line_starts.iter() {
-                        // Line starts are positions of newlines + 1, or beginning of buffer (0)
-                        // We want line_starts that are > piece_start and < piece_end
-                        if line_start > piece_start_in_buffer && line_start < piece_end_in_buffer {
-                            if lines_seen == line_in_piece - 1 {
-                                // This is the start of our target line
-                                found_line_start = Some(line_start);
-                                break;
-                            }
-                            lines_seen += 1;
-                        }
-                    }
-
-                    found_line_start?
-                };
-
-                // Add column offset
-                let target_offset_in_buffer = line_start_in_buffer + column;
-
-                // Convert to document offset
-                let offset_in_piece = target_offset_in_buffer.saturating_sub(piece_start_in_buffer);
-                Some(current_offset + offset_in_piece.min(*bytes))
-            }
-        }
-    }
 }
 
 /// The main piece table structure with integrated line tracking
+#[derive(Clone)]
 pub struct PieceTree {
     root: Arc<PieceTreeNode>,
     total_bytes: usize,
@@ -660,7 +788,22 @@ impl PieceTree {
     }
 
     /// Build a balanced tree from a list of leaves
+    ///
+    /// Empty leaves are dropped, so the only empty leaf a tree ever holds is
+    /// the root of an empty tree.
     fn build_balanced(leaves: &[LeafData]) -> Arc<PieceTreeNode> {
+        if leaves.iter().any(|leaf| leaf.bytes == 0) {
+            let non_empty: Vec<LeafData> = leaves
+                .iter()
+                .copied()
+                .filter(|leaf| leaf.bytes > 0)
+                .collect();
+            return Self::build_nodes(&non_empty);
+        }
+        Self::build_nodes(leaves)
+    }
+
+    fn build_nodes(leaves: &[LeafData]) -> Arc<PieceTreeNode> {
         if leaves.is_empty() {
             return Arc::new(PieceTreeNode::Leaf {
                 location: BufferLocation::Stored(0),
@@ -682,8 +825,8 @@ impl PieceTree {
 
         // Split in the middle
         let mid = leaves.len() / 2;
-        let left = Self::build_balanced(&leaves[..mid]);
-        let right = Self::build_balanced(&leaves[mid..]);
+        let left = Self::build_nodes(&leaves[..mid]);
+        let right = Self::build_nodes(&leaves[mid..]);
 
         let left_bytes = left.total_bytes();
         let lf_left = left.total_line_feeds();
@@ -703,6 +846,75 @@ impl PieceTree {
         self.root = Self::build_balanced(&leaves);
     }
 
+    /// Merge small subtrees into single pieces to reduce fragmentation
+    ///
+    /// Every subtree of at most `max_leaf_bytes` that holds more than one piece
+    /// is replaced by one piece, whose bytes are copied into a new added buffer
+    /// pushed onto `buffers` (buffer ids are their index). Larger subtrees keep
+    /// their shape and are only descended into, so this is much cheaper than a
+    /// rebuild after many small edits. Empty pieces are dropped, and subtrees
+    /// with unloaded data are left alone. Returns the number of pieces removed.
+    pub fn compact(&mut self, max_leaf_bytes: usize, buffers: &mut Vec<StringBuffer>) -> usize {
+        let before = self.root.count_leaves();
+        self.root = Self::compact_node(&self.root, max_leaf_bytes, buffers);
+        before - self.root.count_leaves()
+    }
+
+    fn compact_node(
+        node: &Arc<PieceTreeNode>,
+        max_leaf_bytes: usize,
+        buffers: &mut Vec<StringBuffer>,
+    ) -> Arc<PieceTreeNode> {
+        let PieceTreeNode::Internal { left, right, .. } = node.as_ref() else {
+            return Arc::clone(node);
+        };
+
+        if node.total_bytes() <= max_leaf_bytes {
+            if let Some(data) = Self::subtree_data(node, buffers) {
+                let buffer_id = buffers.len();
+                let bytes = data.len();
+                let line_feed_cnt = data.iter().filter(|&&b| b == b'\n').count();
+                buffers.push(StringBuffer::new(buffer_id, data));
+                return Arc::new(PieceTreeNode::Leaf {
+                    location: BufferLocation::Added(buffer_id),
+                    offset: 0,
+                    bytes,
+                    line_feed_cnt: Some(line_feed_cnt),
+                });
+            }
+        }
+
+        let new_left = Self::compact_node(left, max_leaf_bytes, buffers);
+        let new_right = Self::compact_node(right, max_leaf_bytes, buffers);
+        if new_left.total_bytes() == 0 {
+            return new_right;
+        }
+        if new_right.total_bytes() == 0 {
+            return new_left;
+        }
+        if Arc::ptr_eq(&new_left, left) && Arc::ptr_eq(&new_right, right) {
+            return Arc::clone(node);
+        }
+        Arc::new(PieceTreeNode::Internal {
+            left_bytes: new_left.total_bytes(),
+            lf_left: new_left.total_line_feeds(),
+            left: new_left,
+            right: new_right,
+        })
+    }
+
+    /// Bytes of every piece under `node`, or None if any of them isn't loaded
+    fn subtree_data(node: &PieceTreeNode, buffers: &[StringBuffer]) -> Option<Vec<u8>> {
+        let mut leaves = Vec::new();
+        node.collect_leaves(&mut leaves);
+        let mut data = Vec::with_capacity(node.total_bytes());
+        for leaf in leaves {
+            let buffer = buffers.get(leaf.location.buffer_id())?.get_data()?;
+            data.extend_from_slice(buffer.get(leaf.offset..leaf.offset + leaf.bytes)?);
+        }
+        Some(data)
+    }
+
     /// Check if rebalancing is needed and do it
     fn check_and_rebalance(&mut self) {
         let count = self.root.count_leaves();
@@ -726,6 +938,28 @@ impl PieceTree {
         self.root.find_by_offset(offset).map(|result| result.info)
     }
 
+    /// Find the leaf containing the given byte offset
+    ///
+    /// Returns the document offset where that leaf starts and the leaf's bytes,
+    /// found by a single O(log n) descent. Returns None past the end of the
+    /// document or if the leaf's buffer isn't loaded.
+    pub fn leaf_at<'a>(
+        &self,
+        offset: usize,
+        buffers: &'a [StringBuffer],
+    ) -> Option<(usize, &'a [u8])> {
+        if offset >= self.total_bytes {
+            return None;
+        }
+        let result = self.root.find_by_offset(offset)?;
+        let info = result.info;
+        let data = buffers.get(info.location.buffer_id())?.get_data()?;
+        Some((
+            result.bytes_before,
+            data.get(info.offset..info.offset + info.bytes)?,
+        ))
+    }
+
     /// Create a cursor at the given byte offset
     /// Note: line/col calculation should be done by LineIndex
     pub fn cursor_at_offset(&self, offset: usize) -> Cursor {
@@ -775,7 +1009,10 @@ impl PieceTree {
             // Append at end
             let mut leaves = Vec::new();
             self.root.collect_leaves(&mut leaves);
-            leaves.push(LeafData::new(location, buffer_offset, bytes, line_feed_cnt));
+            Self::push_coalesced(
+                &mut leaves,
+                LeafData::new(location, buffer_offset, bytes, line_feed_cnt),
+            );
 
             self.root = Self::build_balanced(&leaves);
             self.total_bytes += bytes;
@@ -979,7 +1216,7 @@ impl PieceTree {
 
                         // Inserted piece
                         if let Some(insert_leaf) = insert {
-                            leaves.push(insert_leaf);
+                            Self::push_coalesced(leaves, insert_leaf);
                         }
 
                         // Second part (after split)
@@ -1101,7 +1338,7 @@ impl PieceTree {
 
                     // Inserted piece
                     if let Some(insert_leaf) = insert {
-                        leaves.push(insert_leaf);
+                        Self::push_coalesced(leaves, insert_leaf);
                     }
 
                     // Second part (after split)
@@ -1123,7 +1360,7 @@ impl PieceTree {
                 } else if split_offset == current_offset {
                     // Insert before this piece
                     if let Some(insert_leaf) = insert {
-                        leaves.push(insert_leaf);
+                        Self::push_coalesced(leaves, insert_leaf);
                     }
                     leaves.push(LeafData::new(*location, *offset, *bytes, *line_feed_cnt));
                 } else {
@@ -1134,9 +1371,28 @@ impl PieceTree {
         }
     }
 
-    /// Helper to compute line feeds in a buffer range
-    fn compute_line_feeds_static(
-        buffers: &[StringBuffer],
+    /// Push an inserted leaf, extending the previous leaf instead if it ends right
+    /// where the inserted bytes start in the same buffer
+    ///
+    /// Typing appends each character to the same added buffer, so without this
+    /// every keystroke would leave behind a one-byte piece.
+    fn push_coalesced(leaves: &mut Vec<LeafData>, leaf: LeafData) {
+        if let Some(last) = leaves.last_mut() {
+            if last.location == leaf.location && last.offset + last.bytes == leaf.offset {
+                last.bytes += leaf.bytes;
+                last.line_feed_cnt = last
+                    .line_feed_cnt
+                    .zip(leaf.line_feed_cnt)
+                    .map(|(a, b)| a + b);
+                return;
+            }
+        }
+        leaves.push(leaf);
+    }
+
+    /// Helper to compute line feeds in a buffer range
+    fn compute_line_feeds_static(
+        buffers: &[StringBuffer],
         location: BufferLocation,
         offset: usize,
         bytes: usize,
@@ -1156,6 +1412,244 @@ impl PieceTree {
         }
     }
 
+    /// Overwrite `replacement.bytes` bytes at `offset` with a piece of the same length
+    ///
+    /// Fast path for small in-place edits (e.g. overtyping a character): when the
+    /// overwritten range lies inside a single leaf, only the nodes on the path to
+    /// that leaf are reallocated and every sibling subtree is shared with the old
+    /// tree via `Arc`. The total length is unchanged, so no rebalancing is done;
+    /// the next regular insert/delete rebuilds the tree balanced anyway.
+    ///
+    /// Returns false (leaving the tree untouched) if the range spans more than
+    /// one leaf or extends past the end of the document.
+    pub fn replace_in_leaf(
+        &mut self,
+        offset: usize,
+        replacement: LeafData,
+        buffers: &[StringBuffer],
+    ) -> bool {
+        if replacement.bytes == 0 || offset + replacement.bytes > self.total_bytes {
+            return false;
+        }
+
+        match Self::replace_in_leaf_node(&self.root, offset, replacement, buffers) {
+            Some(root) => {
+                self.root = root;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Recursive helper for `replace_in_leaf`; returns the rebuilt node, or None if
+    /// the range doesn't fit within a single leaf of this subtree
+    fn replace_in_leaf_node(
+        node: &Arc<PieceTreeNode>,
+        offset: usize,
+        replacement: LeafData,
+        buffers: &[StringBuffer],
+    ) -> Option<Arc<PieceTreeNode>> {
+        let end = offset + replacement.bytes;
+        match node.as_ref() {
+            PieceTreeNode::Internal {
+                left_bytes,
+                lf_left,
+                left,
+                right,
+            } => {
+                if end <= *left_bytes {
+                    let new_left = Self::replace_in_leaf_node(left, offset, replacement, buffers)?;
+                    Some(Arc::new(PieceTreeNode::Internal {
+                        left_bytes: *left_bytes,
+                        lf_left: new_left.total_line_feeds(),
+                        left: new_left,
+                        right: Arc::clone(right),
+                    }))
+                } else if offset >= *left_bytes {
+                    let new_right = Self::replace_in_leaf_node(
+                        right,
+                        offset - left_bytes,
+                        replacement,
+                        buffers,
+                    )?;
+                    Some(Arc::new(PieceTreeNode::Internal {
+                        left_bytes: *left_bytes,
+                        lf_left: *lf_left,
+                        left: Arc::clone(left),
+                        right: new_right,
+                    }))
+                } else {
+                    // Range straddles the two subtrees
+                    None
+                }
+            }
+            PieceTreeNode::Leaf {
+                location,
+                offset: piece_offset,
+                bytes,
+                ..
+            } => {
+                if end > *bytes {
+                    return None;
+                }
+
+                let mut leaves = Vec::with_capacity(3);
+                if offset > 0 {
+                    let lf_cnt =
+                        Self::compute_line_feeds_static(buffers, *location, *piece_offset, offset);
+                    leaves.push(LeafData::new(*location, *piece_offset, offset, lf_cnt));
+                }
+                leaves.push(replacement);
+                let remaining = bytes - end;
+                if remaining > 0 {
+                    let lf_cnt = Self::compute_line_feeds_static(
+                        buffers,
+                        *location,
+                        piece_offset + end,
+                        remaining,
+                    );
+                    leaves.push(LeafData::new(
+                        *location,
+                        piece_offset + end,
+                        remaining,
+                        lf_cnt,
+                    ));
+                }
+                Some(Self::build_balanced(&leaves))
+            }
+        }
+    }
+
+    /// Build a new tree containing only the bytes for which `pred` returns true
+    ///
+    /// `pred` is called with each byte's absolute offset in the document and the
+    /// byte itself (e.g. `|_, b| b != b'\r'` to drop carriage returns). Pieces are
+    /// split around removed bytes, so the result still references the original
+    /// buffers and no text is copied. Returns None if a piece's buffer isn't
+    /// loaded, since its bytes can't be checked.
+    pub fn retain<F: Fn(usize, u8) -> bool>(
+        &self,
+        buffers: &[StringBuffer],
+        pred: F,
+    ) -> Option<Self> {
+        let mut leaves = Vec::new();
+        let mut doc_offset = 0;
+
+        for leaf in self.get_leaves() {
+            let data = buffers.get(leaf.location.buffer_id())?.get_data()?;
+
+            // Emit one leaf per run of kept bytes
+            let piece = &data[leaf.offset..leaf.offset + leaf.bytes];
+            let mut run_start = None;
+            for (i, &byte) in piece.iter().enumerate() {
+                let keep = pred(doc_offset + i, byte);
+                match (keep, run_start) {
+                    (true, None) => run_start = Some(i),
+                    (false, Some(start)) => {
+                        leaves.push(Self::retained_run(buffers, leaf, start, i));
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(start) = run_start {
+                leaves.push(Self::retained_run(buffers, leaf, start, piece.len()));
+            }
+            doc_offset += leaf.bytes;
+        }
+
+        let total_bytes = leaves.iter().map(|leaf| leaf.bytes).sum();
+        Some(PieceTree {
+            root: Self::build_balanced(&leaves),
+            total_bytes,
+        })
+    }
+
+    /// Leaf covering bytes `start..end` of the given leaf, for `retain`
+    fn retained_run(
+        buffers: &[StringBuffer],
+        leaf: LeafData,
+        start: usize,
+        end: usize,
+    ) -> LeafData {
+        if start == 0 && end == leaf.bytes {
+            return leaf;
+        }
+        let offset = leaf.offset + start;
+        let bytes = end - start;
+        let lf_cnt = Self::compute_line_feeds_static(buffers, leaf.location, offset, bytes);
+        LeafData::new(leaf.location, offset, bytes, lf_cnt)
+    }
+
+    /// Build an independent tree covering exactly `range` of this one
+    ///
+    /// Subtrees that lie entirely inside the range are shared rather than
+    /// copied and only the pieces at either end are trimmed, so this costs
+    /// O(depth) and leaves `self` untouched. Returns None if the range is
+    /// reversed or runs past the end.
+    pub fn subtree(&self, range: Range<usize>, buffers: &[StringBuffer]) -> Option<Self> {
+        if range.start > range.end || range.end > self.total_bytes {
+            return None;
+        }
+        if range.is_empty() {
+            return Some(Self::empty());
+        }
+        Some(PieceTree {
+            root: Self::subtree_node(&self.root, range.start, range.end, buffers),
+            total_bytes: range.len(),
+        })
+    }
+
+    /// Recursive helper for `subtree`; `start..end` is non-empty and relative
+    /// to `node`
+    fn subtree_node(
+        node: &Arc<PieceTreeNode>,
+        start: usize,
+        end: usize,
+        buffers: &[StringBuffer],
+    ) -> Arc<PieceTreeNode> {
+        if start == 0 && end == node.total_bytes() {
+            return Arc::clone(node);
+        }
+        match node.as_ref() {
+            PieceTreeNode::Internal {
+                left_bytes,
+                left,
+                right,
+                ..
+            } => {
+                if end <= *left_bytes {
+                    Self::subtree_node(left, start, end, buffers)
+                } else if start >= *left_bytes {
+                    Self::subtree_node(right, start - left_bytes, end - left_bytes, buffers)
+                } else {
+                    let left = Self::subtree_node(left, start, *left_bytes, buffers);
+                    let right = Self::subtree_node(right, 0, end - left_bytes, buffers);
+                    Arc::new(PieceTreeNode::Internal {
+                        left_bytes: left_bytes - start,
+                        lf_left: left.total_line_feeds(),
+                        left,
+                        right,
+                    })
+                }
+            }
+            PieceTreeNode::Leaf {
+                location, offset, ..
+            } => {
+                let offset = offset + start;
+                let bytes = end - start;
+                let line_feed_cnt =
+                    Self::compute_line_feeds_static(buffers, *location, offset, bytes);
+                Arc::new(PieceTreeNode::Leaf {
+                    location: *location,
+                    offset,
+                    bytes,
+                    line_feed_cnt,
+                })
+            }
+        }
+    }
+
     /// Split a piece at the given offset without inserting anything
     /// This is useful for isolating a chunk of a large piece for partial loading
     ///
@@ -1590,6 +2084,447 @@ impl PieceTree {
         }
     }
 
+    /// Outline of the tree's nodes for diagnosing depth and fragmentation
+    ///
+    /// One line per node, children indented two spaces under their parent:
+    /// `Internal(<bytes>)`, or `Leaf(<bytes>)` followed by the first
+    /// [`DEBUG_PREVIEW_CHARS`] characters of its text (`...` when cut short,
+    /// `<unloaded>` when its buffer isn't in memory).
+    pub fn debug_structure(&self, buffers: &[StringBuffer]) -> String {
+        let mut out = String::new();
+        self.root.write_structure(buffers, 0, &mut out);
+        out
+    }
+
+    /// Borrow the whole document as a single slice, if it is stored contiguously
+    ///
+    /// This holds when the tree is one piece, or when all pieces follow each other
+    /// in the same buffer (e.g. after a split with no edit in between). Returns
+    /// `None` for any other layout, or when the buffer isn't loaded. Lets callers
+    /// skip copying pieces together for small, unedited documents.
+    pub fn as_contiguous<'a>(&self, buffers: &'a [StringBuffer]) -> Option<&'a [u8]> {
+        if self.total_bytes == 0 {
+            return Some(&[]);
+        }
+        let (location, start, end) = self.root.contiguous_span()?;
+        buffers
+            .get(location.buffer_id())?
+            .get_data()?
+            .get(start..end)
+    }
+
+    /// Call `f` with each leaf's document offset and bytes, in order
+    ///
+    /// Stops as soon as `f` returns `Break`, so searches that only need the first
+    /// hit don't walk (or copy) the rest of the document. Pieces in unloaded
+    /// buffers are skipped, but still count towards the offsets of later leaves.
+    pub fn visit_leaves<F: FnMut(usize, &[u8]) -> ControlFlow<()>>(
+        &self,
+        buffers: &[StringBuffer],
+        mut f: F,
+    ) -> ControlFlow<()> {
+        match self.root.visit_leaves(buffers, 0, &mut f) {
+            ControlFlow::Continue(_) => ControlFlow::Continue(()),
+            ControlFlow::Break(()) => ControlFlow::Break(()),
+        }
+    }
+
+    /// Byte offset of the `char_idx`-th character (0-indexed)
+    ///
+    /// Characters are counted as UTF-8 lead bytes, so invalid sequences count
+    /// one character per stray byte. `char_idx` equal to the character count
+    /// gives the end of the document. Returns `None` past that, or when a piece
+    /// before the target isn't loaded.
+    pub fn char_to_byte(&self, char_idx: usize, buffers: &[StringBuffer]) -> Option<usize> {
+        let mut remaining = char_idx;
+        let mut loaded_up_to = 0;
+        let mut found = None;
+        let _ = self.visit_leaves(buffers, |start, data| {
+            if start != loaded_up_to {
+                // Skipped an unloaded piece, so the count is no longer exact
+                return ControlFlow::Break(());
+            }
+            loaded_up_to = start + data.len();
+            for (i, &byte) in data.iter().enumerate() {
+                if byte & 0xC0 != 0x80 {
+                    if remaining == 0 {
+                        found = Some(start + i);
+                        return ControlFlow::Break(());
+                    }
+                    remaining -= 1;
+                }
+            }
+            ControlFlow::Continue(())
+        });
+        if found.is_none() && remaining == 0 && loaded_up_to == self.total_bytes {
+            found = Some(self.total_bytes);
+        }
+        found
+    }
+
+    /// Length of the document in UTF-16 code units, as language servers count
+    ///
+    /// Characters outside the BMP take two units (a surrogate pair). Returns
+    /// `None` if any piece isn't loaded.
+    pub fn utf16_len(&self, buffers: &[StringBuffer]) -> Option<usize> {
+        let mut units = 0;
+        let mut loaded_up_to = 0;
+        let flow = self.visit_leaves(buffers, |start, data| {
+            if start != loaded_up_to {
+                return ControlFlow::Break(());
+            }
+            loaded_up_to = start + data.len();
+            units += data.iter().map(|&byte| utf16_units(byte)).sum::<usize>();
+            ControlFlow::Continue(())
+        });
+        (flow.is_continue() && loaded_up_to == self.total_bytes).then_some(units)
+    }
+
+    /// UTF-16 code-unit offset of byte offset `byte`
+    ///
+    /// Counting goes by UTF-8 lead bytes, so a character split across pieces
+    /// still counts once. An offset inside a character maps to the character's
+    /// start. Returns `None` past the end of the document, or when a piece
+    /// before the target isn't loaded.
+    pub fn byte_to_utf16(&self, byte: usize, buffers: &[StringBuffer]) -> Option<usize> {
+        if byte > self.total_bytes {
+            return None;
+        }
+        let mut units = 0;
+        // Units of the multi-byte character being walked through, if any
+        let mut current = 0;
+        let mut loaded_up_to = 0;
+        let mut found = false;
+        let _ = self.visit_leaves(buffers, |start, data| {
+            if start != loaded_up_to {
+                return ControlFlow::Break(());
+            }
+            loaded_up_to = start + data.len();
+            let end = (byte - start).min(data.len());
+            for &b in &data[..end] {
+                let n = utf16_units(b);
+                if n > 0 {
+                    current = if b >= 0xC0 { n } else { 0 };
+                    units += n;
+                }
+            }
+            if end < data.len() {
+                if utf16_units(data[end]) == 0 {
+                    units -= current;
+                }
+                found = true;
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        });
+        (found || (byte == self.total_bytes && loaded_up_to == self.total_bytes)).then_some(units)
+    }
+
+    /// Byte offset of UTF-16 code-unit offset `utf16_offset`
+    ///
+    /// An offset between the two halves of a surrogate pair maps to the start
+    /// of the character. The document's UTF-16 length gives the end of the
+    /// document. Returns `None` past that, or when a piece before the target
+    /// isn't loaded.
+    pub fn utf16_to_byte(&self, utf16_offset: usize, buffers: &[StringBuffer]) -> Option<usize> {
+        let mut remaining = utf16_offset;
+        let mut loaded_up_to = 0;
+        let mut found = None;
+        let _ = self.visit_leaves(buffers, |start, data| {
+            if start != loaded_up_to {
+                return ControlFlow::Break(());
+            }
+            loaded_up_to = start + data.len();
+            for (i, &byte) in data.iter().enumerate() {
+                let n = utf16_units(byte);
+                if n == 0 {
+                    continue;
+                }
+                if remaining < n {
+                    found = Some(start + i);
+                    return ControlFlow::Break(());
+                }
+                remaining -= n;
+            }
+            ControlFlow::Continue(())
+        });
+        if found.is_none() && remaining == 0 && loaded_up_to == self.total_bytes {
+            found = Some(self.total_bytes);
+        }
+        found
+    }
+
+    /// 64-bit hash of the document content, streamed leaf by leaf
+    ///
+    /// Depends only on the bytes, not on how they are split into pieces, so it
+    /// equals [`content_hash`] of the same text read from disk. Comparing the two
+    /// tells an unchanged file apart without a byte-by-byte comparison. Returns
+    /// `None` if any piece isn't loaded.
+    pub fn content_hash(&self, buffers: &[StringBuffer]) -> Option<u64> {
+        let mut hasher = ContentHasher::default();
+        let mut loaded_up_to = 0;
+        let flow = self.visit_leaves(buffers, |start, data| {
+            if start != loaded_up_to {
+                return ControlFlow::Break(());
+            }
+            loaded_up_to = start + data.len();
+            hasher.write(data);
+            ControlFlow::Continue(())
+        });
+        (flow.is_continue() && loaded_up_to == self.total_bytes).then(|| hasher.finish())
+    }
+
+    /// Build a balanced tree from everything `reader` yields, along with the
+    /// buffer holding the bytes
+    ///
+    /// The source is read a block at a time and each block goes straight into
+    /// the tree's buffer, so it is never held twice. Pieces refer to
+    /// `BufferLocation::Stored(buffer_id)` and hold at most `max_leaf_bytes`.
+    pub fn from_reader<R: Read>(
+        reader: &mut R,
+        buffer_id: usize,
+        max_leaf_bytes: usize,
+    ) -> io::Result<(PieceTree, StringBuffer)> {
+        let mut builder = PieceTreeBuilder::new(buffer_id, max_leaf_bytes);
+        builder.read_from(reader)?;
+        Ok(builder.build())
+    }
+
+    /// Build a balanced tree over a copy of `bytes`, along with the buffer
+    /// holding them, with leaves cut after line feeds where possible
+    ///
+    /// Each leaf holds at most `max_leaf_bytes` and ends after the last line
+    /// feed in its final `tolerance` bytes, so most leaves start at a line
+    /// start and a line's lookup stays within one or two leaves. A line that
+    /// leaves no line feed within reach is cut at `max_leaf_bytes`, as
+    /// [`PieceTreeBuilder`] would.
+    pub fn from_slice_line_aligned(
+        bytes: &[u8],
+        buffer_id: usize,
+        max_leaf_bytes: usize,
+        tolerance: usize,
+    ) -> (PieceTree, StringBuffer) {
+        let max_leaf_bytes = max_leaf_bytes.max(1);
+        let tolerance = tolerance.min(max_leaf_bytes);
+        let mut leaves = Vec::new();
+        let mut start = 0;
+        while start < bytes.len() {
+            let mut end = (start + max_leaf_bytes).min(bytes.len());
+            if end < bytes.len() {
+                if let Some(i) = bytes[end - tolerance..end]
+                    .iter()
+                    .rposition(|&b| b == b'\n')
+                {
+                    end = end - tolerance + i + 1;
+                }
+            }
+            let line_feeds = bytes[start..end].iter().filter(|&&b| b == b'\n').count();
+            leaves.push(LeafData::new(
+                BufferLocation::Stored(buffer_id),
+                start,
+                end - start,
+                Some(line_feeds),
+            ));
+            start = end;
+        }
+
+        let tree = if leaves.is_empty() {
+            PieceTree::empty()
+        } else {
+            PieceTree {
+                root: Self::build_balanced(&leaves),
+                total_bytes: bytes.len(),
+            }
+        };
+        (tree, StringBuffer::new(buffer_id, bytes.to_vec()))
+    }
+
+    /// Build a balanced tree of `pattern` repeated `times` times, along with
+    /// the buffer its pieces refer to
+    ///
+    /// The buffer only holds as many copies of `pattern` as fit in
+    /// `max_leaf_bytes` (at least one), and every piece refers to that same
+    /// run, so a large synthetic document costs one piece per leaf instead of
+    /// its full size in memory.
+    pub fn from_repeated_slice(
+        pattern: &[u8],
+        times: usize,
+        buffer_id: usize,
+        max_leaf_bytes: usize,
+    ) -> (PieceTree, StringBuffer) {
+        if pattern.is_empty() || times == 0 {
+            return (PieceTree::empty(), StringBuffer::new(buffer_id, Vec::new()));
+        }
+        let per_leaf = (max_leaf_bytes / pattern.len()).clamp(1, times);
+        let line_feeds = pattern.iter().filter(|&&b| b == b'\n').count();
+        let leaf = |copies: usize| {
+            LeafData::new(
+                BufferLocation::Stored(buffer_id),
+                0,
+                copies * pattern.len(),
+                Some(copies * line_feeds),
+            )
+        };
+
+        let mut leaves = vec![leaf(per_leaf); times / per_leaf];
+        if !times.is_multiple_of(per_leaf) {
+            leaves.push(leaf(times % per_leaf));
+        }
+        let tree = PieceTree {
+            root: PieceTree::build_balanced(&leaves),
+            total_bytes: times * pattern.len(),
+        };
+        (tree, StringBuffer::new(buffer_id, pattern.repeat(per_leaf)))
+    }
+
+    /// Build a balanced tree of `count` copies of `byte` (see
+    /// [`PieceTree::from_repeated_slice`])
+    pub fn repeated(
+        byte: u8,
+        count: usize,
+        buffer_id: usize,
+        max_leaf_bytes: usize,
+    ) -> (PieceTree, StringBuffer) {
+        Self::from_repeated_slice(&[byte], count, buffer_id, max_leaf_bytes)
+    }
+
+    /// Start a batch of edits against this tree (see [`TreeTransaction`])
+    pub fn transaction(&self) -> TreeTransaction<'_> {
+        TreeTransaction {
+            base: self,
+            removals: Vec::new(),
+            inserts: Vec::new(),
+        }
+    }
+
+    /// A balanced copy of this tree with leaves of at most `max_leaf_bytes`
+    ///
+    /// Only piece descriptors are rewritten; the text stays in `buffers`. Runs of
+    /// pieces that follow each other in the same buffer are merged while they
+    /// fit, longer pieces are cut into `max_leaf_bytes` slices, and pieces that
+    /// already fit are kept as they are. Line feeds of cut pieces are recounted
+    /// from the buffer, or left unknown if it isn't loaded.
+    pub fn rechunk(&self, max_leaf_bytes: usize, buffers: &[StringBuffer]) -> PieceTree {
+        let max_leaf_bytes = max_leaf_bytes.max(1);
+        let mut pieces = Vec::new();
+        self.root.collect_leaves(&mut pieces);
+
+        let mut leaves: Vec<LeafData> = Vec::new();
+        for piece in pieces.into_iter().filter(|piece| piece.bytes > 0) {
+            if let Some(last) = leaves.last_mut() {
+                if last.location == piece.location
+                    && last.offset + last.bytes == piece.offset
+                    && last.bytes + piece.bytes <= max_leaf_bytes
+                {
+                    last.bytes += piece.bytes;
+                    last.line_feed_cnt = last
+                        .line_feed_cnt
+                        .zip(piece.line_feed_cnt)
+                        .map(|(a, b)| a + b);
+                    continue;
+                }
+            }
+            if piece.bytes <= max_leaf_bytes {
+                leaves.push(piece);
+                continue;
+            }
+            let data = buffers
+                .get(piece.location.buffer_id())
+                .and_then(|buffer| buffer.get_data());
+            for start in (piece.offset..piece.offset + piece.bytes).step_by(max_leaf_bytes) {
+                let end = (start + max_leaf_bytes).min(piece.offset + piece.bytes);
+                let line_feeds = piece.line_feed_cnt.and(data).and_then(|data| {
+                    Some(
+                        data.get(start..end)?
+                            .iter()
+                            .filter(|&&b| b == b'\n')
+                            .count(),
+                    )
+                });
+                leaves.push(LeafData::new(
+                    piece.location,
+                    start,
+                    end - start,
+                    line_feeds,
+                ));
+            }
+        }
+
+        if leaves.is_empty() {
+            return PieceTree::empty();
+        }
+        PieceTree {
+            root: Self::build_balanced(&leaves),
+            total_bytes: self.total_bytes,
+        }
+    }
+
+    /// Number of leading bytes that are provably the same in `old` and `new`
+    ///
+    /// Meant for two versions of the same buffer (sharing its `StringBuffer`s), e.g.
+    /// before and after an edit, so a redraw can skip the untouched start. No text
+    /// is read: subtrees shared through the same `Arc` count whole, and pieces
+    /// pointing at the same range of the same buffer are equal because buffers
+    /// are append-only. The result is a lower bound on the real common prefix.
+    pub fn unchanged_prefix_len(old: &PieceTree, new: &PieceTree) -> usize {
+        Self::unchanged_len(old, new, false)
+    }
+
+    /// Number of trailing bytes that are provably the same in `old` and `new`
+    ///
+    /// The suffix counterpart of [`PieceTree::unchanged_prefix_len`]. For identical
+    /// trees both cover the whole document, so callers combining the two should
+    /// clamp them to the shorter length.
+    pub fn unchanged_suffix_len(old: &PieceTree, new: &PieceTree) -> usize {
+        Self::unchanged_len(old, new, true)
+    }
+
+    fn unchanged_len(old: &PieceTree, new: &PieceTree, from_end: bool) -> usize {
+        let mut old_spans = vec![Span::Node(&old.root, old.total_bytes)];
+        let mut new_spans = vec![Span::Node(&new.root, new.total_bytes)];
+        let mut same = 0;
+
+        while let (Some(&a), Some(&b)) = (old_spans.last(), new_spans.last()) {
+            if a.len() == 0 {
+                old_spans.pop();
+                continue;
+            }
+            if b.len() == 0 {
+                new_spans.pop();
+                continue;
+            }
+            match (a, b) {
+                (Span::Node(x, len), Span::Node(y, _)) if Arc::ptr_eq(x, y) => {
+                    same += len;
+                    old_spans.pop();
+                    new_spans.pop();
+                }
+                // Split the larger node first so a shared subtree on the other
+                // side still has a chance to line up with one of its children
+                (Span::Node(..), _) if a.len() >= b.len() || matches!(b, Span::Piece(..)) => {
+                    Span::expand(&mut old_spans, from_end);
+                }
+                (_, Span::Node(..)) => Span::expand(&mut new_spans, from_end),
+                (Span::Node(..), Span::Piece(..)) => Span::expand(&mut old_spans, from_end),
+                (Span::Piece(loc_a, offset_a, len_a), Span::Piece(loc_b, offset_b, len_b)) => {
+                    let aligned = if from_end {
+                        offset_a + len_a == offset_b + len_b
+                    } else {
+                        offset_a == offset_b
+                    };
+                    if loc_a != loc_b || !aligned {
+                        break;
+                    }
+                    let n = len_a.min(len_b);
+                    same += n;
+                    Span::consume(&mut old_spans, n, from_end);
+                    Span::consume(&mut new_spans, n, from_end);
+                }
+            }
+        }
+        same
+    }
+
     /// Get all leaves in order (for debugging)
     pub fn get_leaves(&self) -> Vec<LeafData> {
         let mut leaves = Vec::new();
@@ -1699,6 +2634,9 @@ impl PieceTree {
     }
 
     /// Convert line/column position to byte offset using tree's line metadata
+    ///
+    /// A column past the end of the line gives the offset of its line feed
+    /// (or the end of the document on the last line).
     pub fn position_to_offset(
         &self,
         line: usize,
@@ -1737,8 +2675,37 @@ impl PieceTree {
         column: usize,
         buffers: &[StringBuffer],
     ) -> Option<usize> {
-        self.root
-            .find_byte_offset_for_line(0, 0, target_line, column, buffers)
+        let start = self.line_start(target_line, buffers)?;
+        // A column past the end of the line stops before its line feed
+        let end = match self.line_start(target_line + 1, buffers) {
+            Some(next_start) => next_start - 1,
+            None => self.total_bytes,
+        };
+        Some((start + column).min(end))
+    }
+
+    /// Byte offset where `line` starts
+    ///
+    /// Returns None past the last line, or if line counts or line starts
+    /// are unknown.
+    fn line_start(&self, line: usize, buffers: &[StringBuffer]) -> Option<usize> {
+        if line == 0 {
+            return Some(0);
+        }
+        // The line starts right after the document's `line`-th line feed, so
+        // descend to the leaf holding that line feed
+        let (leaf, before) = self
+            .root
+            .descend_by(|_, mid| Some(line <= mid.line_feeds?))?;
+        let nth = line - before.line_feeds?;
+        if nth > leaf.line_feed_cnt? {
+            return None;
+        }
+        let line_starts = buffers.get(leaf.location.buffer_id())?.get_line_starts()?;
+        // Line starts after a line feed in the piece fall in (offset, offset + bytes]
+        let first = line_starts.partition_point(|&start| start <= leaf.offset);
+        let start = *line_starts.get(first + nth - 1)?;
+        Some(before.bytes + start - leaf.offset)
     }
 
     /// Get the byte range for a specific line
@@ -1769,31 +2736,250 @@ impl PieceTree {
     }
 }
 
-/// A view into a piece's data within the document
-#[derive(Debug, Clone)]
-pub struct PieceView {
-    /// The location of this piece (which buffer it references)
-    pub location: BufferLocation,
-    /// Offset within the source buffer where this piece starts
-    pub buffer_offset: usize,
-    /// Number of bytes in this piece
-    pub bytes: usize,
-    /// Byte offset where this piece starts in the document
-    pub doc_offset: usize,
-    /// Number of line feeds in this piece (None if unknown for large files)
-    pub line_feed_cnt: Option<usize>,
+/// Builds a piece tree from content that arrives in chunks, e.g. blocks read
+/// from a file
+///
+/// The chunks are appended to a single stored buffer and each becomes one
+/// piece, or several if it is larger than `max_leaf_bytes`, so nothing is
+/// re-split or copied again when the tree is built.
+#[derive(Debug)]
+pub struct PieceTreeBuilder {
+    buffer_id: usize,
+    max_leaf_bytes: usize,
+    data: Vec<u8>,
+    leaves: Vec<LeafData>,
 }
 
-/// Iterator over pieces in a byte range
-/// Performs ONE O(log n) traversal to collect pieces, then iterates in O(1) per piece
-pub struct PieceRangeIter {
-    pieces: Vec<PieceView>,
-    current_index: usize,
-}
+impl PieceTreeBuilder {
+    /// Start a tree whose pieces refer to `BufferLocation::Stored(buffer_id)`
+    pub fn new(buffer_id: usize, max_leaf_bytes: usize) -> Self {
+        PieceTreeBuilder {
+            buffer_id,
+            max_leaf_bytes: max_leaf_bytes.max(1),
+            data: Vec::new(),
+            leaves: Vec::new(),
+        }
+    }
 
-impl PieceRangeIter {
-    fn new(root: &Arc<PieceTreeNode>, start: usize, end: usize) -> Self {
-        let mut pieces = Vec::new();
+    /// Append the next chunk of content
+    pub fn push_chunk(&mut self, chunk: &[u8]) {
+        for piece in chunk.chunks(self.max_leaf_bytes) {
+            let line_feeds = piece.iter().filter(|&&b| b == b'\n').count();
+            self.leaves.push(LeafData::new(
+                BufferLocation::Stored(self.buffer_id),
+                self.data.len(),
+                piece.len(),
+                Some(line_feeds),
+            ));
+            self.data.extend_from_slice(piece);
+        }
+    }
+
+    /// Push everything `reader` yields until end of file, returning the
+    /// number of bytes read
+    pub fn read_from<R: Read>(&mut self, reader: &mut R) -> io::Result<usize> {
+        let mut block = vec![0u8; 64 * 1024];
+        let start = self.len();
+        loop {
+            match reader.read(&mut block) {
+                Ok(0) => return Ok(self.len() - start),
+                Ok(n) => self.push_chunk(&block[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Total bytes pushed so far
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Assemble a balanced tree over the pushed chunks, along with the buffer
+    /// holding their bytes
+    pub fn build(self) -> (PieceTree, StringBuffer) {
+        let tree = if self.leaves.is_empty() {
+            PieceTree::empty()
+        } else {
+            PieceTree {
+                root: PieceTree::build_balanced(&self.leaves),
+                total_bytes: self.data.len(),
+            }
+        };
+        (tree, StringBuffer::new(self.buffer_id, self.data))
+    }
+}
+
+/// A batch of inserts and removals applied to a piece tree in one pass
+///
+/// Every offset refers to the base tree as it was when the transaction
+/// started, so edits don't shift one another: replacing each match of a
+/// search is `remove(m.clone())` plus `insert(m.start, ...)` per match, in any
+/// order. Inserts at the same offset keep the order they were added in, and an
+/// insert inside removed text lands where that text was. Committing builds one
+/// new tree instead of one per edit; the base tree is left untouched.
+pub struct TreeTransaction<'a> {
+    base: &'a PieceTree,
+    removals: Vec<Range<usize>>,
+    /// (base offset, piece to insert there)
+    inserts: Vec<(usize, LeafData)>,
+}
+
+impl TreeTransaction<'_> {
+    /// Insert `bytes` bytes of `location` starting at `buffer_offset`, at
+    /// `offset` in the base tree
+    pub fn insert(
+        &mut self,
+        offset: usize,
+        location: BufferLocation,
+        buffer_offset: usize,
+        bytes: usize,
+        line_feed_cnt: Option<usize>,
+    ) {
+        if bytes > 0 {
+            let offset = offset.min(self.base.total_bytes);
+            let leaf = LeafData::new(location, buffer_offset, bytes, line_feed_cnt);
+            self.inserts.push((offset, leaf));
+        }
+    }
+
+    /// Remove `range` of the base tree; overlapping removals are merged
+    pub fn remove(&mut self, range: Range<usize>) {
+        let range = range.start..range.end.min(self.base.total_bytes);
+        if !range.is_empty() {
+            self.removals.push(range);
+        }
+    }
+
+    /// Number of edits added so far
+    pub fn len(&self) -> usize {
+        self.removals.len() + self.inserts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Build the edited tree
+    ///
+    /// `buffers` must hold the base tree's pieces, to count line feeds in the
+    /// pieces that get cut.
+    pub fn commit(mut self, buffers: &[StringBuffer]) -> PieceTree {
+        self.removals.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in self.removals {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        // Stable, so inserts at one offset keep their order
+        self.inserts.sort_by_key(|(offset, _)| *offset);
+
+        let total = self.base.total_bytes;
+        let mut kept = KeptLeaves::new(self.base.get_leaves(), buffers);
+        let mut leaves = Vec::new();
+        let mut inserts = self.inserts.into_iter().peekable();
+        let mut pos = 0;
+        for removal in merged.into_iter().chain(std::iter::once(total..total)) {
+            while let Some((offset, leaf)) = inserts.next_if(|(offset, _)| *offset <= removal.start)
+            {
+                let offset = offset.max(pos);
+                kept.copy(pos..offset, &mut leaves);
+                pos = offset;
+                PieceTree::push_coalesced(&mut leaves, leaf);
+            }
+            kept.copy(pos..removal.start, &mut leaves);
+            pos = removal.end;
+        }
+
+        let total_bytes = leaves.iter().map(|leaf| leaf.bytes).sum();
+        if leaves.is_empty() {
+            return PieceTree::empty();
+        }
+        PieceTree {
+            root: PieceTree::build_balanced(&leaves),
+            total_bytes,
+        }
+    }
+}
+
+/// The base tree's leaves, copied out range by range in increasing order
+struct KeptLeaves<'a> {
+    leaves: std::iter::Peekable<std::vec::IntoIter<LeafData>>,
+    /// Base offset of the next leaf
+    leaf_start: usize,
+    buffers: &'a [StringBuffer],
+}
+
+impl<'a> KeptLeaves<'a> {
+    fn new(leaves: Vec<LeafData>, buffers: &'a [StringBuffer]) -> Self {
+        KeptLeaves {
+            leaves: leaves.into_iter().peekable(),
+            leaf_start: 0,
+            buffers,
+        }
+    }
+
+    /// Append the pieces covering `range` of the base tree to `out`, cutting
+    /// leaves at its ends; `range` must not start before the previous one ended
+    fn copy(&mut self, range: Range<usize>, out: &mut Vec<LeafData>) {
+        while let Some(&leaf) = self.leaves.peek() {
+            let leaf_end = self.leaf_start + leaf.bytes;
+            let start = range.start.max(self.leaf_start);
+            let end = range.end.min(leaf_end);
+            if start < end {
+                out.push(if end - start == leaf.bytes {
+                    leaf
+                } else {
+                    let offset = leaf.offset + start - self.leaf_start;
+                    let lf_cnt = PieceTree::compute_line_feeds_static(
+                        self.buffers,
+                        leaf.location,
+                        offset,
+                        end - start,
+                    );
+                    LeafData::new(leaf.location, offset, end - start, lf_cnt)
+                });
+            }
+            if leaf_end > range.end {
+                break;
+            }
+            self.leaves.next();
+            self.leaf_start = leaf_end;
+        }
+    }
+}
+
+/// A view into a piece's data within the document
+#[derive(Debug, Clone)]
+pub struct PieceView {
+    /// The location of this piece (which buffer it references)
+    pub location: BufferLocation,
+    /// Offset within the source buffer where this piece starts
+    pub buffer_offset: usize,
+    /// Number of bytes in this piece
+    pub bytes: usize,
+    /// Byte offset where this piece starts in the document
+    pub doc_offset: usize,
+    /// Number of line feeds in this piece (None if unknown for large files)
+    pub line_feed_cnt: Option<usize>,
+}
+
+/// Iterator over pieces in a byte range
+/// Performs ONE O(log n) traversal to collect pieces, then iterates in O(1) per piece
+pub struct PieceRangeIter {
+    pieces: Vec<PieceView>,
+    current_index: usize,
+}
+
+impl PieceRangeIter {
+    fn new(root: &Arc<PieceTreeNode>, start: usize, end: usize) -> Self {
+        let mut pieces = Vec::new();
         Self::collect_pieces(root, 0, start, end, &mut pieces);
         PieceRangeIter {
             pieces,
@@ -1973,6 +3159,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_replace_in_leaf_shares_siblings() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 100, Some(0));
+        for i in 0..8 {
+            tree.insert(
+                i * 10,
+                BufferLocation::Added(1),
+                i * 5,
+                5,
+                Some(0),
+                &buffers,
+            );
+        }
+        let before = tree.get_leaves();
+        let old_root = tree.root();
+
+        // Overwrite 2 bytes in the middle of the last piece
+        let total = tree.total_bytes();
+        let replacement = LeafData::new(BufferLocation::Added(2), 0, 2, Some(0));
+        assert!(tree.replace_in_leaf(total - 5, replacement, &buffers));
+        assert_eq!(tree.total_bytes(), total);
+
+        let after = tree.get_leaves();
+        let sum: usize = after.iter().map(|l| l.bytes).sum();
+        assert_eq!(sum, total);
+        // Untouched pieces are preserved in order
+        assert_eq!(after.len(), before.len() + 2);
+        for (a, b) in before.iter().zip(after.iter()).take(before.len() - 1) {
+            assert_eq!(
+                (a.location, a.offset, a.bytes),
+                (b.location, b.offset, b.bytes)
+            );
+        }
+        let replaced = tree.find_by_offset(total - 5).unwrap();
+        assert_eq!(replaced.location, BufferLocation::Added(2));
+        assert_eq!(replaced.bytes, 2);
+
+        // The left subtree of the root was not on the edited path, so it's shared
+        match (old_root.as_ref(), tree.root().as_ref()) {
+            (
+                PieceTreeNode::Internal { left: old_left, .. },
+                PieceTreeNode::Internal { left: new_left, .. },
+            ) => assert!(Arc::ptr_eq(old_left, new_left)),
+            _ => panic!("expected internal root"),
+        }
+    }
+
+    #[test]
+    fn test_replace_in_leaf_rejects_spanning_range() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 10, Some(0));
+        tree.insert(10, BufferLocation::Added(1), 0, 10, Some(0), &buffers);
+
+        let replacement = LeafData::new(BufferLocation::Added(2), 0, 4, Some(0));
+        assert!(!tree.replace_in_leaf(8, replacement, &buffers));
+        assert!(!tree.replace_in_leaf(18, replacement, &buffers));
+        assert_eq!(tree.get_leaves().len(), 2);
+    }
+
     #[test]
     fn test_find_by_offset() {
         let tree = PieceTree::new(BufferLocation::Stored(0), 0, 100, Some(0));
@@ -1996,6 +3242,82 @@ mod tests {
         assert_eq!(info.location, BufferLocation::Added(1));
     }
 
+    #[test]
+    fn test_descent_lookups_match_text() {
+        // Small pieces from two buffers, so lines and pieces end all over the place
+        let buffers = vec![
+            StringBuffer::new(0, b"one\ntwo\n\nthree".to_vec()),
+            StringBuffer::new(1, b"ab\ncd\n".to_vec()),
+        ];
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 14, Some(3));
+        for (pos, start, len) in [(4, 0, 3), (0, 2, 4), (9, 3, 3), (22, 0, 6), (13, 5, 1)] {
+            let data = &buffers[1].get_data().unwrap()[start..start + len];
+            let lf = data.iter().filter(|&&b| b == b'\n').count();
+            tree.insert(
+                pos,
+                BufferLocation::Added(1),
+                start,
+                len,
+                Some(lf),
+                &buffers,
+            );
+        }
+        assert!(tree.stats().depth > 2);
+        let mut text = Vec::new();
+        let _ = tree.visit_leaves(&buffers, |_, data| {
+            text.extend_from_slice(data);
+            ControlFlow::Continue(())
+        });
+
+        for (offset, &byte) in text.iter().enumerate() {
+            let info = tree.find_by_offset(offset).unwrap();
+            let data = buffers[info.location.buffer_id()].get_data().unwrap();
+            assert_eq!(data[info.offset + info.offset_in_piece.unwrap()], byte);
+        }
+        assert!(tree.find_by_offset(text.len()).is_none());
+
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain((0..text.len()).filter(|&i| text[i] == b'\n').map(|i| i + 1))
+            .collect();
+        for (line, &start) in line_starts.iter().enumerate() {
+            assert_eq!(
+                tree.position_to_offset(line, 0, &buffers),
+                start,
+                "line {line}"
+            );
+            let end = line_starts.get(line + 1).copied();
+            assert_eq!(tree.line_range(line, &buffers), Some((start, end)));
+            // Columns count from the line start, even when it's in an earlier piece,
+            // and stop at the line's end
+            let line_end = end.map_or(text.len(), |next| next - 1);
+            assert_eq!(
+                tree.position_to_offset(line, 1, &buffers),
+                (start + 1).min(line_end)
+            );
+        }
+        let past_end = line_starts.len();
+        assert_eq!(tree.position_to_offset(past_end, 0, &buffers), text.len());
+        assert_eq!(tree.line_range(past_end, &buffers), None);
+    }
+
+    #[test]
+    fn test_position_to_offset_clamps_column_to_line() {
+        let content = b"ab\ncdef\ng";
+        let buffers = vec![StringBuffer::new(0, content.to_vec())];
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, content.len(), Some(2));
+        // Split the first line feed into a piece of its own
+        tree.split_at_offset(2, &buffers);
+        tree.split_at_offset(3, &buffers);
+
+        assert_eq!(tree.position_to_offset(0, 1, &buffers), 1);
+        assert_eq!(tree.position_to_offset(0, 2, &buffers), 2);
+        // Past the end of a line: its line feed, not the next line
+        assert_eq!(tree.position_to_offset(0, 5, &buffers), 2);
+        assert_eq!(tree.position_to_offset(1, 10, &buffers), 7);
+        // Past the end of the last line: the end of the document
+        assert_eq!(tree.position_to_offset(2, 10, &buffers), 9);
+    }
+
     #[test]
     fn test_offset_to_position_column_after_modification() {
         // This test reproduces a bug where offset_to_position returns incorrect
@@ -2068,6 +3390,751 @@ mod tests {
         let pos = tree.offset_to_position(21, &buffers);
         assert_eq!(pos, Some((1, 0)), "Position 21 should be line 1, column 0");
     }
+
+    #[test]
+    fn test_retain_strips_carriage_returns() {
+        let stored = b"one\r\ntwo\r\n";
+        let added = b"three\r\n";
+        let buffers = vec![
+            StringBuffer::new(0, stored.to_vec()),
+            StringBuffer::new(1, added.to_vec()),
+        ];
+
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, stored.len(), Some(2));
+        tree.insert(
+            stored.len(),
+            BufferLocation::Added(1),
+            0,
+            added.len(),
+            Some(1),
+            &buffers,
+        );
+
+        let lf = tree.retain(&buffers, |_, b| b != b'\r').unwrap();
+
+        let mut text = Vec::new();
+        for leaf in lf.get_leaves() {
+            let data = buffers[leaf.location.buffer_id()].get_data().unwrap();
+            text.extend_from_slice(&data[leaf.offset..leaf.offset + leaf.bytes]);
+        }
+        assert_eq!(text, b"one\ntwo\nthree\n");
+        assert_eq!(lf.total_bytes(), text.len());
+        assert_eq!(lf.line_count(), Some(4));
+        assert_eq!(lf.offset_to_position(4, &buffers), Some((1, 0)));
+
+        // The original tree is untouched
+        assert_eq!(tree.total_bytes(), stored.len() + added.len());
+    }
+
+    #[test]
+    fn test_retain_passes_absolute_offsets() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 10, Some(0));
+        tree.insert(5, BufferLocation::Added(1), 0, 10, Some(0), &buffers);
+
+        // Keep every other byte across the piece boundaries
+        let kept = tree.retain(&buffers, |offset, _| offset % 2 == 0).unwrap();
+        assert_eq!(kept.total_bytes(), 10);
+        let mut text = Vec::new();
+        for leaf in kept.get_leaves() {
+            let data = buffers[leaf.location.buffer_id()].get_data().unwrap();
+            text.extend_from_slice(&data[leaf.offset..leaf.offset + leaf.bytes]);
+        }
+        assert_eq!(text, b"aaabbbbbaa");
+
+        let all = tree.retain(&buffers, |_, _| true).unwrap();
+        assert_eq!(all.get_leaves().len(), tree.get_leaves().len());
+
+        let none = tree.retain(&buffers, |_, _| false).unwrap();
+        assert_eq!(none.total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_retain_needs_loaded_buffers() {
+        let unloaded = vec![StringBuffer::new_unloaded(0, "missing".into(), 0, 100)];
+        let tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, None);
+        assert!(tree.retain(&unloaded, |_, b| b != b'\r').is_none());
+    }
+
+    #[test]
+    fn test_as_contiguous_single_leaf() {
+        let buffers = test_buffers();
+        let tree = PieceTree::new(BufferLocation::Stored(0), 10, 20, Some(0));
+        assert_eq!(tree.as_contiguous(&buffers), Some(&[b'a'; 20][..]));
+
+        assert_eq!(PieceTree::empty().as_contiguous(&[]), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_as_contiguous_multiple_leaves() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, Some(0));
+
+        // Splitting leaves both halves adjacent in the same buffer
+        tree.split_at_offset(8, &buffers);
+        assert_eq!(tree.get_leaves().len(), 2);
+        assert_eq!(tree.as_contiguous(&buffers), Some(&[b'a'; 20][..]));
+
+        // An insert from another buffer breaks contiguity
+        tree.insert(8, BufferLocation::Added(1), 0, 5, Some(0), &buffers);
+        assert_eq!(tree.as_contiguous(&buffers), None);
+
+        // So does an unloaded buffer
+        let unloaded = vec![StringBuffer::new_unloaded(0, "missing".into(), 0, 100)];
+        let tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, None);
+        assert_eq!(tree.as_contiguous(&unloaded), None);
+    }
+
+    #[test]
+    fn test_insert_coalesces_consecutive_typing() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, Some(0));
+
+        // Type 30 bytes one at a time in the middle, each appended to buffer 1
+        for i in 0..30 {
+            tree.insert(10 + i, BufferLocation::Added(1), i, 1, Some(0), &buffers);
+        }
+        assert_eq!(tree.total_bytes(), 50);
+        assert_eq!(tree.stats().leaf_count, 3);
+
+        // Same at the end of the document
+        for i in 30..40 {
+            tree.insert(20 + i, BufferLocation::Added(1), i, 1, Some(0), &buffers);
+        }
+        assert_eq!(tree.stats().leaf_count, 4);
+
+        // A non-contiguous insert still gets its own piece
+        tree.insert(0, BufferLocation::Added(1), 45, 1, Some(0), &buffers);
+        assert_eq!(tree.stats().leaf_count, 5);
+    }
+
+    #[test]
+    fn test_visit_leaves_in_order() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, Some(0));
+        tree.insert(10, BufferLocation::Added(1), 0, 5, Some(0), &buffers);
+        tree.insert(25, BufferLocation::Added(2), 0, 3, Some(0), &buffers);
+
+        let mut visited = Vec::new();
+        let flow = tree.visit_leaves(&buffers, |offset, data| {
+            visited.push((offset, data.to_vec()));
+            ControlFlow::Continue(())
+        });
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(
+            visited,
+            vec![
+                (0, vec![b'a'; 10]),
+                (10, vec![b'b'; 5]),
+                (15, vec![b'a'; 10]),
+                (25, vec![b'c'; 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visit_leaves_stops_on_break() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, Some(0));
+        tree.insert(10, BufferLocation::Added(1), 0, 5, Some(0), &buffers);
+        assert_eq!(tree.get_leaves().len(), 3);
+
+        let mut visits = 0;
+        let flow = tree.visit_leaves(&buffers, |_, _| {
+            visits += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(visits, 1);
+    }
+
+    #[test]
+    fn test_leaf_at_boundaries() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, Some(0));
+        tree.insert(10, BufferLocation::Added(1), 0, 5, Some(0), &buffers);
+        // Leaves: a*10 [0,10), b*5 [10,15), a*10 [15,25)
+
+        assert_eq!(tree.leaf_at(0, &buffers), Some((0, &[b'a'; 10][..])));
+        assert_eq!(tree.leaf_at(9, &buffers), Some((0, &[b'a'; 10][..])));
+        assert_eq!(tree.leaf_at(10, &buffers), Some((10, &[b'b'; 5][..])));
+        assert_eq!(tree.leaf_at(14, &buffers), Some((10, &[b'b'; 5][..])));
+        assert_eq!(tree.leaf_at(15, &buffers), Some((15, &[b'a'; 10][..])));
+        assert_eq!(tree.leaf_at(24, &buffers), Some((15, &[b'a'; 10][..])));
+    }
+
+    #[test]
+    fn test_compact_merges_small_subtrees() {
+        let mut buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 100, Some(0));
+        // Fragment the tail with single-byte inserts alternating between buffers
+        for i in 0..20 {
+            let location = if i % 2 == 0 {
+                BufferLocation::Added(1)
+            } else {
+                BufferLocation::Stored(0)
+            };
+            let end = tree.total_bytes();
+            tree.insert(end, location, i, 1, Some(0), &buffers);
+        }
+        let content = |tree: &PieceTree, buffers: &[StringBuffer]| {
+            let mut text = Vec::new();
+            let flow = tree.visit_leaves(buffers, |_, bytes| {
+                text.extend_from_slice(bytes);
+                ControlFlow::Continue(())
+            });
+            assert_eq!(flow, ControlFlow::Continue(()));
+            text
+        };
+        let before = content(&tree, &buffers);
+        let leaves_before = tree.get_leaves().len();
+        assert!(leaves_before > 10);
+
+        let removed = tree.compact(16, &mut buffers);
+
+        assert!(removed > 0);
+        assert_eq!(tree.get_leaves().len(), leaves_before - removed);
+        assert!(tree.get_leaves().len() < leaves_before / 2);
+        assert_eq!(content(&tree, &buffers), before);
+        assert_eq!(tree.total_bytes(), 120);
+        // The large stored piece is untouched
+        assert!(tree
+            .get_leaves()
+            .iter()
+            .any(|leaf| leaf.location == BufferLocation::Stored(0) && leaf.bytes == 100));
+    }
+
+    #[test]
+    fn test_char_to_byte_across_pieces() {
+        // "héllo" in the original buffer with "→x" inserted after "hé"
+        let buffers = vec![
+            StringBuffer::new(0, "héllo".as_bytes().to_vec()),
+            StringBuffer::new(1, "→x".as_bytes().to_vec()),
+        ];
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 6, Some(0));
+        tree.insert(3, BufferLocation::Added(1), 0, 4, Some(0), &buffers);
+
+        // h é → x l l o
+        let offsets: Vec<_> = (0..=8).map(|i| tree.char_to_byte(i, &buffers)).collect();
+        assert_eq!(
+            offsets,
+            vec![
+                Some(0),
+                Some(1),
+                Some(3),
+                Some(6),
+                Some(7),
+                Some(8),
+                Some(9),
+                Some(10),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_utf16_offsets_with_surrogate_pairs_split_across_leaves() {
+        // a 😀 b é 😀, with leaf boundaries inside both emoji
+        let text = "a😀bé😀";
+        let buffers = vec![StringBuffer::new(0, text.as_bytes().to_vec())];
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, text.len(), Some(0));
+        tree.split_at_offset(3, &buffers);
+        tree.split_at_offset(10, &buffers);
+        assert_eq!(tree.get_leaves().len(), 3);
+
+        assert_eq!(tree.utf16_len(&buffers), Some(text.encode_utf16().count()));
+        let units: Vec<_> = (0..=13).map(|b| tree.byte_to_utf16(b, &buffers)).collect();
+        assert_eq!(
+            units,
+            vec![
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(1),
+                Some(1),
+                Some(3),
+                Some(4),
+                Some(4),
+                Some(5),
+                Some(5),
+                Some(5),
+                Some(5),
+                Some(7),
+                None
+            ]
+        );
+        let bytes: Vec<_> = (0..=8).map(|u| tree.utf16_to_byte(u, &buffers)).collect();
+        assert_eq!(
+            bytes,
+            vec![
+                Some(0),
+                Some(1),
+                // Between the halves of a surrogate pair
+                Some(1),
+                Some(5),
+                Some(6),
+                Some(8),
+                Some(8),
+                Some(12),
+                None
+            ]
+        );
+
+        // Round trip at every character boundary
+        for (byte, ch) in text.char_indices().chain([(text.len(), ' ')]) {
+            let utf16 = text[..byte].encode_utf16().count();
+            assert_eq!(tree.byte_to_utf16(byte, &buffers), Some(utf16), "{ch}");
+            assert_eq!(tree.utf16_to_byte(utf16, &buffers), Some(byte), "{ch}");
+        }
+    }
+
+    #[test]
+    fn test_repeated_trees_share_one_run() {
+        let text = |tree: &PieceTree, buffers: &[StringBuffer]| {
+            let mut bytes = Vec::new();
+            let _ = tree.visit_leaves(buffers, |_, data| {
+                bytes.extend_from_slice(data);
+                ControlFlow::Continue(())
+            });
+            bytes
+        };
+
+        let (tree, buffer) = PieceTree::from_repeated_slice(b"ab\n", 1000, 0, 64);
+        let buffers = vec![buffer];
+        assert_eq!(tree.total_bytes(), 3000);
+        assert_eq!(tree.line_count(), Some(1001));
+        assert_eq!(text(&tree, &buffers), b"ab\n".repeat(1000));
+        // 21 copies per leaf, all backed by the same 63 bytes
+        assert!(matches!(&buffers[0].data, BufferData::Loaded { data, .. } if data.len() == 63));
+        assert_eq!(tree.get_leaves().len(), 48);
+        assert_eq!(tree.offset_to_position(2998, &buffers), Some((999, 1)));
+
+        // Edits only touch the pieces they land in
+        let mut tree = tree;
+        tree.delete(1, 2997, &buffers);
+        assert_eq!(text(&tree, &buffers), b"ab\n");
+
+        let (tree, buffer) = PieceTree::repeated(b'\n', 5, 1, 2);
+        let buffers = vec![StringBuffer::new(0, Vec::new()), buffer];
+        assert_eq!(text(&tree, &buffers), b"\n\n\n\n\n");
+        assert_eq!(tree.line_count(), Some(6));
+
+        // A pattern longer than a leaf still gets one copy per piece
+        let (tree, buffer) = PieceTree::from_repeated_slice(b"xyz", 3, 0, 2);
+        assert_eq!(text(&tree, &[buffer]), b"xyzxyzxyz");
+        assert_eq!(tree.get_leaves().len(), 3);
+
+        let (tree, _) = PieceTree::repeated(b'a', 0, 0, 64);
+        assert_eq!(tree.total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_subtree_shares_nodes_and_leaves_original_alone() {
+        let buffers = vec![
+            StringBuffer::new(0, b"hello\nworld\n".to_vec()),
+            StringBuffer::new(1, b"big ".to_vec()),
+        ];
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 12, Some(2));
+        tree.insert(6, BufferLocation::Added(1), 0, 4, Some(0), &buffers);
+        let content = |tree: &PieceTree| {
+            let mut text = Vec::new();
+            let flow = tree.visit_leaves(&buffers, |_, bytes| {
+                text.extend_from_slice(bytes);
+                ControlFlow::Continue(())
+            });
+            assert_eq!(flow, ControlFlow::Continue(()));
+            String::from_utf8(text).unwrap()
+        };
+        assert_eq!(content(&tree), "hello\nbig world\n");
+
+        let sub = tree.subtree(3..13, &buffers).unwrap();
+        assert_eq!(content(&sub), "lo\nbig wor");
+        assert_eq!(sub.total_bytes(), 10);
+        assert_eq!(sub.line_count(), Some(2));
+
+        // The whole range is the same tree, and the original is unchanged
+        let whole = tree.subtree(0..16, &buffers).unwrap();
+        assert!(Arc::ptr_eq(&whole.root(), &tree.root()));
+        assert_eq!(content(&tree), "hello\nbig world\n");
+        assert_eq!(tree.total_bytes(), 16);
+
+        assert_eq!(tree.subtree(5..5, &buffers).unwrap().total_bytes(), 0);
+        assert!(tree.subtree(10..17, &buffers).is_none());
+    }
+
+    #[test]
+    fn test_leaf_at_out_of_range() {
+        let buffers = test_buffers();
+        let tree = PieceTree::new(BufferLocation::Stored(0), 0, 20, Some(0));
+        assert_eq!(tree.leaf_at(20, &buffers), None);
+        assert_eq!(tree.leaf_at(usize::MAX, &buffers), None);
+        assert_eq!(PieceTree::empty().leaf_at(0, &buffers), None);
+    }
+
+    #[test]
+    fn test_unchanged_prefix_and_suffix_after_middle_insert() {
+        let mut buffers = vec![
+            StringBuffer::new(0, vec![b'a'; 10_000]),
+            StringBuffer::new(1, vec![b'b'; 10]),
+        ];
+        let mut old = PieceTree::new(BufferLocation::Stored(0), 0, 10_000, Some(0));
+        // Give the old tree some structure so the walk has internal nodes to split
+        for at in [2_000, 4_000, 6_000, 8_000] {
+            old.split_at_offset(at, &buffers);
+        }
+
+        let mut new = old.clone();
+        new.insert(5_000, BufferLocation::Added(1), 0, 10, Some(0), &buffers);
+
+        assert_eq!(PieceTree::unchanged_prefix_len(&old, &new), 5_000);
+        assert_eq!(PieceTree::unchanged_suffix_len(&old, &new), 5_000);
+
+        // Typing again right after the first insert extends the same added
+        // buffer; only the bytes around the edit are reported as changed
+        buffers[1].append(b"bbbbb");
+        let mut newer = new.clone();
+        newer.insert(5_010, BufferLocation::Added(1), 10, 5, Some(0), &buffers);
+        assert_eq!(PieceTree::unchanged_prefix_len(&new, &newer), 5_010);
+        assert_eq!(PieceTree::unchanged_suffix_len(&new, &newer), 5_000);
+    }
+
+    #[test]
+    fn test_unchanged_len_of_shared_and_unrelated_trees() {
+        let buffers = test_buffers();
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 100, Some(0));
+        tree.insert(40, BufferLocation::Added(1), 0, 50, Some(0), &buffers);
+
+        // A clone shares the root, so the whole document is unchanged
+        let copy = tree.clone();
+        assert_eq!(PieceTree::unchanged_prefix_len(&tree, &copy), 150);
+        assert_eq!(PieceTree::unchanged_suffix_len(&tree, &copy), 150);
+
+        // Same bytes from a different buffer can't be proven equal
+        let other = PieceTree::new(BufferLocation::Stored(2), 0, 25, Some(0));
+        assert_eq!(PieceTree::unchanged_prefix_len(&tree, &other), 0);
+        assert_eq!(PieceTree::unchanged_suffix_len(&tree, &other), 0);
+        assert_eq!(
+            PieceTree::unchanged_prefix_len(&tree, &PieceTree::empty()),
+            0
+        );
+
+        let mut deleted = tree.clone();
+        deleted.delete(10, 20, &buffers);
+        assert_eq!(PieceTree::unchanged_prefix_len(&tree, &deleted), 10);
+        assert_eq!(PieceTree::unchanged_suffix_len(&tree, &deleted), 120);
+    }
+
+    #[test]
+    fn test_content_hash_follows_content_not_layout() {
+        let buffers = vec![
+            StringBuffer::new(0, b"hello world\n".to_vec()),
+            StringBuffer::new(1, b"big ".to_vec()),
+            StringBuffer::new(2, b"hello big world\n".to_vec()),
+            StringBuffer::new_unloaded(3, PathBuf::from("/nonexistent"), 0, 12),
+        ];
+        let original = PieceTree::new(BufferLocation::Stored(0), 0, 12, Some(1));
+        let original_hash = original.content_hash(&buffers).unwrap();
+        assert_eq!(original_hash, content_hash(b"hello world\n"));
+
+        let mut edited = original.clone();
+        edited.insert(6, BufferLocation::Added(1), 0, 4, Some(0), &buffers);
+        let edited_hash = edited.content_hash(&buffers).unwrap();
+        assert_ne!(edited_hash, original_hash);
+
+        // Same bytes in a single piece of another buffer hash the same
+        let flat = PieceTree::new(BufferLocation::Stored(2), 0, 16, Some(1));
+        assert_eq!(flat.content_hash(&buffers), Some(edited_hash));
+
+        // Undoing the edit brings the original hash back
+        edited.delete(6, 4, &buffers);
+        assert_eq!(edited.content_hash(&buffers), Some(original_hash));
+
+        let unloaded = PieceTree::new(BufferLocation::Stored(3), 0, 12, None);
+        assert_eq!(unloaded.content_hash(&buffers), None);
+    }
+
+    #[test]
+    fn test_rechunk_merges_and_splits_leaves() {
+        let text = b"ab\ncd\nefgh\nijklmnopq\nrst".to_vec();
+        let mut builder = PieceTreeBuilder::new(0, 2);
+        builder.push_chunk(&text);
+        let (small, buffer) = builder.build();
+        let buffers = vec![buffer];
+        let leaf_sizes = |tree: &PieceTree| {
+            let mut leaves = Vec::new();
+            tree.root.collect_leaves(&mut leaves);
+            leaves.iter().map(|leaf| leaf.bytes).collect::<Vec<_>>()
+        };
+        let content = |tree: &PieceTree| {
+            let mut content = Vec::new();
+            let _ = tree.visit_leaves(&buffers, |_, data| {
+                content.extend_from_slice(data);
+                ControlFlow::Continue(())
+            });
+            content
+        };
+        assert_eq!(leaf_sizes(&small), [2; 12]);
+
+        // 2-byte leaves from one buffer merge into 8-byte ones
+        let large = small.rechunk(8, &buffers);
+        assert_eq!(leaf_sizes(&large), vec![8, 8, 8]);
+        assert_eq!(content(&large), text);
+        assert_eq!(large.total_bytes(), text.len());
+        assert_eq!(large.line_count(), small.line_count());
+
+        // Going back down cuts them again, recounting line feeds
+        let smaller = large.rechunk(3, &buffers);
+        assert_eq!(leaf_sizes(&smaller), vec![3, 3, 2, 3, 3, 2, 3, 3, 2]);
+        assert_eq!(content(&smaller), text);
+        assert_eq!(smaller.line_count(), small.line_count());
+    }
+
+    #[test]
+    fn test_from_reader_matches_tree_over_slice() {
+        let text: Vec<u8> = (0..5000)
+            .map(|i| format!("line {i}\n"))
+            .collect::<String>()
+            .into_bytes();
+        let (tree, buffer) =
+            PieceTree::from_reader(&mut std::io::Cursor::new(&text), 2, 4096).unwrap();
+        let buffers = vec![buffer];
+
+        let whole = PieceTree::new(
+            BufferLocation::Stored(2),
+            0,
+            text.len(),
+            Some(text.iter().filter(|&&b| b == b'\n').count()),
+        );
+        let whole_buffers = vec![StringBuffer::new(2, text.clone())];
+        assert_eq!(tree.total_bytes(), whole.total_bytes());
+        assert_eq!(tree.line_count(), whole.line_count());
+        assert_eq!(
+            tree.content_hash(&buffers),
+            whole.content_hash(&whole_buffers)
+        );
+        // The 64 KiB read blocks are cut into 4 KiB leaves
+        assert_eq!(tree.stats().leaf_count, text.len().div_ceil(4096));
+
+        let (empty, _) = PieceTree::from_reader(&mut std::io::empty(), 0, 4096).unwrap();
+        assert_eq!(empty.total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_line_aligned_leaves_start_at_line_starts() {
+        let text: Vec<u8> = (0..2000)
+            .map(|i| format!("{}\n", "x".repeat(i % 70)))
+            .collect::<String>()
+            .into_bytes();
+        let (tree, buffer) = PieceTree::from_slice_line_aligned(&text, 0, 1024, 256);
+        let buffers = vec![buffer];
+        let leaves = tree.get_leaves();
+        assert!(leaves.len() > 1);
+        for leaf in &leaves {
+            assert!(leaf.bytes <= 1024);
+            assert!(leaf.offset == 0 || text[leaf.offset - 1] == b'\n');
+        }
+        assert_eq!(tree.total_bytes(), text.len());
+        assert_eq!(tree.line_count(), Some(2001));
+        let whole = PieceTree::new(BufferLocation::Stored(0), 0, text.len(), Some(2000));
+        assert_eq!(tree.content_hash(&buffers), whole.content_hash(&buffers));
+        // Line 1000 sits at the same place as in a tree over the whole slice
+        assert_eq!(
+            tree.position_to_offset(1000, 3, &buffers),
+            whole.position_to_offset(1000, 3, &buffers)
+        );
+    }
+
+    #[test]
+    fn test_line_aligned_falls_back_to_byte_split_for_long_lines() {
+        let mut text = b"short\n".to_vec();
+        text.extend(b"y".repeat(25));
+        text.extend(b"\nend\n");
+        let (tree, _) = PieceTree::from_slice_line_aligned(&text, 0, 10, 10);
+        let leaves: Vec<_> = tree
+            .get_leaves()
+            .iter()
+            .map(|l| (l.offset, l.bytes))
+            .collect();
+        // "short\n", then the long line in 10-byte cuts; the rest fits one leaf
+        assert_eq!(leaves, [(0, 6), (6, 10), (16, 10), (26, 10)]);
+
+        let (empty, _) = PieceTree::from_slice_line_aligned(b"", 0, 10, 10);
+        assert_eq!(empty.total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_debug_structure_outlines_nodes() {
+        let buffers = vec![
+            StringBuffer::new(0, b"Hello world".to_vec()),
+            StringBuffer::new(1, b", dear reader of this\ntree,".to_vec()),
+            StringBuffer::new_unloaded(2, PathBuf::from("/nonexistent"), 0, 4),
+        ];
+        let mut tree = PieceTree::new(BufferLocation::Stored(0), 0, 11, Some(0));
+        tree.insert(5, BufferLocation::Added(1), 0, 27, Some(1), &buffers);
+        tree.insert(38, BufferLocation::Stored(2), 0, 4, None, &buffers);
+        assert_eq!(
+            tree.debug_structure(&buffers),
+            "Internal(42)\n\
+             \x20 Internal(32)\n\
+             \x20   Leaf(5) \"Hello\"\n\
+             \x20   Leaf(27) \", dear reader of\"...\n\
+             \x20 Internal(10)\n\
+             \x20   Leaf(6) \" world\"\n\
+             \x20   Leaf(4) <unloaded>\n"
+        );
+        assert_eq!(
+            PieceTree::empty().debug_structure(&buffers),
+            "Leaf(0) \"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_edits_never_store_empty_leaves() {
+        let buffers = vec![
+            StringBuffer::new(0, b"hello world\n".to_vec()),
+            StringBuffer::new(1, b"abcdef".to_vec()),
+        ];
+        let assert_no_empty_leaves = |tree: &PieceTree| {
+            let leaves = tree.get_leaves();
+            if tree.total_bytes() == 0 {
+                assert_eq!(leaves.len(), 1, "only the root of an empty tree");
+            } else {
+                assert!(leaves.iter().all(|leaf| leaf.bytes > 0), "{:?}", leaves);
+            }
+            let _ = tree.visit_leaves(&buffers, |_, data| {
+                assert!(!data.is_empty() || tree.total_bytes() == 0);
+                ControlFlow::Continue(())
+            });
+        };
+
+        // Inserting into an empty tree used to keep the empty root as a leaf
+        let mut tree = PieceTree::empty();
+        tree.insert(0, BufferLocation::Stored(0), 0, 12, Some(1), &buffers);
+        assert_no_empty_leaves(&tree);
+        tree.insert(5, BufferLocation::Added(1), 0, 6, Some(0), &buffers);
+        assert_no_empty_leaves(&tree);
+
+        // Removing whole pieces, piece ends and everything
+        for (offset, bytes) in [(5, 6), (0, 2), (8, 2), (3, 1), (0, 100)] {
+            tree.delete(offset, bytes, &buffers);
+            assert_no_empty_leaves(&tree);
+        }
+        assert_eq!(tree.total_bytes(), 0);
+
+        tree.insert(0, BufferLocation::Added(1), 0, 3, Some(0), &buffers);
+        assert_no_empty_leaves(&tree);
+        assert_eq!(tree.stats().leaf_count, 1);
+    }
+
+    #[test]
+    fn test_transaction_matches_sequential_edits() {
+        let line = b"let foo = 1;\n";
+        let text = line.repeat(1000);
+        let mut builder = PieceTreeBuilder::new(0, 100);
+        builder.push_chunk(&text);
+        let (base, stored) = builder.build();
+        let buffers = vec![stored, StringBuffer::new(1, b"renamed".to_vec())];
+        let content = |tree: &PieceTree| {
+            let mut content = Vec::new();
+            let _ = tree.visit_leaves(&buffers, |_, data| {
+                content.extend_from_slice(data);
+                ControlFlow::Continue(())
+            });
+            content
+        };
+        let matches: Vec<usize> = (0..1000).map(|i| i * line.len() + 4).collect();
+
+        // Replace every "foo" with "renamed", back to front so offsets hold
+        let mut sequential = base.clone();
+        for &offset in matches.iter().rev() {
+            sequential.delete(offset, 3, &buffers);
+            sequential.insert(offset, BufferLocation::Added(1), 0, 7, Some(0), &buffers);
+        }
+
+        // The same edits in one transaction, in base-tree offsets
+        let mut transaction = base.transaction();
+        for &offset in &matches {
+            transaction.remove(offset..offset + 3);
+            transaction.insert(offset, BufferLocation::Added(1), 0, 7, Some(0));
+        }
+        assert_eq!(transaction.len(), 2000);
+        let batched = transaction.commit(&buffers);
+
+        let expected = String::from_utf8(text.clone())
+            .unwrap()
+            .replace("foo", "renamed");
+        assert_eq!(content(&batched), expected.as_bytes());
+        assert_eq!(content(&sequential), expected.as_bytes());
+        assert_eq!(batched.total_bytes(), sequential.total_bytes());
+        assert_eq!(batched.line_count(), sequential.line_count());
+        assert_eq!(
+            batched.content_hash(&buffers),
+            sequential.content_hash(&buffers)
+        );
+        // The base tree is untouched
+        assert_eq!(content(&base), text);
+
+        // Overlapping removals merge; inserts at one offset keep their order,
+        // and one inside removed text lands where that text was
+        let mut transaction = base.transaction();
+        transaction.remove(0..6);
+        transaction.remove(2..4);
+        transaction.insert(3, BufferLocation::Added(1), 0, 3, Some(0));
+        transaction.insert(8, BufferLocation::Added(1), 3, 4, Some(0));
+        transaction.insert(8, BufferLocation::Added(1), 0, 3, Some(0));
+        let edited = transaction.commit(&buffers);
+        assert!(content(&edited).starts_with(b"reno amedren= 1;\nlet foo"));
+        assert_eq!(edited.line_count(), base.line_count());
+
+        // Removing everything leaves an empty tree
+        let mut transaction = base.transaction();
+        transaction.remove(0..text.len() + 10);
+        assert_eq!(transaction.commit(&buffers).total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_builder_round_trips_chunks_into_balanced_tree() {
+        let chunks: Vec<Vec<u8>> = (0..10)
+            .map(|i| format!("chunk {i}\n").repeat(i * 7 + 1).into_bytes())
+            .collect();
+        let expected = chunks.concat();
+
+        let mut builder = PieceTreeBuilder::new(0, 64);
+        for chunk in &chunks {
+            builder.push_chunk(chunk);
+        }
+        builder.push_chunk(b"");
+        assert_eq!(builder.len(), expected.len());
+        let (tree, buffer) = builder.build();
+        let buffers = vec![buffer];
+
+        let mut content = Vec::new();
+        let _ = tree.visit_leaves(&buffers, |_, data| {
+            content.extend_from_slice(data);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(content, expected);
+        assert_eq!(tree.total_bytes(), expected.len());
+        assert_eq!(
+            tree.line_count(),
+            Some(expected.iter().filter(|&&b| b == b'\n').count() + 1)
+        );
+
+        // Small chunks stay whole, large ones are cut at 64 bytes
+        let stats = tree.stats();
+        let expected_leaves: usize = chunks.iter().map(|c| c.len().div_ceil(64)).sum();
+        assert_eq!(stats.leaf_count, expected_leaves);
+        let min_depth = (stats.leaf_count as f64).log2().ceil() as usize;
+        assert!(
+            stats.depth <= min_depth + 1,
+            "depth {} for {} leaves",
+            stats.depth,
+            stats.leaf_count
+        );
+
+        let (empty, _) = PieceTreeBuilder::new(0, 64).build();
+        assert_eq!(empty.total_bytes(), 0);
+    }
 }
 
 #[cfg(test)]
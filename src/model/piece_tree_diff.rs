@@ -66,6 +66,28 @@ pub fn diff_piece_trees(
     }
 }
 
+/// Lengths in bytes of the longest prefix and suffix two trees share
+/// piece-for-piece, found without reading any content.
+///
+/// The two never overlap, so whatever differs lies between them.
+pub fn shared_prefix_suffix(
+    before: &Arc<PieceTreeNode>,
+    after: &Arc<PieceTreeNode>,
+) -> (usize, usize) {
+    let mut before_leaves = Vec::new();
+    collect_leaves(before, &mut before_leaves);
+    let before_spans = with_doc_offsets(&normalize_leaves(before_leaves));
+
+    let mut after_leaves = Vec::new();
+    collect_leaves(after, &mut after_leaves);
+    let after_spans = with_doc_offsets(&normalize_leaves(after_leaves));
+
+    let prefix = common_prefix_bytes(&before_spans, &after_spans);
+    let suffix = common_suffix_bytes(&before_spans, &after_spans, prefix);
+    let before_total = before_spans.iter().map(|s| s.leaf.bytes).sum::<usize>();
+    (prefix, suffix.min(before_total - prefix))
+}
+
 fn collect_leaves(node: &Arc<PieceTreeNode>, out: &mut Vec<LeafData>) {
     match node.as_ref() {
         PieceTreeNode::Internal { left, right, .. } => {
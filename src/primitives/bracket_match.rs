@@ -0,0 +1,362 @@
+//! Bracket matching that skips strings and comments
+//!
+//! A [`CodeClassifier`] tells the matcher which bytes are code; brackets
+//! anywhere else (inside string literals or comments) are ignored, both as the
+//! starting bracket and while counting nesting depth. [`LexicalClassifier`] is
+//! a small lexer for C-like languages that needs no grammar.
+//!
+//! [`match_around`] only lexes a window of text around the cursor, so large
+//! buffers never have to be read in full.
+
+use std::ops::Range;
+
+/// Decides whether the byte at an offset is code, as opposed to part of a
+/// string literal or comment
+pub trait CodeClassifier {
+    fn is_code(&self, offset: usize) -> bool;
+}
+
+/// Classifier that treats every byte as code
+pub struct AllCode;
+
+impl CodeClassifier for AllCode {
+    fn is_code(&self, _offset: usize) -> bool {
+        true
+    }
+}
+
+/// Comment delimiters understood by [`LexicalClassifier`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommentSyntax<'a> {
+    /// Line comment marker, e.g. `//` or `#`
+    pub line: Option<&'a str>,
+    /// Block comment open and close markers, e.g. `/*` and `*/`
+    pub block: Option<(&'a str, &'a str)>,
+}
+
+impl CommentSyntax<'static> {
+    /// `//` line comments and `/* */` block comments
+    pub const C: Self = CommentSyntax {
+        line: Some("//"),
+        block: Some(("/*", "*/")),
+    };
+}
+
+/// Strings and comments understood by [`LexicalClassifier`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexicalSyntax<'a> {
+    pub comments: CommentSyntax<'a>,
+    /// Whether single quotes delimit strings, as in Python or JavaScript,
+    /// rather than character literals, as in C or Rust
+    pub single_quote_strings: bool,
+}
+
+impl LexicalSyntax<'static> {
+    /// C comments, with single quotes around character literals
+    pub const C: Self = LexicalSyntax {
+        comments: CommentSyntax::C,
+        single_quote_strings: false,
+    };
+}
+
+/// Finds strings and comments by scanning the text once
+///
+/// Double-quoted and backtick strings honour backslash escapes and may span
+/// lines, as do single-quoted strings when the syntax has them. Otherwise a
+/// single quote only opens a character literal when it is closed right away
+/// (`'x'`, `'\n'`), so Rust lifetimes and apostrophes in prose are left
+/// alone. An unterminated string or comment runs to the end of the text.
+#[derive(Debug, Clone, Default)]
+pub struct LexicalClassifier {
+    /// Sorted, non-overlapping ranges of string and comment bytes
+    non_code: Vec<Range<usize>>,
+}
+
+impl LexicalClassifier {
+    pub fn new(text: &[u8], syntax: LexicalSyntax) -> Self {
+        let comments = syntax.comments;
+        let starts_with = |pos: usize, marker: &str| text[pos..].starts_with(marker.as_bytes());
+        let mut non_code = Vec::new();
+        let mut pos = 0;
+        while pos < text.len() {
+            let start = pos;
+            let end = if let Some(line) = comments.line.filter(|m| starts_with(pos, m)) {
+                let body = start + line.len();
+                text[body..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(text.len(), |i| body + i)
+            } else if let Some((open, close)) = comments.block.filter(|(o, _)| starts_with(pos, o))
+            {
+                let body = start + open.len();
+                find(&text[body..], close.as_bytes()).map_or(text.len(), |i| body + i + close.len())
+            } else if text[pos] == b'"'
+                || text[pos] == b'`'
+                || (syntax.single_quote_strings && text[pos] == b'\'')
+            {
+                string_end(text, pos)
+            } else if let Some(end) = char_literal_end(text, pos) {
+                end
+            } else {
+                pos += 1;
+                continue;
+            };
+            non_code.push(start..end);
+            pos = end;
+        }
+        Self { non_code }
+    }
+}
+
+impl CodeClassifier for LexicalClassifier {
+    fn is_code(&self, offset: usize) -> bool {
+        let index = self.non_code.partition_point(|range| range.end <= offset);
+        self.non_code
+            .get(index)
+            .is_none_or(|range| range.start > offset)
+    }
+}
+
+/// End (exclusive) of the string opened by the quote at `start`
+fn string_end(text: &[u8], start: usize) -> usize {
+    let quote = text[start];
+    let mut pos = start + 1;
+    while pos < text.len() {
+        match text[pos] {
+            b'\\' => pos += 2,
+            b if b == quote => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+    text.len()
+}
+
+/// End (exclusive) of the character literal at `start`, if there is one
+fn char_literal_end(text: &[u8], start: usize) -> Option<usize> {
+    if text[start] != b'\'' {
+        return None;
+    }
+    let rest = &text[start + 1..];
+    let body = match rest.first()? {
+        b'\\' => {
+            // Escapes are short: '\n', '\x7f', '\u{1F600}'
+            rest.iter()
+                .take(12)
+                .skip(2)
+                .position(|&b| b == b'\'')
+                .map(|i| i + 2)?
+        }
+        b'\'' | b'\n' => return None,
+        &b => utf8_len(b),
+    };
+    (rest.get(body) == Some(&b'\'')).then_some(start + 1 + body + 1)
+}
+
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0xF0.. => 4,
+        0xE0.. => 3,
+        0xC0.. => 2,
+        _ => 1,
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The bracket pair `b` belongs to, and whether it opens the pair
+fn bracket_pair(b: u8) -> Option<(u8, u8, bool)> {
+    match b {
+        b'(' => Some((b'(', b')', true)),
+        b')' => Some((b'(', b')', false)),
+        b'[' => Some((b'[', b']', true)),
+        b']' => Some((b'[', b']', false)),
+        b'{' => Some((b'{', b'}', true)),
+        b'}' => Some((b'{', b'}', false)),
+        b'<' => Some((b'<', b'>', true)),
+        b'>' => Some((b'<', b'>', false)),
+        _ => None,
+    }
+}
+
+/// Whether the byte at `pos` is a bracket in code
+pub fn is_bracket(text: &[u8], pos: usize, classifier: &dyn CodeClassifier) -> bool {
+    text.get(pos)
+        .is_some_and(|&b| bracket_pair(b).is_some() && classifier.is_code(pos))
+}
+
+/// Offset of the bracket matching the one at `pos`
+///
+/// Returns None if `pos` is not a bracket in code or the bracket is unbalanced.
+pub fn find_matching_bracket(
+    text: &[u8],
+    pos: usize,
+    classifier: &dyn CodeClassifier,
+) -> Option<usize> {
+    if !is_bracket(text, pos, classifier) {
+        return None;
+    }
+    let (opening, closing, forward) = bracket_pair(text[pos])?;
+    let (deeper, shallower) = if forward {
+        (opening, closing)
+    } else {
+        (closing, opening)
+    };
+    let candidates: Box<dyn Iterator<Item = usize>> = if forward {
+        Box::new(pos + 1..text.len())
+    } else {
+        Box::new((0..pos).rev())
+    };
+
+    let mut depth = 1usize;
+    for i in candidates {
+        let b = text[i];
+        if (b != deeper && b != shallower) || !classifier.is_code(i) {
+            continue;
+        }
+        if b == deeper {
+            depth += 1;
+        } else {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// How far either side of the cursor [`match_around`] lexes at first
+const WINDOW_REACH: usize = 64 * 1024;
+
+/// How far either side of the cursor [`match_around`] lexes at most
+const MAX_WINDOW_REACH: usize = 4 * 1024 * 1024;
+
+/// The bracket at `cursor` and its partner, as (bracket, partner)
+///
+/// `read` returns the bytes in a range of a text that is `len` bytes long.
+/// The byte at the cursor is tried first, then the one just before it, so a
+/// cursor right after a closing bracket still finds it.
+///
+/// Only a window around the cursor is read and lexed, starting at a line
+/// start so the window doesn't open inside a string. The window doubles until
+/// the partner turns up, it covers the whole text, or it reaches
+/// [`MAX_WINDOW_REACH`] either side. Returns None if there is no bracket, it
+/// is unbalanced, or the read fails.
+pub fn match_around(
+    len: usize,
+    cursor: usize,
+    syntax: LexicalSyntax,
+    mut read: impl FnMut(Range<usize>) -> Option<Vec<u8>>,
+) -> Option<(usize, usize)> {
+    let mut reach = WINDOW_REACH;
+    loop {
+        let window = cursor.saturating_sub(reach)..cursor.saturating_add(reach).min(len);
+        let text = read(window.clone())?;
+        let skip = if window.start == 0 {
+            0
+        } else {
+            text.iter()
+                .position(|&b| b == b'\n')
+                .map_or(text.len(), |i| i + 1)
+        };
+        let start = window.start + skip;
+        let text = &text[skip..];
+        let classifier = LexicalClassifier::new(text, syntax);
+
+        let found = [Some(cursor), cursor.checked_sub(1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|pos| pos.checked_sub(start))
+            .find(|&pos| is_bracket(text, pos, &classifier))
+            .and_then(|pos| {
+                find_matching_bracket(text, pos, &classifier)
+                    .map(|partner| (start + pos, start + partner))
+            });
+        let whole_text = window.start == 0 && window.end == len;
+        if found.is_some() || whole_text || reach >= MAX_WINDOW_REACH {
+            return found;
+        }
+        reach *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_skips_strings_and_comments() {
+        let text = br#"f(a, ")", '(', /* ) */ g(b)) // )"#;
+        let classifier = LexicalClassifier::new(text, LexicalSyntax::C);
+        assert_eq!(find_matching_bracket(text, 1, &classifier), Some(27));
+        assert_eq!(find_matching_bracket(text, 27, &classifier), Some(1));
+        // Brackets inside the string and the comments are not brackets at all
+        assert_eq!(find_matching_bracket(text, 6, &classifier), None);
+        assert_eq!(find_matching_bracket(text, 18, &classifier), None);
+    }
+
+    #[test]
+    fn test_lifetimes_are_not_char_literals() {
+        let text = b"fn f<'a>(x: &'a str) -> char { '}' }";
+        let classifier = LexicalClassifier::new(text, LexicalSyntax::C);
+        assert_eq!(find_matching_bracket(text, 8, &classifier), Some(19));
+        assert_eq!(find_matching_bracket(text, 29, &classifier), Some(35));
+    }
+
+    #[test]
+    fn test_single_quote_strings() {
+        let text = b"f('(', x)";
+        let python = LexicalSyntax {
+            comments: CommentSyntax {
+                line: Some("#"),
+                block: None,
+            },
+            single_quote_strings: true,
+        };
+        let classifier = LexicalClassifier::new(text, python);
+        assert_eq!(find_matching_bracket(text, 1, &classifier), Some(8));
+
+        // Longer single-quoted strings are strings too
+        let text = b"f('((', x)";
+        let classifier = LexicalClassifier::new(text, python);
+        assert_eq!(find_matching_bracket(text, 1, &classifier), Some(9));
+        let classifier = LexicalClassifier::new(text, LexicalSyntax::C);
+        assert_eq!(find_matching_bracket(text, 1, &classifier), None);
+    }
+
+    #[test]
+    fn test_match_around_widens_the_window() {
+        let filler = "x = \"(\";\n".repeat(WINDOW_REACH / 4);
+        let text = format!("f({filler}){filler}");
+        let close = text.rfind(')').unwrap();
+        let mut reads = Vec::new();
+        let read = |range: Range<usize>| {
+            reads.push(range.clone());
+            Some(text.as_bytes()[range].to_vec())
+        };
+        assert_eq!(
+            match_around(text.len(), 1, LexicalSyntax::C, read),
+            Some((1, close))
+        );
+        // Three windows: 64 KiB and 128 KiB either side fall short
+        assert_eq!(reads.len(), 3);
+        assert!(reads[2].end < text.len());
+
+        let read = |range: Range<usize>| Some(text.as_bytes()[range].to_vec());
+        assert_eq!(
+            match_around(text.len(), close + 1, LexicalSyntax::C, read),
+            Some((close, 1))
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_brackets() {
+        assert_eq!(find_matching_bracket(b"(()", 0, &AllCode), None);
+        assert_eq!(find_matching_bracket(b"())", 2, &AllCode), None);
+        assert_eq!(find_matching_bracket(b"x", 5, &AllCode), None);
+    }
+}
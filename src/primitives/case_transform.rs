@@ -0,0 +1,98 @@
+//! Case conversion for the UPPER/lower/Title/tOGGLE case commands
+//!
+//! Uses the full Unicode case mappings rather than ASCII-only ones, so the
+//! result can be longer or shorter than the input: "ß" uppercases to "SS" and
+//! "İ" lowercases to "i̇".
+
+/// A change of letter case applied to a run of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseTransform {
+    /// "Hello World" -> "HELLO WORLD"
+    Upper,
+    /// "Hello World" -> "hello world"
+    Lower,
+    /// "hello wORLD" -> "Hello World": the first letter of each word upper, the rest lower
+    Title,
+    /// "Hello World" -> "hELLO wORLD"
+    Toggle,
+}
+
+impl CaseTransform {
+    /// Convert `text`
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            CaseTransform::Upper => text.to_uppercase(),
+            CaseTransform::Lower => text.to_lowercase(),
+            CaseTransform::Title => {
+                let mut result = String::with_capacity(text.len());
+                let mut in_word = false;
+                for c in text.chars() {
+                    if !c.is_alphanumeric() {
+                        result.push(c);
+                    } else if in_word {
+                        result.extend(c.to_lowercase());
+                    } else {
+                        result.extend(c.to_uppercase());
+                    }
+                    // Apostrophes don't start a new word ("don't" -> "Don't")
+                    in_word = c.is_alphanumeric() || (in_word && c == '\'');
+                }
+                result
+            }
+            CaseTransform::Toggle => {
+                let mut result = String::with_capacity(text.len());
+                for c in text.chars() {
+                    if c.is_uppercase() {
+                        result.extend(c.to_lowercase());
+                    } else if c.is_lowercase() {
+                        result.extend(c.to_uppercase());
+                    } else {
+                        result.push(c);
+                    }
+                }
+                result
+            }
+        }
+    }
+
+    /// Past-tense label for status messages
+    pub fn label(self) -> &'static str {
+        match self {
+            CaseTransform::Upper => "Uppercased",
+            CaseTransform::Lower => "Lowercased",
+            CaseTransform::Title => "Title-cased",
+            CaseTransform::Toggle => "Toggled case of",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upper_and_lower_use_unicode_mappings() {
+        assert_eq!(CaseTransform::Upper.apply("straße café"), "STRASSE CAFÉ");
+        assert_eq!(CaseTransform::Lower.apply("ÉCOLE ΣΟΦΙΑ"), "école σοφια");
+        // Non-letters pass through untouched
+        assert_eq!(CaseTransform::Upper.apply("a_1 → b!"), "A_1 → B!");
+    }
+
+    #[test]
+    fn test_title_case() {
+        assert_eq!(
+            CaseTransform::Title.apply("hello wORLD, élan-vital don't"),
+            "Hello World, Élan-Vital Don't"
+        );
+        assert_eq!(CaseTransform::Title.apply("ßig"), "SSig");
+    }
+
+    #[test]
+    fn test_toggle_case() {
+        assert_eq!(
+            CaseTransform::Toggle.apply("Hello Ärger 42"),
+            "hELLO äRGER 42"
+        );
+        assert_eq!(CaseTransform::Toggle.apply("ß"), "SS");
+    }
+}
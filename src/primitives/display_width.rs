@@ -5,6 +5,7 @@
 //! cursor positioning, line wrapping, and UI layout with CJK characters,
 //! emoji, and other double-width or zero-width characters.
 
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Calculate the display width of a single character.
@@ -27,6 +28,21 @@ pub fn str_width(s: &str) -> usize {
     s.width()
 }
 
+/// Calculate the display width of a line, expanding tabs to the next tab stop.
+///
+/// Width is measured per grapheme cluster, so combining marks add nothing to
+/// the character they attach to. A `tab_width` of 0 is treated as 1.
+pub fn str_width_with_tabs(s: &str, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    s.graphemes(true).fold(0, |col, grapheme| {
+        if grapheme == "\t" {
+            col + tab_width - col % tab_width
+        } else {
+            col + str_width(grapheme)
+        }
+    })
+}
+
 /// Extension trait for convenient width calculation on string types.
 pub trait DisplayWidth {
     /// Returns the display width (number of terminal columns) of this string.
@@ -48,6 +48,42 @@ use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
 /// Maximum bytes to parse before cursor for indent calculation
 const MAX_PARSE_BYTES: usize = 2000;
 
+/// What one level of indentation is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tabs,
+    /// This many spaces
+    Spaces(usize),
+}
+
+impl IndentStyle {
+    /// The style the editor config asks for
+    pub fn from_config(config: &crate::config::EditorConfig) -> Self {
+        if config.indent_with_tabs {
+            Self::Tabs
+        } else {
+            Self::Spaces(config.tab_size)
+        }
+    }
+
+    /// Text of one indentation level
+    pub fn unit(self) -> String {
+        match self {
+            Self::Tabs => "\t".to_string(),
+            Self::Spaces(width) => " ".repeat(width.max(1)),
+        }
+    }
+
+    /// Columns of one indentation level, with tabs `tab_size` wide
+    pub fn width(self, tab_size: usize) -> usize {
+        match self {
+            Self::Tabs => tab_size,
+            Self::Spaces(width) => width,
+        }
+        .max(1)
+    }
+}
+
 /// Indent calculator using tree-sitter queries
 pub struct IndentCalculator {
     /// Map of language to (parser, query)
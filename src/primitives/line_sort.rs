@@ -0,0 +1,155 @@
+//! Line ordering for the Sort Lines command
+//!
+//! Sorting is stable, so lines that compare equal (e.g. "a" and "A" when
+//! ignoring case) keep their order, and removing duplicates keeps the first
+//! of each run.
+
+use std::cmp::Ordering;
+
+/// How [`SortOptions::apply`] orders lines
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub struct SortOptions {
+    /// Largest first
+    pub reverse: bool,
+    /// Compare letters without regard to case
+    pub case_insensitive: bool,
+    /// Compare by the number at the start of each line ("9" before "10");
+    /// lines without one come first
+    pub numeric: bool,
+    /// Keep only the first of the lines that compare equal
+    pub unique: bool,
+}
+
+impl SortOptions {
+    /// Sort the lines of `text`
+    ///
+    /// A trailing line break stays at the end whichever line ends up last,
+    /// and the lines are joined with `\r\n` if `text` uses it.
+    pub fn apply(self, text: &str) -> String {
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let (body, trailing) = match text.strip_suffix(newline) {
+            Some(body) => (body, newline),
+            None => (text, ""),
+        };
+        let mut lines: Vec<&str> = body.split(newline).collect();
+        lines.sort_by(|a, b| {
+            let ordering = self.compare(a, b);
+            if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        if self.unique {
+            lines.dedup_by(|a, b| self.compare(a, b) == Ordering::Equal);
+        }
+        let mut sorted = lines.join(newline);
+        sorted.push_str(trailing);
+        sorted
+    }
+
+    fn compare(self, a: &str, b: &str) -> Ordering {
+        let by_number = if self.numeric {
+            match (leading_number(a), leading_number(b)) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (x, y) => x.is_some().cmp(&y.is_some()),
+            }
+        } else {
+            Ordering::Equal
+        };
+        by_number.then_with(|| {
+            if self.case_insensitive {
+                a.to_lowercase().cmp(&b.to_lowercase())
+            } else {
+                a.cmp(b)
+            }
+        })
+    }
+}
+
+/// The number `line` starts with, after any indentation ("-1.5", "42")
+fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let sign = usize::from(line.starts_with(['-', '+']));
+    let digits = |s: &str| s.bytes().take_while(u8::is_ascii_digit).count();
+    let mut end = sign + digits(&line[sign..]);
+    if end == sign {
+        return None;
+    }
+    if line[end..].starts_with('.') {
+        let fraction = digits(&line[end + 1..]);
+        if fraction > 0 {
+            end += 1 + fraction;
+        }
+    }
+    line[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_keeps_trailing_newline_in_place() {
+        let options = SortOptions::default();
+        assert_eq!(options.apply("pear\napple\nfig"), "apple\nfig\npear");
+        assert_eq!(options.apply("pear\napple\nfig\n"), "apple\nfig\npear\n");
+        assert_eq!(options.apply("b\r\na\r\n"), "a\r\nb\r\n");
+        let reverse = SortOptions {
+            reverse: true,
+            ..Default::default()
+        };
+        assert_eq!(reverse.apply("b\nc\na\n"), "c\nb\na\n");
+    }
+
+    #[test]
+    fn test_sort_case_insensitive() {
+        let text = "banana\nApple\ncherry\napple\nBanana\n";
+        assert_eq!(
+            SortOptions::default().apply(text),
+            "Apple\nBanana\napple\nbanana\ncherry\n"
+        );
+        let options = SortOptions {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        // Equal lines keep their order
+        assert_eq!(
+            options.apply(text),
+            "Apple\napple\nbanana\nBanana\ncherry\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_numeric() {
+        let options = SortOptions {
+            numeric: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.apply("10 ten\n9 nine\n  -2.5 minus\nnone\n100\n9 eight\n"),
+            "none\n  -2.5 minus\n9 eight\n9 nine\n10 ten\n100\n"
+        );
+        // Plain text order would put "10" first
+        assert_eq!(SortOptions::default().apply("9\n10\n"), "10\n9\n");
+    }
+
+    #[test]
+    fn test_sort_unique() {
+        let options = SortOptions {
+            unique: true,
+            ..Default::default()
+        };
+        assert_eq!(options.apply("b\na\nb\nc\na\n"), "a\nb\nc\n");
+
+        let ignoring_case = SortOptions {
+            unique: true,
+            case_insensitive: true,
+            reverse: true,
+            ..Default::default()
+        };
+        assert_eq!(ignoring_case.apply("Go\nrust\ngo\nRust\n"), "rust\nGo\n");
+    }
+}
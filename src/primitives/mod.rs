@@ -5,15 +5,21 @@
 
 pub mod ansi;
 pub mod ansi_background;
+pub mod bracket_match;
+pub mod case_transform;
 pub mod display_width;
 pub mod grammar_registry;
 pub mod highlight_engine;
 pub mod highlighter;
 pub mod indent;
 pub mod line_iterator;
+pub mod line_sort;
 pub mod line_wrapping;
 pub mod semantic_highlight;
+pub mod snippet;
+pub mod spell_check;
 pub mod text_property;
+pub mod text_stats;
 pub mod textmate_highlighter;
 pub mod visual_layout;
 pub mod word_navigation;
@@ -23,7 +23,7 @@
 
 use crate::model::buffer::Buffer;
 use crate::primitives::highlighter::{HighlightSpan, Language};
-use crate::primitives::word_navigation::{find_word_end, find_word_start, is_word_char};
+use crate::primitives::word_navigation::{find_word_end, find_word_start, is_word_char, WordDef};
 use ratatui::style::Color;
 use std::ops::Range;
 use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
@@ -751,7 +751,8 @@ impl SemanticHighlighter {
             // Otherwise, cursor on whitespace/punctuation should not highlight
             if is_after_word && position >= buf_len {
                 // Use the word before cursor
-                let start = find_word_start(buffer, position.saturating_sub(1));
+                let start =
+                    find_word_start(buffer, position.saturating_sub(1), &WordDef::default());
                 let end = position;
                 if start < end {
                     return Some(start..end);
@@ -765,8 +766,8 @@ impl SemanticHighlighter {
         }
 
         // Find word boundaries
-        let start = find_word_start(buffer, position);
-        let end = find_word_end(buffer, position);
+        let start = find_word_start(buffer, position, &WordDef::default());
+        let end = find_word_end(buffer, position, &WordDef::default());
 
         if start < end {
             Some(start..end)
@@ -0,0 +1,339 @@
+//! Snippets with tab stops
+//!
+//! A snippet body uses the familiar TextMate/LSP syntax: `$1`, `${2}` and
+//! `${3:placeholder}` mark tab stops visited in numeric order, and `$0` marks
+//! where the cursor ends up (the end of the snippet if absent). A stop number
+//! used more than once is mirrored: every occurrence shows the same text and
+//! all of them are selected together, so typing edits them in step.
+//! `\$`, `\}` and `\\` insert the character literally.
+
+use std::ops::Range;
+
+/// One tab stop of a parsed snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabStop {
+    /// The stop number (`0` for the final position)
+    pub index: u32,
+    /// Byte ranges of every occurrence within the expanded text
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// A snippet body expanded to plain text plus its tab stops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    /// The text to insert
+    pub text: String,
+    /// Stops in visiting order: `$1`, `$2`, ... and then `$0`
+    pub stops: Vec<TabStop>,
+}
+
+enum Segment {
+    Text(String),
+    Stop {
+        index: u32,
+        placeholder: Option<String>,
+    },
+}
+
+impl Snippet {
+    /// Parse a snippet body
+    ///
+    /// Anything that does not form a valid stop (e.g. a lone `$` or an
+    /// unterminated `${`) is kept as literal text.
+    pub fn parse(body: &str) -> Self {
+        let segments = parse_segments(body);
+
+        // A mirror without its own placeholder shows the first one given
+        let mut defaults: Vec<(u32, String)> = Vec::new();
+        for segment in &segments {
+            if let Segment::Stop {
+                index,
+                placeholder: Some(placeholder),
+            } = segment
+            {
+                if !defaults.iter().any(|(i, _)| i == index) {
+                    defaults.push((*index, placeholder.clone()));
+                }
+            }
+        }
+
+        let mut text = String::new();
+        let mut stops: Vec<TabStop> = Vec::new();
+        for segment in segments {
+            match segment {
+                Segment::Text(literal) => text.push_str(&literal),
+                Segment::Stop { index, .. } => {
+                    let start = text.len();
+                    if let Some((_, default)) = defaults.iter().find(|(i, _)| *i == index) {
+                        text.push_str(default);
+                    }
+                    let range = start..text.len();
+                    match stops.iter_mut().find(|stop| stop.index == index) {
+                        Some(stop) => stop.ranges.push(range),
+                        None => stops.push(TabStop {
+                            index,
+                            ranges: vec![range],
+                        }),
+                    }
+                }
+            }
+        }
+
+        if !stops.iter().any(|stop| stop.index == 0) {
+            let end = text.len();
+            stops.push(TabStop {
+                index: 0,
+                ranges: std::iter::once(end..end).collect(),
+            });
+        }
+        // `$0` sorts last
+        stops.sort_by_key(|stop| stop.index.wrapping_sub(1));
+
+        Self { text, stops }
+    }
+}
+
+fn parse_segments(body: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = body;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            if let Some(escaped @ ('$' | '}' | '\\')) = rest[1..].chars().next() {
+                literal.push(escaped);
+                rest = &rest[2..];
+                continue;
+            }
+        } else if c == '$' {
+            if let Some((index, placeholder, len)) = parse_stop(&rest[1..]) {
+                if !literal.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Stop { index, placeholder });
+                rest = &rest[1 + len..];
+                continue;
+            }
+        }
+        literal.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Text(literal));
+    }
+    segments
+}
+
+/// Parse what follows a `$`: `N`, `{N}` or `{N:placeholder}`
+///
+/// Returns the stop number, placeholder and number of bytes consumed.
+fn parse_stop(s: &str) -> Option<(u32, Option<String>, usize)> {
+    let digits = |s: &str| s.bytes().take_while(u8::is_ascii_digit).count();
+
+    let Some(braced) = s.strip_prefix('{') else {
+        let len = digits(s);
+        return Some((s[..len].parse().ok()?, None, len));
+    };
+
+    let len = digits(braced);
+    let index = braced[..len].parse().ok()?;
+    let after = &braced[len..];
+    if after.starts_with('}') {
+        return Some((index, None, 1 + len + 1));
+    }
+    let body = after.strip_prefix(':')?;
+
+    let mut placeholder = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '}' => return Some((index, Some(placeholder), 1 + len + 1 + i + 1)),
+            '\\' => match chars.clone().next() {
+                Some((_, escaped @ ('$' | '}' | '\\'))) => {
+                    placeholder.push(escaped);
+                    chars.next();
+                }
+                _ => placeholder.push(c),
+            },
+            _ => placeholder.push(c),
+        }
+    }
+    None
+}
+
+/// An active snippet session in a buffer
+///
+/// Holds the absolute byte ranges of every stop and which one the cursor is
+/// on. The ranges follow edits through `adjust_for_insert` and
+/// `adjust_for_delete`; an edit that does not touch the current stop ends the
+/// session.
+#[derive(Debug, Clone)]
+pub struct SnippetEngine {
+    /// Ranges of each stop in visiting order; the last one is `$0`
+    stops: Vec<Vec<Range<usize>>>,
+    /// Index into `stops` of the stop being edited
+    current: usize,
+}
+
+impl SnippetEngine {
+    /// Start a session for `snippet` inserted at byte `offset`
+    pub fn new(snippet: &Snippet, offset: usize) -> Self {
+        let stops = snippet
+            .stops
+            .iter()
+            .map(|stop| {
+                stop.ranges
+                    .iter()
+                    .map(|range| range.start + offset..range.end + offset)
+                    .collect()
+            })
+            .collect();
+        Self { stops, current: 0 }
+    }
+
+    /// Ranges of the stop being edited (more than one if it is mirrored)
+    pub fn current(&self) -> &[Range<usize>] {
+        &self.stops[self.current]
+    }
+
+    /// Whether the current stop is `$0`, which ends the session
+    pub fn is_at_final(&self) -> bool {
+        self.current + 1 == self.stops.len()
+    }
+
+    /// Move to the next stop, returning `false` if already on `$0`
+    pub fn advance(&mut self) -> bool {
+        if self.is_at_final() {
+            return false;
+        }
+        self.current += 1;
+        true
+    }
+
+    /// Move to the previous stop, returning `false` if already on the first
+    pub fn retreat(&mut self) -> bool {
+        if self.current == 0 {
+            return false;
+        }
+        self.current -= 1;
+        true
+    }
+
+    /// Track an insertion of `len` bytes at `position`
+    ///
+    /// Returns `false`, leaving the ranges untouched, if the insertion is not
+    /// within (or at either edge of) one of the current stop's ranges.
+    pub fn adjust_for_insert(&mut self, position: usize, len: usize) -> bool {
+        let Some(hit) = self
+            .current()
+            .iter()
+            .position(|range| range.start <= position && position <= range.end)
+        else {
+            return false;
+        };
+
+        for (stop, ranges) in self.stops.iter_mut().enumerate() {
+            for (i, range) in ranges.iter_mut().enumerate() {
+                if stop == self.current && i == hit {
+                    range.end += len;
+                } else if range.start >= position {
+                    range.start += len;
+                    range.end += len;
+                } else if range.end > position {
+                    range.end += len;
+                }
+            }
+        }
+        true
+    }
+
+    /// Track a deletion of `range`
+    ///
+    /// Returns `false`, leaving the ranges untouched, if the deletion is not
+    /// contained in one of the current stop's ranges.
+    pub fn adjust_for_delete(&mut self, deleted: &Range<usize>) -> bool {
+        if !self
+            .current()
+            .iter()
+            .any(|range| range.start <= deleted.start && deleted.end <= range.end)
+        {
+            return false;
+        }
+
+        let map = |offset: usize| {
+            if offset <= deleted.start {
+                offset
+            } else if offset >= deleted.end {
+                offset - deleted.len()
+            } else {
+                deleted.start
+            }
+        };
+        for range in self.stops.iter_mut().flatten() {
+            *range = map(range.start)..map(range.end);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stops_and_placeholders() {
+        let snippet = Snippet::parse("fn ${1:name}($2) {\n    $0\n}");
+        assert_eq!(snippet.text, "fn name() {\n    \n}");
+        let stops: Vec<_> = snippet
+            .stops
+            .iter()
+            .map(|stop| (stop.index, stop.ranges.clone()))
+            .collect();
+        assert_eq!(
+            stops,
+            [(1, 3..7), (2, 8..8), (0, 16..16)].map(|(index, range)| (index, vec![range]))
+        );
+    }
+
+    #[test]
+    fn test_parse_mirrors_escapes_and_implicit_final_stop() {
+        let snippet = Snippet::parse("${1:x} = $1 + \\$2 ${2:\\}}$");
+        assert_eq!(snippet.text, "x = x + $2 }$");
+        assert_eq!(snippet.stops[0].ranges, vec![0..1, 4..5]);
+        assert_eq!(snippet.stops.len(), 3);
+        assert_eq!(snippet.stops[1].ranges.first(), Some(&(11..12)));
+        assert_eq!(snippet.stops[2].index, 0);
+        assert_eq!(snippet.stops[2].ranges.first(), Some(&(13..13)));
+
+        // Unterminated placeholders stay literal
+        assert_eq!(Snippet::parse("${1:oops").text, "${1:oops");
+    }
+
+    #[test]
+    fn test_engine_tracks_edits_in_current_stop() {
+        // "let  = ;" inserted at 10, stop 1 mirrored at 14 and 17
+        let mut engine = SnippetEngine::new(&Snippet::parse("let $1 = $1;$0"), 10);
+        assert_eq!(engine.current(), &[14..14, 17..17]);
+
+        // Typing into both occurrences, first one first
+        assert!(engine.adjust_for_insert(14, 3));
+        assert_eq!(engine.current(), &[14..17, 20..20]);
+        assert!(engine.adjust_for_insert(20, 3));
+        assert_eq!(engine.current(), &[14..17, 20..23]);
+
+        assert!(engine.adjust_for_delete(&(15..17)));
+        assert_eq!(engine.current(), &[14..15, 18..21]);
+
+        // An edit outside the stop is rejected
+        assert!(!engine.adjust_for_insert(0, 1));
+        assert!(!engine.adjust_for_delete(&(13..15)));
+
+        assert!(engine.advance());
+        assert!(engine.is_at_final());
+        assert_eq!(engine.current().first(), Some(&(22..22)));
+        assert!(!engine.advance());
+        assert!(engine.retreat());
+        assert!(!engine.retreat());
+    }
+}
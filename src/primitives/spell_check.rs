@@ -0,0 +1,142 @@
+//! Spell checking of prose
+//!
+//! Text is split into whitespace-separated tokens. Tokens that look like code
+//! (identifiers with `_` or inner capitals, paths, numbers, calls, acronyms)
+//! are skipped; the words left over are looked up through a [`SpellChecker`].
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Decides whether a word is spelled correctly
+pub trait SpellChecker {
+    /// `word` is a single word of letters, possibly with inner apostrophes
+    fn is_correct(&self, word: &str) -> bool;
+}
+
+/// A dictionary backed by a plain list of words
+///
+/// Lookups ignore case, so "Hello" and "HELLO" match a listed "hello".
+#[derive(Debug, Clone, Default)]
+pub struct WordList {
+    words: HashSet<String>,
+}
+
+impl WordList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        self.words.insert(word.to_lowercase());
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for WordList {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut list = WordList::new();
+        for word in iter {
+            list.insert(word);
+        }
+        list
+    }
+}
+
+impl SpellChecker for WordList {
+    fn is_correct(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+}
+
+/// Byte ranges of the words in `text` that should be spell checked
+///
+/// Surrounding punctuation is trimmed from each token and hyphenated words
+/// are checked part by part. Single letters are never reported.
+pub fn checkable_words(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    tokens(text)
+        .filter(|token| !looks_like_code(&text[token.clone()]))
+        .flat_map(move |token| {
+            let start = token.start;
+            text[token].split('-').scan(start, |offset, part| {
+                let range = *offset..*offset + part.len();
+                *offset = range.end + 1;
+                Some(range)
+            })
+        })
+        .filter(|range| text[range.clone()].chars().count() > 1)
+}
+
+/// Ranges of the misspelled words in `text`, according to `checker`
+pub fn misspelled_words<'a>(
+    text: &'a str,
+    checker: &'a dyn SpellChecker,
+) -> impl Iterator<Item = Range<usize>> + 'a {
+    checkable_words(text).filter(move |range| !checker.is_correct(&text[range.clone()]))
+}
+
+/// Whitespace-separated tokens with leading and trailing punctuation trimmed
+fn tokens(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    text.split_whitespace().filter_map(move |token| {
+        let offset = token.as_ptr() as usize - text.as_ptr() as usize;
+        let trimmed = token.trim_start_matches(is_edge_punctuation);
+        let start = offset + (token.len() - trimmed.len());
+        let trimmed = trimmed.trim_end_matches(is_edge_punctuation);
+        (!trimmed.is_empty()).then(|| start..start + trimmed.len())
+    })
+}
+
+/// Punctuation that may wrap a word in prose: quotes, brackets, sentence marks
+fn is_edge_punctuation(c: char) -> bool {
+    "\"'()[]{}<>,.;:!?*_`‘’“”«»".contains(c)
+}
+
+/// Heuristic for tokens that are code rather than prose
+fn looks_like_code(token: &str) -> bool {
+    // Anything but letters, apostrophes and hyphens: snake_case, paths,
+    // `foo.bar`, `a::b`, `f(x)`, numbers, URLs, operators...
+    if token
+        .chars()
+        .any(|c| !(c.is_alphabetic() || c == '\'' || c == '’' || c == '-'))
+    {
+        return true;
+    }
+    // camelCase or an ACRONYM: an upper case letter after the first character
+    token.chars().skip(1).any(char::is_uppercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<&str> {
+        checkable_words(text).map(|range| &text[range]).collect()
+    }
+
+    #[test]
+    fn test_checkable_words_skip_code() {
+        assert_eq!(
+            words("Call (parse_args) then \"fooBar\" on src/main.rs, see HTTP 404 and x."),
+            vec!["Call", "then", "on", "see", "and"]
+        );
+        assert_eq!(
+            words("well-known don't café’s"),
+            vec!["well", "known", "don't", "café’s"]
+        );
+    }
+
+    #[test]
+    fn test_misspelled_words() {
+        let dictionary: WordList = ["the", "quick", "brown", "fox"].into_iter().collect();
+        let text = "The quick browm fox_name fox.";
+        let found: Vec<_> = misspelled_words(text, &dictionary).collect();
+        assert_eq!(found, vec![10..15]);
+        assert_eq!(&text[found[0].clone()], "browm");
+    }
+}
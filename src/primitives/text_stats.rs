@@ -0,0 +1,124 @@
+//! Line, word and character counts for the "Document Statistics" command
+//!
+//! Counting is streamed: text is fed in arbitrary chunks (e.g. one per piece of
+//! the piece tree) and only the current line is buffered, since grapheme
+//! clusters and UTF-8 sequences never span a line break.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Counts for a piece of text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStats {
+    /// Number of lines (line feeds + 1, like the editor's line numbers)
+    pub lines: usize,
+    /// Number of whitespace-delimited words
+    pub words: usize,
+    /// Size in bytes
+    pub bytes: usize,
+    /// Number of Unicode scalar values
+    pub chars: usize,
+    /// Number of user-perceived characters (extended grapheme clusters)
+    pub graphemes: usize,
+}
+
+impl TextStats {
+    /// Count a complete text in one go
+    pub fn of(text: &[u8]) -> Self {
+        let mut counter = TextStatsCounter::default();
+        counter.feed(text);
+        counter.finish()
+    }
+}
+
+/// Stats for a whole document and, if there is one, its selection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DocStats {
+    pub document: TextStats,
+    pub selection: Option<TextStats>,
+}
+
+/// Incremental `TextStats` computation over chunks of UTF-8
+#[derive(Debug, Default)]
+pub struct TextStatsCounter {
+    stats: TextStats,
+    /// Bytes of the line currently being read
+    line: Vec<u8>,
+}
+
+impl TextStatsCounter {
+    /// Add the next chunk of text
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.stats.bytes += chunk.len();
+        let mut rest = chunk;
+        while let Some(newline) = rest.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&rest[..=newline]);
+            self.count_line();
+            self.stats.lines += 1;
+            rest = &rest[newline + 1..];
+        }
+        self.line.extend_from_slice(rest);
+    }
+
+    /// Count whatever is left and return the totals
+    pub fn finish(mut self) -> TextStats {
+        self.count_line();
+        self.stats.lines += 1;
+        self.stats
+    }
+
+    fn count_line(&mut self) {
+        let line = String::from_utf8_lossy(&self.line);
+        self.stats.chars += line.chars().count();
+        self.stats.graphemes += line.graphemes(true).count();
+        self.stats.words += line.split_whitespace().count();
+        self.line.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAGRAPH: &str = "Crème brûlée costs 5 €.\nNaïve café 👋🏽 — déjà vu!\r\n";
+
+    #[test]
+    fn test_counts_paragraph() {
+        let stats = TextStats::of(PARAGRAPH.as_bytes());
+        assert_eq!(
+            stats,
+            TextStats {
+                lines: 3,
+                words: 11,
+                bytes: PARAGRAPH.len(),
+                chars: PARAGRAPH.chars().count(),
+                // The waving hand with its skin tone and "\r\n" are one grapheme each
+                graphemes: PARAGRAPH.chars().count() - 2,
+            }
+        );
+        assert_eq!(stats.bytes, 67);
+        assert_eq!(stats.chars, 50);
+    }
+
+    #[test]
+    fn test_chunks_split_inside_characters() {
+        let whole = TextStats::of(PARAGRAPH.as_bytes());
+        let mut counter = TextStatsCounter::default();
+        for chunk in PARAGRAPH.as_bytes().chunks(3) {
+            counter.feed(chunk);
+        }
+        assert_eq!(counter.finish(), whole);
+    }
+
+    #[test]
+    fn test_empty_and_whitespace() {
+        assert_eq!(
+            TextStats::of(b""),
+            TextStats {
+                lines: 1,
+                ..Default::default()
+            }
+        );
+        let stats = TextStats::of(b"  \t\n\n");
+        assert_eq!((stats.lines, stats.words, stats.chars), (3, 0, 5));
+    }
+}
@@ -7,6 +7,29 @@ pub fn is_word_char(byte: u8) -> bool {
     byte.is_ascii_alphanumeric() || byte == b'_'
 }
 
+/// The characters word motion and word selection treat as part of a word
+///
+/// Letters, digits and underscore always are; languages can add more, such as
+/// `-` in CSS or Lisp identifiers. The default adds none.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WordDef {
+    /// Extra ASCII word characters
+    extra: Vec<u8>,
+}
+
+impl WordDef {
+    /// The standard word characters plus the ASCII characters of `extra`
+    pub fn with_extra(extra: &str) -> Self {
+        Self {
+            extra: extra.bytes().filter(u8::is_ascii).collect(),
+        }
+    }
+
+    pub fn is_word_char(&self, byte: u8) -> bool {
+        is_word_char(byte) || self.extra.contains(&byte)
+    }
+}
+
 // ============================================================================
 // Core byte-level word navigation (shared by Buffer and String operations)
 // ============================================================================
@@ -30,6 +53,10 @@ pub fn is_word_char(byte: u8) -> bool {
 /// # Returns
 /// Position of the word start (always <= pos)
 pub fn find_word_start_bytes(bytes: &[u8], pos: usize) -> usize {
+    word_start_in(bytes, pos, &WordDef::default())
+}
+
+fn word_start_in(bytes: &[u8], pos: usize, word_def: &WordDef) -> usize {
     if pos == 0 {
         return 0;
     }
@@ -41,7 +68,7 @@ pub fn find_word_start_bytes(bytes: &[u8], pos: usize) -> usize {
     if new_pos >= bytes.len()
         || (bytes
             .get(new_pos)
-            .map(|&b| !is_word_char(b))
+            .map(|&b| !word_def.is_word_char(b))
             .unwrap_or(true))
     {
         if new_pos > 0 {
@@ -52,7 +79,7 @@ pub fn find_word_start_bytes(bytes: &[u8], pos: usize) -> usize {
     // Find start of current word by scanning backwards
     while new_pos > 0 {
         if let Some(&prev_byte) = bytes.get(new_pos.saturating_sub(1)) {
-            if !is_word_char(prev_byte) {
+            if !word_def.is_word_char(prev_byte) {
                 break;
             }
             new_pos = new_pos.saturating_sub(1);
@@ -75,16 +102,20 @@ pub fn find_word_start_bytes(bytes: &[u8], pos: usize) -> usize {
 /// # Returns
 /// Position of the word end (always >= pos)
 pub fn find_word_end_bytes(bytes: &[u8], pos: usize) -> usize {
+    word_end_in(bytes, pos, &WordDef::default())
+}
+
+fn word_end_in(bytes: &[u8], pos: usize, word_def: &WordDef) -> usize {
     let pos = pos.min(bytes.len());
     let mut new_pos = pos;
 
     // Skip to start of next word if we're at non-word character
-    while new_pos < bytes.len() && !is_word_char(bytes[new_pos]) {
+    while new_pos < bytes.len() && !word_def.is_word_char(bytes[new_pos]) {
         new_pos += 1;
     }
 
     // Find end of word
-    while new_pos < bytes.len() && is_word_char(bytes[new_pos]) {
+    while new_pos < bytes.len() && word_def.is_word_char(bytes[new_pos]) {
         new_pos += 1;
     }
 
@@ -162,7 +193,7 @@ pub fn find_completion_word_start(buffer: &Buffer, pos: usize) -> usize {
 ///
 /// Extracts a windowed byte slice from the buffer and uses the shared
 /// byte-level logic to find word boundaries.
-pub fn find_word_start(buffer: &Buffer, pos: usize) -> usize {
+pub fn find_word_start(buffer: &Buffer, pos: usize, word_def: &WordDef) -> usize {
     if pos == 0 {
         return 0;
     }
@@ -177,7 +208,7 @@ pub fn find_word_start(buffer: &Buffer, pos: usize) -> usize {
     let offset = pos - start;
 
     // Use shared byte-level logic
-    let result = find_word_start_bytes(&bytes, offset);
+    let result = word_start_in(&bytes, offset, word_def);
     start + result
 }
 
@@ -185,7 +216,7 @@ pub fn find_word_start(buffer: &Buffer, pos: usize) -> usize {
 ///
 /// Extracts a windowed byte slice from the buffer and uses the shared
 /// byte-level logic to find word boundaries.
-pub fn find_word_end(buffer: &Buffer, pos: usize) -> usize {
+pub fn find_word_end(buffer: &Buffer, pos: usize, word_def: &WordDef) -> usize {
     let buf_len = buffer.len();
     if pos >= buf_len {
         return buf_len;
@@ -197,12 +228,12 @@ pub fn find_word_end(buffer: &Buffer, pos: usize) -> usize {
     let bytes = buffer.slice_bytes(start..end);
 
     // Use shared byte-level logic
-    let result = find_word_end_bytes(&bytes, 0);
+    let result = word_end_in(&bytes, 0, word_def);
     start + result
 }
 
 /// Find the start of the word to the left of the given position
-pub fn find_word_start_left(buffer: &Buffer, pos: usize) -> usize {
+pub fn find_word_start_left(buffer: &Buffer, pos: usize, word_def: &WordDef) -> usize {
     if pos == 0 {
         return 0;
     }
@@ -218,7 +249,11 @@ pub fn find_word_start_left(buffer: &Buffer, pos: usize) -> usize {
     let mut new_pos = bytes.len().saturating_sub(1);
 
     // Skip non-word characters (whitespace and punctuation)
-    while new_pos > 0 && bytes.get(new_pos).is_some_and(|&b| !is_word_char(b)) {
+    while new_pos > 0
+        && bytes
+            .get(new_pos)
+            .is_some_and(|&b| !word_def.is_word_char(b))
+    {
         new_pos = new_pos.saturating_sub(1);
     }
 
@@ -229,7 +264,7 @@ pub fn find_word_start_left(buffer: &Buffer, pos: usize) -> usize {
 
         match (prev_byte, curr_byte) {
             (Some(&prev), Some(&curr)) => {
-                if is_word_char(prev) != is_word_char(curr) {
+                if word_def.is_word_char(prev) != word_def.is_word_char(curr) {
                     break;
                 }
                 new_pos = new_pos.saturating_sub(1);
@@ -242,7 +277,7 @@ pub fn find_word_start_left(buffer: &Buffer, pos: usize) -> usize {
 }
 
 /// Find the start of the word to the right of the given position
-pub fn find_word_start_right(buffer: &Buffer, pos: usize) -> usize {
+pub fn find_word_start_right(buffer: &Buffer, pos: usize, word_def: &WordDef) -> usize {
     let buf_len = buffer.len();
     if pos >= buf_len {
         return buf_len;
@@ -256,12 +291,20 @@ pub fn find_word_start_right(buffer: &Buffer, pos: usize) -> usize {
     let mut new_pos = 0;
 
     // Skip current word
-    while new_pos < bytes.len() && bytes.get(new_pos).is_some_and(|&b| is_word_char(b)) {
+    while new_pos < bytes.len()
+        && bytes
+            .get(new_pos)
+            .is_some_and(|&b| word_def.is_word_char(b))
+    {
         new_pos += 1;
     }
 
     // Skip non-word characters (whitespace and punctuation)
-    while new_pos < bytes.len() && bytes.get(new_pos).is_some_and(|&b| !is_word_char(b)) {
+    while new_pos < bytes.len()
+        && bytes
+            .get(new_pos)
+            .is_some_and(|&b| !word_def.is_word_char(b))
+    {
         new_pos += 1;
     }
 
@@ -287,32 +330,55 @@ mod tests {
     #[test]
     fn test_find_word_start() {
         let buffer = Buffer::from_str_test("hello world test");
-        assert_eq!(find_word_start(&buffer, 0), 0); // Start of "hello"
-        assert_eq!(find_word_start(&buffer, 3), 0); // Middle of "hello"
-        assert_eq!(find_word_start(&buffer, 6), 6); // Start of "world"
-        assert_eq!(find_word_start(&buffer, 8), 6); // Middle of "world"
+        let standard = WordDef::default();
+        assert_eq!(find_word_start(&buffer, 0, &standard), 0); // Start of "hello"
+        assert_eq!(find_word_start(&buffer, 3, &standard), 0); // Middle of "hello"
+        assert_eq!(find_word_start(&buffer, 6, &standard), 6); // Start of "world"
+        assert_eq!(find_word_start(&buffer, 8, &standard), 6); // Middle of "world"
     }
 
     #[test]
     fn test_find_word_end() {
         let buffer = Buffer::from_str_test("hello world test");
-        assert_eq!(find_word_end(&buffer, 0), 5); // End of "hello"
-        assert_eq!(find_word_end(&buffer, 3), 5); // Middle of "hello"
-        assert_eq!(find_word_end(&buffer, 6), 11); // End of "world"
+        let standard = WordDef::default();
+        assert_eq!(find_word_end(&buffer, 0, &standard), 5); // End of "hello"
+        assert_eq!(find_word_end(&buffer, 3, &standard), 5); // Middle of "hello"
+        assert_eq!(find_word_end(&buffer, 6, &standard), 11); // End of "world"
     }
 
     #[test]
     fn test_find_word_start_left() {
         let buffer = Buffer::from_str_test("hello world test");
-        assert_eq!(find_word_start_left(&buffer, 6), 0); // From "world" to "hello"
-        assert_eq!(find_word_start_left(&buffer, 12), 6); // From "test" to "world"
+        let standard = WordDef::default();
+        assert_eq!(find_word_start_left(&buffer, 6, &standard), 0); // From "world" to "hello"
+        assert_eq!(find_word_start_left(&buffer, 12, &standard), 6); // From "test" to "world"
     }
 
     #[test]
     fn test_find_word_start_right() {
         let buffer = Buffer::from_str_test("hello world test");
-        assert_eq!(find_word_start_right(&buffer, 0), 6); // From "hello" to "world"
-        assert_eq!(find_word_start_right(&buffer, 6), 12); // From "world" to "test"
+        let standard = WordDef::default();
+        assert_eq!(find_word_start_right(&buffer, 0, &standard), 6); // From "hello" to "world"
+        assert_eq!(find_word_start_right(&buffer, 6, &standard), 12); // From "world" to "test"
+    }
+
+    #[test]
+    fn test_word_def_extra_chars() {
+        let buffer = Buffer::from_str_test("a.foo-bar baz");
+        let standard = WordDef::default();
+        let css = WordDef::with_extra("-");
+        assert!(!standard.is_word_char(b'-'));
+        assert!(css.is_word_char(b'-'));
+
+        assert_eq!(find_word_start(&buffer, 7, &standard), 6);
+        assert_eq!(find_word_end(&buffer, 2, &standard), 5);
+        assert_eq!(find_word_start_left(&buffer, 9, &standard), 6);
+        assert_eq!(find_word_start_right(&buffer, 2, &standard), 6);
+
+        assert_eq!(find_word_start(&buffer, 7, &css), 2);
+        assert_eq!(find_word_end(&buffer, 2, &css), 9);
+        assert_eq!(find_word_start_left(&buffer, 9, &css), 2);
+        assert_eq!(find_word_start_right(&buffer, 2, &css), 10);
     }
 
     // ========================================================================
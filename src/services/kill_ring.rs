@@ -0,0 +1,211 @@
+//! Emacs-style kill ring
+//!
+//! Every cut and copy is pushed onto a [`KillRing`] of bounded size. A paste
+//! inserts the newest entry; "paste previous" right after it swaps the text
+//! just pasted for the entry before it, walking further back through the ring
+//! on each repeat and wrapping around at the oldest entry.
+
+use crate::model::event::Event;
+use crate::state::EditorState;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Default number of entries kept
+pub const DEFAULT_KILL_RING_CAPACITY: usize = 60;
+
+/// A bounded history of cut and copied text, newest first
+#[derive(Debug, Clone)]
+pub struct KillRing {
+    entries: VecDeque<String>,
+    capacity: usize,
+    /// Where the last paste put its text and which entry it was, so that
+    /// [`KillRing::paste_cycle`] can replace it
+    last_paste: Option<LastPaste>,
+}
+
+#[derive(Debug, Clone)]
+struct LastPaste {
+    range: Range<usize>,
+    index: usize,
+}
+
+impl Default for KillRing {
+    fn default() -> Self {
+        Self::new(DEFAULT_KILL_RING_CAPACITY)
+    }
+}
+
+impl KillRing {
+    /// Create an empty ring keeping at most `capacity` entries (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            last_paste: None,
+        }
+    }
+
+    /// Add `text` as the newest entry, dropping the oldest one when full
+    ///
+    /// Empty text is ignored, and pushing the current newest entry again
+    /// doesn't duplicate it.
+    pub fn push(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        self.last_paste = None;
+        if self.entries.front() == Some(&text) {
+            return;
+        }
+        self.entries.push_front(text);
+        self.entries.truncate(self.capacity);
+    }
+
+    /// The newest entry
+    pub fn latest(&self) -> Option<&str> {
+        self.entries.front().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Build the event inserting the newest entry at the primary cursor
+    pub fn paste(&mut self, state: &EditorState) -> Option<Event> {
+        let text = self.entries.front()?.clone();
+        let position = state.cursors.primary().position;
+        self.last_paste = Some(LastPaste {
+            range: position..position + text.len(),
+            index: 0,
+        });
+        Some(Event::Insert {
+            position,
+            text,
+            cursor_id: state.cursors.primary_id(),
+        })
+    }
+
+    /// Build the event replacing the text of the last paste with the entry
+    /// before it
+    ///
+    /// Returns `None` unless the primary cursor is still right after the
+    /// pasted text and that text is unchanged, i.e. nothing else happened
+    /// since the paste.
+    pub fn paste_cycle(&mut self, state: &EditorState) -> Option<Event> {
+        let last = self.last_paste.as_ref()?;
+        let current = self.entries.get(last.index)?;
+        let cursor_id = state.cursors.primary_id();
+        if state.cursors.primary().position != last.range.end
+            || last.range.end > state.buffer.len()
+            || state.buffer.slice_bytes(last.range.clone()) != current.as_bytes()
+        {
+            self.last_paste = None;
+            return None;
+        }
+
+        let index = (last.index + 1) % self.entries.len();
+        let range = last.range.clone();
+        let text = self.entries[index].clone();
+        let events = vec![
+            Event::Delete {
+                range: range.clone(),
+                deleted_text: current.clone(),
+                cursor_id,
+            },
+            Event::Insert {
+                position: range.start,
+                text: text.clone(),
+                cursor_id,
+            },
+        ];
+        self.last_paste = Some(LastPaste {
+            range: range.start..range.start + text.len(),
+            index,
+        });
+        Some(Event::Batch {
+            events,
+            description: "Paste previous".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cut `range` out of the buffer into the ring
+    fn cut(ring: &mut KillRing, state: &mut EditorState, range: Range<usize>) {
+        let deleted_text = state.get_text_range(range.start, range.end);
+        ring.push(deleted_text.clone());
+        state.apply(&Event::Delete {
+            range,
+            deleted_text,
+            cursor_id: state.cursors.primary_id(),
+        });
+    }
+
+    fn text(state: &EditorState) -> String {
+        state.buffer.to_string().unwrap()
+    }
+
+    #[test]
+    fn test_cut_three_and_cycle_at_paste_site() {
+        let mut state = EditorState::from_str_test("one two three |");
+        let mut ring = KillRing::default();
+        cut(&mut ring, &mut state, 0..4);
+        cut(&mut ring, &mut state, 0..4);
+        cut(&mut ring, &mut state, 0..6);
+        assert_eq!(text(&state), "|");
+        assert_eq!(ring.len(), 3);
+
+        state.cursors.primary_mut().position = 1;
+        let paste = ring.paste(&state).unwrap();
+        state.apply(&paste);
+        assert_eq!(text(&state), "|three ");
+
+        let cycle = ring.paste_cycle(&state).unwrap();
+        state.apply(&cycle);
+        assert_eq!(text(&state), "|two ");
+        assert_eq!(state.cursors.primary().position, 5);
+        // Undoing the cycle restores the previous paste
+        state.apply(&cycle.inverse().unwrap());
+        assert_eq!(text(&state), "|three ");
+        state.apply(&cycle);
+
+        state.apply(&ring.paste_cycle(&state).unwrap());
+        assert_eq!(text(&state), "|one ");
+        // Wraps around to the newest entry
+        state.apply(&ring.paste_cycle(&state).unwrap());
+        assert_eq!(text(&state), "|three ");
+    }
+
+    #[test]
+    fn test_paste_cycle_needs_untouched_paste() {
+        let mut state = EditorState::from_str_test("");
+        let mut ring = KillRing::new(2);
+        assert!(ring.paste(&state).is_none());
+        ring.push("a".to_string());
+        ring.push("b".to_string());
+        ring.push("b".to_string());
+        ring.push("c".to_string());
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.latest(), Some("c"));
+        // Nothing pasted yet
+        assert!(ring.paste_cycle(&state).is_none());
+
+        state.apply(&ring.paste(&state).unwrap());
+        assert_eq!(text(&state), "c");
+        // Typing after the paste ends the cycle
+        state.apply(&Event::Insert {
+            position: 1,
+            text: "x".to_string(),
+            cursor_id: state.cursors.primary_id(),
+        });
+        assert!(ring.paste_cycle(&state).is_none());
+        assert_eq!(text(&state), "cx");
+    }
+}
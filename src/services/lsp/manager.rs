@@ -627,6 +627,7 @@ mod tests {
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
         languages.insert(
@@ -638,6 +639,7 @@ mod tests {
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
         languages.insert(
@@ -649,6 +651,7 @@ mod tests {
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                word_chars: String::new(),
             },
         );
         languages
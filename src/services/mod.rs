@@ -8,10 +8,13 @@ pub mod clipboard;
 pub mod fs;
 #[cfg(target_os = "linux")]
 pub mod gpm;
+pub mod kill_ring;
 pub mod lsp;
 pub mod plugins;
 pub mod process_limits;
+pub mod project_search;
 pub mod recovery;
+pub mod registers;
 pub mod release_checker;
 pub mod signal_handler;
 pub mod terminal;
@@ -6,14 +6,15 @@
 use crate::input::command_registry::CommandRegistry;
 use crate::input::commands::Command;
 use crate::model::event::{BufferId, SplitId};
-use crate::services::plugins::hooks::{HookCallback, HookRegistry};
+use crate::services::plugins::hooks::{EditHook, HookCallback, HookRegistry};
 use crate::view::overlay::{OverlayHandle, OverlayNamespace};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 /// Response from the editor for async plugin operations
 #[derive(Debug, Clone)]
@@ -29,6 +30,11 @@ pub enum PluginResponse {
         request_id: u64,
         result: Result<Value, String>,
     },
+    /// Response to GetBufferText or GetBufferLine with the requested text
+    BufferText {
+        request_id: u64,
+        text: Result<String, String>,
+    },
 }
 
 /// Information about a cursor in the editor
@@ -254,6 +260,15 @@ pub enum PluginCommand {
     /// Set status message
     SetStatus { message: String },
 
+    /// Run the `timer` hook with `token` once `after_ms` milliseconds have passed
+    ///
+    /// Only the handlers of `plugin`, the plugin that scheduled the timer, run.
+    ScheduleTimer {
+        plugin: Option<String>,
+        after_ms: u64,
+        token: u64,
+    },
+
     /// Apply a theme by name
     ApplyTheme { theme_name: String },
 
@@ -596,6 +611,21 @@ pub enum PluginCommand {
         position: usize,
     },
 
+    /// Read a byte range of a buffer, answered with PluginResponse::BufferText
+    GetBufferText {
+        buffer_id: BufferId,
+        range: Range<usize>,
+        request_id: u64,
+    },
+
+    /// Read one line (0-indexed) of a buffer without its line ending,
+    /// answered with PluginResponse::BufferText
+    GetBufferLine {
+        buffer_id: BufferId,
+        line: usize,
+        request_id: u64,
+    },
+
     /// Send an arbitrary LSP request and return the raw JSON response
     SendLspRequest {
         language: String,
@@ -622,6 +652,137 @@ pub enum PluginCommand {
     },
 }
 
+impl PluginCommand {
+    /// Capability a plugin must declare to send this command, if any
+    pub fn required_capability(&self) -> Option<PluginCapability> {
+        match self {
+            PluginCommand::InsertText { .. }
+            | PluginCommand::DeleteRange { .. }
+            | PluginCommand::InsertAtCursor { .. }
+            | PluginCommand::DeleteSelection
+            | PluginCommand::SetVirtualBufferContent { .. }
+            | PluginCommand::CloseBuffer { .. } => Some(PluginCapability::WriteBuffer),
+            PluginCommand::GetTextPropertiesAtCursor { .. }
+            | PluginCommand::GetBufferText { .. }
+            | PluginCommand::GetBufferLine { .. } => Some(PluginCapability::ReadBuffer),
+            PluginCommand::SpawnProcess { .. } => Some(PluginCapability::SpawnProcess),
+            PluginCommand::AddMenuItem { .. }
+            | PluginCommand::AddMenu { .. }
+            | PluginCommand::RemoveMenuItem { .. }
+            | PluginCommand::RemoveMenu { .. } => Some(PluginCapability::AddMenus),
+            _ => None,
+        }
+    }
+}
+
+/// Something a plugin may only do after declaring it in its manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginCapability {
+    /// Read buffer contents beyond basic metadata (text, saved diffs, text properties)
+    ReadBuffer,
+    /// Insert or delete text in buffers, set virtual buffer content and close buffers
+    WriteBuffer,
+    /// Read files and directories and query their metadata
+    ReadFiles,
+    /// Write files
+    WriteFiles,
+    /// Run external processes
+    SpawnProcess,
+    /// Make network requests; no API needs it yet, so declaring it grants
+    /// nothing for now
+    Network,
+    /// Add or remove menus and menu items
+    AddMenus,
+}
+
+/// Capabilities a plugin declares in a `<plugin>.manifest.json` next to its source
+///
+/// ```json
+/// { "capabilities": ["read_buffer", "write_buffer"] }
+/// ```
+///
+/// Plugins without a manifest get [`PluginManifest::legacy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// Capabilities the plugin is allowed to use
+    #[serde(default)]
+    pub capabilities: HashSet<PluginCapability>,
+}
+
+impl PluginCapability {
+    /// Name of the capability as written in manifests
+    pub fn name(self) -> &'static str {
+        match self {
+            PluginCapability::ReadBuffer => "read_buffer",
+            PluginCapability::WriteBuffer => "write_buffer",
+            PluginCapability::ReadFiles => "read_files",
+            PluginCapability::WriteFiles => "write_files",
+            PluginCapability::SpawnProcess => "spawn_process",
+            PluginCapability::Network => "network",
+            PluginCapability::AddMenus => "add_menus",
+        }
+    }
+}
+
+impl PluginManifest {
+    /// Capabilities of a plugin that ships no manifest: all of them, as
+    /// before manifests existed
+    pub fn legacy() -> Self {
+        use PluginCapability::*;
+        Self {
+            capabilities: HashSet::from([
+                ReadBuffer,
+                WriteBuffer,
+                ReadFiles,
+                WriteFiles,
+                SpawnProcess,
+                Network,
+                AddMenus,
+            ]),
+        }
+    }
+
+    /// Whether the manifest declares `capability`
+    pub fn allows(&self, capability: PluginCapability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Fail with a message naming `capability` unless the manifest declares it
+    pub fn check(&self, capability: PluginCapability) -> Result<(), String> {
+        if self.allows(capability) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Plugin manifest does not declare the '{}' capability",
+                capability.name()
+            ))
+        }
+    }
+
+    /// Path of the manifest belonging to a plugin file (`foo.ts` -> `foo.manifest.json`)
+    pub fn path_for(plugin_path: &Path) -> PathBuf {
+        let stem = plugin_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        plugin_path.with_file_name(format!("{}.manifest.json", stem))
+    }
+
+    /// Load the manifest for a plugin file, or None if the plugin has no manifest
+    pub fn load_for(plugin_path: &Path) -> Result<Option<Self>, String> {
+        let manifest_path = Self::path_for(plugin_path);
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("Invalid manifest {}: {}", manifest_path.display(), e))
+    }
+}
+
 /// Plugin API context - provides safe access to editor functionality
 pub struct PluginApi {
     /// Hook registry (shared with editor)
@@ -635,6 +796,9 @@ pub struct PluginApi {
 
     /// Snapshot of editor state (read-only for plugins)
     state_snapshot: Arc<RwLock<EditorStateSnapshot>>,
+
+    /// Declared capabilities
+    manifest: PluginManifest,
 }
 
 impl PluginApi {
@@ -650,9 +814,21 @@ impl PluginApi {
             commands,
             command_sender,
             state_snapshot,
+            manifest: PluginManifest::legacy(),
         }
     }
 
+    /// Restrict this context to the capabilities declared in `manifest`
+    pub fn with_manifest(mut self, manifest: PluginManifest) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Whether the plugin may use `capability`
+    pub fn allows(&self, capability: PluginCapability) -> bool {
+        self.manifest.allows(capability)
+    }
+
     /// Register a hook callback
     pub fn register_hook(&self, hook_name: &str, callback: HookCallback) {
         let mut hooks = self.hooks.write().unwrap();
@@ -665,6 +841,23 @@ impl PluginApi {
         hooks.remove_hooks(hook_name);
     }
 
+    /// Register a hook that may reject or rewrite buffer edits, replacing any
+    /// edit hook of the same name
+    ///
+    /// Fails if the manifest doesn't declare `write_buffer`.
+    pub fn register_edit_hook(&self, name: &str, hook: Box<dyn EditHook>) -> Result<(), String> {
+        self.manifest.check(PluginCapability::WriteBuffer)?;
+        let mut hooks = self.hooks.write().unwrap();
+        hooks.add_edit_hook(name, hook);
+        Ok(())
+    }
+
+    /// Remove an edit hook, returning whether there was one
+    pub fn unregister_edit_hook(&self, name: &str) -> bool {
+        let mut hooks = self.hooks.write().unwrap();
+        hooks.remove_edit_hook(name)
+    }
+
     /// Register a command
     pub fn register_command(&self, command: Command) {
         let commands = self.commands.read().unwrap();
@@ -678,7 +871,12 @@ impl PluginApi {
     }
 
     /// Send a command to the editor (async/non-blocking)
+    ///
+    /// Fails without sending if the command needs a capability the manifest doesn't declare.
     pub fn send_command(&self, command: PluginCommand) -> Result<(), String> {
+        if let Some(capability) = command.required_capability() {
+            self.manifest.check(capability)?;
+        }
         self.command_sender
             .send(command)
             .map_err(|e| format!("Failed to send command: {}", e))
@@ -762,6 +960,17 @@ impl PluginApi {
         self.send_command(PluginCommand::SetStatus { message })
     }
 
+    /// Schedule a one-shot timer; the `timer` hook fires with `token` after `after`
+    ///
+    /// The timer belongs to no plugin, so only handlers no plugin owns see it.
+    pub fn schedule(&self, after: Duration, token: u64) -> Result<(), String> {
+        self.send_command(PluginCommand::ScheduleTimer {
+            plugin: None,
+            after_ms: after.as_millis() as u64,
+            token,
+        })
+    }
+
     /// Open a file at a specific line and column (1-indexed)
     /// This is useful for jumping to locations from git grep, LSP definitions, etc.
     pub fn open_file_at_location(
@@ -972,6 +1181,42 @@ impl PluginApi {
         snapshot.viewport.clone()
     }
 
+    /// Request the text of a byte range of a buffer
+    ///
+    /// The editor reads the range from the buffer, loading only the chunks it
+    /// covers, and answers with [`PluginResponse::BufferText`]. Fails if the
+    /// manifest doesn't declare `read_buffer`.
+    pub fn get_buffer_text(
+        &self,
+        buffer_id: BufferId,
+        range: Range<usize>,
+        request_id: u64,
+    ) -> Result<(), String> {
+        self.send_command(PluginCommand::GetBufferText {
+            buffer_id,
+            range,
+            request_id,
+        })
+    }
+
+    /// Request one line (0-indexed) of a buffer, without its line ending
+    ///
+    /// Answered with [`PluginResponse::BufferText`] like
+    /// [`get_buffer_text`](Self::get_buffer_text), and needs the same
+    /// `read_buffer` capability.
+    pub fn get_buffer_line(
+        &self,
+        buffer_id: BufferId,
+        line: usize,
+        request_id: u64,
+    ) -> Result<(), String> {
+        self.send_command(PluginCommand::GetBufferLine {
+            buffer_id,
+            line,
+            request_id,
+        })
+    }
+
     /// Get access to the state snapshot Arc (for internal use)
     pub fn state_snapshot_handle(&self) -> Arc<RwLock<EditorStateSnapshot>> {
         Arc::clone(&self.state_snapshot)
@@ -985,6 +1230,7 @@ impl Clone for PluginApi {
             commands: Arc::clone(&self.commands),
             command_sender: self.command_sender.clone(),
             state_snapshot: Arc::clone(&self.state_snapshot),
+            manifest: self.manifest.clone(),
         }
     }
 }
@@ -1021,6 +1267,58 @@ mod tests {
         assert_eq!(hook_registry.hook_count("test-hook"), 1);
     }
 
+    /// Uppercases inserted text
+    struct UppercaseHook;
+
+    impl EditHook for UppercaseHook {
+        fn before_edit(
+            &self,
+            edit: &crate::services::plugins::hooks::PendingEdit,
+        ) -> crate::services::plugins::hooks::EditDecision {
+            use crate::services::plugins::hooks::{EditDecision, PendingEdit};
+            match edit {
+                PendingEdit::Insert { text, .. } => EditDecision::Modify(text.to_uppercase()),
+                PendingEdit::Delete { .. } => EditDecision::Allow,
+            }
+        }
+    }
+
+    #[test]
+    fn test_register_edit_hook_reaches_plugin_manager() {
+        use crate::model::event::{CursorId, Event};
+        use crate::services::plugins::manager::PluginManager;
+
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let manager = PluginManager::new(false, commands.clone());
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+        let api = PluginApi::new(manager.hook_registry(), commands, tx, state_snapshot);
+
+        // Without write_buffer the hook is refused
+        let read_only = api.clone().with_manifest(PluginManifest::default());
+        assert!(read_only
+            .register_edit_hook("upper", Box::new(UppercaseHook))
+            .is_err());
+
+        api.register_edit_hook("upper", Box::new(UppercaseHook))
+            .unwrap();
+        let event = Event::Insert {
+            position: 0,
+            text: "shout".to_string(),
+            cursor_id: CursorId(0),
+        };
+        let Some(Event::Insert { text, .. }) = manager.filter_edit(&event, BufferId(1)) else {
+            panic!("insert was rejected");
+        };
+        assert_eq!(text, "SHOUT");
+
+        assert!(api.unregister_edit_hook("upper"));
+        assert_eq!(
+            format!("{:?}", manager.filter_edit(&event, BufferId(1))),
+            format!("{:?}", Some(event))
+        );
+    }
+
     #[test]
     fn test_send_command() {
         let hooks = Arc::new(RwLock::new(HookRegistry::new()));
@@ -1115,6 +1413,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_schedule_command() {
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+
+        let api = PluginApi::new(hooks, commands, tx, state_snapshot);
+
+        assert!(api.schedule(Duration::from_millis(250), 9).is_ok());
+
+        match rx.try_recv().unwrap() {
+            PluginCommand::ScheduleTimer {
+                plugin,
+                after_ms,
+                token,
+            } => {
+                assert_eq!(plugin, None);
+                assert_eq!(after_ms, 250);
+                assert_eq!(token, 9);
+            }
+            _ => panic!("Wrong command type"),
+        }
+    }
+
     #[test]
     fn test_get_active_buffer_id() {
         let hooks = Arc::new(RwLock::new(HookRegistry::new()));
@@ -1312,4 +1635,121 @@ mod tests {
         assert_eq!(viewport.width, 80);
         assert_eq!(viewport.height, 24);
     }
+
+    #[test]
+    fn test_unprivileged_plugin_cannot_edit() {
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+
+        let manifest: PluginManifest =
+            serde_json::from_str(r#"{ "capabilities": ["read_buffer"] }"#).unwrap();
+        let api = PluginApi::new(hooks, commands, tx, state_snapshot).with_manifest(manifest);
+
+        assert!(api.insert_text(BufferId(1), 0, "x".to_string()).is_err());
+        assert!(api.delete_range(BufferId(1), 0..1).is_err());
+        assert!(rx.try_recv().is_err(), "refused edits must not be sent");
+
+        // Commands that need no capability still go through
+        assert!(api
+            .send_command(PluginCommand::SetStatus {
+                message: "hi".to_string()
+            })
+            .is_ok());
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_read_only_plugin_reads_buffer_text() {
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+
+        let manifest: PluginManifest =
+            serde_json::from_str(r#"{ "capabilities": ["read_buffer"] }"#).unwrap();
+        let api =
+            PluginApi::new(hooks, commands, tx, state_snapshot.clone()).with_manifest(manifest);
+
+        // Reading is a request the editor answers; it can't change the buffer
+        assert!(api.get_buffer_text(BufferId(1), 3..7, 42).is_ok());
+        match rx.try_recv().unwrap() {
+            PluginCommand::GetBufferText {
+                buffer_id,
+                range,
+                request_id,
+            } => {
+                assert_eq!(buffer_id, BufferId(1));
+                assert_eq!(range, 3..7);
+                assert_eq!(request_id, 42);
+            }
+            other => panic!("Expected GetBufferText, got {:?}", other),
+        }
+        assert!(api.get_buffer_line(BufferId(1), 5, 43).is_ok());
+        match rx.try_recv().unwrap() {
+            PluginCommand::GetBufferLine {
+                buffer_id,
+                line,
+                request_id,
+            } => {
+                assert_eq!(buffer_id, BufferId(1));
+                assert_eq!(line, 5);
+                assert_eq!(request_id, 43);
+            }
+            other => panic!("Expected GetBufferLine, got {:?}", other),
+        }
+        assert!(api.insert_text(BufferId(1), 0, "x".to_string()).is_err());
+        assert!(rx.try_recv().is_err());
+
+        // Reading needs the read_buffer capability
+        let manifest: PluginManifest =
+            serde_json::from_str(r#"{ "capabilities": ["add_menus"] }"#).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let api = PluginApi::new(
+            Arc::new(RwLock::new(HookRegistry::new())),
+            Arc::new(RwLock::new(CommandRegistry::new())),
+            tx,
+            state_snapshot,
+        )
+        .with_manifest(manifest);
+        assert!(api.get_buffer_text(BufferId(1), 0..1, 1).is_err());
+        assert!(api.get_buffer_line(BufferId(1), 0, 2).is_err());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_plugin_manifest_location_and_defaults() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let plugin_path = temp_dir.path().join("git_blame.ts");
+        let manifest_path = PluginManifest::path_for(&plugin_path);
+        assert_eq!(
+            manifest_path,
+            temp_dir.path().join("git_blame.manifest.json")
+        );
+
+        // No manifest means no restrictions
+        assert_eq!(PluginManifest::load_for(&plugin_path), Ok(None));
+        let legacy = PluginManifest::legacy();
+        assert!(legacy.allows(PluginCapability::WriteBuffer));
+        assert!(legacy.allows(PluginCapability::AddMenus));
+
+        std::fs::write(&manifest_path, r#"{ "capabilities": ["spawn_process"] }"#).unwrap();
+        let manifest = PluginManifest::load_for(&plugin_path).unwrap().unwrap();
+        assert!(manifest.allows(PluginCapability::SpawnProcess));
+        assert!(!manifest.allows(PluginCapability::WriteBuffer));
+
+        std::fs::write(&manifest_path, r#"{ "capabilities": ["teleport"] }"#).unwrap();
+        assert!(PluginManifest::load_for(&plugin_path).is_err());
+    }
+
+    #[test]
+    fn test_plugin_manifest_accepts_network() {
+        let manifest: PluginManifest =
+            serde_json::from_str(r#"{ "capabilities": ["network", "read_buffer"] }"#).unwrap();
+        assert!(manifest.allows(PluginCapability::Network));
+        assert!(manifest.allows(PluginCapability::ReadBuffer));
+        assert_eq!(PluginCapability::Network.name(), "network");
+        assert!(PluginManifest::legacy().allows(PluginCapability::Network));
+    }
 }
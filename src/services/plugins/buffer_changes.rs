@@ -0,0 +1,135 @@
+//! Coalesced buffer-change notifications for plugins
+//!
+//! Every applied edit is recorded here as it happens. Once per frame the
+//! editor drains the queue and runs the `buffer_changed` hook once per edited
+//! buffer with a single replaced range, so a plugin re-parsing or re-linting
+//! the buffer does the work once however many keystrokes went by.
+
+use crate::model::event::BufferId;
+use crate::services::plugins::hooks::AppliedEdit;
+use std::collections::HashMap;
+
+/// Everything that changed in one buffer, as a single replacement
+///
+/// The bytes `start..old_end` of the buffer as it was at the previous
+/// notification are now `start..new_end`. Bytes outside that span were not
+/// touched, although those after it may have moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferChange {
+    pub start: usize,
+    pub old_end: usize,
+    pub new_end: usize,
+}
+
+impl BufferChange {
+    /// Widen this change to also cover `edit`, given in current coordinates:
+    /// `range` of the current text was replaced by `inserted` bytes
+    fn extend(&mut self, range: std::ops::Range<usize>, inserted: usize) {
+        // Bytes past our span are unchanged since the last notification, so
+        // extending over them reaches just as far into the old text
+        let end = self.new_end.max(range.end);
+        self.old_end += end - self.new_end;
+        self.new_end = end + inserted - range.len();
+        // Likewise for bytes before it
+        if range.start < self.start {
+            self.start = range.start;
+        }
+    }
+}
+
+/// Buffer edits not yet reported to plugins
+#[derive(Debug, Default)]
+pub struct BufferChanges {
+    pending: HashMap<BufferId, BufferChange>,
+}
+
+impl BufferChanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold an applied edit into its buffer's pending change
+    pub fn record(&mut self, edit: &AppliedEdit) {
+        let (buffer_id, range, inserted) = match edit {
+            AppliedEdit::Insert {
+                buffer_id, range, ..
+            } => (*buffer_id, range.start..range.start, range.len()),
+            AppliedEdit::Delete {
+                buffer_id, range, ..
+            } => (*buffer_id, range.clone(), 0),
+        };
+        if range.is_empty() && inserted == 0 {
+            return;
+        }
+        let empty = BufferChange {
+            start: range.start,
+            old_end: range.start,
+            new_end: range.start,
+        };
+        self.pending
+            .entry(buffer_id)
+            .or_insert(empty)
+            .extend(range, inserted);
+    }
+
+    /// Remove and return the pending change of every edited buffer
+    pub fn take(&mut self) -> Vec<(BufferId, BufferChange)> {
+        let mut changes: Vec<_> = self.pending.drain().collect();
+        changes.sort_by_key(|(buffer_id, _)| buffer_id.0);
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(position: usize, text: &str) -> AppliedEdit {
+        AppliedEdit::Insert {
+            buffer_id: BufferId(1),
+            range: position..position + text.len(),
+            text: text.to_string(),
+        }
+    }
+
+    fn delete(range: std::ops::Range<usize>) -> AppliedEdit {
+        AppliedEdit::Delete {
+            buffer_id: BufferId(1),
+            deleted_text: "x".repeat(range.len()),
+            range,
+        }
+    }
+
+    #[test]
+    fn test_edits_coalesce_into_one_change() {
+        // "0123456789" -> insert "abc" at 5 -> "01234abc56789"
+        // -> delete 6..9 ("bc5") -> "01234a6789"
+        // -> insert "zz" at 1 -> "0zz1234a6789"
+        let mut changes = BufferChanges::new();
+        changes.record(&insert(5, "abc"));
+        changes.record(&delete(6..9));
+        changes.record(&insert(1, "zz"));
+
+        let expected = BufferChange {
+            start: 1,
+            old_end: 6,
+            new_end: 8,
+        };
+        assert_eq!(changes.take(), vec![(BufferId(1), expected)]);
+        // "0123456789"[1..6] = "12345" became "0zz1234a6789"[1..8] = "zz1234a"
+        assert!(changes.is_empty());
+        assert!(changes.take().is_empty());
+    }
+
+    #[test]
+    fn test_empty_edits_are_not_reported() {
+        let mut changes = BufferChanges::new();
+        changes.record(&insert(3, ""));
+        changes.record(&delete(4..4));
+        assert!(changes.is_empty());
+    }
+}
@@ -4,8 +4,7 @@
 //! This ensures hooks are triggered consistently whenever state changes occur.
 
 use crate::model::event::{BufferId, Event};
-use crate::services::plugins::hooks::{HookArgs, HookRegistry};
-use std::sync::RwLock;
+use crate::services::plugins::hooks::{AppliedEdit, HookArgs, HookRegistry, PendingEdit};
 
 /// Trait for converting Events into Hook invocations
 pub trait EventHooks {
@@ -86,62 +85,164 @@ impl EventHooks for Event {
     }
 }
 
-/// Apply an event with automatic hook invocations
-pub fn apply_event_with_hooks(
-    state: &mut crate::state::EditorState,
-    event: &Event,
-    buffer_id: BufferId,
-    hook_registry: &RwLock<HookRegistry>,
-) -> bool {
-    // Run "before" hooks
-    if let Some(before_args) = event.before_hook(buffer_id) {
-        let registry = hook_registry.read().unwrap();
-        let hook_name = match &before_args {
-            HookArgs::BeforeInsert { .. } => "before_insert",
-            HookArgs::BeforeDelete { .. } => "before_delete",
-            _ => "",
-        };
-
-        if !hook_name.is_empty() {
-            let should_continue = registry.run_hooks(hook_name, &before_args);
-            if !should_continue {
-                // Hook cancelled the operation
-                return false;
+/// Run the edit hooks on an Insert or Delete event
+///
+/// Returns the event to apply, which differs from `event` if a hook rewrote
+/// the inserted text, or `None` if a hook rejected it. Other events pass through.
+fn filter_edit(event: &Event, buffer_id: BufferId, registry: &HookRegistry) -> Option<Event> {
+    match event {
+        Event::Insert {
+            position,
+            text,
+            cursor_id,
+        } => {
+            let pending = PendingEdit::Insert {
+                buffer_id,
+                position: *position,
+                text: text.clone(),
+            };
+            match registry.run_before_edit(pending)? {
+                PendingEdit::Insert { text, .. } => Some(Event::Insert {
+                    position: *position,
+                    text,
+                    cursor_id: *cursor_id,
+                }),
+                PendingEdit::Delete { .. } => Some(event.clone()),
             }
         }
+        Event::Delete { range, .. } => {
+            let pending = PendingEdit::Delete {
+                buffer_id,
+                range: range.clone(),
+            };
+            registry.run_before_edit(pending)?;
+            Some(event.clone())
+        }
+        _ => Some(event.clone()),
     }
+}
+
+/// How a filtered edit changed the buffer compared to the original edit:
+/// at `at`, the original would have left `expected` bytes where there are
+/// now `actual` bytes
+fn length_change(original: &Event, filtered: Option<&Event>) -> Option<(usize, usize, usize)> {
+    let (at, expected, actual) = match (original, filtered) {
+        (Event::Insert { position, text, .. }, Some(Event::Insert { text: new, .. })) => {
+            (*position, text.len(), new.len())
+        }
+        (Event::Insert { position, text, .. }, None) => (*position, text.len(), 0),
+        (Event::Delete { range, .. }, None) => (range.start, 0, range.len()),
+        _ => return None,
+    };
+    (expected != actual).then_some((at, expected, actual))
+}
 
-    // Apply the event
-    state.apply(event);
+/// Move a position computed for the original edit to where it is after the
+/// filtered one (see `length_change`)
+fn shift_position(position: usize, (at, expected, actual): (usize, usize, usize)) -> usize {
+    if position <= at {
+        position
+    } else if position >= at + expected {
+        position + actual - expected
+    } else {
+        at + (position - at).min(actual)
+    }
+}
 
-    // Run "after" hooks
-    if let Some(mut after_args) = event.after_hook(buffer_id) {
-        // Fill in line number for CursorMoved events
-        if let HookArgs::CursorMoved {
+/// Move the positions of a later event of a batch after an earlier edit
+/// came out a different length than planned
+fn shift_event(event: &mut Event, change: (usize, usize, usize)) {
+    let shift = |position: &mut usize| *position = shift_position(*position, change);
+    match event {
+        Event::Insert { position, .. }
+        | Event::AddCursor { position, .. }
+        | Event::SetAnchor { position, .. } => shift(position),
+        Event::Delete { range, .. } => {
+            shift(&mut range.start);
+            shift(&mut range.end);
+        }
+        Event::MoveCursor {
             new_position,
-            ref mut line,
+            new_anchor,
             ..
-        } = after_args
-        {
-            // Compute 1-indexed line number from byte position
-            // get_line_number returns 0-indexed, so add 1
-            *line = state.buffer.get_line_number(new_position) + 1;
+        } => {
+            shift(new_position);
+            if let Some(anchor) = new_anchor {
+                shift(anchor);
+            }
         }
+        _ => {}
+    }
+}
 
-        let registry = hook_registry.read().unwrap();
-        let hook_name = match &after_args {
-            HookArgs::AfterInsert { .. } => "after_insert",
-            HookArgs::AfterDelete { .. } => "after_delete",
-            HookArgs::CursorMoved { .. } => "cursor_moved",
-            _ => "",
-        };
-
-        if !hook_name.is_empty() {
-            registry.run_hooks(hook_name, &after_args);
+/// Run the edit hooks on an event
+///
+/// Returns the event to apply: insertions may come back with rewritten text,
+/// and rejected edits are dropped (`None`). The edits of a batch are filtered
+/// one at a time, and when one comes out a different length than planned the
+/// positions of the events after it are moved to match. A batch loses its
+/// rejected edits, and is `None` only if every edit in it was rejected.
+pub fn filter_event(event: &Event, buffer_id: BufferId, registry: &HookRegistry) -> Option<Event> {
+    if !registry.has_edit_hooks() {
+        return Some(event.clone());
+    }
+    let Event::Batch {
+        events,
+        description,
+    } = event
+    else {
+        return filter_edit(event, buffer_id, registry);
+    };
+
+    let mut pending = events.clone();
+    let mut filtered = Vec::with_capacity(pending.len());
+    for i in 0..pending.len() {
+        let result = filter_edit(&pending[i], buffer_id, registry);
+        if let Some(change) = length_change(&pending[i], result.as_ref()) {
+            for later in &mut pending[i + 1..] {
+                shift_event(later, change);
+            }
         }
+        filtered.extend(result);
     }
 
-    true // Event was applied
+    let has_edits = |events: &[Event]| {
+        events
+            .iter()
+            .any(|e| matches!(e, Event::Insert { .. } | Event::Delete { .. }))
+    };
+    if has_edits(events) && !has_edits(&filtered) {
+        return None;
+    }
+    Some(Event::Batch {
+        events: filtered,
+        description: description.clone(),
+    })
+}
+
+/// The buffer edits an applied event made, in the order they were made
+pub fn applied_edits(event: &Event, buffer_id: BufferId) -> Vec<AppliedEdit> {
+    match event {
+        Event::Insert { position, text, .. } => vec![AppliedEdit::Insert {
+            buffer_id,
+            range: *position..*position + text.len(),
+            text: text.clone(),
+        }],
+        Event::Delete {
+            range,
+            deleted_text,
+            ..
+        } => vec![AppliedEdit::Delete {
+            buffer_id,
+            range: range.clone(),
+            deleted_text: deleted_text.clone(),
+        }],
+        Event::Batch { events, .. } => events
+            .iter()
+            .flat_map(|event| applied_edits(event, buffer_id))
+            .collect(),
+        _ => vec![],
+    }
 }
 
 #[cfg(test)]
@@ -198,20 +299,65 @@ mod tests {
         assert!(event.after_hook(buffer_id).is_none());
     }
 
+    /// Uppercases inserted text, rejects deletions of "keep", and records what was applied
+    #[derive(Default)]
+    struct ShoutingHook {
+        applied: std::sync::Arc<std::sync::Mutex<Vec<AppliedEdit>>>,
+    }
+
+    impl crate::services::plugins::hooks::EditHook for ShoutingHook {
+        fn before_edit(&self, edit: &PendingEdit) -> crate::services::plugins::hooks::EditDecision {
+            use crate::services::plugins::hooks::EditDecision;
+            match edit {
+                PendingEdit::Insert { text, .. } => EditDecision::Modify(text.to_uppercase()),
+                PendingEdit::Delete { range, .. } if range.len() == 4 => EditDecision::Reject,
+                PendingEdit::Delete { .. } => EditDecision::Allow,
+            }
+        }
+
+        fn after_edit(&self, edit: &AppliedEdit) {
+            self.applied.lock().unwrap().push(edit.clone());
+        }
+    }
+
+    /// Filter an event through the edit hooks and apply it, as the editor does
+    fn apply_filtered(
+        state: &mut crate::state::EditorState,
+        event: &Event,
+        buffer_id: BufferId,
+        registry: &HookRegistry,
+    ) -> bool {
+        let Some(event) = filter_event(event, buffer_id, registry) else {
+            return false;
+        };
+        state.apply(&event);
+        for edit in applied_edits(&event, buffer_id) {
+            registry.run_after_edit(&edit);
+        }
+        true
+    }
+
+    /// Allows or rejects every edit
+    struct DecidingHook(crate::services::plugins::hooks::EditDecision);
+
+    impl crate::services::plugins::hooks::EditHook for DecidingHook {
+        fn before_edit(
+            &self,
+            _edit: &PendingEdit,
+        ) -> crate::services::plugins::hooks::EditDecision {
+            self.0.clone()
+        }
+    }
+
     #[test]
     fn test_hooks_can_cancel() {
+        use crate::services::plugins::hooks::EditDecision;
         use crate::state::EditorState;
-        use std::sync::RwLock;
 
         let mut state =
             EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let hook_registry = RwLock::new(HookRegistry::new());
-
-        // Register a hook that cancels the operation
-        {
-            let mut registry = hook_registry.write().unwrap();
-            registry.add_hook("before_insert", Box::new(|_| false)); // Return false to cancel
-        }
+        let mut registry = HookRegistry::new();
+        registry.add_edit_hook("reject", Box::new(DecidingHook(EditDecision::Reject)));
 
         let event = Event::Insert {
             position: 0,
@@ -220,27 +366,20 @@ mod tests {
         };
 
         let buffer_id = BufferId(0);
-        let was_applied = apply_event_with_hooks(&mut state, &event, buffer_id, &hook_registry);
-
-        // Event should have been cancelled
-        assert!(!was_applied);
+        assert!(filter_event(&event, buffer_id, &registry).is_none());
+        assert!(!apply_filtered(&mut state, &event, buffer_id, &registry));
         assert_eq!(state.buffer.len(), 0); // Buffer should still be empty
     }
 
     #[test]
     fn test_hooks_allow_event() {
+        use crate::services::plugins::hooks::EditDecision;
         use crate::state::EditorState;
-        use std::sync::RwLock;
 
         let mut state =
             EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let hook_registry = RwLock::new(HookRegistry::new());
-
-        // Register a hook that allows the operation
-        {
-            let mut registry = hook_registry.write().unwrap();
-            registry.add_hook("before_insert", Box::new(|_| true)); // Return true to allow
-        }
+        let mut registry = HookRegistry::new();
+        registry.add_edit_hook("allow", Box::new(DecidingHook(EditDecision::Allow)));
 
         let event = Event::Insert {
             position: 0,
@@ -249,10 +388,166 @@ mod tests {
         };
 
         let buffer_id = BufferId(0);
-        let was_applied = apply_event_with_hooks(&mut state, &event, buffer_id, &hook_registry);
-
-        // Event should have been applied
-        assert!(was_applied);
+        match filter_event(&event, buffer_id, &registry) {
+            Some(Event::Insert { position, text, .. }) => {
+                assert_eq!(position, 0);
+                assert_eq!(text, "test");
+            }
+            other => panic!("Expected the insert unchanged, got {:?}", other),
+        }
+        assert!(apply_filtered(&mut state, &event, buffer_id, &registry));
         assert_eq!(state.buffer.to_string().unwrap(), "test");
     }
+
+    #[test]
+    fn test_edit_hook_rewrites_inserted_text() {
+        use crate::state::EditorState;
+
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let hook = ShoutingHook::default();
+        let applied = hook.applied.clone();
+        let mut registry = HookRegistry::new();
+        registry.add_edit_hook("shouting", Box::new(hook));
+        let buffer_id = BufferId(0);
+
+        let event = Event::Insert {
+            position: 0,
+            text: "keep ünïcode".to_string(),
+            cursor_id: CursorId(0),
+        };
+        assert!(apply_filtered(&mut state, &event, buffer_id, &registry));
+        assert_eq!(state.buffer.to_string().unwrap(), "KEEP ÜNÏCODE");
+        // The cursor lands after the rewritten text
+        assert_eq!(state.cursors.primary().position, "KEEP ÜNÏCODE".len());
+
+        // Each edit of a batch is filtered separately: one deletion is rejected
+        let batch = Event::Batch {
+            events: vec![
+                Event::Delete {
+                    range: 0..5,
+                    deleted_text: "KEEP ".to_string(),
+                    cursor_id: CursorId(0),
+                },
+                Event::Delete {
+                    range: 0..4,
+                    deleted_text: "KEEP".to_string(),
+                    cursor_id: CursorId(0),
+                },
+                Event::Insert {
+                    position: 0,
+                    text: "big ".to_string(),
+                    cursor_id: CursorId(0),
+                },
+            ],
+            description: "test".to_string(),
+        };
+        assert!(apply_filtered(&mut state, &batch, buffer_id, &registry));
+        assert_eq!(state.buffer.to_string().unwrap(), "BIG ÜNÏCODE");
+
+        assert_eq!(
+            *applied.lock().unwrap(),
+            vec![
+                AppliedEdit::Insert {
+                    buffer_id,
+                    range: 0..14,
+                    text: "KEEP ÜNÏCODE".to_string(),
+                },
+                AppliedEdit::Delete {
+                    buffer_id,
+                    range: 0..5,
+                    deleted_text: "KEEP ".to_string(),
+                },
+                AppliedEdit::Insert {
+                    buffer_id,
+                    range: 0..4,
+                    text: "BIG ".to_string(),
+                },
+            ]
+        );
+    }
+
+    /// Rejects inserting "x" and expands inserted tabs to two spaces
+    struct TabsHook;
+
+    impl crate::services::plugins::hooks::EditHook for TabsHook {
+        fn before_edit(&self, edit: &PendingEdit) -> crate::services::plugins::hooks::EditDecision {
+            use crate::services::plugins::hooks::EditDecision;
+            match edit {
+                PendingEdit::Insert { text, .. } if text == "x" => EditDecision::Reject,
+                PendingEdit::Insert { text, .. } if text.contains('\t') => {
+                    EditDecision::Modify(text.replace('\t', "  "))
+                }
+                _ => EditDecision::Allow,
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_event_moves_later_batch_edits() {
+        let mut registry = HookRegistry::new();
+        registry.add_edit_hook("tabs", Box::new(TabsHook));
+        let insert = |position, text: &str| Event::Insert {
+            position,
+            text: text.to_string(),
+            cursor_id: CursorId(0),
+        };
+
+        // Planned on "abcdef" to give "x\tabc!e?f"
+        let batch = Event::Batch {
+            events: vec![
+                insert(0, "\t"),
+                insert(4, "!"),
+                Event::Delete {
+                    range: 5..6,
+                    deleted_text: "d".to_string(),
+                    cursor_id: CursorId(0),
+                },
+                insert(0, "x"),
+                insert(7, "?"),
+            ],
+            description: "test".to_string(),
+        };
+        let Some(Event::Batch { events, .. }) = filter_event(&batch, BufferId(0), &registry) else {
+            panic!("batch was rejected");
+        };
+
+        // The tab became two spaces, pushing later edits one byte right, and
+        // the rejected "x" pulls the ones after it back one byte
+        assert_eq!(
+            format!("{:?}", events),
+            format!(
+                "{:?}",
+                vec![
+                    insert(0, "  "),
+                    insert(5, "!"),
+                    Event::Delete {
+                        range: 6..7,
+                        deleted_text: "d".to_string(),
+                        cursor_id: CursorId(0),
+                    },
+                    insert(7, "?"),
+                ]
+            )
+        );
+
+        let mut state = crate::state::EditorState::new(
+            80,
+            24,
+            crate::config::LARGE_FILE_THRESHOLD_BYTES as usize,
+        );
+        state.apply(&insert(0, "abcdef"));
+        state.apply(&Event::Batch {
+            events,
+            description: "test".to_string(),
+        });
+        assert_eq!(state.buffer.to_string().unwrap(), "  abc!e?f");
+
+        // A batch whose every edit is rejected is dropped
+        let batch = Event::Batch {
+            events: vec![insert(0, "x")],
+            description: "test".to_string(),
+        };
+        assert!(filter_event(&batch, BufferId(0), &registry).is_none());
+    }
 }
@@ -110,6 +110,20 @@ pub enum HookArgs {
     /// Editor has been idle for N milliseconds (no input)
     Idle { milliseconds: u64 },
 
+    /// A timer scheduled with `editor.schedule()` has elapsed
+    Timer { token: u64 },
+
+    /// A buffer was edited since the last frame (batched, once per buffer)
+    ///
+    /// `start..old_end` of the text as of the previous notification is now
+    /// `start..new_end`.
+    BufferChanged {
+        buffer_id: BufferId,
+        start: usize,
+        old_end: usize,
+        new_end: usize,
+    },
+
     /// Editor is initializing
     EditorInitialized,
 
@@ -257,10 +271,70 @@ pub struct LspLocation {
 /// Returns `true` to continue execution, `false` to cancel the operation
 pub type HookCallback = Box<dyn Fn(&HookArgs) -> bool + Send + Sync>;
 
+/// A buffer edit about to be applied, as seen by `EditHook::before_edit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingEdit {
+    Insert {
+        buffer_id: BufferId,
+        position: usize,
+        text: String,
+    },
+    Delete {
+        buffer_id: BufferId,
+        range: Range<usize>,
+    },
+}
+
+/// What an `EditHook` wants done with a pending edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditDecision {
+    /// Apply the edit unchanged
+    Allow,
+    /// Insert this text instead; only meaningful for insertions, and treated
+    /// as `Allow` for deletions
+    Modify(String),
+    /// Drop the edit
+    Reject,
+}
+
+/// A buffer edit that was applied, as seen by `EditHook::after_edit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppliedEdit {
+    Insert {
+        buffer_id: BufferId,
+        /// Where the text now is
+        range: Range<usize>,
+        text: String,
+    },
+    Delete {
+        buffer_id: BufferId,
+        /// The range that was removed, in the buffer as it was before
+        range: Range<usize>,
+        deleted_text: String,
+    },
+}
+
+/// Hook that can filter or rewrite edits before they reach a buffer
+///
+/// Unlike named hooks, which can only veto, an edit hook may rewrite inserted
+/// text (e.g. trimming trailing whitespace or changing case). Hooks run in
+/// registration order and each sees the edit as left by the previous one.
+pub trait EditHook: Send + Sync {
+    /// Decide what to do with an edit before it is applied
+    fn before_edit(&self, _edit: &PendingEdit) -> EditDecision {
+        EditDecision::Allow
+    }
+
+    /// Observe an edit after it was applied
+    fn after_edit(&self, _edit: &AppliedEdit) {}
+}
+
 /// Registry for managing hooks
 pub struct HookRegistry {
     /// Map from hook name to list of callbacks
     hooks: HashMap<String, Vec<HookCallback>>,
+    /// Hooks that filter buffer edits, with their names, in registration order
+    edit_hooks: Vec<(String, Box<dyn EditHook>)>,
 }
 
 impl HookRegistry {
@@ -268,6 +342,57 @@ impl HookRegistry {
     pub fn new() -> Self {
         Self {
             hooks: HashMap::new(),
+            edit_hooks: Vec::new(),
+        }
+    }
+
+    /// Add a hook that filters buffer edits, replacing any hook of the same name
+    pub fn add_edit_hook(&mut self, name: &str, hook: Box<dyn EditHook>) {
+        match self.edit_hooks.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = hook,
+            None => self.edit_hooks.push((name.to_string(), hook)),
+        }
+    }
+
+    /// Remove the edit hook with this name, returning whether there was one
+    pub fn remove_edit_hook(&mut self, name: &str) -> bool {
+        let before = self.edit_hooks.len();
+        self.edit_hooks.retain(|(n, _)| n != name);
+        self.edit_hooks.len() != before
+    }
+
+    /// Whether any edit hooks are registered
+    pub fn has_edit_hooks(&self) -> bool {
+        !self.edit_hooks.is_empty()
+    }
+
+    /// Run the edit hooks' `before_edit` in order
+    ///
+    /// Returns the edit to apply, rewritten by any hook that returned
+    /// `Modify`, or `None` if a hook rejected it.
+    pub fn run_before_edit(&self, edit: PendingEdit) -> Option<PendingEdit> {
+        let mut edit = edit;
+        for (_, hook) in &self.edit_hooks {
+            match hook.before_edit(&edit) {
+                EditDecision::Allow => {}
+                EditDecision::Modify(new_text) => {
+                    if let PendingEdit::Insert { text, .. } = &mut edit {
+                        *text = new_text;
+                    }
+                }
+                EditDecision::Reject => {
+                    tracing::debug!("Edit hook rejected {:?}", edit);
+                    return None;
+                }
+            }
+        }
+        Some(edit)
+    }
+
+    /// Run the edit hooks' `after_edit`
+    pub fn run_after_edit(&self, edit: &AppliedEdit) {
+        for (_, hook) in &self.edit_hooks {
+            hook.after_edit(edit);
         }
     }
 
@@ -498,6 +623,22 @@ pub fn hook_args_to_json(args: &HookArgs) -> Result<String> {
         HookArgs::Idle { milliseconds } => {
             serde_json::json!({ "milliseconds": milliseconds })
         }
+        HookArgs::Timer { token } => {
+            serde_json::json!({ "token": token })
+        }
+        HookArgs::BufferChanged {
+            buffer_id,
+            start,
+            old_end,
+            new_end,
+        } => {
+            serde_json::json!({
+                "buffer_id": buffer_id.0,
+                "start": start,
+                "old_end": old_end,
+                "new_end": new_end,
+            })
+        }
         HookArgs::EditorInitialized => {
             serde_json::json!({})
         }
@@ -808,6 +949,13 @@ mod tests {
                 action: Action::Save,
             },
             HookArgs::Idle { milliseconds: 500 },
+            HookArgs::Timer { token: 1 },
+            HookArgs::BufferChanged {
+                buffer_id: BufferId(1),
+                start: 0,
+                old_end: 2,
+                new_end: 5,
+            },
             HookArgs::EditorInitialized,
         ];
 
@@ -6,9 +6,12 @@
 //! scattered throughout the codebase.
 
 use crate::input::command_registry::CommandRegistry;
+use crate::model::event::{BufferId, Event};
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
+use super::event_hooks::filter_event;
+use super::hooks::{AppliedEdit, EditHook, HookRegistry};
 #[cfg(feature = "plugins")]
 use super::thread::PluginThreadHandle;
 
@@ -19,6 +22,8 @@ use super::thread::PluginThreadHandle;
 pub struct PluginManager {
     #[cfg(feature = "plugins")]
     inner: Option<PluginThreadHandle>,
+    /// Hooks registered in-process, such as edit hooks; shared with `PluginApi`
+    hooks: Arc<RwLock<HookRegistry>>,
     #[cfg(not(feature = "plugins"))]
     _phantom: std::marker::PhantomData<()>,
 }
@@ -29,6 +34,8 @@ impl PluginManager {
     /// When `plugins` feature is enabled and `enable` is true, spawns the plugin thread.
     /// Otherwise, creates a no-op manager.
     pub fn new(enable: bool, command_registry: Arc<RwLock<CommandRegistry>>) -> Self {
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+
         #[cfg(feature = "plugins")]
         {
             if enable {
@@ -36,6 +43,7 @@ impl PluginManager {
                     Ok(handle) => {
                         return Self {
                             inner: Some(handle),
+                            hooks,
                         }
                     }
                     Err(e) => {
@@ -47,7 +55,7 @@ impl PluginManager {
             } else {
                 tracing::info!("Plugins disabled via --no-plugins flag");
             }
-            Self { inner: None }
+            Self { inner: None, hooks }
         }
 
         #[cfg(not(feature = "plugins"))]
@@ -57,6 +65,7 @@ impl PluginManager {
                 tracing::warn!("Plugins requested but compiled without plugin support");
             }
             Self {
+                hooks,
                 _phantom: std::marker::PhantomData,
             }
         }
@@ -74,6 +83,35 @@ impl PluginManager {
         }
     }
 
+    /// Handle to the shared hook registry, for creating a `PluginApi`.
+    pub fn hook_registry(&self) -> Arc<RwLock<HookRegistry>> {
+        Arc::clone(&self.hooks)
+    }
+
+    /// Add a hook that may reject or rewrite buffer edits, replacing any
+    /// edit hook of the same name.
+    pub fn add_edit_hook(&self, name: &str, hook: Box<dyn EditHook>) {
+        self.hooks.write().unwrap().add_edit_hook(name, hook);
+    }
+
+    /// Remove an edit hook, returning whether there was one.
+    pub fn remove_edit_hook(&self, name: &str) -> bool {
+        self.hooks.write().unwrap().remove_edit_hook(name)
+    }
+
+    /// Run the edit hooks on an event about to be applied to a buffer.
+    ///
+    /// Returns the event to apply, or `None` if the hooks rejected it (see
+    /// `filter_event`).
+    pub fn filter_edit(&self, event: &Event, buffer_id: BufferId) -> Option<Event> {
+        filter_event(event, buffer_id, &self.hooks.read().unwrap())
+    }
+
+    /// Tell the edit hooks about an applied edit.
+    pub fn run_after_edit(&self, edit: &AppliedEdit) {
+        self.hooks.read().unwrap().run_after_edit(edit);
+    }
+
     /// Load plugins from a directory.
     pub fn load_plugins_from_dir(&self, dir: &Path) -> Vec<String> {
         #[cfg(feature = "plugins")]
@@ -104,6 +142,27 @@ impl PluginManager {
         }
     }
 
+    /// Run a hook in one plugin only (fire-and-forget).
+    ///
+    /// `plugin` None addresses the handlers no plugin owns.
+    pub fn run_plugin_hook(
+        &self,
+        plugin: Option<&str>,
+        hook_name: &str,
+        args: super::hooks::HookArgs,
+    ) {
+        #[cfg(feature = "plugins")]
+        {
+            if let Some(ref manager) = self.inner {
+                manager.run_plugin_hook(plugin, hook_name, args);
+            }
+        }
+        #[cfg(not(feature = "plugins"))]
+        {
+            let _ = (plugin, hook_name, args);
+        }
+    }
+
     /// Deliver a response to a pending async plugin operation.
     pub fn deliver_response(&self, response: super::api::PluginResponse) {
         #[cfg(feature = "plugins")]
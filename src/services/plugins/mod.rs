@@ -7,9 +7,11 @@
 //! Use `PluginManager` as the main interface - it handles both enabled and disabled cases.
 
 pub mod api;
+pub mod buffer_changes;
 pub mod event_hooks;
 pub mod hooks;
 pub mod manager;
+pub mod timers;
 
 #[cfg(feature = "plugins")]
 pub mod process;
@@ -43,7 +43,8 @@ use crate::input::commands::Suggestion;
 use crate::model::event::BufferId;
 use crate::model::event::SplitId;
 use crate::services::plugins::api::{
-    EditorStateSnapshot, LayoutHints, PluginCommand, ViewTokenWire,
+    EditorStateSnapshot, LayoutHints, PluginCapability, PluginCommand, PluginManifest,
+    ViewTokenWire,
 };
 use anyhow::{anyhow, Result};
 use deno_core::{
@@ -160,6 +161,146 @@ struct TsRuntimeState {
     background_processes: Rc<RefCell<HashMap<u64, tokio::process::Child>>>,
     /// Next process ID for background processes
     next_process_id: Rc<RefCell<u64>>,
+    /// Capability manifests of loaded plugins, keyed by plugin name
+    manifests: HashMap<String, PluginManifest>,
+    /// Plugin whose code is currently running (None for builtin or unknown code)
+    current_plugin: Option<String>,
+    /// Global JS function name -> plugin that registered it as an action or handler
+    function_owners: Rc<RefCell<HashMap<String, String>>>,
+}
+
+impl TsRuntimeState {
+    /// Check that `plugin` may use a capability
+    ///
+    /// Plugins without a manifest were given [`PluginManifest::legacy`] when
+    /// they loaded. Code that can't be attributed to a loaded plugin may not
+    /// use any capability.
+    fn check_capability(
+        &self,
+        plugin: Option<&str>,
+        capability: PluginCapability,
+    ) -> Result<(), String> {
+        let Some(manifest) = plugin.and_then(|plugin| self.manifests.get(plugin)) else {
+            tracing::warn!(
+                "Code outside any plugin refused the '{}' capability",
+                capability.name()
+            );
+            return Err(format!(
+                "Code outside a plugin may not use the '{}' capability",
+                capability.name()
+            ));
+        };
+        manifest.check(capability).inspect_err(|e| {
+            tracing::warn!("Plugin '{}' refused: {}", plugin.unwrap_or_default(), e);
+        })
+    }
+
+    /// Check that the currently running plugin may use a capability
+    fn check_current(&self, capability: PluginCapability) -> Result<(), String> {
+        self.check_capability(self.current_plugin.as_deref(), capability)
+    }
+
+    /// Whether the currently running plugin may use a capability
+    fn allows(&self, capability: PluginCapability) -> bool {
+        self.check_current(capability).is_ok()
+    }
+
+    /// Send a command to the editor, refusing it if the running plugin lacks the capability
+    fn send_command(&self, command: PluginCommand) -> bool {
+        if let Some(capability) = command.required_capability() {
+            if !self.allows(capability) {
+                return false;
+            }
+        }
+        self.command_sender.send(command).is_ok()
+    }
+
+    /// Record the running plugin as the owner of a global JS function
+    ///
+    /// Refused if another plugin already owns the name. Code outside a plugin
+    /// claims nothing, so its functions run without capabilities.
+    fn claim_function(&self, function_name: &str) -> bool {
+        let Some(plugin) = &self.current_plugin else {
+            return true;
+        };
+        let mut owners = self.function_owners.borrow_mut();
+        match owners.get(function_name) {
+            Some(owner) if owner != plugin => {
+                tracing::warn!(
+                    "Plugin '{}' can't register '{}', which belongs to plugin '{}'",
+                    plugin,
+                    function_name,
+                    owner
+                );
+                false
+            }
+            _ => {
+                owners.insert(function_name.to_string(), plugin.clone());
+                true
+            }
+        }
+    }
+
+    /// Whether the running plugin owns a global JS function
+    fn owns_function(&self, function_name: &str) -> bool {
+        self.current_plugin
+            .as_ref()
+            .is_some_and(|plugin| self.function_owners.borrow().get(function_name) == Some(plugin))
+    }
+}
+
+/// Whether the plugin running a sync op may use a capability
+fn op_allows(state: &OpState, capability: PluginCapability) -> bool {
+    state
+        .try_borrow::<Rc<RefCell<TsRuntimeState>>>()
+        .is_some_and(|runtime_state| runtime_state.borrow().allows(capability))
+}
+
+/// The plugin that started an async op
+///
+/// Taken before the op's first await, so it names the caller even if other
+/// plugin code runs while the op is pending. Dropping it when the op finishes
+/// makes the caller the running plugin again, so the code that resumes after
+/// the await stays attributed to it.
+struct PendingOp {
+    state: Rc<RefCell<OpState>>,
+    plugin: Option<String>,
+}
+
+impl PendingOp {
+    /// Record the running plugin as the caller, failing if it lacks `capability`
+    fn start(
+        state: &Rc<RefCell<OpState>>,
+        capability: Option<PluginCapability>,
+    ) -> Result<Self, JsErrorBox> {
+        let plugin = {
+            let op_state = state.borrow();
+            let runtime_state = op_state
+                .try_borrow::<Rc<RefCell<TsRuntimeState>>>()
+                .ok_or_else(|| JsErrorBox::generic("Runtime state not available"))?
+                .borrow();
+            let plugin = runtime_state.current_plugin.clone();
+            if let Some(capability) = capability {
+                runtime_state
+                    .check_capability(plugin.as_deref(), capability)
+                    .map_err(JsErrorBox::generic)?;
+            }
+            plugin
+        };
+        Ok(Self {
+            state: Rc::clone(state),
+            plugin,
+        })
+    }
+}
+
+impl Drop for PendingOp {
+    fn drop(&mut self) {
+        let op_state = self.state.borrow();
+        if let Some(runtime_state) = op_state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+            runtime_state.borrow_mut().current_plugin = self.plugin.take();
+        }
+    }
 }
 
 /// Display a transient message in the editor's status bar
@@ -178,6 +319,39 @@ fn op_fresh_set_status(state: &mut OpState, #[string] message: String) {
     tracing::info!("TypeScript plugin set_status: {}", message);
 }
 
+/// Schedule a one-shot timer
+///
+/// After at least `after_ms` milliseconds the editor runs this plugin's "timer"
+/// handlers with `{ token }`; other plugins don't see the timer. Register a
+/// handler with `editor.on("timer", "handlerName")` and use the token to tell
+/// your timers apart. Timers fire from the editor's event loop, so they may
+/// run a little late but never early. The token must be a non-negative
+/// integer no larger than `Number.MAX_SAFE_INTEGER`.
+/// @param after_ms - Delay in milliseconds
+/// @param token - Value passed back to the "timer" hook
+/// @returns true if the timer was scheduled, false if the token is out of range
+#[op2(fast)]
+fn op_fresh_schedule(state: &mut OpState, after_ms: u32, token: f64) -> bool {
+    // Anything else can't make the round trip to the "timer" hook unchanged
+    const MAX_SAFE_INTEGER: f64 = ((1u64 << 53) - 1) as f64;
+    if !(0.0..=MAX_SAFE_INTEGER).contains(&token) || token.fract() != 0.0 {
+        tracing::warn!("TypeScript plugin schedule: invalid token {}", token);
+        return false;
+    }
+    if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+        let runtime_state = runtime_state.borrow();
+        let result = runtime_state
+            .command_sender
+            .send(PluginCommand::ScheduleTimer {
+                plugin: runtime_state.current_plugin.clone(),
+                after_ms: after_ms as u64,
+                token: token as u64,
+            });
+        return result.is_ok();
+    }
+    false
+}
+
 /// Apply a theme by name
 ///
 /// Loads and applies the specified theme immediately. The theme can be a built-in
@@ -348,6 +522,140 @@ fn op_fresh_get_buffer_length(state: &mut OpState, buffer_id: u32) -> u32 {
     0
 }
 
+/// Get the text of a byte range of a buffer
+///
+/// The range is clamped to the buffer. Only the part of a large file
+/// covering the range is loaded. Requires the `read_buffer` capability.
+/// @param buffer_id - Target buffer ID
+/// @param start - Start byte offset (inclusive)
+/// @param end - End byte offset (exclusive)
+/// @returns Promise resolving to the text of the range
+#[op2(async)]
+#[string]
+async fn op_fresh_get_buffer_text(
+    state: Rc<RefCell<OpState>>,
+    buffer_id: u32,
+    start: u32,
+    end: u32,
+) -> Result<String, JsErrorBox> {
+    let _op = PendingOp::start(&state, Some(PluginCapability::ReadBuffer))?;
+    let receiver = {
+        let state = state.borrow();
+        let runtime_state = state
+            .try_borrow::<Rc<RefCell<TsRuntimeState>>>()
+            .ok_or_else(|| JsErrorBox::generic("Failed to get runtime state"))?;
+        let runtime_state = runtime_state.borrow();
+
+        let request_id = {
+            let mut id = runtime_state.next_request_id.borrow_mut();
+            let current = *id;
+            *id += 1;
+            current
+        };
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        {
+            let mut pending = runtime_state.pending_responses.lock().unwrap();
+            pending.insert(request_id, tx);
+        }
+
+        if runtime_state
+            .command_sender
+            .send(PluginCommand::GetBufferText {
+                buffer_id: BufferId(buffer_id as usize),
+                range: start as usize..end as usize,
+                request_id,
+            })
+            .is_err()
+        {
+            let mut pending = runtime_state.pending_responses.lock().unwrap();
+            pending.remove(&request_id);
+            return Err(JsErrorBox::generic("Failed to send buffer text request"));
+        }
+
+        rx
+    };
+
+    let response = receiver
+        .await
+        .map_err(|_| JsErrorBox::generic("Buffer text request cancelled"))?;
+
+    match response {
+        crate::services::plugins::api::PluginResponse::BufferText { text, .. } => {
+            text.map_err(JsErrorBox::generic)
+        }
+        _ => Err(JsErrorBox::generic(
+            "Unexpected plugin response for buffer text request",
+        )),
+    }
+}
+
+/// Get one line of a buffer, without its line ending
+///
+/// Lines are 0-indexed. Only the part of a large file up to the line is
+/// loaded. Requires the `read_buffer` capability.
+/// @param buffer_id - Target buffer ID
+/// @param line - Line number (0-indexed)
+/// @returns Promise resolving to the text of the line; rejects if the buffer has fewer lines
+#[op2(async)]
+#[string]
+async fn op_fresh_get_buffer_line(
+    state: Rc<RefCell<OpState>>,
+    buffer_id: u32,
+    line: u32,
+) -> Result<String, JsErrorBox> {
+    let _op = PendingOp::start(&state, Some(PluginCapability::ReadBuffer))?;
+    let receiver = {
+        let state = state.borrow();
+        let runtime_state = state
+            .try_borrow::<Rc<RefCell<TsRuntimeState>>>()
+            .ok_or_else(|| JsErrorBox::generic("Failed to get runtime state"))?;
+        let runtime_state = runtime_state.borrow();
+
+        let request_id = {
+            let mut id = runtime_state.next_request_id.borrow_mut();
+            let current = *id;
+            *id += 1;
+            current
+        };
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        {
+            let mut pending = runtime_state.pending_responses.lock().unwrap();
+            pending.insert(request_id, tx);
+        }
+
+        if runtime_state
+            .command_sender
+            .send(PluginCommand::GetBufferLine {
+                buffer_id: BufferId(buffer_id as usize),
+                line: line as usize,
+                request_id,
+            })
+            .is_err()
+        {
+            let mut pending = runtime_state.pending_responses.lock().unwrap();
+            pending.remove(&request_id);
+            return Err(JsErrorBox::generic("Failed to send buffer line request"));
+        }
+
+        rx
+    };
+
+    let response = receiver
+        .await
+        .map_err(|_| JsErrorBox::generic("Buffer line request cancelled"))?;
+
+    match response {
+        crate::services::plugins::api::PluginResponse::BufferText { text, .. } => {
+            text.map_err(JsErrorBox::generic)
+        }
+        _ => Err(JsErrorBox::generic(
+            "Unexpected plugin response for buffer line request",
+        )),
+    }
+}
+
 /// Check if a buffer has been modified since last save
 ///
 /// Returns false if buffer doesn't exist or has never been saved.
@@ -383,14 +691,11 @@ fn op_fresh_insert_text(
 ) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
-        let result = runtime_state
-            .command_sender
-            .send(PluginCommand::InsertText {
-                buffer_id: BufferId(buffer_id as usize),
-                position: position as usize,
-                text,
-            });
-        return result.is_ok();
+        return runtime_state.send_command(PluginCommand::InsertText {
+            buffer_id: BufferId(buffer_id as usize),
+            position: position as usize,
+            text,
+        });
     }
     false
 }
@@ -407,13 +712,10 @@ fn op_fresh_insert_text(
 fn op_fresh_delete_range(state: &mut OpState, buffer_id: u32, start: u32, end: u32) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
-        let result = runtime_state
-            .command_sender
-            .send(PluginCommand::DeleteRange {
-                buffer_id: BufferId(buffer_id as usize),
-                range: (start as usize)..(end as usize),
-            });
-        return result.is_ok();
+        return runtime_state.send_command(PluginCommand::DeleteRange {
+            buffer_id: BufferId(buffer_id as usize),
+            range: (start as usize)..(end as usize),
+        });
     }
     false
 }
@@ -925,10 +1227,7 @@ fn op_fresh_clear_view_transform(
 fn op_fresh_insert_at_cursor(state: &mut OpState, #[string] text: String) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
-        let result = runtime_state
-            .command_sender
-            .send(PluginCommand::InsertAtCursor { text });
-        return result.is_ok();
+        return runtime_state.send_command(PluginCommand::InsertAtCursor { text });
     }
     false
 }
@@ -952,6 +1251,9 @@ fn op_fresh_register_command(
 ) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
+        if !runtime_state.claim_function(&action) {
+            return false;
+        }
 
         // Parse contexts string - separate into built-in KeyContext values and custom contexts
         let mut context_list: Vec<crate::input::keybindings::KeyContext> = Vec::new();
@@ -1178,6 +1480,7 @@ struct SpawnResult {
 #[op2(async)]
 #[serde]
 async fn op_fresh_spawn_process(
+    state: Rc<RefCell<OpState>>,
     #[string] command: String,
     #[serde] args: Vec<String>,
     #[string] cwd: Option<String>,
@@ -1186,6 +1489,8 @@ async fn op_fresh_spawn_process(
     use tokio::io::{AsyncBufReadExt, BufReader};
     use tokio::process::Command;
 
+    let _op = PendingOp::start(&state, Some(PluginCapability::SpawnProcess))?;
+
     // Check if we're in a tokio runtime context
     if tokio::runtime::Handle::try_current().is_err() {
         return Err(JsErrorBox::generic(
@@ -1296,6 +1601,8 @@ async fn op_fresh_spawn_background_process(
     use std::process::Stdio;
     use tokio::process::Command;
 
+    let _op = PendingOp::start(&state, Some(PluginCapability::SpawnProcess))?;
+
     // Build the command
     let mut cmd = Command::new(&command);
     cmd.args(&args);
@@ -1349,6 +1656,7 @@ async fn op_fresh_kill_process(
     state: Rc<RefCell<OpState>>,
     #[bigint] process_id: u64,
 ) -> Result<bool, JsErrorBox> {
+    let _op = PendingOp::start(&state, Some(PluginCapability::SpawnProcess))?;
     let child_opt = {
         let op_state = state.borrow();
         if let Some(runtime_state) = op_state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
@@ -1429,6 +1737,9 @@ fn op_fresh_on(
 ) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
+        if !runtime_state.claim_function(&handler_name) {
+            return false;
+        }
         let mut handlers = runtime_state.event_handlers.borrow_mut();
         handlers
             .entry(event_name.clone())
@@ -1488,6 +1799,17 @@ fn op_fresh_get_handlers(state: &mut OpState, #[string] event_name: String) -> V
     Vec::new()
 }
 
+/// Whether the running plugin registered a global function name
+///
+/// Used by the bootstrap to capture registered functions for their owner
+/// only. Not part of the editor API, so it has no `op_fresh_` name.
+#[op2(fast)]
+fn op_owns_function(state: &mut OpState, #[string] name: String) -> bool {
+    state
+        .try_borrow::<Rc<RefCell<TsRuntimeState>>>()
+        .is_some_and(|runtime_state| runtime_state.borrow().owns_function(&name))
+}
+
 /// File stat information
 #[derive(serde::Serialize)]
 struct FileStat {
@@ -1617,6 +1939,9 @@ fn op_fresh_get_buffer_saved_diff(
     state: &mut OpState,
     buffer_id: u32,
 ) -> Option<TsBufferSavedDiff> {
+    if !op_allows(state, PluginCapability::ReadBuffer) {
+        return None;
+    }
     let runtime_state = state
         .try_borrow::<Rc<RefCell<TsRuntimeState>>>()?
         .borrow()
@@ -1878,7 +2203,11 @@ fn op_fresh_set_prompt_suggestions(
 /// @param path - File path (absolute or relative to cwd)
 #[op2(async)]
 #[string]
-async fn op_fresh_read_file(#[string] path: String) -> Result<String, JsErrorBox> {
+async fn op_fresh_read_file(
+    state: Rc<RefCell<OpState>>,
+    #[string] path: String,
+) -> Result<String, JsErrorBox> {
+    let _op = PendingOp::start(&state, Some(PluginCapability::ReadFiles))?;
     tokio::fs::read_to_string(&path)
         .await
         .map_err(|e| JsErrorBox::generic(format!("Failed to read file {}: {}", path, e)))
@@ -1892,9 +2221,11 @@ async fn op_fresh_read_file(#[string] path: String) -> Result<String, JsErrorBox
 /// @param content - UTF-8 string to write
 #[op2(async)]
 async fn op_fresh_write_file(
+    state: Rc<RefCell<OpState>>,
     #[string] path: String,
     #[string] content: String,
 ) -> Result<(), JsErrorBox> {
+    let _op = PendingOp::start(&state, Some(PluginCapability::WriteFiles))?;
     tokio::fs::write(&path, content)
         .await
         .map_err(|e| JsErrorBox::generic(format!("Failed to write file {}: {}", path, e)))
@@ -1906,8 +2237,8 @@ async fn op_fresh_write_file(
 /// Use fileStat for more detailed information.
 /// @param path - Path to check (absolute or relative to cwd)
 #[op2(fast)]
-fn op_fresh_file_exists(#[string] path: String) -> bool {
-    std::path::Path::new(&path).exists()
+fn op_fresh_file_exists(state: &mut OpState, #[string] path: String) -> bool {
+    op_allows(state, PluginCapability::ReadFiles) && std::path::Path::new(&path).exists()
 }
 
 /// Get metadata about a file or directory
@@ -1917,9 +2248,14 @@ fn op_fresh_file_exists(#[string] path: String) -> bool {
 /// @param path - Path to stat (absolute or relative to cwd)
 #[op2]
 #[serde]
-fn op_fresh_file_stat(#[string] path: String) -> FileStat {
+fn op_fresh_file_stat(state: &mut OpState, #[string] path: String) -> FileStat {
     let path = std::path::Path::new(&path);
-    match std::fs::metadata(path) {
+    let metadata = if op_allows(state, PluginCapability::ReadFiles) {
+        std::fs::metadata(path)
+    } else {
+        Err(std::io::ErrorKind::PermissionDenied.into())
+    };
+    match metadata {
         Ok(metadata) => FileStat {
             exists: true,
             is_file: metadata.is_file(),
@@ -2074,6 +2410,12 @@ fn op_fresh_read_dir(
     state: &mut OpState,
     #[string] path: String,
 ) -> Result<Vec<DirEntry>, JsErrorBox> {
+    if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+        runtime_state
+            .borrow()
+            .check_current(PluginCapability::ReadFiles)
+            .map_err(JsErrorBox::generic)?;
+    }
     // Resolve relative paths against the editor's working directory
     let resolved_path = if std::path::Path::new(&path).is_absolute() {
         std::path::PathBuf::from(&path)
@@ -2194,6 +2536,7 @@ async fn op_fresh_create_virtual_buffer_in_split(
     state: Rc<RefCell<OpState>>,
     #[serde] options: CreateVirtualBufferOptions,
 ) -> Result<CreateVirtualBufferResult, JsErrorBox> {
+    let _op = PendingOp::start(&state, None)?;
     // Get runtime state and create oneshot channel
     let receiver = {
         let state = state.borrow();
@@ -2305,6 +2648,7 @@ async fn op_fresh_create_virtual_buffer_in_existing_split(
     state: Rc<RefCell<OpState>>,
     #[serde] options: CreateVirtualBufferInExistingSplitOptions,
 ) -> Result<u32, JsErrorBox> {
+    let _op = PendingOp::start(&state, None)?;
     // Get runtime state and create oneshot channel
     let receiver = {
         let state = state.borrow();
@@ -2404,6 +2748,7 @@ async fn op_fresh_create_virtual_buffer(
     state: Rc<RefCell<OpState>>,
     #[serde] options: CreateVirtualBufferInCurrentSplitOptions,
 ) -> Result<u32, JsErrorBox> {
+    let _op = PendingOp::start(&state, None)?;
     // Get runtime state and create oneshot channel
     let receiver = {
         let state = state.borrow();
@@ -2486,6 +2831,7 @@ async fn op_fresh_send_lsp_request(
     #[string] method: String,
     #[serde] params: Option<serde_json::Value>,
 ) -> Result<serde_json::Value, JsErrorBox> {
+    let _op = PendingOp::start(&state, None)?;
     let receiver = {
         let state = state.borrow();
         let runtime_state = state
@@ -2562,6 +2908,11 @@ fn op_fresh_define_mode(
 ) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
+        // Bound commands that are plugin functions run as this plugin; names
+        // another plugin owns keep running as that plugin
+        for (_, command) in &bindings {
+            runtime_state.claim_function(command);
+        }
         let result = runtime_state
             .command_sender
             .send(PluginCommand::DefineMode {
@@ -2599,12 +2950,9 @@ fn op_fresh_show_buffer(state: &mut OpState, buffer_id: u32) -> bool {
 fn op_fresh_close_buffer(state: &mut OpState, buffer_id: u32) -> bool {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
-        let result = runtime_state
-            .command_sender
-            .send(PluginCommand::CloseBuffer {
-                buffer_id: BufferId(buffer_id as usize),
-            });
-        return result.is_ok();
+        return runtime_state.send_command(PluginCommand::CloseBuffer {
+            buffer_id: BufferId(buffer_id as usize),
+        });
     }
     false
 }
@@ -2733,6 +3081,9 @@ fn op_fresh_get_text_properties_at_cursor(
 ) -> Vec<std::collections::HashMap<String, serde_json::Value>> {
     if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
         let runtime_state = runtime_state.borrow();
+        if !runtime_state.allows(PluginCapability::ReadBuffer) {
+            return vec![];
+        }
         if let Ok(snapshot) = runtime_state.state_snapshot.read() {
             let buffer_id_key = BufferId(buffer_id as usize);
 
@@ -2781,13 +3132,10 @@ fn op_fresh_set_virtual_buffer_content(
             })
             .collect();
 
-        let result = runtime_state
-            .command_sender
-            .send(PluginCommand::SetVirtualBufferContent {
-                buffer_id: BufferId(buffer_id as usize),
-                entries: rust_entries,
-            });
-        return result.is_ok();
+        return runtime_state.send_command(PluginCommand::SetVirtualBufferContent {
+            buffer_id: BufferId(buffer_id as usize),
+            entries: rust_entries,
+        });
     }
     false
 }
@@ -2797,6 +3145,7 @@ extension!(
     fresh_runtime,
     ops = [
         op_fresh_set_status,
+        op_fresh_schedule,
         op_fresh_apply_theme,
         op_fresh_reload_config,
         op_fresh_get_config,
@@ -2807,6 +3156,8 @@ extension!(
         op_fresh_get_cursor_position,
         op_fresh_get_buffer_path,
         op_fresh_get_buffer_length,
+        op_fresh_get_buffer_text,
+        op_fresh_get_buffer_line,
         op_fresh_get_buffer_saved_diff,
         op_fresh_is_buffer_modified,
         op_fresh_insert_text,
@@ -2865,6 +3216,7 @@ extension!(
         op_fresh_on,
         op_fresh_off,
         op_fresh_get_handlers,
+        op_owns_function,
         // Virtual buffer operations
         op_fresh_create_virtual_buffer_in_split,
         op_fresh_create_virtual_buffer_in_existing_split,
@@ -2939,6 +3291,9 @@ impl TypeScriptRuntime {
             next_request_id: Rc::new(RefCell::new(1)),
             background_processes: Rc::new(RefCell::new(HashMap::new())),
             next_process_id: Rc::new(RefCell::new(1)),
+            manifests: HashMap::new(),
+            current_plugin: None,
+            function_owners: Rc::new(RefCell::new(HashMap::new())),
         }));
 
         tracing::debug!(
@@ -2965,6 +3320,46 @@ impl TypeScriptRuntime {
                 r#"
                 const core = Deno.core;
 
+                // Actions and event handlers, captured when they're registered
+                //
+                // Dispatch calls the captured function instead of looking its name up on
+                // globalThis again, so a plugin that reassigns the global can't get its code
+                // run with the capabilities of the plugin that registered it.
+                const { captureFunction, capturePending, registeredFunction } = (() => {
+                    const functions = new Map();
+                    // Names registered before their function was defined
+                    let pending = new Set();
+                    return {
+                        captureFunction(name) {
+                            if (!core.ops.op_owns_function(name)) {
+                                return;
+                            }
+                            const fn = globalThis[name];
+                            if (typeof fn === 'function') {
+                                functions.set(name, fn);
+                                pending.delete(name);
+                            } else {
+                                pending.add(name);
+                            }
+                        },
+                        // Called once the registering plugin's code has finished running, so
+                        // functions defined after being registered are captured too
+                        capturePending() {
+                            for (const name of pending) {
+                                if (core.ops.op_owns_function(name)) {
+                                    pending.delete(name);
+                                    if (typeof globalThis[name] === 'function') {
+                                        functions.set(name, globalThis[name]);
+                                    }
+                                }
+                            }
+                        },
+                        registeredFunction(name) {
+                            return functions.get(name);
+                        },
+                    };
+                })();
+
                 // Create the editor API object
                 const editor = {
                     // Status and logging
@@ -2974,6 +3369,9 @@ impl TypeScriptRuntime {
                     debug(message) {
                         core.ops.op_fresh_debug(message);
                     },
+                    schedule(afterMs, token) {
+                        return core.ops.op_fresh_schedule(afterMs, token);
+                    },
 
                     // Theme operations
                     applyTheme(themeName) {
@@ -3009,6 +3407,12 @@ impl TypeScriptRuntime {
                     getBufferLength(bufferId) {
                         return core.ops.op_fresh_get_buffer_length(bufferId);
                     },
+                    getBufferText(bufferId, start, end) {
+                        return core.ops.op_fresh_get_buffer_text(bufferId, start, end);
+                    },
+                    getBufferLine(bufferId, line) {
+                        return core.ops.op_fresh_get_buffer_line(bufferId, line);
+                    },
                     getBufferSavedDiff(bufferId) {
                         return core.ops.op_fresh_get_buffer_saved_diff(bufferId);
                     },
@@ -3099,7 +3503,11 @@ impl TypeScriptRuntime {
                     registerCommand(name, description, action, contexts = "") {
                         // Pass the current plugin source (set by load_module_with_source)
                         const source = globalThis.__PLUGIN_SOURCE__ || "";
-                        return core.ops.op_fresh_register_command(name, description, action, contexts, source);
+                        const registered = core.ops.op_fresh_register_command(name, description, action, contexts, source);
+                        if (registered) {
+                            captureFunction(action);
+                        }
+                        return registered;
                     },
 
                     unregisterCommand(name) {
@@ -3224,7 +3632,11 @@ impl TypeScriptRuntime {
 
                     // Event/Hook operations
                     on(eventName, handlerName) {
-                        return core.ops.op_fresh_on(eventName, handlerName);
+                        const registered = core.ops.op_fresh_on(eventName, handlerName);
+                        if (registered) {
+                            captureFunction(handlerName);
+                        }
+                        return registered;
                     },
                     off(eventName, handlerName) {
                         return core.ops.op_fresh_off(eventName, handlerName);
@@ -3244,7 +3656,11 @@ impl TypeScriptRuntime {
                         return core.ops.op_fresh_create_virtual_buffer(options);
                     },
                     defineMode(name, parent, bindings, readOnly = false) {
-                        return core.ops.op_fresh_define_mode(name, parent, bindings, readOnly);
+                        const defined = core.ops.op_fresh_define_mode(name, parent, bindings, readOnly);
+                        for (const [, command] of bindings) {
+                            captureFunction(command);
+                        }
+                        return defined;
                     },
                     showBuffer(bufferId) {
                         return core.ops.op_fresh_show_buffer(bufferId);
@@ -3281,20 +3697,40 @@ impl TypeScriptRuntime {
                 // Make editor globally available
                 globalThis.editor = editor;
 
+                // A registered name only runs the function captured for it; names no
+                // plugin registered are looked up on globalThis and run without capabilities
+                const lookupFunction = (name, registered) =>
+                    registered ? registeredFunction(name) : globalThis[name];
+
+                // Entry points called by the runtime; plugins can't replace them
+                Object.defineProperty(globalThis, "__capturePending", { value: capturePending });
+
+                Object.defineProperty(globalThis, "__actionDispatcher", {
+                    value: async function(actionName, registered) {
+                        const action = lookupFunction(actionName, registered);
+                        if (typeof action !== 'function') {
+                            throw new Error('Action "' + actionName + '" is not defined as a global function');
+                        }
+                        await action();
+                    },
+                });
+
                 // Pre-compiled event dispatcher for performance
                 // This avoids recompiling JavaScript code for each event emission
-                globalThis.__eventDispatcher = async function(handlerName, eventData) {
-                    const handler = globalThis[handlerName];
-                    if (typeof handler === 'function') {
-                        const result = handler(eventData);
-                        const finalResult = (result instanceof Promise) ? await result : result;
-                        // Return true by default if handler doesn't return anything
-                        return finalResult !== false;
-                    } else {
-                        console.warn('Event handler "' + handlerName + '" is not defined');
-                        return true;
-                    }
-                };
+                Object.defineProperty(globalThis, "__eventDispatcher", {
+                    value: async function(handlerName, registered, eventData) {
+                        const handler = lookupFunction(handlerName, registered);
+                        if (typeof handler === 'function') {
+                            const result = handler(eventData);
+                            const finalResult = (result instanceof Promise) ? await result : result;
+                            // Return true by default if handler doesn't return anything
+                            return finalResult !== false;
+                        } else {
+                            console.warn('Event handler "' + handlerName + '" is not defined');
+                            return true;
+                        }
+                    },
+                });
                 "#
                 .to_string(),
             )
@@ -3320,7 +3756,8 @@ impl TypeScriptRuntime {
                 request_id,
                 ..
             } => *request_id,
-            crate::services::plugins::api::PluginResponse::LspRequest { request_id, .. } => {
+            crate::services::plugins::api::PluginResponse::LspRequest { request_id, .. }
+            | crate::services::plugins::api::PluginResponse::BufferText { request_id, .. } => {
                 *request_id
             }
         };
@@ -3365,7 +3802,32 @@ impl TypeScriptRuntime {
     }
 
     /// Load and execute a TypeScript/JavaScript module file with explicit plugin source
+    ///
+    /// A `<plugin>.manifest.json` next to the module restricts which capabilities the
+    /// plugin's code may use, both while loading and later in its actions and handlers.
     pub async fn load_module_with_source(&mut self, path: &str, plugin_source: &str) -> Result<()> {
+        let plugin = (!plugin_source.is_empty()).then(|| plugin_source.to_string());
+        if let Some(plugin) = &plugin {
+            let manifest = PluginManifest::load_for(Path::new(path))
+                .map_err(|e| anyhow!(e))?
+                .unwrap_or_else(|| {
+                    tracing::warn!(
+                        "Plugin '{}' has no manifest, so it may use every capability",
+                        plugin
+                    );
+                    PluginManifest::legacy()
+                });
+            self.set_manifest(plugin, manifest);
+        }
+
+        let previous = self.set_current_plugin(plugin);
+        let result = self.evaluate_module(path, plugin_source).await;
+        self.capture_pending();
+        self.set_current_plugin(previous);
+        result
+    }
+
+    async fn evaluate_module(&mut self, path: &str, plugin_source: &str) -> Result<()> {
         // Set the plugin source as a global so registerCommand can use it
         let set_source: FastString = format!(
             "globalThis.__PLUGIN_SOURCE__ = {};",
@@ -3414,21 +3876,24 @@ impl TypeScriptRuntime {
     }
 
     /// Execute a global function by name (for plugin actions)
+    ///
+    /// An action a plugin registered runs the function captured when it was
+    /// registered, with that plugin's capabilities.
     pub async fn execute_action(&mut self, action_name: &str) -> Result<()> {
+        let owner = self.function_owner(action_name);
+        let registered = owner.is_some();
+        let previous = self.set_current_plugin(owner);
+        let result = self.run_action(action_name, registered).await;
+        self.capture_pending();
+        self.set_current_plugin(previous);
+        result
+    }
+
+    async fn run_action(&mut self, action_name: &str, registered: bool) -> Result<()> {
         let code = format!(
-            r#"
-            (async () => {{
-                if (typeof globalThis.{} === 'function') {{
-                    const result = globalThis.{}();
-                    if (result instanceof Promise) {{
-                        await result;
-                    }}
-                }} else {{
-                    throw new Error('Action "{}" is not defined as a global function');
-                }}
-            }})();
-            "#,
-            action_name, action_name, action_name
+            "__actionDispatcher({}, {});",
+            serde_json::to_string(action_name).unwrap_or_else(|_| "\"\"".to_string()),
+            registered
         );
 
         self.execute_script("<action>", &code).await
@@ -3448,53 +3913,80 @@ impl TypeScriptRuntime {
     /// * `Ok(false)` if any handler returned false (cancel)
     /// * `Err` if handler execution failed
     pub async fn emit(&mut self, event_name: &str, event_data: &str) -> Result<bool> {
-        let emit_start = std::time::Instant::now();
-        let handlers = self.event_handlers.borrow().get(event_name).cloned();
-
-        if let Some(handler_names) = handlers {
-            if handler_names.is_empty() {
-                return Ok(true);
-            }
+        let handler_names = self.get_registered_handlers(event_name);
+        self.call_handlers(event_name, &handler_names, event_data)
+            .await
+    }
 
-            for handler_name in &handler_names {
-                let call_start = std::time::Instant::now();
+    /// Emit an event to the handlers one plugin registered
+    ///
+    /// `plugin` None addresses the handlers no plugin owns. Used for events
+    /// that belong to a single plugin, such as its timers.
+    pub async fn emit_to(
+        &mut self,
+        plugin: Option<&str>,
+        event_name: &str,
+        event_data: &str,
+    ) -> Result<bool> {
+        let handler_names: Vec<String> = self
+            .get_registered_handlers(event_name)
+            .into_iter()
+            .filter(|handler_name| self.function_owner(handler_name).as_deref() == plugin)
+            .collect();
+        self.call_handlers(event_name, &handler_names, event_data)
+            .await
+    }
 
-                // Use execute_script to call the event dispatcher
-                // This escapes the handler name and embeds the event data as JSON
-                let script = format!(
-                    "__eventDispatcher({}, {})",
-                    serde_json::to_string(handler_name).unwrap_or_else(|_| "\"\"".to_string()),
-                    event_data
-                );
+    async fn call_handlers(
+        &mut self,
+        event_name: &str,
+        handler_names: &[String],
+        event_data: &str,
+    ) -> Result<bool> {
+        let emit_start = std::time::Instant::now();
+        for handler_name in handler_names {
+            let call_start = std::time::Instant::now();
+            let owner = self.function_owner(handler_name);
+            let registered = owner.is_some();
+            let previous = self.set_current_plugin(owner);
+
+            // Use execute_script to call the event dispatcher
+            // This escapes the handler name and embeds the event data as JSON
+            let script = format!(
+                "__eventDispatcher({}, {}, {})",
+                serde_json::to_string(handler_name).unwrap_or_else(|_| "\"\"".to_string()),
+                registered,
+                event_data
+            );
 
-                match self.js_runtime.execute_script("<emit>", script) {
-                    Ok(_) => {
-                        let call_elapsed = call_start.elapsed();
-
-                        // Run event loop to process any async work (promises)
-                        let event_loop_start = std::time::Instant::now();
-                        self.js_runtime
-                            .run_event_loop(Default::default())
-                            .await
-                            .map_err(|e| anyhow!("Event loop error in emit: {}", e))?;
-                        let event_loop_elapsed = event_loop_start.elapsed();
-
-                        tracing::trace!(
-                            event = event_name,
-                            handler = handler_name,
-                            call_us = call_elapsed.as_micros(),
-                            event_loop_us = event_loop_elapsed.as_micros(),
-                            "emit handler timing"
-                        );
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            "Failed to call event handler '{}' for '{}': {:?}",
-                            handler_name,
-                            event_name,
-                            e
-                        );
-                    }
+            match self.js_runtime.execute_script("<emit>", script) {
+                Ok(_) => {
+                    let call_elapsed = call_start.elapsed();
+
+                    // Run event loop to process any async work (promises)
+                    let event_loop_start = std::time::Instant::now();
+                    let event_loop = self.js_runtime.run_event_loop(Default::default()).await;
+                    self.capture_pending();
+                    self.set_current_plugin(previous);
+                    event_loop.map_err(|e| anyhow!("Event loop error in emit: {}", e))?;
+                    let event_loop_elapsed = event_loop_start.elapsed();
+
+                    tracing::trace!(
+                        event = event_name,
+                        handler = handler_name,
+                        call_us = call_elapsed.as_micros(),
+                        event_loop_us = event_loop_elapsed.as_micros(),
+                        "emit handler timing"
+                    );
+                }
+                Err(e) => {
+                    self.set_current_plugin(previous);
+                    tracing::error!(
+                        "Failed to call event handler '{}' for '{}': {:?}",
+                        handler_name,
+                        event_name,
+                        e
+                    );
                 }
             }
         }
@@ -3527,6 +4019,49 @@ impl TypeScriptRuntime {
             .unwrap_or(false)
     }
 
+    /// Restrict a plugin to the capabilities declared in its manifest
+    pub fn set_manifest(&mut self, plugin: &str, manifest: PluginManifest) {
+        let op_state = self.js_runtime.op_state();
+        let op_state = op_state.borrow();
+        if let Some(runtime_state) = op_state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+            runtime_state
+                .borrow_mut()
+                .manifests
+                .insert(plugin.to_string(), manifest);
+        }
+    }
+
+    /// Mark which plugin's code is about to run, returning the previous one
+    fn set_current_plugin(&mut self, plugin: Option<String>) -> Option<String> {
+        let op_state = self.js_runtime.op_state();
+        let op_state = op_state.borrow();
+        op_state
+            .try_borrow::<Rc<RefCell<TsRuntimeState>>>()
+            .and_then(|runtime_state| {
+                std::mem::replace(&mut runtime_state.borrow_mut().current_plugin, plugin)
+            })
+    }
+
+    /// Capture the functions the running plugin registered before defining them
+    fn capture_pending(&mut self) {
+        if let Err(e) = self
+            .js_runtime
+            .execute_script("<capture_pending>", "__capturePending();")
+        {
+            tracing::error!("Failed to capture registered functions: {}", e);
+        }
+    }
+
+    /// Plugin that registered a global function as an action or event handler
+    fn function_owner(&mut self, function_name: &str) -> Option<String> {
+        let op_state = self.js_runtime.op_state();
+        let op_state = op_state.borrow();
+        let runtime_state = op_state.try_borrow::<Rc<RefCell<TsRuntimeState>>>()?;
+        let runtime_state = runtime_state.borrow();
+        let owners = runtime_state.function_owners.borrow();
+        owners.get(function_name).cloned()
+    }
+
     /// Send a status message to the editor UI
     pub fn send_status(&mut self, message: String) {
         let op_state = self.js_runtime.op_state();
@@ -3823,6 +4358,13 @@ impl TypeScriptPluginManager {
 mod tests {
     use super::*;
 
+    /// Run scripts as a plugin without a manifest, like plugins loaded from
+    /// disk, so ops that need a capability are allowed
+    fn run_as_plugin(runtime: &mut TypeScriptRuntime) {
+        runtime.set_manifest("test", PluginManifest::legacy());
+        runtime.set_current_plugin(Some("test".to_string()));
+    }
+
     #[tokio::test]
     async fn test_typescript_runtime_creation() {
         let runtime = TypeScriptRuntime::new();
@@ -3957,6 +4499,7 @@ mod tests {
 
         // Create runtime with state
         let mut runtime = TypeScriptRuntime::with_state(state_snapshot.clone(), tx).unwrap();
+        run_as_plugin(&mut runtime);
 
         // Test querying state from TypeScript
         let result = runtime
@@ -4481,6 +5024,7 @@ mod tests {
     #[tokio::test]
     async fn test_spawn_process_simple() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         // Test spawning a simple echo command
         let result = runtime
@@ -4506,6 +5050,7 @@ mod tests {
     #[tokio::test]
     async fn test_spawn_process_with_stderr() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         // Test spawning a command that writes to stderr
         let result = runtime
@@ -4528,6 +5073,7 @@ mod tests {
     #[tokio::test]
     async fn test_spawn_process_nonzero_exit() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         // Test spawning a command that exits with non-zero
         let result = runtime
@@ -4550,6 +5096,7 @@ mod tests {
     #[tokio::test]
     async fn test_spawn_process_git_example() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         // Test a realistic example: git version
         let result = runtime
@@ -4573,6 +5120,7 @@ mod tests {
     #[tokio::test]
     async fn test_file_exists() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         let result = runtime
             .execute_script(
@@ -4600,6 +5148,7 @@ mod tests {
     #[tokio::test]
     async fn test_file_stat() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         let result = runtime
             .execute_script(
@@ -4636,6 +5185,7 @@ mod tests {
     #[tokio::test]
     async fn test_read_file() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         let result = runtime
             .execute_script(
@@ -4755,6 +5305,7 @@ mod tests {
     #[tokio::test]
     async fn test_write_file() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         // Use platform-appropriate temp directory
         let temp_file = std::env::temp_dir().join("fresh_ts_runtime_test_write.txt");
@@ -4805,6 +5356,7 @@ mod tests {
     #[tokio::test]
     async fn test_read_dir() {
         let mut runtime = TypeScriptRuntime::new().unwrap();
+        run_as_plugin(&mut runtime);
 
         let result = runtime
             .execute_script(
@@ -5127,6 +5679,90 @@ mod tests {
         assert!(verify.is_ok(), "Verify failed: {:?}", verify);
     }
 
+    #[tokio::test]
+    async fn test_emit_to_runs_only_one_plugins_handlers() {
+        let mut runtime = TypeScriptRuntime::new().unwrap();
+        for plugin in ["first", "second"] {
+            runtime.set_current_plugin(Some(plugin.to_string()));
+            let setup = runtime
+                .execute_script(
+                    "<test_emit_to_setup>",
+                    &format!(
+                        r#"
+                        globalThis.{plugin}Tokens = [];
+                        globalThis.{plugin}Timer = function(data) {{
+                            globalThis.{plugin}Tokens.push(data.token);
+                            return true;
+                        }};
+                        editor.on("timer", "{plugin}Timer");
+                        "#
+                    ),
+                )
+                .await;
+            assert!(setup.is_ok(), "Setup failed: {:?}", setup);
+        }
+        runtime.set_current_plugin(None);
+
+        let emit_result = runtime
+            .emit_to(Some("second"), "timer", r#"{"token": 7}"#)
+            .await;
+        assert!(emit_result.is_ok(), "Emit failed: {:?}", emit_result);
+
+        let verify = runtime
+            .execute_script(
+                "<test_emit_to_verify>",
+                r#"
+                if (globalThis.firstTokens.length !== 0) {
+                    throw new Error("first plugin saw another plugin's timer");
+                }
+                if (JSON.stringify(globalThis.secondTokens) !== "[7]") {
+                    throw new Error("second plugin's timer did not fire once");
+                }
+                "#,
+            )
+            .await;
+        assert!(verify.is_ok(), "Verify failed: {:?}", verify);
+    }
+
+    #[tokio::test]
+    async fn test_schedule_keeps_large_tokens_and_rejects_invalid_ones() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+        let mut runtime = TypeScriptRuntime::with_state(state_snapshot, tx).unwrap();
+        runtime.set_current_plugin(Some("timers".to_string()));
+
+        let result = runtime
+            .execute_script(
+                "<test_schedule>",
+                r#"
+                if (!editor.schedule(250, 2 ** 40)) {
+                    throw new Error("a token past u32 was rejected");
+                }
+                for (const token of [-1, 1.5, 2 ** 53]) {
+                    if (editor.schedule(250, token)) {
+                        throw new Error(`token ${token} was accepted`);
+                    }
+                }
+                "#,
+            )
+            .await;
+        assert!(result.is_ok(), "Script failed: {:?}", result);
+
+        match rx.try_recv().unwrap() {
+            PluginCommand::ScheduleTimer {
+                plugin,
+                after_ms,
+                token,
+            } => {
+                assert_eq!(plugin.as_deref(), Some("timers"));
+                assert_eq!(after_ms, 250);
+                assert_eq!(token, 1 << 40);
+            }
+            _ => panic!("Wrong command type"),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
     // === TypeScriptPluginManager Tests ===
 
     #[tokio::test]
@@ -5564,6 +6200,313 @@ mod tests {
         assert!(has_status, "Expected SetStatus with imported MESSAGE");
     }
 
+    #[tokio::test]
+    async fn test_ts_plugin_manager_refuses_undeclared_capability() {
+        use tempfile::TempDir;
+
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let mut manager = TypeScriptPluginManager::new(hooks, commands).unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let plugin_path = temp_dir.path().join("readonly.ts");
+        std::fs::write(
+            &plugin_path,
+            r#"
+            globalThis.readonlyEdit = function() {
+                const ok = editor.insertText(0, 0, "from action");
+                editor.setStatus(`action insert: ${ok}`);
+            };
+            editor.registerCommand("Readonly Edit", "", "readonlyEdit", "normal");
+            const ok = editor.insertText(0, 0, "from load");
+            editor.setStatus(`load insert: ${ok}`);
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("readonly.manifest.json"),
+            r#"{ "capabilities": ["read_buffer"] }"#,
+        )
+        .unwrap();
+
+        manager.load_plugin(&plugin_path).await.unwrap();
+        manager.execute_action("readonlyEdit").await.unwrap();
+
+        let cmds = manager.process_commands();
+        assert!(
+            !cmds
+                .iter()
+                .any(|cmd| matches!(cmd, PluginCommand::InsertText { .. })),
+            "Edits from a plugin without write_buffer must be refused"
+        );
+        for expected in ["load insert: false", "action insert: false"] {
+            assert!(
+                cmds.iter().any(
+                    |cmd| matches!(cmd, PluginCommand::SetStatus { message } if message == expected)
+                ),
+                "Expected status '{}'",
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugin_without_manifest_keeps_every_capability() {
+        use tempfile::TempDir;
+
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let mut manager = TypeScriptPluginManager::new(hooks, commands).unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let plugin_path = temp_dir.path().join("no_manifest.ts");
+        let written = temp_dir.path().join("written.txt");
+        std::fs::write(
+            &plugin_path,
+            format!(
+                r#"
+            globalThis.tryOps = async function() {{
+                const ok = editor.insertText(1, 0, "x");
+                await editor.writeFile({written:?}, "x");
+                editor.setStatus(`insert: ${{ok}}`);
+            }};
+            editor.registerCommand("Try Ops", "", "tryOps", "normal");
+            "#
+            ),
+        )
+        .unwrap();
+
+        manager.load_plugin(&plugin_path).await.unwrap();
+        manager.execute_action("tryOps").await.unwrap();
+
+        let cmds = manager.process_commands();
+        assert!(cmds
+            .iter()
+            .any(|cmd| matches!(cmd, PluginCommand::InsertText { .. })));
+        assert!(cmds.iter().any(
+            |cmd| matches!(cmd, PluginCommand::SetStatus { message } if message == "insert: true")
+        ));
+        assert!(written.exists());
+    }
+
+    #[tokio::test]
+    async fn test_read_ops_refused_without_read_buffer() {
+        use crate::services::plugins::api::BufferSavedDiff;
+        use tempfile::TempDir;
+
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let mut manager = TypeScriptPluginManager::new(hooks, commands).unwrap();
+        manager
+            .state_snapshot_handle()
+            .write()
+            .unwrap()
+            .buffer_saved_diffs
+            .insert(
+                BufferId(1),
+                BufferSavedDiff {
+                    equal: true,
+                    byte_ranges: vec![],
+                    line_ranges: None,
+                },
+            );
+
+        let temp_dir = TempDir::new().unwrap();
+        let plugin_path = temp_dir.path().join("no_reads.ts");
+        std::fs::write(
+            &plugin_path,
+            r#"
+            const diff = editor.getBufferSavedDiff(1);
+            const props = editor.getTextPropertiesAtCursor(1);
+            editor.setStatus(`reads: ${diff} ${props.length}`);
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("no_reads.manifest.json"),
+            r#"{ "capabilities": [] }"#,
+        )
+        .unwrap();
+
+        manager.load_plugin(&plugin_path).await.unwrap();
+        assert!(manager.process_commands().iter().any(
+            |cmd| matches!(cmd, PluginCommand::SetStatus { message } if message == "reads: null 0")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_buffer_line_refused_without_read_buffer() {
+        use tempfile::TempDir;
+
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let mut manager = TypeScriptPluginManager::new(hooks, commands).unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let plugin_path = temp_dir.path().join("no_lines.ts");
+        std::fs::write(
+            &plugin_path,
+            r#"
+            editor.getBufferLine(1, 0).then(
+                () => editor.setStatus("line: read"),
+                () => editor.setStatus("line: refused"),
+            );
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("no_lines.manifest.json"),
+            r#"{ "capabilities": [] }"#,
+        )
+        .unwrap();
+
+        manager.load_plugin(&plugin_path).await.unwrap();
+        let cmds = manager.process_commands();
+        assert!(!cmds
+            .iter()
+            .any(|cmd| matches!(cmd, PluginCommand::GetBufferLine { .. })));
+        assert!(cmds.iter().any(
+            |cmd| matches!(cmd, PluginCommand::SetStatus { message } if message == "line: refused")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_follow_plugin_across_await() {
+        use tempfile::TempDir;
+
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let mut manager = TypeScriptPluginManager::new(hooks, commands).unwrap();
+
+        // The plugin may spawn processes but not edit; the code after the
+        // await still runs as this plugin, so its edit is refused
+        let temp_dir = TempDir::new().unwrap();
+        let plugin_path = temp_dir.path().join("spawner.ts");
+        std::fs::write(
+            &plugin_path,
+            r#"
+            globalThis.spawnThenEdit = async function() {
+                const result = await editor.spawnProcess("echo", ["hi"]);
+                const ok = editor.insertText(0, 0, result.stdout);
+                editor.setStatus(`insert after await: ${ok}`);
+            };
+            editor.registerCommand("Spawn Then Edit", "", "spawnThenEdit", "normal");
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("spawner.manifest.json"),
+            r#"{ "capabilities": ["spawn_process"] }"#,
+        )
+        .unwrap();
+
+        manager.load_plugin(&plugin_path).await.unwrap();
+        manager.execute_action("spawnThenEdit").await.unwrap();
+
+        let cmds = manager.process_commands();
+        assert!(!cmds
+            .iter()
+            .any(|cmd| matches!(cmd, PluginCommand::InsertText { .. })));
+        assert!(cmds.iter().any(|cmd| matches!(
+            cmd,
+            PluginCommand::SetStatus { message } if message == "insert after await: false"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_reassigned_global_keeps_registered_function() {
+        use tempfile::TempDir;
+
+        let hooks = Arc::new(RwLock::new(HookRegistry::new()));
+        let commands = Arc::new(RwLock::new(CommandRegistry::new()));
+        let mut manager = TypeScriptPluginManager::new(hooks, commands).unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let writer_path = temp_dir.path().join("writer.ts");
+        std::fs::write(
+            &writer_path,
+            r#"
+            globalThis.writerEdit = function() {
+                editor.insertText(0, 0, "writer");
+            };
+            editor.registerCommand("Writer Edit", "", "writerEdit", "normal");
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("writer.manifest.json"),
+            r#"{ "capabilities": ["write_buffer"] }"#,
+        )
+        .unwrap();
+
+        // A plugin without capabilities replaces the writer's global and
+        // tries to take over its name
+        let sneaky_path = temp_dir.path().join("sneaky.ts");
+        std::fs::write(
+            &sneaky_path,
+            r#"
+            globalThis.writerEdit = function() {
+                editor.insertText(0, 0, "sneaky");
+            };
+            const stolen = editor.registerCommand("Steal", "", "writerEdit", "normal");
+            editor.setStatus(`stolen: ${stolen}`);
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("sneaky.manifest.json"),
+            r#"{ "capabilities": [] }"#,
+        )
+        .unwrap();
+
+        manager.load_plugin(&writer_path).await.unwrap();
+        manager.load_plugin(&sneaky_path).await.unwrap();
+        manager.execute_action("writerEdit").await.unwrap();
+
+        let cmds = manager.process_commands();
+        let inserted: Vec<&str> = cmds
+            .iter()
+            .filter_map(|cmd| match cmd {
+                PluginCommand::InsertText { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(inserted, vec!["writer"]);
+        assert!(cmds.iter().any(
+            |cmd| matches!(cmd, PluginCommand::SetStatus { message } if message == "stolen: false")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_code_outside_plugins_has_no_capabilities() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let state_snapshot = Arc::new(RwLock::new(EditorStateSnapshot::new()));
+        let mut runtime = TypeScriptRuntime::with_state(state_snapshot, tx).unwrap();
+
+        let result = runtime
+            .execute_script(
+                "<test_unowned>",
+                r#"
+                globalThis.unownedAction = function() {
+                    if (editor.insertText(0, 0, "x")) {
+                        throw new Error("insertText should be refused");
+                    }
+                    if (editor.fileExists("Cargo.toml")) {
+                        throw new Error("fileExists should be refused");
+                    }
+                };
+                "#,
+            )
+            .await;
+        assert!(result.is_ok(), "Setup failed: {:?}", result);
+
+        let result = runtime.execute_action("unownedAction").await;
+        assert!(result.is_ok(), "Unowned action failed: {:?}", result);
+        assert!(!rx
+            .try_iter()
+            .any(|cmd| matches!(cmd, PluginCommand::InsertText { .. })));
+    }
+
     #[test]
     fn test_plugin_thread_load_plugin_with_import() {
         use crate::services::plugins::thread::PluginThreadHandle;
@@ -5788,6 +6731,7 @@ mod tests {
                 editor.setStatus("Spawn completed: exit=" + result.exit_code);
                 editor.debug("stdout: " + result.stdout);
             };
+            editor.registerCommand("Test Spawn", "", "test_spawn", "normal");
 
             editor.setStatus("Spawn test plugin loaded");
             "#,
@@ -5872,6 +6816,7 @@ mod tests {
 
                 editor.setStatus("Git log completed: exit=" + result.exit_code + ", lines=" + result.stdout.split("\n").length);
             };
+            editor.registerCommand("Test Git", "", "test_git", "normal");
 
             editor.setStatus("Git test plugin loaded");
             "#,
@@ -57,6 +57,13 @@ pub enum PluginRequest {
     /// Run a hook (fire-and-forget, no response needed)
     RunHook { hook_name: String, args: HookArgs },
 
+    /// Run a hook in one plugin only (fire-and-forget)
+    RunPluginHook {
+        plugin: Option<String>,
+        hook_name: String,
+        args: HookArgs,
+    },
+
     /// Check if any handlers are registered for a hook
     HasHookHandlers {
         hook_name: String,
@@ -324,6 +331,15 @@ impl PluginThreadHandle {
         });
     }
 
+    /// Run a hook in one plugin only (non-blocking)
+    pub fn run_plugin_hook(&self, plugin: Option<&str>, hook_name: &str, args: HookArgs) {
+        let _ = self.request_sender.send(PluginRequest::RunPluginHook {
+            plugin: plugin.map(str::to_string),
+            hook_name: hook_name.to_string(),
+            args,
+        });
+    }
+
     /// Check if any handlers are registered for a hook (blocking)
     pub fn has_hook_handlers(&self, hook_name: &str) -> bool {
         let (tx, rx) = oneshot::channel();
@@ -402,7 +418,10 @@ fn respond_to_pending(
         crate::services::plugins::api::PluginResponse::VirtualBufferCreated {
             request_id, ..
         } => *request_id,
-        crate::services::plugins::api::PluginResponse::LspRequest { request_id, .. } => *request_id,
+        crate::services::plugins::api::PluginResponse::LspRequest { request_id, .. }
+        | crate::services::plugins::api::PluginResponse::BufferText { request_id, .. } => {
+            *request_id
+        }
     };
 
     let sender = {
@@ -565,6 +584,21 @@ async fn run_hook_internal_rc(
     Ok(())
 }
 
+/// Run a hook in one plugin only
+async fn run_plugin_hook_internal_rc(
+    runtime: Rc<RefCell<TypeScriptRuntime>>,
+    plugin: Option<&str>,
+    hook_name: &str,
+    args: &HookArgs,
+) -> Result<()> {
+    let json_data = hook_args_to_json(args)?;
+    runtime
+        .borrow_mut()
+        .emit_to(plugin, hook_name, &json_data)
+        .await?;
+    Ok(())
+}
+
 /// Handle a single request in the plugin thread
 async fn handle_request(
     request: PluginRequest,
@@ -619,6 +653,25 @@ async fn handle_request(
             }
         }
 
+        PluginRequest::RunPluginHook {
+            plugin,
+            hook_name,
+            args,
+        } => {
+            if let Err(e) = run_plugin_hook_internal_rc(
+                Rc::clone(&runtime),
+                plugin.as_deref(),
+                &hook_name,
+                &args,
+            )
+            .await
+            {
+                let error_msg = format!("Plugin error in '{}': {}", hook_name, e);
+                tracing::error!("{}", error_msg);
+                runtime.borrow_mut().send_status(error_msg);
+            }
+        }
+
         PluginRequest::HasHookHandlers {
             hook_name,
             response,
@@ -0,0 +1,105 @@
+//! One-shot timers scheduled by plugins
+//!
+//! A plugin calls `editor.schedule(afterMs, token)`; the editor queues the
+//! token here along with the plugin that scheduled it and, once the deadline
+//! has passed, runs that plugin's `timer` handlers with `{ token }` so it can
+//! tell its timers apart. Other plugins never see the timer. Deadlines are
+//! taken from the editor's `TimeSource`, so tests can drive them with a fake
+//! clock.
+
+use std::time::{Duration, Instant};
+
+/// A timer that has come due: the plugin that scheduled it and its token
+///
+/// `plugin` is None for timers scheduled by code no plugin owns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueTimer {
+    pub plugin: Option<String>,
+    pub token: u64,
+}
+
+/// Pending plugin timers, ordered by deadline
+#[derive(Debug, Default)]
+pub struct TimerQueue {
+    /// (deadline, timer), kept sorted by deadline; equal deadlines keep scheduling order
+    pending: Vec<(Instant, DueTimer)>,
+}
+
+impl TimerQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `plugin`'s `token` to fire once `after` has elapsed from `now`
+    pub fn schedule(&mut self, now: Instant, after: Duration, plugin: Option<String>, token: u64) {
+        let deadline = now + after;
+        let index = self.pending.partition_point(|(d, _)| *d <= deadline);
+        self.pending
+            .insert(index, (deadline, DueTimer { plugin, token }));
+    }
+
+    /// Remove and return all timers due at `now`, earliest first
+    pub fn take_due(&mut self, now: Instant) -> Vec<DueTimer> {
+        let due = self.pending.partition_point(|(d, _)| *d <= now);
+        self.pending.drain(..due).map(|(_, timer)| timer).collect()
+    }
+
+    /// Deadline of the next timer to fire, if any
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.first().map(|(d, _)| *d)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::time_source::{TestTimeSource, TimeSource};
+
+    fn tokens(timers: Vec<DueTimer>) -> Vec<u64> {
+        timers.into_iter().map(|timer| timer.token).collect()
+    }
+
+    #[test]
+    fn test_timer_fires_once_after_interval() {
+        let clock = TestTimeSource::new();
+        let mut timers = TimerQueue::new();
+        let plugin = Some("reminder".to_string());
+        timers.schedule(clock.now(), Duration::from_millis(100), plugin.clone(), 7);
+
+        clock.advance(Duration::from_millis(99));
+        assert!(timers.take_due(clock.now()).is_empty());
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(
+            timers.take_due(clock.now()),
+            vec![DueTimer { plugin, token: 7 }]
+        );
+
+        clock.advance(Duration::from_millis(500));
+        assert!(timers.take_due(clock.now()).is_empty());
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn test_timers_fire_in_deadline_order() {
+        let clock = TestTimeSource::new();
+        let mut timers = TimerQueue::new();
+        timers.schedule(clock.now(), Duration::from_millis(300), None, 3);
+        timers.schedule(clock.now(), Duration::from_millis(100), None, 1);
+        timers.schedule(clock.now(), Duration::from_millis(100), None, 2);
+        assert_eq!(
+            timers.next_deadline(),
+            Some(clock.now() + Duration::from_millis(100))
+        );
+
+        clock.advance(Duration::from_millis(200));
+        assert_eq!(tokens(timers.take_due(clock.now())), vec![1, 2]);
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(tokens(timers.take_due(clock.now())), vec![3]);
+    }
+}
@@ -0,0 +1,241 @@
+//! Search for text across the files of a directory tree
+//!
+//! [`ProjectSearch`] walks the tree with the `ignore` crate, so `.gitignore`,
+//! `.ignore` and hidden files are skipped the way `rg` would skip them, and
+//! runs each file through the buffer's chunked literal search. Results come
+//! from an iterator that only opens the next file once the current one's
+//! matches are used up, so a UI can show them while the search goes on.
+
+use crate::model::buffer::TextBuffer;
+use ignore::overrides::OverrideBuilder;
+use ignore::{Walk, WalkBuilder};
+use std::collections::VecDeque;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// One occurrence of the search text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectMatch {
+    pub path: PathBuf,
+    /// Line of the match (0-indexed)
+    pub line: usize,
+    /// Byte range of the match in the file, as loaded by the editor
+    /// (line endings normalized to `\n`)
+    pub range: Range<usize>,
+    /// The text of the line, without its line break
+    pub preview: String,
+}
+
+/// A literal text search over a directory tree
+#[derive(Debug, Clone)]
+pub struct ProjectSearch {
+    root: PathBuf,
+    pattern: String,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl ProjectSearch {
+    pub fn new(root: impl Into<PathBuf>, pattern: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            pattern: pattern.into(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Only search files matching `glob` (e.g. `*.rs`); with several include
+    /// globs a file must match one of them
+    pub fn include(mut self, glob: impl Into<String>) -> Self {
+        self.include.push(glob.into());
+        self
+    }
+
+    /// Skip files and directories matching `glob` (e.g. `target` or `*.min.js`)
+    pub fn exclude(mut self, glob: impl Into<String>) -> Self {
+        self.exclude.push(glob.into());
+        self
+    }
+
+    /// Start the search
+    ///
+    /// Fails if a glob is invalid. Files that can't be read and binary files
+    /// are skipped.
+    pub fn matches(&self) -> io::Result<ProjectMatches> {
+        let invalid = |e: ignore::Error| io::Error::new(io::ErrorKind::InvalidInput, e);
+        let mut overrides = OverrideBuilder::new(&self.root);
+        for glob in &self.include {
+            overrides.add(glob).map_err(invalid)?;
+        }
+        for glob in &self.exclude {
+            overrides.add(&format!("!{glob}")).map_err(invalid)?;
+        }
+        let walk = WalkBuilder::new(&self.root)
+            .overrides(overrides.build().map_err(invalid)?)
+            // Honour .gitignore files outside of git repositories too
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        Ok(ProjectMatches {
+            walk,
+            pattern: self.pattern.clone(),
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+/// Iterator over the matches of a [`ProjectSearch`], file by file in path order
+pub struct ProjectMatches {
+    walk: Walk,
+    pattern: String,
+    /// Matches of the current file not yet yielded
+    pending: VecDeque<ProjectMatch>,
+}
+
+impl ProjectMatches {
+    fn search_file(&mut self, path: &Path) {
+        let buffer = match TextBuffer::load_from_file(path, 0) {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                tracing::debug!("Project search skipping {:?}: {}", path, e);
+                return;
+            }
+        };
+        if buffer.is_binary() {
+            return;
+        }
+
+        let len = buffer.len();
+        let mut pos = 0;
+        while let Some(start) = buffer.find_next_in_range(&self.pattern, pos, Some(0..len)) {
+            let line = buffer.get_line_number(start);
+            let preview = buffer
+                .get_line(line)
+                .map(|text| {
+                    String::from_utf8_lossy(&text)
+                        .trim_end_matches(['\n', '\r'])
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let end = start + self.pattern.len();
+            self.pending.push_back(ProjectMatch {
+                path: path.to_path_buf(),
+                line,
+                range: start..end,
+                preview,
+            });
+            pos = end;
+        }
+    }
+}
+
+impl Iterator for ProjectMatches {
+    type Item = ProjectMatch;
+
+    fn next(&mut self) -> Option<ProjectMatch> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        loop {
+            if let Some(found) = self.pending.pop_front() {
+                return Some(found);
+            }
+            let entry = match self.walk.next()? {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::debug!("Project search walk error: {}", e);
+                    continue;
+                }
+            };
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                self.search_file(entry.path());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("README.md"), "Call todo() here\n").unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "fn a() {}\n// todo: b\nfn todo() { todo }\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/nested/none.rs"), "nothing to see\n").unwrap();
+        fs::write(root.join("src/nested/deep.txt"), "todo").unwrap();
+        fs::write(root.join("target/build.rs"), "todo\n").unwrap();
+        dir
+    }
+
+    fn found(search: ProjectSearch, root: &Path) -> Vec<(String, usize, Range<usize>)> {
+        search
+            .matches()
+            .unwrap()
+            .map(|m| {
+                let path = m.path.strip_prefix(root).unwrap();
+                (path.to_string_lossy().replace('\\', "/"), m.line, m.range)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_project_search_skips_ignored_files() {
+        let dir = project();
+        let root = dir.path();
+        let mut matches = ProjectSearch::new(root, "todo").matches().unwrap();
+        let first = matches.next().unwrap();
+        assert_eq!(first.path, root.join("README.md"));
+        assert_eq!(first.preview, "Call todo() here");
+
+        assert_eq!(
+            found(ProjectSearch::new(root, "todo"), root),
+            vec![
+                ("README.md".to_string(), 0, 5..9),
+                ("src/lib.rs".to_string(), 1, 13..17),
+                ("src/lib.rs".to_string(), 2, 24..28),
+                ("src/lib.rs".to_string(), 2, 33..37),
+                ("src/nested/deep.txt".to_string(), 0, 0..4),
+            ]
+        );
+        assert!(ProjectSearch::new(root, "absent")
+            .matches()
+            .unwrap()
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_project_search_glob_filters() {
+        let dir = project();
+        let root = dir.path();
+        let rust_only = found(ProjectSearch::new(root, "todo").include("*.rs"), root);
+        assert_eq!(rust_only.len(), 3);
+        assert!(rust_only.iter().all(|(path, ..)| path == "src/lib.rs"));
+
+        let no_nested = found(ProjectSearch::new(root, "todo").exclude("nested"), root);
+        assert_eq!(
+            no_nested
+                .iter()
+                .map(|(path, ..)| path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["README.md", "src/lib.rs", "src/lib.rs", "src/lib.rs"]
+        );
+
+        assert!(ProjectSearch::new(root, "todo")
+            .include("[unclosed")
+            .matches()
+            .is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! Vim-style named registers
+//!
+//! Each register holds an independent clip under a single-character name.
+//! Yanking into an uppercase letter appends to the register of the lowercase
+//! letter instead of replacing it, and every yank also lands in the unnamed
+//! register, which is what a plain paste reads.
+
+use crate::model::event::Event;
+use crate::state::EditorState;
+use std::collections::HashMap;
+
+/// Register that plain yanks go to and plain pastes read from
+pub const UNNAMED_REGISTER: char = '"';
+
+/// Named clips, keyed by register name
+#[derive(Debug, Clone, Default)]
+pub struct Registers {
+    registers: HashMap<char, Vec<u8>>,
+}
+
+impl Registers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `text` in register `reg`, and in the unnamed register
+    ///
+    /// An uppercase letter appends to the register of its lowercase letter.
+    pub fn yank_to(&mut self, reg: char, text: &[u8]) {
+        let content = if reg.is_ascii_uppercase() {
+            let register = self.registers.entry(reg.to_ascii_lowercase()).or_default();
+            register.extend_from_slice(text);
+            register.clone()
+        } else {
+            self.registers.insert(reg, text.to_vec());
+            text.to_vec()
+        };
+        self.registers.insert(UNNAMED_REGISTER, content);
+    }
+
+    /// Store `text` in the unnamed register
+    pub fn yank(&mut self, text: &[u8]) {
+        self.yank_to(UNNAMED_REGISTER, text);
+    }
+
+    /// Content of register `reg` (uppercase letters read their lowercase register)
+    pub fn get(&self, reg: char) -> Option<&[u8]> {
+        self.registers
+            .get(&reg.to_ascii_lowercase())
+            .map(Vec::as_slice)
+    }
+
+    /// Build the event inserting register `reg` at the primary cursor
+    ///
+    /// Returns None if the register is empty.
+    pub fn paste_from(&self, reg: char, state: &EditorState) -> Option<Event> {
+        let text = self.get(reg).filter(|text| !text.is_empty())?;
+        Some(Event::Insert {
+            position: state.cursors.primary().position,
+            text: String::from_utf8_lossy(text).into_owned(),
+            cursor_id: state.cursors.primary_id(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paste(registers: &Registers, reg: char, state: &mut EditorState) {
+        let event = registers.paste_from(reg, state).unwrap();
+        state.apply(&event);
+    }
+
+    #[test]
+    fn test_two_registers_paste_independently() {
+        let mut registers = Registers::new();
+        registers.yank_to('a', b"alpha");
+        registers.yank_to('b', b"beta");
+
+        let mut state = EditorState::from_str_test("[]");
+        state.cursors.primary_mut().position = 1;
+        paste(&registers, 'a', &mut state);
+        paste(&registers, 'b', &mut state);
+        paste(&registers, 'a', &mut state);
+        assert_eq!(state.buffer.to_string().unwrap(), "[alphabetaalpha]");
+
+        // The unnamed register holds the last yank; plain yanks only go there
+        assert_eq!(registers.get(UNNAMED_REGISTER), Some(&b"beta"[..]));
+        registers.yank(b"plain");
+        assert_eq!(registers.get(UNNAMED_REGISTER), Some(&b"plain"[..]));
+        assert_eq!(registers.get('a'), Some(&b"alpha"[..]));
+        assert!(registers.paste_from('z', &state).is_none());
+    }
+
+    #[test]
+    fn test_uppercase_register_appends() {
+        let mut registers = Registers::new();
+        registers.yank_to('a', b"one");
+        registers.yank_to('A', b" two");
+        assert_eq!(registers.get('a'), Some(&b"one two"[..]));
+        assert_eq!(registers.get('A'), Some(&b"one two"[..]));
+        assert_eq!(registers.get(UNNAMED_REGISTER), Some(&b"one two"[..]));
+
+        // Appending to an empty register starts it
+        registers.yank_to('Q', b"q");
+        assert_eq!(registers.get('q'), Some(&b"q"[..]));
+
+        // A lowercase yank replaces again
+        registers.yank_to('a', b"three");
+        let mut state = EditorState::from_str_test("");
+        paste(&registers, 'A', &mut state);
+        assert_eq!(state.buffer.to_string().unwrap(), "three");
+    }
+}
@@ -7,6 +7,10 @@
 //! - Search/replace history and options
 //! - Bookmarks
 //!
+//! Separately, [`SessionStore`] remembers the cursor, marks and scroll position
+//! of each file by absolute path, so reopening a file in any project puts the
+//! view back where it was left.
+//!
 //! ## Storage
 //!
 //! Sessions are stored in `$XDG_DATA_HOME/fresh/sessions/{encoded_path}.json`
@@ -30,6 +34,8 @@ use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::input::input_history::get_data_dir;
+use crate::state::EditorState;
+use crate::view::viewport::Viewport;
 
 /// Current session file format version
 pub const SESSION_VERSION: u32 = 1;
@@ -580,6 +586,187 @@ impl Session {
     }
 }
 
+// ============================================================================
+// Per-file navigation state
+// ============================================================================
+
+/// Most files remembered by a [`SessionStore`]; the least recently saved are
+/// forgotten first
+pub const MAX_FILE_SESSIONS: usize = 1000;
+
+/// Navigation state remembered for a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSession {
+    /// Primary cursor
+    pub cursor: SerializedCursor,
+
+    /// Named marks (byte offsets)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub marks: HashMap<char, usize>,
+
+    /// Scroll position, if the file was showing in a split
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scroll: Option<SerializedScroll>,
+
+    /// When this entry was saved (Unix epoch seconds)
+    #[serde(default)]
+    pub saved_at: u64,
+}
+
+/// Cursor, marks and scroll position of each file, keyed by absolute path
+///
+/// Stored in `$XDG_DATA_HOME/fresh/sessions/files.json`. Offsets are saved as
+/// they were; the file may have changed by the time it is reopened, so
+/// [`SessionStore::restore_session`] clamps them to the current contents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionStore {
+    #[serde(default)]
+    files: HashMap<PathBuf, FileSession>,
+}
+
+/// Get the path of the per-file session store
+pub fn get_file_sessions_path() -> io::Result<PathBuf> {
+    Ok(get_sessions_dir()?.join("files.json"))
+}
+
+impl SessionStore {
+    /// Load the store from its default location
+    pub fn load() -> Result<Self, SessionError> {
+        Self::load_from(&get_file_sessions_path()?)
+    }
+
+    /// Load the store from `path`; a missing file gives an empty store
+    pub fn load_from(path: &Path) -> Result<Self, SessionError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save the store to its default location
+    pub fn save(&self) -> Result<(), SessionError> {
+        self.save_to(&get_file_sessions_path()?)
+    }
+
+    /// Save the store to `path` using atomic write (temp file + rename)
+    pub fn save_to(&self, path: &Path) -> Result<(), SessionError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        let temp_path = path.with_extension("json.tmp");
+        {
+            let mut file = std::fs::File::create(&temp_path)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Remembered state for `path`, if any
+    pub fn get(&self, path: &Path) -> Option<&FileSession> {
+        self.files.get(path)
+    }
+
+    /// Remember the navigation state of the file at `path`
+    ///
+    /// `viewport` is the view the file is showing in, if any; without one the
+    /// previously remembered scroll position is kept.
+    pub fn save_session(&mut self, path: &Path, state: &EditorState, viewport: Option<&Viewport>) {
+        let cursor = state.cursors.primary();
+        let scroll = match viewport {
+            Some(viewport) => Some(SerializedScroll {
+                top_byte: viewport.top_byte,
+                top_view_line_offset: viewport.top_view_line_offset,
+                left_column: viewport.left_column,
+            }),
+            None => self.files.get(path).and_then(|entry| entry.scroll.clone()),
+        };
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.files.insert(
+            path.to_path_buf(),
+            FileSession {
+                cursor: SerializedCursor {
+                    position: cursor.position,
+                    anchor: cursor.anchor,
+                    sticky_column: cursor.sticky_column,
+                },
+                marks: state.marks(),
+                scroll,
+                saved_at,
+            },
+        );
+
+        if self.files.len() > MAX_FILE_SESSIONS {
+            let oldest = self
+                .files
+                .iter()
+                .min_by_key(|(_, entry)| entry.saved_at)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                self.files.remove(&oldest);
+            }
+        }
+    }
+
+    /// Put the remembered navigation state of `path` back into `state` and
+    /// `viewport`
+    ///
+    /// Every offset is clamped to the buffer and snapped to a character
+    /// boundary, and the scroll position to the start of its line, in case the
+    /// file changed since the state was saved. Returns false if nothing was
+    /// remembered for `path`.
+    pub fn restore_session(
+        &self,
+        path: &Path,
+        state: &mut EditorState,
+        viewport: Option<&mut Viewport>,
+    ) -> bool {
+        let Some(entry) = self.files.get(path) else {
+            return false;
+        };
+        let buffer = &state.buffer;
+        let clamp = |pos: usize| buffer.snap_to_char_boundary(pos.min(buffer.len()));
+
+        let position = clamp(entry.cursor.position);
+        let anchor = entry
+            .cursor
+            .anchor
+            .map(clamp)
+            .filter(|&anchor| anchor != position);
+        let marks: Vec<(char, usize)> = entry
+            .marks
+            .iter()
+            .map(|(&name, &pos)| (name, clamp(pos)))
+            .collect();
+
+        if let (Some(viewport), Some(scroll)) = (viewport, &entry.scroll) {
+            let top_byte = clamp(scroll.top_byte);
+            viewport.top_byte = buffer
+                .offset_to_position(top_byte)
+                .map_or(top_byte, |pos| top_byte - pos.column);
+            viewport.top_view_line_offset = scroll.top_view_line_offset;
+            viewport.left_column = scroll.left_column;
+            viewport.set_skip_resize_sync();
+        }
+
+        let cursor = state.cursors.primary_mut();
+        cursor.position = position;
+        cursor.anchor = anchor;
+        cursor.sticky_column = entry.cursor.sticky_column;
+        state.clear_marks();
+        for (name, pos) in marks {
+            state.set_mark_at(name, pos);
+        }
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -941,4 +1128,88 @@ mod tests {
         assert_eq!(restored.expanded_dirs.len(), 3);
         assert_eq!(restored.scroll_offset, 5);
     }
+
+    #[test]
+    fn test_file_session_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("files.json");
+        let file = PathBuf::from("/project/src/main.rs");
+
+        let mut state = EditorState::from_str_test("fn main() {\n    println!(\"hi\");\n}\n");
+        state.cursors.primary_mut().position = 20;
+        state.cursors.primary_mut().anchor = Some(16);
+        state.cursors.primary_mut().sticky_column = 8;
+        state.set_mark_at('a', 12);
+        let mut viewport = Viewport::new(80, 24);
+        viewport.top_byte = 12;
+        viewport.left_column = 3;
+
+        let mut store = SessionStore::default();
+        store.save_session(&file, &state, Some(&viewport));
+        store.save_to(&store_path).unwrap();
+
+        let loaded = SessionStore::load_from(&store_path).unwrap();
+        let mut reopened = EditorState::from_str_test("fn main() {\n    println!(\"hi\");\n}\n");
+        let mut new_viewport = Viewport::new(80, 24);
+        assert!(loaded.restore_session(&file, &mut reopened, Some(&mut new_viewport)));
+
+        let cursor = reopened.cursors.primary();
+        assert_eq!(cursor.position, 20);
+        assert_eq!(cursor.anchor, Some(16));
+        assert_eq!(cursor.sticky_column, 8);
+        assert_eq!(reopened.mark('a'), Some(12));
+        assert_eq!(new_viewport.top_byte, 12);
+        assert_eq!(new_viewport.left_column, 3);
+
+        // Unknown files and a missing store are left alone
+        assert!(!loaded.restore_session(Path::new("/other"), &mut reopened, None));
+        let missing = SessionStore::load_from(&temp_dir.path().join("none.json")).unwrap();
+        assert!(missing.get(&file).is_none());
+    }
+
+    #[test]
+    fn test_file_session_clamped_when_file_shrank() {
+        let file = PathBuf::from("/project/notes.txt");
+        let mut state = EditorState::from_str_test("first line\nsecond line\nthird line\n");
+        state.cursors.primary_mut().position = 30;
+        state.cursors.primary_mut().anchor = Some(25);
+        state.set_mark_at('m', 28);
+        state.set_mark_at('n', 8);
+        let mut viewport = Viewport::new(80, 24);
+        viewport.top_byte = 23;
+
+        let mut store = SessionStore::default();
+        store.save_session(&file, &state, Some(&viewport));
+
+        // The file was cut short, and byte 8 now falls inside "é"
+        let mut reopened = EditorState::from_str_test("first\nsé");
+        let mut new_viewport = Viewport::new(80, 24);
+        assert!(store.restore_session(&file, &mut reopened, Some(&mut new_viewport)));
+
+        let len = reopened.buffer.len();
+        let cursor = reopened.cursors.primary();
+        assert_eq!(cursor.position, len);
+        // The anchor collapsed onto the cursor, so there is no selection left
+        assert_eq!(cursor.anchor, None);
+        assert_eq!(reopened.mark('m'), Some(len));
+        assert_eq!(reopened.mark('n'), Some(7));
+        // Scroll snaps back to the start of the last line
+        assert_eq!(new_viewport.top_byte, 6);
+    }
+
+    #[test]
+    fn test_file_session_keeps_scroll_without_viewport() {
+        let file = PathBuf::from("/project/a.rs");
+        let state = EditorState::from_str_test("a\nb\nc\n");
+        let mut viewport = Viewport::new(80, 24);
+        viewport.top_byte = 2;
+
+        let mut store = SessionStore::default();
+        store.save_session(&file, &state, Some(&viewport));
+        store.save_session(&file, &state, None);
+        assert_eq!(
+            store.get(&file).unwrap().scroll.as_ref().unwrap().top_byte,
+            2
+        );
+    }
 }
@@ -1,26 +1,42 @@
+use crate::config::WhitespaceMode;
+use crate::model::auto_save::AutoSave;
 use crate::model::buffer::{Buffer, LineNumber};
-use crate::model::cursor::{Cursor, Cursors};
+use crate::model::cursor::{Cursor, CursorShape, Cursors};
+use crate::model::diagnostic::{self, Diagnostic};
 use crate::model::document_model::{
     DocumentCapabilities, DocumentModel, DocumentPosition, ViewportContent, ViewportLine,
 };
 use crate::model::event::{
-    Event, MarginContentData, MarginPositionData, OverlayFace as EventOverlayFace, PopupData,
-    PopupPositionData,
+    CursorId, Event, MarginContentData, MarginPositionData, OverlayFace as EventOverlayFace,
+    PopupData, PopupPositionData,
 };
-use crate::model::marker::MarkerList;
+use crate::model::marker::{MarkerId, MarkerList};
+use crate::primitives::bracket_match::{self, LexicalSyntax};
+use crate::primitives::case_transform::CaseTransform;
 use crate::primitives::grammar_registry::GrammarRegistry;
 use crate::primitives::highlight_engine::HighlightEngine;
 use crate::primitives::highlighter::Language;
-use crate::primitives::indent::IndentCalculator;
+use crate::primitives::indent::{IndentCalculator, IndentStyle};
+use crate::primitives::line_sort::SortOptions;
 use crate::primitives::semantic_highlight::SemanticHighlighter;
+use crate::primitives::snippet::{Snippet, SnippetEngine};
+use crate::primitives::spell_check::{self, SpellChecker};
 use crate::primitives::text_property::TextPropertyManager;
+use crate::primitives::text_stats::{DocStats, TextStats, TextStatsCounter};
+use crate::primitives::word_navigation::WordDef;
 use crate::view::margin::{MarginAnnotation, MarginContent, MarginManager, MarginPosition};
 use crate::view::overlay::{Overlay, OverlayFace, OverlayManager, UnderlineStyle};
 use crate::view::popup::{Popup, PopupContent, PopupListItem, PopupManager, PopupPosition};
+use crate::view::viewport::Viewport;
 use crate::view::virtual_text::VirtualTextManager;
 use anyhow::Result;
 use ratatui::style::{Color, Style};
-use std::cell::RefCell;
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::ops::{ControlFlow, Range};
+
+/// How many lines from the top of a buffer `EditorState::detect_indentation` looks at
+pub const INDENT_SAMPLE_LINES: usize = 1000;
 
 /// Display mode for a buffer
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -31,6 +47,17 @@ pub enum ViewMode {
     Compose,
 }
 
+/// Kind of whitespace marker reported by `EditorState::whitespace_markers`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsMarker {
+    /// A tab character, drawn as "→"
+    Tab,
+    /// A space followed only by whitespace up to the end of its line, drawn as "·"
+    TrailingSpace,
+    /// Any other space, drawn as "·" when all spaces are shown
+    Space,
+}
+
 /// The complete editor state - everything needed to represent the current editing session
 ///
 /// NOTE: Viewport is NOT stored here - it lives in SplitViewState.
@@ -49,10 +76,16 @@ pub struct EditorState {
     /// Auto-indent calculator for smart indentation (RefCell for interior mutability)
     pub indent_calculator: RefCell<IndentCalculator>,
 
+    /// Indentation detected from the buffer's lines (None when they don't make
+    /// it clear), worked out on first use and cleared by every edit
+    detected_indent: OnceCell<Option<IndentStyle>>,
+
     /// Overlays for visual decorations (underlines, highlights, etc.)
     pub overlays: OverlayManager,
 
-    /// Marker list for content-anchored overlay positions
+    /// The document's interval tracker: overlays, virtual text, margin line
+    /// indicators, named marks and diagnostics all register their positions
+    /// here, so each edit adjusts them in one pass
     pub marker_list: MarkerList,
 
     /// Virtual text manager for inline hints (type hints, parameter hints, etc.)
@@ -64,6 +97,9 @@ pub struct EditorState {
     /// Margins for line numbers, annotations, gutter symbols, etc.)
     pub margins: MarginManager,
 
+    /// Named marks, anchored in `marker_list` and dropped when their line is deleted
+    marks: HashMap<char, MarkerId>,
+
     /// Cached line number for primary cursor (0-indexed)
     /// Maintained incrementally to avoid O(n) scanning on every render
     pub primary_cursor_line_number: LineNumber,
@@ -84,6 +120,10 @@ pub struct EditorState {
     /// but navigation, selection, and copy are still allowed
     pub editing_disabled: bool,
 
+    /// Overtype mode (toggled with Insert): typed text replaces the
+    /// characters under the cursors instead of being inserted before them
+    pub overwrite: bool,
+
     /// Semantic highlighter for word occurrence highlighting
     pub semantic_highlighter: SemanticHighlighter,
 
@@ -101,6 +141,15 @@ pub struct EditorState {
 
     /// Optional transformed view payload for current viewport (tokens + map)
     pub view_transform: Option<crate::services::plugins::api::ViewTransformPayload>,
+
+    /// Debounce state for saving the buffer once editing goes idle
+    pub auto_save: AutoSave,
+
+    /// Active snippet session, whose tab stops Tab and Shift+Tab move between
+    pub snippet: Option<SnippetEngine>,
+
+    /// Characters word motion and word selection treat as part of a word
+    pub word_def: WordDef,
 }
 
 impl EditorState {
@@ -114,25 +163,48 @@ impl EditorState {
             cursors: Cursors::new(),
             highlighter: HighlightEngine::None, // No file path, so no syntax highlighting
             indent_calculator: RefCell::new(IndentCalculator::new()),
+            detected_indent: OnceCell::new(),
             overlays: OverlayManager::new(),
             marker_list: MarkerList::new(),
             virtual_texts: VirtualTextManager::new(),
             popups: PopupManager::new(),
             margins: MarginManager::new(),
+            marks: HashMap::new(),
             primary_cursor_line_number: LineNumber::Absolute(0), // Start at line 0
             mode: "insert".to_string(),
             text_properties: TextPropertyManager::new(),
             show_cursors: true,
             editing_disabled: false,
+            overwrite: false,
             semantic_highlighter: SemanticHighlighter::new(),
             view_mode: ViewMode::Source,
             compose_width: None,
             compose_prev_line_numbers: None,
             compose_column_guides: None,
             view_transform: None,
+            auto_save: AutoSave::default(),
+            snippet: None,
+            word_def: WordDef::default(),
         }
     }
 
+    /// Create an editor state holding `text` for testing
+    #[cfg(test)]
+    pub fn from_str_test(text: &str) -> Self {
+        let mut state = Self::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        state.buffer = Buffer::from_str_test(text);
+        state
+    }
+
+    /// Whether the buffer was opened in large-file mode (larger than
+    /// `large_file_threshold_bytes`)
+    ///
+    /// Its content is loaded on demand and its lines aren't indexed up front,
+    /// and syntax highlighting and line wrapping are off.
+    pub fn is_large(&self) -> bool {
+        self.buffer.is_large_file()
+    }
+
     /// Set the syntax highlighting language based on a filename or extension
     /// This allows virtual buffers to get highlighting even without a real file path
     pub fn set_language_from_name(&mut self, name: &str, registry: &GrammarRegistry) {
@@ -161,8 +233,13 @@ impl EditorState {
     ) -> std::io::Result<Self> {
         let buffer = Buffer::load_from_file(path, large_file_threshold)?;
 
-        // Create highlighter using HighlightEngine (tree-sitter preferred, TextMate fallback)
-        let highlighter = HighlightEngine::for_file(path, registry);
+        // Create highlighter using HighlightEngine (tree-sitter preferred, TextMate fallback).
+        // Large files aren't highlighted: parsing would read far beyond the viewport.
+        let highlighter = if buffer.is_large_file() {
+            HighlightEngine::None
+        } else {
+            HighlightEngine::for_file(path, registry)
+        };
         tracing::debug!(
             "Created highlighter for {:?} (backend: {})",
             path,
@@ -170,7 +247,7 @@ impl EditorState {
         );
 
         // Initialize semantic highlighter with language if available
-        let language = Language::from_path(path);
+        let language = Language::from_path(path).filter(|_| !buffer.is_large_file());
         let mut semantic_highlighter = SemanticHighlighter::new();
         if let Some(lang) = language {
             semantic_highlighter.set_language(&lang);
@@ -191,22 +268,28 @@ impl EditorState {
             cursors: Cursors::new(),
             highlighter,
             indent_calculator: RefCell::new(IndentCalculator::new()),
+            detected_indent: OnceCell::new(),
             overlays: OverlayManager::new(),
             marker_list,
             virtual_texts: VirtualTextManager::new(),
             popups: PopupManager::new(),
             margins: MarginManager::new(),
+            marks: HashMap::new(),
             primary_cursor_line_number: LineNumber::Absolute(0), // Start at line 0
             mode: "insert".to_string(),
             text_properties: TextPropertyManager::new(),
             show_cursors: true,
             editing_disabled: false,
+            overwrite: false,
             semantic_highlighter,
             view_mode: ViewMode::Source,
             compose_width: None,
             compose_prev_line_numbers: None,
             compose_column_guides: None,
             view_transform: None,
+            auto_save: AutoSave::default(),
+            snippet: None,
+            word_def: WordDef::default(),
         })
     }
 
@@ -221,7 +304,7 @@ impl EditorState {
 
         // CRITICAL: Adjust markers BEFORE modifying buffer
         self.marker_list.adjust_for_insert(position, text.len());
-        self.margins.adjust_for_insert(position, text.len());
+        self.forget_indentation();
 
         // Insert text into buffer
         self.buffer.insert(position, text);
@@ -265,8 +348,9 @@ impl EditorState {
         let newlines_deleted = deleted_text.matches('\n').count();
 
         // CRITICAL: Adjust markers BEFORE modifying buffer
+        self.drop_marks_in_deleted_lines(range, deleted_text);
         self.marker_list.adjust_for_delete(range.start, len);
-        self.margins.adjust_for_delete(range.start, len);
+        self.forget_indentation();
 
         // Delete from buffer
         self.buffer.delete(range.clone());
@@ -308,13 +392,33 @@ impl EditorState {
                 position,
                 text,
                 cursor_id,
-            } => self.apply_insert(*position, text, *cursor_id),
+            } => {
+                self.apply_insert(*position, text, *cursor_id);
+                self.auto_save.mark_edited();
+                if self
+                    .snippet
+                    .as_mut()
+                    .is_some_and(|snippet| !snippet.adjust_for_insert(*position, text.len()))
+                {
+                    self.snippet = None;
+                }
+            }
 
             Event::Delete {
                 range,
                 cursor_id,
                 deleted_text,
-            } => self.apply_delete(range, *cursor_id, deleted_text),
+            } => {
+                self.apply_delete(range, *cursor_id, deleted_text);
+                self.auto_save.mark_edited();
+                if self
+                    .snippet
+                    .as_mut()
+                    .is_some_and(|snippet| !snippet.adjust_for_delete(range))
+                {
+                    self.snippet = None;
+                }
+            }
 
             Event::MoveCursor {
                 cursor_id,
@@ -552,6 +656,96 @@ impl EditorState {
         }
     }
 
+    /// Remove the marks whose whole line a deletion is about to remove
+    ///
+    /// The marker list collapses the other marks inside the deletion to its
+    /// start. Must run before the buffer is modified.
+    fn drop_marks_in_deleted_lines(&mut self, range: &std::ops::Range<usize>, deleted_text: &str) {
+        if self.marks.is_empty() {
+            return;
+        }
+
+        let deleted = deleted_text.as_bytes();
+        let starts_at_line_start =
+            range.start == 0 || self.buffer.slice_bytes(range.start - 1..range.start) == b"\n";
+        let ends_at_eof = range.end >= self.buffer.len();
+
+        let marker_list = &mut self.marker_list;
+        self.marks.retain(|_, &mut id| {
+            let Some(mark) = marker_list.get_position(id) else {
+                return false;
+            };
+            if mark < range.start || mark >= range.end {
+                return true;
+            }
+            let (before, after) = deleted.split_at((mark - range.start).min(deleted.len()));
+            let line_start_deleted = starts_at_line_start || before.contains(&b'\n');
+            let line_end_deleted = ends_at_eof || after.contains(&b'\n');
+            if line_start_deleted && line_end_deleted {
+                marker_list.delete(id);
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Set mark `c` at the primary cursor, replacing any mark with that name
+    pub fn set_mark(&mut self, c: char) {
+        self.set_mark_at(c, self.cursors.primary().position);
+    }
+
+    /// Set mark `c` at `position`, replacing any mark with that name
+    pub fn set_mark_at(&mut self, c: char, position: usize) {
+        let id = self.marker_list.create(position, true);
+        if let Some(old) = self.marks.insert(c, id) {
+            self.marker_list.delete(old);
+        }
+    }
+
+    /// Current position of mark `c`, if it's set and its line wasn't deleted
+    pub fn mark(&self, c: char) -> Option<usize> {
+        self.marker_list.get_position(*self.marks.get(&c)?)
+    }
+
+    /// Every mark and its current position
+    pub fn marks(&self) -> HashMap<char, usize> {
+        self.marks
+            .iter()
+            .filter_map(|(&c, &id)| Some((c, self.marker_list.get_position(id)?)))
+            .collect()
+    }
+
+    /// Remove mark `c`
+    pub fn remove_mark(&mut self, c: char) {
+        if let Some(id) = self.marks.remove(&c) {
+            self.marker_list.delete(id);
+        }
+    }
+
+    /// Remove every mark
+    pub fn clear_marks(&mut self) {
+        for (_, id) in self.marks.drain() {
+            self.marker_list.delete(id);
+        }
+    }
+
+    /// Build the event that moves the primary cursor to mark `c`
+    ///
+    /// Returns None if the mark was never set or its line has been deleted.
+    pub fn goto_mark(&self, c: char) -> Option<Event> {
+        let position = self.mark(c)?.min(self.buffer.len());
+        let cursor = self.cursors.primary();
+        Some(Event::MoveCursor {
+            cursor_id: self.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: position,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        })
+    }
+
     /// Apply multiple events in sequence
     pub fn apply_many(&mut self, events: &[Event]) {
         for event in events {
@@ -569,6 +763,24 @@ impl EditorState {
         self.cursors.primary_mut()
     }
 
+    /// Attach a diagnostic to a byte range of the buffer
+    pub fn add_diagnostic(&mut self, diagnostic: Diagnostic) {
+        diagnostic::add(&mut self.marker_list, diagnostic);
+    }
+
+    /// Remove all diagnostics from the buffer
+    pub fn clear_diagnostics(&mut self) {
+        diagnostic::clear(&mut self.marker_list);
+    }
+
+    /// Diagnostics overlapping the given byte range (typically the visible viewport)
+    pub fn diagnostics_in(
+        &self,
+        viewport: std::ops::Range<usize>,
+    ) -> impl Iterator<Item = Diagnostic> + '_ {
+        diagnostic::in_range(&self.marker_list, viewport)
+    }
+
     /// Called when this buffer loses focus (e.g., switching to another buffer,
     /// opening a prompt, focusing file explorer, etc.)
     /// Dismisses transient popups like Hover and Signature Help.
@@ -802,759 +1014,3447 @@ impl EditorState {
             Ok(String::new())
         }
     }
-}
 
-/// Implement DocumentModel trait for EditorState
-///
-/// This provides a clean abstraction layer between rendering/editing operations
-/// and the underlying text buffer implementation.
-impl DocumentModel for EditorState {
-    fn capabilities(&self) -> DocumentCapabilities {
-        let line_count = self.buffer.line_count();
-        DocumentCapabilities {
-            has_line_index: line_count.is_some(),
-            uses_lazy_loading: false, // TODO: add large file detection
-            byte_length: self.buffer.len(),
-            approximate_line_count: line_count.unwrap_or_else(|| {
-                // Estimate assuming ~80 bytes per line
-                self.buffer.len() / 80
-            }),
+    /// Collect whitespace markers for the source lines visible in `viewport`
+    ///
+    /// Returns `(byte offset, marker)` pairs in document order. Which characters
+    /// are reported depends on `mode`. Lines are located through the line index,
+    /// so trailing spaces are found by scanning back from each line's end; buffers
+    /// without a line index (large file mode) report no markers.
+    pub fn whitespace_markers(
+        &self,
+        viewport: &Viewport,
+        mode: WhitespaceMode,
+    ) -> Vec<(usize, WsMarker)> {
+        let mut markers = Vec::new();
+        if mode == WhitespaceMode::None {
+            return markers;
         }
-    }
 
-    fn get_viewport_content(
-        &mut self,
-        start_pos: DocumentPosition,
-        max_lines: usize,
-    ) -> Result<ViewportContent> {
-        // Convert to byte offset
-        let start_offset = self.position_to_offset(start_pos)?;
+        let first_line = self.buffer.get_line_number(viewport.top_byte);
+        for line in first_line..first_line + viewport.visible_line_count() {
+            let (Some(line_start), Some(text)) = (
+                self.buffer.line_start_offset(line),
+                self.buffer.get_line(line),
+            ) else {
+                break;
+            };
 
-        // Use new efficient line iteration that tracks line numbers during iteration
-        // by accumulating line_feed_cnt from pieces (single source of truth)
-        let line_iter = self.buffer.iter_lines_from(start_offset, max_lines)?;
-        let has_more = line_iter.has_more;
+            // Ignore the line terminator, then find where the trailing run of
+            // whitespace begins
+            let content = text.strip_suffix(b"\n").unwrap_or(&text);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+            let trailing_start = content
+                .iter()
+                .rposition(|&b| b != b' ' && b != b'\t')
+                .map_or(0, |i| i + 1);
+
+            for (i, &b) in content.iter().enumerate() {
+                let marker = match b {
+                    b'\t' => WsMarker::Tab,
+                    b' ' if i >= trailing_start && mode != WhitespaceMode::Tabs => {
+                        WsMarker::TrailingSpace
+                    }
+                    b' ' if mode == WhitespaceMode::All => WsMarker::Space,
+                    _ => continue,
+                };
+                markers.push((line_start + i, marker));
+            }
+        }
+        markers
+    }
 
-        let lines = line_iter
-            .map(|line_data| ViewportLine {
-                byte_offset: line_data.byte_offset,
-                content: line_data.content,
-                has_newline: line_data.has_newline,
-                approximate_line_number: line_data.line_number,
-            })
-            .collect();
+    /// Byte ranges of the misspelled words on the lines visible in `viewport`
+    ///
+    /// Only the visible lines are read, one at a time, so the cost doesn't grow
+    /// with the document. Code-like tokens are skipped (see
+    /// [`spell_check::checkable_words`]), as are bytes that aren't valid UTF-8.
+    /// Buffers without a line index (large file mode) report nothing.
+    pub fn misspellings(
+        &self,
+        viewport: &Viewport,
+        checker: &dyn SpellChecker,
+    ) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let first_line = self.buffer.get_line_number(viewport.top_byte);
+        for line in first_line..first_line + viewport.visible_line_count() {
+            let (Some(line_start), Some(text)) = (
+                self.buffer.line_start_offset(line),
+                self.buffer.get_line(line),
+            ) else {
+                break;
+            };
 
-        Ok(ViewportContent {
-            start_position: DocumentPosition::ByteOffset(start_offset),
-            lines,
-            has_more,
-        })
+            let mut offset = line_start;
+            for chunk in text.utf8_chunks() {
+                let valid = chunk.valid();
+                ranges.extend(
+                    spell_check::misspelled_words(valid, checker)
+                        .map(|range| offset + range.start..offset + range.end),
+                );
+                offset += valid.len() + chunk.invalid().len();
+            }
+        }
+        ranges
     }
 
-    fn position_to_offset(&self, pos: DocumentPosition) -> Result<usize> {
-        match pos {
-            DocumentPosition::ByteOffset(offset) => Ok(offset),
-            DocumentPosition::LineColumn { line, column } => {
-                if !self.has_line_index() {
-                    anyhow::bail!("Line indexing not available for this document");
+    /// Line, word and character counts for the document and the primary selection
+    ///
+    /// The document is streamed piece by piece in a single pass. In large file
+    /// mode only the parts that have been loaded are counted.
+    pub fn stats(&self) -> DocStats {
+        let mut counter = TextStatsCounter::default();
+        let _ = self.buffer.visit_chunks(|_, chunk| {
+            counter.feed(chunk);
+            ControlFlow::Continue(())
+        });
+        let selection = self
+            .cursors
+            .primary()
+            .selection_range()
+            .map(|range| TextStats::of(&self.buffer.slice_bytes(range)));
+        DocStats {
+            document: counter.finish(),
+            selection,
+        }
+    }
+
+    /// Lines of the scopes enclosing `top_line`, outermost first, to pin above
+    /// the viewport when it is scrolled to `top_line` (sticky scroll)
+    ///
+    /// Scopes are found by indentation: walking up from `top_line`, each line
+    /// indented less than everything below it so far is a header. A blank
+    /// `top_line` takes the indentation of the next non-blank line. Tabs advance
+    /// to the next multiple of `tab_size`. At most `max_lines` headers are
+    /// returned, dropping the innermost ones first.
+    pub fn sticky_context(&self, top_line: usize, max_lines: usize, tab_size: usize) -> Vec<usize> {
+        let indent_of = |line: usize| self.line_indent(line, tab_size);
+        let Some(mut threshold) = (top_line..).map_while(indent_of).flatten().next() else {
+            return Vec::new();
+        };
+        let mut headers = Vec::new();
+        for line in (0..top_line).rev() {
+            if threshold == 0 {
+                break;
+            }
+            if let Some(Some(indent)) = indent_of(line) {
+                if indent < threshold {
+                    headers.push(line);
+                    threshold = indent;
                 }
-                // Use piece tree's position conversion
-                let position = crate::model::piece_tree::Position { line, column };
-                Ok(self.buffer.position_to_offset(position))
             }
         }
+        headers.reverse();
+        headers.truncate(max_lines);
+        headers
     }
 
-    fn offset_to_position(&self, offset: usize) -> DocumentPosition {
-        if self.has_line_index() {
-            if let Some(pos) = self.buffer.offset_to_position(offset) {
-                DocumentPosition::LineColumn {
-                    line: pos.line,
-                    column: pos.column,
+    /// Line ranges to hide when folding every indentation block at `level`
+    ///
+    /// Blocks are found the same way as the [`sticky_context`] scopes: a line
+    /// followed by more indented lines heads a block that runs to the last
+    /// non-blank line before the indentation drops back. Top-level headers are
+    /// level 1 and each enclosing header adds one. Each range covers a block's
+    /// body, header excluded, in line order. The whole buffer is scanned.
+    ///
+    /// [`sticky_context`]: Self::sticky_context
+    pub fn indent_fold_ranges(&self, level: usize, tab_size: usize) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        // Lines enclosing the current one, innermost last: (indent, line)
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut last_non_blank = 0;
+        let mut close = |open: &mut Vec<(usize, usize)>, last_non_blank: usize| {
+            if let Some((_, header)) = open.pop() {
+                if open.len() + 1 == level && last_non_blank > header {
+                    ranges.push(header + 1..last_non_blank + 1);
                 }
-            } else {
-                // Line index exists but metadata unavailable - fall back to byte offset
-                DocumentPosition::ByteOffset(offset)
             }
-        } else {
-            DocumentPosition::ByteOffset(offset)
+        };
+        for line in 0.. {
+            let Some(indent) = self.line_indent(line, tab_size) else {
+                break;
+            };
+            let Some(indent) = indent else {
+                continue;
+            };
+            while open.last().is_some_and(|&(outer, _)| outer >= indent) {
+                close(&mut open, last_non_blank);
+            }
+            open.push((indent, line));
+            last_non_blank = line;
         }
+        while !open.is_empty() {
+            close(&mut open, last_non_blank);
+        }
+        ranges.sort_by_key(|range| range.start);
+        ranges
     }
 
-    fn get_range(&mut self, start: DocumentPosition, end: DocumentPosition) -> Result<String> {
-        let start_offset = self.position_to_offset(start)?;
-        let end_offset = self.position_to_offset(end)?;
-
-        if start_offset > end_offset {
-            anyhow::bail!(
-                "Invalid range: start offset {} > end offset {}",
-                start_offset,
-                end_offset
-            );
+    /// Indentation width of `line`, with tabs advancing to the next multiple of
+    /// `tab_size`; None past the end of the buffer, Some(None) for a blank line
+    fn line_indent(&self, line: usize, tab_size: usize) -> Option<Option<usize>> {
+        let tab_size = tab_size.max(1);
+        let text = self.buffer.get_line(line)?;
+        let mut width = 0;
+        for &b in &text {
+            match b {
+                b' ' => width += 1,
+                b'\t' => width = (width / tab_size + 1) * tab_size,
+                b'\r' | b'\n' => return Some(None),
+                _ => return Some(Some(width)),
+            }
         }
+        Some(None)
+    }
 
-        let bytes = self
-            .buffer
-            .get_text_range_mut(start_offset, end_offset - start_offset)?;
+    /// Byte ranges of the text in `lines` that reaches past column `limit`
+    ///
+    /// Each range runs from the first character ending beyond the limit to the
+    /// end of its line, line break excluded. Tabs advance to the next multiple
+    /// of `tab_size`, so a tab straddling the limit counts as over it. Lines
+    /// that are not valid UTF-8 are skipped.
+    pub fn over_limit_ranges(
+        &self,
+        lines: Range<usize>,
+        limit: usize,
+        tab_size: usize,
+    ) -> Vec<Range<usize>> {
+        use crate::primitives::display_width::str_width;
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let tab_size = tab_size.max(1);
+        let mut ranges = Vec::new();
+        for line in lines {
+            let (Some(start), Some(bytes)) = (
+                self.buffer.line_start_offset(line),
+                self.buffer.get_line(line),
+            ) else {
+                break;
+            };
+            let Ok(text) = std::str::from_utf8(&bytes) else {
+                continue;
+            };
+            let text = text.trim_end_matches(['\n', '\r']);
+            let mut column = 0;
+            for (offset, grapheme) in text.grapheme_indices(true) {
+                column += if grapheme == "\t" {
+                    tab_size - column % tab_size
+                } else {
+                    str_width(grapheme)
+                };
+                if column > limit {
+                    ranges.push(start + offset..start + text.len());
+                    break;
+                }
+            }
+        }
+        ranges
+    }
 
-        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    /// The spaces a soft tab inserts at `position`: enough to reach the next
+    /// multiple of `tab_size` columns, so a tab typed mid-line still lines up
+    pub fn soft_tab(&self, position: usize, tab_size: usize) -> String {
+        use crate::primitives::display_width::str_width_with_tabs;
+
+        let tab_size = tab_size.max(1);
+        let line = self.buffer.get_line_number(position);
+        let line_start = self.buffer.line_start_offset(line).unwrap_or(position);
+        let before = self.buffer.slice_bytes(line_start..position);
+        let column = str_width_with_tabs(&String::from_utf8_lossy(&before), tab_size);
+        " ".repeat(tab_size - column % tab_size)
     }
 
-    fn get_line_content(&mut self, line_number: usize) -> Option<String> {
-        if !self.has_line_index() {
+    /// Where Backspace at `position` should delete from to remove a whole
+    /// soft tab
+    ///
+    /// Applies when indenting with spaces and only spaces come before
+    /// `position` on its line: the spaces back to the previous multiple of the
+    /// indent width go at once. Returns None anywhere else, or when that would
+    /// be a single space anyway.
+    pub fn soft_tab_backspace_start(&self, position: usize, indent: IndentStyle) -> Option<usize> {
+        let IndentStyle::Spaces(tab_size) = indent else {
+            return None;
+        };
+        let tab_size = tab_size.max(1);
+        let line = self.buffer.get_line_number(position);
+        let line_start = self.buffer.line_start_offset(line)?;
+        let column = position.checked_sub(line_start)?;
+        if column < 2
+            || !self
+                .buffer
+                .slice_bytes(line_start..position)
+                .iter()
+                .all(|&b| b == b' ')
+        {
             return None;
         }
+        let width = match column % tab_size {
+            0 => tab_size,
+            partial => partial,
+        };
+        (width > 1).then(|| position - width)
+    }
 
-        // Convert line number to byte offset
-        let line_start_offset = self.buffer.line_start_offset(line_number)?;
+    /// Build the cursor moves for "smart home"
+    ///
+    /// Each cursor first jumps to the first non-whitespace character of its line,
+    /// and to column 0 if it is already there. On a line that is all whitespace
+    /// the end of the whitespace takes the place of the first character.
+    pub fn smart_home(&self) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (cursor_id, cursor) in self.cursors.iter() {
+            let line = self.buffer.get_line_number(cursor.position);
+            let (Some(line_start), Some(text)) = (
+                self.buffer.line_start_offset(line),
+                self.buffer.get_line(line),
+            ) else {
+                continue;
+            };
+            let indent = text
+                .iter()
+                .take_while(|&&b| b == b' ' || b == b'\t')
+                .count();
+            let first_non_ws = line_start + indent;
 
-        // Get line content using iterator
-        let mut iter = self.buffer.line_iterator(line_start_offset, 80);
-        if let Some((_start, content)) = iter.next() {
-            let has_newline = content.ends_with('\n');
-            let line_content = if has_newline {
-                content[..content.len() - 1].to_string()
+            let new_position = if cursor.position == first_non_ws {
+                line_start
             } else {
-                content
+                first_non_ws
             };
-            Some(line_content)
-        } else {
-            None
+            // Preserve anchor if deselect_on_move is false (Emacs mark mode)
+            let new_anchor = if cursor.deselect_on_move {
+                None
+            } else {
+                cursor.anchor
+            };
+            events.push(Event::MoveCursor {
+                cursor_id,
+                old_position: cursor.position,
+                new_position,
+                old_anchor: cursor.anchor,
+                new_anchor,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            });
         }
+        events
     }
 
-    fn get_chunk_at_offset(&mut self, offset: usize, size: usize) -> Result<(usize, String)> {
-        let bytes = self.buffer.get_text_range_mut(offset, size)?;
+    /// The bracket under the primary cursor and its partner, as (bracket, partner)
+    ///
+    /// The character at the cursor is tried first, then the one just before it,
+    /// so a cursor right after a closing bracket still finds it. Brackets in
+    /// strings or comments of `syntax` don't count. Only the text around the
+    /// cursor is read, loading it if needed (see
+    /// [`bracket_match::match_around`]). Returns None if there is no bracket or
+    /// it is unbalanced.
+    pub fn matching_bracket(&mut self, syntax: LexicalSyntax) -> Option<(usize, usize)> {
+        let position = self.cursors.primary().position;
+        let buffer = &mut self.buffer;
+        bracket_match::match_around(buffer.len(), position, syntax, |range| {
+            buffer
+                .get_text_range_mut(range.start, range.len())
+                .map_err(|e| tracing::warn!("Failed to read text for bracket matching: {}", e))
+                .ok()
+        })
+    }
 
-        Ok((offset, String::from_utf8_lossy(&bytes).into_owned()))
+    /// Build the event that moves the primary cursor to the partner of the
+    /// bracket under it (see [`Self::matching_bracket`])
+    pub fn goto_matching_bracket(&mut self, syntax: LexicalSyntax) -> Option<Event> {
+        let (_, partner) = self.matching_bracket(syntax)?;
+        let cursor = self.cursors.primary();
+        Some(Event::MoveCursor {
+            cursor_id: self.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: partner,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        })
     }
 
-    fn insert(&mut self, pos: DocumentPosition, text: &str) -> Result<usize> {
-        let offset = self.position_to_offset(pos)?;
-        self.buffer.insert_bytes(offset, text.as_bytes().to_vec());
-        Ok(text.len())
+    /// Build the event that selects from the bracket under the primary cursor
+    /// to its partner, both brackets included
+    ///
+    /// The selection is anchored at the bracket under the cursor, so the
+    /// cursor ends up on the far side of the partner.
+    pub fn select_to_matching_bracket(&mut self, syntax: LexicalSyntax) -> Option<Event> {
+        let (bracket, partner) = self.matching_bracket(syntax)?;
+        let (anchor, new_position) = if bracket < partner {
+            (bracket, partner + 1)
+        } else {
+            (bracket + 1, partner)
+        };
+        let cursor = self.cursors.primary();
+        Some(Event::MoveCursor {
+            cursor_id: self.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position,
+            old_anchor: cursor.anchor,
+            new_anchor: Some(anchor),
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        })
     }
 
-    fn delete(&mut self, start: DocumentPosition, end: DocumentPosition) -> Result<()> {
-        let start_offset = self.position_to_offset(start)?;
-        let end_offset = self.position_to_offset(end)?;
+    /// Whether `line` holds nothing but whitespace, or None past the end
+    fn is_blank_line(&self, line: usize) -> Option<bool> {
+        let text = self.buffer.get_line(line)?;
+        Some(text.iter().all(|b| b.is_ascii_whitespace()))
+    }
 
-        if start_offset > end_offset {
-            anyhow::bail!(
-                "Invalid range: start offset {} > end offset {}",
-                start_offset,
-                end_offset
-            );
+    /// Start of the blank line ending the paragraph at or after `line`, or the
+    /// end of the buffer if the paragraph runs to the end
+    ///
+    /// Blank lines at `line` are skipped first, so repeated calls step from
+    /// one paragraph gap to the next.
+    fn next_paragraph_boundary(&self, line: usize) -> usize {
+        let mut line = line;
+        let mut in_paragraph = false;
+        while let Some(blank) = self.is_blank_line(line) {
+            if blank && in_paragraph {
+                return self
+                    .buffer
+                    .line_start_offset(line)
+                    .unwrap_or(self.buffer.len());
+            }
+            in_paragraph |= !blank;
+            line += 1;
         }
+        self.buffer.len()
+    }
 
-        self.buffer.delete(start_offset..end_offset);
-        Ok(())
+    /// Start of the blank line before the paragraph at or before `line`, or 0
+    /// if the paragraph starts the buffer
+    fn previous_paragraph_boundary(&self, line: usize) -> usize {
+        let mut in_paragraph = false;
+        for line in (0..=line).rev() {
+            let blank = self.is_blank_line(line).unwrap_or(true);
+            if blank && in_paragraph {
+                return self.buffer.line_start_offset(line).unwrap_or(0);
+            }
+            in_paragraph |= !blank;
+        }
+        0
     }
 
-    fn replace(
-        &mut self,
-        start: DocumentPosition,
-        end: DocumentPosition,
-        text: &str,
-    ) -> Result<()> {
-        // Delete then insert
-        self.delete(start, end)?;
-        self.insert(start, text)?;
-        Ok(())
+    /// Build the cursor moves to the next blank line after each cursor's
+    /// paragraph, or the end of the buffer
+    pub fn move_paragraph_forward(&self) -> Vec<Event> {
+        self.move_cursors_by_line(|line| self.next_paragraph_boundary(line))
     }
 
-    fn find_matches(
-        &mut self,
-        pattern: &str,
-        search_range: Option<(DocumentPosition, DocumentPosition)>,
-    ) -> Result<Vec<usize>> {
-        let (start_offset, end_offset) = if let Some((start, end)) = search_range {
-            (
-                self.position_to_offset(start)?,
-                self.position_to_offset(end)?,
-            )
-        } else {
-            (0, self.buffer.len())
-        };
+    /// Build the cursor moves to the blank line before each cursor's
+    /// paragraph, or the start of the buffer
+    pub fn move_paragraph_backward(&self) -> Vec<Event> {
+        self.move_cursors_by_line(|line| self.previous_paragraph_boundary(line))
+    }
 
-        // Get text in range
-        let bytes = self
+    /// Move every cursor to `target(line)`, where `line` is the cursor's line
+    fn move_cursors_by_line(&self, target: impl Fn(usize) -> usize) -> Vec<Event> {
+        self.cursors
+            .iter()
+            .map(|(cursor_id, cursor)| {
+                let line = self.buffer.get_line_number(cursor.position);
+                // Preserve anchor if deselect_on_move is false (Emacs mark mode)
+                let new_anchor = if cursor.deselect_on_move {
+                    None
+                } else {
+                    cursor.anchor
+                };
+                Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position: target(line),
+                    old_anchor: cursor.anchor,
+                    new_anchor,
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Build the event that selects the paragraph around the primary cursor,
+    /// including its last line break
+    ///
+    /// On a blank line the whole run of blank lines is selected instead.
+    /// Returns None on an empty buffer.
+    pub fn select_paragraph(&self) -> Option<Event> {
+        let cursor = self.cursors.primary();
+        let line = self.buffer.get_line_number(cursor.position);
+        let blank = self.is_blank_line(line)?;
+        let same_kind = |line: usize| self.is_blank_line(line) == Some(blank);
+
+        let first = (0..line)
+            .rev()
+            .find(|&l| !same_kind(l))
+            .map_or(0, |l| l + 1);
+        let after = (line + 1..).find(|&l| !same_kind(l)).unwrap_or(line + 1);
+        let start = self.buffer.line_start_offset(first)?;
+        let end = self
             .buffer
-            .get_text_range_mut(start_offset, end_offset - start_offset)?;
-        let text = String::from_utf8_lossy(&bytes);
+            .line_start_offset(after)
+            .unwrap_or(self.buffer.len());
+        if start == end {
+            return None;
+        }
+        Some(Event::MoveCursor {
+            cursor_id: self.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: end,
+            old_anchor: cursor.anchor,
+            new_anchor: Some(start),
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        })
+    }
 
-        // Find all matches (simple substring search for now)
-        let mut matches = Vec::new();
-        let mut search_offset = 0;
-        while let Some(pos) = text[search_offset..].find(pattern) {
-            matches.push(start_offset + search_offset + pos);
-            search_offset += pos + pattern.len();
+    /// Build the event that toggles a line comment on `lines`
+    ///
+    /// The prefix goes after each line's indentation. If every non-blank line
+    /// already starts with it the lines are uncommented instead; a prefix whose
+    /// trailing space was lost (e.g. `//foo` for `"// "`) still counts. Blank lines
+    /// are left alone either way. Returns None if there is nothing to change.
+    pub fn toggle_comment(&self, lines: Range<usize>, prefix: &str) -> Option<Event> {
+        let marker = prefix.trim_end();
+        if marker.is_empty() {
+            return None;
         }
 
-        Ok(matches)
-    }
-}
+        // (offset after indentation, text after indentation) for each non-blank line
+        let mut targets = Vec::new();
+        for line in lines {
+            let (Some(line_start), Some(text)) = (
+                self.buffer.line_start_offset(line),
+                self.buffer.get_line(line),
+            ) else {
+                break;
+            };
+            let indent = text
+                .iter()
+                .take_while(|&&b| b == b' ' || b == b'\t')
+                .count();
+            let rest = text[indent..].to_vec();
+            if rest.is_empty() || rest[0] == b'\n' || rest.starts_with(b"\r\n") {
+                continue;
+            }
+            targets.push((line_start + indent, rest));
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::model::event::CursorId;
+        let uncomment = !targets.is_empty()
+            && targets
+                .iter()
+                .all(|(_, rest)| rest.starts_with(marker.as_bytes()));
+        let cursor_id = self.cursors.primary_id();
+
+        // Edit bottom-up so earlier offsets stay valid
+        let events: Vec<Event> = targets
+            .iter()
+            .rev()
+            .map(|(position, rest)| {
+                if uncomment {
+                    let len = if rest.starts_with(prefix.as_bytes()) {
+                        prefix.len()
+                    } else {
+                        marker.len()
+                    };
+                    Event::Delete {
+                        range: *position..*position + len,
+                        deleted_text: prefix[..len].to_string(),
+                        cursor_id,
+                    }
+                } else {
+                    Event::Insert {
+                        position: *position,
+                        text: prefix.to_string(),
+                        cursor_id,
+                    }
+                }
+            })
+            .collect();
 
-    #[test]
-    fn test_state_new() {
-        let state = EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        assert!(state.buffer.is_empty());
-        assert_eq!(state.cursors.count(), 1);
-        assert_eq!(state.cursors.primary().position, 0);
+        if events.is_empty() {
+            return None;
+        }
+        Some(Event::Batch {
+            events,
+            description: if uncomment {
+                "Uncomment lines".to_string()
+            } else {
+                "Comment lines".to_string()
+            },
+        })
     }
 
-    #[test]
-    fn test_apply_insert() {
-        let mut state =
-            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let cursor_id = state.cursors.primary_id();
-
-        state.apply(&Event::Insert {
-            position: 0,
-            text: "hello".to_string(),
-            cursor_id,
-        });
+    /// Guess the indentation style from the leading whitespace of the first
+    /// [`INDENT_SAMPLE_LINES`] lines
+    ///
+    /// Indented lines vote for tabs or spaces by their first character (a
+    /// single space doesn't count, as in the " *" of block comments). The width
+    /// of space indentation is the most common step, 2 to 8 columns, between a
+    /// line and a more indented one after it. Returns `default` when tabs and
+    /// spaces tie or no step was seen.
+    ///
+    /// The result is cached until the buffer is next edited.
+    pub fn detect_indentation(&self, default: IndentStyle) -> IndentStyle {
+        self.detected_indent
+            .get_or_init(|| self.scan_indentation())
+            .unwrap_or(default)
+    }
 
-        assert_eq!(state.buffer.to_string().unwrap(), "hello");
-        assert_eq!(state.cursors.primary().position, 5);
-        assert!(state.buffer.is_modified());
+    /// Drop the cached [`Self::detect_indentation`] result
+    ///
+    /// Edits made through events do this themselves; code that writes to
+    /// `buffer` directly (loading or replacing its content) must call it.
+    pub fn forget_indentation(&mut self) {
+        self.detected_indent.take();
     }
 
-    #[test]
-    fn test_apply_delete() {
-        let mut state =
-            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let cursor_id = state.cursors.primary_id();
+    fn scan_indentation(&self) -> Option<IndentStyle> {
+        let mut tab_lines = 0;
+        let mut space_lines = 0;
+        let mut steps = [0usize; 9];
+        let mut previous = Some(0);
+        for line in 0..INDENT_SAMPLE_LINES {
+            let Some(text) = self.buffer.get_line(line) else {
+                break;
+            };
+            let lead = text
+                .iter()
+                .take_while(|b| matches!(b, b' ' | b'\t'))
+                .count();
+            if matches!(text.get(lead), None | Some(b'\r' | b'\n')) {
+                continue;
+            }
+            let spaces = if text[..lead].contains(&b'\t') {
+                if text[0] == b'\t' {
+                    tab_lines += 1;
+                }
+                None
+            } else {
+                Some(lead)
+            };
+            if let (Some(spaces), Some(previous)) = (spaces, previous) {
+                if let Some(step) = steps.get_mut(spaces.saturating_sub(previous)) {
+                    *step += 1;
+                }
+            }
+            if spaces.is_some_and(|spaces| spaces >= 2) {
+                space_lines += 1;
+            }
+            previous = spaces;
+        }
 
-        // Insert then delete
-        state.apply(&Event::Insert {
-            position: 0,
-            text: "hello world".to_string(),
-            cursor_id,
-        });
+        match tab_lines.cmp(&space_lines) {
+            std::cmp::Ordering::Greater => Some(IndentStyle::Tabs),
+            std::cmp::Ordering::Less => (2..steps.len())
+                .filter(|&width| steps[width] > 0)
+                .max_by_key(|&width| (steps[width], std::cmp::Reverse(width)))
+                .map(IndentStyle::Spaces),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
 
-        state.apply(&Event::Delete {
-            range: 5..11,
-            deleted_text: " world".to_string(),
-            cursor_id,
-        });
+    /// Build the event that indents each of `lines` by one `unit` (a tab or spaces)
+    ///
+    /// The edits aren't made at any cursor, so cursors and selections stay on
+    /// the same text. Returns None if none of the lines exist.
+    pub fn indent(&self, lines: Range<usize>, unit: &str) -> Option<Event> {
+        let line_starts: Vec<usize> = lines
+            .map_while(|line| self.buffer.line_start_offset(line))
+            .collect();
 
-        assert_eq!(state.buffer.to_string().unwrap(), "hello");
-        assert_eq!(state.cursors.primary().position, 5);
+        // Edit bottom-up so earlier offsets stay valid
+        let events: Vec<Event> = line_starts
+            .into_iter()
+            .rev()
+            .map(|position| Event::Insert {
+                position,
+                text: unit.to_string(),
+                cursor_id: CursorId::UNDO_SENTINEL,
+            })
+            .collect();
+
+        if events.is_empty() {
+            return None;
+        }
+        Some(Event::Batch {
+            events,
+            description: "Indent lines".to_string(),
+        })
     }
 
-    #[test]
-    fn test_apply_move_cursor() {
-        let mut state =
-            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let cursor_id = state.cursors.primary_id();
+    /// Build the event that pastes `text` at the primary cursor, re-indented
+    /// to fit the cursor's line
+    ///
+    /// The indentation shared by the pasted lines is stripped and the target
+    /// indentation put in its place, so nesting within the block is kept. The
+    /// target is the text before the cursor when that is all whitespace, else
+    /// the line's own indentation. An unindented first line doesn't count
+    /// towards the shared indentation, as when a copy started mid-line. Blank
+    /// lines stay empty. Returns None if `text` is empty.
+    pub fn paste_reindent(&self, text: &str) -> Option<Event> {
+        if text.is_empty() {
+            return None;
+        }
+        let position = self.cursors.primary().position;
+        let line_start = self
+            .buffer
+            .line_start_offset(self.buffer.get_line_number(position))?;
+        let before_cursor = self.buffer.slice_bytes(line_start..position);
+        let before_cursor = String::from_utf8_lossy(&before_cursor);
+        let leading = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+        let target = &before_cursor[..leading(&before_cursor)];
+        let is_blank = |line: &str| line.trim_end_matches('\r').len() == leading(line);
+
+        let lines: Vec<&str> = text.split('\n').collect();
+        let common = lines
+            .iter()
+            .enumerate()
+            .filter(|&(i, line)| !is_blank(line) && (i > 0 || leading(line) > 0))
+            .map(|(_, line)| &line[..leading(line)])
+            .reduce(|common, indent| {
+                let shared = common
+                    .bytes()
+                    .zip(indent.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                &common[..shared]
+            })
+            .unwrap_or("");
+
+        let last = lines.len() - 1;
+        let mut reindented = String::with_capacity(text.len());
+        for (i, line) in lines.iter().enumerate() {
+            let stripped = line.strip_prefix(common).unwrap_or(&line[leading(line)..]);
+            if i > 0 {
+                reindented.push('\n');
+                if i == last || !is_blank(line) {
+                    reindented.push_str(target);
+                }
+            }
+            if i == 0 || !is_blank(line) {
+                reindented.push_str(stripped);
+            } else if line.ends_with('\r') {
+                reindented.push('\r');
+            }
+        }
 
-        state.apply(&Event::Insert {
-            position: 0,
-            text: "hello".to_string(),
-            cursor_id,
-        });
+        Some(Event::Insert {
+            position,
+            text: reindented,
+            cursor_id: self.cursors.primary_id(),
+        })
+    }
 
-        state.apply(&Event::MoveCursor {
-            cursor_id,
-            old_position: 5,
-            new_position: 2,
-            old_anchor: None,
+    /// Build the event that joins the primary cursor's line with the `count`
+    /// lines after it (at least one)
+    ///
+    /// The whitespace around each line break is collapsed into a single space,
+    /// or removed when either side is blank, after an opening bracket or before
+    /// a closing one. The cursor lands on the last join point. Returns None on
+    /// the last line.
+    pub fn join_lines(&self, count: usize) -> Option<Event> {
+        let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+        let content = |line: usize| -> Option<(usize, Vec<u8>)> {
+            let start = self.buffer.line_start_offset(line)?;
+            let mut text = self.buffer.get_line(line)?;
+            if text.last() == Some(&b'\n') {
+                text.pop();
+                if text.last() == Some(&b'\r') {
+                    text.pop();
+                }
+            }
+            Some((start, text))
+        };
+
+        let cursor = self.cursors.primary();
+        let first_line = self.buffer.get_line_number(cursor.position);
+        let (start, text) = content(first_line)?;
+        let trimmed_len = text.iter().rposition(|b| !is_blank(b)).map_or(0, |i| i + 1);
+        // Where the trailing whitespace of the text joined so far begins, and
+        // its last non-blank byte
+        let mut tail = start + trimmed_len;
+        let mut last_byte = text[..trimmed_len].last().copied();
+
+        // (range, separator) in document order
+        let mut edits: Vec<(Range<usize>, &str)> = Vec::new();
+        for line in first_line + 1..=first_line + count.max(1) {
+            let Some((start, text)) = content(line).filter(|(start, _)| *start < self.buffer.len())
+            else {
+                break;
+            };
+            let lead = text.iter().take_while(|b| is_blank(b)).count();
+            let rest = &text[lead..];
+            let separator = match (last_byte, rest.first()) {
+                (None, _) | (_, None) => "",
+                (Some(b'(' | b'['), _) | (_, Some(b')' | b']')) => "",
+                _ => " ",
+            };
+            let range = tail..start + lead;
+            // Joining a blank line leaves the tail where it was, so the next
+            // join's range covers this one
+            if edits
+                .last()
+                .is_some_and(|(last, _)| last.start == range.start)
+            {
+                edits.pop();
+            }
+            edits.push((range, separator));
+            if let Some(end) = rest.iter().rposition(|b| !is_blank(b)) {
+                tail = start + lead + end + 1;
+                last_byte = Some(rest[end]);
+            }
+        }
+        let last_range = edits.last()?.0.clone();
+
+        let removed_before: usize = edits[..edits.len() - 1]
+            .iter()
+            .map(|(range, separator)| range.len() - separator.len())
+            .sum();
+        let join_point = last_range.start - removed_before;
+
+        // Edit bottom-up so earlier offsets stay valid, then move the cursor
+        let mut events = Vec::new();
+        for (range, separator) in edits.into_iter().rev() {
+            events.push(Event::Delete {
+                range: range.clone(),
+                deleted_text: String::from_utf8_lossy(&self.buffer.slice_bytes(range.clone()))
+                    .into_owned(),
+                cursor_id: CursorId::UNDO_SENTINEL,
+            });
+            if !separator.is_empty() {
+                events.push(Event::Insert {
+                    position: range.start,
+                    text: separator.to_string(),
+                    cursor_id: CursorId::UNDO_SENTINEL,
+                });
+            }
+        }
+        events.push(Event::MoveCursor {
+            cursor_id: self.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: join_point,
+            old_anchor: cursor.anchor,
             new_anchor: None,
-            old_sticky_column: 0,
+            old_sticky_column: cursor.sticky_column,
             new_sticky_column: 0,
         });
-
-        assert_eq!(state.cursors.primary().position, 2);
+        Some(Event::Batch {
+            events,
+            description: "Join lines".to_string(),
+        })
     }
 
-    #[test]
-    fn test_apply_add_cursor() {
-        let mut state =
-            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let cursor_id = CursorId(1);
-
-        state.apply(&Event::AddCursor {
-            cursor_id,
-            position: 5,
-            anchor: None,
-        });
+    /// The cursor shape for the current mode
+    ///
+    /// Overtype mode covers the character that typing replaces with a block,
+    /// insert mode draws a bar between characters, and a buffer that can't be
+    /// edited gets an underline.
+    pub fn cursor_shape(&self) -> CursorShape {
+        if self.editing_disabled {
+            CursorShape::Underline
+        } else if self.overwrite {
+            CursorShape::Block
+        } else {
+            CursorShape::Bar
+        }
+    }
 
-        assert_eq!(state.cursors.count(), 2);
+    /// Build the events that type `text` over each cursor in overtype mode
+    ///
+    /// As many characters as `text` has are replaced after each cursor, but
+    /// never the line break: at the end of a line the text is inserted. A
+    /// selection is replaced as a whole, like in insert mode.
+    pub fn overwrite_events(&self, text: &str) -> Vec<Event> {
+        let mut cursors: Vec<_> = self
+            .cursors
+            .iter()
+            .map(|(id, cursor)| {
+                let range = cursor.selection_range().unwrap_or_else(|| {
+                    cursor.position..self.overwritten_end(cursor.position, text)
+                });
+                (id, range)
+            })
+            .collect();
+        // Bottom-up so earlier offsets stay valid
+        cursors.sort_by_key(|(_, range)| std::cmp::Reverse(range.start));
+
+        let mut events = Vec::new();
+        for (cursor_id, range) in cursors {
+            if !range.is_empty() {
+                events.push(Event::Delete {
+                    range: range.clone(),
+                    deleted_text: String::from_utf8_lossy(&self.buffer.slice_bytes(range.clone()))
+                        .into_owned(),
+                    cursor_id,
+                });
+            }
+            events.push(Event::Insert {
+                position: range.start,
+                text: text.to_string(),
+                cursor_id,
+            });
+        }
+        events
     }
 
-    #[test]
-    fn test_apply_many() {
-        let mut state =
-            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let cursor_id = state.cursors.primary_id();
+    /// End of the characters that typing `text` at `position` overwrites
+    fn overwritten_end(&self, position: usize, text: &str) -> usize {
+        let mut end = position;
+        for _ in 0..text.chars().count() {
+            if end >= self.buffer.len()
+                || matches!(
+                    self.buffer.slice_bytes(end..end + 1).first(),
+                    Some(b'\n' | b'\r')
+                )
+            {
+                break;
+            }
+            end = self.buffer.next_char_boundary(end);
+        }
+        end
+    }
 
-        let events = vec![
-            Event::Insert {
-                position: 0,
-                text: "hello ".to_string(),
-                cursor_id,
-            },
-            Event::Insert {
-                position: 6,
-                text: "world".to_string(),
-                cursor_id,
-            },
-        ];
+    /// Build the event that dedents each of `lines` by one level
+    ///
+    /// Up to `tab_size` columns of leading whitespace are removed, so a tab, a
+    /// full run of spaces, or a partial indent all count as one level. Like
+    /// `indent`, cursors and selections stay on the same text. Returns None if
+    /// no line has leading whitespace.
+    pub fn dedent(&self, lines: Range<usize>, tab_size: usize) -> Option<Event> {
+        let tab_size = tab_size.max(1);
+
+        let mut targets = Vec::new();
+        for line in lines {
+            let (Some(line_start), Some(text)) = (
+                self.buffer.line_start_offset(line),
+                self.buffer.get_line(line),
+            ) else {
+                break;
+            };
+            let mut width = 0;
+            let mut len = 0;
+            for &b in &text {
+                match b {
+                    b' ' => width += 1,
+                    b'\t' => width = (width / tab_size + 1) * tab_size,
+                    _ => break,
+                }
+                len += 1;
+                if width >= tab_size {
+                    break;
+                }
+            }
+            if len > 0 {
+                let removed = String::from_utf8_lossy(&text[..len]).into_owned();
+                targets.push((line_start..line_start + len, removed));
+            }
+        }
 
-        state.apply_many(&events);
+        // Edit bottom-up so earlier offsets stay valid
+        let events: Vec<Event> = targets
+            .into_iter()
+            .rev()
+            .map(|(range, deleted_text)| Event::Delete {
+                range,
+                deleted_text,
+                cursor_id: CursorId::UNDO_SENTINEL,
+            })
+            .collect();
 
-        assert_eq!(state.buffer.to_string().unwrap(), "hello world");
+        if events.is_empty() {
+            return None;
+        }
+        Some(Event::Batch {
+            events,
+            description: "Dedent lines".to_string(),
+        })
     }
 
-    #[test]
-    fn test_cursor_adjustment_after_insert() {
-        let mut state =
-            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-        let cursor_id = state.cursors.primary_id();
+    /// Build the event that pastes `lines` as a block: `lines[i]` goes in at
+    /// display column `col` of line `line + i`
+    ///
+    /// Lines too short to reach the column are padded with spaces, and new
+    /// lines are appended if the block runs past the end of the buffer. Like
+    /// `indent`, cursors stay on the same text. Returns None if `lines` is empty.
+    pub fn paste_block(&self, lines: &[String], line: usize, col: usize) -> Option<Event> {
+        use crate::primitives::display_width::{byte_offset_at_visual_column, str_width};
+
+        let mut events = Vec::new();
+        let mut appended = String::new();
+        for (target, text) in (line..).zip(lines) {
+            let (Some(line_start), Some(content)) = (
+                self.buffer.line_start_offset(target),
+                self.buffer.get_line(target),
+            ) else {
+                appended.push_str(self.buffer.line_ending().as_str());
+                appended.push_str(&" ".repeat(col));
+                appended.push_str(text);
+                continue;
+            };
+            let content = String::from_utf8_lossy(&content);
+            let content = content.trim_end_matches(['\n', '\r']);
+            let width = str_width(content);
+            let (offset, padding) = if width >= col {
+                (byte_offset_at_visual_column(content, col), 0)
+            } else {
+                (content.len(), col - width)
+            };
+            events.push(Event::Insert {
+                position: line_start + offset,
+                text: format!("{}{}", " ".repeat(padding), text),
+                cursor_id: CursorId::UNDO_SENTINEL,
+            });
+        }
+        if !appended.is_empty() {
+            events.push(Event::Insert {
+                position: self.buffer.len(),
+                text: appended,
+                cursor_id: CursorId::UNDO_SENTINEL,
+            });
+        }
 
-        // Add a second cursor at position 5
-        state.apply(&Event::AddCursor {
-            cursor_id: CursorId(1),
-            position: 5,
-            anchor: None,
-        });
+        if events.is_empty() {
+            return None;
+        }
+        // Edit bottom-up so earlier offsets stay valid
+        events.reverse();
+        Some(Event::Batch {
+            events,
+            description: "Paste block".to_string(),
+        })
+    }
 
-        // Insert at position 0 - should push second cursor forward
-        state.apply(&Event::Insert {
-            position: 0,
-            text: "abc".to_string(),
+    /// Build the event that changes the case of every cursor's selection, or
+    /// of the word under cursors without one
+    ///
+    /// The new text can differ in length from the old ("ß" uppercases to
+    /// "SS"); selections are kept around the replaced text. Returns None if
+    /// nothing would change.
+    pub fn transform_case(&self, transform: CaseTransform) -> Option<Event> {
+        let mut targets: Vec<(CursorId, &Cursor, Range<usize>)> = self
+            .cursors
+            .iter()
+            .filter_map(|(id, cursor)| {
+                let range = cursor
+                    .selection_range()
+                    .or_else(|| self.word_range_at(cursor.position))?;
+                Some((id, cursor, range))
+            })
+            .collect();
+        targets.sort_by_key(|(_, _, range)| range.start);
+
+        // (range, old text, new text) in document order, without overlaps
+        let mut edits: Vec<(Range<usize>, String, String)> = Vec::new();
+        let mut moves = Vec::new();
+        let mut delta: isize = 0;
+        for (cursor_id, cursor, range) in targets {
+            if edits
+                .last()
+                .is_some_and(|(last, _, _)| range.start < last.end)
+            {
+                continue;
+            }
+            let Ok(old) = String::from_utf8(self.buffer.slice_bytes(range.clone())) else {
+                continue;
+            };
+            let new = transform.apply(&old);
+            let start = (range.start as isize + delta) as usize;
+            let end = start + new.len();
+            let (new_position, new_anchor) = match cursor.anchor {
+                Some(anchor) if anchor < cursor.position => (end, Some(start)),
+                Some(_) => (start, Some(end)),
+                // In a word: stay at its start or end, or in place if the length is the same
+                None if cursor.position == range.start => (start, None),
+                None if new.len() == old.len() => (start + cursor.position - range.start, None),
+                None => (end, None),
+            };
+            moves.push(Event::MoveCursor {
+                cursor_id,
+                old_position: cursor.position,
+                new_position,
+                old_anchor: cursor.anchor,
+                new_anchor,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            });
+            delta += new.len() as isize - old.len() as isize;
+            if new != old {
+                edits.push((range, old, new));
+            }
+        }
+        if edits.is_empty() {
+            return None;
+        }
+
+        // Edit bottom-up so earlier offsets stay valid, then restore the cursors
+        let mut events = Vec::new();
+        for (range, old, new) in edits.into_iter().rev() {
+            events.push(Event::Delete {
+                range: range.clone(),
+                deleted_text: old,
+                cursor_id: CursorId::UNDO_SENTINEL,
+            });
+            events.push(Event::Insert {
+                position: range.start,
+                text: new,
+                cursor_id: CursorId::UNDO_SENTINEL,
+            });
+        }
+        events.extend(moves);
+        Some(Event::Batch {
+            events,
+            description: format!("{} text", transform.label()),
+        })
+    }
+
+    /// Sort the lines `lines` (0-indexed, end exclusive)
+    ///
+    /// The block keeps its trailing line break, or lack of one at the end of
+    /// the document, whichever line ends up last. A selection of the primary
+    /// cursor is kept around the sorted lines. Returns None if the order
+    /// doesn't change.
+    pub fn sort_lines(&self, lines: Range<usize>, options: SortOptions) -> Option<Event> {
+        let start = self.buffer.line_start_offset(lines.start)?;
+        let end = self
+            .buffer
+            .line_start_offset(lines.end)
+            .unwrap_or(self.buffer.len())
+            .max(start);
+        let old = String::from_utf8(self.buffer.slice_bytes(start..end)).ok()?;
+        let new = options.apply(&old);
+        if new == old {
+            return None;
+        }
+
+        let cursor = self.cursors.primary();
+        let new_end = start + new.len();
+        let (new_position, new_anchor) = match cursor.anchor {
+            Some(anchor) if anchor < cursor.position => (new_end, Some(start)),
+            Some(_) => (start, Some(new_end)),
+            None => (
+                cursor
+                    .position
+                    .min(self.buffer.len() - old.len() + new.len()),
+                None,
+            ),
+        };
+        let events = vec![
+            Event::Delete {
+                range: start..end,
+                deleted_text: old,
+                cursor_id: CursorId::UNDO_SENTINEL,
+            },
+            Event::Insert {
+                position: start,
+                text: new,
+                cursor_id: CursorId::UNDO_SENTINEL,
+            },
+            Event::MoveCursor {
+                cursor_id: self.cursors.primary_id(),
+                old_position: cursor.position,
+                new_position,
+                old_anchor: cursor.anchor,
+                new_anchor,
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            },
+        ];
+        Some(Event::Batch {
+            events,
+            description: "Sort lines".to_string(),
+        })
+    }
+
+    /// Range of the word (letters, digits and `_`, in any script) touching `position`
+    fn word_range_at(&self, position: usize) -> Option<Range<usize>> {
+        let line = self.buffer.get_line_number(position);
+        let line_start = self.buffer.line_start_offset(line)?;
+        let text = String::from_utf8(self.buffer.get_line(line)?).ok()?;
+        let column = position.checked_sub(line_start)?;
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+        let start = text
+            .get(..column)?
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| is_word(c))
+            .last()
+            .map_or(column, |(i, _)| i);
+        let end = text[column..]
+            .char_indices()
+            .find(|&(_, c)| !is_word(c))
+            .map_or(text.len(), |(i, _)| column + i);
+        (start < end).then(|| line_start + start..line_start + end)
+    }
+
+    /// Build the event that swaps the characters on either side of each cursor
+    ///
+    /// The cursor moves past both, so repeating it drags a character forward.
+    /// Nothing happens at the start or end of a line: a character is never
+    /// swapped with a line break. Returns None if no cursor can transpose.
+    pub fn transpose_chars(&self) -> Option<Event> {
+        let mut positions: Vec<_> = self
+            .cursors
+            .iter()
+            .map(|(id, cursor)| (id, cursor.position))
+            .collect();
+        positions.sort_by_key(|&(_, position)| position);
+
+        // (cursor, range, swapped text) in document order
+        let mut swaps: Vec<(CursorId, Range<usize>, String)> = Vec::new();
+        for (cursor_id, position) in positions {
+            let before = self
+                .buffer
+                .slice_bytes(position.saturating_sub(4)..position);
+            let after = self
+                .buffer
+                .slice_bytes(position..(position + 4).min(self.buffer.len()));
+            let (Some(first), Some(second)) = (
+                String::from_utf8_lossy(&before).chars().next_back(),
+                String::from_utf8_lossy(&after).chars().next(),
+            ) else {
+                continue;
+            };
+            if matches!(first, '\n' | '\r') || matches!(second, '\n' | '\r') {
+                continue;
+            }
+            let range = position - first.len_utf8()..position + second.len_utf8();
+            // Neighbouring cursors would swap overlapping pairs
+            if swaps
+                .last()
+                .is_some_and(|(_, last, _)| last.end > range.start)
+            {
+                continue;
+            }
+            swaps.push((cursor_id, range, format!("{second}{first}")));
+        }
+        self.swap_events(swaps, "Transpose characters")
+    }
+
+    /// Build the event that swaps the word at or before each cursor with the
+    /// word after it, on the cursor's line
+    ///
+    /// Before the first word the first two are swapped, and after the last
+    /// word the last two. Whatever separates the words stays in place and the
+    /// cursor moves past both. Returns None if no cursor's line has two words.
+    pub fn transpose_words(&self) -> Option<Event> {
+        let is_word = |c: char| {
+            c.is_alphanumeric() || c == '_' || (c.is_ascii() && self.word_def.is_word_char(c as u8))
+        };
+        let mut positions: Vec<_> = self
+            .cursors
+            .iter()
+            .map(|(id, cursor)| (id, cursor.position))
+            .collect();
+        positions.sort_by_key(|&(_, position)| position);
+
+        // (cursor, range, swapped text) in document order
+        let mut swaps: Vec<(CursorId, Range<usize>, String)> = Vec::new();
+        for (cursor_id, position) in positions {
+            let line = self.buffer.get_line_number(position);
+            let Some(line_start) = self.buffer.line_start_offset(line) else {
+                continue;
+            };
+            let Some(text) = self
+                .buffer
+                .get_line(line)
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+            else {
+                continue;
+            };
+            let column = position.saturating_sub(line_start);
+
+            let mut words: Vec<Range<usize>> = Vec::new();
+            for (i, c) in text.char_indices() {
+                match words.last_mut() {
+                    Some(word) if word.end == i && is_word(c) => word.end = i + c.len_utf8(),
+                    _ if is_word(c) => words.push(i..i + c.len_utf8()),
+                    _ => {}
+                }
+            }
+            if words.len() < 2 {
+                continue;
+            }
+            // The word the cursor is in or after, paired with the next one
+            let before = words.iter().filter(|word| word.start < column).count();
+            let second = before.clamp(1, words.len() - 1);
+            let (first, second) = (&words[second - 1], &words[second]);
+
+            let range = line_start + first.start..line_start + second.end;
+            if swaps
+                .last()
+                .is_some_and(|(_, last, _)| last.end > range.start)
+            {
+                continue;
+            }
+            let swapped = format!(
+                "{}{}{}",
+                &text[second.clone()],
+                &text[first.end..second.start],
+                &text[first.clone()]
+            );
+            swaps.push((cursor_id, range, swapped));
+        }
+        self.swap_events(swaps, "Transpose words")
+    }
+
+    /// Build the batch replacing each range with its swapped text and moving
+    /// its cursor past it
+    ///
+    /// Every swap keeps the length, so the offsets stay valid in any order.
+    fn swap_events(
+        &self,
+        swaps: Vec<(CursorId, Range<usize>, String)>,
+        description: &str,
+    ) -> Option<Event> {
+        if swaps.is_empty() {
+            return None;
+        }
+        let mut events = Vec::new();
+        for (cursor_id, range, text) in swaps {
+            events.push(Event::Delete {
+                range: range.clone(),
+                deleted_text: String::from_utf8_lossy(&self.buffer.slice_bytes(range.clone()))
+                    .into_owned(),
+                cursor_id,
+            });
+            events.push(Event::Insert {
+                position: range.start,
+                text,
+                cursor_id,
+            });
+        }
+        Some(Event::Batch {
+            events,
+            description: description.to_string(),
+        })
+    }
+
+    /// Build the event that wraps every cursor's selection in `open` and `close`
+    ///
+    /// `close` is inserted after each selection before `open` goes in front of
+    /// it, so the offsets of the first edit stay valid for the second. The
+    /// selections keep covering the original text, now inside the wrapping,
+    /// and keep their direction. Cursors without a selection are left alone.
+    /// Returns None if no cursor has a selection.
+    pub fn surround(&self, open: &str, close: &str) -> Option<Event> {
+        let mut targets: Vec<(CursorId, &Cursor, Range<usize>)> = self
+            .cursors
+            .iter()
+            .filter_map(|(id, cursor)| Some((id, cursor, cursor.selection_range()?)))
+            .collect();
+        targets.sort_by_key(|(_, _, range)| range.start);
+
+        let mut edits = Vec::new();
+        let mut moves = Vec::new();
+        let mut delta = 0;
+        for (cursor_id, cursor, range) in targets {
+            if edits
+                .last()
+                .is_some_and(|last: &Range<usize>| range.start < last.end)
+            {
+                continue;
+            }
+            let start = range.start + delta + open.len();
+            let end = range.end + delta + open.len();
+            let (new_position, new_anchor) = if cursor.position < range.end {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            // The old selection as it sits in the wrapped text, so that undoing
+            // the move and then the inserts lands back on the original
+            let wrapped = |p: usize| p + delta + if p == range.end { open.len() } else { 0 };
+            moves.push(Event::MoveCursor {
+                cursor_id,
+                old_position: wrapped(cursor.position),
+                new_position,
+                old_anchor: cursor.anchor.map(wrapped),
+                new_anchor: Some(new_anchor),
+                old_sticky_column: cursor.sticky_column,
+                new_sticky_column: 0,
+            });
+            delta += open.len() + close.len();
+            edits.push(range);
+        }
+        if edits.is_empty() {
+            return None;
+        }
+
+        // Edit bottom-up, right side first, then restore the selections
+        let mut events = Vec::new();
+        for range in edits.into_iter().rev() {
+            for (position, text) in [(range.end, close), (range.start, open)] {
+                events.push(Event::Insert {
+                    position,
+                    text: text.to_string(),
+                    cursor_id: CursorId::UNDO_SENTINEL,
+                });
+            }
+        }
+        events.extend(moves);
+        Some(Event::Batch {
+            events,
+            description: "Surround selection".to_string(),
+        })
+    }
+
+    /// Build the event that tidies the buffer before it is saved
+    ///
+    /// `trim_trailing_whitespace` removes the spaces and tabs before each line
+    /// ending and at the end of the buffer. `ensure_final_newline` appends the
+    /// buffer's line ending unless it already ends with one or would be empty.
+    /// The edits aren't made at a cursor, so a cursor on removed whitespace ends
+    /// up where the run started. Returns None if there is nothing to change.
+    pub fn save_cleanup(
+        &self,
+        trim_trailing_whitespace: bool,
+        ensure_final_newline: bool,
+    ) -> Option<Event> {
+        let len = self.buffer.len();
+
+        let mut runs = Vec::new();
+        if trim_trailing_whitespace {
+            let mut run_start = None;
+            let mut next_offset = 0;
+            let _ = self.buffer.visit_chunks(|offset, chunk| {
+                // Don't join runs across unloaded pieces
+                if offset != next_offset {
+                    run_start = None;
+                }
+                for (i, &b) in chunk.iter().enumerate() {
+                    match b {
+                        b' ' | b'\t' => {
+                            run_start.get_or_insert(offset + i);
+                        }
+                        b'\n' | b'\r' => {
+                            if let Some(start) = run_start.take() {
+                                runs.push(start..offset + i);
+                            }
+                        }
+                        _ => run_start = None,
+                    }
+                }
+                next_offset = offset + chunk.len();
+                ControlFlow::Continue(())
+            });
+            if let Some(start) = run_start.filter(|_| next_offset == len) {
+                runs.push(start..len);
+            }
+        }
+
+        let mut events = Vec::new();
+        if ensure_final_newline {
+            // Where the text ends once trailing whitespace at the very end is gone
+            let end = match runs.last() {
+                Some(run) if run.end == len => run.start,
+                _ => len,
+            };
+            let last_byte = end
+                .checked_sub(1)
+                .and_then(|last| self.buffer.slice_bytes(last..end).first().copied());
+            if matches!(last_byte, Some(b) if b != b'\n' && b != b'\r') {
+                events.push(Event::Insert {
+                    position: len,
+                    text: self.buffer.line_ending().as_str().to_string(),
+                    cursor_id: CursorId::UNDO_SENTINEL,
+                });
+            }
+        }
+        // Bottom-up, after the append, so earlier offsets stay valid
+        for run in runs.into_iter().rev() {
+            events.push(Event::Delete {
+                deleted_text: String::from_utf8_lossy(&self.buffer.slice_bytes(run.clone()))
+                    .into_owned(),
+                range: run,
+                cursor_id: CursorId::UNDO_SENTINEL,
+            });
+        }
+
+        if events.is_empty() {
+            return None;
+        }
+        Some(Event::Batch {
+            events,
+            description: "Clean up on save".to_string(),
+        })
+    }
+
+    /// Build the event that replaces `trigger` with an expanded snippet
+    ///
+    /// The batch deletes the trigger, inserts the snippet text and selects the
+    /// first stop. The returned session should be stored in `self.snippet`
+    /// only after the event is applied, so the expansion itself does not end
+    /// it, and not at all if it is already on `$0`.
+    pub fn expand_snippet(
+        &self,
+        snippet: &Snippet,
+        trigger: Range<usize>,
+    ) -> (Event, SnippetEngine) {
+        let cursor_id = self.cursors.primary_id();
+        let engine = SnippetEngine::new(snippet, trigger.start);
+
+        let mut events = Vec::new();
+        if !trigger.is_empty() {
+            events.push(Event::Delete {
+                deleted_text: String::from_utf8_lossy(&self.buffer.slice_bytes(trigger.clone()))
+                    .into_owned(),
+                range: trigger.clone(),
+                cursor_id,
+            });
+        }
+        events.push(Event::Insert {
+            position: trigger.start,
+            text: snippet.text.clone(),
+            cursor_id,
+        });
+        events.extend(self.snippet_stop_events(engine.current()));
+
+        let event = Event::Batch {
+            events,
+            description: "Expand snippet".to_string(),
+        };
+        (event, engine)
+    }
+
+    /// Move the snippet session to the next (or previous) stop
+    ///
+    /// Returns the cursor events selecting the new stop, or None without a
+    /// session. Reaching `$0` ends the session.
+    pub fn snippet_jump(&mut self, forward: bool) -> Option<Event> {
+        let snippet = self.snippet.as_mut()?;
+        if forward {
+            snippet.advance();
+        } else {
+            snippet.retreat();
+        }
+        let ranges = snippet.current().to_vec();
+        if snippet.is_at_final() {
+            self.snippet = None;
+        }
+        let events = self.snippet_stop_events(&ranges);
+        Some(Event::Batch {
+            events,
+            description: "Jump to snippet stop".to_string(),
+        })
+    }
+
+    /// Cursor events that select each of `ranges`, one cursor per range
+    ///
+    /// The primary cursor takes the first range and any other cursors are
+    /// removed, so mirrored stops end up with exactly one cursor each.
+    fn snippet_stop_events(&self, ranges: &[Range<usize>]) -> Vec<Event> {
+        let primary_id = self.cursors.primary_id();
+        let primary = self.cursors.primary();
+        let first_new_id = self.cursors.ids().iter().map(|id| id.0).max().unwrap_or(0) + 1;
+        let anchor = |range: &Range<usize>| (!range.is_empty()).then_some(range.start);
+
+        let mut events: Vec<Event> = self
+            .cursors
+            .iter()
+            .filter(|(cursor_id, _)| *cursor_id != primary_id)
+            .map(|(cursor_id, cursor)| Event::RemoveCursor {
+                cursor_id,
+                position: cursor.position,
+                anchor: cursor.anchor,
+            })
+            .collect();
+        let Some((first, mirrors)) = ranges.split_first() else {
+            return events;
+        };
+        events.push(Event::MoveCursor {
+            cursor_id: primary_id,
+            old_position: primary.position,
+            new_position: first.end,
+            old_anchor: primary.anchor,
+            new_anchor: anchor(first),
+            old_sticky_column: primary.sticky_column,
+            new_sticky_column: 0,
+        });
+        for (id, range) in (first_new_id..).zip(mirrors) {
+            events.push(Event::AddCursor {
+                cursor_id: CursorId(id),
+                position: range.end,
+                anchor: anchor(range),
+            });
+        }
+        events
+    }
+}
+
+/// Implement DocumentModel trait for EditorState
+///
+/// This provides a clean abstraction layer between rendering/editing operations
+/// and the underlying text buffer implementation.
+impl DocumentModel for EditorState {
+    fn capabilities(&self) -> DocumentCapabilities {
+        let line_count = self.buffer.line_count();
+        DocumentCapabilities {
+            has_line_index: line_count.is_some(),
+            uses_lazy_loading: false, // TODO: add large file detection
+            byte_length: self.buffer.len(),
+            approximate_line_count: line_count.unwrap_or_else(|| {
+                // Estimate assuming ~80 bytes per line
+                self.buffer.len() / 80
+            }),
+        }
+    }
+
+    fn get_viewport_content(
+        &mut self,
+        start_pos: DocumentPosition,
+        max_lines: usize,
+    ) -> Result<ViewportContent> {
+        // Convert to byte offset
+        let start_offset = self.position_to_offset(start_pos)?;
+
+        // Use new efficient line iteration that tracks line numbers during iteration
+        // by accumulating line_feed_cnt from pieces (single source of truth)
+        let line_iter = self.buffer.iter_lines_from(start_offset, max_lines)?;
+        let has_more = line_iter.has_more;
+
+        let lines = line_iter
+            .map(|line_data| ViewportLine {
+                byte_offset: line_data.byte_offset,
+                content: line_data.content,
+                has_newline: line_data.has_newline,
+                approximate_line_number: line_data.line_number,
+            })
+            .collect();
+
+        Ok(ViewportContent {
+            start_position: DocumentPosition::ByteOffset(start_offset),
+            lines,
+            has_more,
+        })
+    }
+
+    fn position_to_offset(&self, pos: DocumentPosition) -> Result<usize> {
+        match pos {
+            DocumentPosition::ByteOffset(offset) => Ok(offset),
+            DocumentPosition::LineColumn { line, column } => {
+                if !self.has_line_index() {
+                    anyhow::bail!("Line indexing not available for this document");
+                }
+                // Use piece tree's position conversion
+                let position = crate::model::piece_tree::Position { line, column };
+                Ok(self.buffer.position_to_offset(position))
+            }
+        }
+    }
+
+    fn offset_to_position(&self, offset: usize) -> DocumentPosition {
+        if self.has_line_index() {
+            if let Some(pos) = self.buffer.offset_to_position(offset) {
+                DocumentPosition::LineColumn {
+                    line: pos.line,
+                    column: pos.column,
+                }
+            } else {
+                // Line index exists but metadata unavailable - fall back to byte offset
+                DocumentPosition::ByteOffset(offset)
+            }
+        } else {
+            DocumentPosition::ByteOffset(offset)
+        }
+    }
+
+    fn get_range(&mut self, start: DocumentPosition, end: DocumentPosition) -> Result<String> {
+        let start_offset = self.position_to_offset(start)?;
+        let end_offset = self.position_to_offset(end)?;
+
+        if start_offset > end_offset {
+            anyhow::bail!(
+                "Invalid range: start offset {} > end offset {}",
+                start_offset,
+                end_offset
+            );
+        }
+
+        let bytes = self
+            .buffer
+            .get_text_range_mut(start_offset, end_offset - start_offset)?;
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn get_line_content(&mut self, line_number: usize) -> Option<String> {
+        if !self.has_line_index() {
+            return None;
+        }
+
+        // Convert line number to byte offset
+        let line_start_offset = self.buffer.line_start_offset(line_number)?;
+
+        // Get line content using iterator
+        let mut iter = self.buffer.line_iterator(line_start_offset, 80);
+        if let Some((_start, content)) = iter.next() {
+            let has_newline = content.ends_with('\n');
+            let line_content = if has_newline {
+                content[..content.len() - 1].to_string()
+            } else {
+                content
+            };
+            Some(line_content)
+        } else {
+            None
+        }
+    }
+
+    fn get_chunk_at_offset(&mut self, offset: usize, size: usize) -> Result<(usize, String)> {
+        let bytes = self.buffer.get_text_range_mut(offset, size)?;
+
+        Ok((offset, String::from_utf8_lossy(&bytes).into_owned()))
+    }
+
+    fn insert(&mut self, pos: DocumentPosition, text: &str) -> Result<usize> {
+        let offset = self.position_to_offset(pos)?;
+        self.buffer.insert_bytes(offset, text.as_bytes().to_vec());
+        self.forget_indentation();
+        Ok(text.len())
+    }
+
+    fn delete(&mut self, start: DocumentPosition, end: DocumentPosition) -> Result<()> {
+        let start_offset = self.position_to_offset(start)?;
+        let end_offset = self.position_to_offset(end)?;
+
+        if start_offset > end_offset {
+            anyhow::bail!(
+                "Invalid range: start offset {} > end offset {}",
+                start_offset,
+                end_offset
+            );
+        }
+
+        self.buffer.delete(start_offset..end_offset);
+        self.forget_indentation();
+        Ok(())
+    }
+
+    fn replace(
+        &mut self,
+        start: DocumentPosition,
+        end: DocumentPosition,
+        text: &str,
+    ) -> Result<()> {
+        // Delete then insert
+        self.delete(start, end)?;
+        self.insert(start, text)?;
+        Ok(())
+    }
+
+    fn find_matches(
+        &mut self,
+        pattern: &str,
+        search_range: Option<(DocumentPosition, DocumentPosition)>,
+    ) -> Result<Vec<usize>> {
+        let (start_offset, end_offset) = if let Some((start, end)) = search_range {
+            (
+                self.position_to_offset(start)?,
+                self.position_to_offset(end)?,
+            )
+        } else {
+            (0, self.buffer.len())
+        };
+
+        // Get text in range
+        let bytes = self
+            .buffer
+            .get_text_range_mut(start_offset, end_offset - start_offset)?;
+        let text = String::from_utf8_lossy(&bytes);
+
+        // Find all matches (simple substring search for now)
+        let mut matches = Vec::new();
+        let mut search_offset = 0;
+        while let Some(pos) = text[search_offset..].find(pattern) {
+            matches.push(start_offset + search_offset + pos);
+            search_offset += pos + pattern.len();
+        }
+
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::CursorId;
+    use crate::primitives::bracket_match::LexicalSyntax;
+
+    #[test]
+    fn test_state_new() {
+        let state = EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        assert!(state.buffer.is_empty());
+        assert_eq!(state.cursors.count(), 1);
+        assert_eq!(state.cursors.primary().position, 0);
+    }
+
+    #[test]
+    fn test_apply_insert() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let cursor_id = state.cursors.primary_id();
+
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "hello".to_string(),
+            cursor_id,
+        });
+
+        assert_eq!(state.buffer.to_string().unwrap(), "hello");
+        assert_eq!(state.cursors.primary().position, 5);
+        assert!(state.buffer.is_modified());
+    }
+
+    #[test]
+    fn test_apply_delete() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let cursor_id = state.cursors.primary_id();
+
+        // Insert then delete
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "hello world".to_string(),
+            cursor_id,
+        });
+
+        state.apply(&Event::Delete {
+            range: 5..11,
+            deleted_text: " world".to_string(),
+            cursor_id,
+        });
+
+        assert_eq!(state.buffer.to_string().unwrap(), "hello");
+        assert_eq!(state.cursors.primary().position, 5);
+    }
+
+    #[test]
+    fn test_apply_move_cursor() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let cursor_id = state.cursors.primary_id();
+
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "hello".to_string(),
+            cursor_id,
+        });
+
+        state.apply(&Event::MoveCursor {
+            cursor_id,
+            old_position: 5,
+            new_position: 2,
+            old_anchor: None,
+            new_anchor: None,
+            old_sticky_column: 0,
+            new_sticky_column: 0,
+        });
+
+        assert_eq!(state.cursors.primary().position, 2);
+    }
+
+    #[test]
+    fn test_apply_add_cursor() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let cursor_id = CursorId(1);
+
+        state.apply(&Event::AddCursor {
+            cursor_id,
+            position: 5,
+            anchor: None,
+        });
+
+        assert_eq!(state.cursors.count(), 2);
+    }
+
+    #[test]
+    fn test_apply_many() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let cursor_id = state.cursors.primary_id();
+
+        let events = vec![
+            Event::Insert {
+                position: 0,
+                text: "hello ".to_string(),
+                cursor_id,
+            },
+            Event::Insert {
+                position: 6,
+                text: "world".to_string(),
+                cursor_id,
+            },
+        ];
+
+        state.apply_many(&events);
+
+        assert_eq!(state.buffer.to_string().unwrap(), "hello world");
+    }
+
+    #[test]
+    fn test_cursor_adjustment_after_insert() {
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let cursor_id = state.cursors.primary_id();
+
+        // Add a second cursor at position 5
+        state.apply(&Event::AddCursor {
+            cursor_id: CursorId(1),
+            position: 5,
+            anchor: None,
+        });
+
+        // Insert at position 0 - should push second cursor forward
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "abc".to_string(),
+            cursor_id,
+        });
+
+        // Second cursor should be at position 5 + 3 = 8
+        if let Some(cursor) = state.cursors.get(CursorId(1)) {
+            assert_eq!(cursor.position, 8);
+        }
+    }
+
+    #[test]
+    fn test_diagnostics_follow_edits() {
+        use crate::model::diagnostic::DiagnosticSeverity;
+
+        let mut state =
+            EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+        let cursor_id = state.cursors.primary_id();
+
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "let x = foo;\nlet y = bar;".to_string(),
+            cursor_id,
+        });
+        state.add_diagnostic(Diagnostic::new(
+            8..11,
+            DiagnosticSeverity::Error,
+            "unknown foo",
+        ));
+        state.add_diagnostic(Diagnostic::new(
+            21..24,
+            DiagnosticSeverity::Warning,
+            "unknown bar",
+        ));
+
+        // Inserting at the start shifts both diagnostics forward
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "// \n".to_string(),
+            cursor_id,
+        });
+        let ranges: Vec<_> = state
+            .diagnostics_in(0..usize::MAX)
+            .map(|d| d.range.clone())
+            .collect();
+        assert_eq!(ranges, vec![12..15, 25..28]);
+
+        // Deleting "foo" drops the first diagnostic
+        state.apply(&Event::Delete {
+            range: 12..15,
+            deleted_text: "foo".to_string(),
+            cursor_id,
+        });
+        let remaining: Vec<_> = state.diagnostics_in(0..state.buffer.len()).collect();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].message, "unknown bar");
+        assert_eq!(remaining[0].range, 22..25);
+
+        state.clear_diagnostics();
+        assert_eq!(state.diagnostics_in(0..usize::MAX).count(), 0);
+    }
+
+    // DocumentModel trait tests
+    mod document_model_tests {
+        use super::*;
+        use crate::model::document_model::{DocumentModel, DocumentPosition};
+
+        #[test]
+        fn test_capabilities_small_file() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("line1\nline2\nline3");
+
+            let caps = state.capabilities();
+            assert!(caps.has_line_index, "Small file should have line index");
+            assert_eq!(caps.byte_length, "line1\nline2\nline3".len());
+            assert_eq!(caps.approximate_line_count, 3, "Should have 3 lines");
+        }
+
+        #[test]
+        fn test_position_conversions() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello\nworld\ntest");
+
+            // Test ByteOffset -> offset
+            let pos1 = DocumentPosition::ByteOffset(6);
+            let offset1 = state.position_to_offset(pos1).unwrap();
+            assert_eq!(offset1, 6);
+
+            // Test LineColumn -> offset
+            let pos2 = DocumentPosition::LineColumn { line: 1, column: 0 };
+            let offset2 = state.position_to_offset(pos2).unwrap();
+            assert_eq!(offset2, 6, "Line 1, column 0 should be at byte 6");
+
+            // Test offset -> position (should return LineColumn for small files)
+            let converted = state.offset_to_position(6);
+            match converted {
+                DocumentPosition::LineColumn { line, column } => {
+                    assert_eq!(line, 1);
+                    assert_eq!(column, 0);
+                }
+                _ => panic!("Expected LineColumn for small file"),
+            }
+        }
+
+        #[test]
+        fn test_get_viewport_content() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("line1\nline2\nline3\nline4\nline5");
+
+            let content = state
+                .get_viewport_content(DocumentPosition::ByteOffset(0), 3)
+                .unwrap();
+
+            assert_eq!(content.lines.len(), 3);
+            assert_eq!(content.lines[0].content, "line1");
+            assert_eq!(content.lines[1].content, "line2");
+            assert_eq!(content.lines[2].content, "line3");
+            assert!(content.has_more);
+        }
+
+        #[test]
+        fn test_get_range() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world");
+
+            let text = state
+                .get_range(
+                    DocumentPosition::ByteOffset(0),
+                    DocumentPosition::ByteOffset(5),
+                )
+                .unwrap();
+            assert_eq!(text, "hello");
+
+            let text2 = state
+                .get_range(
+                    DocumentPosition::ByteOffset(6),
+                    DocumentPosition::ByteOffset(11),
+                )
+                .unwrap();
+            assert_eq!(text2, "world");
+        }
+
+        #[test]
+        fn test_get_line_content() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("line1\nline2\nline3");
+
+            let line0 = state.get_line_content(0).unwrap();
+            assert_eq!(line0, "line1");
+
+            let line1 = state.get_line_content(1).unwrap();
+            assert_eq!(line1, "line2");
+
+            let line2 = state.get_line_content(2).unwrap();
+            assert_eq!(line2, "line3");
+        }
+
+        #[test]
+        fn test_insert_delete() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world");
+
+            // Insert text
+            let bytes_inserted = state
+                .insert(DocumentPosition::ByteOffset(6), "beautiful ")
+                .unwrap();
+            assert_eq!(bytes_inserted, 10);
+            assert_eq!(state.buffer.to_string().unwrap(), "hello beautiful world");
+
+            // Delete text
+            state
+                .delete(
+                    DocumentPosition::ByteOffset(6),
+                    DocumentPosition::ByteOffset(16),
+                )
+                .unwrap();
+            assert_eq!(state.buffer.to_string().unwrap(), "hello world");
+        }
+
+        #[test]
+        fn test_replace() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world");
+
+            state
+                .replace(
+                    DocumentPosition::ByteOffset(0),
+                    DocumentPosition::ByteOffset(5),
+                    "hi",
+                )
+                .unwrap();
+            assert_eq!(state.buffer.to_string().unwrap(), "hi world");
+        }
+
+        #[test]
+        fn test_find_matches() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world hello");
+
+            let matches = state.find_matches("hello", None).unwrap();
+            assert_eq!(matches.len(), 2);
+            assert_eq!(matches[0], 0);
+            assert_eq!(matches[1], 12);
+        }
+
+        #[test]
+        fn test_prepare_for_render() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("line1\nline2\nline3\nline4\nline5");
+
+            // Should not panic - pass top_byte=0 and height=24 (typical viewport params)
+            state.prepare_for_render(0, 24).unwrap();
+        }
+
+        #[test]
+        fn test_helper_get_text_range() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world");
+
+            // Test normal range
+            let text = state.get_text_range(0, 5);
+            assert_eq!(text, "hello");
+
+            // Test middle range
+            let text2 = state.get_text_range(6, 11);
+            assert_eq!(text2, "world");
+        }
+
+        #[test]
+        fn test_helper_get_line_at_offset() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("line1\nline2\nline3");
+
+            // Get first line (offset 0)
+            let (offset, content) = state.get_line_at_offset(0).unwrap();
+            assert_eq!(offset, 0);
+            assert_eq!(content, "line1");
+
+            // Get second line (offset in middle of line)
+            let (offset2, content2) = state.get_line_at_offset(8).unwrap();
+            assert_eq!(offset2, 6); // Line starts at byte 6
+            assert_eq!(content2, "line2");
+
+            // Get last line
+            let (offset3, content3) = state.get_line_at_offset(12).unwrap();
+            assert_eq!(offset3, 12);
+            assert_eq!(content3, "line3");
+        }
+
+        #[test]
+        fn test_helper_get_text_to_end_of_line() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world\nline2");
+
+            // From beginning of line
+            let text = state.get_text_to_end_of_line(0).unwrap();
+            assert_eq!(text, "hello world");
+
+            // From middle of line
+            let text2 = state.get_text_to_end_of_line(6).unwrap();
+            assert_eq!(text2, "world");
+
+            // From end of line
+            let text3 = state.get_text_to_end_of_line(11).unwrap();
+            assert_eq!(text3, "");
+
+            // From second line
+            let text4 = state.get_text_to_end_of_line(12).unwrap();
+            assert_eq!(text4, "line2");
+        }
+    }
+
+    // Virtual text integration tests
+    mod virtual_text_integration_tests {
+        use super::*;
+        use crate::view::virtual_text::VirtualTextPosition;
+        use ratatui::style::Style;
+
+        #[test]
+        fn test_virtual_text_add_and_query() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world");
+
+            // Initialize marker list for buffer
+            if state.buffer.len() > 0 {
+                state.marker_list.adjust_for_insert(0, state.buffer.len());
+            }
+
+            // Add virtual text at position 5 (after 'hello')
+            let vtext_id = state.virtual_texts.add(
+                &mut state.marker_list,
+                5,
+                ": string".to_string(),
+                Style::default(),
+                VirtualTextPosition::AfterChar,
+                0,
+            );
+
+            // Query should return the virtual text
+            let results = state.virtual_texts.query_range(&state.marker_list, 0, 11);
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].0, 5); // Position
+            assert_eq!(results[0].1.text, ": string");
+
+            // Build lookup should work
+            let lookup = state.virtual_texts.build_lookup(&state.marker_list, 0, 11);
+            assert!(lookup.contains_key(&5));
+            assert_eq!(lookup[&5].len(), 1);
+            assert_eq!(lookup[&5][0].text, ": string");
+
+            // Clean up
+            state.virtual_texts.remove(&mut state.marker_list, vtext_id);
+            assert!(state.virtual_texts.is_empty());
+        }
+
+        #[test]
+        fn test_virtual_text_position_tracking_on_insert() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello world");
+
+            // Initialize marker list for buffer
+            if state.buffer.len() > 0 {
+                state.marker_list.adjust_for_insert(0, state.buffer.len());
+            }
+
+            // Add virtual text at position 6 (the 'w' in 'world')
+            let _vtext_id = state.virtual_texts.add(
+                &mut state.marker_list,
+                6,
+                "/*param*/".to_string(),
+                Style::default(),
+                VirtualTextPosition::BeforeChar,
+                0,
+            );
+
+            // Insert "beautiful " at position 6 using Event
+            let cursor_id = state.cursors.primary_id();
+            state.apply(&Event::Insert {
+                position: 6,
+                text: "beautiful ".to_string(),
+                cursor_id,
+            });
+
+            // Virtual text should now be at position 16 (6 + 10)
+            let results = state.virtual_texts.query_range(&state.marker_list, 0, 30);
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].0, 16); // Position should have moved
+            assert_eq!(results[0].1.text, "/*param*/");
+        }
+
+        #[test]
+        fn test_virtual_text_position_tracking_on_delete() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("hello beautiful world");
+
+            // Initialize marker list for buffer
+            if state.buffer.len() > 0 {
+                state.marker_list.adjust_for_insert(0, state.buffer.len());
+            }
+
+            // Add virtual text at position 16 (the 'w' in 'world')
+            let _vtext_id = state.virtual_texts.add(
+                &mut state.marker_list,
+                16,
+                ": string".to_string(),
+                Style::default(),
+                VirtualTextPosition::AfterChar,
+                0,
+            );
+
+            // Delete "beautiful " (positions 6-16) using Event
+            let cursor_id = state.cursors.primary_id();
+            state.apply(&Event::Delete {
+                range: 6..16,
+                deleted_text: "beautiful ".to_string(),
+                cursor_id,
+            });
+
+            // Virtual text should now be at position 6
+            let results = state.virtual_texts.query_range(&state.marker_list, 0, 20);
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].0, 6); // Position should have moved back
+            assert_eq!(results[0].1.text, ": string");
+        }
+
+        #[test]
+        fn test_multiple_virtual_texts_with_priorities() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("let x = 5");
+
+            // Initialize marker list for buffer
+            if state.buffer.len() > 0 {
+                state.marker_list.adjust_for_insert(0, state.buffer.len());
+            }
+
+            // Add type hint after 'x' (position 5)
+            state.virtual_texts.add(
+                &mut state.marker_list,
+                5,
+                ": i32".to_string(),
+                Style::default(),
+                VirtualTextPosition::AfterChar,
+                0, // Lower priority - renders first
+            );
+
+            // Add another hint at same position with higher priority
+            state.virtual_texts.add(
+                &mut state.marker_list,
+                5,
+                " /* inferred */".to_string(),
+                Style::default(),
+                VirtualTextPosition::AfterChar,
+                10, // Higher priority - renders second
+            );
+
+            // Build lookup - should have both, sorted by priority (lower first)
+            let lookup = state.virtual_texts.build_lookup(&state.marker_list, 0, 10);
+            assert!(lookup.contains_key(&5));
+            let vtexts = &lookup[&5];
+            assert_eq!(vtexts.len(), 2);
+            // Lower priority first (like layer ordering)
+            assert_eq!(vtexts[0].text, ": i32");
+            assert_eq!(vtexts[1].text, " /* inferred */");
+        }
+
+        #[test]
+        fn test_virtual_text_clear() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("test");
+
+            // Initialize marker list for buffer
+            if state.buffer.len() > 0 {
+                state.marker_list.adjust_for_insert(0, state.buffer.len());
+            }
+
+            // Add multiple virtual texts
+            state.virtual_texts.add(
+                &mut state.marker_list,
+                0,
+                "hint1".to_string(),
+                Style::default(),
+                VirtualTextPosition::BeforeChar,
+                0,
+            );
+            state.virtual_texts.add(
+                &mut state.marker_list,
+                2,
+                "hint2".to_string(),
+                Style::default(),
+                VirtualTextPosition::AfterChar,
+                0,
+            );
+
+            assert_eq!(state.virtual_texts.len(), 2);
+
+            // Clear all
+            state.virtual_texts.clear(&mut state.marker_list);
+            assert!(state.virtual_texts.is_empty());
+
+            // Query should return nothing
+            let results = state.virtual_texts.query_range(&state.marker_list, 0, 10);
+            assert!(results.is_empty());
+        }
+
+        #[test]
+        fn test_line_anchored_virtual_texts_follow_insert_before_them() {
+            let mut state =
+                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
+            state.buffer = Buffer::from_str_test("fn a() {}\nlet x = ;\n");
+            state.marker_list.adjust_for_insert(0, state.buffer.len());
+
+            // An inline error at the end of line 2 and a header above it
+            state.virtual_texts.add(
+                &mut state.marker_list,
+                18,
+                "expected expression".to_string(),
+                Style::default(),
+                VirtualTextPosition::EndOfLine,
+                0,
+            );
+            state.virtual_texts.add(
+                &mut state.marker_list,
+                10,
+                "-- header --".to_string(),
+                Style::default(),
+                VirtualTextPosition::LineAbove,
+                0,
+            );
+
+            let cursor_id = state.cursors.primary_id();
+            state.apply(&Event::Insert {
+                position: 0,
+                text: "// doc\n".to_string(),
+                cursor_id,
+            });
+
+            let results = state.virtual_texts.query_range(&state.marker_list, 0, 40);
+            let anchors: Vec<_> = results
+                .iter()
+                .map(|(pos, vtext)| (*pos, vtext.position))
+                .collect();
+            assert_eq!(
+                anchors,
+                vec![
+                    (17, VirtualTextPosition::LineAbove),
+                    (25, VirtualTextPosition::EndOfLine)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_mark_follows_insert_before_it() {
+        let mut state = EditorState::from_str_test("one\ntwo\nthree\n");
+        let cursor_id = state.cursors.primary_id();
+        state.cursors.primary_mut().position = 4;
+        state.set_mark('a');
+
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "zero\n".to_string(),
+            cursor_id,
+        });
+        assert_eq!(state.mark('a'), Some(9));
+
+        // Inserting after the mark leaves it alone
+        state.apply(&Event::Insert {
+            position: 12,
+            text: "!".to_string(),
+            cursor_id,
+        });
+        assert_eq!(state.mark('a'), Some(9));
+
+        let event = state.goto_mark('a').unwrap();
+        state.apply(&event);
+        assert_eq!(state.cursors.primary().position, 9);
+        assert_eq!(state.get_text_range(9, 12), "two");
+    }
+
+    #[test]
+    fn test_mark_dropped_when_line_deleted() {
+        let mut state = EditorState::from_str_test("one\ntwo\nthree\n");
+        let cursor_id = state.cursors.primary_id();
+        state.cursors.primary_mut().position = 5;
+        state.set_mark('a');
+        state.cursors.primary_mut().position = 10;
+        state.set_mark('b');
+
+        // Delete the whole "two\n" line
+        state.apply(&Event::Delete {
+            range: 4..8,
+            deleted_text: "two\n".to_string(),
+            cursor_id,
+        });
+        assert_eq!(state.mark('a'), None);
+        assert!(state.goto_mark('a').is_none());
+        assert_eq!(state.mark('b'), Some(6));
+
+        // Deleting part of a line clamps the mark instead
+        state.apply(&Event::Delete {
+            range: 5..7,
+            deleted_text: "hr".to_string(),
             cursor_id,
         });
+        assert_eq!(state.mark('b'), Some(5));
+    }
 
-        // Second cursor should be at position 5 + 3 = 8
-        if let Some(cursor) = state.cursors.get(CursorId(1)) {
-            assert_eq!(cursor.position, 8);
-        }
+    #[test]
+    fn test_misspellings_in_viewport() {
+        use crate::primitives::spell_check::WordList;
+
+        let dictionary: WordList = ["this", "line", "is", "fine", "has", "an", "error"]
+            .into_iter()
+            .collect();
+        let mut state =
+            EditorState::from_str_test("This line is fine.\nThis one has an errror in_code.\n");
+        let viewport = Viewport::new(80, 24);
+
+        let ranges = state.misspellings(&viewport, &dictionary);
+        let words: Vec<_> = ranges
+            .iter()
+            .map(|r| state.get_text_range(r.start, r.end))
+            .collect();
+        assert_eq!(words, vec!["one", "errror"]);
+        assert_eq!(ranges[1], 35..41);
     }
 
-    // DocumentModel trait tests
-    mod document_model_tests {
-        use super::*;
-        use crate::model::document_model::{DocumentModel, DocumentPosition};
+    #[test]
+    fn test_misspellings_limited_to_viewport() {
+        use crate::primitives::spell_check::WordList;
 
-        #[test]
-        fn test_capabilities_small_file() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("line1\nline2\nline3");
+        let dictionary: WordList = ["fine"].into_iter().collect();
+        let text = format!("{}wrongg\n", "fine\n".repeat(40));
+        let state = EditorState::from_str_test(&text);
+        let mut viewport = Viewport::new(80, 10);
 
-            let caps = state.capabilities();
-            assert!(caps.has_line_index, "Small file should have line index");
-            assert_eq!(caps.byte_length, "line1\nline2\nline3".len());
-            assert_eq!(caps.approximate_line_count, 3, "Should have 3 lines");
-        }
+        assert!(state.misspellings(&viewport, &dictionary).is_empty());
 
-        #[test]
-        fn test_position_conversions() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello\nworld\ntest");
+        viewport.top_byte = 35 * 5;
+        assert_eq!(state.misspellings(&viewport, &dictionary), vec![200..206]);
+    }
 
-            // Test ByteOffset -> offset
-            let pos1 = DocumentPosition::ByteOffset(6);
-            let offset1 = state.position_to_offset(pos1).unwrap();
-            assert_eq!(offset1, 6);
+    #[test]
+    fn test_whitespace_markers_trailing_spaces() {
+        let state = EditorState::from_str_test("let x = 1;  \n\tfoo \t\nbar\n   \r\n");
+        let viewport = Viewport::new(80, 24);
+
+        let markers = state.whitespace_markers(&viewport, WhitespaceMode::Trailing);
+        assert_eq!(
+            markers,
+            vec![
+                (10, WsMarker::TrailingSpace),
+                (11, WsMarker::TrailingSpace),
+                (13, WsMarker::Tab),
+                (17, WsMarker::TrailingSpace),
+                (18, WsMarker::Tab),
+                // A whitespace-only line is all trailing, but its "\r\n" is not
+                (24, WsMarker::TrailingSpace),
+                (25, WsMarker::TrailingSpace),
+                (26, WsMarker::TrailingSpace),
+            ]
+        );
+    }
 
-            // Test LineColumn -> offset
-            let pos2 = DocumentPosition::LineColumn { line: 1, column: 0 };
-            let offset2 = state.position_to_offset(pos2).unwrap();
-            assert_eq!(offset2, 6, "Line 1, column 0 should be at byte 6");
+    #[test]
+    fn test_whitespace_markers_by_mode() {
+        let state = EditorState::from_str_test("\ta b \n");
+        let viewport = Viewport::new(80, 24);
+
+        assert!(state
+            .whitespace_markers(&viewport, WhitespaceMode::None)
+            .is_empty());
+        assert_eq!(
+            state.whitespace_markers(&viewport, WhitespaceMode::Tabs),
+            vec![(0, WsMarker::Tab)]
+        );
+        assert_eq!(
+            state.whitespace_markers(&viewport, WhitespaceMode::All),
+            vec![
+                (0, WsMarker::Tab),
+                (2, WsMarker::Space),
+                (4, WsMarker::TrailingSpace),
+            ]
+        );
+    }
 
-            // Test offset -> position (should return LineColumn for small files)
-            let converted = state.offset_to_position(6);
-            match converted {
-                DocumentPosition::LineColumn { line, column } => {
-                    assert_eq!(line, 1);
-                    assert_eq!(column, 0);
-                }
-                _ => panic!("Expected LineColumn for small file"),
-            }
-        }
+    #[test]
+    fn test_whitespace_markers_limited_to_viewport() {
+        let text: String = (0..10).map(|_| "x \n").collect();
+        let state = EditorState::from_str_test(&text);
+        let mut viewport = Viewport::new(80, 3);
+        viewport.top_byte = state.buffer.line_start_offset(4).unwrap();
+
+        let markers = state.whitespace_markers(&viewport, WhitespaceMode::Trailing);
+        let lines: Vec<usize> = markers
+            .iter()
+            .map(|&(offset, _)| state.buffer.get_line_number(offset))
+            .collect();
+        assert_eq!(lines, vec![4, 5, 6]);
+    }
 
-        #[test]
-        fn test_get_viewport_content() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("line1\nline2\nline3\nline4\nline5");
+    #[test]
+    fn test_stats_with_selection() {
+        let mut state = EditorState::from_str_test("héllo wörld\nsecond line here\n");
+        let stats = state.stats();
+        assert_eq!(stats.document.lines, 3);
+        assert_eq!(stats.document.words, 5);
+        assert_eq!(stats.document.bytes, 31);
+        assert_eq!(stats.document.chars, 29);
+        assert_eq!(stats.selection, None);
+
+        // Select "wörld\nsecond"
+        let cursor = state.cursors.primary_mut();
+        cursor.anchor = Some(7);
+        cursor.position = 20;
+        let selection = state.stats().selection.unwrap();
+        assert_eq!(
+            (selection.lines, selection.words, selection.chars),
+            (2, 2, 12)
+        );
+    }
 
-            let content = state
-                .get_viewport_content(DocumentPosition::ByteOffset(0), 3)
-                .unwrap();
+    fn press_smart_home(state: &mut EditorState) -> usize {
+        let events = state.smart_home();
+        state.apply_many(&events);
+        state.cursors.primary().position
+    }
 
-            assert_eq!(content.lines.len(), 3);
-            assert_eq!(content.lines[0].content, "line1");
-            assert_eq!(content.lines[1].content, "line2");
-            assert_eq!(content.lines[2].content, "line3");
-            assert!(content.has_more);
-        }
+    #[test]
+    fn test_smart_home_toggles() {
+        let mut state = EditorState::from_str_test("fn f() {\n    let x = 1;\n}\n");
+        state.cursors.primary_mut().position = 20;
+
+        assert_eq!(press_smart_home(&mut state), 13);
+        assert_eq!(press_smart_home(&mut state), 9);
+        assert_eq!(press_smart_home(&mut state), 13);
+
+        // From inside the indentation it goes forward to the first character
+        state.cursors.primary_mut().position = 11;
+        assert_eq!(press_smart_home(&mut state), 13);
+
+        // Without indentation both stops are column 0
+        state.cursors.primary_mut().position = 5;
+        assert_eq!(press_smart_home(&mut state), 0);
+        assert_eq!(press_smart_home(&mut state), 0);
+    }
 
-        #[test]
-        fn test_get_range() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world");
+    #[test]
+    fn test_smart_home_whitespace_only_line() {
+        let mut state = EditorState::from_str_test("a\n  \t\nb");
+        state.cursors.primary_mut().position = 2;
 
-            let text = state
-                .get_range(
-                    DocumentPosition::ByteOffset(0),
-                    DocumentPosition::ByteOffset(5),
-                )
-                .unwrap();
-            assert_eq!(text, "hello");
+        assert_eq!(press_smart_home(&mut state), 5);
+        assert_eq!(press_smart_home(&mut state), 2);
+    }
 
-            let text2 = state
-                .get_range(
-                    DocumentPosition::ByteOffset(6),
-                    DocumentPosition::ByteOffset(11),
-                )
-                .unwrap();
-            assert_eq!(text2, "world");
+    fn toggled(text: &str, lines: Range<usize>) -> String {
+        let mut state = EditorState::from_str_test(text);
+        if let Some(event) = state.toggle_comment(lines, "// ") {
+            state.apply(&event);
         }
+        state.buffer.to_string().unwrap()
+    }
 
-        #[test]
-        fn test_get_line_content() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("line1\nline2\nline3");
+    #[test]
+    fn test_toggle_comment_keeps_indentation() {
+        let text = "fn f() {\n    let x = 1;\n\n\tx\n}\n";
+        assert_eq!(
+            toggled(text, 0..5),
+            "// fn f() {\n    // let x = 1;\n\n\t// x\n// }\n"
+        );
+        // Only the requested lines are touched
+        assert_eq!(
+            toggled(text, 1..2),
+            "fn f() {\n    // let x = 1;\n\n\tx\n}\n"
+        );
+    }
 
-            let line0 = state.get_line_content(0).unwrap();
-            assert_eq!(line0, "line1");
+    #[test]
+    fn test_toggle_comment_uncomments() {
+        let text = "// a\n    // b\n\n    //c\n";
+        assert_eq!(toggled(text, 0..4), "a\n    b\n\n    c\n");
 
-            let line1 = state.get_line_content(1).unwrap();
-            assert_eq!(line1, "line2");
+        // Round trip
+        let text = "a\n  b\n";
+        assert_eq!(toggled(&toggled(text, 0..2), 0..2), text);
+    }
 
-            let line2 = state.get_line_content(2).unwrap();
-            assert_eq!(line2, "line3");
-        }
+    #[test]
+    fn test_toggle_comment_mixed_lines_are_commented() {
+        let text = "// a\nb\n";
+        assert_eq!(toggled(text, 0..2), "// // a\n// b\n");
 
-        #[test]
-        fn test_insert_delete() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world");
+        // Nothing but blank lines: no edit at all
+        let state = EditorState::from_str_test("\n   \n");
+        assert!(state.toggle_comment(0..2, "// ").is_none());
+    }
 
-            // Insert text
-            let bytes_inserted = state
-                .insert(DocumentPosition::ByteOffset(6), "beautiful ")
-                .unwrap();
-            assert_eq!(bytes_inserted, 10);
-            assert_eq!(state.buffer.to_string().unwrap(), "hello beautiful world");
+    #[test]
+    fn test_indent_and_dedent_with_spaces() {
+        let mut state = EditorState::from_str_test("a\n  b\n      c\nd\n");
+        // Select from "a" through "c"
+        state.cursors.primary_mut().anchor = Some(0);
+        state.cursors.primary_mut().position = 13;
+
+        let indent = state.indent(0..3, "    ").unwrap();
+        state.apply(&indent);
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "    a\n      b\n          c\nd\n"
+        );
+        // The selection still covers the same text
+        assert_eq!(state.cursors.primary().selection_range(), Some(4..25));
 
-            // Delete text
-            state
-                .delete(
-                    DocumentPosition::ByteOffset(6),
-                    DocumentPosition::ByteOffset(16),
-                )
-                .unwrap();
-            assert_eq!(state.buffer.to_string().unwrap(), "hello world");
-        }
+        let dedent = state.dedent(0..3, 4).unwrap();
+        state.apply(&dedent);
+        assert_eq!(state.buffer.to_string().unwrap(), "a\n  b\n      c\nd\n");
+        assert_eq!(state.cursors.primary().selection_range(), Some(0..13));
 
-        #[test]
-        fn test_replace() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world");
+        // A partial indent is removed entirely, a deeper one by a single level
+        let dedent = state.dedent(0..3, 4).unwrap();
+        state.apply(&dedent);
+        assert_eq!(state.buffer.to_string().unwrap(), "a\nb\n  c\nd\n");
 
-            state
-                .replace(
-                    DocumentPosition::ByteOffset(0),
-                    DocumentPosition::ByteOffset(5),
-                    "hi",
-                )
-                .unwrap();
-            assert_eq!(state.buffer.to_string().unwrap(), "hi world");
-        }
+        // Nothing left to remove on these lines
+        assert!(state.dedent(0..2, 4).is_none());
+    }
 
-        #[test]
-        fn test_find_matches() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world hello");
+    #[test]
+    fn test_paste_block_pads_short_lines_and_extends_buffer() {
+        let mut state = EditorState::from_str_test("alpha\nb\ngamma\n");
+        let block = ["12", "34", "56"].map(String::from);
+
+        let paste = state.paste_block(&block, 0, 3).unwrap();
+        state.apply(&paste);
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "alp12ha\nb  34\ngam56ma\n"
+        );
 
-            let matches = state.find_matches("hello", None).unwrap();
-            assert_eq!(matches.len(), 2);
-            assert_eq!(matches[0], 0);
-            assert_eq!(matches[1], 12);
-        }
+        // Past the last line the buffer grows, with the same padding
+        let paste = state.paste_block(&block, 2, 1).unwrap();
+        state.apply(&paste);
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "alp12ha\nb  34\ng12am56ma\n 34\n 56"
+        );
 
-        #[test]
-        fn test_prepare_for_render() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("line1\nline2\nline3\nline4\nline5");
+        assert!(state.paste_block(&[], 0, 0).is_none());
+    }
 
-            // Should not panic - pass top_byte=0 and height=24 (typical viewport params)
-            state.prepare_for_render(0, 24).unwrap();
-        }
+    #[test]
+    fn test_transform_case_selection_changes_length() {
+        let mut state = EditorState::from_str_test("die straße hier\n");
+        // Select "straße"
+        state.cursors.primary_mut().anchor = Some(4);
+        state.cursors.primary_mut().position = 11;
+
+        let upper = state.transform_case(CaseTransform::Upper).unwrap();
+        state.apply(&upper);
+        assert_eq!(state.buffer.to_string().unwrap(), "die STRASSE hier\n");
+        // The selection grew with the text
+        assert_eq!(state.cursors.primary().selection_range(), Some(4..11));
+        assert_eq!(state.cursors.primary().position, 11);
+
+        let lower = state.transform_case(CaseTransform::Lower).unwrap();
+        state.apply(&lower);
+        assert_eq!(state.buffer.to_string().unwrap(), "die strasse hier\n");
+
+        // Undo restores the original text
+        state.apply(&lower.inverse().unwrap());
+        state.apply(&upper.inverse().unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "die straße hier\n");
+    }
 
-        #[test]
-        fn test_helper_get_text_range() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world");
+    #[test]
+    fn test_sort_lines_keeps_selection_and_undoes() {
+        let mut state = EditorState::from_str_test("keep\n10\n9\n9\nlast");
+        // Select lines 1-3, ending at the start of line 4
+        state.cursors.primary_mut().anchor = Some(5);
+        state.cursors.primary_mut().position = 13;
+
+        let options = SortOptions {
+            numeric: true,
+            unique: true,
+            ..Default::default()
+        };
+        let sort = state.sort_lines(1..4, options).unwrap();
+        state.apply(&sort);
+        assert_eq!(state.buffer.to_string().unwrap(), "keep\n9\n10\nlast");
+        assert_eq!(state.cursors.primary().selection_range(), Some(5..10));
+        // Already in order
+        assert!(state.sort_lines(1..3, options).is_none());
+
+        state.apply(&sort.inverse().unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "keep\n10\n9\n9\nlast");
+
+        // The last line of the document has no line break to keep
+        let sort = state
+            .sort_lines(0..usize::MAX, SortOptions::default())
+            .unwrap();
+        state.apply(&sort);
+        assert_eq!(state.buffer.to_string().unwrap(), "10\n9\n9\nkeep\nlast");
+    }
 
-            // Test normal range
-            let text = state.get_text_range(0, 5);
-            assert_eq!(text, "hello");
+    #[test]
+    fn test_transform_case_word_under_cursors() {
+        let mut state = EditorState::from_str_test("élan vital_force x\n");
+        // Inside "vital_force" and right after "élan"
+        state.cursors.primary_mut().position = 8;
+        state.cursors.add(Cursor::new(5));
+
+        let title = state.transform_case(CaseTransform::Title).unwrap();
+        state.apply(&title);
+        assert_eq!(state.buffer.to_string().unwrap(), "Élan Vital_Force x\n");
+        let mut positions: Vec<usize> = state.cursors.iter().map(|(_, c)| c.position).collect();
+        positions.sort();
+        assert_eq!(positions, vec![5, 8]);
+
+        let toggle = state.transform_case(CaseTransform::Toggle).unwrap();
+        state.apply(&toggle);
+        assert_eq!(state.buffer.to_string().unwrap(), "éLAN vITAL_fORCE x\n");
+
+        // Nothing to change on punctuation-only lines
+        let state = EditorState::from_str_test("  ()\n");
+        assert!(state.transform_case(CaseTransform::Upper).is_none());
+    }
 
-            // Test middle range
-            let text2 = state.get_text_range(6, 11);
-            assert_eq!(text2, "world");
-        }
+    #[test]
+    fn test_join_lines_collapses_whitespace() {
+        let mut state = EditorState::from_str_test("let x = foo(  \n    a,\n\t  b\n) ;\nlast");
+        state.cursors.primary_mut().position = 3;
+
+        let join = state.join_lines(1).unwrap();
+        state.apply(&join);
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "let x = foo(a,\n\t  b\n) ;\nlast"
+        );
+        assert_eq!(state.cursors.primary().position, 12);
 
-        #[test]
-        fn test_helper_get_line_at_offset() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("line1\nline2\nline3");
+        let join = state.join_lines(2).unwrap();
+        state.apply(&join);
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "let x = foo(a, b) ;\nlast"
+        );
+        // At the last join point, before ")"
+        assert_eq!(state.cursors.primary().position, 16);
+
+        let join = state.join_lines(1).unwrap();
+        state.apply(&join);
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "let x = foo(a, b) ; last"
+        );
 
-            // Get first line (offset 0)
-            let (offset, content) = state.get_line_at_offset(0).unwrap();
-            assert_eq!(offset, 0);
-            assert_eq!(content, "line1");
+        // Nothing to join on the last line; undo restores the line break
+        assert!(state.join_lines(1).is_none());
+        state.apply(&join.inverse().unwrap());
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "let x = foo(a, b) ;\nlast"
+        );
+    }
 
-            // Get second line (offset in middle of line)
-            let (offset2, content2) = state.get_line_at_offset(8).unwrap();
-            assert_eq!(offset2, 6); // Line starts at byte 6
-            assert_eq!(content2, "line2");
+    #[test]
+    fn test_join_lines_with_blank_lines() {
+        let mut state = EditorState::from_str_test("one  \n\n   \r\n  two\nthree\n");
+        let join = state.join_lines(3).unwrap();
+        state.apply(&join);
+        assert_eq!(state.buffer.to_string().unwrap(), "one two\nthree\n");
+        assert_eq!(state.cursors.primary().position, 3);
+
+        // Joining onto a blank line adds no space
+        let mut state = EditorState::from_str_test("\nword\n");
+        state.apply(&state.join_lines(1).unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "word\n");
+        assert_eq!(state.cursors.primary().position, 0);
 
-            // Get last line
-            let (offset3, content3) = state.get_line_at_offset(12).unwrap();
-            assert_eq!(offset3, 12);
-            assert_eq!(content3, "line3");
-        }
+        // The empty line after a final newline doesn't count
+        let state = EditorState::from_str_test("only\n");
+        assert!(state.join_lines(1).is_none());
+    }
 
-        #[test]
-        fn test_helper_get_text_to_end_of_line() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world\nline2");
+    #[test]
+    fn test_matching_bracket_nested() {
+        let mut state = EditorState::from_str_test("{a{b{c}d}e}");
+        state.cursors.primary_mut().position = 2;
+        let event = state.goto_matching_bracket(LexicalSyntax::C).unwrap();
+        state.apply(&event);
+        assert_eq!(state.cursors.primary().position, 8);
+
+        let event = state.select_to_matching_bracket(LexicalSyntax::C).unwrap();
+        state.apply(&event);
+        let cursor = state.cursors.primary();
+        assert_eq!((cursor.anchor, cursor.position), (Some(9), 2));
+
+        // Unbalanced input leaves the cursor alone
+        let mut state = EditorState::from_str_test("{a{b}");
+        assert!(state.goto_matching_bracket(LexicalSyntax::C).is_none());
+    }
 
-            // From beginning of line
-            let text = state.get_text_to_end_of_line(0).unwrap();
-            assert_eq!(text, "hello world");
+    #[test]
+    fn test_matching_bracket_skips_string() {
+        let text = r#"call(")", x)"#;
+        let mut state = EditorState::from_str_test(text);
+
+        // Right after the closing paren
+        state.cursors.primary_mut().position = text.len();
+        let event = state.select_to_matching_bracket(LexicalSyntax::C).unwrap();
+        state.apply(&event);
+        let cursor = state.cursors.primary();
+        assert_eq!((cursor.anchor, cursor.position), (Some(12), 4));
+
+        let event = state.goto_matching_bracket(LexicalSyntax::C).unwrap();
+        state.apply(&event);
+        assert_eq!(state.cursors.primary().position, 11);
+
+        // The paren inside the string is not a bracket
+        state.cursors.primary_mut().position = 6;
+        assert!(state.goto_matching_bracket(LexicalSyntax::C).is_none());
+    }
+
+    #[test]
+    fn test_matching_bracket_in_large_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("big.rs");
+        let body = "    let s = \"}\";\n".repeat(20_000);
+        let text = format!("fn main() {{\n{body}}}\n");
+        std::fs::write(&path, &text).unwrap();
+
+        let registry = GrammarRegistry::load();
+        let mut state = EditorState::from_file(&path, 80, 24, 4096, &registry).unwrap();
+        assert!(state.is_large());
+        state.cursors.primary_mut().position = 10;
+        let event = state.goto_matching_bracket(LexicalSyntax::C).unwrap();
+        state.apply(&event);
+        assert_eq!(state.cursors.primary().position, text.len() - 2);
+    }
+
+    #[test]
+    fn test_over_limit_ranges_expand_tabs() {
+        let state = EditorState::from_str_test("\tlet x = 1;\nshort\nab\tcdefg\n");
+        // The leading tab is 4 columns wide, so "x" is the 9th column
+        assert_eq!(state.over_limit_ranges(0..10, 8, 4), vec![5..11, 25..26]);
+        // With 8-column tabs the first line is over from "let" on, and the
+        // tab in the last line ends exactly at the limit
+        assert_eq!(state.over_limit_ranges(0..3, 8, 8), vec![1..11, 21..26]);
+        assert!(state.over_limit_ranges(1..2, 8, 4).is_empty());
+
+        // A tab straddling the limit is over it
+        let state = EditorState::from_str_test("abcdef\tz");
+        assert_eq!(state.over_limit_ranges(0..1, 7, 4), vec![6..8]);
+    }
+
+    #[test]
+    fn test_paragraph_motion() {
+        // Lines: "one", "two", "", "", "three", "  ", "four"
+        let mut state = EditorState::from_str_test("one\ntwo\n\n\nthree\n  \nfour\n");
+        let step = |state: &mut EditorState, forward: bool| {
+            let events = if forward {
+                state.move_paragraph_forward()
+            } else {
+                state.move_paragraph_backward()
+            };
+            state.apply_many(&events);
+            state.cursors.primary().position
+        };
+
+        // Consecutive blank lines are one gap; whitespace-only lines are blank
+        assert_eq!(step(&mut state, true), 8);
+        assert_eq!(step(&mut state, true), 16);
+        assert_eq!(step(&mut state, true), 24);
+        // Stays put at the end of the document
+        assert_eq!(step(&mut state, true), 24);
+
+        assert_eq!(step(&mut state, false), 16);
+        assert_eq!(step(&mut state, false), 9);
+        assert_eq!(step(&mut state, false), 0);
+        assert_eq!(step(&mut state, false), 0);
+
+        // From the middle of a paragraph
+        state.cursors.primary_mut().position = 12;
+        assert_eq!(step(&mut state, true), 16);
+    }
+
+    #[test]
+    fn test_select_paragraph() {
+        let mut state = EditorState::from_str_test("one\ntwo\n\n\nthree\n  \nfour");
+        let mut select_at = |position: usize| {
+            state.cursors.primary_mut().position = position;
+            state.cursors.primary_mut().anchor = None;
+            state.apply(&state.select_paragraph().unwrap());
+            let cursor = state.cursors.primary();
+            (cursor.anchor.unwrap(), cursor.position)
+        };
 
-            // From middle of line
-            let text2 = state.get_text_to_end_of_line(6).unwrap();
-            assert_eq!(text2, "world");
+        assert_eq!(select_at(5), (0, 8));
+        assert_eq!(select_at(12), (10, 16));
+        // On a gap, the run of blank lines
+        assert_eq!(select_at(9), (8, 10));
+        // The last paragraph runs to the end of the document
+        assert_eq!(select_at(21), (19, 23));
 
-            // From end of line
-            let text3 = state.get_text_to_end_of_line(11).unwrap();
-            assert_eq!(text3, "");
+        assert!(EditorState::from_str_test("").select_paragraph().is_none());
+    }
 
-            // From second line
-            let text4 = state.get_text_to_end_of_line(12).unwrap();
-            assert_eq!(text4, "line2");
-        }
+    #[test]
+    fn test_sticky_context_nested_blocks() {
+        let state = EditorState::from_str_test(
+            "mod outer {\n    fn first() {\n        if x {\n            a();\n\n            b();\n        }\n    }\n\n    fn second() {\n\tc();\n    }\n}\n",
+        );
+        assert_eq!(state.sticky_context(5, 5, 4), vec![0, 1, 2]);
+        // A blank top line takes the indentation of the line below it
+        assert_eq!(state.sticky_context(4, 5, 4), vec![0, 1, 2]);
+        // The closing brace belongs to the function, not the `if`
+        assert_eq!(state.sticky_context(6, 5, 4), vec![0, 1]);
+        // Only the outermost headers fit
+        assert_eq!(state.sticky_context(5, 2, 4), vec![0, 1]);
+        // With 8-column tabs the tab-indented body sits inside `second`
+        assert_eq!(state.sticky_context(10, 5, 8), vec![0, 9]);
+        assert!(state.sticky_context(0, 5, 4).is_empty());
+        assert!(state.sticky_context(20, 5, 4).is_empty());
     }
 
-    // Virtual text integration tests
-    mod virtual_text_integration_tests {
-        use super::*;
-        use crate::view::virtual_text::VirtualTextPosition;
-        use ratatui::style::Style;
+    #[test]
+    fn test_indent_fold_ranges_by_level() {
+        let state = EditorState::from_str_test(
+            "mod outer {\n    fn first() {\n        if x {\n            a();\n\n            b();\n        }\n    }\n\n    fn second() {\n\tc();\n    }\n}\nfn last() {\n    d();\n}\n",
+        );
+        // Every top-level block folds down to its header and closing brace
+        assert_eq!(state.indent_fold_ranges(1, 4), vec![1..12, 14..15]);
+        // With 4-column tabs `second` has no body; with 8-column tabs it does
+        assert_eq!(state.indent_fold_ranges(2, 4), vec![2..7]);
+        assert_eq!(state.indent_fold_ranges(2, 8), vec![2..7, 10..11]);
+        // The blank line inside the `if` is folded with it
+        assert_eq!(state.indent_fold_ranges(3, 4), vec![3..6]);
+        assert!(state.indent_fold_ranges(4, 4).is_empty());
+        assert!(state.indent_fold_ranges(0, 4).is_empty());
+        assert!(EditorState::from_str_test("")
+            .indent_fold_ranges(1, 4)
+            .is_empty());
+    }
 
-        #[test]
-        fn test_virtual_text_add_and_query() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world");
+    #[test]
+    fn test_surround_multiline_selection_in_braces() {
+        let mut state = EditorState::from_str_test("let x = a +\n    b;\n");
+        // Select "a +\n    b" from the end back to the start
+        let cursor = state.cursors.primary_mut();
+        cursor.position = 8;
+        cursor.anchor = Some(17);
+
+        let wrap = state.surround("{", "}").unwrap();
+        state.apply(&wrap);
+        assert_eq!(state.buffer.to_string().unwrap(), "let x = {a +\n    b};\n");
+        let cursor = state.cursors.primary();
+        assert_eq!((cursor.position, cursor.anchor), (9, Some(18)));
+
+        state.apply(&wrap.inverse().unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "let x = a +\n    b;\n");
+        let cursor = state.cursors.primary();
+        assert_eq!((cursor.position, cursor.anchor), (8, Some(17)));
+
+        // Nothing is wrapped without a selection
+        state.cursors.primary_mut().anchor = None;
+        assert!(state.surround("{", "}").is_none());
+    }
 
-            // Initialize marker list for buffer
-            if state.buffer.len() > 0 {
-                state.marker_list.adjust_for_insert(0, state.buffer.len());
-            }
+    #[test]
+    fn test_surround_multiple_selections() {
+        let mut state = EditorState::from_str_test("one two\nthree\n");
+        let cursor = state.cursors.primary_mut();
+        cursor.position = 3;
+        cursor.anchor = Some(0);
+        let second = Cursor::with_selection(8, 13);
+        state.cursors.add(second);
+
+        state.apply(&state.surround("<<", ">>").unwrap());
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "<<one>> two\n<<three>>\n"
+        );
+        let mut selected: Vec<_> = state
+            .cursors
+            .iter()
+            .map(|(_, cursor)| cursor.selection_range().unwrap())
+            .collect();
+        selected.sort_by_key(|range| range.start);
+        assert_eq!(selected, vec![2..5, 14..19]);
+    }
 
-            // Add virtual text at position 5 (after 'hello')
-            let vtext_id = state.virtual_texts.add(
-                &mut state.marker_list,
-                5,
-                ": string".to_string(),
-                Style::default(),
-                VirtualTextPosition::AfterChar,
-                0,
-            );
+    #[test]
+    fn test_soft_tab_reaches_next_tab_stop() {
+        let state = EditorState::from_str_test("ab\tc\n    x\nhéllo\n");
+        assert_eq!(state.soft_tab(0, 4), "    ");
+        assert_eq!(state.soft_tab(1, 4), "   ");
+        assert_eq!(state.soft_tab(2, 4), "  ");
+        // After the tab the column is 4, a tab stop: a full tab again
+        assert_eq!(state.soft_tab(3, 4), "    ");
+        assert_eq!(state.soft_tab(4, 4), "   ");
+        assert_eq!(state.soft_tab(7, 8), "      ");
+        // Columns count characters, not bytes
+        assert_eq!(state.soft_tab(15, 4), " ");
+    }
 
-            // Query should return the virtual text
-            let results = state.virtual_texts.query_range(&state.marker_list, 0, 11);
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].0, 5); // Position
-            assert_eq!(results[0].1.text, ": string");
+    #[test]
+    fn test_soft_tab_backspace_start() {
+        let state = EditorState::from_str_test("      x\n   y\nab  z\n");
+        let spaces = IndentStyle::Spaces(4);
+        // A partial level goes back to the previous stop, a full one all the way
+        assert_eq!(state.soft_tab_backspace_start(6, spaces), Some(4));
+        assert_eq!(state.soft_tab_backspace_start(4, spaces), Some(0));
+        assert_eq!(state.soft_tab_backspace_start(3, spaces), Some(0));
+        // A single space is just a normal backspace
+        assert_eq!(state.soft_tab_backspace_start(5, spaces), None);
+        assert_eq!(state.soft_tab_backspace_start(11, spaces), Some(8));
+        // Not in the indentation
+        assert_eq!(state.soft_tab_backspace_start(7, spaces), None);
+        assert_eq!(state.soft_tab_backspace_start(17, spaces), None);
+        // Spaces aren't soft tabs in a file indented with tabs
+        assert_eq!(state.soft_tab_backspace_start(6, IndentStyle::Tabs), None);
+    }
+    #[test]
+    fn test_indent_and_dedent_with_tabs() {
+        let mut state = EditorState::from_str_test("x\n\ty\n  \tz");
 
-            // Build lookup should work
-            let lookup = state.virtual_texts.build_lookup(&state.marker_list, 0, 11);
-            assert!(lookup.contains_key(&5));
-            assert_eq!(lookup[&5].len(), 1);
-            assert_eq!(lookup[&5][0].text, ": string");
+        let indent = state.indent(0..3, "\t").unwrap();
+        state.apply(&indent);
+        assert_eq!(state.buffer.to_string().unwrap(), "\tx\n\t\ty\n\t  \tz");
 
-            // Clean up
-            state.virtual_texts.remove(&mut state.marker_list, vtext_id);
-            assert!(state.virtual_texts.is_empty());
-        }
+        let dedent = state.dedent(0..3, 4).unwrap();
+        state.apply(&dedent);
+        assert_eq!(state.buffer.to_string().unwrap(), "x\n\ty\n  \tz");
 
-        #[test]
-        fn test_virtual_text_position_tracking_on_insert() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello world");
+        // Spaces followed by a tab reach the next stop: one level together
+        let dedent = state.dedent(0..3, 4).unwrap();
+        state.apply(&dedent);
+        assert_eq!(state.buffer.to_string().unwrap(), "x\ny\nz");
 
-            // Initialize marker list for buffer
-            if state.buffer.len() > 0 {
-                state.marker_list.adjust_for_insert(0, state.buffer.len());
+        // Lines past the end of the buffer are ignored
+        assert!(state.indent(5..8, "\t").is_none());
+    }
+
+    #[test]
+    fn test_large_file_opens_without_highlighting_or_line_index() {
+        use crate::input::actions::action_to_events;
+        use crate::input::keybindings::Action;
+        use std::io::Write;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("big.rs");
+        let mut file = std::fs::File::create(&path).unwrap();
+        for i in 0..5000 {
+            writeln!(file, "fn f{i}() {{ let x = {i}; }}").unwrap();
+        }
+        drop(file);
+
+        let registry = GrammarRegistry::load();
+        let mut state = EditorState::from_file(&path, 80, 24, 4096, &registry).unwrap();
+        assert!(state.is_large());
+        assert!(!state.highlighter.has_highlighting());
+        assert_eq!(state.buffer.line_count(), None);
+
+        // Moving through the file and scrolling to it work on demand
+        for action in [Action::MoveDown, Action::MoveDown, Action::MoveLineEnd] {
+            for event in action_to_events(&mut state, action, 4, None, true, 80, 24).unwrap() {
+                state.apply(&event);
             }
+        }
+        let second_line_end = "fn f0() { let x = 0; }\nfn f1() { let x = 1; }\n".len() + 22;
+        assert_eq!(state.cursors.primary().position, second_line_end);
 
-            // Add virtual text at position 6 (the 'w' in 'world')
-            let _vtext_id = state.virtual_texts.add(
-                &mut state.marker_list,
-                6,
-                "/*param*/".to_string(),
-                Style::default(),
-                VirtualTextPosition::BeforeChar,
-                0,
-            );
+        let mut viewport = Viewport::new(80, 10);
+        viewport.line_wrap_enabled = true;
+        assert!(!viewport.wraps(&state.buffer));
+        let far = state.buffer.len() / 2;
+        viewport.ensure_visible(&mut state.buffer, &Cursor::new(far));
+        assert!(viewport.top_byte > 0 && viewport.top_byte <= far);
 
-            // Insert "beautiful " at position 6 using Event
-            let cursor_id = state.cursors.primary_id();
-            state.apply(&Event::Insert {
-                position: 6,
-                text: "beautiful ".to_string(),
-                cursor_id,
-            });
+        // Editing still works, and still without indexing every line
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "// big\n".to_string(),
+            cursor_id: state.cursors.primary_id(),
+        });
+        assert_eq!(state.get_text_range(0, 10), "// big\nfn ");
+        assert_eq!(state.buffer.line_count(), None);
+    }
 
-            // Virtual text should now be at position 16 (6 + 10)
-            let results = state.virtual_texts.query_range(&state.marker_list, 0, 30);
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].0, 16); // Position should have moved
-            assert_eq!(results[0].1.text, "/*param*/");
-        }
+    #[test]
+    fn test_edits_move_registered_intervals() {
+        let mut state = EditorState::from_str_test("hello world");
+        let id = state.marker_list.insert(6..11, "word");
+        state.apply(&Event::Insert {
+            position: 0,
+            text: ">> ".to_string(),
+            cursor_id: state.cursors.primary_id(),
+        });
+        state.apply(&Event::Delete {
+            range: 3..9,
+            deleted_text: "hello ".to_string(),
+            cursor_id: state.cursors.primary_id(),
+        });
+        assert_eq!(state.marker_list.get::<&str>(id), Some((3..8, &"word")));
+    }
 
-        #[test]
-        fn test_virtual_text_position_tracking_on_delete() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("hello beautiful world");
+    #[test]
+    fn test_detect_indentation_spaces() {
+        let default = IndentStyle::Tabs;
+        let state = EditorState::from_str_test(
+            "fn main() {\n  if x {\n    y();\n\n    /*\n     * note\n     */\n  }\n  z();\n}\n",
+        );
+        assert_eq!(state.detect_indentation(default), IndentStyle::Spaces(2));
+
+        let state = EditorState::from_str_test("a:\n    b:\n        c\n    d\n");
+        assert_eq!(state.detect_indentation(default), IndentStyle::Spaces(4));
+
+        // Detected spaces indent and dedent by their own width
+        let mut state = EditorState::from_str_test("a\n  b\n");
+        let style = state.detect_indentation(default);
+        state.apply(&state.indent(0..2, &style.unit()).unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "  a\n    b\n");
+        state.apply(&state.dedent(0..2, style.width(8)).unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "a\n  b\n");
+    }
 
-            // Initialize marker list for buffer
-            if state.buffer.len() > 0 {
-                state.marker_list.adjust_for_insert(0, state.buffer.len());
-            }
+    #[test]
+    fn test_detect_indentation_tabs() {
+        let default = IndentStyle::Spaces(4);
+        let state = EditorState::from_str_test("int f() {\n\tif (x) {\n\t\ty();\n\t}\n}\n");
+        assert_eq!(state.detect_indentation(default), IndentStyle::Tabs);
+        assert_eq!(IndentStyle::Tabs.unit(), "\t");
+        assert_eq!(IndentStyle::Tabs.width(8), 8);
+
+        // Unindented or evenly mixed files fall back to the default
+        let state = EditorState::from_str_test("a\nb\n");
+        assert_eq!(state.detect_indentation(default), default);
+        let state = EditorState::from_str_test("a\n\tb\nc\n  d\n");
+        assert_eq!(state.detect_indentation(default), default);
+    }
 
-            // Add virtual text at position 16 (the 'w' in 'world')
-            let _vtext_id = state.virtual_texts.add(
-                &mut state.marker_list,
-                16,
-                ": string".to_string(),
-                Style::default(),
-                VirtualTextPosition::AfterChar,
-                0,
-            );
+    #[test]
+    fn test_detected_indentation_is_redetected_after_edits() {
+        let default = IndentStyle::Spaces(4);
+        let mut state = EditorState::from_str_test("a\n\tb\n");
+        assert_eq!(state.detect_indentation(default), IndentStyle::Tabs);
 
-            // Delete "beautiful " (positions 6-16) using Event
-            let cursor_id = state.cursors.primary_id();
-            state.apply(&Event::Delete {
-                range: 6..16,
-                deleted_text: "beautiful ".to_string(),
-                cursor_id,
-            });
+        state.apply(&Event::Delete {
+            range: 2..3,
+            deleted_text: "\t".to_string(),
+            cursor_id: CursorId(0),
+        });
+        assert_eq!(state.detect_indentation(default), default);
 
-            // Virtual text should now be at position 6
-            let results = state.virtual_texts.query_range(&state.marker_list, 0, 20);
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].0, 6); // Position should have moved back
-            assert_eq!(results[0].1.text, ": string");
+        state.apply(&Event::Insert {
+            position: 2,
+            text: "  ".to_string(),
+            cursor_id: CursorId(0),
+        });
+        assert_eq!(state.detect_indentation(default), IndentStyle::Spaces(2));
+    }
+
+    #[test]
+    fn test_transpose_chars_mid_word() {
+        let mut state = EditorState::from_str_test("hlelo\nwrold");
+        state.cursors.primary_mut().position = 2;
+        state.apply(&state.transpose_chars().unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "hello\nwrold");
+        assert_eq!(state.cursors.primary().position, 3);
+
+        // Multi-byte characters swap whole
+        let mut state = EditorState::from_str_test("aéb");
+        state.cursors.primary_mut().position = 3;
+        state.apply(&state.transpose_chars().unwrap());
+        assert_eq!(state.buffer.to_string().unwrap(), "abé");
+        assert_eq!(state.cursors.primary().position, 4);
+
+        // Never across a line break or at the ends of the buffer
+        let mut state = EditorState::from_str_test("ab\ncd");
+        for position in [0, 2, 3, 5] {
+            state.cursors.primary_mut().position = position;
+            assert!(state.transpose_chars().is_none(), "at {position}");
         }
+    }
 
-        #[test]
-        fn test_multiple_virtual_texts_with_priorities() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("let x = 5");
+    #[test]
+    fn test_transpose_words_across_whitespace() {
+        let mut state = EditorState::from_str_test("one  two\tthree\nfour five");
+        // Inside "two": swaps it with the next word, keeping the separator
+        state.cursors.primary_mut().position = 6;
+        state.apply(&state.transpose_words().unwrap());
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "one  three\ttwo\nfour five"
+        );
+        assert_eq!(state.cursors.primary().position, 14);
 
-            // Initialize marker list for buffer
-            if state.buffer.len() > 0 {
-                state.marker_list.adjust_for_insert(0, state.buffer.len());
-            }
+        // At the end of the line the last two words swap; the next line stays
+        state.apply(&state.transpose_words().unwrap());
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "one  two\tthree\nfour five"
+        );
 
-            // Add type hint after 'x' (position 5)
-            state.virtual_texts.add(
-                &mut state.marker_list,
-                5,
-                ": i32".to_string(),
-                Style::default(),
-                VirtualTextPosition::AfterChar,
-                0, // Lower priority - renders first
-            );
+        // At the start of the line the first two words swap
+        state.cursors.primary_mut().position = 15;
+        state.apply(&state.transpose_words().unwrap());
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "one  two\tthree\nfive four"
+        );
+        assert_eq!(state.cursors.primary().position, 24);
 
-            // Add another hint at same position with higher priority
-            state.virtual_texts.add(
-                &mut state.marker_list,
-                5,
-                " /* inferred */".to_string(),
-                Style::default(),
-                VirtualTextPosition::AfterChar,
-                10, // Higher priority - renders second
-            );
+        let state = EditorState::from_str_test("  single  \n");
+        assert!(state.transpose_words().is_none());
+    }
 
-            // Build lookup - should have both, sorted by priority (lower first)
-            let lookup = state.virtual_texts.build_lookup(&state.marker_list, 0, 10);
-            assert!(lookup.contains_key(&5));
-            let vtexts = &lookup[&5];
-            assert_eq!(vtexts.len(), 2);
-            // Lower priority first (like layer ordering)
-            assert_eq!(vtexts[0].text, ": i32");
-            assert_eq!(vtexts[1].text, " /* inferred */");
-        }
+    #[test]
+    fn test_paste_reindent_into_deeper_block() {
+        let block = "  if x {\n      y();\n\n  }\n";
+        let mut state = EditorState::from_str_test("fn f() {\n        \n}");
+        state.cursors.primary_mut().position = 17;
+        state.apply(&state.paste_reindent(block).unwrap());
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "fn f() {\n        if x {\n            y();\n\n        }\n        \n}"
+        );
+        assert_eq!(state.cursors.primary().position, 60);
+
+        // After text on the line: a first line copied mid-line has no indent
+        // and the line's own indentation is the target
+        let mut state = EditorState::from_str_test("\tlet a = \n");
+        state.cursors.primary_mut().position = 9;
+        state.apply(&state.paste_reindent("vec![\n        1,\n    ];").unwrap());
+        assert_eq!(
+            state.buffer.to_string().unwrap(),
+            "\tlet a = vec![\n\t    1,\n\t];\n"
+        );
 
-        #[test]
-        fn test_virtual_text_clear() {
-            let mut state =
-                EditorState::new(80, 24, crate::config::LARGE_FILE_THRESHOLD_BYTES as usize);
-            state.buffer = Buffer::from_str_test("test");
+        assert!(state.paste_reindent("").is_none());
+    }
 
-            // Initialize marker list for buffer
-            if state.buffer.len() > 0 {
-                state.marker_list.adjust_for_insert(0, state.buffer.len());
-            }
+    #[test]
+    fn test_cursor_shape_follows_mode() {
+        let mut state = EditorState::from_str_test("abc");
+        assert_eq!(state.cursor_shape(), CursorShape::Bar);
+        state.overwrite = true;
+        assert_eq!(state.cursor_shape(), CursorShape::Block);
+        state.editing_disabled = true;
+        assert_eq!(state.cursor_shape(), CursorShape::Underline);
+    }
 
-            // Add multiple virtual texts
-            state.virtual_texts.add(
-                &mut state.marker_list,
-                0,
-                "hint1".to_string(),
-                Style::default(),
-                VirtualTextPosition::BeforeChar,
-                0,
-            );
-            state.virtual_texts.add(
-                &mut state.marker_list,
-                2,
-                "hint2".to_string(),
-                Style::default(),
-                VirtualTextPosition::AfterChar,
-                0,
-            );
+    #[test]
+    fn test_save_cleanup_trims_every_line() {
+        let mut state = EditorState::from_str_test("a  \r\n\tb\t \n \n  c   ");
+        state.cursors.primary_mut().position = 2;
 
-            assert_eq!(state.virtual_texts.len(), 2);
+        let cleanup = state.save_cleanup(true, false).unwrap();
+        state.apply(&cleanup);
+        assert_eq!(state.buffer.to_string().unwrap(), "a\r\n\tb\n\n  c");
+        // The cursor was on removed whitespace
+        assert_eq!(state.cursors.primary().position, 1);
 
-            // Clear all
-            state.virtual_texts.clear(&mut state.marker_list);
-            assert!(state.virtual_texts.is_empty());
+        assert!(state.save_cleanup(true, false).is_none());
+    }
 
-            // Query should return nothing
-            let results = state.virtual_texts.query_range(&state.marker_list, 0, 10);
-            assert!(results.is_empty());
+    #[test]
+    fn test_save_cleanup_adds_single_final_newline() {
+        let mut state = EditorState::from_str_test("a\nb");
+        let cleanup = state.save_cleanup(false, true).unwrap();
+        state.apply(&cleanup);
+        assert_eq!(state.buffer.to_string().unwrap(), "a\nb\n");
+        assert!(state.save_cleanup(false, true).is_none());
+
+        // Trailing whitespace after the last newline doesn't need another one
+        let mut state = EditorState::from_str_test("a\n  ");
+        let cleanup = state.save_cleanup(true, true).unwrap();
+        state.apply(&cleanup);
+        assert_eq!(state.buffer.to_string().unwrap(), "a\n");
+
+        let mut state = EditorState::from_str_test("a  ");
+        let cleanup = state.save_cleanup(true, true).unwrap();
+        state.apply(&cleanup);
+        assert_eq!(state.buffer.to_string().unwrap(), "a\n");
+
+        // Empty buffers stay empty
+        assert!(EditorState::from_str_test("")
+            .save_cleanup(true, true)
+            .is_none());
+    }
+
+    fn expand_trigger(state: &mut EditorState, body: &str) {
+        let trigger_end = state.cursors.primary().position;
+        let trigger_start = trigger_end - 2;
+        let (event, engine) =
+            state.expand_snippet(&Snippet::parse(body), trigger_start..trigger_end);
+        state.apply(&event);
+        state.snippet = Some(engine);
+    }
+
+    #[test]
+    fn test_snippet_cycles_through_stops() {
+        let mut state = EditorState::from_str_test("fn");
+        state.cursors.primary_mut().position = 2;
+        expand_trigger(&mut state, "fn ${1:name}($2) {\n    $0\n}");
+
+        assert_eq!(state.buffer.to_string().unwrap(), "fn name() {\n    \n}");
+        assert_eq!(state.cursors.primary().selection_range(), Some(3..7));
+
+        let jump = state.snippet_jump(true).unwrap();
+        state.apply(&jump);
+        assert_eq!(state.cursors.primary().position, 8);
+        assert_eq!(state.cursors.primary().anchor, None);
+
+        let back = state.snippet_jump(false).unwrap();
+        state.apply(&back);
+        assert_eq!(state.cursors.primary().selection_range(), Some(3..7));
+
+        for _ in 0..2 {
+            let jump = state.snippet_jump(true).unwrap();
+            state.apply(&jump);
+        }
+        // Landing on $0 ends the session
+        assert_eq!(state.cursors.primary().position, 16);
+        assert!(state.snippet.is_none());
+        assert!(state.snippet_jump(true).is_none());
+    }
+
+    #[test]
+    fn test_snippet_mirrors_edit_together_until_edit_outside() {
+        let mut state = EditorState::from_str_test("le");
+        state.cursors.primary_mut().position = 2;
+        expand_trigger(&mut state, "let ${1:x} = $1;");
+
+        assert_eq!(state.buffer.to_string().unwrap(), "let x = x;");
+        let mut selections = state.cursors.selections();
+        selections.sort_by_key(|range| range.start);
+        assert_eq!(selections, vec![4..5, 8..9]);
+
+        // Typing over both selections, last cursor first as multi-cursor edits do
+        let mut cursors: Vec<_> = state.cursors.iter().map(|(id, c)| (id, *c)).collect();
+        cursors.sort_by_key(|(_, cursor)| std::cmp::Reverse(cursor.position));
+        for (cursor_id, cursor) in cursors {
+            let range = cursor.selection_range().unwrap();
+            state.apply(&Event::Delete {
+                range: range.clone(),
+                deleted_text: "x".to_string(),
+                cursor_id,
+            });
+            state.apply(&Event::Insert {
+                position: range.start,
+                text: "val".to_string(),
+                cursor_id,
+            });
         }
+        assert_eq!(state.buffer.to_string().unwrap(), "let val = val;");
+        assert_eq!(state.snippet.as_ref().unwrap().current(), &[4..7, 10..13]);
+
+        // An edit outside the stop cancels the session
+        state.apply(&Event::Insert {
+            position: 0,
+            text: "// ".to_string(),
+            cursor_id: state.cursors.primary_id(),
+        });
+        assert!(state.snippet.is_none());
     }
 }
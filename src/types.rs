@@ -22,6 +22,13 @@ pub mod context_keys {
     pub const HAS_SELECTION: &str = "has_selection";
 }
 
+/// Provider id of the built-in "Recent Commands" submenu
+///
+/// Submenus declared with this provider list the commands most recently run
+/// from keybindings, the command palette or the menus. It is registered like
+/// any other provider, so a plugin registering the same id replaces it.
+pub const RECENT_COMMANDS_PROVIDER: &str = "recent_commands";
+
 /// Configuration for process resource limits
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct ProcessLimits {
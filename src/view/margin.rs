@@ -282,8 +282,9 @@ impl MarginAnnotation {
 /// Manages margins and annotations for a buffer
 /// This is similar to OverlayManager - a general-purpose primitive for margin decorations
 ///
-/// Line indicators use byte-position markers that automatically adjust when the buffer
-/// is edited. This ensures indicators stay anchored to the content they represent.
+/// Line indicators are anchored with markers in the document's marker list, which
+/// moves them when the buffer is edited. This keeps indicators on the content they
+/// represent.
 #[derive(Debug)]
 pub struct MarginManager {
     /// Configuration for left margin
@@ -306,10 +307,6 @@ pub struct MarginManager {
     /// Maps line number to (symbol, color) tuple
     diagnostic_indicators: BTreeMap<usize, (String, Color)>,
 
-    /// Marker list for tracking indicator positions through edits
-    /// Shared with the buffer's edit tracking
-    indicator_markers: MarkerList,
-
     /// Line indicators stored by marker ID
     /// Maps marker_id -> (namespace -> indicator)
     /// The line number is computed at render time from the marker's byte position
@@ -326,7 +323,6 @@ impl MarginManager {
             right_annotations: BTreeMap::new(),
             show_line_numbers: true,
             diagnostic_indicators: BTreeMap::new(),
-            indicator_markers: MarkerList::new(),
             line_indicators: BTreeMap::new(),
         }
     }
@@ -338,22 +334,6 @@ impl MarginManager {
         manager
     }
 
-    // =========================================================================
-    // Edit Propagation - called when buffer content changes
-    // =========================================================================
-
-    /// Adjust all indicator markers after an insertion
-    /// Call this when text is inserted into the buffer
-    pub fn adjust_for_insert(&mut self, position: usize, length: usize) {
-        self.indicator_markers.adjust_for_insert(position, length);
-    }
-
-    /// Adjust all indicator markers after a deletion
-    /// Call this when text is deleted from the buffer
-    pub fn adjust_for_delete(&mut self, position: usize, length: usize) {
-        self.indicator_markers.adjust_for_delete(position, length);
-    }
-
     /// Set a diagnostic indicator for a line
     pub fn set_diagnostic_indicator(&mut self, line: usize, symbol: String, color: Color) {
         self.diagnostic_indicators.insert(line, (symbol, color));
@@ -382,12 +362,13 @@ impl MarginManager {
     /// Returns the marker ID that can be used to remove or update the indicator.
     pub fn set_line_indicator(
         &mut self,
+        marker_list: &mut MarkerList,
         byte_offset: usize,
         namespace: String,
         mut indicator: LineIndicator,
     ) -> MarkerId {
         // Create a marker at this byte position (left affinity - stays before inserted text)
-        let marker_id = self.indicator_markers.create(byte_offset, true);
+        let marker_id = marker_list.create(byte_offset, true);
         indicator.marker_id = marker_id;
 
         self.line_indicators
@@ -399,18 +380,27 @@ impl MarginManager {
     }
 
     /// Remove line indicator for a specific namespace at a marker
-    pub fn remove_line_indicator(&mut self, marker_id: MarkerId, namespace: &str) {
+    pub fn remove_line_indicator(
+        &mut self,
+        marker_list: &mut MarkerList,
+        marker_id: MarkerId,
+        namespace: &str,
+    ) {
         if let Some(indicators) = self.line_indicators.get_mut(&marker_id.0) {
             indicators.remove(namespace);
             if indicators.is_empty() {
                 self.line_indicators.remove(&marker_id.0);
-                self.indicator_markers.delete(marker_id);
+                marker_list.delete(marker_id);
             }
         }
     }
 
     /// Clear all line indicators for a specific namespace
-    pub fn clear_line_indicators_for_namespace(&mut self, namespace: &str) {
+    pub fn clear_line_indicators_for_namespace(
+        &mut self,
+        marker_list: &mut MarkerList,
+        namespace: &str,
+    ) {
         // Collect marker IDs to delete (can't modify while iterating)
         let mut markers_to_delete = Vec::new();
 
@@ -424,7 +414,7 @@ impl MarginManager {
         // Delete empty marker entries and their markers
         for marker_id in markers_to_delete {
             self.line_indicators.remove(&marker_id);
-            self.indicator_markers.delete(MarkerId(marker_id));
+            marker_list.delete(MarkerId(marker_id));
         }
     }
 
@@ -437,6 +427,7 @@ impl MarginManager {
     /// `get_indicators_in_viewport` which is more efficient.
     pub fn get_line_indicator(
         &self,
+        marker_list: &MarkerList,
         line: usize,
         get_line_fn: impl Fn(usize) -> usize,
     ) -> Option<&LineIndicator> {
@@ -444,7 +435,7 @@ impl MarginManager {
         let mut best: Option<&LineIndicator> = None;
 
         for (&marker_id, indicators) in &self.line_indicators {
-            if let Some(byte_pos) = self.indicator_markers.get_position(MarkerId(marker_id)) {
+            if let Some(byte_pos) = marker_list.get_position(MarkerId(marker_id)) {
                 let indicator_line = get_line_fn(byte_pos);
                 if indicator_line == line {
                     // Found an indicator on this line, check if it's higher priority
@@ -469,6 +460,7 @@ impl MarginManager {
     /// The `get_line_fn` converts byte offsets to line numbers.
     pub fn get_indicators_for_viewport(
         &self,
+        marker_list: &MarkerList,
         viewport_start: usize,
         viewport_end: usize,
         get_line_fn: impl Fn(usize) -> usize,
@@ -476,10 +468,7 @@ impl MarginManager {
         let mut by_line: BTreeMap<usize, LineIndicator> = BTreeMap::new();
 
         // Query only markers within the viewport byte range
-        for (marker_id, byte_pos, _end) in self
-            .indicator_markers
-            .query_range(viewport_start, viewport_end)
-        {
+        for (marker_id, byte_pos, _end) in marker_list.query_range(viewport_start, viewport_end) {
             // Look up the indicators for this marker
             if let Some(indicators) = self.line_indicators.get(&marker_id.0) {
                 let line = get_line_fn(byte_pos);
@@ -820,13 +809,19 @@ mod tests {
     #[test]
     fn test_line_indicator_basic() {
         let mut manager = MarginManager::new();
+        let mut markers = MarkerList::new();
 
         // Add a line indicator at byte offset 50 (line 5 in our simulated buffer)
         let indicator = LineIndicator::new("│", Color::Green, 10);
-        manager.set_line_indicator(line_to_byte(5), "git-gutter".to_string(), indicator);
+        manager.set_line_indicator(
+            &mut markers,
+            line_to_byte(5),
+            "git-gutter".to_string(),
+            indicator,
+        );
 
         // Check it can be retrieved on line 5
-        let retrieved = manager.get_line_indicator(5, byte_to_line);
+        let retrieved = manager.get_line_indicator(&markers, 5, byte_to_line);
         assert!(retrieved.is_some());
         let retrieved = retrieved.unwrap();
         assert_eq!(retrieved.symbol, "│");
@@ -834,26 +829,35 @@ mod tests {
         assert_eq!(retrieved.priority, 10);
 
         // Non-existent line should return None
-        assert!(manager.get_line_indicator(10, byte_to_line).is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 10, byte_to_line)
+            .is_none());
     }
 
     #[test]
     fn test_line_indicator_multiple_namespaces() {
         let mut manager = MarginManager::new();
+        let mut markers = MarkerList::new();
 
         // Add indicators from different namespaces at the same byte position (line 5)
         let git_indicator = LineIndicator::new("│", Color::Green, 10);
         let breakpoint_indicator = LineIndicator::new("●", Color::Red, 20);
 
-        manager.set_line_indicator(line_to_byte(5), "git-gutter".to_string(), git_indicator);
         manager.set_line_indicator(
+            &mut markers,
+            line_to_byte(5),
+            "git-gutter".to_string(),
+            git_indicator,
+        );
+        manager.set_line_indicator(
+            &mut markers,
             line_to_byte(5),
             "breakpoints".to_string(),
             breakpoint_indicator,
         );
 
         // Should return the highest priority indicator
-        let retrieved = manager.get_line_indicator(5, byte_to_line);
+        let retrieved = manager.get_line_indicator(&markers, 5, byte_to_line);
         assert!(retrieved.is_some());
         let retrieved = retrieved.unwrap();
         assert_eq!(retrieved.symbol, "●"); // Breakpoint has higher priority
@@ -863,33 +867,41 @@ mod tests {
     #[test]
     fn test_line_indicator_clear_namespace() {
         let mut manager = MarginManager::new();
+        let mut markers = MarkerList::new();
 
         // Add indicators on multiple lines
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(1),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Green, 10),
         );
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(2),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Yellow, 10),
         );
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(3),
             "breakpoints".to_string(),
             LineIndicator::new("●", Color::Red, 20),
         );
 
         // Clear git-gutter namespace
-        manager.clear_line_indicators_for_namespace("git-gutter");
+        manager.clear_line_indicators_for_namespace(&mut markers, "git-gutter");
 
         // Git gutter indicators should be gone
-        assert!(manager.get_line_indicator(1, byte_to_line).is_none());
-        assert!(manager.get_line_indicator(2, byte_to_line).is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 1, byte_to_line)
+            .is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 2, byte_to_line)
+            .is_none());
 
         // Breakpoint should still be there
-        let breakpoint = manager.get_line_indicator(3, byte_to_line);
+        let breakpoint = manager.get_line_indicator(&markers, 3, byte_to_line);
         assert!(breakpoint.is_some());
         assert_eq!(breakpoint.unwrap().symbol, "●");
     }
@@ -897,95 +909,122 @@ mod tests {
     #[test]
     fn test_line_indicator_remove_specific() {
         let mut manager = MarginManager::new();
+        let mut markers = MarkerList::new();
 
         // Add two indicators at the same byte position (line 5)
         let git_marker = manager.set_line_indicator(
+            &mut markers,
             line_to_byte(5),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Green, 10),
         );
         let bp_marker = manager.set_line_indicator(
+            &mut markers,
             line_to_byte(5),
             "breakpoints".to_string(),
             LineIndicator::new("●", Color::Red, 20),
         );
 
         // Remove just the git-gutter indicator
-        manager.remove_line_indicator(git_marker, "git-gutter");
+        manager.remove_line_indicator(&mut markers, git_marker, "git-gutter");
 
         // Should still have the breakpoint indicator on line 5
-        let retrieved = manager.get_line_indicator(5, byte_to_line);
+        let retrieved = manager.get_line_indicator(&markers, 5, byte_to_line);
         assert!(retrieved.is_some());
         assert_eq!(retrieved.unwrap().symbol, "●");
 
         // Remove the breakpoint indicator too
-        manager.remove_line_indicator(bp_marker, "breakpoints");
+        manager.remove_line_indicator(&mut markers, bp_marker, "breakpoints");
 
         // Now no indicators on line 5
-        assert!(manager.get_line_indicator(5, byte_to_line).is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 5, byte_to_line)
+            .is_none());
     }
 
     #[test]
     fn test_line_indicator_shifts_on_insert() {
         let mut manager = MarginManager::new();
+        let mut markers = MarkerList::new();
 
         // Add indicator on line 5 (byte 50)
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(5),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Green, 10),
         );
 
         // Verify it's on line 5
-        assert!(manager.get_line_indicator(5, byte_to_line).is_some());
-        assert!(manager.get_line_indicator(6, byte_to_line).is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 5, byte_to_line)
+            .is_some());
+        assert!(manager
+            .get_line_indicator(&markers, 6, byte_to_line)
+            .is_none());
 
         // Insert 10 bytes (one line) at the beginning
-        manager.adjust_for_insert(0, 10);
+        markers.adjust_for_insert(0, 10);
 
         // Now indicator should be on line 6 (shifted down by 1)
-        assert!(manager.get_line_indicator(5, byte_to_line).is_none());
-        assert!(manager.get_line_indicator(6, byte_to_line).is_some());
+        assert!(manager
+            .get_line_indicator(&markers, 5, byte_to_line)
+            .is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 6, byte_to_line)
+            .is_some());
     }
 
     #[test]
     fn test_line_indicator_shifts_on_delete() {
         let mut manager = MarginManager::new();
+        let mut markers = MarkerList::new();
 
         // Add indicator on line 5 (byte 50)
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(5),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Green, 10),
         );
 
         // Verify it's on line 5
-        assert!(manager.get_line_indicator(5, byte_to_line).is_some());
+        assert!(manager
+            .get_line_indicator(&markers, 5, byte_to_line)
+            .is_some());
 
         // Delete first 20 bytes (2 lines)
-        manager.adjust_for_delete(0, 20);
+        markers.adjust_for_delete(0, 20);
 
         // Now indicator should be on line 3 (shifted up by 2)
-        assert!(manager.get_line_indicator(5, byte_to_line).is_none());
-        assert!(manager.get_line_indicator(3, byte_to_line).is_some());
+        assert!(manager
+            .get_line_indicator(&markers, 5, byte_to_line)
+            .is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 3, byte_to_line)
+            .is_some());
     }
 
     #[test]
     fn test_multiple_indicators_shift_together() {
         let mut manager = MarginManager::new();
+        let mut markers = MarkerList::new();
 
         // Add indicators on lines 3, 5, and 7
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(3),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Green, 10),
         );
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(5),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Yellow, 10),
         );
         manager.set_line_indicator(
+            &mut markers,
             line_to_byte(7),
             "git-gutter".to_string(),
             LineIndicator::new("│", Color::Red, 10),
@@ -993,14 +1032,22 @@ mod tests {
 
         // Insert 2 lines (20 bytes) at byte 25 (middle of line 2)
         // This should shift lines 3, 5, 7 -> lines 5, 7, 9
-        manager.adjust_for_insert(25, 20);
+        markers.adjust_for_insert(25, 20);
 
         // Old positions should be empty
-        assert!(manager.get_line_indicator(3, byte_to_line).is_none());
+        assert!(manager
+            .get_line_indicator(&markers, 3, byte_to_line)
+            .is_none());
 
         // New positions should have indicators
-        assert!(manager.get_line_indicator(5, byte_to_line).is_some());
-        assert!(manager.get_line_indicator(7, byte_to_line).is_some());
-        assert!(manager.get_line_indicator(9, byte_to_line).is_some());
+        assert!(manager
+            .get_line_indicator(&markers, 5, byte_to_line)
+            .is_some());
+        assert!(manager
+            .get_line_indicator(&markers, 7, byte_to_line)
+            .is_some());
+        assert!(manager
+            .get_line_indicator(&markers, 9, byte_to_line)
+            .is_some());
     }
 }
@@ -16,6 +16,8 @@ pub enum PromptType {
     SaveFileAs,
     /// Search for text in buffer
     Search,
+    /// Incremental search: the cursor follows the nearest match as the query is typed
+    IncrementalSearch,
     /// Search for text in buffer (for replace operation - will prompt for replacement after)
     ReplaceSearch,
     /// Replace text in buffer
@@ -65,6 +67,11 @@ pub enum PromptType {
     ConfirmRevert,
     /// Confirm saving over a file that changed on disk
     ConfirmSaveConflict,
+    /// Resolve a file that changed on disk while its buffer has unsaved edits
+    /// (reload/keep mine/diff)
+    ConfirmExternalChange {
+        buffer_id: crate::model::event::BufferId,
+    },
     /// Confirm closing a modified buffer (save/discard/cancel)
     /// Stores buffer_id to close after user confirms
     ConfirmCloseBuffer {
@@ -1,6 +1,7 @@
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::Arc;
 
 /// Serializable color representation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,17 +35,44 @@ impl From<ColorDef> for Color {
                 "LightCyan" => Color::LightCyan,
                 "White" => Color::White,
                 // Default/Reset uses the terminal's default color (preserves transparency)
-                "Default" | "Reset" => Color::Reset,
+                "Default" | "Reset" | "default" | "reset" => Color::Reset,
                 _ => Color::White, // Default fallback
             },
         }
     }
 }
 
+/// Border drawn around dropdown menus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuBorderStyle {
+    /// Single lines with square corners
+    #[default]
+    Plain,
+    /// Single lines with rounded corners (`╭╮╰╯`)
+    Rounded,
+    /// Double lines
+    Double,
+    /// No border; items fill the whole dropdown
+    None,
+}
+
+impl MenuBorderStyle {
+    /// Rows and columns the border takes on each side of a dropdown
+    pub fn inset(self) -> u16 {
+        match self {
+            MenuBorderStyle::None => 0,
+            _ => 1,
+        }
+    }
+}
+
 /// Serializable theme definition (matches JSON structure)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ThemeFile {
     name: String,
+    #[serde(default)]
+    palette: Palette,
     editor: EditorColors,
     ui: UiColors,
     search: SearchColors,
@@ -52,6 +80,59 @@ struct ThemeFile {
     syntax: SyntaxColors,
 }
 
+/// Palette roles that related UI colors default to
+///
+/// Role-derived fields (mostly the menu colors) that a theme leaves out take the
+/// color of their role, so a theme can restyle all menus by setting `accent` or
+/// `surface` once. Fields the theme sets explicitly always win.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Palette {
+    /// Behind everything: drop shadows, compose margins
+    background: Option<ColorDef>,
+    /// Raised panels: menu bar and dropdowns
+    surface: Option<ColorDef>,
+    /// Highlights: active and hovered menu items, hovered separators
+    accent: Option<ColorDef>,
+    /// Foreground text on surfaces and accents
+    text: Option<ColorDef>,
+    /// De-emphasized details: borders, separators, disabled items
+    muted: Option<ColorDef>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PaletteRole {
+    Background,
+    Surface,
+    Accent,
+    Text,
+    Muted,
+}
+
+impl Palette {
+    fn role(&self, role: PaletteRole) -> Option<&ColorDef> {
+        match role {
+            PaletteRole::Background => self.background.as_ref(),
+            PaletteRole::Surface => self.surface.as_ref(),
+            PaletteRole::Accent => self.accent.as_ref(),
+            PaletteRole::Text => self.text.as_ref(),
+            PaletteRole::Muted => self.muted.as_ref(),
+        }
+    }
+
+    /// Color of a role-derived field: the explicit value, else its role, else the default
+    fn resolve(
+        &self,
+        field: Option<ColorDef>,
+        role: PaletteRole,
+        default: fn() -> ColorDef,
+    ) -> Color {
+        field
+            .or_else(|| self.role(role).cloned())
+            .unwrap_or_else(default)
+            .into()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct EditorColors {
     bg: ColorDef,
@@ -63,12 +144,24 @@ struct EditorColors {
     current_line_bg: ColorDef,
     line_number_fg: ColorDef,
     line_number_bg: ColorDef,
+    #[serde(default = "default_whitespace_indicator_fg")]
+    whitespace_indicator_fg: ColorDef,
+    #[serde(default = "default_ruler_bg")]
+    ruler_bg: ColorDef,
 }
 
 fn default_inactive_cursor() -> ColorDef {
     ColorDef::Named("DarkGray".to_string())
 }
 
+fn default_whitespace_indicator_fg() -> ColorDef {
+    ColorDef::Rgb(70, 70, 70) // Dim gray, just above the background
+}
+
+fn default_ruler_bg() -> ColorDef {
+    ColorDef::Rgb(45, 45, 45)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct UiColors {
     tab_active_fg: ColorDef,
@@ -80,34 +173,47 @@ struct UiColors {
     tab_close_hover_fg: ColorDef,
     #[serde(default = "default_tab_hover_bg")]
     tab_hover_bg: ColorDef,
-    #[serde(default = "default_menu_bg")]
-    menu_bg: ColorDef,
-    #[serde(default = "default_menu_fg")]
-    menu_fg: ColorDef,
-    #[serde(default = "default_menu_active_bg")]
-    menu_active_bg: ColorDef,
-    #[serde(default = "default_menu_active_fg")]
-    menu_active_fg: ColorDef,
-    #[serde(default = "default_menu_dropdown_bg")]
-    menu_dropdown_bg: ColorDef,
-    #[serde(default = "default_menu_dropdown_fg")]
-    menu_dropdown_fg: ColorDef,
-    #[serde(default = "default_menu_highlight_bg")]
-    menu_highlight_bg: ColorDef,
-    #[serde(default = "default_menu_highlight_fg")]
-    menu_highlight_fg: ColorDef,
-    #[serde(default = "default_menu_border_fg")]
-    menu_border_fg: ColorDef,
-    #[serde(default = "default_menu_separator_fg")]
-    menu_separator_fg: ColorDef,
-    #[serde(default = "default_menu_hover_bg")]
-    menu_hover_bg: ColorDef,
-    #[serde(default = "default_menu_hover_fg")]
-    menu_hover_fg: ColorDef,
-    #[serde(default = "default_menu_disabled_fg")]
-    menu_disabled_fg: ColorDef,
-    #[serde(default = "default_menu_disabled_bg")]
-    menu_disabled_bg: ColorDef,
+    #[serde(default)]
+    menu_bg: Option<ColorDef>,
+    #[serde(default)]
+    menu_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_active_bg: Option<ColorDef>,
+    #[serde(default)]
+    menu_active_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_dropdown_bg: Option<ColorDef>,
+    #[serde(default)]
+    menu_dropdown_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_highlight_bg: Option<ColorDef>,
+    /// End color of a gradient across highlighted dropdown items, starting at `menu_highlight_bg`
+    #[serde(default)]
+    menu_highlight_bg_end: Option<ColorDef>,
+    #[serde(default)]
+    menu_highlight_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_border_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_separator_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_hover_bg: Option<ColorDef>,
+    #[serde(default)]
+    menu_hover_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_disabled_fg: Option<ColorDef>,
+    #[serde(default)]
+    menu_disabled_bg: Option<ColorDef>,
+    #[serde(default)]
+    menu_shadow_bg: Option<ColorDef>,
+    #[serde(default = "default_menu_destructive_fg")]
+    menu_destructive_fg: ColorDef,
+    #[serde(default = "default_menu_primary_fg")]
+    menu_primary_fg: ColorDef,
+    #[serde(default = "default_menu_shadow")]
+    menu_shadow: bool,
+    #[serde(default)]
+    menu_border_style: MenuBorderStyle,
     status_bar_fg: ColorDef,
     status_bar_bg: ColorDef,
     prompt_fg: ColorDef,
@@ -129,8 +235,8 @@ struct UiColors {
     #[serde(default = "default_inline_code_bg")]
     inline_code_bg: ColorDef,
     split_separator_fg: ColorDef,
-    #[serde(default = "default_split_separator_hover_fg")]
-    split_separator_hover_fg: ColorDef,
+    #[serde(default)]
+    split_separator_hover_fg: Option<ColorDef>,
     #[serde(default = "default_scrollbar_track_fg")]
     scrollbar_track_fg: ColorDef,
     #[serde(default = "default_scrollbar_thumb_fg")]
@@ -139,8 +245,8 @@ struct UiColors {
     scrollbar_track_hover_fg: ColorDef,
     #[serde(default = "default_scrollbar_thumb_hover_fg")]
     scrollbar_thumb_hover_fg: ColorDef,
-    #[serde(default = "default_compose_margin_bg")]
-    compose_margin_bg: ColorDef,
+    #[serde(default)]
+    compose_margin_bg: Option<ColorDef>,
     #[serde(default = "default_semantic_highlight_bg")]
     semantic_highlight_bg: ColorDef,
     #[serde(default = "default_terminal_bg")]
@@ -200,6 +306,18 @@ fn default_menu_disabled_fg() -> ColorDef {
 fn default_menu_disabled_bg() -> ColorDef {
     ColorDef::Rgb(50, 50, 50) // Same as dropdown bg
 }
+fn default_menu_shadow_bg() -> ColorDef {
+    ColorDef::Rgb(10, 10, 10) // Near-black drop shadow
+}
+fn default_menu_destructive_fg() -> ColorDef {
+    ColorDef::Rgb(255, 110, 110)
+}
+fn default_menu_primary_fg() -> ColorDef {
+    ColorDef::Rgb(110, 180, 255)
+}
+fn default_menu_shadow() -> bool {
+    true
+}
 fn default_inline_code_bg() -> ColorDef {
     ColorDef::Named("DarkGray".to_string())
 }
@@ -277,6 +395,10 @@ pub struct Theme {
     pub current_line_bg: Color,
     pub line_number_fg: Color,
     pub line_number_bg: Color,
+    /// Foreground of the tab and space markers drawn by `render_whitespace`
+    pub whitespace_indicator_fg: Color,
+    /// Background of the columns marked by the `rulers` setting
+    pub ruler_bg: Color,
 
     // UI element colors
     pub tab_active_fg: Color,
@@ -295,6 +417,9 @@ pub struct Theme {
     pub menu_dropdown_bg: Color,
     pub menu_dropdown_fg: Color,
     pub menu_highlight_bg: Color,
+    /// If set, highlighted dropdown items fade from `menu_highlight_bg` to this
+    /// color across their width (true color terminals only)
+    pub menu_highlight_bg_end: Option<Color>,
     pub menu_highlight_fg: Color,
     pub menu_border_fg: Color,
     pub menu_separator_fg: Color,
@@ -302,6 +427,16 @@ pub struct Theme {
     pub menu_hover_fg: Color,
     pub menu_disabled_fg: Color,
     pub menu_disabled_bg: Color,
+    /// Background of the drop shadow drawn behind dropdown menus
+    pub menu_shadow_bg: Color,
+    /// Label color of menu items with the `destructive` role (e.g. "Delete All")
+    pub menu_destructive_fg: Color,
+    /// Label color of menu items with the `primary` role
+    pub menu_primary_fg: Color,
+    /// Whether dropdown menus draw a drop shadow
+    pub menu_shadow: bool,
+    /// Border drawn around dropdown menus
+    pub menu_border_style: MenuBorderStyle,
 
     pub status_bar_fg: Color,
     pub status_bar_bg: Color,
@@ -375,6 +510,7 @@ pub struct Theme {
 
 impl From<ThemeFile> for Theme {
     fn from(file: ThemeFile) -> Self {
+        let palette = file.palette;
         Self {
             name: file.name,
             editor_bg: file.editor.bg.into(),
@@ -385,6 +521,8 @@ impl From<ThemeFile> for Theme {
             current_line_bg: file.editor.current_line_bg.into(),
             line_number_fg: file.editor.line_number_fg.into(),
             line_number_bg: file.editor.line_number_bg.into(),
+            whitespace_indicator_fg: file.editor.whitespace_indicator_fg.into(),
+            ruler_bg: file.editor.ruler_bg.into(),
             tab_active_fg: file.ui.tab_active_fg.into(),
             tab_active_bg: file.ui.tab_active_bg.into(),
             tab_inactive_fg: file.ui.tab_inactive_fg.into(),
@@ -392,20 +530,78 @@ impl From<ThemeFile> for Theme {
             tab_separator_bg: file.ui.tab_separator_bg.into(),
             tab_close_hover_fg: file.ui.tab_close_hover_fg.into(),
             tab_hover_bg: file.ui.tab_hover_bg.into(),
-            menu_bg: file.ui.menu_bg.into(),
-            menu_fg: file.ui.menu_fg.into(),
-            menu_active_bg: file.ui.menu_active_bg.into(),
-            menu_active_fg: file.ui.menu_active_fg.into(),
-            menu_dropdown_bg: file.ui.menu_dropdown_bg.into(),
-            menu_dropdown_fg: file.ui.menu_dropdown_fg.into(),
-            menu_highlight_bg: file.ui.menu_highlight_bg.into(),
-            menu_highlight_fg: file.ui.menu_highlight_fg.into(),
-            menu_border_fg: file.ui.menu_border_fg.into(),
-            menu_separator_fg: file.ui.menu_separator_fg.into(),
-            menu_hover_bg: file.ui.menu_hover_bg.into(),
-            menu_hover_fg: file.ui.menu_hover_fg.into(),
-            menu_disabled_fg: file.ui.menu_disabled_fg.into(),
-            menu_disabled_bg: file.ui.menu_disabled_bg.into(),
+            menu_bg: palette.resolve(file.ui.menu_bg, PaletteRole::Surface, default_menu_bg),
+            menu_fg: palette.resolve(file.ui.menu_fg, PaletteRole::Text, default_menu_fg),
+            menu_active_bg: palette.resolve(
+                file.ui.menu_active_bg,
+                PaletteRole::Accent,
+                default_menu_active_bg,
+            ),
+            menu_active_fg: palette.resolve(
+                file.ui.menu_active_fg,
+                PaletteRole::Text,
+                default_menu_active_fg,
+            ),
+            menu_dropdown_bg: palette.resolve(
+                file.ui.menu_dropdown_bg,
+                PaletteRole::Surface,
+                default_menu_dropdown_bg,
+            ),
+            menu_dropdown_fg: palette.resolve(
+                file.ui.menu_dropdown_fg,
+                PaletteRole::Text,
+                default_menu_dropdown_fg,
+            ),
+            menu_highlight_bg: palette.resolve(
+                file.ui.menu_highlight_bg,
+                PaletteRole::Accent,
+                default_menu_highlight_bg,
+            ),
+            menu_highlight_bg_end: file.ui.menu_highlight_bg_end.map(Color::from),
+            menu_highlight_fg: palette.resolve(
+                file.ui.menu_highlight_fg,
+                PaletteRole::Text,
+                default_menu_highlight_fg,
+            ),
+            menu_border_fg: palette.resolve(
+                file.ui.menu_border_fg,
+                PaletteRole::Muted,
+                default_menu_border_fg,
+            ),
+            menu_separator_fg: palette.resolve(
+                file.ui.menu_separator_fg,
+                PaletteRole::Muted,
+                default_menu_separator_fg,
+            ),
+            menu_hover_bg: palette.resolve(
+                file.ui.menu_hover_bg,
+                PaletteRole::Accent,
+                default_menu_hover_bg,
+            ),
+            menu_hover_fg: palette.resolve(
+                file.ui.menu_hover_fg,
+                PaletteRole::Text,
+                default_menu_hover_fg,
+            ),
+            menu_disabled_fg: palette.resolve(
+                file.ui.menu_disabled_fg,
+                PaletteRole::Muted,
+                default_menu_disabled_fg,
+            ),
+            menu_disabled_bg: palette.resolve(
+                file.ui.menu_disabled_bg,
+                PaletteRole::Surface,
+                default_menu_disabled_bg,
+            ),
+            menu_shadow_bg: palette.resolve(
+                file.ui.menu_shadow_bg,
+                PaletteRole::Background,
+                default_menu_shadow_bg,
+            ),
+            menu_destructive_fg: file.ui.menu_destructive_fg.into(),
+            menu_primary_fg: file.ui.menu_primary_fg.into(),
+            menu_shadow: file.ui.menu_shadow,
+            menu_border_style: file.ui.menu_border_style,
             status_bar_fg: file.ui.status_bar_fg.into(),
             status_bar_bg: file.ui.status_bar_bg.into(),
             prompt_fg: file.ui.prompt_fg.into(),
@@ -426,12 +622,20 @@ impl From<ThemeFile> for Theme {
             help_indicator_bg: file.ui.help_indicator_bg.into(),
             inline_code_bg: file.ui.inline_code_bg.into(),
             split_separator_fg: file.ui.split_separator_fg.into(),
-            split_separator_hover_fg: file.ui.split_separator_hover_fg.into(),
+            split_separator_hover_fg: palette.resolve(
+                file.ui.split_separator_hover_fg,
+                PaletteRole::Accent,
+                default_split_separator_hover_fg,
+            ),
             scrollbar_track_fg: file.ui.scrollbar_track_fg.into(),
             scrollbar_thumb_fg: file.ui.scrollbar_thumb_fg.into(),
             scrollbar_track_hover_fg: file.ui.scrollbar_track_hover_fg.into(),
             scrollbar_thumb_hover_fg: file.ui.scrollbar_thumb_hover_fg.into(),
-            compose_margin_bg: file.ui.compose_margin_bg.into(),
+            compose_margin_bg: palette.resolve(
+                file.ui.compose_margin_bg,
+                PaletteRole::Background,
+                default_compose_margin_bg,
+            ),
             semantic_highlight_bg: file.ui.semantic_highlight_bg.into(),
             terminal_bg: file.ui.terminal_bg.into(),
             terminal_fg: file.ui.terminal_fg.into(),
@@ -467,6 +671,99 @@ impl Theme {
         Ok(theme_file.into())
     }
 
+    /// Convert a base16 color scheme into a theme
+    ///
+    /// Accepts the YAML scheme format (`scheme:` or `name:` plus `base00` to
+    /// `base0F` as hex colors, optionally nested under `palette:`). The sixteen
+    /// colors are mapped onto the editor, menu, status bar, diagnostic and syntax
+    /// colors following the base16 styling guidelines; everything else comes from
+    /// the dark or light builtin theme, whichever suits the scheme's background.
+    pub fn from_base16(source: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut colors: [Option<Color>; 16] = [None; 16];
+        for line in source.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim();
+            let value = yaml_scalar(value);
+            if key == "scheme" || key == "name" {
+                name.get_or_insert_with(|| value.to_string());
+            } else if let Some(index) = key
+                .strip_prefix("base")
+                .filter(|digits| digits.len() == 2)
+                .and_then(|digits| usize::from_str_radix(digits, 16).ok())
+            {
+                let color = parse_hex_color(value)
+                    .ok_or_else(|| format!("Invalid color for {}: {:?}", key, value))?;
+                colors[index] = Some(color);
+            }
+        }
+        let mut base = [Color::Reset; 16];
+        for (index, color) in colors.into_iter().enumerate() {
+            base[index] = color.ok_or_else(|| format!("Missing base{:02X} color", index))?;
+        }
+
+        let is_light = color_to_rgb(base[0]).is_some_and(|rgb| relative_luminance(rgb) > 0.5);
+        let mut theme = if is_light {
+            Self::light()
+        } else {
+            Self::dark()
+        };
+        theme.name = name.unwrap_or_else(|| "base16".to_string());
+
+        // base00-07 run from background to foreground, base08-0F are accents
+        theme.editor_bg = base[0x0];
+        theme.editor_fg = base[0x5];
+        theme.cursor = base[0x5];
+        theme.inactive_cursor = base[0x3];
+        theme.selection_bg = base[0x2];
+        theme.current_line_bg = base[0x1];
+        theme.line_number_fg = base[0x3];
+        theme.line_number_bg = base[0x0];
+        theme.whitespace_indicator_fg = base[0x2];
+        theme.ruler_bg = base[0x1];
+
+        theme.menu_bg = base[0x1];
+        theme.menu_fg = base[0x5];
+        theme.menu_active_bg = base[0x2];
+        theme.menu_active_fg = base[0x6];
+        theme.menu_dropdown_bg = base[0x1];
+        theme.menu_dropdown_fg = base[0x5];
+        theme.menu_highlight_bg = base[0xD];
+        theme.menu_highlight_bg_end = None;
+        theme.menu_highlight_fg = base[0x0];
+        theme.menu_border_fg = base[0x3];
+        theme.menu_separator_fg = base[0x3];
+        theme.menu_hover_bg = base[0x2];
+        theme.menu_hover_fg = base[0x6];
+        theme.menu_disabled_fg = base[0x3];
+        theme.menu_disabled_bg = base[0x1];
+        theme.menu_shadow_bg = base[0x0];
+        theme.menu_destructive_fg = base[0x8];
+        theme.menu_primary_fg = base[0xD];
+
+        theme.status_bar_fg = base[0x4];
+        theme.status_bar_bg = base[0x1];
+        theme.search_match_bg = base[0xA];
+        theme.search_match_fg = base[0x0];
+
+        theme.diagnostic_error_fg = base[0x8];
+        theme.diagnostic_warning_fg = base[0xA];
+        theme.diagnostic_info_fg = base[0xD];
+        theme.diagnostic_hint_fg = base[0xC];
+
+        theme.syntax_keyword = base[0xE];
+        theme.syntax_string = base[0xB];
+        theme.syntax_comment = base[0x3];
+        theme.syntax_function = base[0xD];
+        theme.syntax_type = base[0xA];
+        theme.syntax_variable = base[0x8];
+        theme.syntax_constant = base[0x9];
+        theme.syntax_operator = base[0x5];
+        Ok(theme)
+    }
+
     /// Load builtin theme from the themes directory
     fn load_builtin_theme(name: &str) -> Option<Self> {
         // Build list of paths to search
@@ -509,6 +806,8 @@ impl Theme {
             current_line_bg: Color::Rgb(40, 40, 40),
             line_number_fg: Color::Rgb(100, 100, 100),
             line_number_bg: Color::Rgb(30, 30, 30),
+            whitespace_indicator_fg: Color::Rgb(70, 70, 70),
+            ruler_bg: Color::Rgb(45, 45, 45),
 
             // UI element colors
             tab_active_fg: Color::Yellow,
@@ -527,6 +826,7 @@ impl Theme {
             menu_dropdown_bg: Color::Rgb(50, 50, 50),
             menu_dropdown_fg: Color::Rgb(220, 220, 220),
             menu_highlight_bg: Color::Rgb(70, 130, 180),
+            menu_highlight_bg_end: None,
             menu_highlight_fg: Color::Rgb(255, 255, 255),
             menu_border_fg: Color::Rgb(100, 100, 100),
             menu_separator_fg: Color::Rgb(80, 80, 80),
@@ -534,6 +834,11 @@ impl Theme {
             menu_hover_fg: Color::Rgb(255, 255, 255),
             menu_disabled_fg: Color::Rgb(100, 100, 100), // Gray for disabled items
             menu_disabled_bg: Color::Rgb(50, 50, 50),
+            menu_shadow_bg: Color::Rgb(10, 10, 10),
+            menu_destructive_fg: Color::Rgb(255, 110, 110),
+            menu_primary_fg: Color::Rgb(110, 180, 255),
+            menu_shadow: true,
+            menu_border_style: MenuBorderStyle::Plain,
 
             status_bar_fg: Color::White,
             status_bar_bg: Color::Rgb(30, 30, 30), // Darker than DarkGray
@@ -619,6 +924,8 @@ impl Theme {
             current_line_bg: Color::Rgb(245, 245, 245),
             line_number_fg: Color::Rgb(140, 140, 140),
             line_number_bg: Color::Rgb(255, 255, 255),
+            whitespace_indicator_fg: Color::Rgb(200, 200, 200),
+            ruler_bg: Color::Rgb(235, 235, 235),
 
             // UI element colors
             tab_active_fg: Color::Rgb(40, 40, 40),
@@ -637,13 +944,19 @@ impl Theme {
             menu_dropdown_bg: Color::Rgb(248, 248, 248),
             menu_dropdown_fg: Color::Rgb(30, 30, 30),
             menu_highlight_bg: Color::Rgb(209, 226, 243), // Light blue highlight
-            menu_highlight_fg: Color::Rgb(0, 0, 0),       // Dark text on light highlight
+            menu_highlight_bg_end: None,
+            menu_highlight_fg: Color::Rgb(0, 0, 0), // Dark text on light highlight
             menu_border_fg: Color::Rgb(180, 180, 180),
             menu_separator_fg: Color::Rgb(210, 210, 210),
             menu_hover_bg: Color::Rgb(230, 235, 240),
             menu_hover_fg: Color::Rgb(0, 0, 0),
             menu_disabled_fg: Color::Rgb(160, 160, 160), // Gray for disabled items
             menu_disabled_bg: Color::Rgb(248, 248, 248),
+            menu_shadow_bg: Color::Rgb(190, 190, 190),
+            menu_destructive_fg: Color::Rgb(190, 30, 30),
+            menu_primary_fg: Color::Rgb(0, 95, 190),
+            menu_shadow: true,
+            menu_border_style: MenuBorderStyle::Plain,
 
             status_bar_fg: Color::Black,
             status_bar_bg: Color::Rgb(220, 220, 220), // Light grey
@@ -729,6 +1042,8 @@ impl Theme {
             current_line_bg: Color::Rgb(20, 20, 20),
             line_number_fg: Color::Rgb(140, 140, 140),
             line_number_bg: Color::Black,
+            whitespace_indicator_fg: Color::DarkGray,
+            ruler_bg: Color::Rgb(50, 50, 50),
 
             // UI element colors
             tab_active_fg: Color::Black,
@@ -747,6 +1062,7 @@ impl Theme {
             menu_dropdown_bg: Color::Rgb(20, 20, 20),
             menu_dropdown_fg: Color::White,
             menu_highlight_bg: Color::Rgb(0, 100, 200),
+            menu_highlight_bg_end: None,
             menu_highlight_fg: Color::White,
             menu_border_fg: Color::Yellow,
             menu_separator_fg: Color::White,
@@ -754,6 +1070,11 @@ impl Theme {
             menu_hover_fg: Color::Yellow,
             menu_disabled_fg: Color::DarkGray, // Low contrast gray for disabled
             menu_disabled_bg: Color::Rgb(20, 20, 20),
+            menu_shadow_bg: Color::Black,
+            menu_destructive_fg: Color::Rgb(255, 90, 90),
+            menu_primary_fg: Color::Rgb(100, 200, 255),
+            menu_shadow: false,
+            menu_border_style: MenuBorderStyle::Plain,
 
             status_bar_fg: Color::White,
             status_bar_bg: Color::Rgb(20, 20, 20), // Darker for high contrast
@@ -844,14 +1165,17 @@ impl Theme {
         }
     }
 
+    /// Names of the themes that ship with the editor
+    pub fn builtin_names() -> &'static [&'static str] {
+        &["dark", "light", "high-contrast", "nostalgia"]
+    }
+
     /// Get all available theme names (builtin + user themes)
     pub fn available_themes() -> Vec<String> {
-        let mut themes: Vec<String> = vec![
-            "dark".to_string(),
-            "light".to_string(),
-            "high-contrast".to_string(),
-            "nostalgia".to_string(),
-        ];
+        let mut themes: Vec<String> = Self::builtin_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
 
         // Scan user themes directory
         if let Some(config_dir) = dirs::config_dir() {
@@ -875,6 +1199,117 @@ impl Theme {
         themes
     }
 
+    /// Return a copy of this theme with every color reduced to what the
+    /// terminal can display.
+    ///
+    /// RGB values are mapped to the nearest entry of the xterm 256-color
+    /// palette or the 16 ANSI colors. Named colors and `Reset` are kept as-is.
+    pub fn degrade_to(&self, support: ColorSupport) -> Theme {
+        let mut theme = self.clone();
+        if support != ColorSupport::TrueColor {
+            theme.map_colors(|color| support.degrade(color));
+            // Too few colors for a smooth fade; keep the solid start color
+            theme.menu_highlight_bg_end = None;
+        }
+        theme
+    }
+
+    /// Return a copy of this theme with every foreground color moved `factor`
+    /// of the way toward its background, for drawing unfocused splits
+    ///
+    /// A `*_fg` color fades toward the matching `*_bg` color, and syntax colors
+    /// and `*_fg` keys without a background toward `editor_bg`. Cursors are
+    /// drawn as backgrounds and stay visible. The mix happens in RGB, so the result is RGB and
+    /// should be degraded again for terminals without true color. `factor` is
+    /// clamped to 0.0 (unchanged) ..= 1.0 (foreground equals background), and
+    /// colors without an RGB value, like `Reset`, are kept.
+    pub fn dim(&self, factor: f32) -> Theme {
+        let factor = factor.clamp(0.0, 1.0);
+        let mut theme = self.clone();
+        if factor == 0.0 {
+            return theme;
+        }
+        for &key in ThemeKey::ALL {
+            let Some(background) = key.background() else {
+                continue;
+            };
+            let (Some((r1, g1, b1)), Some((r2, g2, b2))) = (
+                color_to_rgb(self.get(key)),
+                color_to_rgb(self.get(background)),
+            ) else {
+                continue;
+            };
+            let mix = |a: u8, b: u8| {
+                (a as f32 + (b as f32 - a as f32) * factor)
+                    .round()
+                    .clamp(0.0, 255.0) as u8
+            };
+            *theme.color_mut(key) = Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2));
+        }
+        theme
+    }
+
+    /// Background of column `col` of a highlighted dropdown item `width` columns wide
+    ///
+    /// Interpolates linearly from `menu_highlight_bg` at the first column to
+    /// `menu_highlight_bg_end` at the last. Without an end color, or if either
+    /// color isn't RGB, the bar is solid `menu_highlight_bg`.
+    pub fn menu_highlight_bg_at(&self, col: usize, width: usize) -> Color {
+        let start = self.menu_highlight_bg;
+        let (Color::Rgb(r1, g1, b1), Some(Color::Rgb(r2, g2, b2))) =
+            (start, self.menu_highlight_bg_end)
+        else {
+            return start;
+        };
+        if width < 2 {
+            return start;
+        }
+        let t = col.min(width - 1) as f64 / (width - 1) as f64;
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+    }
+
+    /// Find foreground/background pairs that are hard to read
+    ///
+    /// Returns each pair in `CONTRAST_PAIRS` whose WCAG contrast ratio is below
+    /// `MIN_CONTRAST_RATIO`. Pairs involving `Reset` are skipped, since the
+    /// terminal's default colors aren't known.
+    pub fn contrast_warnings(&self) -> Vec<ContrastWarning> {
+        CONTRAST_PAIRS
+            .iter()
+            .filter_map(|&(fg, bg)| {
+                let ratio =
+                    contrast_ratio(color_to_rgb(self.get(fg))?, color_to_rgb(self.get(bg))?);
+                (ratio < MIN_CONTRAST_RATIO).then_some(ContrastWarning { fg, bg, ratio })
+            })
+            .collect()
+    }
+
+    /// Apply `f` to every color in the theme
+    fn map_colors(&mut self, f: impl Fn(Color) -> Color) {
+        for &key in ThemeKey::ALL {
+            let color = self.color_mut(key);
+            *color = f(*color);
+        }
+    }
+
+    /// Get the color for a theme key
+    pub fn get(&self, key: ThemeKey) -> Color {
+        *self.color_ref(key)
+    }
+
+    /// Change a single color, e.g. from a theme editor preview
+    ///
+    /// Only colors that can be written back to a theme file (RGB and the named
+    /// ANSI colors, including `Reset`) are accepted; palette indices are rejected.
+    pub fn set(&mut self, key: ThemeKey, color: Color) -> Result<(), ThemeError> {
+        if let Color::Indexed(_) = color {
+            return Err(ThemeError::UnsupportedColor(color));
+        }
+        *self.color_mut(key) = color;
+        Ok(())
+    }
+
     /// Nostalgia theme (Turbo Pascal 5 / WordPerfect 5 inspired)
     pub fn nostalgia() -> Self {
         Self {
@@ -889,6 +1324,8 @@ impl Theme {
             current_line_bg: Color::Rgb(0, 0, 128),  // Slightly darker blue
             line_number_fg: Color::Rgb(85, 255, 255), // Cyan
             line_number_bg: Color::Rgb(0, 0, 170),
+            whitespace_indicator_fg: Color::Rgb(85, 85, 255),
+            ruler_bg: Color::Rgb(0, 0, 140),
 
             // UI element colors
             tab_active_fg: Color::Rgb(0, 0, 0),
@@ -907,6 +1344,7 @@ impl Theme {
             menu_dropdown_bg: Color::Rgb(170, 170, 170),
             menu_dropdown_fg: Color::Rgb(0, 0, 0),
             menu_highlight_bg: Color::Rgb(0, 170, 0), // Green highlight
+            menu_highlight_bg_end: None,
             menu_highlight_fg: Color::Rgb(255, 255, 255),
             menu_border_fg: Color::Rgb(0, 0, 0),
             menu_separator_fg: Color::Rgb(85, 85, 85),
@@ -914,6 +1352,11 @@ impl Theme {
             menu_hover_fg: Color::Rgb(255, 255, 255),
             menu_disabled_fg: Color::Rgb(85, 85, 85), // Dark gray for disabled
             menu_disabled_bg: Color::Rgb(170, 170, 170),
+            menu_shadow_bg: Color::Black,
+            menu_destructive_fg: Color::Rgb(128, 0, 0),
+            menu_primary_fg: Color::Rgb(0, 0, 170),
+            menu_shadow: true,
+            menu_border_style: MenuBorderStyle::Plain,
 
             status_bar_fg: Color::Rgb(0, 0, 0),
             status_bar_bg: Color::Rgb(0, 170, 170), // Cyan status bar
@@ -986,12 +1429,566 @@ impl Theme {
     }
 }
 
+/// Defines `ThemeKey` with one variant per color field of `Theme`, along with
+/// the accessors that map a key to its field
+macro_rules! theme_keys {
+    ($($variant:ident => $field:ident,)*) => {
+        /// Identifies one color of a `Theme`
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum ThemeKey {
+            $($variant,)*
+        }
+
+        impl ThemeKey {
+            /// Every key, in field declaration order
+            pub const ALL: &'static [ThemeKey] = &[$(ThemeKey::$variant,)*];
+
+            /// Name of the key, matching the `Theme` field (e.g. "editor_bg")
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(ThemeKey::$variant => stringify!($field),)*
+                }
+            }
+
+            /// The background a foreground key is drawn on (see [`Theme::dim`]),
+            /// or None for background keys
+            pub fn background(self) -> Option<ThemeKey> {
+                let name = self.name();
+                if let Some(stem) = name.strip_suffix("_fg") {
+                    let background = format!("{stem}_bg");
+                    Some(
+                        Self::ALL
+                            .iter()
+                            .copied()
+                            .find(|key| key.name() == background)
+                            .unwrap_or(ThemeKey::EditorBg),
+                    )
+                } else if name.starts_with("syntax_") {
+                    Some(ThemeKey::EditorBg)
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl Theme {
+            fn color_ref(&self, key: ThemeKey) -> &Color {
+                match key {
+                    $(ThemeKey::$variant => &self.$field,)*
+                }
+            }
+
+            fn color_mut(&mut self, key: ThemeKey) -> &mut Color {
+                match key {
+                    $(ThemeKey::$variant => &mut self.$field,)*
+                }
+            }
+        }
+    };
+}
+
+theme_keys! {
+    EditorBg => editor_bg,
+    EditorFg => editor_fg,
+    Cursor => cursor,
+    InactiveCursor => inactive_cursor,
+    SelectionBg => selection_bg,
+    CurrentLineBg => current_line_bg,
+    LineNumberFg => line_number_fg,
+    LineNumberBg => line_number_bg,
+    WhitespaceIndicatorFg => whitespace_indicator_fg,
+    RulerBg => ruler_bg,
+    TabActiveFg => tab_active_fg,
+    TabActiveBg => tab_active_bg,
+    TabInactiveFg => tab_inactive_fg,
+    TabInactiveBg => tab_inactive_bg,
+    TabSeparatorBg => tab_separator_bg,
+    TabCloseHoverFg => tab_close_hover_fg,
+    TabHoverBg => tab_hover_bg,
+    MenuBg => menu_bg,
+    MenuFg => menu_fg,
+    MenuActiveBg => menu_active_bg,
+    MenuActiveFg => menu_active_fg,
+    MenuDropdownBg => menu_dropdown_bg,
+    MenuDropdownFg => menu_dropdown_fg,
+    MenuHighlightBg => menu_highlight_bg,
+    MenuHighlightFg => menu_highlight_fg,
+    MenuBorderFg => menu_border_fg,
+    MenuSeparatorFg => menu_separator_fg,
+    MenuHoverBg => menu_hover_bg,
+    MenuHoverFg => menu_hover_fg,
+    MenuDisabledFg => menu_disabled_fg,
+    MenuDisabledBg => menu_disabled_bg,
+    MenuShadowBg => menu_shadow_bg,
+    MenuDestructiveFg => menu_destructive_fg,
+    MenuPrimaryFg => menu_primary_fg,
+    StatusBarFg => status_bar_fg,
+    StatusBarBg => status_bar_bg,
+    PromptFg => prompt_fg,
+    PromptBg => prompt_bg,
+    PromptSelectionFg => prompt_selection_fg,
+    PromptSelectionBg => prompt_selection_bg,
+    PopupBorderFg => popup_border_fg,
+    PopupBg => popup_bg,
+    PopupSelectionBg => popup_selection_bg,
+    PopupTextFg => popup_text_fg,
+    SuggestionBg => suggestion_bg,
+    SuggestionSelectedBg => suggestion_selected_bg,
+    HelpBg => help_bg,
+    HelpFg => help_fg,
+    HelpKeyFg => help_key_fg,
+    HelpSeparatorFg => help_separator_fg,
+    HelpIndicatorFg => help_indicator_fg,
+    HelpIndicatorBg => help_indicator_bg,
+    InlineCodeBg => inline_code_bg,
+    SplitSeparatorFg => split_separator_fg,
+    SplitSeparatorHoverFg => split_separator_hover_fg,
+    ScrollbarTrackFg => scrollbar_track_fg,
+    ScrollbarThumbFg => scrollbar_thumb_fg,
+    ScrollbarTrackHoverFg => scrollbar_track_hover_fg,
+    ScrollbarThumbHoverFg => scrollbar_thumb_hover_fg,
+    ComposeMarginBg => compose_margin_bg,
+    SemanticHighlightBg => semantic_highlight_bg,
+    TerminalBg => terminal_bg,
+    TerminalFg => terminal_fg,
+    SearchMatchBg => search_match_bg,
+    SearchMatchFg => search_match_fg,
+    DiagnosticErrorFg => diagnostic_error_fg,
+    DiagnosticErrorBg => diagnostic_error_bg,
+    DiagnosticWarningFg => diagnostic_warning_fg,
+    DiagnosticWarningBg => diagnostic_warning_bg,
+    DiagnosticInfoFg => diagnostic_info_fg,
+    DiagnosticInfoBg => diagnostic_info_bg,
+    DiagnosticHintFg => diagnostic_hint_fg,
+    DiagnosticHintBg => diagnostic_hint_bg,
+    SyntaxKeyword => syntax_keyword,
+    SyntaxString => syntax_string,
+    SyntaxComment => syntax_comment,
+    SyntaxFunction => syntax_function,
+    SyntaxType => syntax_type,
+    SyntaxVariable => syntax_variable,
+    SyntaxConstant => syntax_constant,
+    SyntaxOperator => syntax_operator,
+}
+
+impl std::str::FromStr for ThemeKey {
+    type Err = ThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ThemeKey::ALL
+            .iter()
+            .copied()
+            .find(|key| key.name() == s)
+            .ok_or_else(|| ThemeError::UnknownKey(s.to_string()))
+    }
+}
+
+/// Errors from editing a theme at runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeError {
+    /// No theme color has this name
+    UnknownKey(String),
+    /// The color can't be stored in a theme file
+    UnsupportedColor(Color),
+}
+
+impl std::fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeError::UnknownKey(key) => write!(f, "Unknown theme color: {key}"),
+            ThemeError::UnsupportedColor(color) => {
+                write!(f, "Unsupported theme color: {color:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+/// Color capability of the terminal the editor is running in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSupport {
+    /// 24-bit RGB colors are rendered as-is
+    #[default]
+    TrueColor,
+    /// xterm 256-color palette (6x6x6 cube + grayscale ramp)
+    Ansi256,
+    /// The 16 basic ANSI colors
+    Ansi16,
+}
+
+/// xterm default RGB values for the 16 ANSI colors, paired with the ratatui color
+const ANSI16_PALETTE: [((u8, u8, u8), Color); 16] = [
+    ((0, 0, 0), Color::Black),
+    ((205, 0, 0), Color::Red),
+    ((0, 205, 0), Color::Green),
+    ((205, 205, 0), Color::Yellow),
+    ((0, 0, 238), Color::Blue),
+    ((205, 0, 205), Color::Magenta),
+    ((0, 205, 205), Color::Cyan),
+    ((229, 229, 229), Color::Gray),
+    ((127, 127, 127), Color::DarkGray),
+    ((255, 0, 0), Color::LightRed),
+    ((0, 255, 0), Color::LightGreen),
+    ((255, 255, 0), Color::LightYellow),
+    ((92, 92, 255), Color::LightBlue),
+    ((255, 0, 255), Color::LightMagenta),
+    ((0, 255, 255), Color::LightCyan),
+    ((255, 255, 255), Color::White),
+];
+
+/// Channel levels used by the 6x6x6 color cube (indices 16-231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    /// Detect the color capability of the current terminal.
+    ///
+    /// `FRESH_COLOR_SUPPORT` (`truecolor`, `256` or `16`) overrides detection.
+    /// Otherwise `$COLORTERM` and `$TERM` are consulted. When nothing indicates
+    /// a limited terminal, true color is assumed.
+    pub fn detect() -> Self {
+        let override_value = std::env::var("FRESH_COLOR_SUPPORT").ok();
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+        Self::detect_from(
+            override_value.as_deref(),
+            colorterm.as_deref(),
+            term.as_deref(),
+        )
+    }
+
+    /// Detection logic behind [`ColorSupport::detect`], separated for testing
+    fn detect_from(
+        override_value: Option<&str>,
+        colorterm: Option<&str>,
+        term: Option<&str>,
+    ) -> Self {
+        if let Some(support) = override_value.and_then(Self::parse) {
+            return support;
+        }
+
+        if let Some(colorterm) = colorterm {
+            let colorterm = colorterm.to_lowercase();
+            if colorterm == "truecolor" || colorterm == "24bit" {
+                return ColorSupport::TrueColor;
+            }
+        }
+
+        match term {
+            Some(term) if term.contains("256color") => ColorSupport::Ansi256,
+            Some(term)
+                if term.contains("16color")
+                    || matches!(term, "linux" | "vt100" | "vt220" | "ansi" | "cygwin") =>
+            {
+                ColorSupport::Ansi16
+            }
+            _ => ColorSupport::TrueColor,
+        }
+    }
+
+    /// Parse a capability name as accepted by `FRESH_COLOR_SUPPORT`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "truecolor" | "24bit" | "rgb" => Some(ColorSupport::TrueColor),
+            "256" | "256color" | "ansi256" => Some(ColorSupport::Ansi256),
+            "16" | "16color" | "ansi16" | "ansi" => Some(ColorSupport::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// Reduce a single color to this capability
+    pub fn degrade(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::TrueColor, _) => color,
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_ansi256(r, g, b)),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => rgb_to_ansi16(r, g, b),
+            (ColorSupport::Ansi16, Color::Indexed(idx)) if idx >= 16 => {
+                let (r, g, b) = ansi256_to_rgb(idx);
+                rgb_to_ansi16(r, g, b)
+            }
+            _ => color,
+        }
+    }
+}
+
+fn distance_sq(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+/// Index of the cube level closest to a channel value
+fn nearest_cube_level(value: u8) -> usize {
+    CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, level)| (**level as i32 - value as i32).abs())
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Map an RGB value to the nearest xterm 256-color index (16-255)
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let (ri, gi, bi) = (
+        nearest_cube_level(r),
+        nearest_cube_level(g),
+        nearest_cube_level(b),
+    );
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    // Grayscale ramp: 232-255 covers 8, 18, ..., 238
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (avg.saturating_sub(3) / 10).min(23) as u8;
+    let gray_value = 8 + gray_step * 10;
+    let gray_index = 232 + gray_step as usize;
+
+    let target = (r, g, b);
+    if distance_sq(target, (gray_value, gray_value, gray_value)) < distance_sq(target, cube_rgb) {
+        gray_index as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+/// RGB value of an xterm 256-color index
+fn ansi256_to_rgb(idx: u8) -> (u8, u8, u8) {
+    match idx {
+        0..=15 => ANSI16_PALETTE[idx as usize].0,
+        16..=231 => {
+            let i = (idx - 16) as usize;
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[(i / 6) % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let gray = 8 + (idx - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Lowest WCAG contrast ratio accepted between text and its background
+///
+/// 3:1 is the WCAG AA level for large text; terminal cells are small, but
+/// themes often trade some contrast for style, so only clear problems are flagged.
+pub const MIN_CONTRAST_RATIO: f64 = 3.0;
+
+/// Foreground/background pairs that are drawn on top of each other as text
+///
+/// Disabled menu items are meant to look faded and aren't checked.
+pub const CONTRAST_PAIRS: &[(ThemeKey, ThemeKey)] = &[
+    (ThemeKey::EditorFg, ThemeKey::EditorBg),
+    (ThemeKey::MenuFg, ThemeKey::MenuBg),
+    (ThemeKey::MenuActiveFg, ThemeKey::MenuActiveBg),
+    (ThemeKey::MenuDropdownFg, ThemeKey::MenuDropdownBg),
+    (ThemeKey::MenuHighlightFg, ThemeKey::MenuHighlightBg),
+    (ThemeKey::MenuHoverFg, ThemeKey::MenuHoverBg),
+    (ThemeKey::MenuDestructiveFg, ThemeKey::MenuDropdownBg),
+    (ThemeKey::MenuPrimaryFg, ThemeKey::MenuDropdownBg),
+    (ThemeKey::StatusBarFg, ThemeKey::StatusBarBg),
+    (ThemeKey::PromptFg, ThemeKey::PromptBg),
+    (ThemeKey::PopupTextFg, ThemeKey::PopupBg),
+];
+
+/// A foreground/background pair of a theme with too little contrast
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastWarning {
+    pub fg: ThemeKey,
+    pub bg: ThemeKey,
+    /// WCAG contrast ratio between the two colors (1.0 to 21.0)
+    pub ratio: f64,
+}
+
+impl std::fmt::Display for ContrastWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on {} has a contrast ratio of {:.2}:1 (at least {}:1 recommended)",
+            self.fg.name(),
+            self.bg.name(),
+            self.ratio,
+            MIN_CONTRAST_RATIO
+        )
+    }
+}
+
+/// Approximate RGB value of a color, None for `Reset`
+fn color_to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(idx) => Some(ansi256_to_rgb(idx)),
+        Color::Reset => None,
+        named => ANSI16_PALETTE
+            .iter()
+            .find(|(_, color)| *color == named)
+            .map(|(rgb, _)| *rgb),
+    }
+}
+
+/// WCAG relative luminance of an sRGB color
+fn relative_luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    let channel = |value: u8| {
+        let c = value as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// Whether text in `fg` on `bg` meets `MIN_CONTRAST_RATIO`
+///
+/// Colors that can't be judged (`Reset`) count as readable.
+pub fn is_readable(fg: Color, bg: Color) -> bool {
+    match (color_to_rgb(fg), color_to_rgb(bg)) {
+        (Some(fg), Some(bg)) => contrast_ratio(fg, bg) >= MIN_CONTRAST_RATIO,
+        _ => true,
+    }
+}
+
+/// WCAG contrast ratio between two colors, from 1.0 (identical) to 21.0
+fn contrast_ratio(a: (u8, u8, u8), b: (u8, u8, u8)) -> f64 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Map an RGB value to the nearest of the 16 ANSI colors
+fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    ANSI16_PALETTE
+        .iter()
+        .min_by_key(|(rgb, _)| distance_sq((r, g, b), *rgb))
+        .map(|(_, color)| *color)
+        .unwrap_or(Color::White)
+}
+
+/// The value of a `key: value` YAML line: unquoted, without a trailing comment
+fn yaml_scalar(value: &str) -> &str {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(rest) = value.strip_prefix(quote) {
+            return rest.split(quote).next().unwrap_or(rest);
+        }
+    }
+    value.split(" #").next().unwrap_or(value).trim()
+}
+
+/// Parse `rrggbb` or `#rrggbb`
+fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
 impl Default for Theme {
     fn default() -> Self {
         Self::high_contrast()
     }
 }
 
+/// The active theme, with changes staged until the next frame boundary
+///
+/// Readers always see the committed theme, so switching themes while a frame
+/// is being drawn can't leave it half in the old colors and half in the new.
+/// The render loop calls `commit` before drawing each frame.
+#[derive(Debug)]
+pub struct ThemeManager {
+    active: Arc<Theme>,
+    staged: Option<Theme>,
+    /// The active theme as returned by `dimmed`, with the factor and color
+    /// support it was made for; dropped when another theme is committed
+    dimmed: Option<(f32, ColorSupport, Arc<Theme>)>,
+}
+
+impl ThemeManager {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            active: Arc::new(theme),
+            staged: None,
+            dimmed: None,
+        }
+    }
+
+    /// The committed theme, for holding on to across a frame
+    pub fn current(&self) -> Arc<Theme> {
+        Arc::clone(&self.active)
+    }
+
+    /// Queue `theme` to replace the active one at the next `commit`
+    ///
+    /// Staging again before committing replaces the pending theme.
+    pub fn stage(&mut self, theme: Theme) {
+        self.staged = Some(theme);
+    }
+
+    /// Whether a staged theme is waiting for `commit`
+    pub fn has_staged(&self) -> bool {
+        self.staged.is_some()
+    }
+
+    /// Make the staged theme active
+    /// Returns true if there was one
+    pub fn commit(&mut self) -> bool {
+        match self.staged.take() {
+            Some(theme) => {
+                self.active = Arc::new(theme);
+                self.dimmed = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The committed theme [dimmed](Theme::dim) by `factor` and degraded to
+    /// `support`, for drawing unfocused splits
+    ///
+    /// It is built on the first call after the theme, `factor` or `support`
+    /// changes and reused until then.
+    pub fn dimmed(&mut self, factor: f32, support: ColorSupport) -> Arc<Theme> {
+        match &self.dimmed {
+            Some((f, s, theme)) if *f == factor && *s == support => Arc::clone(theme),
+            _ => {
+                let theme = Arc::new(self.active.dim(factor).degrade_to(support));
+                self.dimmed = Some((factor, support, Arc::clone(&theme)));
+                theme
+            }
+        }
+    }
+
+    /// Stage the builtin theme following the current one (the staged one if
+    /// a change is already pending), wrapping around
+    ///
+    /// `load` builds the theme from its name, so the caller can prepare it the
+    /// way it prepares any other theme it loads.
+    /// Returns the name of the staged theme
+    pub fn cycle(&mut self, load: impl FnOnce(&str) -> Theme) -> &'static str {
+        let names = Theme::builtin_names();
+        let current = &self.staged.as_ref().unwrap_or(self.active.as_ref()).name;
+        let next = names
+            .iter()
+            .position(|name| name == current)
+            .map_or(0, |i| (i + 1) % names.len());
+        self.stage(load(names[next]));
+        names[next]
+    }
+}
+
+impl std::ops::Deref for ThemeManager {
+    type Target = Theme;
+
+    fn deref(&self) -> &Theme {
+        &self.active
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1020,6 +2017,138 @@ mod tests {
         assert_eq!(theme.name, "dark");
     }
 
+    #[test]
+    fn test_theme_set_get_round_trip() {
+        let mut theme = Theme::dark();
+        for (key, color) in [
+            (ThemeKey::EditorBg, Color::Rgb(1, 2, 3)),
+            (ThemeKey::Cursor, Color::Yellow),
+            (ThemeKey::MenuHighlightFg, Color::Reset),
+            (ThemeKey::SyntaxOperator, Color::Rgb(200, 100, 50)),
+        ] {
+            theme.set(key, color).unwrap();
+            assert_eq!(theme.get(key), color);
+        }
+        assert_eq!(theme.editor_bg, Color::Rgb(1, 2, 3));
+        assert_eq!(theme.syntax_operator, Color::Rgb(200, 100, 50));
+        // Other colors are untouched
+        assert_eq!(theme.editor_fg, Theme::dark().editor_fg);
+    }
+
+    #[test]
+    fn test_theme_set_rejects_indexed_color() {
+        let mut theme = Theme::dark();
+        let before = theme.get(ThemeKey::StatusBarBg);
+        assert_eq!(
+            theme.set(ThemeKey::StatusBarBg, Color::Indexed(42)),
+            Err(ThemeError::UnsupportedColor(Color::Indexed(42)))
+        );
+        assert_eq!(theme.get(ThemeKey::StatusBarBg), before);
+    }
+
+    #[test]
+    fn test_theme_key_names() {
+        assert_eq!(ThemeKey::ALL.len(), 81);
+        for &key in ThemeKey::ALL {
+            assert_eq!(key.name().parse::<ThemeKey>(), Ok(key));
+        }
+        assert_eq!(
+            "not_a_color".parse::<ThemeKey>(),
+            Err(ThemeError::UnknownKey("not_a_color".to_string()))
+        );
+    }
+
+    /// dark.json with the role-derived fields removed and `palette` set
+    fn theme_with_palette(palette: serde_json::Value) -> Theme {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/themes/dark.json");
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let ui = json["ui"].as_object_mut().unwrap();
+        ui.retain(|key, _| !key.starts_with("menu_") || key == "menu_shadow");
+        ui.remove("split_separator_hover_fg");
+        ui.remove("compose_margin_bg");
+        json["palette"] = palette;
+        serde_json::from_value::<ThemeFile>(json).unwrap().into()
+    }
+
+    #[test]
+    fn test_palette_accent_colors_derived_fields() {
+        let theme = theme_with_palette(serde_json::json!({ "accent": [200, 50, 100] }));
+        let accent = Color::Rgb(200, 50, 100);
+        assert_eq!(theme.menu_active_bg, accent);
+        assert_eq!(theme.menu_highlight_bg, accent);
+        assert_eq!(theme.menu_hover_bg, accent);
+        assert_eq!(theme.split_separator_hover_fg, accent);
+
+        // Fields of roles that weren't set keep their defaults
+        assert_eq!(theme.menu_dropdown_bg, default_menu_dropdown_bg().into());
+        assert_eq!(theme.menu_border_fg, default_menu_border_fg().into());
+    }
+
+    #[test]
+    fn test_palette_explicit_field_wins() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/themes/dark.json");
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        json["palette"] = serde_json::json!({ "surface": "Blue" });
+        json["ui"]["menu_bg"] = serde_json::json!("Red");
+        let theme: Theme = serde_json::from_value::<ThemeFile>(json).unwrap().into();
+
+        assert_eq!(theme.menu_bg, Color::Red);
+        assert_eq!(theme.menu_dropdown_bg, Color::Blue);
+        assert_eq!(theme.menu_disabled_bg, Color::Blue);
+    }
+
+    #[test]
+    fn test_theme_from_base16_scheme() {
+        let scheme = r##"
+scheme: "Default Dark"
+author: "Chris Kempson (http://chriskempson.com)"
+base00: "181818"
+base01: "282828"
+base02: "383838"
+base03: "585858" # comments
+base04: "b8b8b8"
+base05: "d8d8d8"
+base06: "e8e8e8"
+base07: "f8f8f8"
+base08: "ab4642"
+base09: "dc9656"
+base0A: "f7ca88"
+base0B: "a1b56c"
+base0C: "86c1b9"
+base0D: "7cafc2"
+base0E: "ba8baf"
+base0F: "a16946"
+"##;
+        let theme = Theme::from_base16(scheme).unwrap();
+        assert_eq!(theme.name, "Default Dark");
+        assert_eq!(theme.editor_bg, Color::Rgb(0x18, 0x18, 0x18));
+        assert_eq!(theme.editor_fg, Color::Rgb(0xd8, 0xd8, 0xd8));
+        assert_eq!(theme.selection_bg, Color::Rgb(0x38, 0x38, 0x38));
+        assert_eq!(theme.menu_bg, Color::Rgb(0x28, 0x28, 0x28));
+        assert_eq!(theme.menu_highlight_bg, Color::Rgb(0x7c, 0xaf, 0xc2));
+        assert_eq!(theme.syntax_keyword, Color::Rgb(0xba, 0x8b, 0xaf));
+        assert_eq!(theme.syntax_string, Color::Rgb(0xa1, 0xb5, 0x6c));
+        assert_eq!(theme.syntax_comment, Color::Rgb(0x58, 0x58, 0x58));
+        // Unmapped fields come from the builtin theme of the same brightness
+        assert_eq!(theme.tab_close_hover_fg, Theme::dark().tab_close_hover_fg);
+
+        // The newer format nests `#`-prefixed colors under `palette:`
+        let light = scheme
+            .replace("scheme: \"Default Dark\"", "name: Paper\npalette:")
+            .replace("base00: \"181818\"", "  base00: '#f8f8f8'");
+        let theme = Theme::from_base16(&light).unwrap();
+        assert_eq!(theme.name, "Paper");
+        assert_eq!(theme.editor_bg, Color::Rgb(0xf8, 0xf8, 0xf8));
+        assert_eq!(theme.tab_close_hover_fg, Theme::light().tab_close_hover_fg);
+
+        let missing = scheme.replace("base0F: \"a16946\"", "");
+        assert!(Theme::from_base16(&missing).is_err());
+        let invalid = scheme.replace("\"ab4642\"", "red");
+        assert!(Theme::from_base16(&invalid).is_err());
+    }
+
     #[test]
     fn test_available_themes() {
         let themes = Theme::available_themes();
@@ -1047,4 +2176,224 @@ mod tests {
         let color: Color = ColorDef::Named("Reset".to_string()).into();
         assert_eq!(color, Color::Reset);
     }
+
+    #[test]
+    fn test_theme_file_accepts_lowercase_default() {
+        // Any field of a theme file may be "default", in either case
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/themes/dark.json");
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        json["ui"]["menu_bg"] = "default".into();
+        json["ui"]["menu_dropdown_bg"] = "reset".into();
+        json["editor"]["bg"] = "Default".into();
+        let theme: Theme = serde_json::from_value::<ThemeFile>(json).unwrap().into();
+        assert_eq!(theme.menu_bg, Color::Reset);
+        assert_eq!(theme.menu_dropdown_bg, Color::Reset);
+        assert_eq!(theme.editor_bg, Color::Reset);
+    }
+
+    #[test]
+    fn test_dim_zero_keeps_theme() {
+        let dark = Theme::dark();
+        let same = dark.dim(0.0);
+        for &key in ThemeKey::ALL {
+            assert_eq!(same.get(key), dark.get(key), "{}", key.name());
+        }
+        // Negative factors clamp to no change
+        assert_eq!(dark.dim(-1.0).editor_fg, dark.editor_fg);
+    }
+
+    #[test]
+    fn test_dim_one_collapses_fg_onto_bg() {
+        let dark = Theme::dark();
+        let faded = dark.dim(1.0);
+        assert_eq!(faded.editor_fg, faded.editor_bg);
+        assert_eq!(faded.syntax_keyword, faded.editor_bg);
+        assert_eq!(faded.inactive_cursor, dark.inactive_cursor);
+        // A fg with its own bg fades toward that one
+        assert_eq!(faded.status_bar_fg, faded.status_bar_bg);
+        // Backgrounds stay, and factors above 1.0 clamp
+        assert_eq!(faded.editor_bg, dark.editor_bg);
+        assert_eq!(faded.selection_bg, dark.selection_bg);
+        assert_eq!(dark.dim(2.0).editor_fg, faded.editor_fg);
+
+        let mut theme = Theme::dark();
+        theme.editor_fg = Color::Rgb(200, 100, 0);
+        theme.editor_bg = Color::Rgb(0, 0, 100);
+        assert_eq!(theme.dim(0.5).editor_fg, Color::Rgb(100, 50, 50));
+        theme.editor_bg = Color::Reset;
+        assert_eq!(theme.dim(0.5).editor_fg, Color::Rgb(200, 100, 0));
+    }
+
+    #[test]
+    fn test_rgb_to_ansi256() {
+        assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+        assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+        assert_eq!(rgb_to_ansi256(95, 135, 175), 67);
+        // Mid gray is closer to the grayscale ramp than to the cube
+        assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+        // VSCode-style editor background lands on the dark end of the ramp
+        assert_eq!(rgb_to_ansi256(30, 30, 30), 234);
+    }
+
+    #[test]
+    fn test_rgb_to_ansi16() {
+        assert_eq!(rgb_to_ansi16(0, 0, 0), Color::Black);
+        assert_eq!(rgb_to_ansi16(250, 10, 10), Color::LightRed);
+        assert_eq!(rgb_to_ansi16(0, 0, 170), Color::Blue);
+        assert_eq!(rgb_to_ansi16(250, 250, 250), Color::White);
+    }
+
+    #[test]
+    fn test_degrade_theme() {
+        let dark = Theme::dark();
+
+        let same = dark.degrade_to(ColorSupport::TrueColor);
+        assert_eq!(same.editor_bg, Color::Rgb(30, 30, 30));
+
+        let degraded = dark.degrade_to(ColorSupport::Ansi256);
+        assert_eq!(degraded.editor_bg, Color::Indexed(234));
+        // Named colors are left alone
+        assert_eq!(degraded.tab_active_fg, Color::Yellow);
+
+        let degraded = dark.degrade_to(ColorSupport::Ansi16);
+        assert!(!matches!(
+            degraded.editor_bg,
+            Color::Rgb(..) | Color::Indexed(_)
+        ));
+        assert_eq!(
+            ColorSupport::Ansi16.degrade(Color::Indexed(196)),
+            Color::LightRed
+        );
+    }
+
+    #[test]
+    fn test_detect_color_support() {
+        let detect = ColorSupport::detect_from;
+        assert_eq!(
+            detect(None, Some("truecolor"), Some("xterm-256color")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(None, None, Some("xterm-256color")),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(detect(None, None, Some("linux")), ColorSupport::Ansi16);
+        assert_eq!(detect(None, None, None), ColorSupport::TrueColor);
+        assert_eq!(
+            detect(Some("16"), Some("truecolor"), None),
+            ColorSupport::Ansi16
+        );
+        // Unknown override values fall through to detection
+        assert_eq!(
+            detect(Some("bogus"), None, Some("screen-256color")),
+            ColorSupport::Ansi256
+        );
+    }
+
+    #[test]
+    fn test_contrast_warnings_flag_low_contrast_menu() {
+        let mut theme = Theme::dark();
+        theme.menu_bg = Color::Rgb(60, 60, 60);
+        theme.menu_fg = Color::Rgb(70, 70, 70);
+
+        let warnings = theme.contrast_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            (warnings[0].fg, warnings[0].bg),
+            (ThemeKey::MenuFg, ThemeKey::MenuBg)
+        );
+        assert!(warnings[0].ratio < 1.2);
+        assert!(warnings[0].to_string().starts_with("menu_fg on menu_bg"));
+    }
+
+    #[test]
+    fn test_contrast_warnings_pass_readable_pairs() {
+        let mut theme = Theme::dark();
+        theme.menu_bg = Color::Black;
+        theme.menu_fg = Color::Rgb(255, 255, 255);
+        assert!(theme.contrast_warnings().is_empty());
+
+        // Black on white is the maximum possible ratio
+        assert!((contrast_ratio((0, 0, 0), (255, 255, 255)) - 21.0).abs() < 1e-9);
+
+        // Terminal default colors can't be judged
+        theme.menu_fg = Color::Reset;
+        theme.menu_bg = Color::Reset;
+        assert!(theme.contrast_warnings().is_empty());
+
+        for name in ["dark", "light", "high-contrast", "nostalgia"] {
+            let theme = Theme::from_name(name);
+            assert_eq!(theme.contrast_warnings(), vec![], "theme {name}");
+        }
+    }
+
+    #[test]
+    fn test_theme_manager_stage_waits_for_commit() {
+        let mut manager = ThemeManager::new(Theme::dark());
+        let before = manager.current();
+
+        manager.stage(Theme::light());
+        assert!(manager.has_staged());
+        assert_eq!(manager.name, "dark");
+        assert_eq!(manager.current().name, "dark");
+
+        // Staging again replaces the pending theme
+        manager.stage(Theme::nostalgia());
+        assert!(manager.commit());
+        assert!(!manager.has_staged());
+        assert_eq!(manager.name, "nostalgia");
+        assert!(!manager.commit());
+
+        // A reader holding the old theme keeps it for the rest of its frame
+        assert_eq!(before.name, "dark");
+    }
+
+    #[test]
+    fn test_theme_manager_dimmed_is_rebuilt_only_on_change() {
+        let mut manager = ThemeManager::new(Theme::dark());
+        let dimmed = manager.dimmed(0.5, ColorSupport::TrueColor);
+        assert_eq!(dimmed.editor_fg, Theme::dark().dim(0.5).editor_fg);
+        assert!(Arc::ptr_eq(
+            &dimmed,
+            &manager.dimmed(0.5, ColorSupport::TrueColor)
+        ));
+
+        // A new factor, color support or theme each make a new one
+        assert!(!Arc::ptr_eq(
+            &dimmed,
+            &manager.dimmed(0.25, ColorSupport::TrueColor)
+        ));
+        let degraded = manager.dimmed(0.25, ColorSupport::Ansi256);
+        assert_eq!(
+            degraded.editor_fg,
+            Theme::dark()
+                .dim(0.25)
+                .degrade_to(ColorSupport::Ansi256)
+                .editor_fg
+        );
+        manager.stage(Theme::light());
+        manager.commit();
+        let light = manager.dimmed(0.25, ColorSupport::Ansi256);
+        assert!(!Arc::ptr_eq(&degraded, &light));
+        assert_eq!(light.name, "light");
+    }
+
+    #[test]
+    fn test_theme_manager_cycle_wraps_through_builtins() {
+        let mut manager = ThemeManager::new(Theme::dark());
+        assert_eq!(manager.cycle(Theme::from_name), "light");
+        assert_eq!(manager.cycle(Theme::from_name), "high-contrast");
+        assert_eq!(manager.name, "dark");
+        manager.commit();
+        assert_eq!(manager.name, "high-contrast");
+        assert_eq!(manager.cycle(Theme::from_name), "nostalgia");
+        assert_eq!(manager.cycle(Theme::from_name), "dark");
+
+        // A custom theme cycles to the first builtin
+        let mut custom = Theme::light();
+        custom.name = "custom".to_string();
+        assert_eq!(ThemeManager::new(custom).cycle(Theme::from_name), "dark");
+    }
 }
@@ -1,15 +1,19 @@
 //! Menu bar rendering
 
-use crate::config::{Menu, MenuConfig, MenuItem};
-use crate::view::theme::Theme;
+use crate::config::{Menu, MenuConfig, MenuItem, MenuRole};
+use crate::primitives::display_width::{char_width, str_width};
+use crate::view::theme::{is_readable, MenuBorderStyle, Theme};
 use ratatui::layout::Rect;
-use ratatui::style::{Modifier, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, BorderType, Borders, Paragraph};
 use ratatui::Frame;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 
-// Re-export context_keys from the shared types module
-pub use crate::types::context_keys;
+// Re-export shared menu constants from the types module
+pub use crate::types::{context_keys, RECENT_COMMANDS_PROVIDER};
 
 /// Menu state context - provides named boolean states for menu item conditions
 /// Both `when` conditions and `checkbox` states look up values here
@@ -43,13 +47,23 @@ impl MenuContext {
     }
 }
 
-fn is_menu_item_enabled(item: &MenuItem, context: &MenuContext) -> bool {
+/// Predicate deciding from app state whether an action is currently available
+///
+/// Called with the action name of each menu item, on top of its `when` condition.
+pub type ActionEnabled<'a> = &'a dyn Fn(&str) -> bool;
+
+fn is_menu_item_enabled(
+    item: &MenuItem,
+    context: &MenuContext,
+    is_action_enabled: ActionEnabled,
+) -> bool {
     match item {
-        MenuItem::Action { when, .. } => {
-            match when.as_deref() {
+        MenuItem::Action { action, when, .. } => {
+            let condition_met = match when.as_deref() {
                 Some(condition) => context.get(condition),
                 None => true, // No condition means always enabled
-            }
+            };
+            condition_met && is_action_enabled(action)
         }
         _ => true,
     }
@@ -62,8 +76,259 @@ fn is_checkbox_checked(checkbox: &Option<String>, context: &MenuContext) -> bool
     }
 }
 
-/// Menu bar state (tracks which menu is open and which item is highlighted)
+/// Divider drawn in the menu bar before a menu that starts a new group
+pub const MENU_GROUP_SEPARATOR: &str = "│ ";
+
+/// Fewest rows below the menu bar that fit a boxed dropdown (two borders and
+/// one item); with less room the open menu is drawn on a single line instead
+pub const MIN_DROPDOWN_ROWS: u16 = 3;
+
+/// X offset of each menu label in the menu bar
+///
+/// Every menu takes " Label " plus a space, measured in display columns; a
+/// menu with `group_start` (other than the first) is preceded by
+/// `MENU_GROUP_SEPARATOR`.
+pub fn menu_label_offsets<'a>(menus: impl IntoIterator<Item = &'a Menu>) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut x = 0;
+    for (idx, menu) in menus.into_iter().enumerate() {
+        if menu.group_start && idx > 0 {
+            x += str_width(MENU_GROUP_SEPARATOR);
+        }
+        offsets.push(x);
+        x += str_width(&menu.label) + 3;
+    }
+    offsets
+}
+
+/// Width of a dropdown of `menu` containing the given items
+///
+/// The width fits the longest label, capped at the menu's `max_width` (40 by
+/// default) and widened to its `min_width`, which wins if the two conflict.
+pub fn dropdown_width(menu: &Menu, items: &[MenuItem]) -> usize {
+    let max_width = menu.max_width.map_or(40, usize::from);
+    let min_width = menu.min_width.map_or(0, usize::from);
+    items
+        .iter()
+        .filter_map(|item| match item {
+            MenuItem::Action { label, .. } => Some(str_width(label) + 20),
+            MenuItem::Submenu { label, .. } => Some(str_width(label) + 20),
+            MenuItem::Separator { .. } => Some(20),
+        })
+        .max()
+        .unwrap_or(20)
+        .min(max_width)
+        .max(min_width)
+}
+
+/// Label of the submenu holding the items past a menu's `max_visible_items`
+pub const MORE_ITEMS_LABEL: &str = "More…";
+
+/// Limit `items` to `max_visible` entries, the last being a "More…" submenu
+/// with the rest
+///
+/// The overflow and static submenus are split the same way. Lazy submenus
+/// are left alone since their items only exist once opened.
+pub fn split_overflow(items: &[MenuItem], max_visible: usize) -> Vec<MenuItem> {
+    let items = items
+        .iter()
+        .map(|item| match item {
+            MenuItem::Submenu {
+                label,
+                items,
+                provider: None,
+            } => MenuItem::Submenu {
+                label: label.clone(),
+                items: split_overflow(items, max_visible),
+                provider: None,
+            },
+            item => item.clone(),
+        })
+        .collect();
+    // One slot for an item and one for "More…"
+    overflow_into_more(items, max_visible.max(2))
+}
+
+fn overflow_into_more(mut items: Vec<MenuItem>, max_visible: usize) -> Vec<MenuItem> {
+    if items.len() <= max_visible {
+        return items;
+    }
+    let mut rest = items.split_off(max_visible - 1);
+    // Don't leave a separator right above "More…" or at the top of it
+    if matches!(items.last(), Some(MenuItem::Separator { .. })) {
+        items.pop();
+    }
+    if matches!(rest.first(), Some(MenuItem::Separator { .. })) {
+        rest.remove(0);
+    }
+    items.push(MenuItem::Submenu {
+        label: MORE_ITEMS_LABEL.to_string(),
+        items: overflow_into_more(rest, max_visible),
+        provider: None,
+    });
+    items
+}
+
+/// An action item of a menu, as listed by [`MenuState::all_actions`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuAction {
+    /// Labels from the top-level menu down to the item, e.g.
+    /// `["View", "Terminal", "Open Terminal"]`
+    pub path: Vec<String>,
+    /// Index of the top-level menu, then of each item down to this one
+    pub indices: Vec<usize>,
+    pub action: String,
+    pub args: HashMap<String, serde_json::Value>,
+}
+
+/// The action items of `menus`, depth first, skipping lazy submenus
+fn menu_actions(menus: &[Menu]) -> Vec<MenuAction> {
+    fn walk(
+        items: &[MenuItem],
+        path: &mut Vec<String>,
+        indices: &mut Vec<usize>,
+        found: &mut Vec<MenuAction>,
+    ) {
+        for (idx, item) in items.iter().enumerate() {
+            indices.push(idx);
+            match item {
+                MenuItem::Action {
+                    label,
+                    action,
+                    args,
+                    ..
+                } => {
+                    path.push(label.clone());
+                    found.push(MenuAction {
+                        path: path.clone(),
+                        indices: indices.clone(),
+                        action: action.clone(),
+                        args: args.clone(),
+                    });
+                    path.pop();
+                }
+                MenuItem::Submenu {
+                    label,
+                    items,
+                    provider: None,
+                } => {
+                    path.push(label.clone());
+                    walk(items, path, indices, found);
+                    path.pop();
+                }
+                _ => {}
+            }
+            indices.pop();
+        }
+    }
+
+    let mut found = Vec::new();
+    for (idx, menu) in menus.iter().enumerate() {
+        walk(
+            &menu.items,
+            &mut vec![menu.label.clone()],
+            &mut vec![idx],
+            &mut found,
+        );
+    }
+    found
+}
+
+/// How many actions the "Recent Commands" submenu remembers
+pub const MAX_RECENT_ACTIONS: usize = 10;
+
+/// Closure that produces the items of a lazily loaded submenu
+pub type SubmenuProvider = Arc<dyn Fn() -> Vec<MenuItem> + Send + Sync>;
+
+/// Registered submenu providers, keyed by the id used in `MenuItem::Submenu::provider`
+#[derive(Clone, Default)]
+pub struct SubmenuProviders {
+    providers: HashMap<String, SubmenuProvider>,
+}
+
+impl std::fmt::Debug for SubmenuProviders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.providers.keys()).finish()
+    }
+}
+
+/// Closure undoing what the live preview of a highlighted menu item changed
+pub type PreviewRevert = Arc<dyn Fn() + Send + Sync>;
+
+/// Revert of the live preview in progress, if any
+#[derive(Clone, Default)]
+struct ActivePreview(Option<PreviewRevert>);
+
+impl std::fmt::Debug for ActivePreview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ActivePreview")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+/// Label for a recent action: its name in sentence case, then any argument values
+///
+/// `("menu_open", {"name": "File"})` becomes "Menu open: File".
+fn recent_action_label(action: &str, args: &HashMap<String, serde_json::Value>) -> String {
+    let mut label = action.replace('_', " ");
+    if let Some(first) = label.get(..1) {
+        label.replace_range(..1, &first.to_uppercase());
+    }
+    let mut keys: Vec<_> = args.keys().collect();
+    keys.sort();
+    let values: Vec<String> = keys
+        .into_iter()
+        .map(|key| match &args[key] {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect();
+    if !values.is_empty() {
+        label.push_str(": ");
+        label.push_str(&values.join(", "));
+    }
+    label
+}
+
+/// An action and the arguments it was dispatched with
+type RecentEntry = (String, HashMap<String, serde_json::Value>);
+
+/// Actions recently dispatched, most recent first, without duplicates
+///
+/// Clones share the same history, so the "Recent Commands" provider sees
+/// every action recorded through the menu state.
 #[derive(Debug, Clone, Default)]
+struct RecentActions(Arc<Mutex<VecDeque<RecentEntry>>>);
+
+impl RecentActions {
+    fn record(&self, action: &str, args: &HashMap<String, serde_json::Value>) {
+        let mut actions = self.0.lock().unwrap();
+        actions.retain(|(a, existing)| a != action || existing != args);
+        actions.push_front((action.to_string(), args.clone()));
+        actions.truncate(MAX_RECENT_ACTIONS);
+    }
+
+    /// Menu items re-running the recorded actions, most recent first
+    fn menu_items(&self) -> Vec<MenuItem> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(action, args)| MenuItem::Action {
+                label: recent_action_label(action, args),
+                action: action.clone(),
+                args: args.clone(),
+                when: None,
+                checkbox: None,
+                style_role: None,
+            })
+            .collect()
+    }
+}
+
+/// Menu bar state (tracks which menu is open and which item is highlighted)
+#[derive(Debug, Clone)]
 pub struct MenuState {
     /// Index of the currently open menu (None if menu bar is closed)
     pub active_menu: Option<usize>,
@@ -76,11 +341,56 @@ pub struct MenuState {
     pub plugin_menus: Vec<Menu>,
     /// Context containing named boolean states for conditions and checkboxes
     pub context: MenuContext,
+    /// Providers for lazily loaded submenus
+    providers: SubmenuProviders,
+    /// Items fetched from providers since the menu was opened, keyed by provider id
+    provided_items: HashMap<String, Vec<MenuItem>>,
+    /// Commands dispatched recently, listed by the "Recent Commands" provider
+    recent_actions: RecentActions,
+    /// Live preview started with [`MenuState::begin_preview`]
+    preview: ActivePreview,
+}
+
+impl Default for MenuState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MenuState {
     pub fn new() -> Self {
-        Self::default()
+        let mut state = Self {
+            active_menu: None,
+            highlighted_item: None,
+            submenu_path: Vec::new(),
+            plugin_menus: Vec::new(),
+            context: MenuContext::new(),
+            providers: SubmenuProviders::default(),
+            provided_items: HashMap::new(),
+            recent_actions: RecentActions::default(),
+            preview: ActivePreview::default(),
+        };
+        let recent_actions = state.recent_actions.clone();
+        state.register_submenu_provider(RECENT_COMMANDS_PROVIDER, move || {
+            recent_actions.menu_items()
+        });
+        state
+    }
+
+    /// The configured menus followed by the plugin menus, with the items of
+    /// each past its `max_visible_items` moved into a "More…" submenu
+    pub fn menus(&self, configured: &[Menu]) -> Vec<Menu> {
+        configured
+            .iter()
+            .chain(self.plugin_menus.iter())
+            .map(|menu| match menu.max_visible_items {
+                Some(max) => Menu {
+                    items: split_overflow(&menu.items, usize::from(max)),
+                    ..menu.clone()
+                },
+                None => menu.clone(),
+            })
+            .collect()
     }
 
     /// Open a menu by index
@@ -88,13 +398,112 @@ impl MenuState {
         self.active_menu = Some(index);
         self.highlighted_item = Some(0);
         self.submenu_path.clear();
+        self.provided_items.clear();
     }
 
     /// Close the currently open menu (and all submenus)
+    ///
+    /// Closing without running an item cancels any live preview; executing
+    /// an item should [`commit_preview`](Self::commit_preview) first.
     pub fn close_menu(&mut self) {
+        self.cancel_preview();
         self.active_menu = None;
         self.highlighted_item = None;
         self.submenu_path.clear();
+        self.provided_items.clear();
+    }
+
+    /// Start a live preview of the highlighted item's effect
+    ///
+    /// `revert` restores the state from before the preview and runs if the
+    /// menu is cancelled. Previewing one item after another keeps the first
+    /// revert, so cancelling goes back to the state before any preview.
+    pub fn begin_preview(&mut self, revert: impl Fn() + Send + Sync + 'static) {
+        if self.preview.0.is_none() {
+            self.preview.0 = Some(Arc::new(revert));
+        }
+    }
+
+    /// Keep the previewed effect, dropping the revert without running it
+    pub fn commit_preview(&mut self) {
+        self.preview.0 = None;
+    }
+
+    /// Revert the live preview in progress
+    /// Returns true if there was one
+    pub fn cancel_preview(&mut self) -> bool {
+        match self.preview.0.take() {
+            Some(revert) => {
+                revert();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a live preview is in progress
+    pub fn is_previewing(&self) -> bool {
+        self.preview.0.is_some()
+    }
+
+    /// Register a provider for submenus declared with `provider: Some(id)`
+    ///
+    /// The provider is called when such a submenu is first opened, and its
+    /// items are reused until the menu closes.
+    pub fn register_submenu_provider(
+        &mut self,
+        id: impl Into<String>,
+        provider: impl Fn() -> Vec<MenuItem> + Send + Sync + 'static,
+    ) {
+        self.providers
+            .providers
+            .insert(id.into(), Arc::new(provider));
+    }
+
+    /// Remember a dispatched command for the "Recent Commands" submenu
+    ///
+    /// Running an action again moves it to the front instead of adding a
+    /// second entry; beyond [`MAX_RECENT_ACTIONS`] the oldest one is dropped.
+    pub fn record_action(&self, action: &str, args: &HashMap<String, serde_json::Value>) {
+        self.recent_actions.record(action, args);
+    }
+
+    /// Get the child items of a submenu item
+    ///
+    /// For a lazy submenu this is whatever its provider returned when it was
+    /// opened (empty if it hasn't been opened yet). Returns None for items
+    /// that aren't submenus.
+    pub fn submenu_items<'a>(&'a self, item: &'a MenuItem) -> Option<&'a [MenuItem]> {
+        match item {
+            MenuItem::Submenu {
+                provider: Some(id), ..
+            } => Some(self.provided_items.get(id).map_or(&[], Vec::as_slice)),
+            MenuItem::Submenu { items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Fetch a lazy submenu's items from its provider if they aren't cached yet
+    ///
+    /// Returns true if the item is a submenu with at least one child, i.e. it
+    /// can be opened.
+    pub fn load_submenu(&mut self, item: &MenuItem) -> bool {
+        if let MenuItem::Submenu {
+            provider: Some(id), ..
+        } = item
+        {
+            if !self.provided_items.contains_key(id) {
+                let items = self
+                    .providers
+                    .providers
+                    .get(id)
+                    .map(|provider| provider())
+                    .unwrap_or_default();
+                self.provided_items.insert(id.clone(), items);
+            }
+        }
+        self.submenu_items(item)
+            .is_some_and(|items| !items.is_empty())
     }
 
     /// Navigate to the next menu (right) - only at top level
@@ -103,6 +512,7 @@ impl MenuState {
             self.active_menu = Some((active + 1) % total_menus);
             self.highlighted_item = Some(0);
             self.submenu_path.clear();
+            self.provided_items.clear();
         }
     }
 
@@ -112,6 +522,7 @@ impl MenuState {
             self.active_menu = Some((active + total_menus - 1) % total_menus);
             self.highlighted_item = Some(0);
             self.submenu_path.clear();
+            self.provided_items.clear();
         }
     }
 
@@ -125,6 +536,60 @@ impl MenuState {
         self.submenu_path.len()
     }
 
+    /// Labels from the open top-level menu down to the current submenu
+    ///
+    /// For a menu bar showing File ▸ Export ▸ Formats this is
+    /// `["File", "Export", "Formats"]`. Empty when no menu is open.
+    pub fn current_path_labels(&self, configured: &[Menu]) -> Vec<String> {
+        let menus = self.menus(configured);
+        let Some(menu) = self.active_menu.and_then(|idx| menus.get(idx)) else {
+            return Vec::new();
+        };
+        let mut labels = vec![menu.label.clone()];
+        let mut items: &[MenuItem] = &menu.items;
+
+        for &idx in &self.submenu_path {
+            let Some(item @ MenuItem::Submenu { label, .. }) = items.get(idx) else {
+                break;
+            };
+            labels.push(label.clone());
+            items = self.submenu_items(item).unwrap_or_default();
+        }
+        labels
+    }
+
+    /// Open the menu containing the first item that runs `action`, with the
+    /// submenus leading to it open and the item highlighted
+    ///
+    /// Menus are searched left to right, depth first, including items moved
+    /// into "More…". Lazy submenus are skipped since their items only exist
+    /// once opened. Returns false, leaving the state alone, if no item runs
+    /// the action.
+    pub fn open_to_action(&mut self, action: &str, configured: &[Menu]) -> bool {
+        let Some(found) = self
+            .all_actions(configured)
+            .into_iter()
+            .find(|item| item.action == action)
+        else {
+            return false;
+        };
+        let mut indices = found.indices;
+        self.open_menu(indices.remove(0));
+        self.highlighted_item = indices.pop();
+        self.submenu_path = indices;
+        true
+    }
+
+    /// Every action item of the [`menus`](Self::menus) built from
+    /// `configured`, with its path, in menu order and depth first
+    ///
+    /// Paths and indices go through "More…" for items past a menu's
+    /// `max_visible_items`. Lazy submenus are skipped since their items only
+    /// exist once opened.
+    pub fn all_actions(&self, configured: &[Menu]) -> Vec<MenuAction> {
+        menu_actions(&self.menus(configured))
+    }
+
     /// Open a submenu at the current highlighted item
     /// Returns true if a submenu was opened, false if the item wasn't a submenu
     pub fn open_submenu(&mut self, menus: &[Menu]) -> bool {
@@ -140,17 +605,14 @@ impl MenuState {
             return false;
         };
 
-        // Check if highlighted item is a submenu
-        if let Some(MenuItem::Submenu {
-            items: submenu_items,
-            ..
-        }) = items.get(highlighted)
-        {
-            if !submenu_items.is_empty() {
-                self.submenu_path.push(highlighted);
-                self.highlighted_item = Some(0);
-                return true;
-            }
+        // Check if highlighted item is a submenu (loading it if it is lazy)
+        let Some(item) = items.get(highlighted).cloned() else {
+            return false;
+        };
+        if self.load_submenu(&item) {
+            self.submenu_path.push(highlighted);
+            self.highlighted_item = Some(0);
+            return true;
         }
         false
     }
@@ -166,9 +628,20 @@ impl MenuState {
         }
     }
 
+    /// Handle Escape: leave the current submenu, or close the menu bar if
+    /// already at the top level
+    /// Returns true if the menu bar was fully closed
+    pub fn escape(&mut self) -> bool {
+        if self.close_submenu() {
+            return false;
+        }
+        self.close_menu();
+        true
+    }
+
     /// Get the menu items at the current submenu level
     pub fn get_current_items<'a>(
-        &self,
+        &'a self,
         menus: &'a [Menu],
         active_idx: usize,
     ) -> Option<&'a [MenuItem]> {
@@ -176,15 +649,7 @@ impl MenuState {
         let mut items: &[MenuItem] = &menu.items;
 
         for &idx in &self.submenu_path {
-            match items.get(idx)? {
-                MenuItem::Submenu {
-                    items: submenu_items,
-                    ..
-                } => {
-                    items = submenu_items;
-                }
-                _ => return None,
-            }
+            items = self.submenu_items(items.get(idx)?)?;
         }
 
         Some(items)
@@ -192,21 +657,13 @@ impl MenuState {
 
     /// Get owned vec of current items (for use when Menu is cloned)
     pub fn get_current_items_cloned(&self, menu: &Menu) -> Option<Vec<MenuItem>> {
-        let mut items = menu.items.clone();
+        let mut items: &[MenuItem] = &menu.items;
 
         for &idx in &self.submenu_path {
-            match items.get(idx)? {
-                MenuItem::Submenu {
-                    items: submenu_items,
-                    ..
-                } => {
-                    items = submenu_items.clone();
-                }
-                _ => return None,
-            }
+            items = self.submenu_items(items.get(idx)?)?;
         }
 
-        Some(items)
+        Some(items.to_vec())
     }
 
     /// Navigate to the next item in the current menu/submenu (down)
@@ -257,10 +714,12 @@ impl MenuState {
     }
 
     /// Get the currently highlighted action (if any)
-    /// This navigates through the submenu path to find the currently highlighted item
+    /// This navigates through the submenu path to find the currently highlighted item.
+    /// Disabled actions (by `when` or `is_action_enabled`) return None.
     pub fn get_highlighted_action(
         &self,
         menus: &[Menu],
+        is_action_enabled: ActionEnabled,
     ) -> Option<(String, std::collections::HashMap<String, serde_json::Value>)> {
         let active_menu = self.active_menu?;
         let highlighted_item = self.highlighted_item?;
@@ -271,7 +730,7 @@ impl MenuState {
 
         match item {
             MenuItem::Action { action, args, .. } => {
-                if is_menu_item_enabled(item, &self.context) {
+                if is_menu_item_enabled(item, &self.context, is_action_enabled) {
                     Some((action.clone(), args.clone()))
                 } else {
                     None
@@ -300,20 +759,14 @@ impl MenuState {
     /// Get the menu index at a given x position in the menu bar
     /// Returns the menu index if the click is on a menu label
     pub fn get_menu_at_position(&self, menus: &[Menu], x: u16) -> Option<usize> {
-        let mut current_x = 0u16;
-
-        for (idx, menu) in menus.iter().enumerate() {
-            let label_width = menu.label.len() as u16 + 2; // " Label "
-            let total_width = label_width + 1; // Plus trailing space
-
-            if x >= current_x && x < current_x + label_width {
-                return Some(idx);
-            }
-
-            current_x += total_width;
-        }
-
-        None
+        let x = x as usize;
+        menu_label_offsets(menus)
+            .into_iter()
+            .zip(menus)
+            .position(|(start, menu)| {
+                let label_width = str_width(&menu.label) + 2; // " Label "
+                x >= start && x < start + label_width
+            })
     }
 
     /// Get the item index at a given y position in the dropdown
@@ -352,6 +805,8 @@ impl MenuRenderer {
     /// * `keybindings` - Keybinding resolver for displaying shortcuts
     /// * `theme` - The active theme for colors
     /// * `hover_target` - The currently hovered UI element (if any)
+    /// * `is_action_enabled` - Whether each item's action is available; disabled items are dimmed
+    #[allow(clippy::too_many_arguments)]
     pub fn render(
         frame: &mut Frame,
         area: Rect,
@@ -360,18 +815,24 @@ impl MenuRenderer {
         keybindings: &crate::input::keybindings::KeybindingResolver,
         theme: &Theme,
         hover_target: Option<&crate::app::HoverTarget>,
+        is_action_enabled: ActionEnabled,
     ) {
         // Combine config menus with plugin menus
-        let all_menus: Vec<&Menu> = menu_config
-            .menus
-            .iter()
-            .chain(menu_state.plugin_menus.iter())
-            .collect();
+        let all_menus = menu_state.menus(&menu_config.menus);
 
         // Build spans for each menu label
         let mut spans = Vec::new();
 
         for (idx, menu) in all_menus.iter().enumerate() {
+            if menu.group_start && idx > 0 {
+                spans.push(Span::styled(
+                    MENU_GROUP_SEPARATOR,
+                    Style::default()
+                        .fg(theme.menu_separator_fg)
+                        .bg(theme.menu_bg),
+                ));
+            }
+
             let is_active = menu_state.active_menu == Some(idx);
             let is_hovered =
                 matches!(hover_target, Some(crate::app::HoverTarget::MenuBarItem(i)) if *i == idx);
@@ -426,6 +887,10 @@ impl MenuRenderer {
         // Render dropdown if a menu is active
         if let Some(active_idx) = menu_state.active_menu {
             if let Some(menu) = all_menus.get(active_idx) {
+                if Self::use_compact_layout(area, frame.area()) {
+                    Self::render_compact(frame, area, menu, menu_state, theme, is_action_enabled);
+                    return;
+                }
                 Self::render_dropdown_chain(
                     frame,
                     area,
@@ -436,31 +901,121 @@ impl MenuRenderer {
                     keybindings,
                     theme,
                     hover_target,
+                    is_action_enabled,
                 );
             }
         }
     }
 
+    /// Whether the terminal is too short below `menu_bar_area` for boxed dropdowns
+    pub fn use_compact_layout(menu_bar_area: Rect, terminal_area: Rect) -> bool {
+        let rows_below = terminal_area
+            .bottom()
+            .saturating_sub(menu_bar_area.y.saturating_add(1));
+        menu_bar_area.height == 0 || rows_below < MIN_DROPDOWN_ROWS
+    }
+
+    /// Render the open menu level as a single line of items for terminals too
+    /// short for dropdowns
+    ///
+    /// The items go on the row below the menu bar, or over the bar itself when
+    /// there is no such row, and the line scrolls horizontally to keep the
+    /// highlighted item in view.
+    fn render_compact(
+        frame: &mut Frame,
+        menu_bar_area: Rect,
+        menu: &Menu,
+        menu_state: &MenuState,
+        theme: &Theme,
+        is_action_enabled: ActionEnabled,
+    ) {
+        let mut items: &[MenuItem] = &menu.items;
+        for &idx in &menu_state.submenu_path {
+            match items
+                .get(idx)
+                .and_then(|item| menu_state.submenu_items(item))
+            {
+                Some(sub) => items = sub,
+                None => return,
+            }
+        }
+
+        let terminal_area = frame.area();
+        let y = if menu_bar_area.y.saturating_add(1) < terminal_area.bottom() {
+            menu_bar_area.y + 1
+        } else {
+            menu_bar_area.y
+        };
+        let row = Rect::new(menu_bar_area.x, y, menu_bar_area.width, 1);
+        if row.width == 0 {
+            return;
+        }
+
+        let base_style = Style::default()
+            .fg(theme.menu_dropdown_fg)
+            .bg(theme.menu_dropdown_bg);
+        let mut spans = vec![Span::styled(format!(" {}: ", menu.label), base_style)];
+        let mut width = str_width(&menu.label) + 3;
+        let mut highlighted_end = 0;
+        for (idx, item) in items.iter().enumerate() {
+            let (text, enabled) = match item {
+                MenuItem::Action { label, .. } => (
+                    format!(" {label} "),
+                    is_menu_item_enabled(item, &menu_state.context, is_action_enabled),
+                ),
+                MenuItem::Submenu { label, .. } => (format!(" {label} ▶ "), true),
+                MenuItem::Separator { .. } => {
+                    spans.push(Span::styled(
+                        "│",
+                        Style::default()
+                            .fg(theme.menu_separator_fg)
+                            .bg(theme.menu_dropdown_bg),
+                    ));
+                    width += 1;
+                    continue;
+                }
+            };
+            let style = if menu_state.highlighted_item == Some(idx) {
+                Style::default()
+                    .fg(theme.menu_highlight_fg)
+                    .bg(theme.menu_highlight_bg)
+            } else if !enabled {
+                Style::default()
+                    .fg(theme.menu_disabled_fg)
+                    .bg(theme.menu_disabled_bg)
+            } else {
+                base_style
+            };
+            width += str_width(&text);
+            if menu_state.highlighted_item == Some(idx) {
+                highlighted_end = width;
+            }
+            spans.push(Span::styled(text, style));
+        }
+
+        let scroll = highlighted_end.saturating_sub(row.width as usize);
+        let paragraph = Paragraph::new(Line::from(spans))
+            .style(base_style)
+            .scroll((0, scroll as u16));
+        frame.render_widget(paragraph, row);
+    }
+
     /// Render a dropdown menu and all its open submenus
+    #[allow(clippy::too_many_arguments)]
     fn render_dropdown_chain(
         frame: &mut Frame,
         menu_bar_area: Rect,
         menu: &Menu,
         menu_state: &MenuState,
         menu_index: usize,
-        all_menus: &[&Menu],
+        all_menus: &[Menu],
         keybindings: &crate::input::keybindings::KeybindingResolver,
         theme: &Theme,
         hover_target: Option<&crate::app::HoverTarget>,
+        is_action_enabled: ActionEnabled,
     ) {
         // Calculate the x position of the top-level dropdown based on menu index
-        let mut x_offset = 0usize;
-        for (idx, m) in all_menus.iter().enumerate() {
-            if idx == menu_index {
-                break;
-            }
-            x_offset += m.label.len() + 3; // label + spaces
-        }
+        let x_offset = menu_label_offsets(all_menus)[menu_index];
 
         let terminal_width = frame.area().width;
         let terminal_height = frame.area().height;
@@ -483,6 +1038,7 @@ impl MenuRenderer {
             // Render this dropdown level
             let dropdown_rect = Self::render_dropdown_level(
                 frame,
+                menu,
                 current_items,
                 highlighted_item,
                 current_x,
@@ -496,26 +1052,32 @@ impl MenuRenderer {
                 theme,
                 hover_target,
                 &menu_state.context,
+                is_action_enabled,
             );
 
             // If not at the deepest level, navigate into the submenu for next iteration
             if !is_deepest {
                 let submenu_idx = menu_state.submenu_path[depth];
-                if let Some(MenuItem::Submenu { items, .. }) = current_items.get(submenu_idx) {
+                if let Some(items) = current_items
+                    .get(submenu_idx)
+                    .and_then(|item| menu_state.submenu_items(item))
+                {
                     current_items = items;
-                    // Position submenu to the right of parent, aligned with the highlighted item
+                    // Position submenu to the right of parent, aligned with the highlighted
+                    // item; the two dropdowns share a border column
+                    let inset = theme.menu_border_style.inset();
                     current_x = dropdown_rect
                         .x
-                        .saturating_add(dropdown_rect.width.saturating_sub(1));
-                    current_y = dropdown_rect.y.saturating_add(submenu_idx as u16 + 1); // +1 for border
+                        .saturating_add(dropdown_rect.width.saturating_sub(inset));
+                    current_y = dropdown_rect.y.saturating_add(submenu_idx as u16 + inset);
 
                     // Adjust if submenu would go off screen to the right - flip to left side
-                    let next_width = Self::calculate_dropdown_width(items);
+                    let next_width = dropdown_width(menu, items);
                     if current_x.saturating_add(next_width as u16) > terminal_width {
                         current_x = dropdown_rect
                             .x
                             .saturating_sub(next_width as u16)
-                            .saturating_add(1);
+                            .saturating_add(inset);
                     }
                 } else {
                     break;
@@ -524,24 +1086,24 @@ impl MenuRenderer {
         }
     }
 
-    /// Calculate the width needed for a dropdown containing the given items
-    fn calculate_dropdown_width(items: &[MenuItem]) -> usize {
-        items
-            .iter()
-            .filter_map(|item| match item {
-                MenuItem::Action { label, .. } => Some(label.len() + 20),
-                MenuItem::Submenu { label, .. } => Some(label.len() + 20),
-                MenuItem::Separator { .. } => Some(20),
-            })
-            .max()
-            .unwrap_or(20)
-            .min(40)
+    /// Cells covered by the drop shadow of a dropdown: a one-cell strip along the
+    /// right edge and one along the bottom, offset one cell down-right and clipped
+    /// to `bounds`
+    fn shadow_areas(area: Rect, bounds: Rect) -> Vec<Rect> {
+        let right = Rect::new(area.right(), area.y.saturating_add(1), 1, area.height);
+        let bottom = Rect::new(area.x.saturating_add(1), area.bottom(), area.width, 1);
+        [right, bottom]
+            .into_iter()
+            .map(|strip| strip.intersection(bounds))
+            .filter(|strip| !strip.is_empty())
+            .collect()
     }
 
     /// Render a single dropdown level and return its bounding Rect
     #[allow(clippy::too_many_arguments)]
     fn render_dropdown_level(
         frame: &mut Frame,
+        menu: &Menu,
         items: &[MenuItem],
         highlighted_item: Option<usize>,
         x: u16,
@@ -555,9 +1117,11 @@ impl MenuRenderer {
         theme: &Theme,
         hover_target: Option<&crate::app::HoverTarget>,
         context: &MenuContext,
+        is_action_enabled: ActionEnabled,
     ) -> Rect {
-        let max_width = Self::calculate_dropdown_width(items);
-        let dropdown_height = items.len() + 2; // +2 for borders
+        let inset = theme.menu_border_style.inset();
+        let max_width = dropdown_width(menu, items);
+        let dropdown_height = items.len() + 2 * inset as usize;
 
         let desired_width = max_width as u16;
         let desired_height = dropdown_height as u16;
@@ -576,7 +1140,7 @@ impl MenuRenderer {
         let width = desired_width.min(available_width);
 
         // Only render if we have at least minimal space
-        if width < 10 || height < 3 {
+        if width < 10 || height < 1 + 2 * inset {
             return Rect {
                 x: adjusted_x,
                 y,
@@ -592,11 +1156,23 @@ impl MenuRenderer {
             height,
         };
 
+        // Draw the drop shadow first so the dropdown covers its own corner. A
+        // shadow in the terminal's default color would only blank what's below.
+        if theme.menu_shadow && theme.menu_shadow_bg != Color::Reset {
+            let bounds = Rect::new(0, 0, terminal_width, terminal_height);
+            let shadow_style = Style::default().bg(theme.menu_shadow_bg);
+            for shadow in Self::shadow_areas(dropdown_area, bounds) {
+                frame.buffer_mut().set_style(shadow, shadow_style);
+            }
+        }
+
         // Build dropdown content
         let mut lines = Vec::new();
-        let max_items = (height.saturating_sub(2)) as usize;
+        let max_items = (height.saturating_sub(2 * inset)) as usize;
         let items_to_show = items.len().min(max_items);
-        let content_width = (width as usize).saturating_sub(2);
+        let content_width = (width as usize).saturating_sub(2 * inset as usize);
+        // Rows drawn in the highlight color, for the gradient pass below
+        let mut highlighted_rows = Vec::new();
 
         for (idx, item) in items.iter().enumerate().take(items_to_show) {
             let is_highlighted = highlighted_item == Some(idx);
@@ -615,13 +1191,14 @@ impl MenuRenderer {
                     Some(crate::app::HoverTarget::SubmenuItem(d, ii)) if *d == depth && *ii == idx
                 )
             };
-            let enabled = is_menu_item_enabled(item, context);
+            let enabled = is_menu_item_enabled(item, context, is_action_enabled);
 
             let line = match item {
                 MenuItem::Action {
                     label,
                     action,
                     checkbox,
+                    style_role,
                     ..
                 } => {
                     let style = if !enabled {
@@ -629,6 +1206,7 @@ impl MenuRenderer {
                             .fg(theme.menu_disabled_fg)
                             .bg(theme.menu_disabled_bg)
                     } else if is_highlighted {
+                        highlighted_rows.push(idx);
                         Style::default()
                             .fg(theme.menu_highlight_fg)
                             .bg(theme.menu_highlight_bg)
@@ -641,6 +1219,23 @@ impl MenuRenderer {
                             .fg(theme.menu_dropdown_fg)
                             .bg(theme.menu_dropdown_bg)
                     };
+                    // The role color replaces the text color unless it would be
+                    // hard to read on this item's background
+                    let style = match style_role {
+                        Some(role) if enabled => {
+                            let accent = match role {
+                                MenuRole::Destructive => theme.menu_destructive_fg,
+                                MenuRole::Primary => theme.menu_primary_fg,
+                            };
+                            let bg = style.bg.unwrap_or(theme.menu_dropdown_bg);
+                            if is_readable(accent, bg) {
+                                style.fg(accent)
+                            } else {
+                                style
+                            }
+                        }
+                        _ => style,
+                    };
 
                     let keybinding = keybindings
                         .find_keybinding_for_action(
@@ -661,12 +1256,19 @@ impl MenuRenderer {
 
                     let checkbox_width = if checkbox.is_some() { 2 } else { 0 };
                     let label_width =
-                        content_width.saturating_sub(keybinding.len() + checkbox_width + 2);
+                        content_width.saturating_sub(str_width(&keybinding) + checkbox_width + 2);
                     let text = if keybinding.is_empty() {
                         let padding = content_width.saturating_sub(checkbox_width);
-                        format!(" {}{:<width$}", checkbox_icon, label, width = padding)
+                        let label = truncate_label(label, padding.saturating_sub(1));
+                        format!(" {}{}", checkbox_icon, pad_label(&label, padding))
                     } else {
-                        format!(" {}{:<label_width$} {}", checkbox_icon, label, keybinding)
+                        let label = truncate_label(label, label_width);
+                        format!(
+                            " {}{} {}",
+                            checkbox_icon,
+                            pad_label(&label, label_width),
+                            keybinding
+                        )
                     };
 
                     Line::from(vec![Span::styled(text, style)])
@@ -683,6 +1285,7 @@ impl MenuRenderer {
                 MenuItem::Submenu { label, .. } => {
                     // Highlight submenu items that have an open child
                     let style = if is_highlighted || has_open_submenu {
+                        highlighted_rows.push(idx);
                         Style::default()
                             .fg(theme.menu_highlight_fg)
                             .bg(theme.menu_highlight_bg)
@@ -699,8 +1302,9 @@ impl MenuRenderer {
                     // Format: " Label        ▶ " - label left-aligned, arrow near the end with padding
                     // content_width minus: leading space (1) + space before arrow (1) + arrow (1) + trailing space (2)
                     let label_width = content_width.saturating_sub(5);
+                    let label = truncate_label(label, label_width);
                     Line::from(vec![Span::styled(
-                        format!(" {:<label_width$} ▶  ", label),
+                        format!(" {} ▶  ", pad_label(&label, label_width)),
                         style,
                     )])
                 }
@@ -709,18 +1313,61 @@ impl MenuRenderer {
             lines.push(line);
         }
 
+        let borders = match theme.menu_border_style {
+            MenuBorderStyle::None => Borders::NONE,
+            _ => Borders::ALL,
+        };
+        let border_type = match theme.menu_border_style {
+            MenuBorderStyle::Rounded => BorderType::Rounded,
+            MenuBorderStyle::Double => BorderType::Double,
+            MenuBorderStyle::Plain | MenuBorderStyle::None => BorderType::Plain,
+        };
         let block = Block::default()
-            .borders(Borders::ALL)
+            .borders(borders)
+            .border_type(border_type)
             .border_style(Style::default().fg(theme.menu_border_fg))
             .style(Style::default().bg(theme.menu_dropdown_bg));
 
         let paragraph = Paragraph::new(lines).block(block);
         frame.render_widget(paragraph, dropdown_area);
 
+        if theme.menu_highlight_bg_end.is_some() {
+            let buf = frame.buffer_mut();
+            for row in highlighted_rows {
+                let y = dropdown_area.y + inset + row as u16;
+                for col in 0..content_width {
+                    let x = dropdown_area.x + inset + col as u16;
+                    buf[(x, y)].set_bg(theme.menu_highlight_bg_at(col, content_width));
+                }
+            }
+        }
+
         dropdown_area
     }
 }
 
+/// Shorten `label` to at most `width` columns, ending it with "…" if cut
+fn truncate_label(label: &str, width: usize) -> Cow<'_, str> {
+    if str_width(label) <= width {
+        return Cow::Borrowed(label);
+    }
+    let mut used = 0;
+    let truncated: String = label
+        .chars()
+        .take_while(|&ch| {
+            used += char_width(ch);
+            used < width
+        })
+        .collect();
+    Cow::Owned(format!("{truncated}…"))
+}
+
+/// `label` followed by enough spaces to fill `width` columns
+fn pad_label(label: &str, width: usize) -> String {
+    let padding = width.saturating_sub(str_width(label));
+    format!("{label}{}", " ".repeat(padding))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -730,6 +1377,10 @@ mod tests {
         vec![
             Menu {
                 label: "File".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "New".to_string(),
@@ -737,6 +1388,7 @@ mod tests {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -745,6 +1397,7 @@ mod tests {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Quit".to_string(),
@@ -752,11 +1405,16 @@ mod tests {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
             Menu {
                 label: "Edit".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![
                     MenuItem::Action {
                         label: "Undo".to_string(),
@@ -764,6 +1422,7 @@ mod tests {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                     MenuItem::Action {
                         label: "Redo".to_string(),
@@ -771,17 +1430,23 @@ mod tests {
                         args: HashMap::new(),
                         when: None,
                         checkbox: None,
+                        style_role: None,
                     },
                 ],
             },
             Menu {
                 label: "View".to_string(),
+                group_start: false,
+                min_width: None,
+                max_width: None,
+                max_visible_items: None,
                 items: vec![MenuItem::Action {
                     label: "Toggle Explorer".to_string(),
                     action: "toggle_file_explorer".to_string(),
                     args: HashMap::new(),
                     when: None,
                     checkbox: None,
+                    style_role: None,
                 }],
             },
         ]
@@ -889,7 +1554,7 @@ mod tests {
         state.open_menu(0);
         state.highlighted_item = Some(2); // Save action
 
-        let action = state.get_highlighted_action(&menus);
+        let action = state.get_highlighted_action(&menus, &|_| true);
         assert!(action.is_some());
         let (action_name, _args) = action.unwrap();
         assert_eq!(action_name, "save");
@@ -900,12 +1565,17 @@ mod tests {
         let mut state = MenuState::new();
         let select_menu = Menu {
             label: "Edit".to_string(),
+            group_start: false,
+            min_width: None,
+            max_width: None,
+            max_visible_items: None,
             items: vec![MenuItem::Action {
                 label: "Find in Selection".to_string(),
                 action: "find_in_selection".to_string(),
                 args: HashMap::new(),
                 when: Some(context_keys::HAS_SELECTION.to_string()),
                 checkbox: None,
+                style_role: None,
             }],
         };
         state.open_menu(0);
@@ -913,47 +1583,438 @@ mod tests {
 
         // Without has_selection set, action should be disabled
         assert!(state
-            .get_highlighted_action(&[select_menu.clone()])
+            .get_highlighted_action(&[select_menu.clone()], &|_| true)
             .is_none());
 
         // With has_selection set to true, action should be enabled
         state.context.set(context_keys::HAS_SELECTION, true);
-        assert!(state.get_highlighted_action(&[select_menu]).is_some());
-    }
-
-    #[test]
-    fn test_get_highlighted_action_none_when_closed() {
-        let state = MenuState::new();
-        let menus = create_test_menus();
-        assert!(state.get_highlighted_action(&menus).is_none());
+        assert!(state
+            .get_highlighted_action(&[select_menu], &|_| true)
+            .is_some());
     }
 
     #[test]
-    fn test_get_highlighted_action_none_for_separator() {
+    fn test_action_predicate_disables_items() {
         let mut state = MenuState::new();
         let menus = create_test_menus();
+        let no_save = |action: &str| action != "save";
         state.open_menu(0);
-        state.highlighted_item = Some(1); // Separator
 
-        assert!(state.get_highlighted_action(&menus).is_none());
+        // File menu: New (0), Separator (1), Save (2), Quit (3)
+        state.highlighted_item = Some(2);
+        assert!(state.get_highlighted_action(&menus, &no_save).is_none());
+        state.highlighted_item = Some(3);
+        assert_eq!(
+            state.get_highlighted_action(&menus, &no_save).unwrap().0,
+            "quit"
+        );
+
+        // The disabled item is dimmed, the others aren't
+        let backend = ratatui::backend::TestBackend::new(30, 8);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        let config = crate::config::Config::default();
+        let keybindings = crate::input::keybindings::KeybindingResolver::new(&config);
+        let menu_config = MenuConfig { menus };
+        let theme = Theme::dark();
+        terminal
+            .draw(|frame| {
+                MenuRenderer::render(
+                    frame,
+                    frame.area(),
+                    &menu_config,
+                    &state,
+                    &keybindings,
+                    &theme,
+                    None,
+                    &no_save,
+                )
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        // Item rows start at y=2, below the menu bar and the top border
+        assert_eq!(buffer[(2, 4)].fg, theme.menu_disabled_fg);
+        assert_ne!(buffer[(2, 2)].fg, theme.menu_disabled_fg);
+        assert_ne!(buffer[(2, 5)].fg, theme.menu_disabled_fg);
     }
 
-    #[test]
-    fn test_get_menu_at_position() {
-        let state = MenuState::new();
-        let menus = create_test_menus();
+    /// Render `menus` with the first one open and return the terminal rows
+    fn render_open_menu(menus: Vec<Menu>, width: u16) -> Vec<String> {
+        let mut state = MenuState::new();
+        state.open_menu(0);
+        render_menu_rows(menus, &state, width, 8)
+    }
 
-        // Menu positions: " File " (6 chars) + " " = 7, " Edit " (6 chars) + " " = 7, " View " (6 chars)
-        // File: x=0-5
-        assert_eq!(state.get_menu_at_position(&menus, 0), Some(0));
-        assert_eq!(state.get_menu_at_position(&menus, 3), Some(0));
-        assert_eq!(state.get_menu_at_position(&menus, 5), Some(0));
+    /// Render the menu bar on the top row of a `width` x `height` terminal and
+    /// return the rows
+    fn render_menu_rows(
+        menus: Vec<Menu>,
+        state: &MenuState,
+        width: u16,
+        height: u16,
+    ) -> Vec<String> {
+        let buffer = render_menu_buffer(menus, state, width, height, &Theme::dark());
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect()
+    }
 
-        // Space between: x=6
-        assert_eq!(state.get_menu_at_position(&menus, 6), None);
+    fn render_menu_buffer(
+        menus: Vec<Menu>,
+        state: &MenuState,
+        width: u16,
+        height: u16,
+        theme: &Theme,
+    ) -> ratatui::buffer::Buffer {
+        let backend = ratatui::backend::TestBackend::new(width, height);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        let config = crate::config::Config::default();
+        let keybindings = crate::input::keybindings::KeybindingResolver::new(&config);
+        let menu_config = MenuConfig { menus };
+        terminal
+            .draw(|frame| {
+                MenuRenderer::render(
+                    frame,
+                    Rect::new(0, 0, width, 1),
+                    &menu_config,
+                    state,
+                    &keybindings,
+                    theme,
+                    None,
+                    &|_| true,
+                )
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
 
-        // Edit: x=7-12
-        assert_eq!(state.get_menu_at_position(&menus, 7), Some(1));
+    #[test]
+    fn test_highlight_gradient_interpolates_across_row() {
+        let mut theme = Theme::dark();
+        theme.menu_highlight_bg = Color::Rgb(0, 0, 0);
+        theme.menu_highlight_bg_end = Some(Color::Rgb(200, 100, 50));
+        let menu = Menu {
+            label: "File".to_string(),
+            group_start: false,
+            min_width: None,
+            // 21 content columns, so column 10 is the exact midpoint
+            max_width: Some(23),
+            max_visible_items: None,
+            items: vec![
+                MenuItem::Action {
+                    label: "Save".to_string(),
+                    action: "save".to_string(),
+                    args: HashMap::new(),
+                    when: None,
+                    checkbox: None,
+                    style_role: None,
+                },
+                MenuItem::Action {
+                    label: "Quit".to_string(),
+                    action: "quit".to_string(),
+                    args: HashMap::new(),
+                    when: None,
+                    checkbox: None,
+                    style_role: None,
+                },
+            ],
+        };
+        let mut state = MenuState::new();
+        state.open_menu(0);
+
+        let buffer = render_menu_buffer(vec![menu.clone()], &state, 60, 10, &theme);
+        // Row 0 is the menu bar and row 1 the top border; content starts after the left border
+        let left = (0..60u16)
+            .find(|&x| buffer[(x, 1)].symbol() == "┌")
+            .unwrap()
+            + 1;
+        assert_eq!(buffer[(left, 2)].bg, Color::Rgb(0, 0, 0));
+        assert_eq!(buffer[(left + 10, 2)].bg, Color::Rgb(100, 50, 25));
+        assert_eq!(buffer[(left + 20, 2)].bg, Color::Rgb(200, 100, 50));
+        // The border and the other items keep their colors
+        assert_eq!(buffer[(left + 21, 2)].symbol(), "│");
+        assert_eq!(buffer[(left + 10, 3)].bg, theme.menu_dropdown_bg);
+
+        // Without true color the bar is solid
+        let degraded = theme.degrade_to(crate::view::theme::ColorSupport::Ansi256);
+        let buffer = render_menu_buffer(vec![menu], &state, 60, 10, &degraded);
+        assert_eq!(buffer[(left + 10, 2)].bg, degraded.menu_highlight_bg);
+        assert_eq!(buffer[(left + 20, 2)].bg, degraded.menu_highlight_bg);
+    }
+
+    #[test]
+    fn test_wide_labels_stay_inside_dropdown() {
+        let theme = Theme::dark();
+        let menu = Menu {
+            label: "File".to_string(),
+            group_start: false,
+            min_width: None,
+            // 21 content columns
+            max_width: Some(23),
+            max_visible_items: None,
+            items: vec![
+                MenuItem::Action {
+                    label: "保存当前文件到磁盘上".to_string(),
+                    action: "save".to_string(),
+                    args: HashMap::new(),
+                    when: None,
+                    checkbox: None,
+                    style_role: None,
+                },
+                MenuItem::Submenu {
+                    label: "最近打开的文件和文件夹列表".to_string(),
+                    items: vec![],
+                    provider: None,
+                },
+            ],
+        };
+        let mut state = MenuState::new();
+        state.open_menu(0);
+
+        let buffer = render_menu_buffer(vec![menu], &state, 60, 10, &theme);
+        let left = (0..60u16)
+            .find(|&x| buffer[(x, 1)].symbol() == "┌")
+            .unwrap()
+            + 1;
+        let row_text =
+            |y: u16| -> String { (left..left + 21).map(|x| buffer[(x, y)].symbol()).collect() };
+
+        // The keybinding is drawn whole, right before the border
+        let config = crate::config::Config::default();
+        let keybinding = crate::input::keybindings::KeybindingResolver::new(&config)
+            .find_keybinding_for_action("save", crate::input::keybindings::KeyContext::Normal)
+            .unwrap();
+        assert!(row_text(2).contains('…'));
+        assert!(row_text(2).ends_with(&format!(" {keybinding}")));
+        assert_eq!(buffer[(left + 21, 2)].symbol(), "│");
+
+        // The submenu arrow keeps its place too
+        assert!(row_text(3).ends_with("▶  "));
+        assert_eq!(buffer[(left + 21, 3)].symbol(), "│");
+    }
+
+    #[test]
+    fn test_destructive_items_use_accent_when_readable() {
+        let mut theme = Theme::dark();
+        // A highlight the accent can't be read on
+        theme.menu_highlight_bg = theme.menu_destructive_fg;
+        let action = |label: &str, style_role| MenuItem::Action {
+            label: label.to_string(),
+            action: label.to_lowercase(),
+            args: HashMap::new(),
+            when: None,
+            checkbox: None,
+            style_role,
+        };
+        let menu = Menu {
+            label: "File".to_string(),
+            group_start: false,
+            min_width: None,
+            max_width: None,
+            max_visible_items: None,
+            items: vec![
+                action("Save", None),
+                action("Wipe", Some(MenuRole::Destructive)),
+                action("Open", Some(MenuRole::Primary)),
+            ],
+        };
+        let mut state = MenuState::new();
+        state.open_menu(0);
+        state.highlighted_item = None;
+
+        let buffer = render_menu_buffer(vec![menu.clone()], &state, 40, 8, &theme);
+        let label_x = |y: u16| (0..40u16).find(|&x| buffer[(x, y)].symbol() == "W");
+        let x = label_x(3).unwrap();
+        assert_eq!(buffer[(x, 2)].fg, theme.menu_dropdown_fg);
+        assert_eq!(buffer[(x, 3)].fg, theme.menu_destructive_fg);
+        assert_eq!(buffer[(x, 4)].fg, theme.menu_primary_fg);
+
+        // Highlighted, it falls back to the plain highlight color
+        state.highlighted_item = Some(1);
+        let buffer = render_menu_buffer(vec![menu], &state, 40, 8, &theme);
+        assert_eq!(buffer[(x, 3)].fg, theme.menu_highlight_fg);
+    }
+
+    #[test]
+    fn test_default_colors_leave_terminal_background() {
+        let mut theme = Theme::dark();
+        theme.menu_bg = Color::Reset;
+        theme.menu_dropdown_bg = Color::Reset;
+        theme.menu_shadow_bg = Color::Reset;
+        let mut state = MenuState::new();
+        state.open_menu(0);
+        state.highlighted_item = None;
+
+        let buffer = render_menu_buffer(create_test_menus(), &state, 40, 10, &theme);
+        // The bar after the last title and the body of the dropdown
+        assert_eq!(buffer[(39, 0)].bg, Color::Reset);
+        let left = (0..40u16)
+            .find(|&x| buffer[(x, 1)].symbol() == "┌")
+            .unwrap();
+        assert_eq!(buffer[(left + 1, 2)].bg, Color::Reset);
+        assert_eq!(buffer[(left + 1, 2)].fg, theme.menu_dropdown_fg);
+    }
+
+    #[test]
+    fn test_rounded_and_borderless_dropdowns() {
+        let menu = Menu {
+            label: "File".to_string(),
+            group_start: false,
+            min_width: None,
+            max_width: None,
+            max_visible_items: None,
+            items: vec![MenuItem::Action {
+                label: "Save".to_string(),
+                action: "save".to_string(),
+                args: HashMap::new(),
+                when: None,
+                checkbox: None,
+                style_role: None,
+            }],
+        };
+        let mut state = MenuState::new();
+        state.open_menu(0);
+        let row = |buffer: &ratatui::buffer::Buffer, y: u16| -> String {
+            (0..40u16).map(|x| buffer[(x, y)].symbol()).collect()
+        };
+
+        let mut theme = Theme::dark();
+        theme.menu_border_style = MenuBorderStyle::Rounded;
+        let buffer = render_menu_buffer(vec![menu.clone()], &state, 40, 8, &theme);
+        assert!(row(&buffer, 1).starts_with("╭────"));
+        assert!(row(&buffer, 1).trim_end().ends_with('╮'));
+        assert!(row(&buffer, 2).starts_with("│ Save"));
+        assert!(row(&buffer, 3).starts_with("╰────"));
+        assert!(row(&buffer, 3).trim_end().ends_with('╯'));
+
+        // Without a border the item sits right below the menu bar, full width
+        theme.menu_border_style = MenuBorderStyle::None;
+        let buffer = render_menu_buffer(vec![menu.clone()], &state, 40, 8, &theme);
+        assert!(row(&buffer, 1).starts_with(" Save"));
+        let width = dropdown_width(&menu, &menu.items) as u16;
+        assert_eq!(buffer[(width - 1, 1)].bg, theme.menu_highlight_bg);
+        assert_eq!(row(&buffer, 2).trim(), "");
+    }
+
+    /// Width of the open dropdown, measured along its top border
+    fn dropdown_rendered_width(rows: &[String]) -> usize {
+        rows[1].chars().position(|c| c == '┐').unwrap() + 1
+    }
+
+    #[test]
+    fn test_compact_layout_chosen_when_dropdown_does_not_fit() {
+        let bar = Rect::new(0, 0, 80, 1);
+        assert!(MenuRenderer::use_compact_layout(
+            bar,
+            Rect::new(0, 0, 80, 2)
+        ));
+        assert!(MenuRenderer::use_compact_layout(
+            bar,
+            Rect::new(0, 0, 80, 3)
+        ));
+        assert!(!MenuRenderer::use_compact_layout(
+            bar,
+            Rect::new(0, 0, 80, 4)
+        ));
+    }
+
+    #[test]
+    fn test_compact_menu_renders_items_on_one_line() {
+        let menus = create_test_menus();
+        let mut state = MenuState::new();
+        state.open_menu(0);
+        state.next_item(&menus[0]);
+
+        let rows = render_menu_rows(menus.clone(), &state, 80, 2);
+        assert!(!rows.concat().contains('┌'));
+        assert!(rows[1].contains(" New │ Save  Quit "), "{:?}", rows[1]);
+
+        // Keyboard navigation and activation behave as with a dropdown
+        assert_eq!(
+            state.get_highlighted_action(&menus, &|_| true),
+            Some(("save".to_string(), HashMap::new()))
+        );
+    }
+
+    #[test]
+    fn test_compact_menu_scrolls_to_highlighted_item() {
+        let menus = create_test_menus();
+        let mut state = MenuState::new();
+        state.open_menu(0);
+        state.next_item(&menus[0]);
+        state.next_item(&menus[0]);
+
+        let rows = render_menu_rows(menus, &state, 14, 2);
+        assert!(rows[1].trim_end().ends_with("Quit"), "{:?}", rows[1]);
+        assert!(!rows[1].contains("File:"));
+    }
+
+    #[test]
+    fn test_dropdown_min_width() {
+        let default_width = dropdown_rendered_width(&render_open_menu(create_test_menus(), 80));
+        assert!(default_width < 50);
+
+        let mut menus = create_test_menus();
+        menus[0].min_width = Some(50);
+        assert!(dropdown_rendered_width(&render_open_menu(menus.clone(), 80)) >= 50);
+
+        // Still clamped to the terminal
+        menus[0].min_width = Some(100);
+        assert_eq!(dropdown_rendered_width(&render_open_menu(menus, 60)), 60);
+    }
+
+    #[test]
+    fn test_dropdown_max_width_truncates_labels() {
+        let mut menus = create_test_menus();
+        menus[0].max_width = Some(24);
+        if let MenuItem::Action { label, .. } = &mut menus[0].items[3] {
+            *label = "Quit Without Saving Any Changes".to_string();
+        }
+
+        let rows = render_open_menu(menus, 80);
+        assert_eq!(dropdown_rendered_width(&rows), 24);
+        // Quit is the fourth item, below the menu bar and the top border
+        assert!(rows[5].contains("Quit Without"), "{}", rows[5]);
+        assert!(rows[5].contains('…'), "{}", rows[5]);
+        assert!(!rows[5].contains("Changes"), "{}", rows[5]);
+    }
+
+    #[test]
+    fn test_get_highlighted_action_none_when_closed() {
+        let state = MenuState::new();
+        let menus = create_test_menus();
+        assert!(state.get_highlighted_action(&menus, &|_| true).is_none());
+    }
+
+    #[test]
+    fn test_get_highlighted_action_none_for_separator() {
+        let mut state = MenuState::new();
+        let menus = create_test_menus();
+        state.open_menu(0);
+        state.highlighted_item = Some(1); // Separator
+
+        assert!(state.get_highlighted_action(&menus, &|_| true).is_none());
+    }
+
+    #[test]
+    fn test_get_menu_at_position() {
+        let state = MenuState::new();
+        let menus = create_test_menus();
+
+        // Menu positions: " File " (6 chars) + " " = 7, " Edit " (6 chars) + " " = 7, " View " (6 chars)
+        // File: x=0-5
+        assert_eq!(state.get_menu_at_position(&menus, 0), Some(0));
+        assert_eq!(state.get_menu_at_position(&menus, 3), Some(0));
+        assert_eq!(state.get_menu_at_position(&menus, 5), Some(0));
+
+        // Space between: x=6
+        assert_eq!(state.get_menu_at_position(&menus, 6), None);
+
+        // Edit: x=7-12
+        assert_eq!(state.get_menu_at_position(&menus, 7), Some(1));
         assert_eq!(state.get_menu_at_position(&menus, 10), Some(1));
         assert_eq!(state.get_menu_at_position(&menus, 12), Some(1));
 
@@ -970,6 +2031,71 @@ mod tests {
         assert_eq!(state.get_menu_at_position(&menus, 100), None);
     }
 
+    #[test]
+    fn test_menu_positions_use_display_width() {
+        let state = MenuState::new();
+        let mut menus = create_test_menus();
+        // Three wide characters take six columns but nine bytes
+        menus[0].label = "文件夹".to_string();
+
+        // " 文件夹   Edit   View "
+        assert_eq!(menu_label_offsets(&menus), vec![0, 9, 16]);
+        assert_eq!(state.get_menu_at_position(&menus, 7), Some(0));
+        assert_eq!(state.get_menu_at_position(&menus, 8), None);
+        assert_eq!(state.get_menu_at_position(&menus, 9), Some(1));
+        assert_eq!(state.get_menu_at_position(&menus, 16), Some(2));
+    }
+
+    #[test]
+    fn test_menu_group_separator_positions() {
+        let state = MenuState::new();
+        let mut menus = create_test_menus();
+        menus[2].group_start = true;
+
+        // " File   Edit  │  View " - the divider and its space push View right by 2
+        assert_eq!(menu_label_offsets(&menus), vec![0, 7, 16]);
+        assert_eq!(state.get_menu_at_position(&menus, 13), None);
+        assert_eq!(state.get_menu_at_position(&menus, 14), None);
+        assert_eq!(state.get_menu_at_position(&menus, 15), None);
+        assert_eq!(state.get_menu_at_position(&menus, 16), Some(2));
+        assert_eq!(state.get_menu_at_position(&menus, 21), Some(2));
+        assert_eq!(state.get_menu_at_position(&menus, 22), None);
+
+        // A group start on the first menu draws nothing
+        menus[0].group_start = true;
+        assert_eq!(menu_label_offsets(&menus), vec![0, 7, 16]);
+
+        // The renderer draws the divider where the offsets leave room for it
+        let backend = ratatui::backend::TestBackend::new(30, 1);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        let config = crate::config::Config::default();
+        let keybindings = crate::input::keybindings::KeybindingResolver::new(&config);
+        let menu_config = MenuConfig { menus };
+        terminal
+            .draw(|frame| {
+                MenuRenderer::render(
+                    frame,
+                    frame.area(),
+                    &menu_config,
+                    &state,
+                    &keybindings,
+                    &Theme::dark(),
+                    None,
+                    &|_| true,
+                )
+            })
+            .unwrap();
+        let row: String = terminal.backend().buffer().content()[..22]
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert_eq!(row, " File   Edit  │  View ");
+        assert_eq!(
+            terminal.backend().buffer()[(14, 0)].fg,
+            Theme::dark().menu_separator_fg
+        );
+    }
+
     #[test]
     fn test_get_item_at_position() {
         let state = MenuState::new();
@@ -1105,6 +2231,10 @@ mod tests {
     fn create_menu_with_submenus() -> Vec<Menu> {
         vec![Menu {
             label: "View".to_string(),
+            group_start: false,
+            min_width: None,
+            max_width: None,
+            max_visible_items: None,
             items: vec![
                 MenuItem::Action {
                     label: "Toggle Explorer".to_string(),
@@ -1112,6 +2242,7 @@ mod tests {
                     args: HashMap::new(),
                     when: None,
                     checkbox: None,
+                    style_role: None,
                 },
                 MenuItem::Submenu {
                     label: "Terminal".to_string(),
@@ -1122,6 +2253,7 @@ mod tests {
                             args: HashMap::new(),
                             when: None,
                             checkbox: None,
+                            style_role: None,
                         },
                         MenuItem::Action {
                             label: "Close Terminal".to_string(),
@@ -1129,6 +2261,7 @@ mod tests {
                             args: HashMap::new(),
                             when: None,
                             checkbox: None,
+                            style_role: None,
                         },
                         MenuItem::Submenu {
                             label: "Terminal Settings".to_string(),
@@ -1138,9 +2271,12 @@ mod tests {
                                 args: HashMap::new(),
                                 when: None,
                                 checkbox: None,
+                                style_role: None,
                             }],
+                            provider: None,
                         },
                     ],
+                    provider: None,
                 },
                 MenuItem::Separator { separator: true },
                 MenuItem::Action {
@@ -1149,6 +2285,7 @@ mod tests {
                     args: HashMap::new(),
                     when: None,
                     checkbox: None,
+                    style_role: None,
                 },
             ],
         }]
@@ -1215,6 +2352,122 @@ mod tests {
         assert!(!state.close_submenu());
     }
 
+    #[test]
+    fn test_escape_pops_one_level_at_a_time() {
+        let mut state = MenuState::new();
+        let menus = create_menu_with_submenus();
+
+        state.open_menu(0);
+        state.highlighted_item = Some(1);
+        assert!(state.open_submenu(&menus));
+        state.highlighted_item = Some(2);
+        assert!(state.open_submenu(&menus));
+        assert_eq!(state.submenu_path, vec![1, 2]);
+
+        // Two levels deep: each escape pops one submenu
+        assert!(!state.escape());
+        assert_eq!(state.submenu_path, vec![1]);
+        assert_eq!(state.highlighted_item, Some(2));
+        assert_eq!(state.active_menu, Some(0));
+
+        assert!(!state.escape());
+        assert!(state.submenu_path.is_empty());
+        assert_eq!(state.highlighted_item, Some(1));
+        assert_eq!(state.active_menu, Some(0));
+
+        // At the top level escape closes the whole bar
+        assert!(state.escape());
+        assert_eq!(state.active_menu, None);
+        assert_eq!(state.highlighted_item, None);
+    }
+
+    #[test]
+    fn test_current_path_labels() {
+        let mut state = MenuState::new();
+        let menus = create_menu_with_submenus();
+        assert!(state.current_path_labels(&menus).is_empty());
+
+        state.open_menu(0);
+        assert_eq!(state.current_path_labels(&menus), vec!["View"]);
+
+        state.highlighted_item = Some(1);
+        assert!(state.open_submenu(&menus));
+        assert_eq!(state.current_path_labels(&menus), vec!["View", "Terminal"]);
+
+        state.highlighted_item = Some(2);
+        assert!(state.open_submenu(&menus));
+        assert_eq!(
+            state.current_path_labels(&menus),
+            vec!["View", "Terminal", "Terminal Settings"]
+        );
+
+        state.close_submenu();
+        assert_eq!(state.current_path_labels(&menus), vec!["View", "Terminal"]);
+    }
+
+    #[test]
+    fn test_all_actions_reaches_nested_plugin_submenu() {
+        let mut state = MenuState::new();
+        let menus = create_test_menus();
+        state.plugin_menus = create_menu_with_submenus();
+
+        let actions = state.all_actions(&menus);
+        let font_size = actions
+            .iter()
+            .find(|item| item.action == "terminal_font_size")
+            .unwrap();
+        assert_eq!(
+            font_size.path,
+            vec!["View", "Terminal", "Terminal Settings", "Font Size"]
+        );
+        // Plugin menus come after the configured ones
+        assert_eq!(font_size.indices, vec![menus.len(), 1, 2, 0]);
+
+        // Separators and submenus themselves aren't listed
+        assert_eq!(actions[0].path, vec!["File", "New"]);
+        assert!(actions
+            .iter()
+            .all(|item| item.path.len() >= 2 && !item.action.is_empty()));
+        assert_eq!(
+            actions
+                .iter()
+                .filter(|item| item.indices[0] == menus.len())
+                .count(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_open_to_action_in_nested_submenu() {
+        let mut state = MenuState::new();
+        let mut menus = create_test_menus();
+        menus.extend(create_menu_with_submenus());
+        let view = menus.len() - 1;
+
+        assert!(state.open_to_action("terminal_font_size", &menus));
+        assert_eq!(state.active_menu, Some(view));
+        assert_eq!(state.submenu_path, vec![1, 2]);
+        assert_eq!(state.highlighted_item, Some(0));
+        assert_eq!(
+            state.current_path_labels(&menus),
+            vec!["View", "Terminal", "Terminal Settings"]
+        );
+        assert_eq!(
+            state.get_highlighted_action(&menus, &|_| true),
+            Some(("terminal_font_size".to_string(), HashMap::new()))
+        );
+
+        // A top-level item closes the submenus again
+        assert!(state.open_to_action("zoom_in", &menus));
+        assert!(state.submenu_path.is_empty());
+        assert_eq!(state.highlighted_item, Some(3));
+
+        // Unknown actions leave the menu as it was
+        assert!(!state.open_to_action("no_such_action", &menus));
+        assert_eq!(state.active_menu, Some(view));
+        assert_eq!(state.highlighted_item, Some(3));
+    }
+
     #[test]
     fn test_get_highlighted_action_in_submenu() {
         let mut state = MenuState::new();
@@ -1224,19 +2477,19 @@ mod tests {
         state.highlighted_item = Some(1); // Terminal submenu
 
         // On a submenu item, get_highlighted_action should return None
-        assert!(state.get_highlighted_action(&menus).is_none());
+        assert!(state.get_highlighted_action(&menus, &|_| true).is_none());
 
         // Open the submenu
         state.open_submenu(&menus);
         // Now highlighted_item is 0 which is "Open Terminal"
-        let action = state.get_highlighted_action(&menus);
+        let action = state.get_highlighted_action(&menus, &|_| true);
         assert!(action.is_some());
         let (action_name, _) = action.unwrap();
         assert_eq!(action_name, "open_terminal");
 
         // Navigate to second item
         state.highlighted_item = Some(1);
-        let action = state.get_highlighted_action(&menus);
+        let action = state.get_highlighted_action(&menus, &|_| true);
         assert!(action.is_some());
         let (action_name, _) = action.unwrap();
         assert_eq!(action_name, "close_terminal");
@@ -1356,4 +2609,371 @@ mod tests {
         state.prev_item(&menus[0]);
         assert_eq!(state.highlighted_item, Some(2));
     }
+
+    #[test]
+    fn test_shadow_areas_within_bounds() {
+        let bounds = Rect::new(0, 0, 80, 24);
+
+        // Dropdown with room on both sides: shadow sits one cell down-right
+        let shadows = MenuRenderer::shadow_areas(Rect::new(10, 1, 20, 6), bounds);
+        assert_eq!(
+            shadows,
+            vec![Rect::new(30, 2, 1, 6), Rect::new(11, 7, 20, 1)]
+        );
+
+        // Dropdown clamped against the right and bottom edges: no shadow outside
+        let shadows = MenuRenderer::shadow_areas(Rect::new(60, 18, 20, 6), bounds);
+        assert!(shadows.is_empty());
+
+        // Against the right edge only: bottom strip is clipped to the screen width
+        let shadows = MenuRenderer::shadow_areas(Rect::new(65, 1, 15, 6), bounds);
+        assert_eq!(shadows, vec![Rect::new(66, 7, 14, 1)]);
+
+        for area in [
+            Rect::new(0, 0, 80, 24),
+            Rect::new(79, 23, 1, 1),
+            Rect::new(70, 20, 10, 4),
+        ] {
+            for shadow in MenuRenderer::shadow_areas(area, bounds) {
+                assert_eq!(shadow.intersection(bounds), shadow);
+            }
+        }
+    }
+
+    fn create_menu_with_lazy_submenu() -> Vec<Menu> {
+        vec![Menu {
+            label: "View".to_string(),
+            group_start: false,
+            min_width: None,
+            max_width: None,
+            max_visible_items: None,
+            items: vec![
+                MenuItem::Action {
+                    label: "Toggle Explorer".to_string(),
+                    action: "toggle_file_explorer".to_string(),
+                    args: HashMap::new(),
+                    when: None,
+                    checkbox: None,
+                    style_role: None,
+                },
+                MenuItem::Submenu {
+                    label: "Theme".to_string(),
+                    items: vec![],
+                    provider: Some("themes".to_string()),
+                },
+            ],
+        }]
+    }
+
+    fn counting_provider(
+        calls: &Arc<std::sync::atomic::AtomicUsize>,
+    ) -> impl Fn() -> Vec<MenuItem> {
+        let calls = Arc::clone(calls);
+        move || {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            ["dark", "light"]
+                .iter()
+                .map(|name| MenuItem::Action {
+                    label: name.to_string(),
+                    action: "select_theme".to_string(),
+                    args: HashMap::new(),
+                    when: None,
+                    checkbox: None,
+                    style_role: None,
+                })
+                .collect()
+        }
+    }
+
+    fn recent_actions(state: &MenuState) -> Vec<(String, String)> {
+        state
+            .recent_actions
+            .menu_items()
+            .into_iter()
+            .map(|item| match item {
+                MenuItem::Action { label, action, .. } => (label, action),
+                other => panic!("expected an action, got {other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recent_actions_deduplicated_most_recent_first() {
+        let state = MenuState::new();
+        let no_args = HashMap::new();
+        let file_menu = HashMap::from([("name".to_string(), serde_json::json!("File"))]);
+        let edit_menu = HashMap::from([("name".to_string(), serde_json::json!("Edit"))]);
+
+        state.record_action("save", &no_args);
+        state.record_action("menu_open", &file_menu);
+        state.record_action("toggle_line_wrap", &no_args);
+        state.record_action("save", &no_args);
+        state.record_action("menu_open", &edit_menu);
+
+        assert_eq!(
+            recent_actions(&state),
+            vec![
+                ("Menu open: Edit".to_string(), "menu_open".to_string()),
+                ("Save".to_string(), "save".to_string()),
+                (
+                    "Toggle line wrap".to_string(),
+                    "toggle_line_wrap".to_string()
+                ),
+                ("Menu open: File".to_string(), "menu_open".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recent_actions_evict_oldest_past_capacity() {
+        let state = MenuState::new();
+        for i in 0..MAX_RECENT_ACTIONS + 2 {
+            state.record_action(&format!("action_{i}"), &HashMap::new());
+        }
+
+        let actions: Vec<_> = recent_actions(&state)
+            .into_iter()
+            .map(|(_, action)| action)
+            .collect();
+        assert_eq!(actions.len(), MAX_RECENT_ACTIONS);
+        assert_eq!(actions[0], format!("action_{}", MAX_RECENT_ACTIONS + 1));
+        assert_eq!(actions.last().unwrap(), "action_2");
+    }
+
+    #[test]
+    fn test_recent_commands_submenu_uses_history() {
+        let menus = vec![Menu {
+            label: "Go".to_string(),
+            group_start: false,
+            min_width: None,
+            max_width: None,
+            max_visible_items: None,
+            items: vec![MenuItem::Submenu {
+                label: "Recent Commands".to_string(),
+                items: vec![],
+                provider: Some(RECENT_COMMANDS_PROVIDER.to_string()),
+            }],
+        }];
+        let mut state = MenuState::new();
+        state.open_menu(0);
+        // Nothing run yet, so there is nothing to open
+        assert!(!state.open_submenu(&menus));
+
+        state.close_menu();
+        state.record_action("save", &HashMap::new());
+        state.open_menu(0);
+        assert!(state.open_submenu(&menus));
+        assert_eq!(
+            state.get_highlighted_action(&menus, &|_| true),
+            Some(("save".to_string(), HashMap::new()))
+        );
+    }
+
+    #[test]
+    fn test_lazy_submenu_provider_called_once_on_open() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut state = MenuState::new();
+        state.register_submenu_provider("themes", counting_provider(&calls));
+        let menus = create_menu_with_lazy_submenu();
+
+        // Opening the top-level menu doesn't materialize the submenu
+        state.open_menu(0);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        state.highlighted_item = Some(1);
+        assert!(state.open_submenu(&menus));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let items = state.get_current_items(&menus, 0).unwrap();
+        assert_eq!(items.len(), 2);
+
+        // Navigating and reopening within the same menu session uses the cache
+        state.next_item(&menus[0]);
+        assert!(state.close_submenu());
+        assert!(state.open_submenu(&menus));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Closing the menu drops the cache, so the next open fetches again
+        state.close_menu();
+        state.open_menu(0);
+        state.highlighted_item = Some(1);
+        assert!(state.open_submenu(&menus));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_cancel_reverts_preview_and_commit_keeps_it() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let reverts = Arc::new(AtomicUsize::new(0));
+        let revert = |id: usize| {
+            let reverts = reverts.clone();
+            move || {
+                reverts.fetch_add(id, Ordering::SeqCst);
+            }
+        };
+        let mut state = MenuState::new();
+        let menus = create_menu_with_submenus();
+
+        // Escaping out of the menu bar cancels; only the first revert runs
+        state.open_menu(0);
+        state.begin_preview(revert(1));
+        state.next_item(&menus[0]);
+        state.begin_preview(revert(10));
+        assert!(state.is_previewing());
+        assert!(state.escape());
+        assert_eq!(reverts.load(Ordering::SeqCst), 1);
+        assert!(!state.is_previewing());
+        assert!(!state.cancel_preview());
+
+        // Executing the item commits before closing
+        state.open_menu(0);
+        state.begin_preview(revert(100));
+        state.commit_preview();
+        state.close_menu();
+        assert_eq!(reverts.load(Ordering::SeqCst), 1);
+    }
+
+    fn numbered_menu(count: usize, max_visible_items: Option<u16>) -> Menu {
+        Menu {
+            label: "Long".to_string(),
+            group_start: false,
+            min_width: None,
+            max_width: None,
+            max_visible_items,
+            items: (0..count)
+                .map(|i| MenuItem::Action {
+                    label: format!("Item {i}"),
+                    action: format!("action_{i}"),
+                    args: HashMap::new(),
+                    when: None,
+                    checkbox: None,
+                    style_role: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn item_labels(items: &[MenuItem]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| match item {
+                MenuItem::Action { label, .. } | MenuItem::Submenu { label, .. } => label.as_str(),
+                MenuItem::Separator { .. } => "-",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_menu_past_max_visible_items_gets_more_submenu() {
+        let mut state = MenuState::new();
+        let menus = state.menus(&[numbered_menu(8, Some(4)), numbered_menu(4, Some(4))]);
+
+        // The last visible slot holds the overflow, which overflows again
+        assert_eq!(
+            item_labels(&menus[0].items),
+            vec!["Item 0", "Item 1", "Item 2", "More…"]
+        );
+        let more = state.submenu_items(&menus[0].items[3]).unwrap();
+        assert_eq!(
+            item_labels(more),
+            vec!["Item 3", "Item 4", "Item 5", "More…"]
+        );
+        let more = state.submenu_items(&more[3]).unwrap();
+        assert_eq!(item_labels(more), vec!["Item 6", "Item 7"]);
+        // A menu within its limit is unchanged
+        assert_eq!(menus[1].items.len(), 4);
+        assert_eq!(state.menus(&[numbered_menu(8, None)])[0].items.len(), 8);
+
+        // "More…" opens like any other submenu
+        state.open_menu(0);
+        state.highlighted_item = Some(3);
+        assert!(state.open_submenu(&menus));
+        state.highlighted_item = Some(3);
+        assert!(state.open_submenu(&menus));
+        state.next_item(&menus[0]);
+        assert_eq!(
+            state.get_highlighted_action(&menus, &|_| true),
+            Some(("action_7".to_string(), HashMap::new()))
+        );
+    }
+
+    #[test]
+    fn test_actions_past_max_visible_items_are_found_through_more() {
+        let mut state = MenuState::new();
+        let configured = vec![numbered_menu(8, Some(4))];
+
+        let actions = state.all_actions(&configured);
+        let last = actions
+            .iter()
+            .find(|item| item.action == "action_7")
+            .unwrap();
+        assert_eq!(last.path, vec!["Long", "More…", "More…", "Item 7"]);
+        assert_eq!(last.indices, vec![0, 3, 3, 1]);
+
+        assert!(state.open_to_action("action_7", &configured));
+        assert_eq!(state.submenu_path, vec![3, 3]);
+        assert_eq!(state.highlighted_item, Some(1));
+        assert_eq!(
+            state.current_path_labels(&configured),
+            vec!["Long", "More…", "More…"]
+        );
+        assert_eq!(
+            state.get_highlighted_action(&state.menus(&configured), &|_| true),
+            Some(("action_7".to_string(), HashMap::new()))
+        );
+    }
+
+    #[test]
+    fn test_overflow_drops_separators_at_the_split() {
+        let mut menu = numbered_menu(4, Some(3));
+        menu.items
+            .insert(2, MenuItem::Separator { separator: true });
+        let items = split_overflow(&menu.items, 3);
+        assert_eq!(item_labels(&items), vec!["Item 0", "Item 1", "More…"]);
+        let MenuItem::Submenu { items: more, .. } = &items[2] else {
+            panic!("Expected Submenu, got {:?}", items[2]);
+        };
+        assert_eq!(item_labels(more), vec!["Item 2", "Item 3"]);
+    }
+
+    #[test]
+    fn test_lazy_submenu_without_provider_does_not_open() {
+        let mut state = MenuState::new();
+        let menus = create_menu_with_lazy_submenu();
+
+        state.open_menu(0);
+        state.highlighted_item = Some(1);
+        assert!(state.is_highlighted_submenu(&menus));
+        assert!(!state.open_submenu(&menus));
+        assert!(state.submenu_path.is_empty());
+    }
+
+    #[test]
+    fn test_lazy_submenu_config_parsing() {
+        let json = r#"{
+            "menus": [
+                {
+                    "label": "View",
+                    "items": [
+                        { "label": "Theme", "provider": "themes" }
+                    ]
+                }
+            ]
+        }"#;
+        let config: MenuConfig = serde_json::from_str(json).unwrap();
+        match &config.menus[0].items[0] {
+            MenuItem::Submenu {
+                label,
+                items,
+                provider,
+            } => {
+                assert_eq!(label, "Theme");
+                assert!(items.is_empty());
+                assert_eq!(provider.as_deref(), Some("themes"));
+            }
+            other => panic!("Expected Submenu, got {:?}", other),
+        }
+    }
 }
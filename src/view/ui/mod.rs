@@ -31,7 +31,7 @@ pub use scroll_panel::{
     FocusRegion, RenderInfo, ScrollItem, ScrollState, ScrollablePanel, ScrollablePanelLayout,
 };
 pub use scrollbar::{render_scrollbar, ScrollbarColors, ScrollbarState};
-pub use split_rendering::SplitRenderer;
+pub use split_rendering::{RenderOptions, SplitRenderer};
 pub use status_bar::{truncate_path, StatusBarRenderer, TruncatedPath};
 pub use suggestions::SuggestionsRenderer;
 pub use tabs::TabsRenderer;
@@ -4,6 +4,7 @@ use std::collections::BTreeMap;
 
 use crate::app::types::ViewLineMapping;
 use crate::app::BufferMetadata;
+use crate::config::WhitespaceMode;
 use crate::model::buffer::Buffer;
 use crate::model::cursor::SelectionMode;
 use crate::model::event::{BufferId, EventLog, SplitDirection};
@@ -11,7 +12,7 @@ use crate::primitives::ansi::AnsiParser;
 use crate::primitives::ansi_background::AnsiBackground;
 use crate::primitives::display_width::char_width;
 use crate::services::plugins::api::ViewTransformPayload;
-use crate::state::{EditorState, ViewMode};
+use crate::state::{EditorState, ViewMode, WsMarker};
 use crate::view::split::SplitManager;
 use crate::view::ui::tabs::TabsRenderer;
 use crate::view::ui::view_pipeline::{
@@ -48,6 +49,17 @@ fn push_span_with_map(
     spans.push(Span::styled(text, style));
 }
 
+/// Append a line's EndOfLine virtual texts, each separated by a space
+fn push_end_of_line_texts(
+    spans: &mut Vec<Span<'static>>,
+    map: &mut Vec<Option<usize>>,
+    texts: &[crate::view::virtual_text::VirtualText],
+) {
+    for vtext in texts {
+        push_span_with_map(spans, map, format!(" {}", vtext.text), vtext.style, None);
+    }
+}
+
 /// Processed view data containing display lines from the view pipeline
 struct ViewData {
     /// Display lines with all token information preserved
@@ -77,6 +89,9 @@ struct DecorationContext {
     semantic_spans: Vec<crate::primitives::highlighter::HighlightSpan>,
     viewport_overlays: Vec<(crate::view::overlay::Overlay, Range<usize>)>,
     virtual_text_lookup: HashMap<usize, Vec<crate::view::virtual_text::VirtualText>>,
+    /// EndOfLine virtual texts keyed by the byte offset of their line's newline
+    /// (or the buffer length for the last line)
+    end_of_line_lookup: HashMap<usize, Vec<crate::view::virtual_text::VirtualText>>,
     diagnostic_lines: HashSet<usize>,
     /// Line indicators indexed by line number (highest priority indicator per line)
     line_indicators: BTreeMap<usize, crate::view::margin::LineIndicator>,
@@ -108,6 +123,17 @@ struct ViewPreferences {
     view_transform: Option<ViewTransformPayload>,
 }
 
+/// Editor settings that change how buffer text is drawn
+#[derive(Clone, Copy, Debug)]
+pub struct RenderOptions<'a> {
+    /// Which whitespace characters get visible markers
+    pub render_whitespace: WhitespaceMode,
+    /// Text columns to draw a vertical ruler at
+    pub rulers: &'a [usize],
+    /// The faded theme unfocused splits are drawn with
+    pub inactive_theme: &'a crate::view::theme::Theme,
+}
+
 struct LineRenderInput<'a> {
     state: &'a EditorState,
     theme: &'a crate::view::theme::Theme,
@@ -126,6 +152,10 @@ struct LineRenderInput<'a> {
     estimated_lines: usize,
     /// Left column offset for horizontal scrolling
     left_column: usize,
+    /// Which whitespace characters get visible markers
+    render_whitespace: WhitespaceMode,
+    /// Whitespace markers for the visible source lines, keyed by byte offset
+    whitespace_markers: &'a HashMap<usize, WsMarker>,
 }
 
 /// Context for computing the style of a single character
@@ -400,6 +430,7 @@ impl SplitRenderer {
     /// * `large_file_threshold_bytes` - Threshold for using constant scrollbar thumb size
     /// * `line_wrap` - Whether line wrapping is enabled
     /// * `estimated_line_length` - Estimated average line length for large file line estimation
+    /// * `options` - Editor settings that change how buffer text is drawn
     /// * `hide_cursor` - Whether to hide the hardware cursor (e.g., when menu is open)
     ///
     /// # Returns
@@ -419,6 +450,7 @@ impl SplitRenderer {
         _line_wrap: bool,
         estimated_line_length: usize,
         highlight_context_bytes: usize,
+        options: RenderOptions<'_>,
         mut split_view_states: Option<
             &mut HashMap<crate::model::event::SplitId, crate::view::split::SplitViewState>,
         >,
@@ -459,6 +491,12 @@ impl SplitRenderer {
         // Render each split
         for (split_id, buffer_id, split_area) in visible_buffers {
             let is_active = split_id == active_split_id;
+            // Unfocused splits are drawn faded, except when they are the only one
+            let theme = if is_active || !has_multiple_splits {
+                theme
+            } else {
+                options.inactive_theme
+            };
 
             let layout = Self::split_layout(split_area);
             let (split_buffers, tab_scroll_offset) =
@@ -592,6 +630,7 @@ impl SplitRenderer {
                     view_prefs.view_transform,
                     estimated_line_length,
                     highlight_context_bytes,
+                    options,
                     buffer_id,
                     hide_cursor,
                 );
@@ -1768,8 +1807,28 @@ impl SplitRenderer {
                 .map(|(position, texts)| (position, texts.into_iter().cloned().collect()))
                 .collect();
 
+        let mut end_of_line_lookup: HashMap<usize, Vec<crate::view::virtual_text::VirtualText>> =
+            HashMap::new();
+        let buffer_len = state.buffer.len();
+        for (position, texts) in &virtual_text_lookup {
+            let line_end = state
+                .buffer
+                .find_next_in_range("\n", *position, Some(*position..buffer_len))
+                .unwrap_or(buffer_len);
+            end_of_line_lookup.entry(line_end).or_default().extend(
+                texts
+                    .iter()
+                    .filter(|v| v.position == VirtualTextPosition::EndOfLine)
+                    .cloned(),
+            );
+        }
+        for texts in end_of_line_lookup.values_mut() {
+            texts.sort_by_key(|vt| vt.priority);
+        }
+
         // Pre-compute line indicators for the viewport (only query markers in visible range)
         let line_indicators = state.margins.get_indicators_for_viewport(
+            &state.marker_list,
             viewport_start,
             viewport_end,
             |byte_offset| state.buffer.get_line_number(byte_offset),
@@ -1780,6 +1839,7 @@ impl SplitRenderer {
             semantic_spans,
             viewport_overlays,
             virtual_text_lookup,
+            end_of_line_lookup,
             diagnostic_lines,
             line_indicators,
         }
@@ -1822,6 +1882,8 @@ impl SplitRenderer {
             line_wrap,
             estimated_lines,
             left_column,
+            render_whitespace,
+            whitespace_markers,
         } = input;
 
         let selection_ranges = &selection.ranges;
@@ -1833,6 +1895,7 @@ impl SplitRenderer {
         let semantic_spans = &decorations.semantic_spans;
         let viewport_overlays = &decorations.viewport_overlays;
         let virtual_text_lookup = &decorations.virtual_text_lookup;
+        let end_of_line_lookup = &decorations.end_of_line_lookup;
         let diagnostic_lines = &decorations.diagnostic_lines;
         let line_indicators = &decorations.line_indicators;
 
@@ -2084,6 +2147,17 @@ impl SplitRenderer {
                         is_active,
                     });
 
+                    // Tab starts come from the view pipeline, so tabs are marked even
+                    // without a line index; spaces rely on the precomputed markers
+                    let is_space_marker = ch == ' '
+                        && byte_pos.is_some_and(|bp| whitespace_markers.contains_key(&bp));
+                    let is_tab_marker = is_tab_start && render_whitespace.shows_tabs();
+                    let style = if (is_tab_marker || is_space_marker) && !is_cursor {
+                        style.fg(theme.whitespace_indicator_fg)
+                    } else {
+                        style
+                    };
+
                     // Determine display character (tabs already expanded in ViewLineIterator)
                     // Show tab indicator (→) at the start of tab expansions
                     let tab_indicator: String;
@@ -2093,10 +2167,13 @@ impl SplitRenderer {
                         ""
                     } else if ch == '\n' {
                         ""
-                    } else if is_tab_start {
+                    } else if is_tab_marker {
                         // Visual indicator for tab: show → at the first position
                         tab_indicator = "→".to_string();
                         &tab_indicator
+                    } else if is_space_marker {
+                        tab_indicator = "·".to_string();
+                        &tab_indicator
                     } else {
                         tab_indicator = ch.to_string();
                         &tab_indicator
@@ -2183,6 +2260,12 @@ impl SplitRenderer {
                             );
                         }
                     }
+
+                    if ch == '\n' {
+                        if let Some(vtexts) = byte_pos.and_then(|bp| end_of_line_lookup.get(&bp)) {
+                            push_end_of_line_texts(&mut line_spans, &mut line_view_map, vtexts);
+                        }
+                    }
                 }
 
                 byte_index += ch.len_utf8();
@@ -2271,6 +2354,16 @@ impl SplitRenderer {
                         );
                     }
                 }
+
+                // The last line has no newline to hang its end-of-line texts on
+                let line_end = last_char_buf_pos.map_or(0, |bp| {
+                    bp + line_content.chars().last().map_or(1, char::len_utf8)
+                });
+                if line_end == state.buffer.len() {
+                    if let Some(vtexts) = end_of_line_lookup.get(&line_end) {
+                        push_end_of_line_texts(&mut line_spans, &mut line_view_map, vtexts);
+                    }
+                }
             }
 
             // ViewLines are already wrapped (Break tokens became newlines in ViewLineIterator)
@@ -2467,12 +2560,13 @@ impl SplitRenderer {
         view_transform: Option<ViewTransformPayload>,
         estimated_line_length: usize,
         highlight_context_bytes: usize,
+        options: RenderOptions<'_>,
         _buffer_id: BufferId,
         hide_cursor: bool,
     ) -> Vec<ViewLineMapping> {
         let _span = tracing::trace_span!("render_buffer_in_split").entered();
 
-        let line_wrap = viewport.line_wrap_enabled;
+        let line_wrap = viewport.wraps(&state.buffer);
 
         let overlay_count = state.overlays.all().len();
         if overlay_count > 0 {
@@ -2506,7 +2600,8 @@ impl SplitRenderer {
         // Ensure cursor is visible using Layout-aware check (handles virtual lines)
         // This detects when cursor is beyond the rendered view_lines and scrolls
         let primary = *state.cursors.primary();
-        let scrolled = viewport.ensure_visible_in_layout(&view_data.lines, &primary, gutter_width);
+        let scrolled =
+            viewport.ensure_visible_in_layout(&view_data.lines, &primary, gutter_width, line_wrap);
 
         // If we scrolled, rebuild view_data from new position WITH the view_transform
         // This ensures virtual lines are included in the rebuilt view
@@ -2571,6 +2666,11 @@ impl SplitRenderer {
             highlight_context_bytes,
         );
 
+        let whitespace_markers: HashMap<usize, WsMarker> = state
+            .whitespace_markers(viewport, options.render_whitespace)
+            .into_iter()
+            .collect();
+
         // Apply top_view_line_offset to skip virtual lines when scrolling through them
         let view_line_offset = viewport.top_view_line_offset;
         let view_lines_to_render =
@@ -2596,6 +2696,8 @@ impl SplitRenderer {
             line_wrap,
             estimated_lines,
             left_column: viewport.left_column,
+            render_whitespace: options.render_whitespace,
+            whitespace_markers: &whitespace_markers,
         });
 
         let mut lines = render_output.lines;
@@ -2644,6 +2746,22 @@ impl SplitRenderer {
             }
         }
 
+        // Tint the ruler columns; they scroll horizontally with the text
+        let content_right = render_area.x as usize + render_area.width as usize;
+        for ruler in options.rulers {
+            let Some(column) = ruler.checked_sub(viewport.left_column) else {
+                continue;
+            };
+            let x = render_area.x as usize + gutter_width + column;
+            if x >= content_right {
+                continue;
+            }
+            let buf = frame.buffer_mut();
+            for row in 0..render_area.height {
+                buf[(x as u16, render_area.y + row)].set_bg(theme.ruler_bg);
+            }
+        }
+
         let buffer_ends_with_newline = if state.buffer.len() > 0 {
             let last_char = state.get_text_range(state.buffer.len() - 1, state.buffer.len());
             last_char == "\n"
@@ -2901,6 +3019,8 @@ mod tests {
             line_wrap: viewport.line_wrap_enabled,
             estimated_lines,
             left_column: viewport.left_column,
+            render_whitespace: WhitespaceMode::default(),
+            whitespace_markers: &HashMap::new(),
         });
 
         (
@@ -106,6 +106,24 @@ impl Viewport {
         self.scroll_offset = offset;
     }
 
+    /// Keep showing the same text after `old_len` bytes at `edit_pos` were
+    /// replaced by `new_len` bytes
+    ///
+    /// Used for splits showing a buffer edited from another split. Edits before
+    /// the top of the view move it along; edits starting at the top are shown.
+    /// If the top itself was deleted, the view starts where the deletion was
+    /// (line iteration begins at the start of that line).
+    pub fn adjust_for_edit(&mut self, edit_pos: usize, old_len: usize, new_len: usize) {
+        if edit_pos >= self.top_byte {
+            return;
+        }
+        if edit_pos + old_len <= self.top_byte {
+            self.top_byte = self.top_byte - old_len + new_len;
+        } else {
+            self.top_byte = edit_pos;
+        }
+    }
+
     /// Update terminal dimensions
     pub fn resize(&mut self, width: u16, height: u16) {
         self.width = width;
@@ -266,6 +284,7 @@ impl Viewport {
         view_lines: &[ViewLine],
         cursor: &Cursor,
         gutter_width: usize,
+        line_wrap: bool,
     ) -> bool {
         // Check if we should skip sync due to session restore
         // This prevents the restored scroll position from being overwritten
@@ -381,9 +400,12 @@ impl Viewport {
             // Calculate visual column by walking through characters and summing widths
             // until we've consumed cursor_byte_offset bytes
             let line_text = line.text.trim_end_matches('\n');
+            // A cursor past this line's break is at the start of the empty
+            // last line, which has no view line of its own
+            let on_next_line = line.text.ends_with('\n') && cursor_byte_offset >= line.text.len();
             let mut bytes_consumed = 0usize;
             let mut cursor_visual_col = 0usize;
-            for ch in line_text.chars() {
+            for ch in line_text.chars().take_while(|_| !on_next_line) {
                 if bytes_consumed >= cursor_byte_offset {
                     break;
                 }
@@ -392,7 +414,12 @@ impl Viewport {
             }
 
             let line_visual_width = str_width(line_text);
-            self.ensure_column_visible_simple(cursor_visual_col, line_visual_width, gutter_width);
+            self.ensure_column_visible_simple(
+                cursor_visual_col,
+                line_visual_width,
+                gutter_width,
+                line_wrap,
+            );
         }
 
         false
@@ -404,9 +431,10 @@ impl Viewport {
         column: usize,
         line_length: usize,
         gutter_width: usize,
+        line_wrap: bool,
     ) {
         // Skip if line wrapping is enabled (all columns visible via wrapping)
-        if self.line_wrap_enabled {
+        if line_wrap {
             self.left_column = 0;
             return;
         }
@@ -655,186 +683,51 @@ impl Viewport {
         let cursor_iter = buffer.line_iterator(cursor.position, 80);
         let cursor_line_start = cursor_iter.current_position();
 
-        // Check if cursor is visible by counting VISUAL ROWS between top_byte and cursor
-        // When line wrapping is enabled, we need to count wrapped rows, not logical lines!
-        // Apply scroll_offset to keep cursor away from edges
-        let effective_offset = self.scroll_offset.min(viewport_lines / 2);
-
-        let cursor_is_visible = if cursor_line_start < self.top_byte {
-            // Cursor is above viewport
-            false
-        } else {
-            if self.line_wrap_enabled {
-                // With line wrapping: count VISUAL ROWS (wrapped segments), not logical lines
-                let gutter_width = self.gutter_width(buffer);
-                let wrap_config = WrapConfig::new(self.width as usize, gutter_width, true);
-
-                let mut iter = buffer.line_iterator(self.top_byte, 80);
-                let mut visual_rows = 0;
-
-                // Iterate through logical lines, but count their wrapped rows
-                loop {
-                    let current_pos = iter.current_position();
-
-                    // If we reached the cursor's line, check if the cursor is within visible rows
-                    if current_pos >= cursor_line_start {
-                        // The cursor's line starts within or after the viewport
-                        if current_pos == cursor_line_start {
-                            // We need to check if the cursor's SPECIFIC POSITION within the wrapped line is visible
-                            // Get the line content
-                            let line_content = if let Some((_, content)) = iter.next() {
-                                content.trim_end_matches('\n').to_string()
-                            } else {
-                                // At EOF after trailing newline - empty line
-                                String::new()
-                            };
-
-                            // Wrap the line (even if empty, it still takes 1 row)
-                            let segments = wrap_line(&line_content, &wrap_config);
-                            let segments_count = segments.len().max(1); // Empty line is 1 row
-
-                            // Find which segment the cursor is in
-                            let cursor_column = cursor.position.saturating_sub(cursor_line_start);
-                            let (cursor_segment_idx, _) =
-                                char_position_to_segment(cursor_column, &segments);
-
-                            // Add the rows for this line up to and including the cursor's segment
-                            // For empty lines, cursor_segment_idx is 0, so we add 1 row
-                            visual_rows += cursor_segment_idx.min(segments_count - 1) + 1;
-
-                            // Check if cursor's row is within viewport with scroll offset applied
-                            // Cursor should be between effective_offset and (viewport_lines - effective_offset)
-                            break visual_rows > effective_offset
-                                && visual_rows <= viewport_lines.saturating_sub(effective_offset);
-                        } else {
-                            // We passed the cursor's line without finding it - shouldn't happen
-                            break false;
-                        }
-                    }
-
-                    // Get the next line
-                    if let Some((_line_start, line_content)) = iter.next() {
-                        // Wrap this line to count how many visual rows it takes
-                        let line_text = line_content.trim_end_matches('\n');
-                        let segments = wrap_line(line_text, &wrap_config);
-                        visual_rows += segments.len();
-
-                        // If we've exceeded the viewport, cursor is not visible
-                        if visual_rows >= viewport_lines {
-                            break false;
-                        }
-                    } else {
-                        // Reached end of buffer
-                        break false;
-                    }
-                }
-            } else {
-                // Without line wrapping: count logical lines as before
-                let mut iter = buffer.line_iterator(self.top_byte, 80);
-                let mut lines_from_top = 0;
-
-                while iter.current_position() < cursor_line_start && lines_from_top < viewport_lines
-                {
-                    if iter.next().is_none() {
-                        break;
-                    }
-                    lines_from_top += 1;
-                }
-
-                // Apply scroll offset: cursor should be between offset and (viewport_lines - offset)
-                let visible = lines_from_top > effective_offset
-                    && lines_from_top < viewport_lines.saturating_sub(effective_offset);
-                tracing::trace!(
-                    "ensure_visible (no wrap): lines_from_top={}, effective_offset={}, visible={}",
-                    lines_from_top,
-                    effective_offset,
-                    visible
-                );
-                visible
+        // Keep the cursor at least `scroll_offset` rows from the top and bottom
+        // edges, or as close as a short viewport allows
+        let effective_offset = self.scroll_offset.min(viewport_lines.saturating_sub(1) / 2);
+        let top_row = effective_offset;
+        let bottom_row = viewport_lines - 1 - effective_offset;
+
+        let cursor_row = self.cursor_row(buffer, cursor, cursor_line_start, viewport_lines);
+        let target_row = match cursor_row {
+            Some(row) if (top_row..=bottom_row).contains(&row) => None,
+            // Moved into a margin, or onto the row just past an edge: scroll
+            // just enough to restore the margin
+            Some(row) if row < top_row => Some(top_row),
+            Some(_) => Some(bottom_row),
+            None if buffer
+                .line_iterator(self.top_byte, 80)
+                .prev()
+                .is_some_and(|(line_start, _)| line_start == cursor_line_start) =>
+            {
+                Some(top_row)
             }
+            // Jumped further away: center the cursor
+            None => Some(viewport_lines / 2),
         };
 
         tracing::trace!(
-            "ensure_visible: cursor_line_start={}, cursor_is_visible={}",
+            "ensure_visible: cursor_line_start={}, cursor_row={:?}, target_row={:?}",
             cursor_line_start,
-            cursor_is_visible
+            cursor_row,
+            target_row
         );
 
-        // If cursor is not visible, scroll to make it visible
-        if !cursor_is_visible {
-            // Position cursor at center of viewport when jumping
-            let target_rows_from_top = viewport_lines / 2;
-
-            if self.line_wrap_enabled {
-                // When wrapping is enabled, count visual rows (wrapped segments) not logical lines
-                let gutter_width = self.gutter_width(buffer);
-                let wrap_config = WrapConfig::new(self.width as usize, gutter_width, true);
-
-                let mut iter = buffer.line_iterator(cursor_line_start, 80);
-                let mut visual_rows_counted = 0;
-
-                // First, count how many rows the cursor's line takes up to the cursor position
-                if let Some((_line_start, line_content)) = iter.next() {
-                    let line_text = if line_content.ends_with('\n') {
-                        &line_content[..line_content.len() - 1]
-                    } else {
-                        &line_content
-                    };
-                    let segments = wrap_line(line_text, &wrap_config);
-                    let cursor_column = cursor.position.saturating_sub(cursor_line_start);
-                    let (cursor_segment_idx, _) =
-                        char_position_to_segment(cursor_column, &segments);
-                    visual_rows_counted += cursor_segment_idx + 1;
-                } else {
-                    // At EOF after trailing newline - cursor is on empty line, needs 1 row
-                    visual_rows_counted += 1;
-                }
-
-                // Now move backwards counting visual rows until we reach target
-                iter = buffer.line_iterator(cursor_line_start, 80);
-                while visual_rows_counted < target_rows_from_top {
-                    if iter.prev().is_none() {
-                        break; // Hit beginning of buffer
-                    }
-
-                    if let Some((_line_start, line_content)) = iter.next() {
-                        let line_text = if line_content.ends_with('\n') {
-                            &line_content[..line_content.len() - 1]
-                        } else {
-                            &line_content
-                        };
-                        let segments = wrap_line(line_text, &wrap_config);
-                        visual_rows_counted += segments.len();
-
-                        // Move back to where prev() left us
-                        iter.prev();
-                    }
-                }
-
-                let new_top_byte = iter.current_position();
-                self.set_top_byte_with_limit(buffer, new_top_byte);
-            } else {
-                // Non-wrapped mode: count logical lines as before
-                let mut iter = buffer.line_iterator(cursor_line_start, 80);
-
-                for _ in 0..target_rows_from_top {
-                    if iter.prev().is_none() {
-                        break; // Hit beginning of buffer
-                    }
-                }
-
-                let new_top_byte = iter.current_position();
-                tracing::trace!(
-                    "ensure_visible: SCROLLING from top_byte={} to new_top_byte={} (target_rows={})",
-                    self.top_byte, new_top_byte, target_rows_from_top
-                );
-                self.set_top_byte_with_limit(buffer, new_top_byte);
-            }
+        if let Some(target_row) = target_row {
+            let new_top_byte = self.top_byte_for_row(buffer, cursor, cursor_line_start, target_row);
+            tracing::trace!(
+                "ensure_visible: SCROLLING from top_byte={} to new_top_byte={} (target_row={})",
+                self.top_byte,
+                new_top_byte,
+                target_row
+            );
+            self.set_top_byte_with_limit(buffer, new_top_byte);
         }
 
         // Horizontal scrolling - skip if line wrapping is enabled
         // When wrapping is enabled, all columns are always visible via wrapping
-        if !self.line_wrap_enabled {
+        if !self.wraps(buffer) {
             let cursor_column = cursor.position.saturating_sub(cursor_line_start);
 
             // Get the line content to know its length (for limiting horizontal scroll)
@@ -853,6 +746,105 @@ impl Viewport {
         }
     }
 
+    /// Row of the cursor counted from the top of the view, if its line starts
+    /// at or below `top_byte` and the row is at most `max_row`
+    ///
+    /// With line wrapping, rows are wrapped segments rather than logical lines.
+    fn cursor_row(
+        &self,
+        buffer: &mut Buffer,
+        cursor: &Cursor,
+        cursor_line_start: usize,
+        max_row: usize,
+    ) -> Option<usize> {
+        if cursor_line_start < self.top_byte {
+            return None;
+        }
+        let wrap_config = self.wrap_config(buffer);
+        let mut iter = buffer.line_iterator(self.top_byte, 80);
+        let mut row = 0;
+        while iter.current_position() < cursor_line_start {
+            let (_, content) = iter.next()?;
+            row += wrap_config.as_ref().map_or(1, |config| {
+                wrap_line(content.trim_end_matches('\n'), config).len()
+            });
+            if row > max_row {
+                return None;
+            }
+        }
+        let segment = match &wrap_config {
+            Some(config) => {
+                let text = iter.next().map(|(_, content)| content).unwrap_or_default();
+                Self::cursor_segment(cursor, cursor_line_start, &text, config)
+            }
+            None => 0,
+        };
+        Some(row + segment).filter(|&row| row <= max_row)
+    }
+
+    /// Start of the line to show at the top so the cursor lands on
+    /// `target_row`, or as close above it as whole lines allow
+    fn top_byte_for_row(
+        &self,
+        buffer: &mut Buffer,
+        cursor: &Cursor,
+        cursor_line_start: usize,
+        target_row: usize,
+    ) -> usize {
+        let wrap_config = self.wrap_config(buffer);
+        let mut iter = buffer.line_iterator(cursor_line_start, 80);
+        // Rows of the cursor's own line above the cursor
+        let mut rows_above = match &wrap_config {
+            Some(config) => {
+                let text = iter.next().map(|(_, content)| content).unwrap_or_default();
+                iter = buffer.line_iterator(cursor_line_start, 80);
+                Self::cursor_segment(cursor, cursor_line_start, &text, config)
+            }
+            None => 0,
+        };
+        let mut top = cursor_line_start;
+        while rows_above < target_row {
+            let Some((line_start, content)) = iter.prev() else {
+                break; // Hit beginning of buffer
+            };
+            let rows = wrap_config.as_ref().map_or(1, |config| {
+                wrap_line(content.trim_end_matches('\n'), config).len()
+            });
+            if rows_above + rows > target_row {
+                break;
+            }
+            rows_above += rows;
+            top = line_start;
+        }
+        top
+    }
+
+    /// Whether lines of `buffer` wrap: never for a large file, whose wrapped
+    /// layout would have to be worked out from text far outside the view
+    pub fn wraps(&self, buffer: &Buffer) -> bool {
+        self.line_wrap_enabled && !buffer.is_large_file()
+    }
+
+    /// How lines wrap, or None when wrapping is off
+    fn wrap_config(&self, buffer: &Buffer) -> Option<WrapConfig> {
+        self.wraps(buffer)
+            .then(|| WrapConfig::new(self.width as usize, self.gutter_width(buffer), true))
+    }
+
+    /// Index of the wrapped segment of the cursor's line (`line`, starting at
+    /// `line_start`) that holds the cursor
+    fn cursor_segment(
+        cursor: &Cursor,
+        line_start: usize,
+        line: &str,
+        config: &WrapConfig,
+    ) -> usize {
+        let segments = wrap_line(line.trim_end_matches('\n'), config);
+        let column = cursor.position.saturating_sub(line_start);
+        let (segment, _) = char_position_to_segment(column, &segments);
+        segment.min(segments.len().saturating_sub(1))
+    }
+
     /// Ensure a line is visible with scroll offset applied
     /// This is a legacy method kept for backward compatibility with tests
     /// In practice, use ensure_visible() which works directly with cursors and bytes
@@ -1053,7 +1045,7 @@ impl Viewport {
         }
 
         // Calculate screen column and additional wrapped rows if line wrapping is enabled
-        let (screen_col, additional_rows) = if self.line_wrap_enabled {
+        let (screen_col, additional_rows) = if self.wraps(buffer) {
             // Use new clean wrapping implementation
             let gutter_width = self.gutter_width(buffer);
             let config = WrapConfig::new(self.width as usize, gutter_width, true);
@@ -1100,6 +1092,25 @@ mod tests {
         assert_eq!(vp.top_byte, 0);
     }
 
+    #[test]
+    fn test_adjust_for_edit_keeps_top_line() {
+        let mut vp = Viewport::new(80, 24);
+        vp.top_byte = 20;
+
+        // Inserting and deleting before the top moves it along
+        vp.adjust_for_edit(0, 0, 6);
+        assert_eq!(vp.top_byte, 26);
+        vp.adjust_for_edit(10, 4, 0);
+        assert_eq!(vp.top_byte, 22);
+        // Edits at or after the top leave it alone
+        vp.adjust_for_edit(22, 0, 5);
+        vp.adjust_for_edit(30, 3, 0);
+        assert_eq!(vp.top_byte, 22);
+        // Deleting across the top starts the view where the deletion was
+        vp.adjust_for_edit(15, 10, 0);
+        assert_eq!(vp.top_byte, 15);
+    }
+
     #[test]
     fn test_scroll_up_down() {
         // Create a buffer with more lines than the viewport to make scrolling possible
@@ -1253,6 +1264,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ensure_visible_keeps_scroll_offset_margin() {
+        let text: Vec<String> = (1..=30).map(|i| format!("line{i}")).collect();
+        let mut buffer = Buffer::from_str_test(&text.join("\n"));
+        let mut vp = Viewport::new(80, 10);
+        vp.set_scroll_offset(2);
+        let rows = vp.visible_line_count();
+        let line_start = |buffer: &Buffer, line: usize| buffer.line_start_offset(line).unwrap();
+
+        // Moving down one line at a time scrolls as soon as the cursor would
+        // get closer than 2 lines to the bottom, keeping it on the same row
+        let mut top_lines = Vec::new();
+        for line in 0..30 {
+            let cursor = Cursor::new(line_start(&buffer, line));
+            vp.ensure_visible(&mut buffer, &cursor);
+            let top_line = buffer.get_line_number(vp.top_byte);
+            top_lines.push(top_line);
+            if line + 2 < 30 {
+                assert!(
+                    line - top_line < rows - 2,
+                    "line {line} is within the bottom margin"
+                );
+            }
+        }
+        let bottom_row = rows - 3;
+        for (line, &top_line) in top_lines.iter().enumerate() {
+            let expected = line.saturating_sub(bottom_row).min(30 - rows);
+            assert_eq!(
+                top_line, expected,
+                "top line when the cursor is on line {line}"
+            );
+        }
+
+        // Moving back up keeps 2 lines above the cursor until the document start
+        for line in (0..30).rev() {
+            let cursor = Cursor::new(line_start(&buffer, line));
+            vp.ensure_visible(&mut buffer, &cursor);
+            let top_line = buffer.get_line_number(vp.top_byte);
+            assert_eq!(top_line, line.saturating_sub(2).min(30 - rows));
+        }
+    }
+
+    /// A view line showing `text` from source byte `start`, one column per char
+    fn source_view_line(text: &str, start: usize) -> ViewLine {
+        use crate::view::ui::view_pipeline::LineStart;
+        let count = text.chars().count();
+        ViewLine {
+            text: text.to_string(),
+            char_source_bytes: text.char_indices().map(|(i, _)| Some(start + i)).collect(),
+            char_styles: vec![None; count],
+            char_visual_cols: (0..count).collect(),
+            visual_to_char: (0..count).collect(),
+            tab_starts: Default::default(),
+            line_start: LineStart::Beginning,
+            ends_with_newline: text.ends_with('\n'),
+        }
+    }
+
+    #[test]
+    fn test_ensure_visible_in_layout_cursor_on_empty_last_line() {
+        // The empty line after a trailing newline has no view line, so the
+        // cursor there maps onto the long line above it
+        let text = format!("{}\n", "x".repeat(100));
+        let view_lines = vec![source_view_line(&text, 0)];
+        let mut vp = Viewport::new(40, 10);
+
+        let cursor = Cursor::new(text.len());
+        vp.ensure_visible_in_layout(&view_lines, &cursor, 0, false);
+        assert_eq!(vp.left_column, 0, "cursor is at column 0 of the next line");
+
+        // The end of the long line itself still scrolls into view
+        let cursor = Cursor::new(100);
+        vp.ensure_visible_in_layout(&view_lines, &cursor, 0, false);
+        assert!(vp.left_column > 0);
+    }
+
     #[test]
     fn test_ensure_visible_cursor_below_viewport_centers() {
         // Create buffer with many lines
@@ -4,7 +4,8 @@
 //! Used for inlay hints (type annotations, parameter names), git blame headers, etc.
 //!
 //! Two types of virtual text are supported:
-//! - **Inline**: Text inserted before/after a character (e.g., `: i32` type hints)
+//! - **Inline**: Text inserted before/after a character (e.g., `: i32` type hints),
+//!   or after the end of the line (e.g., inline diagnostics)
 //! - **Line**: Full lines inserted above/below a position (e.g., git blame headers)
 //!
 //! Virtual text is rendered during the render phase by reading from VirtualTextManager.
@@ -32,6 +33,9 @@ pub enum VirtualTextPosition {
     BeforeChar,
     /// Render after the character (e.g., type hints: `x: i32`)
     AfterChar,
+    /// Render after the last character of the line containing this position
+    /// (e.g., inline diagnostics: `let x = ;  expected expression`)
+    EndOfLine,
 
     // ─── Line positions (full lines) ───
     /// Render as a full line ABOVE the line containing this position
@@ -50,9 +54,9 @@ impl VirtualTextPosition {
         matches!(self, Self::LineAbove | Self::LineBelow)
     }
 
-    /// Returns true if this is an inline position (BeforeChar/AfterChar)
+    /// Returns true if this is an inline position (BeforeChar/AfterChar/EndOfLine)
     pub fn is_inline(&self) -> bool {
-        matches!(self, Self::BeforeChar | Self::AfterChar)
+        matches!(self, Self::BeforeChar | Self::AfterChar | Self::EndOfLine)
     }
 }
 
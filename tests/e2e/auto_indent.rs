@@ -837,3 +837,33 @@ fn test_indent_after_empty_line_in_function_body() {
         line3_indent, content
     );
 }
+
+/// Test that with `soft_tab_backspace` one Backspace removes a whole level of
+/// auto-indentation
+#[test]
+fn test_soft_tab_backspace_removes_auto_indent_level() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.rs");
+    std::fs::write(&file_path, "").unwrap();
+
+    let mut config = Config::default();
+    config.editor.auto_indent = true;
+    config.editor.soft_tab_backspace = true;
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    harness.type_text("fn main() {").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("let x = 1;").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Backspace, KeyModifiers::NONE)
+        .unwrap();
+
+    // The auto-closed brace follows the cursor to the start of the line
+    harness.assert_buffer_content("fn main() {\n    let x = 1;\n}");
+}
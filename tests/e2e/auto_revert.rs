@@ -389,3 +389,111 @@ fn test_auto_revert_with_temp_rename_save() {
         harness.assert_buffer_content(&new_content);
     }
 }
+
+/// Change a file on disk behind the editor's back, with a modification time
+/// clearly after the one the editor recorded
+fn change_on_disk(harness: &mut EditorTestHarness, path: &Path, content: &str) {
+    write_and_sync(path, content);
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    harness
+        .editor_mut()
+        .handle_file_changed(path.to_str().unwrap());
+    harness.render().unwrap();
+}
+
+/// Open `name` with `original` on disk and add `edit` to the end of the buffer
+fn open_with_unsaved_edit(
+    original: &str,
+    edit: &str,
+    name: &str,
+) -> (EditorTestHarness, std::path::PathBuf) {
+    let mut harness = EditorTestHarness::with_temp_project(80, 24).unwrap();
+    let file_path = harness.project_dir().unwrap().join(name);
+    write_and_sync(&file_path, original);
+    harness.open_file(&file_path).unwrap();
+
+    use crossterm::event::{KeyCode, KeyModifiers};
+    harness
+        .send_key(KeyCode::End, KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(edit).unwrap();
+    (harness, file_path)
+}
+
+/// A conflicting external change asks whether to reload, keep or diff, and
+/// reloading takes the file from disk
+#[test]
+fn test_external_change_conflict_prompt_reload() {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    let (mut harness, file_path) = open_with_unsaved_edit("one\ntwo", "!", "reload.txt");
+
+    change_on_disk(&mut harness, &file_path, "one\nTWO");
+    assert!(harness.editor().is_prompting());
+    harness.assert_screen_contains("(r)eload, (k)eep mine, (d)iff?");
+
+    harness.type_text("r").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("one\nTWO");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+}
+
+/// Keeping the buffer's edits leaves them in place and stops the file
+/// counting as changed, so saving doesn't ask about it again
+#[test]
+fn test_external_change_conflict_prompt_keep_mine() {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    let (mut harness, file_path) = open_with_unsaved_edit("one\ntwo", "!", "keep.txt");
+
+    change_on_disk(&mut harness, &file_path, "one\nTWO");
+    harness.type_text("k").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("one\ntwo!");
+    assert_eq!(
+        harness
+            .editor()
+            .active_state()
+            .buffer
+            .external_change_status(),
+        fresh::model::buffer::ExternalChange::Unchanged
+    );
+
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert!(!harness.editor().is_prompting());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\ntwo!");
+}
+
+/// Asking for a diff shows disk and buffer lines side by side in a new
+/// buffer, then asks again
+#[test]
+fn test_external_change_conflict_prompt_diff() {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    let (mut harness, file_path) = open_with_unsaved_edit("one\ntwo", "!", "diff.txt");
+
+    change_on_disk(&mut harness, &file_path, "one\nTWO");
+    harness.type_text("d").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+
+    harness.assert_buffer_content("  one\n- TWO\n+ two!\n");
+    assert!(harness.editor().is_prompting());
+    harness.assert_screen_contains("(r)eload, (k)eep mine, (d)iff?");
+
+    // The edits are still there to keep after looking at the diff
+    harness.type_text("k").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_screen_contains("Kept your edits");
+}
@@ -88,6 +88,26 @@ fn test_transpose_chars_at_end() {
     harness.assert_buffer_content("ab");
 }
 
+/// Test transpose_words swaps the last two words at the end of the line
+#[test]
+fn test_transpose_words_at_end_of_line() {
+    let mut harness = emacs_harness(80, 24);
+
+    harness.type_text("hello big world").unwrap();
+    harness.render().unwrap();
+
+    // Execute transpose_words (Alt+T)
+    harness
+        .send_key(KeyCode::Char('t'), KeyModifiers::ALT)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("hello world big");
+
+    // Cursor stays after the swapped words, so typing continues there
+    harness.type_text("!").unwrap();
+    harness.assert_buffer_content("hello world big!");
+}
+
 // =============================================================================
 // Open Line (C-o) Tests
 // =============================================================================
@@ -428,3 +428,51 @@ fn test_view_menu_other_checkboxes_sync() {
         screen
     );
 }
+
+/// Click the first on-screen occurrence of `text`
+fn click_text(harness: &mut EditorTestHarness, text: &str) {
+    let screen = harness.screen_to_string();
+    let (row, col) = screen
+        .lines()
+        .enumerate()
+        .find_map(|(row, line)| {
+            line.find(text)
+                .map(|byte| (row, line[..byte].chars().count()))
+        })
+        .unwrap_or_else(|| panic!("{text:?} not on screen:\n{screen}"));
+    harness.mouse_click(col as u16 + 1, row as u16).unwrap();
+    harness.render().unwrap();
+}
+
+/// Test that commands run from the palette and from keybindings are listed
+/// in Go > Recent Commands, most recent first
+#[test]
+fn test_recent_commands_lists_palette_and_keybinding_commands() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.render().unwrap();
+
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Toggle Line Wrap").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    // Cursor motions aren't commands, so they aren't remembered
+    harness
+        .send_key(KeyCode::Right, KeyModifiers::NONE)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('a'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+
+    click_text(&mut harness, " Go ");
+    click_text(&mut harness, "Recent Commands");
+
+    let screen = harness.screen_to_string();
+    let select_all = screen.find("Select all").expect(&screen);
+    let line_wrap = screen.find("Toggle line wrap").expect(&screen);
+    assert!(select_all < line_wrap, "Screen:\n{screen}");
+    assert!(!screen.contains("Move right"), "Screen:\n{screen}");
+}
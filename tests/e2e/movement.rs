@@ -650,3 +650,52 @@ fn test_movement_through_multiple_empty_lines() {
         "Should be somewhere on Line 6, got position {final_pos}"
     );
 }
+
+/// Test that vertical movement remembers the goal column across a shorter line
+#[test]
+fn test_goal_column_restored_after_short_line() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.txt");
+
+    // Line 1: "long line here\n" (positions 0-14)
+    // Line 2: "ab\n" (positions 15-17)
+    // Line 3: "another long line\n" (positions 18-35)
+    let content = "long line here\nab\nanother long line\n";
+    std::fs::write(&file_path, content).unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    // Move to column 10 on line 1
+    for _ in 0..10 {
+        harness
+            .send_key(KeyCode::Right, KeyModifiers::NONE)
+            .unwrap();
+    }
+    assert_eq!(harness.cursor_position(), 10);
+
+    // Down onto the short line clamps to its end
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 17, "Should clamp to end of 'ab'");
+
+    // Down again restores column 10 on the long line
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 28, "Should return to column 10");
+
+    // And back up through the short line to the original position
+    harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 17);
+    harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 10, "Should restore column 10");
+
+    // A horizontal move resets the goal column
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness.send_key(KeyCode::Left, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 16);
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    assert_eq!(
+        harness.cursor_position(),
+        19,
+        "Goal column should now be column 1"
+    );
+}
@@ -1065,3 +1065,96 @@ fn test_recovery_insert_at_end_of_large_file() {
         println!("No chunked recovery entry found");
     }
 }
+
+/// Auto-save keeps saving the other buffers when one of them fails, and
+/// reports every failure
+#[test]
+fn test_auto_save_collects_errors_per_buffer() {
+    let mut config = fresh::config::Config::default();
+    config.editor.auto_save_delay_ms = 500;
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+
+    let good = TestFixture::new("good.txt", "good").unwrap();
+    let bad_dir = tempfile::tempdir().unwrap();
+    let bad = bad_dir.path().join("bad.txt");
+    std::fs::write(&bad, "bad").unwrap();
+
+    for path in [&bad, &good.path] {
+        harness.open_file(path).unwrap();
+        harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+        harness.type_text("!").unwrap();
+    }
+    // Saving bad.txt fails once its directory is gone
+    std::fs::remove_dir_all(bad_dir.path()).unwrap();
+
+    assert_eq!(harness.editor_mut().auto_save_idle_buffers().unwrap(), 0);
+    harness.advance_time(std::time::Duration::from_millis(500));
+    let err = harness.editor_mut().auto_save_idle_buffers().unwrap_err();
+
+    assert!(err.to_string().contains("bad.txt"), "{}", err);
+    assert!(!err.to_string().contains("good.txt"), "{}", err);
+    assert_eq!(std::fs::read_to_string(&good.path).unwrap(), "good!");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+
+    // The failure is shown to the user, not just logged
+    let status = harness.editor().get_status_message().unwrap();
+    assert!(status.starts_with("Auto-save failed"), "{}", status);
+    assert!(status.contains("bad.txt"), "{}", status);
+}
+
+/// Saving a file explicitly removes the sidecar auto-save left next to it
+#[test]
+fn test_save_removes_auto_save_sidecar() {
+    let mut config = fresh::config::Config::default();
+    config.editor.auto_save_delay_ms = 500;
+    config.editor.auto_save_to_sidecar = true;
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+
+    let fixture = TestFixture::new("notes.txt", "notes").unwrap();
+    let sidecar = fresh::model::auto_save::sidecar_path(&fixture.path);
+    harness.open_file(&fixture.path).unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text("!").unwrap();
+
+    harness.editor_mut().auto_save_idle_buffers().unwrap();
+    harness.advance_time(std::time::Duration::from_millis(500));
+    assert_eq!(harness.editor_mut().auto_save_idle_buffers().unwrap(), 1);
+    assert_eq!(std::fs::read_to_string(&sidecar).unwrap(), "notes!");
+
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&fixture.path).unwrap(), "notes!");
+    assert!(!sidecar.exists());
+}
+
+/// Auto-save leaves a file alone when it changed on disk under unsaved edits,
+/// and asks what to do instead
+#[test]
+fn test_auto_save_skips_file_changed_on_disk() {
+    let mut config = fresh::config::Config::default();
+    config.editor.auto_save_delay_ms = 500;
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+
+    let fixture = TestFixture::new("shared.txt", "mine").unwrap();
+    harness.open_file(&fixture.path).unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text("!").unwrap();
+
+    std::fs::write(&fixture.path, "theirs").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&fixture.path)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(10))
+        .unwrap();
+
+    harness.editor_mut().auto_save_idle_buffers().unwrap();
+    harness.advance_time(std::time::Duration::from_millis(500));
+    assert_eq!(harness.editor_mut().auto_save_idle_buffers().unwrap(), 0);
+
+    assert_eq!(std::fs::read_to_string(&fixture.path).unwrap(), "theirs");
+    assert!(harness.editor().active_state().buffer.is_modified());
+    harness.render().unwrap();
+    harness.assert_screen_contains("changed on disk");
+}
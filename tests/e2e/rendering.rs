@@ -590,3 +590,124 @@ fn test_ansi_rgb_color_rendering() {
         );
     }
 }
+
+/// Test that trailing spaces are drawn as dim markers when enabled in the config
+#[test]
+fn test_render_whitespace_trailing_spaces() {
+    use fresh::config::{Config, WhitespaceMode};
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("whitespace.txt");
+    std::fs::write(&file_path, "a b  \n\tc\n").unwrap();
+
+    let mut config = Config::default();
+    config.editor.render_whitespace = WhitespaceMode::Trailing;
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+    // Keep the cursor off the first line so its cells keep the marker style
+    harness
+        .send_key(
+            crossterm::event::KeyCode::Down,
+            crossterm::event::KeyModifiers::NONE,
+        )
+        .unwrap();
+    harness.render().unwrap();
+
+    let (content_row, _) = harness.content_area_rows();
+    let first_line = harness.get_screen_row(content_row);
+    assert!(
+        first_line.contains("a b··"),
+        "Expected only the trailing spaces to be marked, got {first_line:?}"
+    );
+    let second_line = harness.get_screen_row(content_row + 1);
+    assert!(
+        second_line.contains('→'),
+        "Expected the tab to be marked, got {second_line:?}"
+    );
+
+    // The gutter is: indicator (1) + line numbers (4) + separator (3) = 8 chars
+    let marker_style = harness.get_cell_style(8 + 3, content_row as u16).unwrap();
+    let theme = fresh::view::theme::Theme::default();
+    assert_eq!(marker_style.fg, Some(theme.whitespace_indicator_fg));
+}
+
+/// Test that configured rulers tint their column and scroll with the text
+#[test]
+fn test_rulers_tint_their_columns() {
+    use fresh::config::Config;
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("rulers.txt");
+    std::fs::write(&file_path, "short\n\ta line that runs past the ruler\n").unwrap();
+
+    let mut config = Config::default();
+    config.editor.rulers = vec![10, 20];
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+
+    let (content_row, _) = harness.content_area_rows();
+    let theme = fresh::view::theme::Theme::default();
+    // The gutter is: indicator (1) + line numbers (4) + separator (3) = 8 chars
+    for row in [content_row, content_row + 1, content_row + 5] {
+        for x in [8 + 10, 8 + 20] {
+            let style = harness.get_cell_style(x, row as u16).unwrap();
+            assert_eq!(style.bg, Some(theme.ruler_bg), "cell ({x}, {row})");
+        }
+        let style = harness.get_cell_style(8 + 11, row as u16).unwrap();
+        assert_ne!(style.bg, Some(theme.ruler_bg), "cell (19, {row})");
+    }
+    // The text under the ruler is still drawn
+    assert!(harness
+        .get_screen_row(content_row + 1)
+        .contains("a line that runs past the ruler"));
+}
+
+/// Test that end-of-line virtual text is drawn after the line's last character
+#[test]
+fn test_end_of_line_virtual_text() {
+    use fresh::view::virtual_text::VirtualTextPosition;
+    use ratatui::style::Style;
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("eol.rs");
+    std::fs::write(&file_path, "let x = ;\nlet y = 2;").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    {
+        let state = harness.editor_mut().active_state_mut();
+        // Anchored mid-line, drawn after the end of it
+        state.virtual_texts.add(
+            &mut state.marker_list,
+            4,
+            "<- expected expression".to_string(),
+            Style::default(),
+            VirtualTextPosition::EndOfLine,
+            0,
+        );
+        // The last line has no newline
+        state.virtual_texts.add(
+            &mut state.marker_list,
+            10,
+            "// i32".to_string(),
+            Style::default(),
+            VirtualTextPosition::EndOfLine,
+            0,
+        );
+    }
+    harness.render().unwrap();
+
+    let (content_row, _) = harness.content_area_rows();
+    assert!(harness
+        .get_screen_row(content_row)
+        .contains("let x = ; <- expected expression"));
+    assert!(harness
+        .get_screen_row(content_row + 1)
+        .contains("let y = 2; // i32"));
+    // The buffer itself is unchanged
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "let x = ;\nlet y = 2;"
+    );
+}
@@ -2,6 +2,7 @@
 
 use crate::common::harness::EditorTestHarness;
 use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::Config;
 use tempfile::TempDir;
 
 /// Test basic forward search functionality
@@ -1428,3 +1429,98 @@ fn test_status_bar_hidden_during_suggestions() {
         "Status bar should show 'Palette:' indicator after closing command palette"
     );
 }
+
+/// Incremental search moves the cursor as the query is typed and refined
+#[test]
+fn test_incremental_search_follows_typing() {
+    let config = Config {
+        active_keybinding_map: "emacs".into(),
+        ..Default::default()
+    };
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness
+        .type_text("alpha beta\nbetter alpine\nalpaca")
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("I-search: ");
+
+    // The search wraps from the end: "be" matches "beta", "bett" only "better"
+    harness.type_text("be").unwrap();
+    assert_eq!(harness.cursor_position(), 6);
+    harness.type_text("tt").unwrap();
+    assert_eq!(harness.cursor_position(), 11);
+
+    // Backspace goes back to the earlier match
+    harness
+        .send_key(KeyCode::Backspace, KeyModifiers::NONE)
+        .unwrap();
+    assert_eq!(harness.cursor_position(), 6);
+
+    // C-s again moves to the next match
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(harness.cursor_position(), 11);
+
+    // Enter keeps the cursor at the match
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    assert_eq!(harness.cursor_position(), 11);
+    harness.assert_screen_not_contains("I-search: ");
+}
+
+/// Cancelling an incremental search puts the cursor back where it started
+#[test]
+fn test_incremental_search_cancel_restores_cursor() {
+    let config = Config {
+        active_keybinding_map: "emacs".into(),
+        ..Default::default()
+    };
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.type_text("one two three two").unwrap();
+    let start = harness.cursor_position();
+
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("two").unwrap();
+    assert_eq!(harness.cursor_position(), 4);
+
+    harness.send_key(KeyCode::Esc, KeyModifiers::NONE).unwrap();
+    harness.render().unwrap();
+    assert_eq!(harness.cursor_position(), start);
+    assert_eq!(harness.count_search_highlights(), 0);
+}
+
+/// C-s continues an incremental search but doesn't start one from another prompt
+#[test]
+fn test_incremental_search_key_only_applies_in_isearch_prompt() {
+    let config = Config {
+        active_keybinding_map: "emacs".into(),
+        ..Default::default()
+    };
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.type_text("one\ntwo").unwrap();
+
+    // M-g g opens the go to line prompt
+    harness
+        .send_key(KeyCode::Char('g'), KeyModifiers::ALT)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('g'), KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    let prompt = harness.screen_to_string();
+
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_not_contains("I-search: ");
+    assert_eq!(harness.screen_to_string(), prompt);
+}
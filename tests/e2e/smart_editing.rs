@@ -744,6 +744,64 @@ fn test_play_nonexistent_macro() {
     assert_eq!(content, "test", "Content should be unchanged");
 }
 
+/// Test replaying a macro several times from the Play Macro prompt,
+/// and that the whole replay is undone in one step
+#[test]
+fn test_macro_replay_with_count_is_single_undo() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "abcdef").unwrap();
+
+    let mut harness = harness_with_auto_indent();
+    harness.open_file(&file_path).unwrap();
+    harness.send_key(KeyCode::Home, KeyModifiers::NONE).unwrap();
+
+    // Record macro 2: insert "-" and move right past the next character
+    harness
+        .send_key(KeyCode::Char('2'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    harness.type_text("-").unwrap();
+    harness
+        .send_key(KeyCode::Right, KeyModifiers::NONE)
+        .unwrap();
+    harness
+        .send_key(KeyCode::Char('2'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "-abcdef");
+
+    // Play macro 2 three times via the prompt
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Play Macro").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text("2 3").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+
+    assert_eq!(harness.get_buffer_content().unwrap(), "-a-b-c-def");
+    let status = harness
+        .editor()
+        .get_status_message()
+        .cloned()
+        .unwrap_or_default();
+    assert!(
+        status.contains("3 times"),
+        "Status should mention the repeat count, got: {}",
+        status
+    );
+
+    // A single undo reverts all three replays
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "-abcdef");
+}
+
 /// Test that toggle recording starts and stops correctly
 #[test]
 fn test_toggle_macro_recording() {
@@ -1240,3 +1298,64 @@ fn test_block_selection_up() {
         .get_line_number(cursor.position);
     assert_eq!(cur_line, 1, "Cursor should be on line 1 after moving up");
 }
+
+// =============================================================================
+// Save Cleanup Tests
+// =============================================================================
+
+/// Test that saving trims trailing whitespace and adds exactly one final newline
+#[test]
+fn test_save_trims_whitespace_and_adds_final_newline() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    std::fs::write(&file_path, "one  \ntwo\t\n   \nthree").unwrap();
+
+    let mut config = Config::default();
+    config.editor.trim_trailing_whitespace = true;
+    config.editor.ensure_final_newline = true;
+    let mut harness = EditorTestHarness::with_config(80, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    // Leave the cursor on the trailing spaces of the first line
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&file_path).unwrap(),
+        "one\ntwo\n\nthree\n"
+    );
+    assert_eq!(harness.get_buffer_content().unwrap(), "one\ntwo\n\nthree\n");
+    assert_eq!(
+        harness.editor().active_state().cursors.primary().position,
+        3
+    );
+
+    // Saving again doesn't add a second newline
+    harness.type_text("!").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file_path).unwrap(),
+        "one!\ntwo\n\nthree\n"
+    );
+}
+
+/// Test that save cleanup is off by default
+#[test]
+fn test_save_leaves_whitespace_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("notes.txt");
+    std::fs::write(&file_path, "one  \nlast").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.type_text("x").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "xone  \nlast");
+}
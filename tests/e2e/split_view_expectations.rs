@@ -705,6 +705,57 @@ fn test_cursor_adjustment_on_shared_buffer_edit() {
     );
 }
 
+/// Test that a split scrolled down keeps showing the same lines when text is
+/// inserted above them from another split
+#[test]
+fn test_scroll_adjustment_on_shared_buffer_edit() {
+    let mut harness = EditorTestHarness::new(120, 30).unwrap();
+    let long_text = (1..=50)
+        .map(|i| format!("Line {}", i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    harness.type_text(&long_text).unwrap();
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+
+    // Scroll the second split to the end of the buffer, with the cursor in
+    // the middle of the view so following it wouldn't scroll
+    split_vertical(&mut harness);
+    harness
+        .send_key(KeyCode::End, KeyModifiers::CONTROL)
+        .unwrap();
+    for _ in 0..10 {
+        harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    }
+    harness.render().unwrap();
+    let top_byte = harness.top_byte();
+    let cursor = harness.cursor_position();
+    assert!(top_byte > 0);
+
+    // Insert three lines at the top of the buffer from the first split
+    prev_split(&mut harness);
+    harness
+        .send_key(KeyCode::Home, KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("New 1\nNew 2\nNew 3\n").unwrap();
+    harness.render().unwrap();
+    assert_eq!(harness.top_byte(), 0);
+
+    // The second split moved along with the text it was showing
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.render().unwrap();
+    harness.type_text("next split").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    assert_eq!(harness.top_byte(), top_byte + 18);
+    assert_eq!(harness.cursor_position(), cursor + 18);
+}
+
 /// Test that cursors in inactive splits are rendered (visible on screen)
 /// Each split should show its cursor position even when not focused
 #[test]
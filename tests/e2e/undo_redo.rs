@@ -503,3 +503,51 @@ fn test_undo_to_empty_after_save_as() {
         final_content
     );
 }
+
+/// Rejects typing "x" and doubles a typed "a" into "AA"
+struct DoublingHook;
+
+impl fresh::services::plugins::hooks::EditHook for DoublingHook {
+    fn before_edit(
+        &self,
+        edit: &fresh::services::plugins::hooks::PendingEdit,
+    ) -> fresh::services::plugins::hooks::EditDecision {
+        use fresh::services::plugins::hooks::{EditDecision, PendingEdit};
+        match edit {
+            PendingEdit::Insert { text, .. } if text == "x" => EditDecision::Reject,
+            PendingEdit::Insert { text, .. } if text == "a" => {
+                EditDecision::Modify("AA".to_string())
+            }
+            _ => EditDecision::Allow,
+        }
+    }
+}
+
+/// Edit hooks see typed text before it is logged, so undo takes back exactly
+/// what they let through
+#[test]
+fn test_undo_after_edit_hook_rewrites_typing() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness
+        .editor_mut()
+        .add_edit_hook("test", Box::new(DoublingHook));
+
+    harness.type_text("baxb").unwrap();
+    harness.assert_buffer_content("bAAb");
+    assert_eq!(
+        harness.editor().active_state().cursors.primary().position,
+        4
+    );
+
+    for expected in ["bAA", "b", ""] {
+        harness
+            .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+            .unwrap();
+        harness.assert_buffer_content(expected);
+    }
+    // Redo replays the logged edits without filtering them again
+    harness
+        .send_key(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("b");
+}